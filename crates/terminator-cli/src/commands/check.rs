@@ -0,0 +1,81 @@
+use anyhow::{anyhow, Context, Result};
+use clap::Args;
+use colored::*;
+use terminator_mcp_agent::server::DesktopWrapper;
+use terminator_mcp_agent::tools::validate_workflow::{self, Severity, ValidateWorkflowResult};
+use terminator_mcp_agent::utils::ExecuteSequenceArgs;
+
+#[derive(Debug, Args)]
+pub struct CheckCommand {
+    /// Workflow file (YAML or JSON)
+    pub input: String,
+    /// Print the analysis as JSON
+    #[arg(long)]
+    pub json: bool,
+    /// Fail on warnings as well as errors
+    #[arg(long)]
+    pub strict: bool,
+}
+
+impl CheckCommand {
+    pub fn execute(&self) -> Result<()> {
+        let content = std::fs::read_to_string(&self.input)
+            .with_context(|| format!("Failed to read workflow file: {}", self.input))?;
+        let workflow =
+            validate_workflow::parse_workflow_content(&content).map_err(|e| anyhow!(e))?;
+        let result = validate_workflow::analyze_workflow(
+            &workflow,
+            Some(&DesktopWrapper::tool_definitions()),
+        );
+
+        if self.json {
+            println!("{}", serde_json::to_string_pretty(&result)?);
+        } else {
+            print_issues(&result);
+        }
+
+        if !result.valid || (self.strict && result.warning_count > 0) {
+            return Err(anyhow!(
+                "{} error(s), {} warning(s) in {}",
+                result.error_count,
+                result.warning_count,
+                self.input
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Statically analyze an already-parsed workflow (used by `mcp run --dry-run`).
+pub fn analyze(workflow: &serde_json::Value) -> Result<ValidateWorkflowResult> {
+    let workflow: ExecuteSequenceArgs = serde_json::from_value(workflow.clone())
+        .context("Workflow does not match the execute_sequence arguments")?;
+    Ok(validate_workflow::analyze_workflow(
+        &workflow,
+        Some(&DesktopWrapper::tool_definitions()),
+    ))
+}
+
+pub fn print_issues(result: &ValidateWorkflowResult) {
+    for issue in &result.issues {
+        let label = match issue.severity {
+            Severity::Error => "error".red().bold(),
+            Severity::Warning => "warning".yellow().bold(),
+        };
+        println!(
+            "{label}[{}] {}: {}",
+            issue.code,
+            issue.location.dimmed(),
+            issue.message
+        );
+    }
+    let summary = format!(
+        "{} error(s), {} warning(s)",
+        result.error_count, result.warning_count
+    );
+    if result.valid {
+        println!("{} {summary}", "✓".green());
+    } else {
+        println!("{} {summary}", "✗".red());
+    }
+}
@@ -0,0 +1,53 @@
+use anyhow::{anyhow, Context, Result};
+use clap::Args;
+use std::path::PathBuf;
+use terminator_workflow_recorder::{diff_recordings, PostProcessOptions, RecordedWorkflow};
+
+#[derive(Debug, Args)]
+pub struct DiffRecordingsCommand {
+    /// Reference recording (JSON, CBOR or a JSONL journal)
+    pub reference: String,
+    /// Recording to compare with the reference
+    pub actual: String,
+    /// Print the diff as JSON instead of Markdown
+    #[arg(long)]
+    pub json: bool,
+    /// Write to a file instead of stdout
+    #[arg(long, short)]
+    pub output: Option<PathBuf>,
+    /// Fail if the recordings differ
+    #[arg(long)]
+    pub strict: bool,
+}
+
+impl DiffRecordingsCommand {
+    pub fn execute(&self) -> Result<()> {
+        let load = |path: &str| {
+            RecordedWorkflow::load_from_file(path)
+                .map_err(|e| anyhow!("{e}"))
+                .with_context(|| format!("Failed to load recording: {path}"))
+        };
+        let reference = load(&self.reference)?;
+        let actual = load(&self.actual)?;
+        let diff = diff_recordings(&reference, &actual, &PostProcessOptions::default());
+
+        let rendered = if self.json {
+            format!("{}\n", serde_json::to_string_pretty(&diff)?)
+        } else {
+            diff.to_markdown()
+        };
+        match &self.output {
+            Some(path) => {
+                std::fs::write(path, rendered)
+                    .with_context(|| format!("Failed to write {}", path.display()))?;
+                eprintln!("Wrote {}", path.display());
+            }
+            None => print!("{rendered}"),
+        }
+
+        if self.strict && !diff.is_identical() {
+            return Err(anyhow!("{} differs from {}", self.actual, self.reference));
+        }
+        Ok(())
+    }
+}
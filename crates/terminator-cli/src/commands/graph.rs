@@ -0,0 +1,74 @@
+use anyhow::{anyhow, bail, Context, Result};
+use clap::{Args, ValueEnum};
+use std::path::PathBuf;
+use terminator_mcp_agent::execution_logger;
+use terminator_mcp_agent::tools::validate_workflow::parse_workflow_content;
+use terminator_mcp_agent::workflow_graph::{self, WorkflowGraph};
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+pub enum GraphFormat {
+    Dot,
+    Mermaid,
+}
+
+#[derive(Debug, Args)]
+pub struct GraphCommand {
+    /// Workflow file (YAML or JSON)
+    pub input: String,
+    /// Output format
+    #[arg(long, short, value_enum, default_value = "mermaid")]
+    pub format: GraphFormat,
+    /// Write to a file instead of stdout
+    #[arg(long, short)]
+    pub output: Option<PathBuf>,
+    /// Overlay the most recent run from this execution log directory
+    #[arg(long)]
+    pub logs: Option<PathBuf>,
+    /// Overlay the most recent run from the workflow's own execution logs (requires workflow_id)
+    #[arg(long, conflicts_with = "logs")]
+    pub last_run: bool,
+}
+
+impl GraphCommand {
+    pub fn execute(&self) -> Result<()> {
+        let content = std::fs::read_to_string(&self.input)
+            .with_context(|| format!("Failed to read workflow file: {}", self.input))?;
+        let workflow = parse_workflow_content(&content).map_err(|e| anyhow!(e))?;
+        let graph = WorkflowGraph::build(&workflow);
+
+        let logs_dir = if self.last_run {
+            let Some(workflow_id) = workflow.workflow_id.as_deref() else {
+                bail!("--last-run needs a workflow_id in the workflow; use --logs <dir> instead");
+            };
+            Some(execution_logger::get_workflow_executions_dir(workflow_id))
+        } else {
+            self.logs.clone()
+        };
+        let overlay = match &logs_dir {
+            Some(dir) => {
+                let logs = workflow_graph::load_execution_logs(dir).with_context(|| {
+                    format!("Failed to read execution logs from {}", dir.display())
+                })?;
+                if logs.is_empty() {
+                    bail!("No execution logs found in {}", dir.display());
+                }
+                Some(graph.overlay_last_run(&logs))
+            }
+            None => None,
+        };
+
+        let rendered = match self.format {
+            GraphFormat::Dot => graph.to_dot(overlay.as_ref()),
+            GraphFormat::Mermaid => graph.to_mermaid(overlay.as_ref()),
+        };
+        match &self.output {
+            Some(path) => {
+                std::fs::write(path, rendered)
+                    .with_context(|| format!("Failed to write {}", path.display()))?;
+                eprintln!("Wrote {}", path.display());
+            }
+            None => print!("{rendered}"),
+        }
+        Ok(())
+    }
+}
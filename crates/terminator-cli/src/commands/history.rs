@@ -0,0 +1,294 @@
+use anyhow::{anyhow, Result};
+use chrono::Utc;
+use clap::Subcommand;
+use colored::*;
+use std::path::PathBuf;
+use terminator_mcp_agent::execution_history::{self, History, RunQuery, RunStatus};
+use terminator_mcp_agent::execution_logger;
+use terminator_mcp_agent::run_report;
+
+#[derive(Debug, Subcommand)]
+pub enum HistoryCommand {
+    /// List recorded workflow runs, newest first
+    List {
+        /// Workflow id, file name without extension, or part of the URL
+        #[arg(long, short)]
+        workflow: Option<String>,
+        /// running, completed, failed or cancelled
+        #[arg(long, short)]
+        status: Option<RunStatus>,
+        /// Only runs started after this (RFC 3339, YYYY-MM-DD, or an age like 7d)
+        #[arg(long)]
+        since: Option<String>,
+        /// Maximum number of runs (0 for all)
+        #[arg(long, short = 'n', default_value_t = 20)]
+        limit: usize,
+        /// Print JSON instead of a table
+        #[arg(long)]
+        json: bool,
+    },
+    /// Show a run step by step, with its log files and screenshots
+    Show {
+        /// Run id (execution id) or a unique prefix of it
+        run_id: String,
+        /// Print the run as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Per-step duration statistics (p50/p95) across runs
+    Stats {
+        /// Workflow id, file name without extension, or part of the URL
+        #[arg(long, short)]
+        workflow: Option<String>,
+        /// Only runs started after this (RFC 3339, YYYY-MM-DD, or an age like 7d)
+        #[arg(long)]
+        since: Option<String>,
+        /// Print JSON instead of a table
+        #[arg(long)]
+        json: bool,
+    },
+    /// Write a self-contained HTML report of a run (defaults to the latest run)
+    Report {
+        /// Run id (execution id) or a unique prefix of it
+        run_id: Option<String>,
+        /// Report on the latest run of this workflow
+        #[arg(long, short, conflicts_with = "run_id")]
+        workflow: Option<String>,
+        /// Output file (default: next to the run's execution logs)
+        #[arg(long, short)]
+        output: Option<PathBuf>,
+    },
+    /// Delete execution logs and history older than the retention policy
+    Prune,
+}
+
+impl HistoryCommand {
+    pub fn execute(&self) -> Result<()> {
+        match self {
+            HistoryCommand::List {
+                workflow,
+                status,
+                since,
+                limit,
+                json,
+            } => list(
+                RunQuery {
+                    workflow: workflow.clone(),
+                    status: *status,
+                    since: parse_since(since.as_deref())?,
+                    limit: *limit,
+                },
+                *json,
+            ),
+            HistoryCommand::Show { run_id, json } => show(run_id, *json),
+            HistoryCommand::Stats {
+                workflow,
+                since,
+                json,
+            } => stats(
+                RunQuery {
+                    workflow: workflow.clone(),
+                    since: parse_since(since.as_deref())?,
+                    ..Default::default()
+                },
+                *json,
+            ),
+            HistoryCommand::Report {
+                run_id,
+                workflow,
+                output,
+            } => report(run_id.as_deref(), workflow.as_deref(), output.as_ref()),
+            HistoryCommand::Prune => prune(),
+        }
+    }
+}
+
+fn parse_since(since: Option<&str>) -> Result<Option<chrono::DateTime<Utc>>> {
+    since
+        .map(|s| execution_history::parse_since(s, Utc::now()))
+        .transpose()
+}
+
+fn list(query: RunQuery, json: bool) -> Result<()> {
+    let runs = History::open_default().query(&query)?;
+    if json {
+        let summaries: Vec<_> = runs.iter().map(|run| run.summary()).collect();
+        println!("{}", serde_json::to_string_pretty(&summaries)?);
+        return Ok(());
+    }
+    if runs.is_empty() {
+        println!("No recorded runs.");
+        return Ok(());
+    }
+
+    println!(
+        "{:<38} {:<10} {:<20} {:>9} {:>6}  {}",
+        "RUN".bold(),
+        "STATUS".bold(),
+        "STARTED".bold(),
+        "DURATION".bold(),
+        "STEPS".bold(),
+        "WORKFLOW".bold()
+    );
+    for run in &runs {
+        let mut workflow = run
+            .workflow_name()
+            .unwrap_or_else(|| "(inline)".to_string());
+        if let Some(failed) = run.steps.iter().find(|s| !s.success) {
+            workflow.push_str(&format!(" — failed at {}", failed.label()));
+        }
+        println!(
+            "{:<38} {:<10} {:<20} {:>9} {:>6}  {}",
+            run.run_id,
+            status_label(run.status),
+            run.started_at.format("%Y-%m-%d %H:%M:%S"),
+            run.duration_ms.map(format_ms).unwrap_or_else(|| "-".into()),
+            run.steps.len(),
+            workflow
+        );
+    }
+    Ok(())
+}
+
+fn show(run_id: &str, json: bool) -> Result<()> {
+    let run = History::open_default()
+        .run(run_id)?
+        .ok_or_else(|| anyhow!("No run '{run_id}' in the execution history"))?;
+    if json {
+        println!("{}", serde_json::to_string_pretty(&run)?);
+        return Ok(());
+    }
+
+    println!("{} {}", "Run:".bold(), run.run_id);
+    println!("{} {}", "Status:".bold(), status_label(run.status));
+    if let Some(workflow) = &run.workflow {
+        println!("{} {}", "Workflow:".bold(), workflow);
+    }
+    if let Some(workflow_id) = &run.workflow_id {
+        println!("{} {}", "Workflow id:".bold(), workflow_id);
+    }
+    if let Some(parent) = &run.parent_run_id {
+        println!("{} {}", "Parent run:".bold(), parent);
+    }
+    if let Some(client) = &run.client {
+        println!("{} {}", "Client:".bold(), client);
+    }
+    println!("{} {}", "Started:".bold(), run.started_at.to_rfc3339());
+    if let Some(duration) = run.duration_ms {
+        println!("{} {}", "Duration:".bold(), format_ms(duration));
+    }
+    if let Some(error) = &run.error {
+        println!("{} {}", "Error:".bold(), error.red());
+    }
+
+    if run.steps.is_empty() {
+        println!("{}", "No steps recorded.".dimmed());
+        return Ok(());
+    }
+    println!("{}", "Steps:".bold());
+    for (i, step) in run.steps.iter().enumerate() {
+        let mark = if step.success {
+            "✓".green()
+        } else {
+            "✗".red()
+        };
+        let attempt = step
+            .retry_attempt
+            .filter(|a| *a > 0)
+            .map(|a| format!(" (retry {a})"))
+            .unwrap_or_default();
+        println!(
+            "  {:>3}. {} {:<32} {:>9}{}",
+            i + 1,
+            mark,
+            step.label(),
+            format_ms(step.duration_ms),
+            attempt
+        );
+        if let Some(error) = &step.error {
+            println!("       {}", error.red());
+        }
+        if let Some(log) = &step.log_path {
+            println!("       {} {}", "log:".dimmed(), log);
+        }
+        for screenshot in &step.screenshots {
+            println!("       {} {}", "screenshot:".dimmed(), screenshot);
+        }
+    }
+    Ok(())
+}
+
+fn stats(query: RunQuery, json: bool) -> Result<()> {
+    let stats = History::open_default().step_stats(&query)?;
+    if json {
+        println!("{}", serde_json::to_string_pretty(&stats)?);
+        return Ok(());
+    }
+    if stats.is_empty() {
+        println!("No recorded steps.");
+        return Ok(());
+    }
+
+    println!(
+        "{:<32} {:<24} {:>6} {:>6} {:>9} {:>9} {:>9}",
+        "STEP".bold(),
+        "TOOL".bold(),
+        "RUNS".bold(),
+        "FAILED".bold(),
+        "P50".bold(),
+        "P95".bold(),
+        "MAX".bold()
+    );
+    for s in &stats {
+        println!(
+            "{:<32} {:<24} {:>6} {:>6} {:>9} {:>9} {:>9}",
+            s.step,
+            s.tool_name,
+            s.count,
+            s.failures,
+            format_ms(s.p50_ms),
+            format_ms(s.p95_ms),
+            format_ms(s.max_ms)
+        );
+    }
+    Ok(())
+}
+
+fn report(run_id: Option<&str>, workflow: Option<&str>, output: Option<&PathBuf>) -> Result<()> {
+    let run = run_report::find_run(&History::open_default(), run_id, workflow)?;
+    let (id, status) = (run.run_id.clone(), run.status);
+    let path = run_report::write_report(run, output.map(PathBuf::as_path))?;
+    println!(
+        "{} Report for run {} ({}): {}",
+        "✓".green(),
+        id,
+        status_label(status),
+        path.display()
+    );
+    Ok(())
+}
+
+fn prune() -> Result<()> {
+    let (files, runs) = execution_logger::cleanup_old_executions();
+    println!("Deleted {files} execution log file(s) and {runs} run(s) from the history.");
+    Ok(())
+}
+
+fn format_ms(ms: u64) -> String {
+    if ms < 1000 {
+        format!("{ms}ms")
+    } else if ms < 60_000 {
+        format!("{:.1}s", ms as f64 / 1000.0)
+    } else {
+        format!("{}m{:02}s", ms / 60_000, (ms % 60_000) / 1000)
+    }
+}
+
+fn status_label(status: RunStatus) -> ColoredString {
+    match status {
+        RunStatus::Running => "running".yellow(),
+        RunStatus::Completed => "completed".green(),
+        RunStatus::Failed => "failed".red(),
+        RunStatus::Cancelled => "cancelled".yellow(),
+    }
+}
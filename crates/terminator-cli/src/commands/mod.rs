@@ -1,2 +1,8 @@
+pub mod check;
+pub mod diff_recordings;
+pub mod graph;
+pub mod history;
 pub mod init;
 pub mod setup;
+pub mod state;
+pub mod test;
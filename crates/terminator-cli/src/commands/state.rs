@@ -0,0 +1,171 @@
+use anyhow::{anyhow, Result};
+use clap::Subcommand;
+use colored::*;
+use terminator_mcp_agent::workflow_state::{self, CheckpointStatus, StoredState};
+
+#[derive(Debug, Subcommand)]
+pub enum StateCommand {
+    /// List saved workflow checkpoints
+    List,
+    /// Show a saved checkpoint (workflow id or state key from `list`)
+    Show {
+        key: String,
+        /// Print the checkpoint as JSON, secrets masked, instead of a summary
+        #[arg(long)]
+        json: bool,
+    },
+    /// Delete saved checkpoints so the next run starts fresh
+    Clear {
+        /// Workflow id or state key from `list`
+        #[arg(required_unless_present = "all")]
+        key: Option<String>,
+        /// Delete every saved checkpoint
+        #[arg(long, conflicts_with = "key")]
+        all: bool,
+    },
+}
+
+impl StateCommand {
+    pub fn execute(&self) -> Result<()> {
+        match self {
+            StateCommand::List => list(),
+            StateCommand::Show { key, json } => show(key, *json),
+            StateCommand::Clear { key, all } => clear(key.as_deref(), *all),
+        }
+    }
+}
+
+fn list() -> Result<()> {
+    let states = workflow_state::list_states()?;
+    if states.is_empty() {
+        println!("No saved workflow state.");
+        return Ok(());
+    }
+
+    println!(
+        "{:<28} {:<10} {:<8} {:<24} {}",
+        "KEY".bold(),
+        "STATUS".bold(),
+        "RESUME".bold(),
+        "UPDATED".bold(),
+        "WORKFLOW".bold()
+    );
+    for state in &states {
+        let cp = &state.checkpoint;
+        let resume = cp
+            .resume_point()
+            .map(|p| format!("#{}", p.step_index))
+            .unwrap_or_else(|| "-".to_string());
+        println!(
+            "{:<28} {:<10} {:<8} {:<24} {}",
+            state.key,
+            status_label(cp.status),
+            resume,
+            cp.last_updated.get(..19).unwrap_or(&cp.last_updated),
+            cp.workflow_file.as_deref().unwrap_or("")
+        );
+    }
+    Ok(())
+}
+
+fn show(key: &str, raw: bool) -> Result<()> {
+    let StoredState {
+        path, checkpoint, ..
+    } = workflow_state::find_state(key)?.ok_or_else(|| anyhow!("No saved state for '{key}'"))?;
+
+    if raw {
+        println!("{}", serde_json::to_string_pretty(&checkpoint.redacted())?);
+        return Ok(());
+    }
+
+    println!("{} {}", "State file:".bold(), path.display());
+    println!("{} {}", "Status:".bold(), status_label(checkpoint.status));
+    println!("{} {}", "Updated:".bold(), checkpoint.last_updated);
+    if let Some(file) = &checkpoint.workflow_file {
+        println!("{} {}", "Workflow file:".bold(), file);
+    }
+    if let Some(hash) = &checkpoint.content_hash {
+        println!("{} {}", "Content hash:".bold(), hash);
+    }
+    if let Some(exec_id) = &checkpoint.execution_id {
+        println!("{} {}", "Execution id:".bold(), exec_id);
+    }
+    println!(
+        "{} #{} ({})",
+        "Last step:".bold(),
+        checkpoint.last_step_index,
+        checkpoint.last_step_id.as_deref().unwrap_or("no id")
+    );
+    if let Some(position) = &checkpoint.position {
+        println!(
+            "{} #{} ({}), group sub-step {}, attempt {}",
+            "In progress:".bold(),
+            position.step_index,
+            position.step_id.as_deref().unwrap_or("no id"),
+            position.group_step_index,
+            position.retry_attempt + 1
+        );
+    }
+    match checkpoint.resume_point() {
+        Some(point) => println!(
+            "{} step #{} (group sub-step {}, attempt {})",
+            "Resume at:".bold(),
+            point.step_index,
+            point.group_step_index,
+            point.retry_attempt + 1
+        ),
+        None => println!("{} nothing to resume", "Resume at:".bold()),
+    }
+
+    if !checkpoint.step_results.is_empty() {
+        println!("{}", "Step results:".bold());
+        for (step, result) in &checkpoint.step_results {
+            println!(
+                "  {:<24} {}",
+                step,
+                result["status"].as_str().unwrap_or("unknown")
+            );
+        }
+    }
+    let env_keys = checkpoint
+        .env
+        .as_object()
+        .map(|env| env.keys().cloned().collect::<Vec<_>>().join(", "))
+        .unwrap_or_default();
+    println!("{} {}", "Env keys:".bold(), env_keys);
+    Ok(())
+}
+
+fn clear(key: Option<&str>, all: bool) -> Result<()> {
+    let keys: Vec<String> = if all {
+        workflow_state::list_states()?
+            .into_iter()
+            .map(|s| s.key)
+            .collect()
+    } else {
+        key.map(|k| vec![k.to_string()]).unwrap_or_default()
+    };
+
+    let mut cleared = 0;
+    for key in &keys {
+        if workflow_state::clear_state(key)? {
+            println!("  {} Cleared state for {}", "✓".green(), key);
+            cleared += 1;
+        } else if !all {
+            return Err(anyhow!("No saved state for '{key}'"));
+        }
+    }
+    if all {
+        println!("Cleared {cleared} saved state file(s).");
+    }
+    Ok(())
+}
+
+fn status_label(status: CheckpointStatus) -> ColoredString {
+    match status {
+        CheckpointStatus::Running => "running".yellow(),
+        CheckpointStatus::Completed => "completed".green(),
+        CheckpointStatus::Failed => "failed".red(),
+        CheckpointStatus::Cancelled => "cancelled".yellow(),
+    }
+}
@@ -0,0 +1,908 @@
+//! `terminator mcp test`: run a suite of workflows as acceptance tests.
+//!
+//! Workflows are discovered from files, directories and glob patterns, filtered
+//! by the `tags:` in their metadata, optionally split into shards for parallel
+//! CI jobs, and run one after another on a single agent connection.
+//!
+//! A YAML workflow can tune how it is tested with a `test:` block; hook paths
+//! are relative to the workflow file:
+//!
+//! ```yaml
+//! tags: [smoke, invoices]
+//! test:
+//!   setup: fixtures/login.yml
+//!   teardown: fixtures/logout.yml
+//!   retries: 2
+//! ```
+//!
+//! TypeScript workflows take setup, teardown and retries from the command line.
+
+use anyhow::{anyhow, Context, Result};
+use clap::Args;
+use colored::*;
+use regex::Regex;
+use serde_json::{json, Value};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::OnceLock;
+use std::time::Instant;
+use terminator_mcp_agent::execution_history::History;
+
+use crate::mcp_client::{McpSession, Transport};
+use crate::typescript_workflow;
+use crate::workflow_report::{self, ReportTarget, TestSuite};
+use crate::workflow_result::WorkflowResult;
+use crate::workflow_validator::WorkflowOutputValidator;
+
+#[derive(Debug, Args)]
+pub struct TestCommand {
+    /// MCP server URL (e.g., http://localhost:3000)
+    #[arg(long, short = 'u', conflicts_with = "command")]
+    pub url: Option<String>,
+
+    /// Command to start the MCP server via stdio (e.g., "npx -y terminator-mcp-agent")
+    #[arg(long, short = 'c', conflicts_with = "url")]
+    pub command: Option<String>,
+
+    /// Workflow files, directories or glob patterns (e.g. "tests/**/*.yml")
+    #[arg(default_value = ".")]
+    pub patterns: Vec<String>,
+
+    /// Only run workflows with one of these tags (repeatable or comma-separated)
+    #[arg(long = "tag", short = 't', value_delimiter = ',')]
+    pub tags: Vec<String>,
+
+    /// Skip workflows with any of these tags
+    #[arg(long = "exclude-tag", value_delimiter = ',')]
+    pub exclude_tags: Vec<String>,
+
+    /// Run one shard of the suite, e.g. 2/4 for the second of four CI jobs
+    #[arg(long)]
+    pub shard: Option<Shard>,
+
+    /// Re-run a failing workflow up to N times, with teardown and setup in
+    /// between; a later pass is reported as flaky
+    #[arg(long, default_value_t = 0)]
+    pub retries: u32,
+
+    /// Workflow to run before each test workflow (overridden by `test.setup`)
+    #[arg(long)]
+    pub setup: Option<PathBuf>,
+
+    /// Workflow to run after each test workflow, even a failed one (overridden by `test.teardown`)
+    #[arg(long)]
+    pub teardown: Option<PathBuf>,
+
+    /// Fail workflows whose output quality score is below this (0-100, as `mcp validate --score`)
+    #[arg(long, value_parser = clap::value_parser!(u8).range(0..=100))]
+    pub min_score: Option<u8>,
+
+    /// Stop after the first failing workflow
+    #[arg(long)]
+    pub fail_fast: bool,
+
+    /// JSON object with input values passed to every workflow
+    #[arg(long)]
+    pub inputs: Option<String>,
+
+    /// Skip TypeScript type checking before running TypeScript workflows
+    #[arg(long)]
+    pub skip_type_check: bool,
+
+    /// Test report, repeatable: junit=<path.xml>, tap, or tap=<path.tap>
+    #[arg(long = "report", value_name = "FORMAT[=PATH]")]
+    pub report: Vec<ReportTarget>,
+
+    /// List the selected workflows without running them
+    #[arg(long)]
+    pub list: bool,
+}
+
+/// `--shard INDEX/TOTAL`, with a 1-based index
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Shard {
+    pub index: usize,
+    pub total: usize,
+}
+
+impl FromStr for Shard {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let parsed = s
+            .split_once('/')
+            .and_then(|(i, t)| Some((i.trim().parse().ok()?, t.trim().parse().ok()?)));
+        match parsed {
+            Some((index, total)) if index >= 1 && index <= total => Ok(Shard { index, total }),
+            _ => Err(format!(
+                "invalid shard '{s}' (expected INDEX/TOTAL with 1 <= INDEX <= TOTAL, e.g. 2/4)"
+            )),
+        }
+    }
+}
+
+impl Shard {
+    /// Workflows are dealt round-robin in path order, so every CI job computes
+    /// the same split from the same checkout
+    pub fn select<T>(&self, items: Vec<T>) -> Vec<T> {
+        items
+            .into_iter()
+            .enumerate()
+            .filter(|(i, _)| i % self.total == self.index - 1)
+            .map(|(_, item)| item)
+            .collect()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum WorkflowKind {
+    Yaml,
+    TypeScript,
+}
+
+/// A discovered workflow and its test metadata
+#[derive(Debug, Clone)]
+struct TestWorkflow {
+    path: PathBuf,
+    /// Path as shown in the summary and used as the report suite name
+    name: String,
+    kind: WorkflowKind,
+    tags: Vec<String>,
+    setup: Option<PathBuf>,
+    teardown: Option<PathBuf>,
+    retries: Option<u32>,
+}
+
+impl TestWorkflow {
+    fn load(path: &Path) -> Result<Self> {
+        let name = display_name(path);
+        let is_typescript =
+            typescript_workflow::is_typescript_workflow(&path.to_string_lossy(), true);
+        if is_typescript {
+            let source = typescript_entry(path)
+                .and_then(|entry| std::fs::read_to_string(entry).ok())
+                .unwrap_or_default();
+            if has_typescript_test_block(&source) {
+                println!(
+                    "{} {name}: `test` settings are only read from YAML workflows; use --setup, --teardown and --retries",
+                    "⚠".yellow()
+                );
+            }
+            return Ok(TestWorkflow {
+                path: path.to_path_buf(),
+                name,
+                kind: WorkflowKind::TypeScript,
+                tags: typescript_tags(&source),
+                setup: None,
+                teardown: None,
+                retries: None,
+            });
+        }
+
+        let content = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read workflow file: {}", path.display()))?;
+        let workflow = crate::parse_workflow_content(&content)
+            .with_context(|| format!("Failed to parse workflow from {}", path.display()))?;
+        let base = path.parent().unwrap_or(Path::new("."));
+        let test = workflow.get("test");
+        let hook = |key: &str| {
+            test.and_then(|t| t.get(key))
+                .and_then(Value::as_str)
+                .map(|p| base.join(p))
+        };
+        Ok(TestWorkflow {
+            path: path.to_path_buf(),
+            name,
+            kind: WorkflowKind::Yaml,
+            tags: yaml_tags(workflow.get("tags")),
+            setup: hook("setup"),
+            teardown: hook("teardown"),
+            retries: test
+                .and_then(|t| t.get("retries"))
+                .and_then(Value::as_u64)
+                .map(|n| n as u32),
+        })
+    }
+
+    fn matches(&self, include: &[String], exclude: &[String]) -> bool {
+        let has = |tag: &String| self.tags.iter().any(|t| t.eq_ignore_ascii_case(tag));
+        (include.is_empty() || include.iter().any(has)) && !exclude.iter().any(has)
+    }
+}
+
+fn display_name(path: &Path) -> String {
+    std::env::current_dir()
+        .ok()
+        .and_then(|cwd| path.strip_prefix(cwd).ok())
+        .unwrap_or(path)
+        .display()
+        .to_string()
+}
+
+fn typescript_entry(path: &Path) -> Option<PathBuf> {
+    if path.is_file() {
+        return Some(path.to_path_buf());
+    }
+    [
+        "terminator.ts",
+        "src/terminator.ts",
+        "workflow.ts",
+        "index.ts",
+    ]
+    .into_iter()
+    .map(|entry| path.join(entry))
+    .find(|p| p.is_file())
+}
+
+/// `tags: [a, b]` or `tags: "a, b"`
+fn yaml_tags(tags: Option<&Value>) -> Vec<String> {
+    match tags {
+        Some(Value::Array(items)) => items
+            .iter()
+            .filter_map(Value::as_str)
+            .map(str::to_string)
+            .collect(),
+        Some(Value::String(list)) => list
+            .split(',')
+            .map(str::trim)
+            .filter(|t| !t.is_empty())
+            .map(str::to_string)
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// Pull a literal `tags: ['a', "b"]` array out of a `createWorkflow` call
+fn typescript_tags(source: &str) -> Vec<String> {
+    static ARRAY: OnceLock<Regex> = OnceLock::new();
+    static ITEM: OnceLock<Regex> = OnceLock::new();
+    let array = ARRAY.get_or_init(|| Regex::new(r"\btags\s*:\s*\[([^\]]*)\]").unwrap());
+    let item = ITEM.get_or_init(|| Regex::new(r#"'([^']*)'|"([^"]*)"|`([^`$]*)`"#).unwrap());
+    let Some(body) = array.captures(source).map(|c| c[1].to_string()) else {
+        return Vec::new();
+    };
+    item.captures_iter(&body)
+        .filter_map(|c| c.get(1).or(c.get(2)).or(c.get(3)))
+        .map(|m| m.as_str().to_string())
+        .collect()
+}
+
+/// Whether the source has a `test: { ... }` block like the YAML metadata
+fn has_typescript_test_block(source: &str) -> bool {
+    static TEST: OnceLock<Regex> = OnceLock::new();
+    TEST.get_or_init(|| Regex::new(r"\btest\s*:\s*\{").unwrap())
+        .is_match(source)
+}
+
+fn is_ignored_dir(path: &Path) -> bool {
+    path.components().any(|c| {
+        let name = c.as_os_str().to_string_lossy();
+        name == "node_modules" || (name.starts_with('.') && name.len() > 1 && name != "..")
+    })
+}
+
+/// Expand the patterns into workflow paths, sorted and deduplicated. Directories
+/// are searched recursively for YAML workflows and TypeScript workflow folders;
+/// `.ts`/`.js` files are only picked up when a pattern names them.
+fn discover(patterns: &[String]) -> Result<Vec<PathBuf>> {
+    let mut found = Vec::new();
+    for pattern in patterns {
+        let path = Path::new(pattern);
+        if path.is_file() {
+            found.push(path.to_path_buf());
+            continue;
+        }
+        if path.is_dir() {
+            found.extend(discover_dir(path)?);
+            continue;
+        }
+        let mut matched = false;
+        for entry in glob::glob(pattern).with_context(|| format!("Invalid pattern '{pattern}'"))? {
+            let entry = entry?;
+            if is_ignored_dir(&entry) {
+                continue;
+            }
+            matched = true;
+            if entry.is_dir() {
+                found.extend(discover_dir(&entry)?);
+            } else if is_workflow_file(&entry, true) {
+                found.push(entry);
+            }
+        }
+        if !matched {
+            return Err(anyhow!("No workflow matches '{pattern}'"));
+        }
+    }
+
+    let mut unique: Vec<PathBuf> = Vec::new();
+    for path in found {
+        let canonical = std::fs::canonicalize(&path).unwrap_or(path);
+        if !unique.contains(&canonical) {
+            unique.push(canonical);
+        }
+    }
+    unique.sort();
+    Ok(unique)
+}
+
+fn discover_dir(dir: &Path) -> Result<Vec<PathBuf>> {
+    if typescript_workflow::is_typescript_workflow(&dir.to_string_lossy(), true) {
+        return Ok(vec![dir.to_path_buf()]);
+    }
+    let root = glob::Pattern::escape(&dir.to_string_lossy());
+    let mut found = Vec::new();
+    for pattern in ["**/*.yml", "**/*.yaml", "**/package.json"] {
+        for entry in glob::glob(&format!("{root}/{pattern}"))?.flatten() {
+            let relative = entry.strip_prefix(dir).unwrap_or(&entry);
+            if is_ignored_dir(relative) {
+                continue;
+            }
+            if pattern == "**/package.json" {
+                if let Some(project) = entry.parent() {
+                    if typescript_workflow::is_typescript_workflow(&project.to_string_lossy(), true)
+                    {
+                        found.push(project.to_path_buf());
+                    }
+                }
+            } else if is_workflow_file(&entry, false) {
+                found.push(entry);
+            }
+        }
+    }
+    Ok(found)
+}
+
+/// Drop the workflows used as a setup or teardown hook, by the command line or
+/// by any discovered workflow: they often sit next to the tests, but aren't tests
+fn without_hooks(
+    workflows: Vec<TestWorkflow>,
+    cli_hooks: [&Option<PathBuf>; 2],
+) -> Vec<TestWorkflow> {
+    let hooks: Vec<PathBuf> = cli_hooks
+        .into_iter()
+        .chain(workflows.iter().flat_map(|w| [&w.setup, &w.teardown]))
+        .flatten()
+        .filter_map(|p| std::fs::canonicalize(p).ok())
+        .collect();
+    workflows
+        .into_iter()
+        .filter(|w| !hooks.contains(&w.path))
+        .collect()
+}
+
+/// YAML/JSON files count when they parse as a workflow, so CI configs and
+/// fixtures lying around don't break discovery
+fn is_workflow_file(path: &Path, named: bool) -> bool {
+    match path.extension().and_then(|e| e.to_str()) {
+        Some("ts" | "js") => named,
+        Some("yml" | "yaml" | "json") => std::fs::read_to_string(path)
+            .ok()
+            .and_then(|content| crate::parse_workflow_content(&content).ok())
+            .is_some(),
+        _ => false,
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Verdict {
+    Passed,
+    Flaky,
+    Failed,
+}
+
+/// Outcome of one test workflow, after retries
+struct TestRun {
+    name: String,
+    verdict: Verdict,
+    attempts: u32,
+    duration_ms: u64,
+    score: Option<u8>,
+    message: Option<String>,
+    suite: TestSuite,
+}
+
+impl TestCommand {
+    pub async fn execute(&self, transport: Transport) -> Result<()> {
+        let inputs = self
+            .inputs
+            .as_deref()
+            .map(serde_json::from_str::<Value>)
+            .transpose()
+            .context("Invalid JSON in --inputs parameter")?;
+
+        let discovered = discover(&self.patterns)?
+            .iter()
+            .map(|path| TestWorkflow::load(path))
+            .collect::<Result<Vec<_>>>()?;
+        let mut workflows: Vec<TestWorkflow> =
+            without_hooks(discovered, [&self.setup, &self.teardown])
+                .into_iter()
+                .filter(|w| w.matches(&self.tags, &self.exclude_tags))
+                .collect();
+        let selected_count = workflows.len();
+        if let Some(shard) = self.shard {
+            workflows = shard.select(workflows);
+        }
+
+        if self.list {
+            for workflow in &workflows {
+                let tags = if workflow.tags.is_empty() {
+                    String::new()
+                } else {
+                    format!(" [{}]", workflow.tags.join(", "))
+                };
+                println!("{}{}", workflow.name, tags.dimmed());
+            }
+            return Ok(());
+        }
+        if workflows.is_empty() {
+            if selected_count > 0 {
+                println!("No workflows in this shard ({selected_count} selected in total).");
+                return Ok(());
+            }
+            return Err(anyhow!("No workflows matched the patterns and tags"));
+        }
+
+        let shard_label = self
+            .shard
+            .map(|s| format!(" (shard {}/{} of {selected_count})", s.index, s.total))
+            .unwrap_or_default();
+        println!(
+            "{} Running {} workflow(s){shard_label}",
+            "🧪".cyan(),
+            workflows.len()
+        );
+
+        let session = McpSession::connect(&transport).await?;
+        let is_remote_http = matches!(transport, Transport::Http { .. });
+        let runner = Runner {
+            session: &session,
+            inputs: inputs.as_ref(),
+            is_remote_http,
+        };
+
+        let mut runs = Vec::new();
+        for workflow in &workflows {
+            println!("\n{} {}", "▶".cyan(), workflow.name.bold());
+            let run = self.run_test(&runner, workflow).await;
+            match run.verdict {
+                Verdict::Passed => {
+                    println!("  {} passed in {}", "✓".green(), format_ms(run.duration_ms))
+                }
+                Verdict::Flaky => println!(
+                    "  {} passed on attempt {} (flaky) in {}",
+                    "⚠".yellow(),
+                    run.attempts,
+                    format_ms(run.duration_ms)
+                ),
+                Verdict::Failed => println!(
+                    "  {} failed: {}",
+                    "✗".red(),
+                    run.message.as_deref().unwrap_or("workflow failed")
+                ),
+            }
+            let failed = run.verdict == Verdict::Failed;
+            runs.push(run);
+            if failed && self.fail_fast {
+                println!(
+                    "{}",
+                    "Stopping after the first failure (--fail-fast)".yellow()
+                );
+                break;
+            }
+        }
+        session.close().await?;
+
+        print_summary(&runs, workflows.len());
+
+        let suites: Vec<TestSuite> = runs.iter().map(|r| r.suite.clone()).collect();
+        workflow_report::write_reports(&self.report, &suites)?;
+
+        let failed = runs.iter().filter(|r| r.verdict == Verdict::Failed).count();
+        if failed > 0 {
+            return Err(anyhow!(
+                "{failed} of {} workflow(s) failed",
+                workflows.len()
+            ));
+        }
+        Ok(())
+    }
+
+    async fn run_test(&self, runner: &Runner<'_>, workflow: &TestWorkflow) -> TestRun {
+        let started = Instant::now();
+        let finish = |verdict, attempts, score, message: Option<String>, suite| TestRun {
+            name: workflow.name.clone(),
+            verdict,
+            attempts,
+            duration_ms: started.elapsed().as_millis() as u64,
+            score,
+            message,
+            suite,
+        };
+
+        if workflow.kind == WorkflowKind::TypeScript && !self.skip_type_check {
+            if let Err(e) = typescript_workflow::run_type_check(&workflow.path.to_string_lossy()) {
+                let message = format!("type check failed: {e}");
+                let suite = TestSuite::error(&workflow.name, "type check", &message, 0);
+                return finish(Verdict::Failed, 0, None, Some(message), suite);
+            }
+        }
+
+        let setup = workflow.setup.as_ref().or(self.setup.as_ref());
+        let teardown = workflow.teardown.as_ref().or(self.teardown.as_ref());
+
+        if let Some(setup) = setup {
+            if let Err(message) = runner.run_hook(setup).await {
+                let message = format!("setup {} failed: {message}", display_name(setup));
+                let suite = TestSuite::error(&workflow.name, "setup", &message, 0);
+                if let Some(teardown) = teardown {
+                    let _ = runner.run_hook(teardown).await;
+                }
+                return finish(Verdict::Failed, 0, None, Some(message), suite);
+            }
+        }
+
+        let max_attempts = 1 + workflow.retries.unwrap_or(self.retries);
+        let mut attempt = 0;
+        let mut teardown_failed = false;
+        let (mut verdict, mut score, mut message, mut suite) = (Verdict::Failed, None, None, None);
+        while attempt < max_attempts {
+            if attempt > 0 {
+                println!(
+                    "  {} retrying ({}/{})",
+                    "↻".yellow(),
+                    attempt + 1,
+                    max_attempts
+                );
+                // Start the retry from what setup leaves, not from where the failed attempt stopped
+                if let Err((hook, reset_message)) = runner.reset(setup, teardown).await {
+                    teardown_failed = hook == "teardown";
+                    if let Some(suite) = suite.as_mut() {
+                        suite.cases.extend(
+                            TestSuite::error(&workflow.name, hook, &reset_message, 0).cases,
+                        );
+                    }
+                    message = Some(reset_message);
+                    break;
+                }
+            }
+            attempt += 1;
+            let outcome = runner.run_workflow(workflow, self.min_score).await;
+            score = outcome.score;
+            message = outcome.failure;
+            suite = Some(outcome.suite);
+            if message.is_none() {
+                verdict = if attempt > 1 {
+                    Verdict::Flaky
+                } else {
+                    Verdict::Passed
+                };
+                break;
+            }
+        }
+        let mut suite = suite.expect("at least one attempt");
+
+        // A teardown that just failed between attempts isn't run a second time
+        if let Some(teardown) = teardown.filter(|_| !teardown_failed) {
+            if let Err(e) = runner.run_hook(teardown).await {
+                let teardown_message = format!("teardown {} failed: {e}", display_name(teardown));
+                suite.cases.extend(
+                    TestSuite::error(&workflow.name, "teardown", &teardown_message, 0).cases,
+                );
+                verdict = Verdict::Failed;
+                message.get_or_insert(teardown_message);
+            }
+        }
+
+        finish(verdict, attempt, score, message, suite)
+    }
+}
+
+struct Runner<'a> {
+    session: &'a McpSession,
+    inputs: Option<&'a Value>,
+    is_remote_http: bool,
+}
+
+/// One attempt at a test workflow
+struct Attempt {
+    suite: TestSuite,
+    score: Option<u8>,
+    /// Why the attempt failed; `None` when it passed
+    failure: Option<String>,
+}
+
+impl Runner<'_> {
+    /// `execute_sequence` arguments for a workflow, mirroring `mcp run`: local
+    /// files go by `file://` URL, remote agents get the workflow content
+    fn arguments(&self, path: &Path, kind: WorkflowKind, execution_id: &str) -> Result<Value> {
+        let mut args = match kind {
+            WorkflowKind::TypeScript => typescript_workflow::build_typescript_workflow_args(
+                typescript_workflow::path_to_file_url(&path.to_string_lossy())?,
+                None,
+                None,
+                None,
+                None,
+                None,
+                false,
+                false,
+            )?,
+            WorkflowKind::Yaml if self.is_remote_http => {
+                let content = std::fs::read_to_string(path)?;
+                crate::parse_workflow_content(&content)?
+            }
+            WorkflowKind::Yaml => {
+                let abs_path = std::fs::canonicalize(path)?;
+                json!({
+                    "url": format!("file://{}", abs_path.display()),
+                    "include_detailed_results": true,
+                })
+            }
+        };
+        if let Some(obj) = args.as_object_mut() {
+            obj.insert("execution_id".to_string(), json!(execution_id));
+            if let Some(inputs) = self.inputs {
+                obj.insert("inputs".to_string(), inputs.clone());
+            }
+        }
+        Ok(args)
+    }
+
+    async fn execute(&self, path: &Path, kind: WorkflowKind) -> Result<(String, Value)> {
+        let execution_id = format!(
+            "test-{}-{}",
+            chrono::Utc::now().format("%Y%m%d%H%M%S%3f"),
+            std::process::id()
+        );
+        let args = self.arguments(path, kind, &execution_id)?;
+        let response = self.session.call_json("execute_sequence", &args).await?;
+        Ok((execution_id, response))
+    }
+
+    async fn run_workflow(&self, workflow: &TestWorkflow, min_score: Option<u8>) -> Attempt {
+        let started = Instant::now();
+        let (execution_id, response) = match self.execute(&workflow.path, workflow.kind).await {
+            Ok(ok) => ok,
+            Err(e) => {
+                let message = e.to_string();
+                let elapsed = started.elapsed().as_millis() as u64;
+                return Attempt {
+                    suite: TestSuite::error(&workflow.name, "execute_sequence", &message, elapsed),
+                    score: None,
+                    failure: Some(message),
+                };
+            }
+        };
+        let result = match WorkflowResult::from_mcp_response(&response) {
+            Ok(result) => result,
+            Err(e) => {
+                let message = format!("unreadable result: {e}");
+                return Attempt {
+                    suite: TestSuite::error(&workflow.name, "execute_sequence", &message, 0),
+                    score: None,
+                    failure: Some(message),
+                };
+            }
+        };
+
+        // Screenshots for the report come from the local history (stdio agents)
+        let steps = History::open_default()
+            .run(&execution_id)
+            .ok()
+            .flatten()
+            .map(|run| run.steps)
+            .unwrap_or_default();
+        let mut suite = TestSuite::from_response(&workflow.name, &response, &result, &steps);
+        let score = WorkflowOutputValidator::validate(&response).quality_score();
+
+        let mut failure = (!result.success).then(|| {
+            first_failure(&suite)
+                .or_else(|| result.error.clone())
+                .unwrap_or_else(|| result.message.clone())
+        });
+        if let Some(min) = min_score.filter(|min| score < *min) {
+            let message = format!("output quality score {score} is below {min}");
+            suite
+                .cases
+                .extend(TestSuite::error(&workflow.name, "output quality", &message, 0).cases);
+            failure.get_or_insert(message);
+        }
+        Attempt {
+            suite,
+            score: Some(score),
+            failure,
+        }
+    }
+
+    /// Run teardown and then setup between two attempts; a failure is returned
+    /// with the name of the hook that failed
+    async fn reset(
+        &self,
+        setup: Option<&PathBuf>,
+        teardown: Option<&PathBuf>,
+    ) -> std::result::Result<(), (&'static str, String)> {
+        if let Some(teardown) = teardown {
+            self.run_hook(teardown).await.map_err(|e| {
+                let message = format!("teardown {} failed: {e}", display_name(teardown));
+                ("teardown", message)
+            })?;
+        }
+        if let Some(setup) = setup {
+            self.run_hook(setup).await.map_err(|e| {
+                let message = format!("setup {} failed: {e}", display_name(setup));
+                ("setup", message)
+            })?;
+        }
+        Ok(())
+    }
+
+    /// Run a setup/teardown workflow; any failure is returned as a message
+    async fn run_hook(&self, path: &Path) -> std::result::Result<(), String> {
+        let kind = if typescript_workflow::is_typescript_workflow(&path.to_string_lossy(), true) {
+            WorkflowKind::TypeScript
+        } else {
+            WorkflowKind::Yaml
+        };
+        let (_, response) = self.execute(path, kind).await.map_err(|e| e.to_string())?;
+        let result = WorkflowResult::from_mcp_response(&response).map_err(|e| e.to_string())?;
+        if result.success {
+            Ok(())
+        } else {
+            Err(result.error.unwrap_or(result.message))
+        }
+    }
+}
+
+fn first_failure(suite: &TestSuite) -> Option<String> {
+    suite.cases.iter().find_map(|case| match &case.outcome {
+        workflow_report::Outcome::Failed { message, .. } => {
+            Some(format!("{}: {message}", case.name))
+        }
+        _ => None,
+    })
+}
+
+fn print_summary(runs: &[TestRun], total: usize) {
+    println!("\n{}", "═".repeat(60));
+    println!(
+        "{:<8} {:>9} {:>6} {:>9}  {}",
+        "RESULT".bold(),
+        "DURATION".bold(),
+        "SCORE".bold(),
+        "ATTEMPTS".bold(),
+        "WORKFLOW".bold()
+    );
+    for run in runs {
+        let verdict = match run.verdict {
+            Verdict::Passed => "pass".green(),
+            Verdict::Flaky => "flaky".yellow(),
+            Verdict::Failed => "FAIL".red(),
+        };
+        println!(
+            "{:<8} {:>9} {:>6} {:>9}  {}",
+            verdict,
+            format_ms(run.duration_ms),
+            run.score
+                .map(|s| s.to_string())
+                .unwrap_or_else(|| "-".into()),
+            run.attempts,
+            run.name
+        );
+    }
+    println!("{}", "─".repeat(60));
+
+    let count = |verdict| runs.iter().filter(|r| r.verdict == verdict).count();
+    let scores: Vec<u32> = runs.iter().filter_map(|r| r.score.map(u32::from)).collect();
+    let mut summary = format!(
+        "{} passed, {} flaky, {} failed",
+        count(Verdict::Passed),
+        count(Verdict::Flaky),
+        count(Verdict::Failed)
+    );
+    if runs.len() < total {
+        summary.push_str(&format!(", {} not run", total - runs.len()));
+    }
+    if !scores.is_empty() {
+        let average = scores.iter().sum::<u32>() as f64 / scores.len() as f64;
+        summary.push_str(&format!(" — average output score {average:.0}/100"));
+    }
+    if count(Verdict::Failed) > 0 {
+        println!("{} {summary}", "✗".red());
+    } else {
+        println!("{} {summary}", "✓".green());
+    }
+}
+
+fn format_ms(ms: u64) -> String {
+    if ms < 1000 {
+        format!("{ms}ms")
+    } else if ms < 60_000 {
+        format!("{:.1}s", ms as f64 / 1000.0)
+    } else {
+        format!("{}m{:02}s", ms / 60_000, (ms % 60_000) / 1000)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn shards_split_round_robin() {
+        let shard: Shard = "2/3".parse().unwrap();
+        assert_eq!(shard.select((1..=7).collect()), [2, 5]);
+        assert!("0/3".parse::<Shard>().is_err());
+        assert!("4/3".parse::<Shard>().is_err());
+        assert!("two".parse::<Shard>().is_err());
+    }
+
+    #[test]
+    fn reads_tags_from_yaml_and_typescript() {
+        assert_eq!(
+            yaml_tags(Some(&json!(["smoke", "invoices"]))),
+            ["smoke", "invoices"]
+        );
+        assert_eq!(
+            yaml_tags(Some(&json!("smoke, nightly"))),
+            ["smoke", "nightly"]
+        );
+        assert_eq!(
+            typescript_tags(
+                "export default createWorkflow({\n  tags: ['smoke', \"sap\"],\n  steps: [],\n});"
+            ),
+            ["smoke", "sap"]
+        );
+        assert!(has_typescript_test_block(
+            "createWorkflow({\n  test: { retries: 2 },\n  steps: [],\n});"
+        ));
+        assert!(!has_typescript_test_block("createWorkflow({ latest: 1 })"));
+
+        let workflow = TestWorkflow {
+            path: PathBuf::from("a.yml"),
+            name: "a.yml".into(),
+            kind: WorkflowKind::Yaml,
+            tags: vec!["Smoke".into(), "sap".into()],
+            setup: None,
+            teardown: None,
+            retries: None,
+        };
+        assert!(workflow.matches(&[], &[]));
+        assert!(workflow.matches(&["smoke".into()], &[]));
+        assert!(!workflow.matches(&["nightly".into()], &[]));
+        assert!(!workflow.matches(&[], &["sap".into()]));
+    }
+
+    #[test]
+    fn per_workflow_hooks_are_not_tests() {
+        let dir =
+            std::env::temp_dir().join(format!("terminator-test-hooks-{}", std::process::id()));
+        std::fs::create_dir_all(dir.join("fixtures")).unwrap();
+        let workflow = |name: &str, setup: Option<&str>| {
+            let path = dir.join(name);
+            std::fs::write(&path, "steps: []\n").unwrap();
+            TestWorkflow {
+                path: std::fs::canonicalize(&path).unwrap(),
+                name: name.into(),
+                kind: WorkflowKind::Yaml,
+                tags: Vec::new(),
+                setup: setup.map(|s| dir.join(s)),
+                teardown: None,
+                retries: None,
+            }
+        };
+        let workflows = vec![
+            workflow("invoice.yml", Some("fixtures/login.yml")),
+            workflow("fixtures/login.yml", None),
+            workflow("fixtures/logout.yml", None),
+            workflow("report.yml", None),
+        ];
+        let cli_teardown = Some(dir.join("fixtures/../fixtures/logout.yml"));
+
+        let tests: Vec<String> = without_hooks(workflows, [&None, &cli_teardown])
+            .into_iter()
+            .map(|w| w.name)
+            .collect();
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(tests, ["invoice.yml", "report.yml"]);
+    }
+}
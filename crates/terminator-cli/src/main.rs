@@ -30,6 +30,7 @@ use tokio::sync::Mutex;
 mod commands;
 mod mcp_client;
 mod typescript_workflow;
+mod workflow_report;
 mod workflow_result;
 mod workflow_validator;
 
@@ -155,6 +156,10 @@ struct McpRunArgs {
     #[clap(long)]
     end_at_step: Option<String>,
 
+    /// Resume from the last saved checkpoint (see `terminator mcp state list`)
+    #[clap(long, conflicts_with = "start_from_step")]
+    resume: bool,
+
     /// Follow fallback_id even beyond end_at_step boundary (default: false when end_at_step is specified)
     #[clap(long)]
     follow_fallback: Option<bool>,
@@ -171,6 +176,11 @@ struct McpRunArgs {
     /// Example: --inputs '{"user":"john","count":5}'
     #[clap(long)]
     inputs: Option<String>,
+
+    /// Write a test report of the run's steps, repeatable:
+    /// junit=<path.xml>, tap, or tap=<path.tap> (without a path, printed to stdout)
+    #[clap(long = "report", value_name = "FORMAT[=PATH]")]
+    report: Vec<workflow_report::ReportTarget>,
 }
 
 #[derive(Subcommand)]
@@ -183,10 +193,24 @@ enum McpCommands {
     Exec(McpExecArgs),
     /// Execute a workflow sequence from a local file or GitHub gist
     Run(McpRunArgs),
+    /// Run a suite of workflows as tests: glob discovery, tag filters, sharding, retries
+    Test(commands::test::TestCommand),
     /// Validate workflow output structure
     Validate(McpValidateArgs),
     /// Generate TypeScript SDK snippet from MCP tool call
     Snippet(McpSnippetArgs),
+    /// List, inspect or clear saved workflow checkpoints
+    #[command(subcommand)]
+    State(commands::state::StateCommand),
+    /// Statically analyze a workflow file without running it
+    Check(commands::check::CheckCommand),
+    /// Export a workflow's control-flow graph as Graphviz DOT or Mermaid
+    Graph(commands::graph::GraphCommand),
+    /// Compare a workflow recording with a reference recording of the same task
+    DiffRecordings(commands::diff_recordings::DiffRecordingsCommand),
+    /// Query recorded workflow runs: failures, step durations, run details
+    #[command(subcommand)]
+    History(commands::history::HistoryCommand),
 }
 
 #[derive(Parser, Debug, Clone)]
@@ -1022,13 +1046,64 @@ fn handle_mcp_command(cmd: McpCommands) {
         return;
     }
 
+    // Handle saved state management - doesn't need MCP connection
+    if let McpCommands::State(state_cmd) = cmd {
+        if let Err(e) = state_cmd.execute() {
+            eprintln!("❌ State command error: {e}");
+            std::process::exit(1);
+        }
+        return;
+    }
+
+    // Handle static analysis - doesn't need MCP connection
+    if let McpCommands::Check(check_cmd) = cmd {
+        if let Err(e) = check_cmd.execute() {
+            eprintln!("❌ Workflow check failed: {e}");
+            std::process::exit(1);
+        }
+        return;
+    }
+
+    // Handle graph export - doesn't need MCP connection
+    if let McpCommands::Graph(graph_cmd) = cmd {
+        if let Err(e) = graph_cmd.execute() {
+            eprintln!("❌ Graph export failed: {e}");
+            std::process::exit(1);
+        }
+        return;
+    }
+
+    // Handle recording comparison - doesn't need MCP connection
+    if let McpCommands::DiffRecordings(diff_cmd) = cmd {
+        if let Err(e) = diff_cmd.execute() {
+            eprintln!("❌ Recording diff failed: {e}");
+            std::process::exit(1);
+        }
+        return;
+    }
+
+    // Handle execution history queries - reads the local history index
+    if let McpCommands::History(history_cmd) = cmd {
+        if let Err(e) = history_cmd.execute() {
+            eprintln!("❌ History command error: {e}");
+            std::process::exit(1);
+        }
+        return;
+    }
+
     let transport = match cmd {
         McpCommands::Chat(ref args) => parse_transport(args.url.clone(), args.command.clone()),
         McpCommands::AiChat(ref args) => parse_transport(args.url.clone(), args.command.clone()),
         McpCommands::Exec(ref args) => parse_transport(args.url.clone(), args.command.clone()),
         McpCommands::Run(ref args) => parse_transport(args.url.clone(), args.command.clone()),
+        McpCommands::Test(ref args) => parse_transport(args.url.clone(), args.command.clone()),
         McpCommands::Validate(_) => unreachable!(), // Handled above
         McpCommands::Snippet(_) => unreachable!(),  // Handled above
+        McpCommands::State(_) => unreachable!(),    // Handled above
+        McpCommands::Check(_) => unreachable!(),    // Handled above
+        McpCommands::Graph(_) => unreachable!(),    // Handled above
+        McpCommands::DiffRecordings(_) => unreachable!(), // Handled above
+        McpCommands::History(_) => unreachable!(),  // Handled above
     };
 
     let rt = tokio::runtime::Runtime::new().expect("Failed to create Tokio runtime");
@@ -1041,8 +1116,14 @@ fn handle_mcp_command(cmd: McpCommands) {
                 mcp_client::execute_command(transport, args.tool, args.args).await
             }
             McpCommands::Run(args) => run_workflow(transport, args).await,
+            McpCommands::Test(test_cmd) => test_cmd.execute(transport).await,
             McpCommands::Validate(_) => unreachable!(), // Handled above
             McpCommands::Snippet(_) => unreachable!(),  // Handled above
+            McpCommands::State(_) => unreachable!(),    // Handled above
+            McpCommands::Check(_) => unreachable!(),    // Handled above
+            McpCommands::Graph(_) => unreachable!(),    // Handled above
+            McpCommands::DiffRecordings(_) => unreachable!(), // Handled above
+            McpCommands::History(_) => unreachable!(),  // Handled above
         }
     });
 
@@ -1430,6 +1511,15 @@ async fn run_workflow(transport: mcp_client::Transport, args: McpRunArgs) -> any
     // Resolve actual input type (auto-detect if needed)
     let resolved_type = determine_input_type(&args.input, args.input_type);
 
+    // Pin the run's execution id so its steps can be looked up in the history for reports
+    let report_execution_id = (!args.report.is_empty()).then(|| {
+        format!(
+            "cli-{}-{}",
+            chrono::Utc::now().format("%Y%m%d%H%M%S%3f"),
+            std::process::id()
+        )
+    });
+
     // Check if this is a TypeScript/JavaScript workflow
     let is_ts_workflow =
         typescript_workflow::is_typescript_workflow(&args.input, resolved_type == InputType::File);
@@ -1450,7 +1540,7 @@ async fn run_workflow(transport: mcp_client::Transport, args: McpRunArgs) -> any
         info!("TypeScript workflow URL: {}", file_url);
 
         // Build execute_sequence args
-        let workflow_args = typescript_workflow::build_typescript_workflow_args(
+        let mut workflow_args = typescript_workflow::build_typescript_workflow_args(
             file_url,
             args.inputs.as_ref(),
             args.start_from_step.as_ref(),
@@ -1461,6 +1551,10 @@ async fn run_workflow(transport: mcp_client::Transport, args: McpRunArgs) -> any
             args.no_detailed_results,
         )?;
 
+        if let (Some(id), Some(obj)) = (&report_execution_id, workflow_args.as_object_mut()) {
+            obj.insert("execution_id".to_string(), Value::String(id.clone()));
+        }
+
         let workflow_str = serde_json::to_string(&workflow_args)?;
         info!("Sending TypeScript workflow args to MCP: {}", workflow_str);
 
@@ -1492,6 +1586,10 @@ async fn run_workflow(transport: mcp_client::Transport, args: McpRunArgs) -> any
             println!("{}", serde_json::to_string_pretty(&result_json)?);
         }
 
+        if let Some(execution_id) = &report_execution_id {
+            write_run_reports(&args, &result_json, &workflow_result, execution_id)?;
+        }
+
         return Ok(());
     }
 
@@ -1555,6 +1653,15 @@ async fn run_workflow(transport: mcp_client::Transport, args: McpRunArgs) -> any
     }
 
     if args.dry_run {
+        let analysis = commands::check::analyze(&workflow_val)?;
+        commands::check::print_issues(&analysis);
+        if !analysis.valid {
+            return Err(anyhow::anyhow!(
+                "Static analysis found {} error(s)",
+                analysis.error_count
+            ));
+        }
+
         println!("✅ Workflow validation successful!");
         println!("📊 Workflow Summary:");
         println!("   • Steps: {steps_count}");
@@ -1631,6 +1738,15 @@ async fn run_workflow(transport: mcp_client::Transport, args: McpRunArgs) -> any
                 serde_json::Value::String(end_step.clone()),
             );
         }
+        if args.resume {
+            workflow_args.insert("resume".to_string(), serde_json::Value::Bool(true));
+        }
+        if let Some(execution_id) = &report_execution_id {
+            workflow_args.insert(
+                "execution_id".to_string(),
+                serde_json::Value::String(execution_id.clone()),
+            );
+        }
         if let Some(follow) = args.follow_fallback {
             workflow_args.insert(
                 "follow_fallback".to_string(),
@@ -1674,6 +1790,10 @@ async fn run_workflow(transport: mcp_client::Transport, args: McpRunArgs) -> any
             }
         }
 
+        if let (Some(id), Some(obj)) = (&report_execution_id, workflow_val.as_object_mut()) {
+            obj.insert("execution_id".to_string(), Value::String(id.clone()));
+        }
+
         // For remote sources, send the entire parsed content
         serde_json::to_string(&workflow_val)?
     };
@@ -1707,6 +1827,10 @@ async fn run_workflow(transport: mcp_client::Transport, args: McpRunArgs) -> any
         println!("{}", serde_json::to_string_pretty(&result_json)?);
     }
 
+    if let Some(execution_id) = &report_execution_id {
+        write_run_reports(&args, &result_json, &workflow_result, execution_id)?;
+    }
+
     // Exit with appropriate code based on success
     if !workflow_result.success {
         std::process::exit(1);
@@ -1715,6 +1839,29 @@ async fn run_workflow(transport: mcp_client::Transport, args: McpRunArgs) -> any
     Ok(())
 }
 
+/// Write the `--report` files for a finished run. Screenshots come from the
+/// local execution history, so they are only attached when the server runs here.
+fn write_run_reports(
+    args: &McpRunArgs,
+    result_json: &Value,
+    workflow_result: &WorkflowResult,
+    execution_id: &str,
+) -> anyhow::Result<()> {
+    let steps = terminator_mcp_agent::execution_history::History::open_default()
+        .run(execution_id)
+        .ok()
+        .flatten()
+        .map(|run| run.steps)
+        .unwrap_or_default();
+    let name = Path::new(args.input.trim_end_matches('/'))
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .unwrap_or_else(|| args.input.clone());
+    let suite =
+        workflow_report::TestSuite::from_response(&name, result_json, workflow_result, &steps);
+    workflow_report::write_reports(&args.report, &[suite])
+}
+
 /// Extract cron expression from workflow YAML
 fn extract_cron_from_workflow(workflow: &Value) -> Option<String> {
     // Primary format: cron field at root level (simpler format)
@@ -1994,6 +2141,9 @@ async fn run_workflow_once(
                 serde_json::Value::String(end_step.clone()),
             );
         }
+        if args.resume {
+            workflow_args.insert("resume".to_string(), serde_json::Value::Bool(true));
+        }
         if let Some(follow) = args.follow_fallback {
             workflow_args.insert(
                 "follow_fallback".to_string(),
@@ -2,8 +2,9 @@ use anyhow::Result;
 use rmcp::{
     model::{CallToolRequestParam, ClientCapabilities, ClientInfo, Implementation},
     object,
+    service::RunningService,
     transport::{StreamableHttpClientTransport, TokioChildProcess},
-    ServiceExt,
+    RoleClient, ServiceExt,
 };
 use std::io::{self, Write};
 use std::time::Duration;
@@ -1334,6 +1335,70 @@ pub async fn execute_command_with_progress_and_retry(
     }
 }
 
+/// One connection to an MCP server, reused for several tool calls (e.g. a
+/// suite of workflows run on the same agent by `terminator mcp test`)
+pub struct McpSession {
+    service: RunningService<RoleClient, ClientInfo>,
+}
+
+impl McpSession {
+    pub async fn connect(transport: &Transport) -> Result<Self> {
+        let client_info = ClientInfo {
+            protocol_version: Default::default(),
+            capabilities: ClientCapabilities::default(),
+            client_info: Implementation {
+                name: "terminator-cli".to_string(),
+                version: env!("CARGO_PKG_VERSION").to_string(),
+                ..Default::default()
+            },
+        };
+        let service = match transport {
+            Transport::Http { url, auth_token } => {
+                client_info
+                    .serve(create_http_transport(url, auth_token.as_ref()))
+                    .await?
+            }
+            Transport::Stdio(command) => {
+                let executable = find_executable(&command[0]).unwrap_or_else(|| command[0].clone());
+                let mut cmd = create_command(&executable, &command[1..]);
+                if std::env::var("LOG_LEVEL").is_err() && std::env::var("RUST_LOG").is_err() {
+                    cmd.env("LOG_LEVEL", "info");
+                }
+                client_info.serve(TokioChildProcess::new(cmd)?).await?
+            }
+        };
+        Ok(Self { service })
+    }
+
+    /// Call a tool and return its first JSON text content
+    pub async fn call_json(
+        &self,
+        tool: &str,
+        arguments: &serde_json::Value,
+    ) -> Result<serde_json::Value> {
+        let result = self
+            .service
+            .call_tool(CallToolRequestParam {
+                name: tool.to_string().into(),
+                arguments: arguments.as_object().cloned(),
+            })
+            .await?;
+        for content in &result.content {
+            if let rmcp::model::RawContent::Text(text) = &content.raw {
+                if let Ok(json_result) = serde_json::from_str::<serde_json::Value>(&text.text) {
+                    return Ok(json_result);
+                }
+            }
+        }
+        Ok(json!({"status": "unknown", "message": "No parseable result from workflow"}))
+    }
+
+    pub async fn close(self) -> Result<()> {
+        self.service.cancel().await?;
+        Ok(())
+    }
+}
+
 #[cfg(test)]
 mod tests {
     // Tests for TypeScript workflow detection and retry logic
@@ -0,0 +1,541 @@
+//! JUnit XML and TAP reports of workflow runs, for CI dashboards.
+//!
+//! Each executed step becomes a test case; a run of several workflow files
+//! produces one suite per file.
+
+use anyhow::{anyhow, Context, Result};
+use serde_json::{json, Value};
+use std::fmt::Write as _;
+use std::path::PathBuf;
+use std::str::FromStr;
+use terminator_mcp_agent::execution_history::StepRecord;
+
+use crate::workflow_result::{WorkflowResult, WorkflowState};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReportFormat {
+    Junit,
+    Tap,
+}
+
+/// A `--report` value: `junit=path.xml`, `tap` or `tap=path.tap`.
+/// Without a path the report is printed to stdout.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReportTarget {
+    pub format: ReportFormat,
+    pub path: Option<PathBuf>,
+}
+
+impl FromStr for ReportTarget {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (format, path) = match s.split_once('=') {
+            Some((format, path)) if !path.trim().is_empty() => {
+                (format, Some(PathBuf::from(path.trim())))
+            }
+            Some((format, _)) => (format, None),
+            None => (s, None),
+        };
+        let format = match format.trim().to_ascii_lowercase().as_str() {
+            "junit" | "xml" => ReportFormat::Junit,
+            "tap" => ReportFormat::Tap,
+            other => {
+                return Err(format!(
+                    "unknown report format '{other}' (expected junit=<path> or tap[=<path>])"
+                ))
+            }
+        };
+        Ok(ReportTarget { format, path })
+    }
+}
+
+impl ReportTarget {
+    pub fn render(&self, suites: &[TestSuite]) -> String {
+        match self.format {
+            ReportFormat::Junit => to_junit(suites),
+            ReportFormat::Tap => to_tap(suites),
+        }
+    }
+
+    /// Write the report to its path, or to stdout when it has none
+    pub fn write(&self, suites: &[TestSuite]) -> Result<()> {
+        let report = self.render(suites);
+        match &self.path {
+            Some(path) => {
+                if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+                    std::fs::create_dir_all(parent)?;
+                }
+                std::fs::write(path, report)
+                    .with_context(|| format!("Failed to write report to {}", path.display()))?;
+                println!("📄 Report written to {}", path.display());
+            }
+            None => print!("{report}"),
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Outcome {
+    Passed,
+    Failed {
+        message: String,
+        /// `error_type` of the structured error, e.g. `ElementNotFound`
+        kind: Option<String>,
+        /// Structured error data: selectors tried, suggestions, ...
+        details: Option<Value>,
+    },
+    Skipped(Option<String>),
+}
+
+#[derive(Debug, Clone)]
+pub struct TestCase {
+    pub name: String,
+    pub classname: String,
+    pub duration_ms: u64,
+    pub outcome: Outcome,
+    /// Screenshot paths captured while the step ran
+    pub attachments: Vec<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct TestSuite {
+    pub name: String,
+    pub duration_ms: u64,
+    pub cases: Vec<TestCase>,
+}
+
+impl TestSuite {
+    /// Build a suite from an `execute_sequence` response. `steps` are the
+    /// run's entries from the execution history, used for screenshots and
+    /// for workflows (TypeScript) whose response has no per-step results.
+    pub fn from_response(
+        name: &str,
+        response: &Value,
+        result: &WorkflowResult,
+        steps: &[StepRecord],
+    ) -> Self {
+        let mut cases = Vec::new();
+        for item in response
+            .get("results")
+            .and_then(Value::as_array)
+            .into_iter()
+            .flatten()
+        {
+            match item.get("results").and_then(Value::as_array) {
+                Some(group_results) if item.get("group_name").is_some() => {
+                    let group = item["group_name"].as_str().unwrap_or("group");
+                    let classname = format!("{name}.{group}");
+                    for sub in group_results {
+                        cases.push(case_from_result(sub, &classname, false, steps));
+                    }
+                }
+                _ => cases.push(case_from_result(item, name, true, steps)),
+            }
+        }
+
+        if cases.is_empty() {
+            cases = steps
+                .iter()
+                .map(|step| TestCase {
+                    name: step.label(),
+                    classname: name.to_string(),
+                    duration_ms: step.duration_ms,
+                    outcome: if step.success {
+                        Outcome::Passed
+                    } else {
+                        Outcome::Failed {
+                            message: step.error.clone().unwrap_or_default(),
+                            kind: None,
+                            details: None,
+                        }
+                    },
+                    attachments: step.screenshots.clone(),
+                })
+                .collect();
+        }
+
+        // The output parser decides business success, which no single step reflects
+        if cases.is_empty() || response.get("parsed_output").is_some() {
+            cases.push(TestCase {
+                name: "workflow result".to_string(),
+                classname: name.to_string(),
+                duration_ms: if cases.is_empty() {
+                    result.duration_ms.unwrap_or(0)
+                } else {
+                    0
+                },
+                outcome: match result.state {
+                    WorkflowState::Success => Outcome::Passed,
+                    WorkflowState::Skipped => Outcome::Skipped(Some(result.message.clone())),
+                    WorkflowState::Failure | WorkflowState::Exception => Outcome::Failed {
+                        message: result.message.clone(),
+                        kind: Some(format!("{:?}", result.state)),
+                        details: result.error.clone().map(Value::String),
+                    },
+                },
+                attachments: Vec::new(),
+            });
+        }
+
+        TestSuite {
+            name: name.to_string(),
+            duration_ms: result
+                .duration_ms
+                .unwrap_or_else(|| cases.iter().map(|c| c.duration_ms).sum()),
+            cases,
+        }
+    }
+
+    /// A suite for a workflow that failed before producing step results
+    /// (connection error, failing setup hook, ...)
+    pub fn error(name: &str, case: &str, message: &str, duration_ms: u64) -> Self {
+        TestSuite {
+            name: name.to_string(),
+            duration_ms,
+            cases: vec![TestCase {
+                name: case.to_string(),
+                classname: name.to_string(),
+                duration_ms,
+                outcome: Outcome::Failed {
+                    message: message.to_string(),
+                    kind: None,
+                    details: None,
+                },
+                attachments: Vec::new(),
+            }],
+        }
+    }
+
+    pub fn failures(&self) -> usize {
+        self.cases
+            .iter()
+            .filter(|c| matches!(c.outcome, Outcome::Failed { .. }))
+            .count()
+    }
+
+    pub fn skipped(&self) -> usize {
+        self.cases
+            .iter()
+            .filter(|c| matches!(c.outcome, Outcome::Skipped(_)))
+            .count()
+    }
+}
+
+/// Map one entry of the response's `results` to a test case. Group sub-steps
+/// share their group's index, so only top-level entries match history by index.
+fn case_from_result(
+    item: &Value,
+    classname: &str,
+    top_level: bool,
+    steps: &[StepRecord],
+) -> TestCase {
+    let step_id = item.get("step_id").and_then(Value::as_str);
+    let index = item
+        .get("index")
+        .and_then(Value::as_u64)
+        .map(|i| i as usize);
+    let tool_name = item.get("tool_name").and_then(Value::as_str);
+    let name = match (step_id, index, tool_name) {
+        (Some(id), _, _) => id.to_string(),
+        (None, Some(index), Some(tool)) => format!("#{index} {tool}"),
+        (None, Some(index), None) => format!("step #{index}"),
+        (None, None, tool) => tool.unwrap_or("step").to_string(),
+    };
+
+    let error = item.get("error").and_then(Value::as_str);
+    let outcome = match item.get("status").and_then(Value::as_str) {
+        Some("error") => {
+            let details = item.get("error_details").cloned();
+            Outcome::Failed {
+                message: error.unwrap_or("Step failed").to_string(),
+                kind: details
+                    .as_ref()
+                    .and_then(|d| d.get("error_type"))
+                    .and_then(Value::as_str)
+                    .map(str::to_string),
+                details,
+            }
+        }
+        Some("skipped") => Outcome::Skipped(
+            item.get("reason")
+                .and_then(Value::as_str)
+                .or(error)
+                .map(str::to_string),
+        ),
+        _ => Outcome::Passed,
+    };
+
+    let attachments = steps
+        .iter()
+        .filter(|s| match (step_id, &s.step_id) {
+            (Some(id), Some(recorded)) => id == recorded,
+            (None, None) => top_level && index.is_some() && s.step_index == index,
+            _ => false,
+        })
+        .flat_map(|s| s.screenshots.iter().cloned())
+        .collect();
+
+    TestCase {
+        name,
+        classname: classname.to_string(),
+        duration_ms: item.get("duration_ms").and_then(Value::as_u64).unwrap_or(0),
+        outcome,
+        attachments,
+    }
+}
+
+fn seconds(ms: u64) -> String {
+    format!("{:.3}", ms as f64 / 1000.0)
+}
+
+/// Escape text for XML, dropping control characters XML 1.0 cannot represent
+fn xml_escape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&apos;"),
+            '\t' | '\n' | '\r' => out.push(c),
+            c if c.is_control() => {}
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+fn details_text(details: &Option<Value>) -> Option<String> {
+    match details {
+        Some(Value::String(s)) => Some(s.clone()),
+        Some(value) => serde_json::to_string_pretty(value).ok(),
+        None => None,
+    }
+}
+
+pub fn to_junit(suites: &[TestSuite]) -> String {
+    let tests: usize = suites.iter().map(|s| s.cases.len()).sum();
+    let failures: usize = suites.iter().map(TestSuite::failures).sum();
+    let skipped: usize = suites.iter().map(TestSuite::skipped).sum();
+    let time: u64 = suites.iter().map(|s| s.duration_ms).sum();
+    let timestamp = chrono::Local::now().format("%Y-%m-%dT%H:%M:%S");
+
+    let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    let _ = writeln!(
+        xml,
+        "<testsuites name=\"terminator\" tests=\"{tests}\" failures=\"{failures}\" errors=\"0\" skipped=\"{skipped}\" time=\"{}\">",
+        seconds(time)
+    );
+    for suite in suites {
+        let _ = writeln!(
+            xml,
+            "  <testsuite name=\"{}\" tests=\"{}\" failures=\"{}\" errors=\"0\" skipped=\"{}\" time=\"{}\" timestamp=\"{timestamp}\">",
+            xml_escape(&suite.name),
+            suite.cases.len(),
+            suite.failures(),
+            suite.skipped(),
+            seconds(suite.duration_ms)
+        );
+        for case in &suite.cases {
+            let _ = write!(
+                xml,
+                "    <testcase name=\"{}\" classname=\"{}\" time=\"{}\"",
+                xml_escape(&case.name),
+                xml_escape(&case.classname),
+                seconds(case.duration_ms)
+            );
+            if case.outcome == Outcome::Passed && case.attachments.is_empty() {
+                xml.push_str("/>\n");
+                continue;
+            }
+            xml.push_str(">\n");
+            match &case.outcome {
+                Outcome::Passed => {}
+                Outcome::Failed {
+                    message,
+                    kind,
+                    details,
+                } => {
+                    let _ = write!(
+                        xml,
+                        "      <failure message=\"{}\" type=\"{}\">",
+                        xml_escape(message),
+                        xml_escape(kind.as_deref().unwrap_or("StepFailed"))
+                    );
+                    xml.push_str(&xml_escape(message));
+                    if let Some(details) = details_text(details) {
+                        xml.push_str("\n\n");
+                        xml.push_str(&xml_escape(&details));
+                    }
+                    xml.push_str("</failure>\n");
+                }
+                Outcome::Skipped(reason) => match reason {
+                    Some(reason) => {
+                        let _ =
+                            writeln!(xml, "      <skipped message=\"{}\"/>", xml_escape(reason));
+                    }
+                    None => xml.push_str("      <skipped/>\n"),
+                },
+            }
+            if !case.attachments.is_empty() {
+                // Jenkins/GitLab attachment convention
+                xml.push_str("      <system-out>");
+                for path in &case.attachments {
+                    let _ = write!(xml, "\n[[ATTACHMENT|{}]]", xml_escape(path));
+                }
+                xml.push_str("\n      </system-out>\n");
+            }
+            xml.push_str("    </testcase>\n");
+        }
+        xml.push_str("  </testsuite>\n");
+    }
+    xml.push_str("</testsuites>\n");
+    xml
+}
+
+pub fn to_tap(suites: &[TestSuite]) -> String {
+    let total: usize = suites.iter().map(|s| s.cases.len()).sum();
+    let mut tap = format!("TAP version 13\n1..{total}\n");
+    let mut number = 0;
+    for suite in suites {
+        let _ = writeln!(tap, "# {}", suite.name);
+        for case in &suite.cases {
+            number += 1;
+            // An unescaped '#' would start a TAP directive
+            let name = if suites.len() > 1 {
+                format!("{} - {}", suite.name, case.name)
+            } else {
+                case.name.clone()
+            }
+            .replace('#', "\\#");
+            let mut diagnostics = serde_json::Map::new();
+            diagnostics.insert("duration_ms".into(), json!(case.duration_ms));
+            match &case.outcome {
+                Outcome::Passed => {
+                    let _ = writeln!(tap, "ok {number} - {name}");
+                }
+                Outcome::Failed {
+                    message,
+                    kind,
+                    details,
+                } => {
+                    let _ = writeln!(tap, "not ok {number} - {name}");
+                    diagnostics.insert("message".into(), json!(message));
+                    diagnostics.insert("severity".into(), json!("fail"));
+                    if let Some(kind) = kind {
+                        diagnostics.insert("error_type".into(), json!(kind));
+                    }
+                    if let Some(details) = details {
+                        diagnostics.insert("details".into(), details.clone());
+                    }
+                }
+                Outcome::Skipped(reason) => {
+                    let reason = reason.as_deref().unwrap_or("").replace('\n', " ");
+                    let _ = writeln!(tap, "ok {number} - {name} # SKIP {reason}");
+                }
+            }
+            if !case.attachments.is_empty() {
+                diagnostics.insert("attachments".into(), json!(case.attachments));
+            }
+            if let Ok(yaml) = serde_yaml::to_string(&Value::Object(diagnostics)) {
+                tap.push_str("  ---\n");
+                for line in yaml.lines() {
+                    let _ = writeln!(tap, "  {line}");
+                }
+                tap.push_str("  ...\n");
+            }
+        }
+    }
+    tap
+}
+
+/// Write every requested report, stopping at the first that fails
+pub fn write_reports(targets: &[ReportTarget], suites: &[TestSuite]) -> Result<()> {
+    if targets.iter().filter(|t| t.path.is_none()).count() > 1 {
+        return Err(anyhow!("Only one --report can be printed to stdout"));
+    }
+    for target in targets {
+        target.write(suites)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn failed_response() -> Value {
+        json!({
+            "status": "executed_with_error",
+            "total_duration_ms": 3400,
+            "results": [
+                {"tool_name": "open_application", "index": 0, "step_id": "open",
+                 "status": "executed_without_error", "duration_ms": 1200, "result": {}},
+                {"tool_name": "click_element", "index": 1, "status": "error",
+                 "duration_ms": 2200, "error": "-32603: Element not found",
+                 "error_details": {"error_type": "ElementNotFound",
+                                   "selectors_tried": ["role:Button|name:Save <new>"]}},
+                {"index": 2, "status": "skipped", "executed": false,
+                 "reason": "Skipped due to a previous unrecoverable error in the sequence."}
+            ]
+        })
+    }
+
+    #[test]
+    fn parses_report_targets() {
+        assert_eq!(
+            "junit=out/results.xml".parse::<ReportTarget>().unwrap(),
+            ReportTarget {
+                format: ReportFormat::Junit,
+                path: Some(PathBuf::from("out/results.xml")),
+            }
+        );
+        assert_eq!(
+            "tap".parse::<ReportTarget>().unwrap(),
+            ReportTarget {
+                format: ReportFormat::Tap,
+                path: None,
+            }
+        );
+        assert!("html=x".parse::<ReportTarget>().is_err());
+    }
+
+    #[test]
+    fn maps_steps_to_cases_with_screenshots() {
+        let response = failed_response();
+        let result = WorkflowResult::from_mcp_response(&response).unwrap();
+        let steps = vec![StepRecord {
+            step_index: Some(1),
+            step_id: None,
+            tool_name: "click_element".into(),
+            started_at: "2026-01-01T00:00:00Z".into(),
+            duration_ms: 2200,
+            success: false,
+            error: None,
+            retry_attempt: None,
+            log_path: None,
+            screenshots: vec!["/logs/shot_1.png".into()],
+        }];
+        let suite = TestSuite::from_response("invoice", &response, &result, &steps);
+
+        let names: Vec<_> = suite.cases.iter().map(|c| c.name.as_str()).collect();
+        assert_eq!(names, ["open", "#1 click_element", "step #2"]);
+        assert_eq!(suite.failures(), 1);
+        assert_eq!(suite.skipped(), 1);
+        assert_eq!(suite.cases[1].attachments, ["/logs/shot_1.png"]);
+
+        let xml = to_junit(std::slice::from_ref(&suite));
+        assert!(xml.contains("type=\"ElementNotFound\""));
+        assert!(xml.contains("role:Button|name:Save &lt;new&gt;"));
+        assert!(xml.contains("[[ATTACHMENT|/logs/shot_1.png]]"));
+        assert!(xml.contains("<testcase name=\"open\" classname=\"invoice\" time=\"1.200\"/>"));
+
+        let tap = to_tap(&[suite]);
+        assert!(tap.starts_with("TAP version 13\n1..3\n"));
+        assert!(tap.contains("not ok 2 - \\#1 click_element\n"));
+        assert!(tap.contains("ok 3 - step \\#2 # SKIP Skipped due to"));
+    }
+}
@@ -119,7 +119,10 @@ impl WorkflowResult {
             (success, state, message, data, error, validation)
         } else {
             // No output parser - determine success from execution status
-            let success = execution_status == "success";
+            let success = matches!(
+                execution_status.as_str(),
+                "success" | "executed_without_error"
+            );
             let state = if success {
                 WorkflowState::Success
             } else {
@@ -263,3 +266,44 @@ impl WorkflowResult {
         println!();
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn execution_status_decides_success_without_parser() {
+        let ok = WorkflowResult::from_mcp_response(&json!({
+            "status": "executed_without_error",
+            "executed_tools": 3
+        }))
+        .unwrap();
+        assert!(ok.success);
+        assert_eq!(ok.state, WorkflowState::Success);
+        assert!(ok.error.is_none());
+
+        let legacy = WorkflowResult::from_mcp_response(&json!({"status": "success"})).unwrap();
+        assert!(legacy.success);
+
+        let failed = WorkflowResult::from_mcp_response(&json!({
+            "status": "executed_with_error",
+            "debug_info_on_failure": {"failed_step": "click"}
+        }))
+        .unwrap();
+        assert!(!failed.success);
+        assert_eq!(failed.state, WorkflowState::Failure);
+        assert!(failed.error.unwrap().contains("failed_step"));
+    }
+
+    #[test]
+    fn parser_result_overrides_execution_status() {
+        let result = WorkflowResult::from_mcp_response(&json!({
+            "status": "executed_without_error",
+            "parsed_output": {"success": false, "message": "No invoices found"}
+        }))
+        .unwrap();
+        assert!(!result.success);
+        assert_eq!(result.message, "No invoices found");
+    }
+}
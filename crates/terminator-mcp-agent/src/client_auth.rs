@@ -0,0 +1,526 @@
+//! Per-client authentication for the HTTP transport
+//!
+//! A token file (`--auth-tokens-file` / `MCP_AUTH_TOKENS_FILE`) lists named
+//! clients, each with its own bearer token, scopes, optional expiry and
+//! request rate limit:
+//!
+//! ```yaml
+//! tokens:
+//!   - name: scheduler
+//!     token_env: SCHEDULER_TOKEN      # or `token: <secret>`
+//!     scopes: [read, action]
+//!     rate_limit: 120/1m
+//!   - name: alice
+//!     token: 3f0c9a...
+//!     scopes: [all]
+//!     expires_at: 2026-12-31T00:00:00Z
+//! ```
+//!
+//! Scopes group tools: `read` covers tools that only inspect the desktop
+//! (`get_window_tree`, `validate_element`, ...), `file` covers tools that
+//! touch the file system (`read_file`, `write_file`, `edit_file`, ...), and
+//! `action` covers everything else. `all` grants every scope.
+//!
+//! The authenticated [`ClientIdentity`] travels with the tool call in a
+//! task-local, so execution logs and telemetry spans can be stamped with it.
+
+use chrono::{DateTime, Utc};
+use rmcp::ErrorData as McpError;
+use serde::Deserialize;
+use serde_json::json;
+use std::future::Future;
+use std::path::Path;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// Group of tools a token may call
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Scope {
+    Read,
+    Action,
+    File,
+}
+
+impl Scope {
+    const ALL: [Scope; 3] = [Scope::Read, Scope::Action, Scope::File];
+
+    /// Scope required to call a tool. Unknown tools need `action`.
+    pub fn for_tool(tool_name: &str) -> Scope {
+        match tool_name {
+            "get_window_tree"
+            | "get_applications_and_windows_list"
+            | "validate_element"
+            | "wait_for_element"
+            | "capture_screenshot"
+            | "highlight_element"
+            | "hide_inspect_overlay"
+            | "stop_highlighting"
+            | "delay"
+            | "ask_user"
+            | "stop_execution"
+            | "typecheck_workflow"
+            | "validate_workflow"
+            | "list_schedules"
+            | "list_execution_queue"
+            | "query_execution_history"
+            | "get_execution_run"
+            | "execution_step_stats"
+            | "get_recording_status" => Scope::Read,
+            "read_file"
+            | "write_file"
+            | "edit_file"
+            | "copy_content"
+            | "glob_files"
+            | "grep_files"
+            | "generate_run_report" => Scope::File,
+            _ => Scope::Action,
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Scope::Read => "read",
+            Scope::Action => "action",
+            Scope::File => "file",
+        }
+    }
+}
+
+/// An authenticated client
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ClientIdentity {
+    pub name: String,
+    pub scopes: Vec<Scope>,
+}
+
+impl ClientIdentity {
+    pub fn allows(&self, tool_name: &str) -> bool {
+        self.scopes.contains(&Scope::for_tool(tool_name))
+    }
+}
+
+/// Why a request was refused
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AuthError {
+    /// No `Authorization: Bearer` header
+    Missing,
+    /// The token doesn't belong to any client
+    Invalid,
+    Expired {
+        client: String,
+    },
+    RateLimited {
+        client: String,
+        retry_after: Duration,
+    },
+}
+
+impl std::fmt::Display for AuthError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AuthError::Missing | AuthError::Invalid => {
+                write!(f, "Unauthorized - invalid or missing Bearer token")
+            }
+            AuthError::Expired { client } => write!(f, "Token for client '{client}' has expired"),
+            AuthError::RateLimited {
+                client,
+                retry_after,
+            } => write!(
+                f,
+                "Rate limit exceeded for client '{client}', retry in {}s",
+                retry_after.as_secs_f64().ceil()
+            ),
+        }
+    }
+}
+
+/// 401 (429 with `Retry-After` when rate limited) with a JSON-RPC style error body
+impl axum::response::IntoResponse for AuthError {
+    fn into_response(self) -> axum::response::Response {
+        use axum::http::{header, StatusCode};
+
+        let status = match self {
+            AuthError::RateLimited { .. } => StatusCode::TOO_MANY_REQUESTS,
+            _ => StatusCode::UNAUTHORIZED,
+        };
+        let body = json!({
+            "error": {
+                "code": -32001,
+                "message": self.to_string()
+            }
+        });
+        let mut response = (status, axum::Json(body)).into_response();
+        if let AuthError::RateLimited { retry_after, .. } = self {
+            let seconds = retry_after.as_secs_f64().ceil() as u64;
+            response
+                .headers_mut()
+                .insert(header::RETRY_AFTER, header::HeaderValue::from(seconds));
+        }
+        response
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct TokenFile {
+    tokens: Vec<TokenSpec>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct TokenSpec {
+    name: String,
+    #[serde(default)]
+    token: Option<String>,
+    /// Read the secret from this environment variable instead of the file
+    #[serde(default)]
+    token_env: Option<String>,
+    scopes: Vec<String>,
+    #[serde(default)]
+    expires_at: Option<DateTime<Utc>>,
+    /// `<requests>/<window>`, e.g. `60/1m` or `5/10s`
+    #[serde(default)]
+    rate_limit: Option<String>,
+}
+
+struct ClientToken {
+    name: String,
+    secret: String,
+    scopes: Vec<Scope>,
+    expires_at: Option<DateTime<Utc>>,
+    limiter: Option<Mutex<RateLimiter>>,
+}
+
+/// Token bucket: `capacity` requests, refilled evenly over the window
+#[derive(Debug)]
+struct RateLimiter {
+    capacity: f64,
+    refill_per_sec: f64,
+    available: f64,
+    last: Instant,
+}
+
+impl RateLimiter {
+    fn parse(spec: &str) -> Result<Self, String> {
+        let (count, window) = spec
+            .split_once('/')
+            .ok_or_else(|| format!("rate_limit '{spec}' must look like <requests>/<window>"))?;
+        let count: u32 = count
+            .trim()
+            .parse()
+            .map_err(|_| format!("rate_limit '{spec}': invalid request count"))?;
+        let window = window.trim();
+        // Allow `60/min` as well as `60/1min`
+        let window = if window.starts_with(|c: char| c.is_ascii_digit()) {
+            window.to_string()
+        } else {
+            format!("1{window}")
+        };
+        let window_ms = crate::duration_parser::parse_duration(&window)
+            .map_err(|e| format!("rate_limit '{spec}': {e}"))?;
+        if count == 0 || window_ms == 0 {
+            return Err(format!("rate_limit '{spec}' must be positive"));
+        }
+        let capacity = f64::from(count);
+        Ok(Self {
+            capacity,
+            refill_per_sec: capacity * 1000.0 / window_ms as f64,
+            available: capacity,
+            last: Instant::now(),
+        })
+    }
+
+    /// Take one request, or report how long until one is available
+    fn acquire(&mut self, now: Instant) -> Result<(), Duration> {
+        let elapsed = now.saturating_duration_since(self.last).as_secs_f64();
+        self.available = (self.available + elapsed * self.refill_per_sec).min(self.capacity);
+        self.last = now;
+        if self.available >= 1.0 {
+            self.available -= 1.0;
+            Ok(())
+        } else {
+            Err(Duration::from_secs_f64(
+                (1.0 - self.available) / self.refill_per_sec,
+            ))
+        }
+    }
+}
+
+/// The configured clients
+#[derive(Default)]
+pub struct TokenStore {
+    tokens: Vec<ClientToken>,
+}
+
+impl TokenStore {
+    /// Parse a token file (YAML or JSON)
+    pub fn parse(content: &str) -> Result<Self, String> {
+        let file: TokenFile =
+            serde_yaml::from_str(content).map_err(|e| format!("Invalid token file: {e}"))?;
+        let mut tokens: Vec<ClientToken> = Vec::with_capacity(file.tokens.len());
+        for spec in file.tokens {
+            let name = spec.name;
+            if tokens.iter().any(|t| t.name == name) {
+                return Err(format!("Duplicate client name '{name}'"));
+            }
+            let secret = match (spec.token, spec.token_env) {
+                (Some(token), None) => token,
+                (None, Some(var)) => std::env::var(&var)
+                    .map_err(|_| format!("{name}: environment variable {var} is not set"))?,
+                _ => return Err(format!("{name}: set exactly one of `token` or `token_env`")),
+            };
+            if secret.is_empty() {
+                return Err(format!("{name}: token is empty"));
+            }
+            if tokens.iter().any(|t| t.secret == secret) {
+                return Err(format!("{name}: token is shared with another client"));
+            }
+            let mut scopes = Vec::new();
+            for scope in &spec.scopes {
+                match scope.as_str() {
+                    "all" | "*" => scopes.extend(Scope::ALL),
+                    other => scopes.push(
+                        serde_yaml::from_str::<Scope>(other)
+                            .map_err(|_| format!("{name}: unknown scope '{other}'"))?,
+                    ),
+                }
+            }
+            scopes.sort_by_key(|s| s.name());
+            scopes.dedup();
+            let limiter = spec
+                .rate_limit
+                .as_deref()
+                .map(RateLimiter::parse)
+                .transpose()
+                .map_err(|e| format!("{name}: {e}"))?
+                .map(Mutex::new);
+            tokens.push(ClientToken {
+                name,
+                secret,
+                scopes,
+                expires_at: spec.expires_at,
+                limiter,
+            });
+        }
+        Ok(Self { tokens })
+    }
+
+    /// Load a token file from disk
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let content = std::fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read token file {}: {e}", path.display()))?;
+        Self::parse(&content)
+    }
+
+    /// Add the single shared `--auth-token` secret as client `default` with every scope
+    pub fn with_shared_token(mut self, token: String) -> Self {
+        self.tokens.push(ClientToken {
+            name: "default".to_string(),
+            secret: token,
+            scopes: Scope::ALL.to_vec(),
+            expires_at: None,
+            limiter: None,
+        });
+        self
+    }
+
+    pub fn len(&self) -> usize {
+        self.tokens.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.tokens.is_empty()
+    }
+
+    /// Check an `Authorization` header value and count the request against
+    /// the client's rate limit
+    pub fn authenticate(&self, authorization: Option<&str>) -> Result<ClientIdentity, AuthError> {
+        let presented = authorization
+            .and_then(|value| value.strip_prefix("Bearer "))
+            .ok_or(AuthError::Missing)?;
+        let token = self
+            .tokens
+            .iter()
+            .find(|t| constant_time_eq(t.secret.as_bytes(), presented.as_bytes()))
+            .ok_or(AuthError::Invalid)?;
+
+        if token.expires_at.is_some_and(|expiry| expiry <= Utc::now()) {
+            return Err(AuthError::Expired {
+                client: token.name.clone(),
+            });
+        }
+        if let Some(limiter) = &token.limiter {
+            let mut limiter = limiter.lock().unwrap_or_else(|e| e.into_inner());
+            limiter
+                .acquire(Instant::now())
+                .map_err(|retry_after| AuthError::RateLimited {
+                    client: token.name.clone(),
+                    retry_after,
+                })?;
+        }
+        Ok(ClientIdentity {
+            name: token.name.clone(),
+            scopes: token.scopes.clone(),
+        })
+    }
+}
+
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+tokio::task_local! {
+    static CURRENT_CLIENT: ClientIdentity;
+}
+
+/// Run `fut` with `identity` as the current client (no-op when `None`)
+pub async fn with_client<F: Future>(identity: Option<ClientIdentity>, fut: F) -> F::Output {
+    match identity {
+        Some(identity) => CURRENT_CLIENT.scope(identity, fut).await,
+        None => fut.await,
+    }
+}
+
+/// The client making the current tool call, if it authenticated with a token
+pub fn current_client() -> Option<ClientIdentity> {
+    CURRENT_CLIENT.try_with(|c| c.clone()).ok()
+}
+
+/// Name of the current client, for stamping logs and spans
+pub fn current_client_name() -> Option<String> {
+    CURRENT_CLIENT.try_with(|c| c.name.clone()).ok()
+}
+
+/// Refuse a tool call the current client's scopes don't cover
+pub fn check_tool_scope(tool_name: &str) -> Result<(), McpError> {
+    let Some(client) = current_client() else {
+        return Ok(());
+    };
+    if client.allows(tool_name) {
+        return Ok(());
+    }
+    let required = Scope::for_tool(tool_name).name();
+    tracing::info!(
+        "[client_auth] Client '{}' lacks scope '{}' for tool '{}'",
+        client.name,
+        required,
+        tool_name
+    );
+    Err(McpError::invalid_request(
+        format!(
+            "Client '{}' is not allowed to call '{tool_name}' (requires scope '{required}')",
+            client.name
+        ),
+        Some(json!({
+            "code": -32004,
+            "tool": tool_name,
+            "client": client.name,
+            "required_scope": required,
+        })),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TOKENS: &str = r#"
+tokens:
+  - name: scheduler
+    token: sched-secret
+    scopes: [read, action]
+    rate_limit: 2/1m
+  - name: dev
+    token: dev-secret
+    scopes: [all]
+  - name: old
+    token: old-secret
+    scopes: [read]
+    expires_at: 2020-01-01T00:00:00Z
+"#;
+
+    #[test]
+    fn test_authenticate_by_token() {
+        let store = TokenStore::parse(TOKENS).unwrap();
+        let client = store.authenticate(Some("Bearer dev-secret")).unwrap();
+        assert_eq!(client.name, "dev");
+        assert!(client.allows("write_file"));
+
+        assert_eq!(store.authenticate(None), Err(AuthError::Missing));
+        assert_eq!(
+            store.authenticate(Some("Bearer nope")),
+            Err(AuthError::Invalid)
+        );
+        assert_eq!(
+            store.authenticate(Some("Bearer old-secret")),
+            Err(AuthError::Expired {
+                client: "old".into()
+            })
+        );
+    }
+
+    #[test]
+    fn test_scopes_per_tool() {
+        let store = TokenStore::parse(TOKENS).unwrap();
+        let scheduler = store.authenticate(Some("Bearer sched-secret")).unwrap();
+        assert!(scheduler.allows("get_window_tree"));
+        assert!(scheduler.allows("click_element"));
+        assert!(scheduler.allows("some_future_tool"));
+        assert!(!scheduler.allows("write_file"));
+        assert!(!scheduler.allows("edit_file"));
+    }
+
+    #[test]
+    fn test_rate_limit_refills() {
+        let mut limiter = RateLimiter::parse("2/1m").unwrap();
+        let start = limiter.last;
+        assert!(limiter.acquire(start).is_ok());
+        assert!(limiter.acquire(start).is_ok());
+        let retry = limiter.acquire(start).unwrap_err();
+        assert!(retry > Duration::from_secs(29) && retry <= Duration::from_secs(30));
+        assert!(limiter.acquire(start + Duration::from_secs(30)).is_ok());
+
+        let store = TokenStore::parse(TOKENS).unwrap();
+        for _ in 0..2 {
+            store.authenticate(Some("Bearer sched-secret")).unwrap();
+        }
+        assert!(matches!(
+            store.authenticate(Some("Bearer sched-secret")),
+            Err(AuthError::RateLimited { .. })
+        ));
+        // Other clients are unaffected
+        assert!(store.authenticate(Some("Bearer dev-secret")).is_ok());
+    }
+
+    #[test]
+    fn test_invalid_token_files() {
+        for bad in [
+            "tokens: [{name: a, token: x, scopes: [root]}]",
+            "tokens: [{name: a, scopes: [read]}]",
+            "tokens: [{name: a, token: x, scopes: [read]}, {name: a, token: y, scopes: [read]}]",
+            "tokens: [{name: a, token: x, scopes: [read]}, {name: b, token: x, scopes: [read]}]",
+            "tokens: [{name: a, token: x, scopes: [read], rate_limit: fast}]",
+        ] {
+            assert!(TokenStore::parse(bad).is_err(), "{bad}");
+        }
+        assert!(RateLimiter::parse("60/min").is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_scope_check_uses_current_client() {
+        assert!(check_tool_scope("write_file").is_ok());
+        let reader = ClientIdentity {
+            name: "reader".into(),
+            scopes: vec![Scope::Read],
+        };
+        with_client(Some(reader), async {
+            assert_eq!(current_client_name().as_deref(), Some("reader"));
+            assert!(check_tool_scope("get_window_tree").is_ok());
+            let error = check_tool_scope("click_element").unwrap_err();
+            assert!(error.message.contains("requires scope 'action'"));
+        })
+        .await;
+        assert!(current_client().is_none());
+    }
+}
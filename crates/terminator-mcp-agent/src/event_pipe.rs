@@ -1,7 +1,9 @@
-//! Windows Named Pipe server for receiving workflow events from TypeScript
+//! IPC server for receiving workflow events from TypeScript
 //!
 //! This module provides a clean IPC mechanism for TypeScript workflows to send
-//! events to the Rust MCP agent without polluting stderr.
+//! events to the Rust MCP agent without polluting stderr. The transport is a
+//! named pipe on Windows and a Unix domain socket (or loopback TCP) elsewhere,
+//! see [`crate::ipc_transport`].
 //!
 //! # Architecture
 //! ```text
@@ -11,17 +13,18 @@
 //! │        │        │         │        │        │
 //! │        ▼        │         │        ▼        │
 //! │  Write to pipe  │ ──────► │  Read events    │
-//! │                 │  Named  │        │        │
+//! │                 │  IPC    │        │        │
 //! │                 │  Pipe   │        ▼        │
 //! └─────────────────┘         │  Forward to MCP │
 //!                             └─────────────────┘
 //! ```
 
+use crate::ipc_transport::{serve_lines, IpcListener, LineServerHandle};
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
-use tokio::io::{AsyncBufReadExt, BufReader};
 use tokio::sync::mpsc;
-use tracing::{debug, error, info};
+use tracing::debug;
+use uuid::Uuid;
 
 /// Workflow event emitted from TypeScript workflows
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
@@ -243,120 +246,45 @@ pub fn generate_pipe_name(execution_id: &str) -> String {
     format!(r"\\.\pipe\mcp-workflow-events-{}", execution_id)
 }
 
-/// Windows Named Pipe server for receiving workflow events
-#[cfg(windows)]
+/// Server for receiving workflow events over the local IPC transport
 pub struct EventPipeServer {
-    pipe_name: String,
+    listener: IpcListener,
     event_sender: EventSender,
 }
 
-#[cfg(windows)]
 impl EventPipeServer {
-    /// Create a new event pipe server
-    pub fn new(execution_id: &str, event_sender: EventSender) -> Self {
-        Self {
-            pipe_name: generate_pipe_name(execution_id),
+    /// Create a new event pipe server, binding its endpoint immediately so the
+    /// address can be passed to the child before it starts. The endpoint name
+    /// gets a random suffix, so concurrent runs with the same id don't collide.
+    pub fn new(execution_id: &str, event_sender: EventSender) -> std::io::Result<Self> {
+        Ok(Self {
+            listener: IpcListener::bind(&format!(
+                "mcp-workflow-events-{execution_id}-{}",
+                Uuid::new_v4().simple()
+            ))?,
             event_sender,
-        }
+        })
     }
 
-    /// Get the pipe name for passing to TypeScript
+    /// Get the endpoint for passing to TypeScript (`MCP_EVENT_PIPE`)
     pub fn pipe_name(&self) -> &str {
-        &self.pipe_name
+        self.listener.endpoint()
     }
 
     /// Start the pipe server and return a handle to stop it
     /// This spawns a background task that reads events from the pipe
     pub async fn start(self) -> Result<PipeServerHandle, std::io::Error> {
-        use tokio::net::windows::named_pipe::{PipeMode, ServerOptions};
-
-        let pipe_name = self.pipe_name.clone();
-        let event_sender = self.event_sender;
-
-        // Create the named pipe server
-        let server = ServerOptions::new()
-            .first_pipe_instance(true)
-            .pipe_mode(PipeMode::Byte)
-            .create(&pipe_name)?;
-
-        info!("Created named pipe: {}", pipe_name);
-
-        let (shutdown_tx, mut shutdown_rx) = mpsc::channel::<()>(1);
-
-        let handle = tokio::spawn(async move {
-            // Wait for client connection
-            debug!("Waiting for TypeScript to connect to pipe...");
-
-            tokio::select! {
-                result = server.connect() => {
-                    match result {
-                        Ok(()) => {
-                            info!("TypeScript connected to event pipe");
-
-                            // Read events from pipe
-                            let reader = BufReader::new(server);
-                            let mut lines = reader.lines();
-
-                            loop {
-                                tokio::select! {
-                                    line_result = lines.next_line() => {
-                                        match line_result {
-                                            Ok(Some(line)) => {
-                                                if let Some(event) = try_parse_event(&line) {
-                                                    debug!("Received event from pipe: {:?}", event);
-                                                    if event_sender.send(event).is_err() {
-                                                        debug!("Event receiver dropped, stopping pipe server");
-                                                        break;
-                                                    }
-                                                }
-                                            }
-                                            Ok(None) => {
-                                                debug!("Pipe closed by client");
-                                                break;
-                                            }
-                                            Err(e) => {
-                                                error!("Error reading from pipe: {}", e);
-                                                break;
-                                            }
-                                        }
-                                    }
-                                    _ = shutdown_rx.recv() => {
-                                        debug!("Pipe server shutdown requested");
-                                        break;
-                                    }
-                                }
-                            }
-                        }
-                        Err(e) => {
-                            error!("Failed to accept pipe connection: {}", e);
-                        }
-                    }
-                }
-                _ = shutdown_rx.recv() => {
-                    debug!("Pipe server shutdown before connection");
-                }
-            }
-        });
-
-        Ok(PipeServerHandle {
-            _handle: handle,
-            shutdown_tx,
-        })
+        Ok(serve_lines(
+            self.listener,
+            "event pipe",
+            try_parse_event,
+            self.event_sender,
+        ))
     }
 }
 
 /// Handle to control the pipe server
-pub struct PipeServerHandle {
-    _handle: tokio::task::JoinHandle<()>,
-    shutdown_tx: mpsc::Sender<()>,
-}
-
-impl PipeServerHandle {
-    /// Signal the pipe server to shutdown
-    pub async fn shutdown(self) {
-        let _ = self.shutdown_tx.send(()).await;
-    }
-}
+pub type PipeServerHandle = LineServerHandle;
 
 #[cfg(test)]
 mod tests {
@@ -719,7 +647,7 @@ mod windows_integration_tests {
     #[tokio::test]
     async fn test_pipe_server_basic() {
         let (tx, mut rx) = create_event_channel();
-        let server = EventPipeServer::new("test-basic", tx);
+        let server = EventPipeServer::new("test-basic", tx).unwrap();
         let pipe_name = server.pipe_name().to_string();
 
         let handle = server.start().await.expect("Failed to start pipe server");
@@ -768,7 +696,7 @@ mod windows_integration_tests {
     #[tokio::test]
     async fn test_pipe_server_multiple_events() {
         let (tx, mut rx) = create_event_channel();
-        let server = EventPipeServer::new("test-multi", tx);
+        let server = EventPipeServer::new("test-multi", tx).unwrap();
         let pipe_name = server.pipe_name().to_string();
 
         let handle = server.start().await.expect("Failed to start pipe server");
@@ -814,7 +742,7 @@ mod windows_integration_tests {
     #[tokio::test]
     async fn test_pipe_server_ignores_non_events() {
         let (tx, mut rx) = create_event_channel();
-        let server = EventPipeServer::new("test-ignore", tx);
+        let server = EventPipeServer::new("test-ignore", tx).unwrap();
         let pipe_name = server.pipe_name().to_string();
 
         let handle = server.start().await.expect("Failed to start pipe server");
@@ -0,0 +1,889 @@
+//! Execution history index (`history.jsonl`).
+//!
+//! `execution_logger` writes one JSON file per tool call, which is fine for
+//! looking at a single call but can't answer "which runs of this workflow
+//! failed" or "which step is slow". Every `execute_sequence` run also appends
+//! to an index next to the logs: a line when the run starts, one per step
+//! (status, duration, error, log file, screenshots) and one when it ends. The
+//! index is folded into [`RunRecord`]s on read and queried by the
+//! `query_execution_history`, `get_execution_run` and `execution_step_stats`
+//! tools and by `terminator mcp history`.
+//!
+//! Retention of both the index and the log files comes from `retention.yaml`
+//! (see [`RetentionPolicy`]) instead of a fixed number of days.
+//!
+//! Files:
+//!   Windows: %LOCALAPPDATA%\mediar\executions\history.jsonl
+//!   macOS: ~/Library/Application Support/mediar/executions/history.jsonl
+//!   Linux: ~/.local/share/mediar/executions/history.jsonl
+
+use crate::execution_logger::{self, ExecutionLog};
+use anyhow::{anyhow, Context, Result};
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::fs::{self, OpenOptions};
+use std::future::Future;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::Instant;
+use tracing::warn;
+
+pub const HISTORY_FILE_NAME: &str = "history.jsonl";
+pub const RETENTION_FILE_NAME: &str = "retention.yaml";
+pub const DEFAULT_RETENTION_DAYS: u32 = 7;
+
+/// Serializes appends and rewrites of the index within this process
+static FILE_LOCK: Mutex<()> = Mutex::new(());
+
+tokio::task_local! {
+    static CURRENT_RUN: String;
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RunStatus {
+    /// Still running, or the agent stopped before the run finished
+    Running,
+    Completed,
+    Failed,
+    Cancelled,
+}
+
+impl std::str::FromStr for RunStatus {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "running" => Ok(RunStatus::Running),
+            "completed" | "success" | "ok" => Ok(RunStatus::Completed),
+            "failed" | "error" => Ok(RunStatus::Failed),
+            "cancelled" | "canceled" => Ok(RunStatus::Cancelled),
+            other => Err(anyhow!(
+                "Unknown run status '{other}' (expected running, completed, failed or cancelled)"
+            )),
+        }
+    }
+}
+
+/// One tool call made by a run
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StepRecord {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub step_index: Option<usize>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub step_id: Option<String>,
+    pub tool_name: String,
+    pub started_at: String,
+    pub duration_ms: u64,
+    pub success: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub retry_attempt: Option<u32>,
+    /// Execution log file of the call
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub log_path: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub screenshots: Vec<String>,
+}
+
+impl StepRecord {
+    /// Name used to group the step in statistics: its id, or index and tool
+    pub fn label(&self) -> String {
+        match (&self.step_id, self.step_index) {
+            (Some(id), _) => id.clone(),
+            (None, Some(index)) => format!("#{index} {}", self.tool_name),
+            (None, None) => self.tool_name.clone(),
+        }
+    }
+}
+
+/// A workflow run folded from the index
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct RunRecord {
+    pub run_id: String,
+    /// Run that called this one through an `execute_sequence` step
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub parent_run_id: Option<String>,
+    /// Workflow URL, when run from a file or URL
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub workflow: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub workflow_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub client: Option<String>,
+    pub status: RunStatus,
+    pub started_at: DateTime<Utc>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub finished_at: Option<DateTime<Utc>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub duration_ms: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    /// Execution log of the `execute_sequence` call itself, once it is written
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub log_path: Option<String>,
+    pub steps: Vec<StepRecord>,
+}
+
+impl RunRecord {
+    /// Workflow id, or the file name of the workflow URL without extension
+    pub fn workflow_name(&self) -> Option<String> {
+        if let Some(id) = &self.workflow_id {
+            return Some(id.clone());
+        }
+        let url = self.workflow.as_deref()?;
+        let path = Path::new(url.strip_prefix("file://").unwrap_or(url));
+        // TypeScript workflows are folders; YAML workflows are files
+        path.file_stem()
+            .map(|stem| stem.to_string_lossy().to_string())
+    }
+
+    /// The run without its steps: step count and the first failed step instead
+    pub fn summary(&self) -> serde_json::Value {
+        let mut value = serde_json::to_value(self).unwrap_or_default();
+        if let Some(obj) = value.as_object_mut() {
+            obj.insert("steps".to_string(), self.steps.len().into());
+            if let Some(failed) = self.steps.iter().find(|s| !s.success) {
+                obj.insert("failed_step".to_string(), failed.label().into());
+            }
+        }
+        value
+    }
+
+    /// Files the run wrote: the execution log, TypeScript snippet and
+    /// screenshots of every step, and the log of the run itself
+    pub fn files(&self) -> Vec<PathBuf> {
+        let logs = self
+            .steps
+            .iter()
+            .filter_map(|step| step.log_path.as_deref())
+            .chain(self.log_path.as_deref())
+            .map(PathBuf::from);
+        let mut files = Vec::new();
+        for log in logs {
+            files.push(log.with_extension("ts"));
+            files.push(log);
+        }
+        files.extend(
+            self.steps
+                .iter()
+                .flat_map(|step| &step.screenshots)
+                .map(PathBuf::from),
+        );
+        files
+    }
+
+    fn matches_workflow(&self, workflow: &str) -> bool {
+        self.workflow_name().as_deref() == Some(workflow)
+            || self
+                .workflow
+                .as_deref()
+                .is_some_and(|url| url.contains(workflow))
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+enum HistoryEvent {
+    RunStarted {
+        run_id: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        parent_run_id: Option<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        workflow: Option<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        workflow_id: Option<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        client: Option<String>,
+        started_at: DateTime<Utc>,
+    },
+    Step {
+        run_id: String,
+        step: StepRecord,
+    },
+    RunFinished {
+        run_id: String,
+        finished_at: DateTime<Utc>,
+        duration_ms: u64,
+        status: RunStatus,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        error: Option<String>,
+    },
+    RunLog {
+        run_id: String,
+        log_path: String,
+    },
+}
+
+impl HistoryEvent {
+    fn run_id(&self) -> &str {
+        match self {
+            HistoryEvent::RunStarted { run_id, .. }
+            | HistoryEvent::Step { run_id, .. }
+            | HistoryEvent::RunFinished { run_id, .. }
+            | HistoryEvent::RunLog { run_id, .. } => run_id,
+        }
+    }
+}
+
+/// Filter for [`History::query`]
+#[derive(Debug, Clone, Default)]
+pub struct RunQuery {
+    /// Workflow id, file name (without extension) or part of the URL
+    pub workflow: Option<String>,
+    pub status: Option<RunStatus>,
+    pub since: Option<DateTime<Utc>>,
+    /// Newest runs first; 0 means no limit
+    pub limit: usize,
+}
+
+/// Duration statistics of one step across runs
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct StepStats {
+    pub step: String,
+    pub tool_name: String,
+    pub count: usize,
+    pub failures: usize,
+    pub mean_ms: u64,
+    pub p50_ms: u64,
+    pub p95_ms: u64,
+    pub max_ms: u64,
+}
+
+/// Days to keep execution logs and history, per workflow
+///
+/// ```yaml
+/// default_days: 7
+/// workflows:
+///   invoice-entry: 30
+///   nightly-sync: 2
+/// ```
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RetentionPolicy {
+    #[serde(default = "default_retention_days")]
+    pub default_days: u32,
+    #[serde(default)]
+    pub workflows: HashMap<String, u32>,
+}
+
+fn default_retention_days() -> u32 {
+    DEFAULT_RETENTION_DAYS
+}
+
+impl Default for RetentionPolicy {
+    fn default() -> Self {
+        Self {
+            default_days: DEFAULT_RETENTION_DAYS,
+            workflows: HashMap::new(),
+        }
+    }
+}
+
+impl RetentionPolicy {
+    pub fn parse(content: &str) -> Result<Self> {
+        serde_yaml::from_str(content).context("Invalid retention policy")
+    }
+
+    /// `retention.yaml` in the mediar data directory, with
+    /// `TERMINATOR_LOG_RETENTION_DAYS` overriding the default. Falls back to
+    /// the default policy (with a warning) when the file can't be read.
+    pub fn load() -> Self {
+        let path = mediar_dir().join(RETENTION_FILE_NAME);
+        let mut policy = match fs::read_to_string(&path) {
+            Ok(content) => Self::parse(&content).unwrap_or_else(|e| {
+                warn!("[execution_history] Ignoring {}: {:#}", path.display(), e);
+                Self::default()
+            }),
+            Err(_) => Self::default(),
+        };
+        if let Some(days) = std::env::var("TERMINATOR_LOG_RETENTION_DAYS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+        {
+            policy.default_days = days;
+        }
+        policy
+    }
+
+    pub fn days_for(&self, workflow: Option<&str>) -> u32 {
+        workflow
+            .and_then(|w| self.workflows.get(w))
+            .copied()
+            .unwrap_or(self.default_days)
+    }
+
+    /// Anything that started before this is expired
+    pub fn cutoff(&self, workflow: Option<&str>, now: DateTime<Utc>) -> DateTime<Utc> {
+        now - Duration::days(i64::from(self.days_for(workflow)))
+    }
+}
+
+fn mediar_dir() -> PathBuf {
+    dirs::data_local_dir()
+        .unwrap_or_else(std::env::temp_dir)
+        .join("mediar")
+}
+
+/// Path of the history index
+pub fn history_path() -> PathBuf {
+    execution_logger::get_executions_dir().join(HISTORY_FILE_NAME)
+}
+
+/// The history index file
+#[derive(Debug, Clone)]
+pub struct History {
+    path: PathBuf,
+}
+
+impl History {
+    pub fn new(path: PathBuf) -> Self {
+        Self { path }
+    }
+
+    pub fn open_default() -> Self {
+        Self::new(history_path())
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    fn append(&self, event: &HistoryEvent) -> Result<()> {
+        let mut line = serde_json::to_string(event)?;
+        line.push('\n');
+        let _lock = FILE_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .with_context(|| format!("Failed to open {}", self.path.display()))?;
+        file.write_all(line.as_bytes())?;
+        Ok(())
+    }
+
+    fn events(&self) -> Result<Vec<HistoryEvent>> {
+        let content = match fs::read_to_string(&self.path) {
+            Ok(content) => content,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => {
+                return Err(e).with_context(|| format!("Failed to read {}", self.path.display()))
+            }
+        };
+        // A line cut short by a crash is skipped rather than failing the whole index
+        Ok(content
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .filter_map(|line| serde_json::from_str(line).ok())
+            .collect())
+    }
+
+    /// All runs, newest first
+    pub fn runs(&self) -> Result<Vec<RunRecord>> {
+        let mut runs: BTreeMap<String, RunRecord> = BTreeMap::new();
+        for event in self.events()? {
+            match event {
+                HistoryEvent::RunStarted {
+                    run_id,
+                    parent_run_id,
+                    workflow,
+                    workflow_id,
+                    client,
+                    started_at,
+                } => {
+                    runs.insert(
+                        run_id.clone(),
+                        RunRecord {
+                            run_id,
+                            parent_run_id,
+                            workflow,
+                            workflow_id,
+                            client,
+                            status: RunStatus::Running,
+                            started_at,
+                            finished_at: None,
+                            duration_ms: None,
+                            error: None,
+                            log_path: None,
+                            steps: Vec::new(),
+                        },
+                    );
+                }
+                HistoryEvent::Step { run_id, step } => {
+                    if let Some(run) = runs.get_mut(&run_id) {
+                        run.steps.push(step);
+                    }
+                }
+                HistoryEvent::RunFinished {
+                    run_id,
+                    finished_at,
+                    duration_ms,
+                    status,
+                    error,
+                } => {
+                    if let Some(run) = runs.get_mut(&run_id) {
+                        run.finished_at = Some(finished_at);
+                        run.duration_ms = Some(duration_ms);
+                        run.status = status;
+                        run.error = error;
+                    }
+                }
+                HistoryEvent::RunLog { run_id, log_path } => {
+                    if let Some(run) = runs.get_mut(&run_id) {
+                        run.log_path = Some(log_path);
+                    }
+                }
+            }
+        }
+        let mut runs: Vec<RunRecord> = runs.into_values().collect();
+        runs.sort_by_key(|run| std::cmp::Reverse(run.started_at));
+        Ok(runs)
+    }
+
+    pub fn query(&self, query: &RunQuery) -> Result<Vec<RunRecord>> {
+        let matching = self.runs()?.into_iter().filter(|run| {
+            query
+                .workflow
+                .as_deref()
+                .is_none_or(|w| run.matches_workflow(w))
+                && query.status.is_none_or(|s| run.status == s)
+                && query.since.is_none_or(|since| run.started_at >= since)
+        });
+        Ok(match query.limit {
+            0 => matching.collect(),
+            limit => matching.take(limit).collect(),
+        })
+    }
+
+    /// A run by id; a unique prefix of the id is enough
+    pub fn run(&self, run_id: &str) -> Result<Option<RunRecord>> {
+        let runs = self.runs()?;
+        if let Some(run) = runs.iter().find(|r| r.run_id == run_id) {
+            return Ok(Some(run.clone()));
+        }
+        let mut prefixed = runs.into_iter().filter(|r| r.run_id.starts_with(run_id));
+        match (prefixed.next(), prefixed.next()) {
+            (Some(run), None) => Ok(Some(run)),
+            (Some(_), Some(_)) => Err(anyhow!("Run id prefix '{run_id}' is ambiguous")),
+            (None, _) => Ok(None),
+        }
+    }
+
+    /// Per-step duration statistics over the matching runs, slowest p95 first
+    pub fn step_stats(&self, query: &RunQuery) -> Result<Vec<StepStats>> {
+        let runs = self.query(&RunQuery {
+            limit: 0,
+            ..query.clone()
+        })?;
+        let mut by_step: BTreeMap<String, (String, Vec<u64>, usize)> = BTreeMap::new();
+        for step in runs.iter().flat_map(|run| &run.steps) {
+            let entry = by_step
+                .entry(step.label())
+                .or_insert_with(|| (step.tool_name.clone(), Vec::new(), 0));
+            entry.1.push(step.duration_ms);
+            if !step.success {
+                entry.2 += 1;
+            }
+        }
+
+        let mut stats: Vec<StepStats> = by_step
+            .into_iter()
+            .map(|(step, (tool_name, mut durations, failures))| {
+                durations.sort_unstable();
+                let count = durations.len();
+                StepStats {
+                    step,
+                    tool_name,
+                    count,
+                    failures,
+                    mean_ms: durations.iter().sum::<u64>() / count as u64,
+                    p50_ms: percentile(&durations, 50),
+                    p95_ms: percentile(&durations, 95),
+                    max_ms: durations[count - 1],
+                }
+            })
+            .collect();
+        stats.sort_by(|a, b| b.p95_ms.cmp(&a.p95_ms).then_with(|| a.step.cmp(&b.step)));
+        Ok(stats)
+    }
+
+    /// Drop runs older than their workflow's retention and return them, so
+    /// their files can be deleted by the same rule.
+    pub fn prune(&self, policy: &RetentionPolicy, now: DateTime<Utc>) -> Result<Vec<RunRecord>> {
+        let _lock = FILE_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        let (expired, _): (Vec<RunRecord>, Vec<RunRecord>) = self
+            .runs()?
+            .into_iter()
+            .partition(|run| run.started_at < policy.cutoff(run.workflow_name().as_deref(), now));
+        if expired.is_empty() {
+            return Ok(expired);
+        }
+
+        let expired_ids: std::collections::HashSet<&str> =
+            expired.iter().map(|run| run.run_id.as_str()).collect();
+        let mut kept = String::new();
+        for event in self.events()? {
+            if !expired_ids.contains(event.run_id()) {
+                kept.push_str(&serde_json::to_string(&event)?);
+                kept.push('\n');
+            }
+        }
+        let tmp = self.path.with_extension("jsonl.tmp");
+        fs::write(&tmp, kept).with_context(|| format!("Failed to write {}", tmp.display()))?;
+        fs::rename(&tmp, &self.path)
+            .with_context(|| format!("Failed to replace {}", self.path.display()))?;
+        Ok(expired)
+    }
+}
+
+/// Start of a time window: an RFC 3339 timestamp, a date (`2025-01-31`), or an
+/// age before `now` such as `7d`, `12h` or `30m`
+pub fn parse_since(input: &str, now: DateTime<Utc>) -> Result<DateTime<Utc>> {
+    let input = input.trim();
+    if let Ok(ts) = DateTime::parse_from_rfc3339(input) {
+        return Ok(ts.with_timezone(&Utc));
+    }
+    if let Ok(date) = chrono::NaiveDate::parse_from_str(input, "%Y-%m-%d") {
+        return Ok(date.and_time(chrono::NaiveTime::MIN).and_utc());
+    }
+    let age_ms = match input.strip_suffix('d') {
+        Some(days) => days
+            .trim()
+            .parse::<f64>()
+            .map(|d| (d * 86_400_000.0) as u64)
+            .map_err(|_| anyhow!("Invalid number of days: {days}")),
+        None => crate::duration_parser::parse_duration(input),
+    }
+    .with_context(|| {
+        format!("Invalid time '{input}' (expected RFC 3339, YYYY-MM-DD, or an age like 7d)")
+    })?;
+    Ok(now - Duration::milliseconds(age_ms as i64))
+}
+
+/// Nearest-rank percentile of sorted values
+fn percentile(sorted: &[u64], pct: usize) -> u64 {
+    let rank = (pct * sorted.len()).div_ceil(100).max(1);
+    sorted[rank - 1]
+}
+
+/// Id of the run the current task is executing, if any
+pub fn current_run_id() -> Option<String> {
+    CURRENT_RUN.try_with(|id| id.clone()).ok()
+}
+
+/// Records one run in the history. Steps logged by `execution_logger` inside
+/// [`RunRecorder::scope`] are attached to it. A recorder dropped without
+/// [`RunRecorder::finish`] (the run was cancelled) records the run as cancelled.
+pub struct RunRecorder {
+    history: Option<History>,
+    run_id: String,
+    started: Instant,
+    finished: bool,
+}
+
+impl RunRecorder {
+    pub fn start(run_id: &str, workflow: Option<String>, workflow_id: Option<String>) -> Self {
+        let history = execution_logger::is_enabled().then(History::open_default);
+        let recorder = Self {
+            history,
+            run_id: run_id.to_string(),
+            started: Instant::now(),
+            finished: false,
+        };
+        recorder.record(HistoryEvent::RunStarted {
+            run_id: run_id.to_string(),
+            parent_run_id: current_run_id(),
+            workflow,
+            workflow_id,
+            client: crate::client_auth::current_client_name(),
+            started_at: Utc::now(),
+        });
+        recorder
+    }
+
+    /// Run `future` as this run, so logged tool calls become its steps
+    pub async fn scope<F: Future>(&self, future: F) -> F::Output {
+        CURRENT_RUN.scope(self.run_id.clone(), future).await
+    }
+
+    pub fn finish(mut self, status: RunStatus, error: Option<String>) {
+        self.finished = true;
+        self.record_finished(status, error);
+    }
+
+    fn record_finished(&self, status: RunStatus, error: Option<String>) {
+        self.record(HistoryEvent::RunFinished {
+            run_id: self.run_id.clone(),
+            finished_at: Utc::now(),
+            duration_ms: self.started.elapsed().as_millis() as u64,
+            status,
+            error,
+        });
+    }
+
+    fn record(&self, event: HistoryEvent) {
+        if let Some(history) = &self.history {
+            if let Err(e) = history.append(&event) {
+                warn!("[execution_history] Failed to record run: {:#}", e);
+            }
+        }
+    }
+}
+
+impl Drop for RunRecorder {
+    fn drop(&mut self) {
+        if !self.finished {
+            self.record_finished(RunStatus::Cancelled, None);
+        }
+    }
+}
+
+/// Attach a logged tool call to the current run (no-op outside a run). The log
+/// of an `execute_sequence` call started with an `execution_id` is also
+/// attached to that run, so its result can be found from the index.
+pub fn record_step(log: &ExecutionLog, log_path: &Path) {
+    if log.tool_name == "execute_sequence" {
+        if let Some(run_id) = log.request.get("execution_id").and_then(|v| v.as_str()) {
+            let event = HistoryEvent::RunLog {
+                run_id: run_id.to_string(),
+                log_path: log_path.display().to_string(),
+            };
+            if let Err(e) = History::open_default().append(&event) {
+                warn!("[execution_history] Failed to record run log: {:#}", e);
+            }
+        }
+    }
+    let Some(run_id) = current_run_id() else {
+        return;
+    };
+    let dir = log_path.parent().unwrap_or(Path::new(""));
+    let screenshots = log
+        .screenshots
+        .iter()
+        .flat_map(|refs| refs.before.iter().chain(&refs.after))
+        .map(|name| dir.join(name).display().to_string())
+        .collect();
+    let event = HistoryEvent::Step {
+        run_id,
+        step: StepRecord {
+            step_index: log.step_index,
+            step_id: log.step_id.clone(),
+            tool_name: log.tool_name.clone(),
+            started_at: log.timestamp.clone(),
+            duration_ms: log.response.duration_ms,
+            success: log.response.error.is_none(),
+            error: log.response.error.clone(),
+            retry_attempt: log.retry_attempt,
+            log_path: Some(log_path.display().to_string()),
+            screenshots,
+        },
+    };
+    if let Err(e) = History::open_default().append(&event) {
+        warn!("[execution_history] Failed to record step: {:#}", e);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn step(id: &str, duration_ms: u64, success: bool) -> StepRecord {
+        StepRecord {
+            step_index: None,
+            step_id: Some(id.to_string()),
+            tool_name: "click_element".to_string(),
+            started_at: Utc::now().to_rfc3339(),
+            duration_ms,
+            success,
+            error: (!success).then(|| "Element not found".to_string()),
+            retry_attempt: None,
+            log_path: None,
+            screenshots: Vec::new(),
+        }
+    }
+
+    fn add_run(
+        history: &History,
+        run_id: &str,
+        workflow: &str,
+        started_at: DateTime<Utc>,
+        steps: Vec<StepRecord>,
+        status: Option<RunStatus>,
+    ) {
+        history
+            .append(&HistoryEvent::RunStarted {
+                run_id: run_id.to_string(),
+                parent_run_id: None,
+                workflow: Some(format!("file:///flows/{workflow}.yml")),
+                workflow_id: None,
+                client: None,
+                started_at,
+            })
+            .unwrap();
+        for step in steps {
+            history
+                .append(&HistoryEvent::Step {
+                    run_id: run_id.to_string(),
+                    step,
+                })
+                .unwrap();
+        }
+        if let Some(status) = status {
+            history
+                .append(&HistoryEvent::RunFinished {
+                    run_id: run_id.to_string(),
+                    finished_at: started_at + Duration::seconds(5),
+                    duration_ms: 5000,
+                    status,
+                    error: (status == RunStatus::Failed).then(|| "step failed".to_string()),
+                })
+                .unwrap();
+        }
+    }
+
+    fn sample_history(dir: &Path) -> History {
+        let history = History::new(dir.join(HISTORY_FILE_NAME));
+        let now = Utc::now();
+        add_run(
+            &history,
+            "run-1",
+            "invoice",
+            now - Duration::hours(3),
+            vec![step("open", 100, true), step("submit", 900, true)],
+            Some(RunStatus::Completed),
+        );
+        add_run(
+            &history,
+            "run-2",
+            "invoice",
+            now - Duration::hours(2),
+            vec![step("open", 300, true), step("submit", 1500, false)],
+            Some(RunStatus::Failed),
+        );
+        add_run(
+            &history,
+            "run-3",
+            "payroll",
+            now - Duration::hours(1),
+            vec![step("login", 50, true)],
+            None,
+        );
+        history
+    }
+
+    #[test]
+    fn test_query_runs() {
+        let dir = tempfile::tempdir().unwrap();
+        let history = sample_history(dir.path());
+        // A torn last line is ignored
+        fs::OpenOptions::new()
+            .append(true)
+            .open(history.path())
+            .unwrap()
+            .write_all(b"{\"event\":\"step\",\"run_")
+            .unwrap();
+
+        let runs = history.runs().unwrap();
+        let ids: Vec<_> = runs.iter().map(|r| r.run_id.as_str()).collect();
+        assert_eq!(ids, ["run-3", "run-2", "run-1"]);
+        assert_eq!(runs[0].status, RunStatus::Running);
+        assert_eq!(runs[1].steps.len(), 2);
+        assert_eq!(runs[1].error.as_deref(), Some("step failed"));
+
+        let failed = history
+            .query(&RunQuery {
+                workflow: Some("invoice".to_string()),
+                status: Some(RunStatus::Failed),
+                ..Default::default()
+            })
+            .unwrap();
+        assert_eq!(failed.len(), 1);
+        assert_eq!(failed[0].run_id, "run-2");
+
+        let latest = history
+            .query(&RunQuery {
+                limit: 1,
+                ..Default::default()
+            })
+            .unwrap();
+        assert_eq!(latest[0].workflow_name().as_deref(), Some("payroll"));
+
+        assert_eq!(history.run("run-1").unwrap().unwrap().steps.len(), 2);
+        assert!(history.run("run-").is_err());
+        assert!(history.run("nope").unwrap().is_none());
+    }
+
+    #[test]
+    fn test_step_stats() {
+        let dir = tempfile::tempdir().unwrap();
+        let history = sample_history(dir.path());
+        let stats = history
+            .step_stats(&RunQuery {
+                workflow: Some("invoice".to_string()),
+                ..Default::default()
+            })
+            .unwrap();
+        assert_eq!(stats.len(), 2);
+        assert_eq!(stats[0].step, "submit");
+        assert_eq!(stats[0].count, 2);
+        assert_eq!(stats[0].failures, 1);
+        assert_eq!(stats[0].p50_ms, 900);
+        assert_eq!(stats[0].p95_ms, 1500);
+        assert_eq!(stats[0].mean_ms, 1200);
+        assert_eq!(stats[1].step, "open");
+        assert_eq!(percentile(&[1, 2, 3, 4, 5, 6, 7, 8, 9, 10], 95), 10);
+        assert_eq!(percentile(&[1, 2, 3, 4, 5, 6, 7, 8, 9, 10], 50), 5);
+    }
+
+    #[test]
+    fn test_parse_since() {
+        let now = DateTime::parse_from_rfc3339("2025-03-10T12:00:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        assert_eq!(parse_since("7d", now).unwrap(), now - Duration::days(7));
+        assert_eq!(parse_since("2h", now).unwrap(), now - Duration::hours(2));
+        assert_eq!(
+            parse_since("2025-03-01", now).unwrap().to_rfc3339(),
+            "2025-03-01T00:00:00+00:00"
+        );
+        assert_eq!(
+            parse_since("2025-03-09T08:30:00+01:00", now).unwrap(),
+            now - Duration::hours(28) - Duration::minutes(30)
+        );
+        assert!(parse_since("last week", now).is_err());
+    }
+
+    #[test]
+    fn test_retention_and_prune() {
+        let policy =
+            RetentionPolicy::parse("default_days: 14\nworkflows:\n  payroll: 0\n").unwrap();
+        assert_eq!(policy.days_for(Some("invoice")), 14);
+        assert_eq!(policy.days_for(Some("payroll")), 0);
+        assert_eq!(policy.days_for(None), 14);
+        assert!(RetentionPolicy::parse("default: 3\n").is_err());
+        assert_eq!(
+            RetentionPolicy::parse("{}").unwrap(),
+            RetentionPolicy::default()
+        );
+
+        let dir = tempfile::tempdir().unwrap();
+        let history = sample_history(dir.path());
+        let pruned = history.prune(&policy, Utc::now()).unwrap();
+        assert_eq!(pruned.len(), 1);
+        assert_eq!(pruned[0].run_id, "run-3");
+        let ids: Vec<_> = history
+            .runs()
+            .unwrap()
+            .into_iter()
+            .map(|r| r.run_id)
+            .collect();
+        assert_eq!(ids, ["run-2", "run-1"]);
+        assert!(history.prune(&policy, Utc::now()).unwrap().is_empty());
+    }
+}
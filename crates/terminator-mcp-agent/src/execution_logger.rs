@@ -1,22 +1,30 @@
 //! MCP Tool Execution Logger
 //!
 //! Logs all MCP tool requests and responses to flat files in %LOCALAPPDATA%\mediar\executions\
-//! with associated before/after screenshots. 7-day retention with automatic cleanup.
+//! with associated before/after screenshots. Retention follows `retention.yaml` (7 days by
+//! default, see `execution_history::RetentionPolicy`) with automatic cleanup.
+//!
+//! Requests, results, errors, captured logs and screenshots are redacted with
+//! `terminator::redaction` before they are written.
 
 use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
 use chrono::{DateTime, Local, Utc};
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
+use std::collections::HashSet;
 use std::fs;
 use std::path::PathBuf;
 use std::sync::atomic::{AtomicBool, Ordering};
+use terminator::redaction::{ImageOrigin, JsonRedaction, Redactor, REDACTED};
 use tracing::{debug, error, info, warn};
 
 /// Whether execution logging is enabled (can be disabled via env var)
 static LOGGING_ENABLED: AtomicBool = AtomicBool::new(true);
 
-/// Retention period in days
-const RETENTION_DAYS: i64 = 7;
+/// Arguments holding the text a tool types or sets, masked when the call
+/// touched a password field or an excluded application
+const TEXT_ARGUMENTS: &[&str] = &["text_to_type", "text", "value"];
+
 /// A captured log entry from workflow execution
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CapturedLogEntry {
@@ -31,10 +39,15 @@ pub struct ExecutionLog {
     pub timestamp: String,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub workflow_id: Option<String>,
+    /// Run this call was made by (the sequence's `execution_id`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub execution_id: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub step_id: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub step_index: Option<usize>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub retry_attempt: Option<u32>,
     pub tool_name: String,
     pub request: Value,
     pub response: ExecutionResponse,
@@ -42,6 +55,12 @@ pub struct ExecutionLog {
     pub screenshots: Option<ScreenshotRefs>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub logs: Option<Vec<CapturedLogEntry>>,
+    /// Tool policy decision for this call (only when a policy is configured)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub policy: Option<crate::tool_policy::PolicyDecision>,
+    /// Name of the authenticated client that made the call (HTTP token auth)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub client: Option<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -67,11 +86,36 @@ pub struct ScreenshotRefs {
 pub struct ExecutionContext {
     pub timestamp: chrono::DateTime<Local>,
     pub workflow_id: Option<String>,
+    pub execution_id: Option<String>,
     pub step_id: Option<String>,
     pub step_index: Option<usize>,
+    /// 1-based retry number when this call is a retry of a sequence step
+    pub retry_attempt: Option<u32>,
     pub tool_name: String,
     pub request: Value,
     pub file_prefix: String,
+    pub policy: Option<crate::tool_policy::PolicyDecision>,
+    pub client: Option<String>,
+}
+
+impl ExecutionContext {
+    /// Record that this execution is a retry of its step
+    pub fn with_retry_attempt(mut self, retry_attempt: Option<u32>) -> Self {
+        self.retry_attempt = retry_attempt;
+        if let Some(attempt) = retry_attempt {
+            self.file_prefix = format!("{}_retry{}", self.file_prefix, attempt);
+        }
+        self
+    }
+
+    /// Attach the tool policy decision that let this call run (or refused it)
+    pub fn with_policy_decision(
+        mut self,
+        decision: Option<crate::tool_policy::PolicyDecision>,
+    ) -> Self {
+        self.policy = decision;
+        self
+    }
 }
 
 /// Get the executions directory path for standalone tool calls (no workflow context)
@@ -129,9 +173,7 @@ pub fn init() {
     );
 
     // Run cleanup in background
-    tokio::spawn(async {
-        cleanup_old_executions().await;
-    });
+    tokio::task::spawn_blocking(cleanup_old_executions);
 }
 
 /// Check if logging is enabled
@@ -230,16 +272,20 @@ pub fn log_request(
     Some(ExecutionContext {
         timestamp,
         workflow_id: workflow_id.map(String::from),
+        execution_id: crate::execution_history::current_run_id(),
         step_id: step_id.map(String::from),
         step_index,
+        retry_attempt: None,
         tool_name: tool_name.to_string(),
         request: arguments.clone(),
         file_prefix,
+        policy: None,
+        client: crate::client_auth::current_client_name(),
     })
 }
 
 /// Complete logging an execution (call after tool dispatch)
-pub fn log_response(ctx: ExecutionContext, result: Result<&Value, &str>, duration_ms: u64) {
+pub fn log_response(mut ctx: ExecutionContext, result: Result<&Value, &str>, duration_ms: u64) {
     info!(
         "[execution_logger] log_response called for tool: {}, workflow_id: {:?}, enabled: {}",
         ctx.tool_name,
@@ -267,24 +313,43 @@ pub fn log_response(ctx: ExecutionContext, result: Result<&Value, &str>, duratio
 
     let json_path = dir.join(format!("{}.json", ctx.file_prefix));
 
+    // Build response, stripping screenshot base64 from result, and redact
+    // everything before it is written. Redacting first also notes password
+    // fields so the screenshots below hide them.
+    let redactor = terminator::redaction::global();
+    let mut clean_result = result.ok().map(strip_screenshot_base64);
+    let mut error = result.err().map(String::from);
+    redact_call(
+        &redactor,
+        &mut ctx.request,
+        clean_result.as_mut(),
+        error.as_mut(),
+        None,
+    );
+
     // Extract screenshots from result and save them
     let screenshots = if let Ok(result_value) = result {
-        extract_and_save_screenshots(&dir, &ctx.file_prefix, result_value)
+        extract_and_save_screenshots(&dir, &ctx.file_prefix, result_value, &redactor)
     } else {
         None
     };
 
-    // Build response, stripping screenshot base64 from result
-    let clean_result = result.ok().map(strip_screenshot_base64);
-
     // Generate TypeScript snippet before moving ctx.request
-    let ts_snippet = generate_typescript_snippet(&ctx.tool_name, &ctx.request, result);
+    let ts_snippet = generate_typescript_snippet(
+        &ctx.tool_name,
+        &ctx.request,
+        clean_result
+            .as_ref()
+            .ok_or(error.as_deref().unwrap_or_default()),
+    );
 
     let log = ExecutionLog {
         timestamp: ctx.timestamp.to_rfc3339(),
         workflow_id: ctx.workflow_id,
+        execution_id: ctx.execution_id,
         step_id: ctx.step_id,
         step_index: ctx.step_index,
+        retry_attempt: ctx.retry_attempt,
         tool_name: ctx.tool_name.clone(),
         request: ctx.request,
         response: ExecutionResponse {
@@ -296,10 +361,12 @@ pub fn log_response(ctx: ExecutionContext, result: Result<&Value, &str>, duratio
             .to_string(),
             duration_ms,
             result: clean_result,
-            error: result.err().map(String::from),
+            error,
         },
         screenshots,
         logs: None,
+        policy: ctx.policy,
+        client: ctx.client,
     };
 
     // Write JSON
@@ -313,6 +380,7 @@ pub fn log_response(ctx: ExecutionContext, result: Result<&Value, &str>, duratio
                 );
             } else {
                 info!("[execution_logger] Logged: {}", json_path.display());
+                crate::execution_history::record_step(&log, &json_path);
             }
         }
         Err(e) => {
@@ -339,10 +407,10 @@ pub fn log_response(ctx: ExecutionContext, result: Result<&Value, &str>, duratio
 /// Complete logging an execution with captured logs (call after tool dispatch)
 /// Same as log_response but includes captured console logs
 pub fn log_response_with_logs(
-    ctx: ExecutionContext,
+    mut ctx: ExecutionContext,
     result: Result<&Value, &str>,
     duration_ms: u64,
-    logs: Option<Vec<CapturedLogEntry>>,
+    mut logs: Option<Vec<CapturedLogEntry>>,
 ) {
     info!(
         "[execution_logger] log_response_with_logs called for tool: {}, workflow_id: {:?}, enabled: {}, logs: {:?}",
@@ -372,24 +440,43 @@ pub fn log_response_with_logs(
 
     let json_path = dir.join(format!("{}.json", ctx.file_prefix));
 
+    // Build response, stripping screenshot base64 from result, and redact
+    // everything before it is written. Redacting first also notes password
+    // fields so the screenshots below hide them.
+    let redactor = terminator::redaction::global();
+    let mut clean_result = result.ok().map(strip_screenshot_base64);
+    let mut error = result.err().map(String::from);
+    redact_call(
+        &redactor,
+        &mut ctx.request,
+        clean_result.as_mut(),
+        error.as_mut(),
+        logs.as_mut(),
+    );
+
     // Extract screenshots from result and save them
     let screenshots = if let Ok(result_value) = result {
-        extract_and_save_screenshots(&dir, &ctx.file_prefix, result_value)
+        extract_and_save_screenshots(&dir, &ctx.file_prefix, result_value, &redactor)
     } else {
         None
     };
 
-    // Build response, stripping screenshot base64 from result
-    let clean_result = result.ok().map(strip_screenshot_base64);
-
     // Generate TypeScript snippet before moving ctx.request
-    let ts_snippet = generate_typescript_snippet(&ctx.tool_name, &ctx.request, result);
+    let ts_snippet = generate_typescript_snippet(
+        &ctx.tool_name,
+        &ctx.request,
+        clean_result
+            .as_ref()
+            .ok_or(error.as_deref().unwrap_or_default()),
+    );
 
     let log = ExecutionLog {
         timestamp: ctx.timestamp.to_rfc3339(),
         workflow_id: ctx.workflow_id,
+        execution_id: ctx.execution_id,
         step_id: ctx.step_id,
         step_index: ctx.step_index,
+        retry_attempt: ctx.retry_attempt,
         tool_name: ctx.tool_name.clone(),
         request: ctx.request,
         response: ExecutionResponse {
@@ -401,10 +488,12 @@ pub fn log_response_with_logs(
             .to_string(),
             duration_ms,
             result: clean_result,
-            error: result.err().map(String::from),
+            error,
         },
         screenshots,
         logs,
+        policy: ctx.policy,
+        client: ctx.client,
     };
 
     // Write JSON
@@ -421,6 +510,7 @@ pub fn log_response_with_logs(
                     "[execution_logger] Logged with logs: {}",
                     json_path.display()
                 );
+                crate::execution_history::record_step(&log, &json_path);
             }
         }
         Err(e) => {
@@ -450,12 +540,17 @@ fn extract_and_save_screenshots(
     dir: &std::path::Path,
     file_prefix: &str,
     result: &Value,
+    redactor: &Redactor,
 ) -> Option<ScreenshotRefs> {
     let mut refs = ScreenshotRefs {
         before: None,
         after: Vec::new(),
     };
     let mut screenshot_counter = 0usize;
+    let origin = screenshot_origin(result);
+    let save_screenshot = |dir: &std::path::Path, filename: &str, data: &str| {
+        save_screenshot(dir, filename, data, redactor, origin)
+    };
 
     // Look for screenshot in various locations in the result
     // Common patterns: result.screenshot, result.screenshot_before, result.screenshot_after
@@ -568,10 +663,17 @@ fn extract_base64_image(value: &Value, field_names: &[&str]) -> Option<String> {
     None
 }
 
-/// Save base64 screenshot as PNG file
-fn save_screenshot(dir: &std::path::Path, filename: &str, base64_data: &str) -> bool {
+/// Save base64 screenshot as PNG file, pixelating password fields
+fn save_screenshot(
+    dir: &std::path::Path,
+    filename: &str,
+    base64_data: &str,
+    redactor: &Redactor,
+    origin: Option<ImageOrigin>,
+) -> bool {
     match BASE64.decode(base64_data.trim()) {
         Ok(bytes) => {
+            let bytes = redactor.redact_png(&bytes, origin).unwrap_or(bytes);
             let path = dir.join(filename);
             match fs::write(&path, bytes) {
                 Ok(_) => {
@@ -597,6 +699,89 @@ fn save_screenshot(dir: &std::path::Path, filename: &str, base64_data: &str) ->
     }
 }
 
+/// Redact a tool call in place before it is logged. Text arguments are
+/// masked as well when the call touched a password field or an excluded
+/// application.
+fn redact_call(
+    redactor: &Redactor,
+    request: &mut Value,
+    result: Option<&mut Value>,
+    error: Option<&mut String>,
+    logs: Option<&mut Vec<CapturedLogEntry>>,
+) -> JsonRedaction {
+    let mut summary = result
+        .map(|result| redactor.redact_json(result))
+        .unwrap_or_default();
+    let request_summary = redactor.redact_json(request);
+    summary.masked += request_summary.masked;
+    summary.password_fields += request_summary.password_fields;
+    summary.excluded_application |= request_summary.excluded_application
+        || ["process", "app_name"].iter().any(|key| {
+            request
+                .get(*key)
+                .and_then(Value::as_str)
+                .is_some_and(|app| redactor.is_excluded_application(app))
+        });
+
+    if summary.is_sensitive() {
+        if let Some(args) = request.as_object_mut() {
+            for key in TEXT_ARGUMENTS {
+                if let Some(value) = args.get_mut(*key) {
+                    if value
+                        .as_str()
+                        .is_some_and(|text| !text.is_empty() && text != REDACTED)
+                    {
+                        *value = Value::String(REDACTED.to_string());
+                        summary.masked += 1;
+                    }
+                }
+            }
+        }
+    }
+    if let Some(error) = error {
+        if redactor.redact_string(error) {
+            summary.masked += 1;
+        }
+    }
+    for entry in logs.into_iter().flatten() {
+        if redactor.redact_string(&mut entry.message) {
+            summary.masked += 1;
+        }
+    }
+    summary
+}
+
+/// Where a tool's screenshot sits on screen, from the `target` element and
+/// `final_size` that `capture_screenshot` reports, so password fields can be
+/// placed in it
+fn screenshot_origin(result: &Value) -> Option<ImageOrigin> {
+    let from = |value: &Value| -> Option<ImageOrigin> {
+        let bounds = value.get("target")?.get("bounds")?;
+        let x = bounds.get("x")?.as_f64()?;
+        let y = bounds.get("y")?.as_f64()?;
+        let width = bounds.get("width")?.as_f64()?;
+        let final_width = value
+            .get("final_size")
+            .and_then(|size| size.get("width"))
+            .and_then(Value::as_f64)
+            .unwrap_or(width);
+        (width > 0.0).then(|| ImageOrigin {
+            x,
+            y,
+            scale: final_width / width,
+        })
+    };
+    from(result).or_else(|| {
+        result
+            .as_array()
+            .or_else(|| result.get("content").and_then(|c| c.as_array()))?
+            .iter()
+            .filter_map(|item| item.get("text").and_then(|t| t.as_str()))
+            .filter_map(|text| serde_json::from_str::<Value>(text).ok())
+            .find_map(|parsed| from(&parsed))
+    })
+}
+
 /// Strip screenshot base64 data from result to keep JSON small
 fn strip_screenshot_base64(value: &Value) -> Value {
     let mut result = value.clone();
@@ -2343,8 +2528,13 @@ fn generate_gemini_computer_use_snippet(args: &Value) -> String {
     )
 }
 
-/// Clean up execution logs older than RETENTION_DAYS in a single directory
-fn cleanup_directory(dir: &std::path::Path, cutoff_prefix: &str) -> (usize, usize) {
+/// Clean up execution logs older than the cutoff prefix (YYYYMMDD) in a single
+/// directory, leaving the files in `keep` alone
+fn cleanup_directory(
+    dir: &std::path::Path,
+    cutoff_prefix: &str,
+    keep: &HashSet<PathBuf>,
+) -> (usize, usize) {
     let mut deleted_count = 0;
     let mut error_count = 0;
 
@@ -2358,7 +2548,7 @@ fn cleanup_directory(dir: &std::path::Path, cutoff_prefix: &str) -> (usize, usiz
                 let filename = entry.file_name().to_string_lossy().to_string();
 
                 // Extract date prefix (first 8 chars: YYYYMMDD)
-                if filename.len() >= 8 {
+                if filename.len() >= 8 && !keep.contains(&entry.path()) {
                     let file_date_prefix = &filename[..8];
 
                     // Compare lexicographically (works for YYYYMMDD format)
@@ -2388,50 +2578,123 @@ fn cleanup_directory(dir: &std::path::Path, cutoff_prefix: &str) -> (usize, usiz
     (deleted_count, error_count)
 }
 
-/// Clean up execution logs older than RETENTION_DAYS
-async fn cleanup_old_executions() {
-    let cutoff_date = Local::now().date_naive() - chrono::Duration::days(RETENTION_DAYS);
-    let cutoff_prefix = cutoff_date.format("%Y%m%d").to_string();
+/// Date prefix (YYYYMMDD) of files older than `days`
+fn cutoff_prefix(days: u32) -> String {
+    (Local::now().date_naive() - chrono::Duration::days(i64::from(days)))
+        .format("%Y%m%d")
+        .to_string()
+}
+
+/// Clean up execution logs and history older than their workflow's retention.
+/// Returns the number of deleted files and pruned history runs.
+pub fn cleanup_old_executions() -> (usize, usize) {
+    let policy = crate::execution_history::RetentionPolicy::load();
 
     debug!(
-        "[execution_logger] Cleaning up files older than {} (prefix < {})",
-        cutoff_date, cutoff_prefix
+        "[execution_logger] Cleaning up files older than {} days ({} per-workflow overrides)",
+        policy.default_days,
+        policy.workflows.len()
     );
 
-    let mut total_deleted = 0;
-    let mut total_errors = 0;
-
-    // 1. Clean standalone executions (mediar/executions/)
-    let standalone_dir = get_executions_dir();
-    let (deleted, errors) = cleanup_directory(&standalone_dir, &cutoff_prefix);
-    total_deleted += deleted;
-    total_errors += errors;
-
-    // 2. Clean workflow executions (mediar/workflows/*/executions/)
+    // Standalone executions (mediar/executions/) and workflow executions
+    // (mediar/workflows/*/executions/)
+    let mut dirs = vec![(get_executions_dir(), policy.default_days)];
     let workflows_dir = dirs::data_local_dir()
         .unwrap_or_else(std::env::temp_dir)
         .join("mediar")
         .join("workflows");
+    if let Ok(entries) = fs::read_dir(&workflows_dir) {
+        for entry in entries.flatten() {
+            if entry.path().is_dir() {
+                let workflow_id = entry.file_name().to_string_lossy().to_string();
+                dirs.push((
+                    entry.path().join("executions"),
+                    policy.days_for(Some(&workflow_id)),
+                ));
+            }
+        }
+    }
 
-    if workflows_dir.exists() {
-        if let Ok(entries) = fs::read_dir(&workflows_dir) {
-            for entry in entries.flatten() {
-                if entry.path().is_dir() {
-                    let executions_dir = entry.path().join("executions");
-                    let (deleted, errors) = cleanup_directory(&executions_dir, &cutoff_prefix);
-                    total_deleted += deleted;
-                    total_errors += errors;
+    apply_retention(
+        &policy,
+        &crate::execution_history::History::open_default(),
+        &dirs,
+        Utc::now(),
+    )
+}
+
+/// A run's files plus its HTML report at the default location
+fn run_files(run: &crate::execution_history::RunRecord) -> Vec<PathBuf> {
+    let mut files = run.files();
+    files.push(crate::run_report::default_report_path(run));
+    files
+}
+
+/// Retention is driven by the history index so runs and their files expire
+/// together: each expired run is dropped from the index along with its logs
+/// and screenshots, whatever directory they are in. Files no run refers to
+/// (standalone calls, logs older than the index) expire by the date in their
+/// name, using the days given for their directory.
+fn apply_retention(
+    policy: &crate::execution_history::RetentionPolicy,
+    history: &crate::execution_history::History,
+    dirs: &[(PathBuf, u32)],
+    now: DateTime<Utc>,
+) -> (usize, usize) {
+    let mut total_deleted = 0;
+    let mut total_errors = 0;
+
+    // 1. Expired runs, one at a time
+    let expired = history.prune(policy, now).unwrap_or_else(|e| {
+        warn!("[execution_logger] Failed to prune history: {:#}", e);
+        Vec::new()
+    });
+    for run in &expired {
+        for path in run_files(run) {
+            match fs::remove_file(&path) {
+                Ok(()) => total_deleted += 1,
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+                Err(e) => {
+                    total_errors += 1;
+                    warn!(
+                        "[execution_logger] Failed to delete {}: {}",
+                        path.display(),
+                        e
+                    );
                 }
             }
         }
     }
 
+    // 2. Files of runs still in the index are kept for as long as the run is
+    let keep: HashSet<PathBuf> = match history.runs() {
+        Ok(runs) => runs.iter().flat_map(run_files).collect(),
+        Err(e) => {
+            // Without the index, deleting by date could remove files of kept runs
+            warn!("[execution_logger] Skipping log cleanup: {:#}", e);
+            return (total_deleted, expired.len());
+        }
+    };
+    for (dir, days) in dirs {
+        let (deleted, errors) = cleanup_directory(dir, &cutoff_prefix(*days), &keep);
+        total_deleted += deleted;
+        total_errors += errors;
+    }
+
     if total_deleted > 0 || total_errors > 0 {
         info!(
             "[execution_logger] Cleanup complete: deleted {} files, {} errors",
             total_deleted, total_errors
         );
     }
+    if !expired.is_empty() {
+        debug!(
+            "[execution_logger] Pruned {} runs from history",
+            expired.len()
+        );
+    }
+
+    (total_deleted, expired.len())
 }
 
 #[cfg(test)]
@@ -2796,4 +3059,158 @@ mod tests {
             generate_typescript_snippet("some_unknown_tool", &unsupported, ok_result)
         );
     }
+
+    fn redactor(yaml: &str) -> Redactor {
+        Redactor::new(terminator::redaction::RedactionConfig::parse(yaml).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn test_redact_call_masks_typed_password() {
+        let redactor = redactor("patterns: [email]\nexcluded_applications: [KeePass.exe]");
+        let mut request = json!({
+            "selector": "role:Edit && name:Password",
+            "text_to_type": "hunter2",
+        });
+        let mut result = json!({
+            "action": "type_into_element",
+            "element": {"role": "Edit", "name": "Password", "is_password": true, "text": "hunter2",
+                        "bounds": {"x": 10.0, "y": 20.0, "width": 200.0, "height": 24.0}},
+        });
+        let mut error = Some("sent to jane@example.com".to_string());
+        let mut logs = vec![CapturedLogEntry {
+            timestamp: Utc::now(),
+            level: "INFO".to_string(),
+            message: "notify jane@example.com".to_string(),
+        }];
+
+        let summary = redact_call(
+            &redactor,
+            &mut request,
+            Some(&mut result),
+            error.as_mut(),
+            Some(&mut logs),
+        );
+        assert!(summary.is_sensitive());
+        assert_eq!(request["text_to_type"], REDACTED);
+        assert_eq!(request["selector"], "role:Edit && name:Password");
+        assert_eq!(result["element"]["text"], REDACTED);
+        assert!(!error.unwrap().contains("jane@example.com"));
+        assert!(!logs[0].message.contains("jane@example.com"));
+
+        // Ordinary fields keep their text; excluded applications do not
+        let mut request = json!({"text_to_type": "hello"});
+        let mut result = json!({"element": {"role": "Edit", "name": "Search"}});
+        assert!(
+            !redact_call(&redactor, &mut request, Some(&mut result), None, None).is_sensitive()
+        );
+        assert_eq!(request["text_to_type"], "hello");
+
+        let mut request = json!({"process": "keepass", "text_to_type": "hello"});
+        assert!(redact_call(&redactor, &mut request, None, None, None).is_sensitive());
+        assert_eq!(request["text_to_type"], REDACTED);
+    }
+
+    #[test]
+    fn test_screenshot_origin() {
+        let result = json!({
+            "target": {"bounds": {"x": 100.0, "y": 50.0, "width": 800.0, "height": 600.0}},
+            "final_size": {"width": 400, "height": 300},
+        });
+        let origin = screenshot_origin(&result).unwrap();
+        assert_eq!((origin.x, origin.y, origin.scale), (100.0, 50.0, 0.5));
+
+        // MCP results wrap the tool's JSON in text content
+        let wrapped = json!({"content": [{"type": "text", "text": result.to_string()}]});
+        assert_eq!(screenshot_origin(&wrapped).unwrap().scale, 0.5);
+
+        assert!(screenshot_origin(&json!({"action": "click"})).is_none());
+    }
+
+    #[test]
+    fn test_retention_follows_history() {
+        use crate::execution_history::{History, RetentionPolicy};
+
+        let root = tempfile::tempdir().unwrap();
+        let standalone = root.path().join("executions");
+        let long_dir = root
+            .path()
+            .join("workflows")
+            .join("invoice")
+            .join("executions");
+        let short_dir = root
+            .path()
+            .join("workflows")
+            .join("sync")
+            .join("executions");
+        for dir in [&standalone, &long_dir, &short_dir] {
+            fs::create_dir_all(dir).unwrap();
+        }
+        let touch = |path: &PathBuf| fs::write(path, "{}").unwrap();
+
+        // invoice keeps 30 days: its 10-day-old run keeps even files whose name
+        // is older than any directory cutoff
+        let invoice_log = long_dir.join("20000101_120000_invoice_open_click_element.json");
+        let invoice_shot = long_dir.join("20000101_120000_invoice_open_click_element_after.png");
+        // sync keeps 2 days: its 5-day-old run goes, wherever its files are
+        let sync_log = standalone.join("29991231_120000_standalone_full_execute_sequence.json");
+        let sync_step = short_dir.join("29991231_120000_sync_login_click_element.json");
+        // Files no run refers to expire by the date in their name
+        let orphan_old = standalone.join("20000101_120000_standalone_full_get_window_tree.json");
+        let orphan_new = standalone.join("29991231_120000_standalone_full_get_window_tree.json");
+        for path in [
+            &invoice_log,
+            &invoice_shot,
+            &sync_log,
+            &sync_step,
+            &sync_step.with_extension("ts"),
+            &orphan_old,
+            &orphan_new,
+        ] {
+            touch(path);
+        }
+
+        let now = Utc::now();
+        let step = |log: &PathBuf, shots: Vec<String>| {
+            json!({"tool_name": "click_element", "started_at": now.to_rfc3339(), "duration_ms": 1,
+                   "success": true, "log_path": log.display().to_string(), "screenshots": shots})
+        };
+        let events = [
+            json!({"event": "run_started", "run_id": "inv", "workflow_id": "invoice",
+                   "started_at": (now - chrono::Duration::days(10)).to_rfc3339()}),
+            json!({"event": "step", "run_id": "inv",
+                   "step": step(&invoice_log, vec![invoice_shot.display().to_string()])}),
+            json!({"event": "run_started", "run_id": "syn", "workflow_id": "sync",
+                   "started_at": (now - chrono::Duration::days(5)).to_rfc3339()}),
+            json!({"event": "step", "run_id": "syn", "step": step(&sync_step, vec![])}),
+            json!({"event": "run_log", "run_id": "syn", "log_path": sync_log.display().to_string()}),
+        ];
+        let history = History::new(standalone.join("history.jsonl"));
+        let lines: Vec<String> = events.iter().map(|e| e.to_string()).collect();
+        fs::write(history.path(), lines.join("\n") + "\n").unwrap();
+
+        let policy =
+            RetentionPolicy::parse("default_days: 7\nworkflows:\n  invoice: 30\n  sync: 2\n")
+                .unwrap();
+        let dirs = [
+            (standalone.clone(), policy.default_days),
+            (long_dir.clone(), 30),
+            (short_dir.clone(), 2),
+        ];
+        let (deleted, pruned) = apply_retention(&policy, &history, &dirs, now);
+        assert_eq!(pruned, 1);
+        assert_eq!(deleted, 4);
+
+        assert!(invoice_log.exists() && invoice_shot.exists());
+        assert!(!sync_log.exists() && !sync_step.exists());
+        assert!(!sync_step.with_extension("ts").exists());
+        assert!(!orphan_old.exists() && orphan_new.exists());
+        assert!(history.path().exists());
+        let runs: Vec<_> = history
+            .runs()
+            .unwrap()
+            .into_iter()
+            .map(|r| r.run_id)
+            .collect();
+        assert_eq!(runs, ["inv"]);
+    }
 }
@@ -0,0 +1,433 @@
+//! Execution queue for `execute_sequence`
+//!
+//! The scheduler, webhooks, the CLI and IDE clients can all start workflows on
+//! the same agent. Every `execute_sequence` call goes through this queue before
+//! it runs:
+//!
+//! - UI workflows (the default) take an exclusive desktop lease, so only one of
+//!   them drives the mouse, keyboard and focus at a time.
+//! - Workflows declared with `ui: false` don't need the desktop and run
+//!   alongside anything else.
+//! - Runs sharing a `concurrency_key` never overlap, UI or not.
+//!
+//! Waiting runs start in priority order (higher first, FIFO within a
+//! priority). An earlier waiter keeps its claim on the desktop or its key, so a
+//! stream of small runs can't starve a queued UI workflow. Runs are identified
+//! by their `RequestManager` request id; cancelling that request (or dropping
+//! the waiting future) takes the run out of the queue.
+//!
+//! `execute_sequence` steps inside a running workflow don't queue again: they
+//! run under their parent's lease (see [`in_queued_run`]).
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::collections::HashSet;
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+use tokio::sync::Notify;
+
+tokio::task_local! {
+    static QUEUED_RUN: ();
+}
+
+/// Whether the current task is running a workflow that already went through
+/// the queue
+pub fn in_queued_run() -> bool {
+    QUEUED_RUN.try_with(|_| ()).is_ok()
+}
+
+/// Run `future` as a queued workflow, so nested runs see [`in_queued_run`]
+pub async fn scope<F: Future>(future: F) -> F::Output {
+    QUEUED_RUN.scope((), future).await
+}
+
+/// A run asking to start
+#[derive(Debug, Clone)]
+pub struct QueueRequest {
+    /// `RequestManager` request id of the run
+    pub id: String,
+    /// Workflow URL or id, for display
+    pub workflow: Option<String>,
+    pub priority: i32,
+    pub concurrency_key: Option<String>,
+    /// Whether the run needs the exclusive desktop lease
+    pub ui: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum EntryState {
+    Queued,
+    Running,
+}
+
+/// A queued or running run, as listed by `list_execution_queue`
+#[derive(Debug, Clone, Serialize)]
+pub struct QueueEntry {
+    pub request_id: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub workflow: Option<String>,
+    pub priority: i32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub concurrency_key: Option<String>,
+    pub ui: bool,
+    pub state: EntryState,
+    /// 1-based place in the queue (queued runs only)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub position: Option<usize>,
+    pub queued_at: DateTime<Utc>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub started_at: Option<DateTime<Utc>>,
+}
+
+impl QueueEntry {
+    fn new(request: QueueRequest) -> Self {
+        Self {
+            request_id: request.id,
+            workflow: request.workflow,
+            priority: request.priority,
+            concurrency_key: request.concurrency_key,
+            ui: request.ui,
+            state: EntryState::Queued,
+            position: None,
+            queued_at: Utc::now(),
+            started_at: None,
+        }
+    }
+}
+
+#[derive(Default)]
+struct QueueState {
+    /// In start order: priority, then arrival (or where `reorder` put them)
+    waiting: Vec<QueueEntry>,
+    running: Vec<QueueEntry>,
+}
+
+impl QueueState {
+    fn insert_waiting(&mut self, entry: QueueEntry) {
+        let index = self
+            .waiting
+            .iter()
+            .position(|e| e.priority < entry.priority)
+            .unwrap_or(self.waiting.len());
+        self.waiting.insert(index, entry);
+    }
+
+    /// Start `id` if it can run now, or return its 1-based queue position
+    fn try_start(&mut self, id: &str) -> Result<(), usize> {
+        let mut desktop_taken = self.running.iter().any(|e| e.ui);
+        let mut keys_taken: HashSet<String> = self
+            .running
+            .iter()
+            .filter_map(|e| e.concurrency_key.clone())
+            .collect();
+
+        for (index, entry) in self.waiting.iter().enumerate() {
+            let free = !(entry.ui && desktop_taken)
+                && entry
+                    .concurrency_key
+                    .as_ref()
+                    .is_none_or(|k| !keys_taken.contains(k));
+            if entry.request_id == id {
+                if !free {
+                    return Err(index + 1);
+                }
+                let mut entry = self.waiting.remove(index);
+                entry.state = EntryState::Running;
+                entry.started_at = Some(Utc::now());
+                self.running.push(entry);
+                return Ok(());
+            }
+            // Earlier waiters keep their claim, even before their task wakes up
+            desktop_taken |= entry.ui;
+            if let Some(key) = &entry.concurrency_key {
+                keys_taken.insert(key.clone());
+            }
+        }
+        // Not waiting (already removed); report the back of the queue
+        Err(self.waiting.len() + 1)
+    }
+
+    fn remove(&mut self, id: &str) {
+        self.waiting.retain(|e| e.request_id != id);
+        self.running.retain(|e| e.request_id != id);
+    }
+}
+
+#[derive(Default)]
+pub struct ExecutionQueue {
+    state: Mutex<QueueState>,
+    changed: Notify,
+}
+
+/// Held for as long as a run is queued or running. Dropping it frees the
+/// desktop lease and concurrency key, or takes the run out of the queue.
+pub struct QueueLease {
+    queue: Arc<ExecutionQueue>,
+    id: String,
+    ui: bool,
+}
+
+impl QueueLease {
+    /// Whether this run holds the exclusive desktop lease
+    pub fn is_ui(&self) -> bool {
+        self.ui
+    }
+}
+
+impl Drop for QueueLease {
+    fn drop(&mut self) {
+        self.queue.state.lock().unwrap().remove(&self.id);
+        self.queue.changed.notify_waiters();
+    }
+}
+
+impl ExecutionQueue {
+    pub fn new() -> Arc<Self> {
+        Arc::new(Self::default())
+    }
+
+    /// Wait until the run may start. `on_wait(position, queued)` is called
+    /// whenever the run's place in the queue changes (not at all if it starts
+    /// right away). Dropping the returned future takes the run out of the queue.
+    pub async fn acquire<F, Fut>(
+        self: &Arc<Self>,
+        request: QueueRequest,
+        mut on_wait: F,
+    ) -> QueueLease
+    where
+        F: FnMut(usize, usize) -> Fut,
+        Fut: Future<Output = ()>,
+    {
+        let lease = QueueLease {
+            queue: self.clone(),
+            id: request.id.clone(),
+            ui: request.ui,
+        };
+        self.state
+            .lock()
+            .unwrap()
+            .insert_waiting(QueueEntry::new(request));
+
+        let mut reported = None;
+        loop {
+            let notified = self.changed.notified();
+            tokio::pin!(notified);
+            notified.as_mut().enable();
+
+            let waiting = {
+                let mut state = self.state.lock().unwrap();
+                state
+                    .try_start(&lease.id)
+                    .map_err(|position| (position, state.waiting.len()))
+            };
+            match waiting {
+                Ok(()) => {
+                    // Someone behind us may be able to start alongside
+                    self.changed.notify_waiters();
+                    return lease;
+                }
+                Err(place) => {
+                    if reported != Some(place) {
+                        reported = Some(place);
+                        on_wait(place.0, place.1).await;
+                    }
+                }
+            }
+            notified.await;
+        }
+    }
+
+    /// Running runs first, then queued runs in start order
+    pub fn list(&self) -> Vec<QueueEntry> {
+        let state = self.state.lock().unwrap();
+        let queued = state.waiting.iter().enumerate().map(|(index, entry)| {
+            let mut entry = entry.clone();
+            entry.position = Some(index + 1);
+            entry
+        });
+        state.running.iter().cloned().chain(queued).collect()
+    }
+
+    pub fn is_queued(&self, id: &str) -> bool {
+        let state = self.state.lock().unwrap();
+        state.waiting.iter().any(|e| e.request_id == id)
+    }
+
+    /// Change a queued run's priority (it moves behind runs of equal priority)
+    /// and/or move it to a 1-based `position`. Returns its new position.
+    pub fn reorder(
+        &self,
+        id: &str,
+        position: Option<usize>,
+        priority: Option<i32>,
+    ) -> Result<usize, String> {
+        let new_position = {
+            let mut state = self.state.lock().unwrap();
+            let index = state
+                .waiting
+                .iter()
+                .position(|e| e.request_id == id)
+                .ok_or_else(|| format!("No queued run with request id '{id}'"))?;
+            let mut entry = state.waiting.remove(index);
+            if let Some(priority) = priority {
+                entry.priority = priority;
+            }
+            match position {
+                Some(position) => {
+                    let index = position.saturating_sub(1).min(state.waiting.len());
+                    state.waiting.insert(index, entry);
+                }
+                None if priority.is_some() => state.insert_waiting(entry),
+                None => state.waiting.insert(index, entry),
+            }
+            state
+                .waiting
+                .iter()
+                .position(|e| e.request_id == id)
+                .map(|index| index + 1)
+                .unwrap_or_default()
+        };
+        self.changed.notify_waiters();
+        Ok(new_position)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::FutureExt;
+
+    fn request(id: &str, priority: i32, ui: bool, key: Option<&str>) -> QueueRequest {
+        QueueRequest {
+            id: id.to_string(),
+            workflow: None,
+            priority,
+            concurrency_key: key.map(str::to_string),
+            ui,
+        }
+    }
+
+    fn ids(queue: &ExecutionQueue, state: EntryState) -> Vec<String> {
+        queue
+            .list()
+            .into_iter()
+            .filter(|e| e.state == state)
+            .map(|e| e.request_id)
+            .collect()
+    }
+
+    async fn settle() {
+        for _ in 0..5 {
+            tokio::task::yield_now().await;
+        }
+    }
+
+    #[tokio::test]
+    async fn test_ui_runs_are_exclusive_and_start_by_priority() {
+        let queue = ExecutionQueue::new();
+        let first = queue
+            .acquire(request("a", 0, true, None), |_, _| async {})
+            .now_or_never()
+            .expect("idle queue starts right away");
+        assert!(first.is_ui());
+
+        let positions = Arc::new(Mutex::new(Vec::new()));
+        let spawn = |id: &'static str, priority: i32, ui: bool| {
+            let queue = queue.clone();
+            let positions = positions.clone();
+            tokio::spawn(async move {
+                let lease = queue
+                    .acquire(request(id, priority, ui, None), |position, _| {
+                        positions.lock().unwrap().push((id, position));
+                        async {}
+                    })
+                    .await;
+                tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+                drop(lease);
+            })
+        };
+        let low = spawn("low", 0, true);
+        settle().await;
+        let high = spawn("high", 5, true);
+        let background = spawn("background", 0, false);
+        settle().await;
+
+        // The non-UI run starts next to "a"; UI runs wait, highest priority first
+        assert_eq!(ids(&queue, EntryState::Running), ["a", "background"]);
+        assert_eq!(ids(&queue, EntryState::Queued), ["high", "low"]);
+        assert!(positions.lock().unwrap().contains(&("low", 2)));
+
+        drop(first);
+        settle().await;
+        let running = ids(&queue, EntryState::Running);
+        assert!(running.contains(&"high".to_string()), "{running:?}");
+        assert_eq!(ids(&queue, EntryState::Queued), ["low"]);
+        for task in [low, high, background] {
+            task.await.unwrap();
+        }
+        assert!(queue.list().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_concurrency_key_serializes_non_ui_runs() {
+        let queue = ExecutionQueue::new();
+        let first = queue
+            .acquire(request("a", 0, false, Some("crm")), |_, _| async {})
+            .now_or_never()
+            .unwrap();
+        assert!(queue
+            .acquire(request("b", 0, false, Some("crm")), |_, _| async {})
+            .now_or_never()
+            .is_none());
+        // The dropped attempt left the queue
+        assert!(!queue.is_queued("b"));
+        assert!(queue
+            .acquire(request("c", 0, false, Some("erp")), |_, _| async {})
+            .now_or_never()
+            .is_some());
+        drop(first);
+        assert!(queue
+            .acquire(request("d", 0, false, Some("crm")), |_, _| async {})
+            .now_or_never()
+            .is_some());
+    }
+
+    #[tokio::test]
+    async fn test_nested_runs_are_marked() {
+        assert!(!in_queued_run());
+        assert!(scope(async { in_queued_run() }).await);
+    }
+
+    #[tokio::test]
+    async fn test_reorder() {
+        let queue = ExecutionQueue::new();
+        let _running = queue
+            .acquire(request("busy", 0, true, None), |_, _| async {})
+            .now_or_never()
+            .unwrap();
+        let mut waiters = Vec::new();
+        for id in ["one", "two", "three"] {
+            let queue = queue.clone();
+            waiters.push(tokio::spawn(async move {
+                let _lease = queue
+                    .acquire(request(id, 0, true, None), |_, _| async {})
+                    .await;
+            }));
+            settle().await;
+        }
+        assert_eq!(ids(&queue, EntryState::Queued), ["one", "two", "three"]);
+
+        assert_eq!(queue.reorder("three", Some(1), None), Ok(1));
+        assert_eq!(ids(&queue, EntryState::Queued), ["three", "one", "two"]);
+        assert_eq!(queue.reorder("two", None, Some(1)), Ok(1));
+        assert_eq!(ids(&queue, EntryState::Queued), ["two", "three", "one"]);
+        assert!(queue.reorder("busy", Some(1), None).is_err());
+
+        for waiter in waiters {
+            waiter.abort();
+        }
+        settle().await;
+        assert!(ids(&queue, EntryState::Queued).is_empty());
+    }
+}
@@ -146,23 +146,18 @@ fn evaluate_contains(collection: &Value, item: &str) -> bool {
 }
 
 // Parses simple expressions like "variable == 'value'" or "variable == true"
+// Splits "lhs op rhs" on the first comparison operator, longest operators first
+// to avoid partial matches.
+fn split_comparison(expr: &str) -> Option<(&str, &'static str, &str)> {
+    const OPERATORS: [&str; 6] = [">=", "<=", "==", "!=", ">", "<"];
+    OPERATORS.iter().find_map(|op| {
+        expr.find(op)
+            .map(|pos| (&expr[..pos], *op, &expr[pos + op.len()..]))
+    })
+}
+
 fn parse_and_evaluate_binary_expression(expr: &str, variables: &Value) -> Option<bool> {
-    // Try to parse comparison operators in order of longest first to avoid partial matches
-    let (var_path, op, raw_rhs) = if let Some(pos) = expr.find(">=") {
-        (&expr[..pos], ">=", &expr[pos + 2..])
-    } else if let Some(pos) = expr.find("<=") {
-        (&expr[..pos], "<=", &expr[pos + 2..])
-    } else if let Some(pos) = expr.find("==") {
-        (&expr[..pos], "==", &expr[pos + 2..])
-    } else if let Some(pos) = expr.find("!=") {
-        (&expr[..pos], "!=", &expr[pos + 2..])
-    } else if let Some(pos) = expr.find('>') {
-        (&expr[..pos], ">", &expr[pos + 1..])
-    } else if let Some(pos) = expr.find('<') {
-        (&expr[..pos], "<", &expr[pos + 1..])
-    } else {
-        return None;
-    };
+    let (var_path, op, raw_rhs) = split_comparison(expr)?;
 
     let var_path = var_path.trim();
     let raw_rhs = raw_rhs.trim();
@@ -348,11 +343,137 @@ fn evaluate_coalesce_to_value(expr: &str, variables: &Value) -> Option<Value> {
     Some(parse_literal_value(default_arg))
 }
 
+/// Checks that an expression is well-formed without evaluating it.
+/// `evaluate` silently treats anything it cannot parse as `false`; this reports why instead.
+pub fn validate(expression: &str) -> Result<(), String> {
+    let normalized = normalize_expression(expression);
+    validate_internal(&normalized)
+}
+
+fn validate_internal(expression: &str) -> Result<(), String> {
+    let expr = expression.trim();
+    if expr.is_empty() {
+        return Err("empty expression".to_string());
+    }
+
+    // Same precedence as evaluate_internal: '!', then '&&', then '||'
+    if let Some(inner_expr) = expr.strip_prefix('!') {
+        return validate_internal(inner_expr);
+    }
+    if let Some(pos) = expr.find("&&") {
+        validate_internal(&expr[..pos])?;
+        return validate_internal(&expr[pos + 2..]);
+    }
+    if let Some(pos) = expr.find("||") {
+        validate_internal(&expr[..pos])?;
+        return validate_internal(&expr[pos + 2..]);
+    }
+
+    if let Some((func_name, args_str)) = expr.split_once('(') {
+        if let Some(args_str) = args_str.strip_suffix(')') {
+            let func_name = func_name.trim();
+            match func_name {
+                "always" if args_str.trim().is_empty() => return Ok(()),
+                "always" => return Err("always() takes no arguments".to_string()),
+                "contains" | "startsWith" | "endsWith" => {
+                    let args: Vec<&str> = args_str.split(',').map(|s| s.trim()).collect();
+                    if args.len() != 2 || args.iter().any(|a| a.is_empty()) {
+                        return Err(format!("{func_name}() expects exactly 2 arguments"));
+                    }
+                    if !is_variable_path(args[0]) {
+                        return Err(format!(
+                            "first argument of {func_name}() must be a variable, got '{}'",
+                            args[0]
+                        ));
+                    }
+                    return Ok(());
+                }
+                _ if split_comparison(expr).is_none() => {
+                    return Err(format!("unknown function '{func_name}'"));
+                }
+                _ => {}
+            }
+        }
+    }
+
+    if let Some((lhs, op, rhs)) = split_comparison(expr) {
+        let (lhs, rhs) = (lhs.trim(), rhs.trim());
+        if lhs.is_empty() || rhs.is_empty() {
+            return Err(format!("missing operand for '{op}'"));
+        }
+        if lhs.contains("coalesce(") {
+            let args_ok = lhs
+                .strip_prefix("coalesce(")
+                .and_then(|rest| rest.strip_suffix(')'))
+                .is_some_and(|args| args.split(',').filter(|a| !a.trim().is_empty()).count() >= 2);
+            if !args_ok {
+                return Err(format!(
+                    "coalesce() expects at least 2 arguments, got '{lhs}'"
+                ));
+            }
+        } else if !is_variable_path(lhs) {
+            return Err(format!("left-hand side '{lhs}' is not a variable path"));
+        }
+        if matches!(op, ">" | "<" | ">=" | "<=") {
+            let literal = rhs.trim_matches(|c| c == '\'' || c == '"');
+            if !matches!(literal, "true" | "false" | "null") && literal.parse::<f64>().is_err() {
+                return Err(format!(
+                    "right-hand side of '{op}' must be a number, got '{rhs}'"
+                ));
+            }
+        }
+        return Ok(());
+    }
+
+    if expr == "true" || expr == "false" || is_variable_path(expr) {
+        return Ok(());
+    }
+    Err(format!("could not parse expression '{expr}'"))
+}
+
+fn is_variable_path(s: &str) -> bool {
+    !s.is_empty()
+        && s.chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '.' | '[' | ']'))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use serde_json::json;
 
+    #[test]
+    fn test_validate_accepts_supported_syntax() {
+        for expr in [
+            "always()",
+            "env.done",
+            "!env.done",
+            "policy.use_max_budget == true",
+            "contains(policy.product_types, 'FEX')",
+            "startsWith(name, 'A') && count >= 2",
+            "coalesce(retries, 0) < 3 || env.force",
+            "login_status != 'success'",
+        ] {
+            assert!(validate(expr).is_ok(), "{expr}: {:?}", validate(expr));
+        }
+    }
+
+    #[test]
+    fn test_validate_rejects_malformed_expressions() {
+        for expr in [
+            "",
+            "count > many",
+            "== 'x'",
+            "matches(name, 'A')",
+            "contains(name)",
+            "always(1)",
+            "name is 'A'",
+            "contains(x, 'a') == true",
+        ] {
+            assert!(validate(expr).is_err(), "{expr} should be rejected");
+        }
+    }
+
     #[test]
     fn test_coalesce_basic() {
         let vars = json!({"x": 5});
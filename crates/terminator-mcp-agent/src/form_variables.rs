@@ -0,0 +1,470 @@
+//! Lifting the data typed during a recording into workflow variables.
+//!
+//! A workflow compiled from a recording types exactly what the user typed.
+//! [`parameterize`] looks at every `TextInputCompleted` and `BrowserTextInput`
+//! event, tells data (a customer name, an amount, an e-mail address) from
+//! navigation (address bars, search and filter boxes, typed URLs), and turns
+//! each data value into a variable: the literal in its `type_into_element`
+//! step becomes a `{{name}}` placeholder, the variable gets a
+//! [`VariableDefinition`] with a label, an inferred type and a validation regex,
+//! and the recorded value goes into example `inputs`.
+
+use crate::utils::{VariableDefinition, VariableType};
+use serde_json::{json, Value};
+use std::collections::{BTreeMap, HashMap};
+use terminator_workflow_recorder::{
+    CompiledWorkflow, SerializableRecordedWorkflow, SerializableWorkflowEvent,
+};
+
+/// Words in a field's label that mark it as navigation rather than data entry
+const NAVIGATION_LABELS: &[&str] = &[
+    "address", "search", "url", "find", "filter", "go to", "location", "command",
+];
+
+/// Processes whose text boxes only launch or find things
+const SHELL_PROCESSES: &[&str] = &[
+    "searchhost",
+    "searchapp",
+    "startmenuexperiencehost",
+    "shellexperiencehost",
+];
+
+/// A compiled workflow whose typed data is read from variables
+#[derive(Debug, Clone)]
+pub struct ParameterizedWorkflow {
+    /// The compiled workflow with `{{name}}` placeholders in place of the typed data
+    pub compiled: CompiledWorkflow,
+    pub variables: BTreeMap<String, VariableDefinition>,
+    /// The values typed during the recording, by variable name. Password
+    /// values were not recorded and are empty.
+    pub inputs: Value,
+}
+
+impl ParameterizedWorkflow {
+    /// Render as an `execute_sequence` YAML workflow with `variables` and `inputs`
+    pub fn to_yaml(&self) -> Result<String, String> {
+        let compiled = self
+            .compiled
+            .to_yaml()
+            .map_err(|e| format!("Failed to render workflow as YAML: {e}"))?;
+        let (header, body) = compiled.split_once('\n').unwrap_or(("", &compiled));
+        let mut workflow: serde_yaml::Value =
+            serde_yaml::from_str(body).map_err(|e| format!("Failed to parse workflow: {e}"))?;
+        let Some(arguments) = workflow
+            .get_mut("arguments")
+            .and_then(|a| a.as_mapping_mut())
+        else {
+            return Err("Compiled workflow has no arguments".to_string());
+        };
+
+        if !self.variables.is_empty() {
+            let variables = serde_yaml::to_value(&self.variables)
+                .map_err(|e| format!("Failed to render variables: {e}"))?;
+            let inputs = serde_yaml::to_value(&self.inputs)
+                .map_err(|e| format!("Failed to render inputs: {e}"))?;
+            let mut with_variables = serde_yaml::Mapping::new();
+            with_variables.insert("variables".into(), without_nulls(variables));
+            with_variables.insert("inputs".into(), inputs);
+            with_variables.extend(std::mem::take(arguments));
+            *arguments = with_variables;
+        }
+
+        let yaml = serde_yaml::to_string(&workflow)
+            .map_err(|e| format!("Failed to render workflow as YAML: {e}"))?;
+        Ok(format!("{header}\n{yaml}"))
+    }
+}
+
+/// Replace the data typed in `recording` with variables in `compiled`. See the
+/// [module docs](self).
+pub fn parameterize(
+    recording: &SerializableRecordedWorkflow,
+    mut compiled: CompiledWorkflow,
+) -> ParameterizedWorkflow {
+    let mut variables = BTreeMap::new();
+    let mut inputs = serde_json::Map::new();
+    let mut typed = HashMap::new();
+    // Fields and steps are both in recording order
+    let mut next_step = 0;
+
+    for field in recording.events.iter().filter_map(|e| data_field(&e.event)) {
+        let Some(offset) = compiled.steps[next_step..].iter().position(|step| {
+            step.tool_name == "type_into_element"
+                && step.arguments["text_to_type"].as_str() == Some(field.value.as_str())
+        }) else {
+            continue;
+        };
+        let step = &mut compiled.steps[next_step + offset];
+        next_step += offset + 1;
+
+        // The same value typed into the same field again is the same datum
+        let key = (field.label.clone(), field.value.clone());
+        if let Some(name) = typed.get(&key).filter(|_| !field.is_password) {
+            step.arguments["text_to_type"] = json!(format!("{{{{{name}}}}}"));
+            continue;
+        }
+        let name = unique_name(&variable_name(&field.label), &variables);
+        step.arguments["text_to_type"] = json!(format!("{{{{{name}}}}}"));
+        let (r#type, regex, example) = if field.is_password {
+            (VariableType::String, None, json!(""))
+        } else {
+            infer_type(&field.value)
+        };
+        let description = if field.is_password {
+            format!("Password typed into '{}'; not recorded", field.label)
+        } else {
+            format!("Typed into '{}'", field.label)
+        };
+        variables.insert(
+            name.clone(),
+            VariableDefinition {
+                r#type,
+                label: Some(field.label),
+                description: Some(description),
+                default: None,
+                regex,
+                options: None,
+                required: None,
+                value_schema: None,
+                properties: None,
+                item_schema: None,
+            },
+        );
+        inputs.insert(name.clone(), example);
+        typed.insert(key, name);
+    }
+
+    ParameterizedWorkflow {
+        compiled,
+        variables,
+        inputs: Value::Object(inputs),
+    }
+}
+
+struct DataField {
+    label: String,
+    value: String,
+    is_password: bool,
+}
+
+/// The field and value of a text input, unless it is navigation
+fn data_field(event: &SerializableWorkflowEvent) -> Option<DataField> {
+    let (label, value, is_password, process) = match event {
+        SerializableWorkflowEvent::TextInputCompleted(t) => {
+            let element = t.metadata.ui_element.as_ref();
+            let label = [
+                t.field_name.as_deref(),
+                element.and_then(|el| el.name.as_deref()),
+            ]
+            .into_iter()
+            .flatten()
+            .map(str::trim)
+            .find(|label| !label.is_empty())
+            .unwrap_or(t.field_type.as_str());
+            let is_password = t.is_password || t.field_type.eq_ignore_ascii_case("PasswordBox");
+            (label, &t.text_value, is_password, t.process_name.as_deref())
+        }
+        SerializableWorkflowEvent::BrowserTextInput(t) => {
+            let dom = t.dom_element.as_ref();
+            let label = [
+                dom.and_then(|d| d.aria_label.as_deref()),
+                dom.and_then(|d| d.id.as_deref()),
+            ]
+            .into_iter()
+            .flatten()
+            .map(str::trim)
+            .find(|label| !label.is_empty())
+            .unwrap_or(t.selector.as_str());
+            let is_password =
+                t.selector.contains("password") || label.to_lowercase().contains("password");
+            (label, &t.text, is_password, None)
+        }
+        _ => return None,
+    };
+
+    let lower = label.to_lowercase();
+    let navigation = NAVIGATION_LABELS.iter().any(|word| lower.contains(word))
+        || process.is_some_and(|p| {
+            let p = p.trim().to_lowercase();
+            SHELL_PROCESSES.contains(&p.strip_suffix(".exe").unwrap_or(&p))
+        })
+        || looks_like_url(value);
+    if navigation || value.trim().is_empty() {
+        return None;
+    }
+    Some(DataField {
+        label: label.to_string(),
+        value: value.clone(),
+        is_password: is_password || lower.contains("password"),
+    })
+}
+
+fn looks_like_url(value: &str) -> bool {
+    let value = value.trim().to_lowercase();
+    ["http://", "https://", "file://", "www."]
+        .iter()
+        .any(|prefix| value.starts_with(prefix))
+}
+
+/// The variable type, validation regex and example input for a typed value
+fn infer_type(value: &str) -> (VariableType, Option<String>, Value) {
+    let trimmed = value.trim();
+    let digits = trimmed.chars().filter(char::is_ascii_digit).count();
+    let all_digits = digits == trimmed.len();
+
+    // Leading zeros are part of codes (zip codes, ids), which are not numbers
+    let leading_zero = trimmed.len() > 1 && trimmed.starts_with('0') && !trimmed.starts_with("0.");
+    if !leading_zero && is_decimal(trimmed) {
+        if let Ok(number) = trimmed.parse::<f64>() {
+            if let Some(number) = serde_json::Number::from_f64(number) {
+                let example = trimmed
+                    .parse::<i64>()
+                    .map(Value::from)
+                    .unwrap_or(Value::Number(number));
+                return (VariableType::Number, None, example);
+            }
+        }
+    }
+
+    let regex = if all_digits {
+        Some(format!(r"^\d{{{digits}}}$"))
+    } else if is_email(trimmed) {
+        Some(r"^[^@\s]+@[^@\s]+\.[^@\s]+$".to_string())
+    } else if is_date(trimmed) {
+        Some(r"^\d{4}-\d{2}-\d{2}$|^\d{1,2}[./-]\d{1,2}[./-]\d{2,4}$".to_string())
+    } else if digits >= 7
+        && trimmed
+            .chars()
+            .all(|c| c.is_ascii_digit() || " +-().".contains(c))
+    {
+        Some(r"^\+?[0-9 ().-]{7,}$".to_string())
+    } else {
+        None
+    };
+    (VariableType::String, regex, json!(value))
+}
+
+fn is_decimal(text: &str) -> bool {
+    let unsigned = text.strip_prefix('-').unwrap_or(text);
+    let (whole, fraction) = unsigned.split_once('.').unwrap_or((unsigned, "0"));
+    !whole.is_empty()
+        && !fraction.is_empty()
+        && whole.chars().all(|c| c.is_ascii_digit())
+        && fraction.chars().all(|c| c.is_ascii_digit())
+}
+
+fn is_email(text: &str) -> bool {
+    let Some((user, domain)) = text.split_once('@') else {
+        return false;
+    };
+    !user.is_empty()
+        && !text.contains(char::is_whitespace)
+        && domain.contains('.')
+        && !domain.starts_with('.')
+        && !domain.ends_with('.')
+}
+
+fn is_date(text: &str) -> bool {
+    let parts: Vec<&str> = text.split(['-', '/', '.']).collect();
+    parts.len() == 3
+        && parts
+            .iter()
+            .all(|p| !p.is_empty() && p.len() <= 4 && p.chars().all(|c| c.is_ascii_digit()))
+        && parts.iter().any(|p| p.len() == 4 || p.len() == 2)
+        && !text.contains(' ')
+}
+
+/// `E-mail address` → `e_mail_address`
+fn variable_name(label: &str) -> String {
+    let mut name = String::new();
+    for c in label.chars() {
+        if c.is_ascii_alphanumeric() {
+            name.push(c.to_ascii_lowercase());
+        } else if !name.is_empty() && !name.ends_with('_') {
+            name.push('_');
+        }
+    }
+    let name = name.trim_end_matches('_');
+    match name.chars().next() {
+        None => "value".to_string(),
+        Some(c) if c.is_ascii_digit() => format!("field_{name}"),
+        Some(_) => name.to_string(),
+    }
+}
+
+fn unique_name(name: &str, taken: &BTreeMap<String, VariableDefinition>) -> String {
+    if !taken.contains_key(name) {
+        return name.to_string();
+    }
+    (2..)
+        .map(|n| format!("{name}_{n}"))
+        .find(|candidate| !taken.contains_key(candidate))
+        .expect("some suffix is free")
+}
+
+/// `VariableDefinition` serializes unset fields as null; leave them out of the YAML
+fn without_nulls(value: serde_yaml::Value) -> serde_yaml::Value {
+    match value {
+        serde_yaml::Value::Mapping(mapping) => mapping
+            .into_iter()
+            .filter(|(_, v)| !v.is_null())
+            .map(|(k, v)| (k, without_nulls(v)))
+            .collect::<serde_yaml::Mapping>()
+            .into(),
+        other => other,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use terminator_workflow_recorder::{compile, CompileOptions};
+
+    fn text_input(timestamp: u64, field: &str, value: &str, process: &str) -> Value {
+        json!({
+            "timestamp": timestamp,
+            "event": {"TextInputCompleted": {
+                "text_value": value,
+                "field_name": field,
+                "field_type": if field == "Password" { "PasswordBox" } else { "Edit" },
+                "input_method": "Typed",
+                "focus_method": "KeyboardNav",
+                "typing_duration_ms": 500,
+                "keystroke_count": value.len(),
+                "process_name": process,
+                "metadata": {
+                    "timestamp": timestamp,
+                    "ui_element": {"role": "Edit", "name": field, "application": process}
+                }
+            }}
+        })
+    }
+
+    fn recording() -> SerializableRecordedWorkflow {
+        serde_json::from_value(json!({
+            "format_version": 2,
+            "name": "New customer",
+            "start_time": 0,
+            "events": [
+                text_input(1_000, "Search", "customers", "contoso.exe"),
+                text_input(5_000, "Customer name", "ACME Corp", "contoso.exe"),
+                text_input(9_000, "E-mail", "billing@acme.example", "contoso.exe"),
+                text_input(13_000, "Zip code", "01234", "contoso.exe"),
+                text_input(17_000, "Credit limit", "2500", "contoso.exe"),
+                text_input(21_000, "Customer name", "ACME Corp", "contoso.exe"),
+                text_input(23_000, "Customer name:", "Globex", "contoso.exe"),
+                text_input(25_000, "Password", "••••••••", "contoso.exe"),
+            ]
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn typed_data_becomes_variables() {
+        let recording = recording();
+        let workflow = parameterize(&recording, compile(&recording, &CompileOptions::default()));
+
+        let typed: Vec<&str> = workflow
+            .compiled
+            .steps
+            .iter()
+            .filter(|s| s.tool_name == "type_into_element")
+            .filter_map(|s| s.arguments["text_to_type"].as_str())
+            .collect();
+        assert_eq!(
+            typed,
+            vec![
+                "customers",
+                "{{customer_name}}",
+                "{{e_mail}}",
+                "{{zip_code}}",
+                "{{credit_limit}}",
+                "{{customer_name}}",
+                "{{customer_name_2}}",
+                "{{password}}",
+            ]
+        );
+        assert_eq!(
+            workflow.inputs,
+            json!({
+                "customer_name": "ACME Corp",
+                "e_mail": "billing@acme.example",
+                "zip_code": "01234",
+                "credit_limit": 2500,
+                "customer_name_2": "Globex",
+                "password": "",
+            })
+        );
+
+        let variable = |name: &str| &workflow.variables[name];
+        assert!(matches!(
+            variable("credit_limit").r#type,
+            VariableType::Number
+        ));
+        assert!(matches!(variable("zip_code").r#type, VariableType::String));
+        assert_eq!(variable("zip_code").regex.as_deref(), Some(r"^\d{5}$"));
+        assert!(variable("e_mail").regex.as_deref().unwrap().contains('@'));
+        assert_eq!(variable("customer_name").regex, None);
+        assert_eq!(
+            variable("customer_name").label.as_deref(),
+            Some("Customer name")
+        );
+        assert!(variable("password")
+            .description
+            .as_deref()
+            .unwrap()
+            .contains("not recorded"));
+
+        let yaml = workflow.to_yaml().unwrap();
+        assert!(yaml.starts_with("# Compiled from recording 'New customer'\n"));
+        let parsed: Value = serde_yaml::from_str(&yaml).unwrap();
+        let arguments = &parsed["arguments"];
+        assert_eq!(arguments["variables"]["zip_code"]["type"], "string");
+        assert!(arguments["variables"]["zip_code"].get("options").is_none());
+        assert_eq!(arguments["inputs"]["credit_limit"], 2500);
+        assert_eq!(
+            arguments["steps"][2]["arguments"]["text_to_type"],
+            "{{customer_name}}"
+        );
+    }
+
+    #[test]
+    fn navigation_is_not_data() {
+        let navigation = [
+            text_input(
+                0,
+                "Address and search bar",
+                "https://contoso.example",
+                "chrome.exe",
+            ),
+            text_input(0, "Search box", "notepad", "SearchHost.exe"),
+            text_input(0, "Name", "www.contoso.example", "chrome.exe"),
+            text_input(0, "Filter", "open orders", "sapgui.exe"),
+        ];
+        for event in navigation {
+            let event: SerializableWorkflowEvent =
+                serde_json::from_value(event["event"].clone()).unwrap();
+            assert!(data_field(&event).is_none(), "{event:?}");
+        }
+    }
+
+    #[test]
+    fn infers_types_from_values() {
+        let regex = |value: &str| infer_type(value).1;
+        assert!(matches!(infer_type("-12.5").0, VariableType::Number));
+        assert_eq!(infer_type("0.5").2, json!(0.5));
+        assert_eq!(
+            regex("2024-03-01").as_deref().map(|r| r.contains("\\d{4}")),
+            Some(true)
+        );
+        assert!(regex("01.03.2024").is_some());
+        assert_eq!(
+            regex("+1 (555) 010-2030"),
+            Some(r"^\+?[0-9 ().-]{7,}$".to_string())
+        );
+        assert_eq!(regex("Main St. 4"), None);
+        assert_eq!(variable_name("  E-mail (work) "), "e_mail_work");
+        assert_eq!(variable_name("2nd line"), "field_2nd_line");
+        assert_eq!(variable_name("!!"), "value");
+    }
+}
@@ -1,5 +1,6 @@
 use crate::expression_eval;
 use crate::mcp_types::TreeOutputFormat;
+use crate::retry_policy::ErrorClass;
 use crate::tree_formatter::{format_tree_as_compact_yaml, format_ui_node_as_compact_yaml};
 use crate::utils::ToolCall;
 use regex::Regex;
@@ -69,6 +70,7 @@ pub fn build_element_info(element: &UIElement) -> Value {
         "is_selected": element.is_selected().unwrap_or(false),
         "is_toggled": element.is_toggled().unwrap_or(false),
         "keyboard_focusable": element.is_keyboard_focusable().unwrap_or(false),
+        "is_password": element.is_password().unwrap_or(false),
     })
 }
 
@@ -80,15 +82,18 @@ pub fn build_element_not_found_error(
     original_error: anyhow::Error,
 ) -> McpError {
     // Check if the underlying error is UIAutomationAPIError
-    if let Some(AutomationError::UIAutomationAPIError {
-        message,
-        com_error,
-        operation,
-        is_retryable,
-    }) = original_error.downcast_ref::<AutomationError>()
+    if let Some(
+        automation_error @ AutomationError::UIAutomationAPIError {
+            message,
+            com_error,
+            operation,
+            is_retryable,
+        },
+    ) = original_error.downcast_ref::<AutomationError>()
     {
         let error_details = json!({
             "error_type": "ui_automation_api_failure",
+            "error_class": ErrorClass::from_automation_error(automation_error),
             "message": format!("Windows UI Automation API failure: {}", message),
             "com_error": com_error,
             "operation": operation,
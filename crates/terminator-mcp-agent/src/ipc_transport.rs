@@ -0,0 +1,698 @@
+//! Local IPC transport shared by the event and log pipes
+//!
+//! TypeScript workflows stream newline-delimited JSON back to the agent over a
+//! local endpoint whose address is handed to the child process in an
+//! environment variable (`MCP_EVENT_PIPE`, `MCP_LOG_PIPE`). The endpoint is:
+//!
+//! - a named pipe (`\\.\pipe\<name>`) on Windows
+//! - a Unix domain socket (`<tmp>/terminator-<uid>/<name>.sock`) on other
+//!   platforms, in a directory only the current user can enter
+//! - a loopback TCP socket (`tcp://127.0.0.1:<port>?token=<token>`) when neither
+//!   is available. Any local user can connect to it, so the client must send the
+//!   random token of the endpoint as its first line; other clients are dropped.
+//!
+//! Each server accepts a single client and forwards every line its parser
+//! accepts, so the framing (`try_parse_event` / `try_parse_log`) is the same
+//! whatever the transport.
+
+use std::fmt::Debug;
+use std::io;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncWrite, BufReader, ReadBuf};
+use tokio::sync::mpsc;
+use tracing::{debug, error, info, warn};
+
+/// Prefix used for loopback TCP endpoints
+pub const TCP_ENDPOINT_PREFIX: &str = "tcp://";
+
+/// How long a TCP client has to send the endpoint token after connecting
+const TOKEN_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(5);
+
+/// Kind of transport backing an [`IpcListener`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TransportKind {
+    NamedPipe,
+    UnixSocket,
+    Tcp,
+}
+
+/// A bound, not yet connected, IPC endpoint
+pub struct IpcListener {
+    endpoint: String,
+    inner: ListenerInner,
+}
+
+enum ListenerInner {
+    /// `None` once the pipe instance has been handed to its client
+    #[cfg(windows)]
+    NamedPipe(Option<tokio::net::windows::named_pipe::NamedPipeServer>),
+    #[cfg(unix)]
+    Unix {
+        listener: tokio::net::UnixListener,
+        _socket_file: SocketFile,
+    },
+    Tcp {
+        listener: tokio::net::TcpListener,
+        /// First line a client must send
+        token: String,
+    },
+}
+
+/// Removes the socket file once the listener is gone. The accepted connection
+/// stays usable after the path is unlinked. The file is only removed while it
+/// is still the one this listener created, never a socket bound by someone else
+/// at the same path since.
+#[cfg(unix)]
+struct SocketFile {
+    path: std::path::PathBuf,
+    id: Option<(u64, u64)>,
+}
+
+#[cfg(unix)]
+impl SocketFile {
+    fn new(path: std::path::PathBuf) -> Self {
+        let id = file_id(&path);
+        Self { path, id }
+    }
+}
+
+#[cfg(unix)]
+impl Drop for SocketFile {
+    fn drop(&mut self) {
+        if self.id.is_some() && file_id(&self.path) == self.id {
+            let _ = std::fs::remove_file(&self.path);
+        }
+    }
+}
+
+/// Device and inode of `path`, identifying the socket file bound there
+#[cfg(unix)]
+fn file_id(path: &std::path::Path) -> Option<(u64, u64)> {
+    use std::os::unix::fs::MetadataExt;
+    std::fs::symlink_metadata(path)
+        .ok()
+        .map(|meta| (meta.dev(), meta.ino()))
+}
+
+/// Directory for the Unix sockets of the current user, with mode 0700. An
+/// existing directory is only used when it is a real directory owned by the
+/// user, so another user can't pre-create it or swap in a symlink.
+#[cfg(unix)]
+fn socket_dir() -> io::Result<std::path::PathBuf> {
+    use std::os::unix::fs::{DirBuilderExt, MetadataExt, PermissionsExt};
+
+    // SAFETY: getuid has no preconditions and cannot fail
+    let uid = unsafe { libc::getuid() };
+    let dir = std::env::temp_dir().join(format!("terminator-{uid}"));
+    match std::fs::DirBuilder::new().mode(0o700).create(&dir) {
+        Err(e) if e.kind() != io::ErrorKind::AlreadyExists => return Err(e),
+        _ => {}
+    }
+    let meta = std::fs::symlink_metadata(&dir)?;
+    if !meta.is_dir() || meta.uid() != uid {
+        return Err(io::Error::new(
+            io::ErrorKind::PermissionDenied,
+            format!("{} is not a directory of the current user", dir.display()),
+        ));
+    }
+    if meta.permissions().mode() & 0o077 != 0 {
+        std::fs::set_permissions(&dir, std::fs::Permissions::from_mode(0o700))?;
+    }
+    Ok(dir)
+}
+
+/// Bind a Unix socket at `path`. A socket file left behind by a crashed run
+/// makes bind fail with `AddrInUse`; it is only removed once a connect attempt
+/// is refused, so a socket another run is still listening on is never taken
+/// over.
+#[cfg(unix)]
+fn bind_unix(path: &std::path::Path) -> io::Result<tokio::net::UnixListener> {
+    match tokio::net::UnixListener::bind(path) {
+        Err(e) if e.kind() == io::ErrorKind::AddrInUse => {
+            match std::os::unix::net::UnixStream::connect(path) {
+                Ok(_) => Err(e),
+                Err(connect_err) if connect_err.kind() == io::ErrorKind::ConnectionRefused => {
+                    debug!("Removing stale socket file {}", path.display());
+                    std::fs::remove_file(path)?;
+                    tokio::net::UnixListener::bind(path)
+                }
+                Err(_) => Err(e),
+            }
+        }
+        result => result,
+    }
+}
+
+impl IpcListener {
+    /// Bind the preferred transport for this platform.
+    ///
+    /// `name` identifies the endpoint (e.g. `mcp-workflow-events-<execution_id>-<uuid>`)
+    /// and must be unique: binding a name that is still in use fails.
+    /// On Unix, falls back to loopback TCP if the socket cannot be created
+    /// (for instance when the temp path exceeds the `sun_path` limit).
+    pub fn bind(name: &str) -> io::Result<Self> {
+        #[cfg(windows)]
+        {
+            Self::bind_with(TransportKind::NamedPipe, name)
+        }
+        #[cfg(unix)]
+        {
+            match Self::bind_with(TransportKind::UnixSocket, name) {
+                Ok(listener) => Ok(listener),
+                Err(e) => {
+                    warn!("Failed to create Unix socket for {name} ({e}), falling back to TCP loopback");
+                    Self::bind_with(TransportKind::Tcp, name)
+                }
+            }
+        }
+        #[cfg(not(any(windows, unix)))]
+        {
+            Self::bind_with(TransportKind::Tcp, name)
+        }
+    }
+
+    /// Bind a specific transport. Must be called from within a Tokio runtime.
+    pub fn bind_with(kind: TransportKind, name: &str) -> io::Result<Self> {
+        match kind {
+            #[cfg(windows)]
+            TransportKind::NamedPipe => {
+                use tokio::net::windows::named_pipe::{PipeMode, ServerOptions};
+
+                let endpoint = format!(r"\\.\pipe\{name}");
+                let server = ServerOptions::new()
+                    .first_pipe_instance(true)
+                    .pipe_mode(PipeMode::Byte)
+                    .create(&endpoint)?;
+                Ok(Self {
+                    endpoint,
+                    inner: ListenerInner::NamedPipe(Some(server)),
+                })
+            }
+            #[cfg(unix)]
+            TransportKind::UnixSocket => {
+                let path = socket_dir()?.join(format!("{name}.sock"));
+                let listener = bind_unix(&path)?;
+                Ok(Self {
+                    endpoint: path.to_string_lossy().into_owned(),
+                    inner: ListenerInner::Unix {
+                        listener,
+                        _socket_file: SocketFile::new(path),
+                    },
+                })
+            }
+            TransportKind::Tcp => {
+                let listener = std::net::TcpListener::bind(("127.0.0.1", 0))?;
+                listener.set_nonblocking(true)?;
+                let addr = listener.local_addr()?;
+                let token = uuid::Uuid::new_v4().simple().to_string();
+                Ok(Self {
+                    endpoint: format!("{TCP_ENDPOINT_PREFIX}{addr}?token={token}"),
+                    inner: ListenerInner::Tcp {
+                        listener: tokio::net::TcpListener::from_std(listener)?,
+                        token,
+                    },
+                })
+            }
+            #[allow(unreachable_patterns)]
+            _ => Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                format!("{kind:?} transport is not available on this platform"),
+            )),
+        }
+    }
+
+    /// Address to pass to the client process
+    pub fn endpoint(&self) -> &str {
+        &self.endpoint
+    }
+
+    pub fn kind(&self) -> TransportKind {
+        match self.inner {
+            #[cfg(windows)]
+            ListenerInner::NamedPipe(_) => TransportKind::NamedPipe,
+            #[cfg(unix)]
+            ListenerInner::Unix { .. } => TransportKind::UnixSocket,
+            ListenerInner::Tcp { .. } => TransportKind::Tcp,
+        }
+    }
+
+    /// Token a client has to send as its first line, for TCP endpoints
+    fn token(&self) -> Option<String> {
+        match &self.inner {
+            ListenerInner::Tcp { token, .. } => Some(token.clone()),
+            #[allow(unreachable_patterns)]
+            _ => None,
+        }
+    }
+
+    /// Whether [`IpcListener::accept`] can hand out another connection. A named
+    /// pipe instance serves a single client.
+    fn can_accept(&self) -> bool {
+        match &self.inner {
+            #[cfg(windows)]
+            ListenerInner::NamedPipe(server) => server.is_some(),
+            #[cfg(unix)]
+            ListenerInner::Unix { .. } => true,
+            ListenerInner::Tcp { .. } => true,
+        }
+    }
+
+    /// Wait for a client connection
+    async fn accept(&mut self) -> io::Result<IpcStream> {
+        match &mut self.inner {
+            #[cfg(windows)]
+            ListenerInner::NamedPipe(slot) => {
+                let server = slot.take().ok_or_else(|| {
+                    io::Error::new(io::ErrorKind::AddrInUse, "named pipe is already connected")
+                })?;
+                server.connect().await?;
+                Ok(IpcStream::NamedPipe(server))
+            }
+            #[cfg(unix)]
+            ListenerInner::Unix { listener, .. } => {
+                let (stream, _) = listener.accept().await?;
+                Ok(IpcStream::Unix(stream))
+            }
+            ListenerInner::Tcp { listener, .. } => {
+                let (stream, _) = listener.accept().await?;
+                stream.set_nodelay(true)?;
+                Ok(IpcStream::Tcp(stream))
+            }
+        }
+    }
+}
+
+/// A connected IPC stream, either end
+pub enum IpcStream {
+    #[cfg(windows)]
+    NamedPipe(tokio::net::windows::named_pipe::NamedPipeServer),
+    #[cfg(windows)]
+    NamedPipeClient(tokio::net::windows::named_pipe::NamedPipeClient),
+    #[cfg(unix)]
+    Unix(tokio::net::UnixStream),
+    Tcp(tokio::net::TcpStream),
+}
+
+macro_rules! dispatch_stream {
+    ($self:expr, $s:ident => $body:expr) => {
+        match $self.get_mut() {
+            #[cfg(windows)]
+            IpcStream::NamedPipe($s) => $body,
+            #[cfg(windows)]
+            IpcStream::NamedPipeClient($s) => $body,
+            #[cfg(unix)]
+            IpcStream::Unix($s) => $body,
+            IpcStream::Tcp($s) => $body,
+        }
+    };
+}
+
+impl AsyncRead for IpcStream {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        dispatch_stream!(self, s => Pin::new(s).poll_read(cx, buf))
+    }
+}
+
+impl AsyncWrite for IpcStream {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        dispatch_stream!(self, s => Pin::new(s).poll_write(cx, buf))
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        dispatch_stream!(self, s => Pin::new(s).poll_flush(cx))
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        dispatch_stream!(self, s => Pin::new(s).poll_shutdown(cx))
+    }
+}
+
+/// Connect to an endpoint produced by [`IpcListener::endpoint`].
+///
+/// This is what the TypeScript SDK does on the other side; it is used by
+/// Rust clients and tests.
+pub async fn connect(endpoint: &str) -> io::Result<IpcStream> {
+    if let Some(address) = endpoint.strip_prefix(TCP_ENDPOINT_PREFIX) {
+        use tokio::io::AsyncWriteExt;
+
+        let (addr, token) = match address.split_once("?token=") {
+            Some((addr, token)) => (addr, Some(token)),
+            None => (address, None),
+        };
+        let mut stream = tokio::net::TcpStream::connect(addr).await?;
+        stream.set_nodelay(true)?;
+        if let Some(token) = token {
+            stream.write_all(format!("{token}\n").as_bytes()).await?;
+        }
+        return Ok(IpcStream::Tcp(stream));
+    }
+    #[cfg(windows)]
+    {
+        let client = tokio::net::windows::named_pipe::ClientOptions::new().open(endpoint)?;
+        Ok(IpcStream::NamedPipeClient(client))
+    }
+    #[cfg(unix)]
+    {
+        Ok(IpcStream::Unix(
+            tokio::net::UnixStream::connect(endpoint).await?,
+        ))
+    }
+    #[cfg(not(any(windows, unix)))]
+    {
+        Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            format!("Unsupported IPC endpoint: {endpoint}"),
+        ))
+    }
+}
+
+/// Handle to control a running line server
+pub struct LineServerHandle {
+    handle: tokio::task::JoinHandle<()>,
+    shutdown_tx: mpsc::Sender<()>,
+}
+
+impl LineServerHandle {
+    /// Signal the server to shutdown (does not wait for completion)
+    pub async fn shutdown(self) {
+        let _ = self.shutdown_tx.send(()).await;
+    }
+
+    /// Signal shutdown AND wait for the server task to complete.
+    /// This ensures all buffered data has been read from the connection and
+    /// sent through the channel before returning.
+    pub async fn shutdown_and_wait(self) {
+        let _ = self.shutdown_tx.send(()).await;
+        let _ = self.handle.await;
+    }
+}
+
+/// Accept one client on `listener` and forward each line `parse` accepts to `sender`.
+///
+/// Lines the parser rejects are ignored. The task ends when the client
+/// disconnects, the receiver is dropped, or shutdown is requested; buffered
+/// lines are drained before a shutdown is honoured. A client that disconnects
+/// without sending anything (such as the stale-socket probe of another bind)
+/// doesn't use up the connection: the server goes back to accepting. Neither
+/// does a TCP client whose first line isn't the endpoint token.
+pub fn serve_lines<T, F>(
+    mut listener: IpcListener,
+    label: &'static str,
+    parse: F,
+    sender: mpsc::UnboundedSender<T>,
+) -> LineServerHandle
+where
+    T: Debug + Send + 'static,
+    F: Fn(&str) -> Option<T> + Send + Sync + 'static,
+{
+    info!(
+        "Created {} ({:?}): {}",
+        label,
+        listener.kind(),
+        listener.endpoint()
+    );
+
+    let (shutdown_tx, mut shutdown_rx) = mpsc::channel::<()>(1);
+    let token = listener.token();
+
+    let handle = tokio::spawn(async move {
+        debug!("Waiting for TypeScript to connect to {}...", label);
+
+        loop {
+            let stream = tokio::select! {
+                result = listener.accept() => match result {
+                    Ok(stream) => stream,
+                    Err(e) => {
+                        error!("Failed to accept {} connection: {}", label, e);
+                        break;
+                    }
+                },
+                _ = shutdown_rx.recv() => {
+                    debug!("{} server shutdown before connection", label);
+                    break;
+                }
+            };
+            info!("TypeScript connected to {}", label);
+
+            let forwarded = forward_lines(
+                stream,
+                token.as_deref(),
+                label,
+                &parse,
+                &sender,
+                &mut shutdown_rx,
+            )
+            .await;
+            match forwarded {
+                Some(0) if listener.can_accept() => {
+                    debug!("{} client sent nothing, waiting for another", label);
+                }
+                _ => break,
+            }
+        }
+    });
+
+    LineServerHandle {
+        handle,
+        shutdown_tx,
+    }
+}
+
+/// Forward the lines of one client. Returns how many lines were read when the
+/// client disconnects (0 when it didn't send `token` first), or `None` when the
+/// server should stop (shutdown requested, receiver dropped or a read error).
+async fn forward_lines<T, F>(
+    stream: IpcStream,
+    token: Option<&str>,
+    label: &str,
+    parse: &F,
+    sender: &mpsc::UnboundedSender<T>,
+    shutdown_rx: &mut mpsc::Receiver<()>,
+) -> Option<usize>
+where
+    T: Debug,
+    F: Fn(&str) -> Option<T>,
+{
+    let mut lines = BufReader::new(stream).lines();
+    let mut read = 0;
+
+    if let Some(token) = token {
+        tokio::select! {
+            first = tokio::time::timeout(TOKEN_TIMEOUT, lines.next_line()) => {
+                if !matches!(first, Ok(Ok(Some(ref line))) if line == token) {
+                    warn!("Dropped a {} client that did not send the endpoint token", label);
+                    return Some(0);
+                }
+            }
+            _ = shutdown_rx.recv() => {
+                debug!("{} server shutdown requested", label);
+                return None;
+            }
+        }
+    }
+
+    loop {
+        // Biased so buffered data is drained before the shutdown signal is seen
+        tokio::select! {
+            biased;
+
+            line_result = lines.next_line() => {
+                match line_result {
+                    Ok(Some(line)) => {
+                        read += 1;
+                        if let Some(item) = parse(&line) {
+                            debug!("Received from {}: {:?}", label, item);
+                            if sender.send(item).is_err() {
+                                debug!("Receiver dropped, stopping {} server", label);
+                                return None;
+                            }
+                        }
+                    }
+                    Ok(None) => {
+                        debug!("{} closed by client", label);
+                        return Some(read);
+                    }
+                    Err(e) => {
+                        error!("Error reading from {}: {}", label, e);
+                        return None;
+                    }
+                }
+            }
+            _ = shutdown_rx.recv() => {
+                debug!("{} server shutdown requested", label);
+                return None;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+    use tokio::io::AsyncWriteExt;
+    use tokio::time::timeout;
+
+    async fn round_trip(listener: IpcListener) -> Vec<String> {
+        let endpoint = listener.endpoint().to_string();
+        let kind = listener.kind();
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let handle = serve_lines(
+            listener,
+            "test pipe",
+            |line| line.starts_with('{').then(|| line.to_string()),
+            tx,
+        );
+
+        // A client that sends nothing doesn't take the connection (named pipes
+        // serve exactly one client)
+        if kind != TransportKind::NamedPipe {
+            drop(connect(&endpoint).await.expect("probe connect"));
+        }
+        let mut client = connect(&endpoint).await.expect("connect");
+        client
+            .write_all(b"{\"a\":1}\nnoise\n{\"b\":2}\n")
+            .await
+            .unwrap();
+        client.shutdown().await.unwrap();
+        drop(client);
+
+        let mut received = Vec::new();
+        while let Ok(Some(line)) = timeout(Duration::from_secs(5), rx.recv()).await {
+            received.push(line);
+        }
+        handle.shutdown_and_wait().await;
+        received
+    }
+
+    #[tokio::test]
+    async fn test_tcp_round_trip() {
+        let listener = IpcListener::bind_with(TransportKind::Tcp, "test").unwrap();
+        assert!(listener.endpoint().starts_with("tcp://127.0.0.1:"));
+        assert!(listener.endpoint().contains("?token="));
+        assert_eq!(
+            round_trip(listener).await,
+            vec![r#"{"a":1}"#.to_string(), r#"{"b":2}"#.to_string()]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_tcp_client_without_token_is_dropped() {
+        let listener = IpcListener::bind_with(TransportKind::Tcp, "test").unwrap();
+        let endpoint = listener.endpoint().to_string();
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let handle = serve_lines(listener, "test pipe", |l| Some(l.to_string()), tx);
+
+        // Knows the port, but not the token
+        let addr = endpoint.strip_prefix(TCP_ENDPOINT_PREFIX).unwrap();
+        let addr = addr.split_once('?').unwrap().0;
+        let mut intruder = tokio::net::TcpStream::connect(addr).await.unwrap();
+        intruder.write_all(b"guess\n{\"x\":1}\n").await.unwrap();
+        intruder.shutdown().await.unwrap();
+
+        // The real client still gets through afterwards
+        let mut client = connect(&endpoint).await.unwrap();
+        client.write_all(b"{\"a\":1}\n").await.unwrap();
+        client.shutdown().await.unwrap();
+        drop(client);
+
+        let mut received = Vec::new();
+        while let Ok(Some(line)) = timeout(Duration::from_secs(5), rx.recv()).await {
+            received.push(line);
+        }
+        handle.shutdown_and_wait().await;
+        assert_eq!(received, [r#"{"a":1}"#]);
+    }
+
+    #[tokio::test]
+    async fn test_default_transport_round_trip() {
+        let name = format!("mcp-ipc-test-{}", std::process::id());
+        let listener = IpcListener::bind(&name).unwrap();
+        assert_eq!(round_trip(listener).await.len(), 2);
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_unix_socket_file_removed() {
+        let name = format!("mcp-ipc-cleanup-{}", std::process::id());
+        let listener = IpcListener::bind_with(TransportKind::UnixSocket, &name).unwrap();
+        let path = std::path::PathBuf::from(listener.endpoint());
+        assert!(path.exists());
+        drop(listener);
+        assert!(!path.exists());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_socket_dir_is_private() {
+        use std::os::unix::fs::PermissionsExt;
+        let dir = socket_dir().unwrap();
+        let mode = std::fs::metadata(&dir).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o700);
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_unix_socket_in_use_is_not_taken_over() {
+        let name = format!("mcp-ipc-in-use-{}", std::process::id());
+        let first = IpcListener::bind_with(TransportKind::UnixSocket, &name).unwrap();
+        let err = IpcListener::bind_with(TransportKind::UnixSocket, &name)
+            .err()
+            .expect("second bind of a live socket must fail");
+        assert_eq!(err.kind(), io::ErrorKind::AddrInUse);
+        assert_eq!(round_trip(first).await.len(), 2);
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_unix_stale_socket_replaced() {
+        let name = format!("mcp-ipc-stale-{}", std::process::id());
+        let path = socket_dir().unwrap().join(format!("{name}.sock"));
+        let _ = std::fs::remove_file(&path);
+        // std listeners leave their socket file behind, like a crashed run
+        drop(std::os::unix::net::UnixListener::bind(&path).unwrap());
+        assert!(path.exists());
+
+        let listener = IpcListener::bind_with(TransportKind::UnixSocket, &name).unwrap();
+        assert_eq!(round_trip(listener).await.len(), 2);
+        assert!(!path.exists());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_socket_file_keeps_foreign_socket() {
+        let name = format!("mcp-ipc-foreign-{}", std::process::id());
+        let path = socket_dir().unwrap().join(format!("{name}.sock"));
+        let _ = std::fs::remove_file(&path);
+        drop(std::os::unix::net::UnixListener::bind(&path).unwrap());
+        let ours = SocketFile::new(path.clone());
+
+        // Someone else's socket replaced the file after we bound it
+        let other = socket_dir().unwrap().join(format!("{name}-other.sock"));
+        let _ = std::fs::remove_file(&other);
+        let _theirs = std::os::unix::net::UnixListener::bind(&other).unwrap();
+        std::fs::rename(&other, &path).unwrap();
+        drop(ours);
+        assert!(path.exists());
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_shutdown_before_connection() {
+        let listener = IpcListener::bind_with(TransportKind::Tcp, "test").unwrap();
+        let (tx, _rx) = mpsc::unbounded_channel::<String>();
+        let handle = serve_lines(listener, "test pipe", |l| Some(l.to_string()), tx);
+        timeout(Duration::from_secs(5), handle.shutdown_and_wait())
+            .await
+            .expect("server should stop without a client");
+    }
+}
@@ -1,28 +1,43 @@
 pub mod cancellation;
 pub mod child_process;
+pub mod client_auth;
 pub mod duration_parser;
 pub mod elicitation;
 pub mod event_pipe;
+pub mod execution_history;
 pub mod execution_logger;
+pub mod execution_queue;
 pub mod expression_eval;
+pub mod form_variables;
 pub mod helpers;
+pub mod ipc_transport;
 pub mod log_pipe;
 pub mod mcp_types;
 pub mod omniparser;
+pub mod output_extractor;
 pub mod output_parser;
 pub mod prompt;
+pub mod recording;
+pub mod retry_policy;
+pub mod run_report;
+pub mod scheduler;
 pub mod scripting_engine;
 pub mod sentry;
 pub mod server;
 pub mod server_sequence;
 pub mod telemetry;
 pub mod tool_logging;
+pub mod tool_policy;
 pub mod tools;
 pub mod transpiler;
 pub mod tree_formatter;
 pub mod utils;
 pub mod vision;
+pub mod webhooks;
 pub mod workflow_format;
+pub mod workflow_graph;
+pub mod workflow_state;
+pub mod workflow_triggers;
 pub mod workflow_typescript;
 
 // Re-export ui_tree_diff from terminator crate (single source of truth)
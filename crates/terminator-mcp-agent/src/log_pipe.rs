@@ -1,7 +1,9 @@
-//! Windows Named Pipe server for receiving workflow logs from TypeScript
+//! IPC server for receiving workflow logs from TypeScript
 //!
 //! This module provides a clean IPC mechanism for TypeScript workflows to send
-//! structured logs to the Rust MCP agent without polluting stderr.
+//! structured logs to the Rust MCP agent without polluting stderr. The transport
+//! is a named pipe on Windows and a Unix domain socket (or loopback TCP)
+//! elsewhere, see [`crate::ipc_transport`].
 //!
 //! # Architecture
 //! ```text
@@ -11,17 +13,18 @@
 //! │        │        │         │        │        │
 //! │        ▼        │         │        ▼        │
 //! │  Write to pipe  │ ──────► │  Read logs      │
-//! │                 │  Named  │        │        │
+//! │                 │  IPC    │        │        │
 //! │                 │  Pipe   │        ▼        │
 //! └─────────────────┘         │  Forward to     │
 //!                             │  tracing        │
 //!                             └─────────────────┘
 //! ```
 
+use crate::ipc_transport::{serve_lines, IpcListener, LineServerHandle};
 use serde::Deserialize;
-use tokio::io::{AsyncBufReadExt, BufReader};
 use tokio::sync::mpsc;
 use tracing::{debug, error, info, warn};
+use uuid::Uuid;
 
 /// Log entry received from TypeScript workflow
 #[derive(Debug, Clone, Deserialize)]
@@ -62,133 +65,44 @@ pub fn try_parse_log(line: &str) -> Option<LogEntry> {
     serde_json::from_str::<LogEntry>(trimmed).ok()
 }
 
-/// Windows Named Pipe server for receiving workflow logs
-#[cfg(windows)]
+/// Server for receiving workflow logs over the local IPC transport
 pub struct LogPipeServer {
-    pipe_name: String,
+    listener: IpcListener,
     log_sender: LogSender,
 }
 
-#[cfg(windows)]
 impl LogPipeServer {
-    /// Create a new log pipe server
-    pub fn new(execution_id: &str, log_sender: LogSender) -> Self {
-        Self {
-            pipe_name: generate_log_pipe_name(execution_id),
+    /// Create a new log pipe server, binding its endpoint immediately so the
+    /// address can be passed to the child before it starts. The endpoint name
+    /// gets a random suffix, so concurrent runs with the same id don't collide.
+    pub fn new(execution_id: &str, log_sender: LogSender) -> std::io::Result<Self> {
+        Ok(Self {
+            listener: IpcListener::bind(&format!(
+                "mcp-workflow-logs-{execution_id}-{}",
+                Uuid::new_v4().simple()
+            ))?,
             log_sender,
-        }
+        })
     }
 
-    /// Get the pipe name for passing to TypeScript
+    /// Get the endpoint for passing to TypeScript (`MCP_LOG_PIPE`)
     pub fn pipe_name(&self) -> &str {
-        &self.pipe_name
+        self.listener.endpoint()
     }
 
     /// Start the pipe server and return a handle to stop it
     pub async fn start(self) -> Result<LogPipeServerHandle, std::io::Error> {
-        use tokio::net::windows::named_pipe::{PipeMode, ServerOptions};
-
-        let pipe_name = self.pipe_name.clone();
-        let log_sender = self.log_sender;
-
-        // Create the named pipe server
-        let server = ServerOptions::new()
-            .first_pipe_instance(true)
-            .pipe_mode(PipeMode::Byte)
-            .create(&pipe_name)?;
-
-        info!("Created log pipe: {}", pipe_name);
-
-        let (shutdown_tx, mut shutdown_rx) = mpsc::channel::<()>(1);
-
-        let handle = tokio::spawn(async move {
-            // Wait for client connection
-            debug!("Waiting for TypeScript to connect to log pipe...");
-
-            tokio::select! {
-                result = server.connect() => {
-                    match result {
-                        Ok(()) => {
-                            info!("TypeScript connected to log pipe");
-
-                            let reader = BufReader::new(server);
-                            let mut lines = reader.lines();
-
-                            loop {
-                                // Use biased select to prefer reading data over shutdown signal.
-                                // This ensures we drain all buffered data before responding to shutdown.
-                                tokio::select! {
-                                    biased;
-
-                                    line_result = lines.next_line() => {
-                                        match line_result {
-                                            Ok(Some(line)) => {
-                                                if let Some(entry) = try_parse_log(&line) {
-                                                    debug!("Received log from pipe: {:?}", entry);
-                                                    if log_sender.send(entry).is_err() {
-                                                        debug!("Log receiver dropped, stopping log pipe server");
-                                                        break;
-                                                    }
-                                                }
-                                            }
-                                            Ok(None) => {
-                                                debug!("Log pipe closed by client");
-                                                break;
-                                            }
-                                            Err(e) => {
-                                                error!("Error reading from log pipe: {}", e);
-                                                break;
-                                            }
-                                        }
-                                    }
-                                    _ = shutdown_rx.recv() => {
-                                        debug!("Log pipe server shutdown requested");
-                                        break;
-                                    }
-                                }
-                            }
-                        }
-                        Err(e) => {
-                            error!("Failed to accept log pipe connection: {}", e);
-                        }
-                    }
-                }
-                _ = shutdown_rx.recv() => {
-                    debug!("Log pipe server shutdown before connection");
-                }
-            }
-        });
-
-        Ok(LogPipeServerHandle {
-            handle,
-            shutdown_tx,
-        })
+        Ok(serve_lines(
+            self.listener,
+            "log pipe",
+            try_parse_log,
+            self.log_sender,
+        ))
     }
 }
 
 /// Handle to control the log pipe server
-pub struct LogPipeServerHandle {
-    handle: tokio::task::JoinHandle<()>,
-    shutdown_tx: mpsc::Sender<()>,
-}
-
-impl LogPipeServerHandle {
-    /// Signal the log pipe server to shutdown (does not wait for completion)
-    #[allow(dead_code)]
-    pub async fn shutdown(self) {
-        let _ = self.shutdown_tx.send(()).await;
-    }
-
-    /// Signal shutdown AND wait for the pipe server task to complete.
-    /// This ensures all buffered data has been read from the pipe and sent
-    /// through the channel before returning.
-    pub async fn shutdown_and_wait(self) {
-        // Signal shutdown
-        let _ = self.shutdown_tx.send(()).await;
-        // Wait for the task to complete - this ensures all data has been processed
-        let _ = self.handle.await;
-    }
-}
+pub type LogPipeServerHandle = LineServerHandle;
 
 /// Forward log entries to tracing with optional execution_id for OTEL filtering
 pub fn forward_log_to_tracing(entry: &LogEntry, execution_id: Option<&str>) {
@@ -285,7 +199,7 @@ mod windows_integration_tests {
     #[tokio::test]
     async fn test_log_pipe_server_basic() {
         let (tx, mut rx) = create_log_channel();
-        let server = LogPipeServer::new("test-log-basic", tx);
+        let server = LogPipeServer::new("test-log-basic", tx).unwrap();
         let pipe_name = server.pipe_name().to_string();
 
         let handle = server
@@ -323,7 +237,7 @@ mod windows_integration_tests {
     #[tokio::test]
     async fn test_log_pipe_server_multiple_logs() {
         let (tx, mut rx) = create_log_channel();
-        let server = LogPipeServer::new("test-log-multi", tx);
+        let server = LogPipeServer::new("test-log-multi", tx).unwrap();
         let pipe_name = server.pipe_name().to_string();
 
         let handle = server
@@ -384,7 +298,7 @@ mod windows_integration_tests {
         use std::sync::{Arc, Mutex};
 
         let (tx, mut rx) = create_log_channel();
-        let server = LogPipeServer::new("test-race-condition", tx);
+        let server = LogPipeServer::new("test-race-condition", tx).unwrap();
         let pipe_name = server.pipe_name().to_string();
 
         let handle = server
@@ -29,8 +29,12 @@ use std::{
 use sysinfo::{ProcessesToUpdate, System};
 use terminator_mcp_agent::cancellation::RequestManager;
 use terminator_mcp_agent::child_process;
+use terminator_mcp_agent::client_auth::TokenStore;
+use terminator_mcp_agent::scheduler::{self, Scheduler};
 use terminator_mcp_agent::server::{self, check_terminator_source};
 use terminator_mcp_agent::utils::init_logging;
+use terminator_mcp_agent::webhooks::{self, Webhooks};
+use terminator_mcp_agent::workflow_triggers::RunWorkflow;
 use tower_http::cors::CorsLayer;
 use tracing::{debug, error, info};
 use uuid::Uuid;
@@ -63,6 +67,11 @@ struct Args {
     #[arg(long, env = "MCP_AUTH_TOKEN")]
     auth_token: Option<String>,
 
+    /// Token file (YAML or JSON) with named per-client tokens, scopes, expiry and rate limits
+    /// for the HTTP transport (can also use MCP_AUTH_TOKENS_FILE env var)
+    #[arg(long, env = "MCP_AUTH_TOKENS_FILE")]
+    auth_tokens_file: Option<std::path::PathBuf>,
+
     /// PID to watch for auto-destruct (Windows only)
     /// When set, the MCP server will automatically shut down if the specified process terminates
     #[arg(long)]
@@ -73,6 +82,23 @@ struct Args {
     /// Default: false (allows multiple instances via smart parent checking)
     #[arg(long)]
     enforce_single_instance: bool,
+
+    /// Tool policy file (YAML or JSON) with allow/deny/confirm rules per tool, argument and process
+    /// The file is reloaded automatically when it changes
+    #[arg(long, env = "TERMINATOR_MCP_POLICY")]
+    policy: Option<std::path::PathBuf>,
+
+    /// Directory of trigger-driven workflows: cron triggers run on their schedule, webhook
+    /// triggers get a POST /hooks/<path> route on the HTTP transport
+    /// The directory is rescanned periodically, so workflows can be added without a restart
+    #[arg(long, alias = "schedule-dir", env = "TERMINATOR_WORKFLOWS_DIR")]
+    workflows_dir: Option<std::path::PathBuf>,
+
+    /// Shared secret for webhook signatures: callers send X-Terminator-Timestamp and
+    /// X-Terminator-Signature: sha256=<hex>, an HMAC-SHA256 of "<timestamp>.<raw body>"
+    /// (can also use TERMINATOR_WEBHOOK_SECRET env var)
+    #[arg(long, env = "TERMINATOR_WEBHOOK_SECRET")]
+    webhook_secret: Option<String>,
 }
 
 #[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, ValueEnum)]
@@ -299,6 +325,11 @@ async fn main() -> Result<()> {
     // Initialize execution logger (MCP request/response logging to files)
     terminator_mcp_agent::execution_logger::init();
 
+    // Load the tool policy (allow/deny/confirm rules) if one is configured
+    if let Some(ref policy_path) = args.policy {
+        terminator_mcp_agent::tool_policy::init(policy_path).map_err(anyhow::Error::msg)?;
+    }
+
     // Add binary identification logging
     tracing::info!("========================================");
     tracing::info!("Terminator MCP Server v{}", env!("CARGO_PKG_VERSION"));
@@ -377,6 +408,10 @@ async fn main() -> Result<()> {
 
             // Background window polling service removed - we capture explicitly on step 0 instead
 
+            if let Some(dir) = args.workflows_dir.clone() {
+                start_scheduler(&desktop, dir).await?;
+            }
+
             // Serve with better error handling
             let service = desktop.serve(stdio()).await.inspect_err(|e| {
                 tracing::error!("Serving error: {:?}", e);
@@ -412,7 +447,7 @@ async fn main() -> Result<()> {
                 return Ok(());
             }
 
-            if args.auth_token.is_some() {
+            if args.auth_token.is_some() || args.auth_tokens_file.is_some() {
                 tracing::warn!("⚠️  SSE transport does not support authentication yet");
                 tracing::warn!("⚠️  Use HTTP transport for Bearer token authentication");
                 tracing::warn!(
@@ -423,6 +458,10 @@ async fn main() -> Result<()> {
 
             let desktop = server::DesktopWrapper::new_with_log_capture(log_capture.clone())?;
 
+            if let Some(dir) = args.workflows_dir.clone() {
+                start_scheduler(&desktop, dir).await?;
+            }
+
             // Background window polling service removed - we capture explicitly on step 0 instead
 
             let ct = SseServer::serve(addr)
@@ -490,6 +529,18 @@ async fn main() -> Result<()> {
                 Default::default(),
             );
 
+            // Triggered workflows need the shared DesktopWrapper right away rather than on
+            // first /mcp use
+            let workflow_runner = match args.workflows_dir.clone() {
+                Some(dir) => {
+                    let wrapper =
+                        server::DesktopWrapper::new_with_log_capture(log_capture.clone())?;
+                    *desktop_wrapper.write().await = Some(wrapper.clone());
+                    Some((dir.clone(), start_scheduler(&wrapper, dir).await?))
+                }
+                None => None,
+            };
+
             // Busy-aware concurrency state with request tracking
             #[derive(Clone)]
             struct AppState {
@@ -497,7 +548,7 @@ async fn main() -> Result<()> {
                 last_activity: Arc<Mutex<String>>, // ISO-8601
                 max_concurrent: usize,
                 request_manager: RequestManager,
-                auth_token: Option<String>,
+                auth: Option<Arc<TokenStore>>,
                 desktop_wrapper: Arc<tokio::sync::RwLock<Option<server::DesktopWrapper>>>,
             }
 
@@ -506,22 +557,55 @@ async fn main() -> Result<()> {
                 .and_then(|s| s.parse::<usize>().ok())
                 .unwrap_or(1);
 
+            // Per-client tokens, plus the shared --auth-token as client "default"
+            let auth = if args.auth_token.is_some() || args.auth_tokens_file.is_some() {
+                let mut store = match &args.auth_tokens_file {
+                    Some(path) => TokenStore::load(path).map_err(anyhow::Error::msg)?,
+                    None => TokenStore::default(),
+                };
+                if let Some(token) = args.auth_token.clone() {
+                    store = store.with_shared_token(token);
+                }
+                Some(Arc::new(store))
+            } else {
+                None
+            };
+
             let app_state = AppState {
                 active_requests: Arc::new(AtomicUsize::new(0)),
                 last_activity: Arc::new(Mutex::new(chrono::Utc::now().to_rfc3339())),
                 max_concurrent,
                 request_manager: RequestManager::new(),
-                auth_token: args.auth_token.clone(),
+                auth,
                 desktop_wrapper: desktop_wrapper.clone(),
             };
 
             // Log authentication status
-            if app_state.auth_token.is_some() {
-                tracing::info!("🔒 Authentication enabled - Bearer token required");
+            if let Some(ref auth) = app_state.auth {
+                tracing::info!(
+                    "🔒 Authentication enabled - Bearer token required ({} client(s))",
+                    auth.len()
+                );
             } else {
                 tracing::warn!("⚠️  Authentication disabled - server is publicly accessible");
             }
 
+            // Webhook triggers authenticate on their own (signature or bearer token), so
+            // they sit outside the /mcp auth middleware
+            let webhooks = workflow_runner.map(|(dir, runner)| {
+                if app_state.auth.is_none() && args.webhook_secret.is_none() {
+                    tracing::warn!(
+                        "⚠️  Webhooks are unauthenticated - set --webhook-secret or an auth token"
+                    );
+                }
+                Webhooks::new(
+                    dir,
+                    runner,
+                    args.webhook_secret.clone(),
+                    app_state.auth.clone(),
+                )
+            });
+
             async fn status_handler(State(state): State<AppState>) -> impl IntoResponse {
                 let active = state.active_requests.load(Ordering::SeqCst);
                 let busy = active >= state.max_concurrent;
@@ -605,16 +689,17 @@ async fn main() -> Result<()> {
                 }
             }
 
-            // Authentication middleware - validates Bearer token if auth is enabled
+            // Authentication middleware - validates the Bearer token if auth is enabled and
+            // attaches the client identity for scope checks and execution logs
             async fn auth_middleware(
                 State(state): State<AppState>,
-                req: Request<Body>,
+                mut req: Request<Body>,
                 next: Next,
             ) -> impl IntoResponse {
                 // Skip auth if no token is configured
-                if state.auth_token.is_none() {
+                let Some(ref tokens) = state.auth else {
                     return next.run(req).await;
-                }
+                };
 
                 // Extract Authorization header
                 let auth_header = req
@@ -622,25 +707,17 @@ async fn main() -> Result<()> {
                     .get(axum::http::header::AUTHORIZATION)
                     .and_then(|v| v.to_str().ok());
 
-                // Validate token
-                if let Some(auth_value) = auth_header {
-                    if let Some(token) = auth_value.strip_prefix("Bearer ") {
-                        if state.auth_token.as_deref() == Some(token) {
-                            // Token valid, proceed
-                            return next.run(req).await;
-                        }
+                match tokens.authenticate(auth_header) {
+                    Ok(client) => {
+                        debug!("Authenticated client '{}'", client.name);
+                        req.extensions_mut().insert(client);
+                        next.run(req).await
                     }
-                }
-
-                // Authentication failed
-                debug!("Authentication failed - invalid or missing Bearer token");
-                let body = serde_json::json!({
-                    "error": {
-                        "code": -32001,
-                        "message": "Unauthorized - invalid or missing Bearer token"
+                    Err(e) => {
+                        debug!("Authentication failed - {}", e);
+                        e.into_response()
                     }
-                });
-                (StatusCode::UNAUTHORIZED, Json(body)).into_response()
+                }
             }
 
             async fn mcp_gate(
@@ -777,6 +854,9 @@ async fn main() -> Result<()> {
                 .route("/mode", post(set_mode_handler))
                 .nest("/mcp", mcp_router)
                 .with_state(app_state.clone());
+            if let Some(webhooks) = webhooks.clone() {
+                router = router.merge(webhooks::router(webhooks));
+            }
 
             if args.cors {
                 router = router.layer(CorsLayer::permissive());
@@ -794,6 +874,12 @@ async fn main() -> Result<()> {
             info!("  Status endpoint: http://{addr}/status");
             info!("  Health check: http://{addr}/health");
             info!("  Mode control: POST http://{addr}/mode");
+            if let Some(webhooks) = &webhooks {
+                for (path, workflow) in webhooks.routes() {
+                    info!("  Webhook ({workflow}): POST http://{addr}/hooks/{path}");
+                }
+                info!("  Execution status: GET http://{addr}/executions/<id>");
+            }
             info!("Press Ctrl+C to stop");
 
             axum::serve(tcp_listener, router)
@@ -822,6 +908,17 @@ async fn main() -> Result<()> {
     Ok(())
 }
 
+/// Start the workflow scheduler for `dir` and return the runner it uses, so webhook
+/// triggers can share it
+async fn start_scheduler(
+    desktop: &server::DesktopWrapper,
+    dir: std::path::PathBuf,
+) -> Result<RunWorkflow> {
+    let runner = desktop.workflow_runner().await?;
+    scheduler::init(Scheduler::new(dir, scheduler::default_state_path(), runner.clone()).await);
+    Ok(runner)
+}
+
 async fn root_handler() -> impl axum::response::IntoResponse {
     (
         axum::http::StatusCode::OK,
@@ -833,7 +930,9 @@ async fn root_handler() -> impl axum::response::IntoResponse {
                 "/": "This endpoint - lists available endpoints",
                 "/mcp": "MCP protocol endpoint - connect your MCP client here",
                 "/health": "Health check endpoint - returns server status",
-                "/status": "Status endpoint - shows active requests and concurrency info"
+                "/status": "Status endpoint - shows active requests and concurrency info",
+                "/hooks/<path>": "Webhook triggers - POST a JSON body to run a workflow (with --workflows-dir)",
+                "/executions/<id>": "Status of a webhook-triggered run"
             },
             "usage": {
                 "mcp_client": "Connect your MCP client to: /mcp",
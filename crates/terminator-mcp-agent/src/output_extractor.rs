@@ -0,0 +1,544 @@
+//! Declarative workflow output extraction, evaluated in Rust.
+//!
+//! An alternative to JavaScript output parsers for the common case of "find these
+//! items in a UI tree and pull some fields out of each":
+//!
+//! ```yaml
+//! output:
+//!   source: capture_orders          # step whose ui_tree to read (optional)
+//!   items:
+//!     selector: "role:ListItem"     # or role / name / name_regex predicates
+//!   fields:
+//!     title: name                   # shorthand: attribute of the item itself
+//!     price:
+//!       selector: "role:Text && name:$"   # first matching descendant
+//!       attribute: name
+//!       transform: [trim, { regex: "\\$([0-9.]+)" }, number]
+//! ```
+//!
+//! The result has the same `{ result: { success, message, data } }` shape a
+//! JavaScript parser returns.
+
+use anyhow::{anyhow, Result};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+use terminator::Selector;
+
+/// Declarative output definition. Recognised by its `items` key.
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct DeclarativeOutput {
+    /// Step id whose UI tree is used; defaults to the last tree in the results
+    #[serde(default, alias = "ui_tree_source_step_id")]
+    pub source: Option<String>,
+    /// Which nodes become output items
+    pub items: ItemMatcher,
+    /// Output field name to how its value is read from each item.
+    /// Without fields, each item is reported as its attributes.
+    #[serde(default)]
+    pub fields: Map<String, Value>,
+    /// Report the first item as `data` instead of an array
+    #[serde(default)]
+    pub single: bool,
+    /// Succeed even when no items match
+    #[serde(default)]
+    pub allow_empty: bool,
+    /// Success message; `{count}` is replaced with the number of items
+    #[serde(default)]
+    pub message: Option<String>,
+}
+
+/// Predicates selecting item nodes. All given predicates must match.
+#[derive(Debug, Deserialize, Serialize, Clone, Default)]
+pub struct ItemMatcher {
+    /// Terminator selector evaluated against the tree (e.g. "role:ListItem >> role:Text")
+    #[serde(default)]
+    pub selector: Option<String>,
+    /// Role, case-insensitive
+    #[serde(default)]
+    pub role: Option<String>,
+    /// Case-insensitive substring of the name
+    #[serde(default)]
+    pub name: Option<String>,
+    /// Regex the name must match
+    #[serde(default)]
+    pub name_regex: Option<String>,
+    /// Keep at most this many items
+    #[serde(default)]
+    pub limit: Option<usize>,
+}
+
+/// How a single field is read from an item.
+#[derive(Debug, Deserialize, Serialize, Clone)]
+#[serde(untagged)]
+pub enum FieldSpec {
+    /// Attribute of the item node itself, e.g. `name` or `properties.AutomationId`
+    Attribute(String),
+    Detailed(FieldDetails),
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct FieldDetails {
+    /// Selector relative to the item; the first matching descendant is read
+    #[serde(default)]
+    pub selector: Option<String>,
+    /// Attribute to read (default: name)
+    #[serde(default)]
+    pub attribute: Option<String>,
+    /// Collect the attribute from every matching descendant into an array
+    #[serde(default)]
+    pub all: bool,
+    #[serde(default)]
+    pub transform: Vec<Transform>,
+    /// Value used when nothing matches or a transform fails
+    #[serde(default)]
+    pub default: Option<Value>,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+#[serde(untagged)]
+pub enum Transform {
+    /// `trim`, `lowercase`, `uppercase`, `number`, `integer`, `bool` or `date`
+    Named(String),
+    /// First capture group (or the `group`th) of a regex; null when it doesn't match
+    Regex {
+        regex: String,
+        #[serde(default)]
+        group: Option<usize>,
+    },
+    /// Replace every regex match
+    Replace {
+        replace: String,
+        #[serde(default)]
+        with: String,
+    },
+    /// Parse a date with a chrono format string; produces YYYY-MM-DD
+    Date { date: String },
+}
+
+/// Date formats tried by the bare `date` transform.
+const DATE_FORMATS: [&str; 6] = [
+    "%Y-%m-%d",
+    "%m/%d/%Y",
+    "%d.%m.%Y",
+    "%b %d, %Y",
+    "%B %d, %Y",
+    "%d %b %Y",
+];
+
+/// Whether an `output:` definition should be handled declaratively.
+pub fn is_declarative(definition: &Value) -> bool {
+    definition.get("items").is_some_and(|v| v.is_object())
+}
+
+/// Evaluate a declarative definition against the sequence result.
+pub fn extract(definition: &Value, tool_output: &Value) -> Result<Value> {
+    let def: DeclarativeOutput = serde_json::from_value(definition.clone())
+        .map_err(|e| anyhow!("Invalid declarative output definition: {e}"))?;
+    let tree =
+        crate::output_parser::find_ui_tree_in_results(tool_output, def.source.as_deref())?
+            .ok_or_else(|| anyhow!("No UI tree found in workflow results for output extraction"))?;
+
+    let fields = def
+        .fields
+        .iter()
+        .map(|(name, spec)| {
+            serde_json::from_value::<FieldSpec>(spec.clone())
+                .map(|spec| (name.as_str(), spec))
+                .map_err(|e| anyhow!("Invalid output field '{name}': {e}"))
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    let mut items = find_items(&tree, &def.items)?;
+    if let Some(limit) = def.items.limit {
+        items.truncate(limit);
+    }
+    let data = items
+        .iter()
+        .map(|item| extract_item(item, &fields))
+        .collect::<Result<Vec<_>>>()?;
+
+    let count = data.len();
+    let result = if count == 0 && !def.allow_empty {
+        json!({
+            "success": false,
+            "message": "No items matched the output definition",
+            "error": "No items matched the output definition",
+            "data": if def.single { Value::Null } else { json!([]) },
+        })
+    } else {
+        let message = def
+            .message
+            .as_deref()
+            .unwrap_or("Extracted {count} item(s)")
+            .replace("{count}", &count.to_string());
+        let data = if def.single {
+            data.into_iter().next().unwrap_or(Value::Null)
+        } else {
+            Value::Array(data)
+        };
+        json!({ "success": true, "message": message, "data": data })
+    };
+    Ok(json!({ "result": result }))
+}
+
+fn find_items<'t>(tree: &'t Value, matcher: &ItemMatcher) -> Result<Vec<&'t Value>> {
+    let mut nodes = match &matcher.selector {
+        Some(selector) => select_all(tree, &parse_selector(selector)?, true)?,
+        None => descendants(tree, true),
+    };
+    let name_regex = matcher
+        .name_regex
+        .as_deref()
+        .map(Regex::new)
+        .transpose()
+        .map_err(|e| anyhow!("Invalid items.name_regex: {e}"))?;
+
+    nodes.retain(|node| {
+        let name = attr_str(node, "name").unwrap_or_default();
+        matcher
+            .role
+            .as_deref()
+            .is_none_or(|role| attr_str(node, "role").is_some_and(|r| r.eq_ignore_ascii_case(role)))
+            && matcher
+                .name
+                .as_deref()
+                .is_none_or(|n| name.to_lowercase().contains(&n.to_lowercase()))
+            && name_regex.as_ref().is_none_or(|re| re.is_match(&name))
+    });
+    Ok(nodes)
+}
+
+fn extract_item(item: &Value, fields: &[(&str, FieldSpec)]) -> Result<Value> {
+    if fields.is_empty() {
+        return Ok(item.get("attributes").cloned().unwrap_or(Value::Null));
+    }
+    let mut out = Map::new();
+    for (name, spec) in fields {
+        let value = match spec {
+            FieldSpec::Attribute(attribute) => attr(item, attribute).unwrap_or(Value::Null),
+            FieldSpec::Detailed(details) => extract_field(item, details)?,
+        };
+        out.insert(name.to_string(), value);
+    }
+    Ok(Value::Object(out))
+}
+
+fn extract_field(item: &Value, details: &FieldDetails) -> Result<Value> {
+    let attribute = details.attribute.as_deref().unwrap_or("name");
+    let sources = match &details.selector {
+        Some(selector) => select_all(item, &parse_selector(selector)?, false)?,
+        None => vec![item],
+    };
+
+    let apply = |node: &Value| -> Result<Value> {
+        let mut value = attr(node, attribute).unwrap_or(Value::Null);
+        for transform in &details.transform {
+            value = apply_transform(value, transform)?;
+        }
+        Ok(value)
+    };
+    let value = if details.all {
+        Value::Array(sources.into_iter().map(apply).collect::<Result<_>>()?)
+    } else {
+        match sources.first().copied() {
+            Some(node) => apply(node)?,
+            None => Value::Null,
+        }
+    };
+    Ok(match (&value, &details.default) {
+        (Value::Null, Some(default)) => default.clone(),
+        _ => value,
+    })
+}
+
+fn apply_transform(value: Value, transform: &Transform) -> Result<Value> {
+    let text = match &value {
+        Value::Null => return Ok(Value::Null),
+        Value::String(s) => s.clone(),
+        other => other.to_string(),
+    };
+    Ok(match transform {
+        Transform::Named(name) => match name.as_str() {
+            "trim" => json!(text.trim()),
+            "lowercase" => json!(text.to_lowercase()),
+            "uppercase" => json!(text.to_uppercase()),
+            "number" => parse_number(&text)
+                .and_then(serde_json::Number::from_f64)
+                .map_or(Value::Null, Value::Number),
+            "integer" => parse_number(&text).map_or(Value::Null, |n| json!(n.trunc() as i64)),
+            "bool" => match text.trim().to_lowercase().as_str() {
+                "true" | "yes" | "1" | "on" | "checked" => json!(true),
+                "false" | "no" | "0" | "off" | "unchecked" | "" => json!(false),
+                _ => Value::Null,
+            },
+            "date" => DATE_FORMATS
+                .iter()
+                .find_map(|f| chrono::NaiveDate::parse_from_str(text.trim(), f).ok())
+                .map_or(Value::Null, |d| json!(d.format("%Y-%m-%d").to_string())),
+            other => return Err(anyhow!("Unknown output transform '{other}'")),
+        },
+        Transform::Regex { regex, group } => {
+            let re = Regex::new(regex).map_err(|e| anyhow!("Invalid transform regex: {e}"))?;
+            let group = group.unwrap_or(if re.captures_len() > 1 { 1 } else { 0 });
+            re.captures(&text)
+                .and_then(|caps| caps.get(group))
+                .map_or(Value::Null, |m| json!(m.as_str()))
+        }
+        Transform::Replace { replace, with } => {
+            let re = Regex::new(replace).map_err(|e| anyhow!("Invalid transform regex: {e}"))?;
+            json!(re.replace_all(&text, with.as_str()))
+        }
+        Transform::Date { date } => chrono::NaiveDate::parse_from_str(text.trim(), date)
+            .map_or(Value::Null, |d| json!(d.format("%Y-%m-%d").to_string())),
+    })
+}
+
+/// Parse "1,234.50", "$12", "-3" and similar into a number.
+fn parse_number(text: &str) -> Option<f64> {
+    let cleaned: String = text
+        .chars()
+        .filter(|c| c.is_ascii_digit() || matches!(c, '.' | '-'))
+        .collect();
+    cleaned.parse().ok()
+}
+
+/// Read an attribute from a UI tree node: `name`, `role`, `is_toggled`, ...,
+/// `properties.<key>`, or the node-level `id`/`selector`.
+fn attr(node: &Value, attribute: &str) -> Option<Value> {
+    let attributes = node.get("attributes");
+    let value = match attribute.split_once('.') {
+        Some(("properties", key)) => attributes?.get("properties")?.get(key),
+        _ => attributes
+            .and_then(|a| a.get(attribute))
+            .or_else(|| node.get(attribute)),
+    }?;
+    Some(value.clone())
+}
+
+fn attr_str(node: &Value, attribute: &str) -> Option<String> {
+    match attr(node, attribute)? {
+        Value::String(s) => Some(s),
+        Value::Null => None,
+        other => Some(other.to_string()),
+    }
+}
+
+fn children(node: &Value) -> impl Iterator<Item = &Value> {
+    node.get("children")
+        .and_then(|c| c.as_array())
+        .into_iter()
+        .flatten()
+}
+
+/// Depth-first, document order.
+fn descendants(node: &Value, include_self: bool) -> Vec<&Value> {
+    let mut out = Vec::new();
+    if include_self {
+        out.push(node);
+    }
+    for child in children(node) {
+        out.extend(descendants(child, true));
+    }
+    out
+}
+
+fn parse_selector(selector: &str) -> Result<Selector> {
+    match Selector::from(selector) {
+        Selector::Invalid(reason) => Err(anyhow!("Invalid selector '{selector}': {reason}")),
+        parsed => Ok(parsed),
+    }
+}
+
+/// Nodes under `root` matching `selector`. A chain narrows step by step, each
+/// step searching the descendants of the previous step's matches.
+fn select_all<'t>(
+    root: &'t Value,
+    selector: &Selector,
+    include_root: bool,
+) -> Result<Vec<&'t Value>> {
+    let Selector::Chain(parts) = selector else {
+        let mut matched = Vec::new();
+        for node in descendants(root, include_root) {
+            if matches(node, selector)? {
+                matched.push(node);
+            }
+        }
+        return Ok(matched);
+    };
+
+    let mut current = vec![root];
+    let mut first = true;
+    for part in parts {
+        current = match part {
+            Selector::Nth(n) => {
+                let index = if *n < 0 {
+                    current.len().checked_sub(n.unsigned_abs() as usize)
+                } else {
+                    Some(*n as usize)
+                };
+                index
+                    .and_then(|i| current.get(i).copied())
+                    .into_iter()
+                    .collect()
+            }
+            _ => {
+                let mut next = Vec::new();
+                for node in &current {
+                    for found in select_all(node, part, first && include_root)? {
+                        if !next.iter().any(|n: &&Value| std::ptr::eq(*n, found)) {
+                            next.push(found);
+                        }
+                    }
+                }
+                next
+            }
+        };
+        first = false;
+    }
+    Ok(current)
+}
+
+fn matches(node: &Value, selector: &Selector) -> Result<bool> {
+    let contains_ci = |attribute: &str, expected: &str| {
+        attr_str(node, attribute)
+            .is_some_and(|v| v.to_lowercase().contains(&expected.to_lowercase()))
+    };
+    Ok(match selector {
+        Selector::Role { role, name } => {
+            attr_str(node, "role").is_some_and(|r| r.eq_ignore_ascii_case(role))
+                && name.as_deref().is_none_or(|n| contains_ci("name", n))
+        }
+        Selector::Name(name) => contains_ci("name", name),
+        Selector::Text(text) => ["name", "text", "value"]
+            .iter()
+            .any(|a| attr_str(node, a).is_some_and(|v| v.contains(text.as_str()))),
+        Selector::Id(id) => {
+            attr_str(node, "id").as_deref() == Some(id.strip_prefix('#').unwrap_or(id))
+        }
+        Selector::NativeId(id) => {
+            attr_str(node, "properties.AutomationId").as_deref() == Some(id.as_str())
+        }
+        Selector::ClassName(class) => {
+            attr_str(node, "properties.ClassName").as_deref() == Some(class.as_str())
+        }
+        Selector::Attributes(expected) => expected
+            .iter()
+            .all(|(key, value)| attr_str(node, key).as_deref() == Some(value.as_str())),
+        Selector::And(parts) => {
+            for part in parts {
+                if !matches(node, part)? {
+                    return Ok(false);
+                }
+            }
+            true
+        }
+        Selector::Or(parts) => {
+            for part in parts {
+                if matches(node, part)? {
+                    return Ok(true);
+                }
+            }
+            false
+        }
+        Selector::Not(inner) => !matches(node, inner)?,
+        Selector::Has(inner) => !select_all(node, inner, false)?.is_empty(),
+        other => {
+            return Err(anyhow!(
+                "Selector {other} is not supported in declarative output; use a JavaScript parser"
+            ))
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sequence_result() -> Value {
+        json!({
+            "results": [{
+                "step_id": "capture",
+                "result": {"ui_tree": {
+                    "attributes": {"role": "Window", "name": "Orders"},
+                    "children": [
+                        {"attributes": {"role": "ListItem", "name": "Order A-100"},
+                         "children": [
+                            {"attributes": {"role": "Text", "name": " Total: $1,250.50 "}},
+                            {"attributes": {"role": "Text", "name": "Placed 03/14/2024"}},
+                            {"attributes": {"role": "CheckBox", "name": "Paid", "is_toggled": true}}
+                         ]},
+                        {"attributes": {"role": "ListItem", "name": "Order B-200"},
+                         "children": [
+                            {"attributes": {"role": "Text", "name": "Total: $80"}}
+                         ]},
+                        {"attributes": {"role": "Button", "name": "Next page"}}
+                    ]
+                }}
+            }]
+        })
+    }
+
+    #[test]
+    fn extracts_items_with_fields_and_transforms() {
+        let def = json!({
+            "source": "capture",
+            "items": {"selector": "role:ListItem"},
+            "fields": {
+                "order": {"attribute": "name", "transform": [{"regex": "Order (\\S+)"}]},
+                "total": {"selector": "role:Text && name:Total", "transform": ["trim", {"regex": "\\$([0-9,.]+)"}, "number"]},
+                "placed": {"selector": "role:Text && name:Placed", "transform": [{"replace": "Placed ", "with": ""}, "date"]},
+                "paid": {"selector": "role:CheckBox", "attribute": "is_toggled", "default": false}
+            }
+        });
+        let out = extract(&def, &sequence_result()).unwrap();
+        let result = &out["result"];
+        assert_eq!(result["success"], json!(true));
+        assert_eq!(result["message"], json!("Extracted 2 item(s)"));
+        assert_eq!(
+            result["data"],
+            json!([
+                {"order": "A-100", "total": 1250.5, "placed": "2024-03-14", "paid": true},
+                {"order": "B-200", "total": 80.0, "placed": null, "paid": false}
+            ])
+        );
+    }
+
+    #[test]
+    fn predicates_single_and_empty_results() {
+        let def = json!({
+            "items": {"role": "listitem", "name_regex": "B-\\d+"},
+            "fields": {"name": "name"},
+            "single": true,
+            "message": "Found {count}"
+        });
+        let out = extract(&def, &sequence_result()).unwrap();
+        assert_eq!(out["result"]["data"], json!({"name": "Order B-200"}));
+        assert_eq!(out["result"]["message"], json!("Found 1"));
+
+        let none = json!({"items": {"role": "Slider"}});
+        let out = extract(&none, &sequence_result()).unwrap();
+        assert_eq!(out["result"]["success"], json!(false));
+        assert_eq!(out["result"]["data"], json!([]));
+    }
+
+    #[test]
+    fn chained_selectors_and_all() {
+        let def = json!({
+            "items": {"selector": "role:Window >> role:ListItem && name:A-100"},
+            "fields": {"texts": {"selector": "role:Text", "all": true}}
+        });
+        let out = extract(&def, &sequence_result()).unwrap();
+        assert_eq!(
+            out["result"]["data"][0]["texts"],
+            json!([" Total: $1,250.50 ", "Placed 03/14/2024"])
+        );
+    }
+
+    #[test]
+    fn detects_declarative_definitions() {
+        assert!(is_declarative(&json!({"items": {"role": "Button"}})));
+        assert!(!is_declarative(&json!({"run": "return 1"})));
+        assert!(!is_declarative(&json!("return tree")));
+    }
+}
@@ -27,6 +27,11 @@ pub async fn run_output_parser(
     parser_def_val: &Value,
     tool_output: &Value,
 ) -> Result<Option<Value>> {
+    // Declarative definitions (with `items`) are evaluated in Rust; no JS runtime needed
+    if crate::output_extractor::is_declarative(parser_def_val) {
+        return crate::output_extractor::extract(parser_def_val, tool_output).map(Some);
+    }
+
     // Support simplified format where output is just a string (JavaScript code)
     let parser_def = if parser_def_val.is_string() {
         OutputParserDefinition {
@@ -125,7 +130,10 @@ pub async fn run_output_parser(
 }
 
 /// Finds a UI tree in the tool output results
-fn find_ui_tree_in_results(tool_output: &Value, step_id: Option<&str>) -> Result<Option<Value>> {
+pub(crate) fn find_ui_tree_in_results(
+    tool_output: &Value,
+    step_id: Option<&str>,
+) -> Result<Option<Value>> {
     // Strategy 0: If step_id is specified, prefer UI tree from that specific step, but gracefully
     // fall back to any available UI tree if that step exists without a tree or is not present.
     if let Some(target_step_id) = step_id {
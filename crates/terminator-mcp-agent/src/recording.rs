@@ -0,0 +1,667 @@
+//! Recording a user demonstration from an MCP client.
+//!
+//! `start_recording` runs a `WorkflowRecorder` while the user performs a task.
+//! High-level events (clicks, typed text, app switches, navigations, hotkeys)
+//! are streamed to the client as `notifications/message` log messages from the
+//! `recording` logger. `stop_recording` returns the
+//! raw events together with the workflow `compile` generates from them, so the
+//! client can refine the steps and run them with `execute_sequence`. With
+//! `extract_variables`, the data typed during the recording is lifted into
+//! workflow variables (see [`crate::form_variables`]).
+//!
+//! One recording runs per agent at a time.
+
+use crate::form_variables;
+use chrono::{DateTime, Utc};
+use futures::StreamExt;
+use rmcp::model::{LoggingLevel, LoggingMessageNotificationParam};
+use rmcp::service::{Peer, RoleServer};
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::path::Path;
+use std::sync::atomic::{AtomicU64, AtomicU8, Ordering};
+use std::sync::{Arc, Mutex};
+use terminator::redaction::{Redactor, REDACTED};
+use terminator_workflow_recorder::{
+    compile, CompileOptions, PerformanceMode, SinkConfig, WorkflowEvent, WorkflowRecorder,
+    WorkflowRecorderConfig,
+};
+use tokio::task::JoinHandle;
+use uuid::Uuid;
+
+/// Longest piece of user text (typed values, element names) put into an
+/// event message
+const MAX_MESSAGE_TEXT: usize = 80;
+
+/// Builds the recorder configuration for `start_recording`.
+///
+/// `performance_mode` picks the preset (`normal`, `balanced` or
+/// `low_energy`); fields of `overrides` are then applied on top of it.
+pub fn build_config(
+    performance_mode: Option<&str>,
+    overrides: Option<&Value>,
+) -> Result<WorkflowRecorderConfig, String> {
+    let mode = match performance_mode {
+        Some(mode) => serde_json::from_value::<PerformanceMode>(Value::String(mode.to_string()))
+            .map_err(|_| {
+                format!(
+                    "Unknown performance_mode '{mode}', expected normal, balanced or low_energy"
+                )
+            })?,
+        None => PerformanceMode::Normal,
+    };
+    let preset = match mode {
+        PerformanceMode::Normal => WorkflowRecorderConfig::default(),
+        PerformanceMode::Balanced => PerformanceMode::balanced_config(),
+        PerformanceMode::LowEnergy => PerformanceMode::low_energy_config(),
+    };
+    let Some(overrides) = overrides else {
+        return Ok(preset);
+    };
+    let Value::Object(overrides) = overrides else {
+        return Err("config must be an object of WorkflowRecorderConfig fields".to_string());
+    };
+
+    let mut merged = serde_json::to_value(&preset)
+        .map_err(|e| format!("Failed to serialize recorder config: {e}"))?;
+    if let Value::Object(fields) = &mut merged {
+        for (key, value) in overrides {
+            fields.insert(key.clone(), value.clone());
+        }
+    }
+    let config: WorkflowRecorderConfig =
+        serde_json::from_value(merged).map_err(|e| format!("Invalid recorder config: {e}"))?;
+    if config.sinks.contains(&SinkConfig::Stdout) {
+        // stdout carries the MCP protocol on the stdio transport
+        return Err(
+            "The stdout sink is not available in the MCP agent; use jsonl or websocket".to_string(),
+        );
+    }
+    Ok(config)
+}
+
+/// One-line description of a recorded event for event messages.
+///
+/// Returns `None` for low-level events (mouse moves, single key strokes,
+/// pending UI captures) that the high-level events already cover. Events in
+/// password fields or excluded applications are only named by their kind;
+/// every other description goes through the redaction patterns.
+pub fn describe_event(event: &WorkflowEvent, redactor: &Redactor) -> Option<String> {
+    let description = describe(event)?;
+    if is_sensitive(event, redactor) {
+        return Some(format!("{} {REDACTED}", event_kind(event)));
+    }
+    Some(redactor.redact_text(&description).into_owned())
+}
+
+fn describe(event: &WorkflowEvent) -> Option<String> {
+    let description = match event {
+        WorkflowEvent::Mouse(_) | WorkflowEvent::Keyboard(_) | WorkflowEvent::PendingAction(_) => {
+            return None
+        }
+        WorkflowEvent::Click(e) => {
+            let mut text = format!("Clicked {}", e.element_role);
+            if !e.element_text.trim().is_empty() {
+                text.push_str(&format!(" '{}'", shorten(&e.element_text)));
+            }
+            if let Some(process) = &e.process_name {
+                text.push_str(&format!(" in {process}"));
+            }
+            text
+        }
+        WorkflowEvent::BrowserClick(e) => {
+            let label = e
+                .dom_element
+                .as_ref()
+                .and_then(|dom| dom.inner_text.as_deref().or(dom.aria_label.as_deref()))
+                .filter(|label| !label.trim().is_empty());
+            match label {
+                Some(label) => format!("Clicked '{}' on {}", shorten(label), e.page_url),
+                None => format!("Clicked on {}", e.page_url),
+            }
+        }
+        WorkflowEvent::TextInputCompleted(e) => format!(
+            "Typed '{}' into {}",
+            shorten(&e.text_value),
+            e.field_name.as_deref().unwrap_or(&e.field_type)
+        ),
+        WorkflowEvent::BrowserTextInput(e) => {
+            format!("Typed '{}' on {}", shorten(&e.text), e.page_url)
+        }
+        WorkflowEvent::ApplicationSwitch(e) => {
+            format!("Switched to {}", e.to_window_and_application_name)
+        }
+        WorkflowEvent::BrowserTabNavigation(e) => format!(
+            "Navigated to {}",
+            e.to_url
+                .as_deref()
+                .or(e.to_title.as_deref())
+                .unwrap_or(&e.browser)
+        ),
+        WorkflowEvent::Hotkey(e) => format!("Pressed {}", e.combination),
+        WorkflowEvent::FileOpened(e) => {
+            format!("Opened {} in {}", e.filename, e.application_name)
+        }
+        WorkflowEvent::Clipboard(e) => format!("Clipboard {:?}", e.action).to_lowercase(),
+        WorkflowEvent::TextSelection(_) => "Selected text".to_string(),
+        WorkflowEvent::DragDrop(_) => "Dragged and dropped".to_string(),
+    };
+    Some(description)
+}
+
+fn event_kind(event: &WorkflowEvent) -> &'static str {
+    match event {
+        WorkflowEvent::Click(_) | WorkflowEvent::BrowserClick(_) => "Click",
+        WorkflowEvent::TextInputCompleted(_) | WorkflowEvent::BrowserTextInput(_) => "Text input",
+        WorkflowEvent::ApplicationSwitch(_) => "Application switch",
+        WorkflowEvent::BrowserTabNavigation(_) => "Navigation",
+        WorkflowEvent::Hotkey(_) => "Hotkey",
+        WorkflowEvent::FileOpened(_) => "File opened",
+        WorkflowEvent::Clipboard(_) => "Clipboard",
+        WorkflowEvent::TextSelection(_) => "Text selection",
+        WorkflowEvent::DragDrop(_) => "Drag and drop",
+        WorkflowEvent::Mouse(_) | WorkflowEvent::Keyboard(_) | WorkflowEvent::PendingAction(_) => {
+            "Event"
+        }
+    }
+}
+
+/// Whether the event happened in a password field or an application that
+/// opted out of recording
+fn is_sensitive(event: &WorkflowEvent, redactor: &Redactor) -> bool {
+    let excluded = |application: Option<&str>| {
+        application.is_some_and(|a| redactor.is_excluded_application(a))
+    };
+    let passwords = redactor.masks_passwords();
+    let element = event.ui_element().is_some_and(|element| {
+        (passwords && element.is_password().unwrap_or(false))
+            || excluded(Some(&element.application_name()))
+    });
+    element
+        || match event {
+            WorkflowEvent::TextInputCompleted(e) => {
+                (passwords && (e.is_password || e.field_type.to_lowercase().contains("password")))
+                    || excluded(e.process_name.as_deref())
+            }
+            WorkflowEvent::BrowserTextInput(e) => {
+                passwords
+                    && (is_password_selector(&e.selector)
+                        || e.dom_element
+                            .as_ref()
+                            .is_some_and(|dom| is_password_selector(&dom.css_selector)))
+            }
+            WorkflowEvent::Click(e) => excluded(e.process_name.as_deref()),
+            WorkflowEvent::Hotkey(e) => excluded(e.process_name.as_deref()),
+            WorkflowEvent::ApplicationSwitch(e) => excluded(e.to_process_name.as_deref()),
+            WorkflowEvent::FileOpened(e) => excluded(e.process_name.as_deref()),
+            _ => false,
+        }
+}
+
+fn is_password_selector(selector: &str) -> bool {
+    let selector = selector.to_lowercase().replace(['"', '\'', ' '], "");
+    selector.contains("type=password")
+}
+
+fn shorten(text: &str) -> String {
+    let text = text.trim();
+    if text.chars().count() <= MAX_MESSAGE_TEXT {
+        return text.to_string();
+    }
+    let cut: String = text.chars().take(MAX_MESSAGE_TEXT).collect();
+    format!("{cut}...")
+}
+
+/// Where recorded events are streamed while recording.
+///
+/// Events are log messages rather than progress notifications: they keep
+/// arriving after the `start_recording` request has returned, and clients
+/// drop progress for requests that are no longer in flight.
+pub struct EventSink {
+    pub peer: Peer<RoleServer>,
+}
+
+/// Snapshot returned by `start_recording` and `get_recording_status`
+#[derive(Debug, Clone, Serialize)]
+pub struct RecordingStatus {
+    pub recording: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub recording_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub started_at: Option<DateTime<Utc>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub duration_ms: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub performance_mode: Option<PerformanceMode>,
+    /// Events captured so far, low-level ones included
+    pub event_count: u64,
+    /// Description of the latest high-level event
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_event: Option<String>,
+    pub streaming_events: bool,
+    /// URLs of the recording's WebSocket sinks, with the session token
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub sink_urls: Vec<String>,
+}
+
+/// What `stop_recording` hands back to the client
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WorkflowOutput {
+    Yaml,
+    TypeScript,
+}
+
+impl std::str::FromStr for WorkflowOutput {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "yaml" | "yml" => Ok(WorkflowOutput::Yaml),
+            "typescript" | "ts" => Ok(WorkflowOutput::TypeScript),
+            other => Err(format!(
+                "Unknown workflow_format '{other}', expected yaml or typescript"
+            )),
+        }
+    }
+}
+
+struct ActiveRecording {
+    id: String,
+    name: String,
+    started_at: DateTime<Utc>,
+    performance_mode: PerformanceMode,
+    recorder: WorkflowRecorder,
+    event_count: Arc<AtomicU64>,
+    last_event: Arc<Mutex<Option<String>>>,
+    streaming_events: bool,
+    forwarder: JoinHandle<()>,
+}
+
+impl ActiveRecording {
+    fn status(&self) -> RecordingStatus {
+        RecordingStatus {
+            recording: true,
+            recording_id: Some(self.id.clone()),
+            name: Some(self.name.clone()),
+            started_at: Some(self.started_at),
+            duration_ms: Some((Utc::now() - self.started_at).num_milliseconds()),
+            performance_mode: Some(self.performance_mode.clone()),
+            event_count: self.event_count.load(Ordering::Relaxed),
+            last_event: self.last_event.lock().ok().and_then(|last| last.clone()),
+            streaming_events: self.streaming_events,
+            sink_urls: self.recorder.sink_urls(),
+        }
+    }
+}
+
+/// Holds the recording running on this agent, if any
+#[derive(Default)]
+pub struct RecordingManager {
+    active: tokio::sync::Mutex<Option<ActiveRecording>>,
+    /// Lowest level the client asked for with `logging/setLevel`, as
+    /// `LoggingLevel as u8`; event messages are `info`
+    log_level: Arc<AtomicU8>,
+}
+
+impl RecordingManager {
+    pub fn new() -> Arc<Self> {
+        Arc::new(Self::default())
+    }
+
+    /// Applies `logging/setLevel`; event messages are only sent when `info`
+    /// is at or above `level`
+    pub fn set_log_level(&self, level: LoggingLevel) {
+        self.log_level.store(level as u8, Ordering::Relaxed);
+    }
+
+    /// Starts recording. Fails if a recording is already running or the
+    /// recorder can't start on this platform.
+    pub async fn start(
+        &self,
+        name: String,
+        config: WorkflowRecorderConfig,
+        sink: Option<EventSink>,
+    ) -> Result<RecordingStatus, String> {
+        let mut active = self.active.lock().await;
+        if let Some(running) = active.as_ref() {
+            return Err(format!(
+                "Recording '{}' ({}) is already running; stop it with stop_recording first",
+                running.name, running.id
+            ));
+        }
+
+        let id = Uuid::new_v4().to_string();
+        let performance_mode = config.performance_mode.clone();
+        let mut recorder = WorkflowRecorder::new(name.clone(), config);
+        let event_count = Arc::new(AtomicU64::new(0));
+        let last_event = Arc::new(Mutex::new(None));
+        let streaming_events = sink.is_some();
+
+        // Subscribe before starting so the first events aren't missed
+        let mut events = Box::pin(recorder.event_stream());
+        let forwarder = {
+            let event_count = event_count.clone();
+            let last_event = last_event.clone();
+            let log_level = self.log_level.clone();
+            let recording_id = id.clone();
+            tokio::spawn(async move {
+                while let Some(event) = events.next().await {
+                    let count = event_count.fetch_add(1, Ordering::Relaxed) + 1;
+                    let redactor = terminator::redaction::global();
+                    let Some(description) = describe_event(&event, &redactor) else {
+                        continue;
+                    };
+                    if let Ok(mut last) = last_event.lock() {
+                        *last = Some(description.clone());
+                    }
+                    let Some(sink) = &sink else {
+                        continue;
+                    };
+                    if log_level.load(Ordering::Relaxed) > LoggingLevel::Info as u8 {
+                        continue;
+                    }
+                    let _ = sink
+                        .peer
+                        .notify_logging_message(LoggingMessageNotificationParam {
+                            level: LoggingLevel::Info,
+                            logger: Some("recording".to_string()),
+                            data: json!({
+                                "type": "recording_event",
+                                "recording_id": recording_id,
+                                "event_count": count,
+                                "description": description,
+                            }),
+                        })
+                        .await;
+                }
+            })
+        };
+
+        if let Err(e) = recorder.start().await {
+            forwarder.abort();
+            return Err(format!("Failed to start recording: {e}"));
+        }
+
+        let recording = ActiveRecording {
+            id,
+            name,
+            started_at: Utc::now(),
+            performance_mode,
+            recorder,
+            event_count,
+            last_event,
+            streaming_events,
+            forwarder,
+        };
+        let status = recording.status();
+        *active = Some(recording);
+        Ok(status)
+    }
+
+    pub async fn status(&self) -> RecordingStatus {
+        match self.active.lock().await.as_ref() {
+            Some(recording) => recording.status(),
+            None => RecordingStatus {
+                recording: false,
+                recording_id: None,
+                name: None,
+                started_at: None,
+                duration_ms: None,
+                performance_mode: None,
+                event_count: 0,
+                last_event: None,
+                streaming_events: false,
+                sink_urls: Vec::new(),
+            },
+        }
+    }
+
+    /// Stops the running recording and compiles it into a workflow.
+    ///
+    /// The raw recording is also written to `save_path` when given, so it can
+    /// be compiled again later. `extract_variables` replaces the typed data
+    /// with variables and returns example `inputs`; it needs YAML output.
+    pub async fn stop(
+        &self,
+        output: WorkflowOutput,
+        include_events: bool,
+        extract_variables: bool,
+        save_path: Option<&Path>,
+    ) -> Result<Value, String> {
+        if extract_variables && output == WorkflowOutput::TypeScript {
+            return Err("extract_variables is only supported for YAML workflows".to_string());
+        }
+        let mut active = self.active.lock().await;
+        let Some(mut recording) = active.take() else {
+            return Err("No recording is running; start one with start_recording".to_string());
+        };
+
+        if let Err(e) = recording.recorder.stop().await {
+            // Leave it in place so the caller can retry instead of losing the events
+            *active = Some(recording);
+            return Err(format!("Failed to stop recording: {e}"));
+        }
+        drop(active);
+        recording.forwarder.abort();
+
+        if let Some(path) = save_path {
+            recording
+                .recorder
+                .save(path)
+                .map_err(|e| format!("Failed to save recording to {}: {e}", path.display()))?;
+        }
+
+        let recorded = recording
+            .recorder
+            .workflow
+            .lock()
+            .map(|workflow| workflow.to_redacted())
+            .map_err(|e| format!("Failed to read recorded events: {e}"))?;
+        let compiled = compile(&recorded, &CompileOptions::default());
+        let parameterized =
+            extract_variables.then(|| form_variables::parameterize(&recorded, compiled.clone()));
+        let (format, mut content) = match (output, &parameterized) {
+            (WorkflowOutput::Yaml, Some(parameterized)) => ("yaml", parameterized.to_yaml()?),
+            (WorkflowOutput::Yaml, None) => (
+                "yaml",
+                compiled
+                    .to_yaml()
+                    .map_err(|e| format!("Failed to render workflow as YAML: {e}"))?,
+            ),
+            (WorkflowOutput::TypeScript, _) => ("typescript", compiled.to_typescript()),
+        };
+        terminator::redaction::global().redact_string(&mut content);
+
+        let mut result = json!({
+            "recording_id": recording.id,
+            "name": recording.name,
+            "started_at": recording.started_at,
+            "duration_ms": (Utc::now() - recording.started_at).num_milliseconds(),
+            "event_count": recorded.events.len(),
+            "workflow": {
+                "format": format,
+                "content": content,
+                "step_count": compiled.steps.len(),
+                "warnings": compiled.warnings,
+            },
+        });
+        if let Some(parameterized) = parameterized {
+            result["workflow"]["variables"] = json!(parameterized.variables);
+            let mut inputs = parameterized.inputs;
+            terminator::redaction::global().redact_json(&mut inputs);
+            result["workflow"]["inputs"] = inputs;
+        }
+        if let Some(path) = save_path {
+            result["saved_to"] = json!(path.display().to_string());
+        }
+        if include_events {
+            result["events"] = json!(recorded.events);
+        }
+        Ok(result)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use terminator::redaction::RedactionConfig;
+    use terminator_workflow_recorder::{
+        EventMetadata, FieldFocusMethod, HotkeyEvent, KeyboardEvent, TextInputCompletedEvent,
+        TextInputMethod,
+    };
+
+    #[test]
+    fn performance_mode_picks_preset_and_overrides_apply_on_top() {
+        let config = build_config(Some("low_energy"), None).unwrap();
+        assert_eq!(config.performance_mode, PerformanceMode::LowEnergy);
+        assert_eq!(config.max_events_per_second, Some(5));
+
+        let config = build_config(
+            Some("balanced"),
+            Some(&json!({"record_clipboard": false, "ignore_applications": ["slack.exe"]})),
+        )
+        .unwrap();
+        assert_eq!(config.performance_mode, PerformanceMode::Balanced);
+        assert!(config.filter_mouse_noise);
+        assert!(!config.record_clipboard);
+        assert!(config.ignore_applications.contains("slack.exe"));
+
+        let config = build_config(None, None).unwrap();
+        assert_eq!(config.performance_mode, PerformanceMode::Normal);
+        assert!(config.record_clipboard);
+    }
+
+    #[test]
+    fn invalid_config_is_rejected() {
+        assert!(build_config(Some("turbo"), None)
+            .unwrap_err()
+            .contains("turbo"));
+        assert!(build_config(None, Some(&json!({"record_mice": true})))
+            .unwrap_err()
+            .contains("record_mice"));
+        assert!(build_config(None, Some(&json!({"record_mouse": "yes"}))).is_err());
+        assert!(build_config(None, Some(&json!(["record_mouse"]))).is_err());
+        assert!(
+            build_config(None, Some(&json!({"sinks": [{"type": "stdout"}]})))
+                .unwrap_err()
+                .contains("stdout")
+        );
+    }
+
+    #[test]
+    fn sinks_are_configured_through_overrides() {
+        let config = build_config(
+            None,
+            Some(&json!({"sinks": [{"type": "jsonl", "path": "C:/recordings/session.jsonl"}]})),
+        )
+        .unwrap();
+        assert!(matches!(
+            &config.sinks[..],
+            [SinkConfig::Jsonl(jsonl)] if jsonl.path.ends_with("session.jsonl")
+        ));
+    }
+
+    #[test]
+    fn only_high_level_events_are_described() {
+        let hotkey = WorkflowEvent::Hotkey(HotkeyEvent {
+            combination: "Ctrl+S".to_string(),
+            action: Some("Save".to_string()),
+            is_global: false,
+            process_name: Some("notepad.exe".to_string()),
+            metadata: EventMetadata::empty(),
+        });
+        let redactor = Redactor::disabled();
+        assert_eq!(
+            describe_event(&hotkey, &redactor).as_deref(),
+            Some("Pressed Ctrl+S")
+        );
+
+        let key = WorkflowEvent::Keyboard(KeyboardEvent {
+            key_code: 65,
+            is_key_down: true,
+            ctrl_pressed: false,
+            alt_pressed: false,
+            shift_pressed: false,
+            win_pressed: false,
+            character: Some('a'),
+            scan_code: None,
+            metadata: EventMetadata::empty(),
+        });
+        assert_eq!(describe_event(&key, &redactor), None);
+    }
+
+    fn typed(text: &str, process: &str, is_password: bool) -> WorkflowEvent {
+        WorkflowEvent::TextInputCompleted(TextInputCompletedEvent {
+            text_value: text.to_string(),
+            field_name: Some("Email".to_string()),
+            field_type: "Edit".to_string(),
+            input_method: TextInputMethod::Typed,
+            focus_method: FieldFocusMethod::MouseClick,
+            typing_duration_ms: 900,
+            keystroke_count: 12,
+            process_name: Some(process.to_string()),
+            is_password,
+            metadata: EventMetadata::empty(),
+        })
+    }
+
+    #[test]
+    fn descriptions_are_redacted() {
+        let redactor = Redactor::new(
+            RedactionConfig::parse("patterns: [email]\nexcluded_applications: [KeePass]").unwrap(),
+        )
+        .unwrap();
+        assert_eq!(
+            describe_event(&typed("jane@example.com", "chrome.exe", false), &redactor).as_deref(),
+            Some("Typed '[REDACTED:email]' into Email")
+        );
+        assert_eq!(
+            describe_event(&typed("hunter2", "chrome.exe", true), &redactor).as_deref(),
+            Some("Text input [REDACTED]")
+        );
+        assert_eq!(
+            describe_event(&typed("master key", "KeePass.exe", false), &redactor).as_deref(),
+            Some("Text input [REDACTED]")
+        );
+        assert_eq!(
+            describe_event(&typed("hunter2", "chrome.exe", true), &Redactor::disabled()).as_deref(),
+            Some("Typed 'hunter2' into Email")
+        );
+    }
+
+    #[test]
+    fn long_text_is_shortened() {
+        let text = "x".repeat(200);
+        let short = shorten(&text);
+        assert_eq!(short.chars().count(), MAX_MESSAGE_TEXT + 3);
+        assert!(short.ends_with("..."));
+        assert_eq!(shorten("  Save  "), "Save");
+    }
+
+    #[tokio::test]
+    async fn stop_without_recording_fails_and_status_is_idle() {
+        let manager = RecordingManager::new();
+        let status = manager.status().await;
+        assert!(!status.recording);
+        assert_eq!(status.event_count, 0);
+        let err = manager
+            .stop(WorkflowOutput::Yaml, true, false, None)
+            .await
+            .unwrap_err();
+        assert!(err.contains("No recording is running"));
+        let err = manager
+            .stop(WorkflowOutput::TypeScript, true, true, None)
+            .await
+            .unwrap_err();
+        assert!(err.contains("only supported for YAML"));
+    }
+
+    #[test]
+    fn workflow_output_parses() {
+        assert_eq!("YAML".parse(), Ok(WorkflowOutput::Yaml));
+        assert_eq!("ts".parse(), Ok(WorkflowOutput::TypeScript));
+        assert!("json".parse::<WorkflowOutput>().is_err());
+    }
+}
@@ -0,0 +1,467 @@
+//! Retry policies for workflow steps.
+//!
+//! A step's `retry:` block controls how many times a failed step is re-run, how long
+//! to wait between attempts, and which kinds of failures are worth retrying at all.
+//!
+//! ```yaml
+//! - tool_name: click_element
+//!   arguments: { selector: "role:Button|name:Submit" }
+//!   retry:
+//!     attempts: 4
+//!     backoff: exponential
+//!     delay: 500ms
+//!     max_delay: 5s
+//!     jitter: 0.2
+//!     on: [ElementNotFound, Timeout, RetryableUIAutomationAPIError]
+//!     before_retry:
+//!       - tool_name: press_key
+//!         arguments: { key: "{Escape}" }
+//! ```
+
+use crate::duration_parser::parse_duration;
+use crate::utils::ToolCall;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::time::Duration;
+use terminator::AutomationError;
+use tracing::warn;
+
+/// Delay before the first retry when none is configured (matches the legacy `retries` behavior)
+const DEFAULT_DELAY_MS: u64 = 500;
+/// Upper bound for a single backoff delay when `max_delay` is not configured
+const DEFAULT_MAX_DELAY_MS: u64 = 30_000;
+/// Growth factor for exponential backoff when `multiplier` is not configured
+const DEFAULT_MULTIPLIER: f64 = 2.0;
+
+/// How the delay between attempts grows
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum BackoffKind {
+    /// Wait `delay` before every retry
+    Fixed,
+    /// Wait `delay * multiplier^(n-1)` before the n-th retry
+    #[default]
+    Exponential,
+}
+
+/// Failure classes a retry policy can filter on.
+///
+/// These mirror the `terminator::AutomationError` variants. `UIAutomationAPIError`
+/// matches every UI Automation failure, while `RetryableUIAutomationAPIError` only
+/// matches the ones the platform layer flagged with `is_retryable`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, JsonSchema)]
+pub enum ErrorClass {
+    ElementNotFound,
+    Timeout,
+    PermissionDenied,
+    PlatformError,
+    UnsupportedOperation,
+    UnsupportedPlatform,
+    InvalidArgument,
+    Internal,
+    InvalidSelector,
+    UIAutomationAPIError,
+    #[serde(alias = "UIAutomationAPIError{is_retryable}")]
+    RetryableUIAutomationAPIError,
+    ElementDetached,
+    ElementNotVisible,
+    ElementNotEnabled,
+    ElementNotStable,
+    ElementObscured,
+    ScrollFailed,
+    OperationCancelled,
+    VerificationFailed,
+    /// Anything that could not be mapped to a known class
+    Other,
+}
+
+/// Display prefixes produced by `AutomationError`'s `#[error(...)]` attributes.
+/// Used for tool errors that reach the sequence runner without an `error_class`
+/// in their data; the display text does not say whether a UI Automation error
+/// is retryable.
+const DISPLAY_PREFIXES: &[(&str, ErrorClass)] = &[
+    ("Element not found:", ErrorClass::ElementNotFound),
+    ("Operation timed out:", ErrorClass::Timeout),
+    ("Permission denied:", ErrorClass::PermissionDenied),
+    ("Platform-specific error:", ErrorClass::PlatformError),
+    ("Unsupported operation:", ErrorClass::UnsupportedOperation),
+    ("Unsupported platform:", ErrorClass::UnsupportedPlatform),
+    ("Invalid argument:", ErrorClass::InvalidArgument),
+    ("Internal error:", ErrorClass::Internal),
+    ("Invalid selector:", ErrorClass::InvalidSelector),
+    ("Element is detached from DOM:", ErrorClass::ElementDetached),
+    ("Element is not visible:", ErrorClass::ElementNotVisible),
+    ("Element is not enabled:", ErrorClass::ElementNotEnabled),
+    (
+        "Element bounds are not stable:",
+        ErrorClass::ElementNotStable,
+    ),
+    (
+        "Element is obscured by another element:",
+        ErrorClass::ElementObscured,
+    ),
+    (
+        "Failed to scroll element into view:",
+        ErrorClass::ScrollFailed,
+    ),
+    ("Operation cancelled:", ErrorClass::OperationCancelled),
+    ("Verification failed:", ErrorClass::VerificationFailed),
+];
+
+impl ErrorClass {
+    /// Map an `AutomationError` directly to its class
+    pub fn from_automation_error(error: &AutomationError) -> Self {
+        match error {
+            AutomationError::ElementNotFound(_) => Self::ElementNotFound,
+            AutomationError::Timeout(_) => Self::Timeout,
+            AutomationError::PermissionDenied(_) => Self::PermissionDenied,
+            AutomationError::PlatformError(_) => Self::PlatformError,
+            AutomationError::UnsupportedOperation(_) => Self::UnsupportedOperation,
+            AutomationError::UnsupportedPlatform(_) => Self::UnsupportedPlatform,
+            AutomationError::InvalidArgument(_) => Self::InvalidArgument,
+            AutomationError::Internal(_) => Self::Internal,
+            AutomationError::InvalidSelector(_) => Self::InvalidSelector,
+            AutomationError::UIAutomationAPIError { is_retryable, .. } => {
+                if *is_retryable {
+                    Self::RetryableUIAutomationAPIError
+                } else {
+                    Self::UIAutomationAPIError
+                }
+            }
+            AutomationError::ElementDetached(_) => Self::ElementDetached,
+            AutomationError::ElementNotVisible(_) => Self::ElementNotVisible,
+            AutomationError::ElementNotEnabled(_) => Self::ElementNotEnabled,
+            AutomationError::ElementNotStable(_) => Self::ElementNotStable,
+            AutomationError::ElementObscured(_) => Self::ElementObscured,
+            AutomationError::ScrollFailed(_) => Self::ScrollFailed,
+            AutomationError::OperationCancelled(_) => Self::OperationCancelled,
+            AutomationError::VerificationFailed(_) => Self::VerificationFailed,
+        }
+    }
+
+    /// Classify a tool error from its structured data, as stored in a step
+    /// result's `error_details`. Tools put the class of the `AutomationError`
+    /// there as `error_class` before it is turned into a message (see
+    /// `helpers::build_element_not_found_error`).
+    pub fn from_error_data(data: &Value) -> Option<Self> {
+        data.get("error_class")
+            .and_then(|class| serde_json::from_value(class.clone()).ok())
+    }
+
+    /// Classify a tool error message as stored in a step result's `error` field.
+    ///
+    /// Structured payloads (an `error_class`, or the `error_type` of
+    /// `helpers::build_element_not_found_error`) take precedence over the
+    /// `AutomationError` display text.
+    pub fn classify(message: &str) -> Self {
+        let compact = message.replace(' ', "");
+        if let Some(class) = compact
+            .split_once("\"error_class\":\"")
+            .and_then(|(_, rest)| rest.split('"').next())
+            .and_then(|name| serde_json::from_value(Value::String(name.to_string())).ok())
+        {
+            return class;
+        }
+        if compact.contains("\"error_type\":\"ui_automation_api_failure\"") {
+            return if compact.contains("\"is_retryable\":true") {
+                Self::RetryableUIAutomationAPIError
+            } else {
+                Self::UIAutomationAPIError
+            };
+        }
+        if compact.contains("\"error_type\":\"ElementNotFound\"") {
+            return Self::ElementNotFound;
+        }
+        if message.contains("UI Automation API error:") {
+            return Self::UIAutomationAPIError;
+        }
+        if let Some((_, class)) = DISPLAY_PREFIXES
+            .iter()
+            .find(|(prefix, _)| message.contains(prefix))
+        {
+            return *class;
+        }
+
+        let lower = message.to_lowercase();
+        if lower.contains("timed out") || lower.contains("timeout") {
+            Self::Timeout
+        } else if lower.contains("cancelled") {
+            Self::OperationCancelled
+        } else {
+            Self::Other
+        }
+    }
+
+    /// Classify a failed step result produced by the sequence runner.
+    /// Group results are classified by their last failing sub-step.
+    pub fn from_step_result(result: &Value) -> Self {
+        fn step_error(result: &Value) -> Option<ErrorClass> {
+            result
+                .get("error_details")
+                .and_then(ErrorClass::from_error_data)
+                .or_else(|| {
+                    result
+                        .get("error")
+                        .and_then(|e| e.as_str())
+                        .map(ErrorClass::classify)
+                })
+        }
+        if let Some(class) = step_error(result) {
+            return class;
+        }
+        result
+            .get("results")
+            .and_then(|r| r.as_array())
+            .and_then(|results| results.iter().rev().find_map(step_error))
+            .unwrap_or(Self::Other)
+    }
+
+    /// Whether a filter entry in `on:` matches this class
+    pub fn matches(self, filter: ErrorClass) -> bool {
+        self == filter
+            || (filter == Self::UIAutomationAPIError && self == Self::RetryableUIAutomationAPIError)
+    }
+}
+
+/// Retry configuration for a single workflow step or group
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct RetryPolicy {
+    #[schemars(
+        description = "Maximum number of attempts, including the first one. 'attempts: 3' means up to two retries."
+    )]
+    pub attempts: u32,
+
+    #[serde(default)]
+    #[schemars(
+        description = "How the wait between attempts grows: 'fixed' or 'exponential' (default)."
+    )]
+    pub backoff: BackoffKind,
+
+    #[schemars(
+        description = "Delay before the first retry. Supports human-readable durations like '500ms', '2s'. Defaults to 500ms."
+    )]
+    pub delay: Option<String>,
+
+    #[schemars(
+        description = "Upper bound for any single wait between attempts, e.g. '10s'. Defaults to 30s."
+    )]
+    pub max_delay: Option<String>,
+
+    #[schemars(description = "Growth factor for exponential backoff. Defaults to 2.0.")]
+    pub multiplier: Option<f64>,
+
+    #[schemars(
+        description = "Random spread applied to each delay as a fraction (0.0-1.0). 0.2 turns a 1s delay into 0.8s-1.2s."
+    )]
+    pub jitter: Option<f64>,
+
+    #[schemars(
+        description = "Only retry when the failure matches one of these error classes (AutomationError variant names, e.g. ElementNotFound, Timeout, UIAutomationAPIError, RetryableUIAutomationAPIError). Retries every failure when omitted."
+    )]
+    pub on: Option<Vec<ErrorClass>>,
+
+    #[schemars(
+        description = "Steps executed before each retry, e.g. to dismiss a popup. Failures here are logged but do not stop the retry."
+    )]
+    pub before_retry: Option<Vec<ToolCall>>,
+}
+
+impl RetryPolicy {
+    /// Build the policy equivalent to the legacy `retries: N` field:
+    /// N immediate retries with a fixed 500ms pause and no error filter.
+    pub fn from_retries(retries: u32) -> Self {
+        Self {
+            attempts: retries.saturating_add(1),
+            backoff: BackoffKind::Fixed,
+            delay: None,
+            max_delay: None,
+            multiplier: None,
+            jitter: None,
+            on: None,
+            before_retry: None,
+        }
+    }
+
+    /// Number of retries allowed after the first attempt
+    pub fn max_retries(&self) -> u32 {
+        self.attempts.saturating_sub(1)
+    }
+
+    /// Whether a failure of the given class should be retried
+    pub fn should_retry(&self, class: ErrorClass) -> bool {
+        match &self.on {
+            Some(filters) => filters.iter().any(|f| class.matches(*f)),
+            None => true,
+        }
+    }
+
+    /// Delay to wait before the given retry (1-based), with jitter applied
+    pub fn delay_for_retry(&self, retry: u32) -> Duration {
+        self.delay_for_retry_with(retry, random_unit())
+    }
+
+    /// Deterministic variant of `delay_for_retry`; `unit` is a sample from [0, 1)
+    pub fn delay_for_retry_with(&self, retry: u32, unit: f64) -> Duration {
+        let base = self.duration_or(&self.delay, DEFAULT_DELAY_MS) as f64;
+        let max = self.duration_or(&self.max_delay, DEFAULT_MAX_DELAY_MS) as f64;
+
+        let mut delay = match self.backoff {
+            BackoffKind::Fixed => base,
+            BackoffKind::Exponential => {
+                let multiplier = self.multiplier.unwrap_or(DEFAULT_MULTIPLIER).max(1.0);
+                base * multiplier.powi(retry.saturating_sub(1).min(i32::MAX as u32) as i32)
+            }
+        };
+
+        if let Some(jitter) = self.jitter {
+            let jitter = jitter.clamp(0.0, 1.0);
+            delay *= 1.0 - jitter + 2.0 * jitter * unit;
+        }
+
+        Duration::from_millis(delay.min(max).max(0.0) as u64)
+    }
+
+    fn duration_or(&self, value: &Option<String>, default_ms: u64) -> u64 {
+        match value {
+            Some(s) => parse_duration(s).unwrap_or_else(|e| {
+                warn!("Failed to parse retry duration '{}': {}", s, e);
+                default_ms
+            }),
+            None => default_ms,
+        }
+    }
+}
+
+/// Uniform sample from [0, 1) without pulling in a dedicated RNG dependency
+fn random_unit() -> f64 {
+    (uuid::Uuid::new_v4().as_u128() >> 75) as f64 / (1u64 << 53) as f64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn policy(yaml: &str) -> RetryPolicy {
+        serde_yaml::from_str(yaml).unwrap()
+    }
+
+    #[test]
+    fn test_exponential_backoff_is_capped() {
+        let p = policy("attempts: 5\ndelay: 100ms\nmax_delay: 350ms");
+        assert_eq!(p.delay_for_retry_with(1, 0.5), Duration::from_millis(100));
+        assert_eq!(p.delay_for_retry_with(2, 0.5), Duration::from_millis(200));
+        assert_eq!(p.delay_for_retry_with(3, 0.5), Duration::from_millis(350));
+        assert_eq!(p.max_retries(), 4);
+    }
+
+    #[test]
+    fn test_fixed_backoff_with_jitter() {
+        let p = policy("attempts: 3\nbackoff: fixed\ndelay: 1s\njitter: 0.5");
+        assert_eq!(p.delay_for_retry_with(2, 0.0), Duration::from_millis(500));
+        assert_eq!(p.delay_for_retry_with(2, 0.5), Duration::from_millis(1000));
+        let d = p.delay_for_retry(2);
+        assert!(d >= Duration::from_millis(500) && d <= Duration::from_millis(1500));
+    }
+
+    #[test]
+    fn test_legacy_retries_mapping() {
+        let p = RetryPolicy::from_retries(2);
+        assert_eq!(p.max_retries(), 2);
+        assert_eq!(p.delay_for_retry_with(2, 0.9), Duration::from_millis(500));
+        assert!(p.should_retry(ErrorClass::Other));
+    }
+
+    #[test]
+    fn test_error_class_filters() {
+        let p = policy("attempts: 2\non: [ElementNotFound, UIAutomationAPIError]");
+        assert!(p.should_retry(ErrorClass::ElementNotFound));
+        assert!(p.should_retry(ErrorClass::RetryableUIAutomationAPIError));
+        assert!(!p.should_retry(ErrorClass::InvalidSelector));
+
+        let p = policy("attempts: 2\non: ['UIAutomationAPIError{is_retryable}']");
+        assert!(p.should_retry(ErrorClass::RetryableUIAutomationAPIError));
+        assert!(!p.should_retry(ErrorClass::UIAutomationAPIError));
+    }
+
+    #[test]
+    fn test_classify_error_messages() {
+        assert_eq!(
+            ErrorClass::classify(
+                r#"-32602: Element not found({"error_type":"ElementNotFound","primary_selector":"x"})"#
+            ),
+            ErrorClass::ElementNotFound
+        );
+        assert_eq!(
+            ErrorClass::classify(
+                r#"-32603: UI Automation API failure({"error_type":"ui_automation_api_failure","is_retryable":true})"#
+            ),
+            ErrorClass::RetryableUIAutomationAPIError
+        );
+        assert_eq!(
+            ErrorClass::classify("-32603: Operation timed out: waiting for window"),
+            ErrorClass::Timeout
+        );
+        assert_eq!(
+            ErrorClass::classify("-32603: Invalid selector: role:"),
+            ErrorClass::InvalidSelector
+        );
+        assert_eq!(ErrorClass::classify("boom"), ErrorClass::Other);
+    }
+
+    #[test]
+    fn test_classify_group_result() {
+        let group = serde_json::json!({
+            "group_name": "login",
+            "status": "executed_with_partial_errors",
+            "results": [
+                {"status": "executed_without_error"},
+                {"status": "error", "error": "-32603: Element is not enabled: Submit"}
+            ]
+        });
+        assert_eq!(
+            ErrorClass::from_step_result(&group),
+            ErrorClass::ElementNotEnabled
+        );
+        assert_eq!(
+            ErrorClass::from_step_result(&serde_json::json!({})),
+            ErrorClass::Other
+        );
+    }
+
+    #[test]
+    fn test_from_automation_error() {
+        let err = AutomationError::UIAutomationAPIError {
+            message: "busy".into(),
+            com_error: None,
+            operation: "find".into(),
+            is_retryable: true,
+        };
+        assert_eq!(
+            ErrorClass::from_automation_error(&err),
+            ErrorClass::RetryableUIAutomationAPIError
+        );
+
+        // The way a failed tool call reaches the retry loop: an McpError whose
+        // data keeps the class, rendered into the step result
+        let error = crate::helpers::build_element_not_found_error(
+            "role:Button|name:Save",
+            None,
+            None,
+            anyhow::Error::new(err),
+        );
+        let step = serde_json::json!({
+            "status": "error",
+            "error": error.to_string(),
+            "error_details": error.data,
+        });
+        assert_eq!(
+            ErrorClass::from_step_result(&step),
+            ErrorClass::RetryableUIAutomationAPIError
+        );
+        assert_eq!(
+            ErrorClass::classify(&error.to_string()),
+            ErrorClass::RetryableUIAutomationAPIError
+        );
+    }
+}
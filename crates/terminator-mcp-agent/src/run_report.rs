@@ -0,0 +1,691 @@
+//! Self-contained HTML report of one workflow run.
+//!
+//! Reconstructing a failed run from its execution logs means opening a JSON
+//! file, a `.ts` snippet and a few PNGs per step. The report puts all of it in
+//! one static HTML file that can be attached to a ticket: a timeline of the
+//! steps, and for every step its request and response, before/after
+//! screenshots (embedded as data URIs), the step's `expected_ui_changes` next
+//! to the UI diff the tool returned, captured logs and the TypeScript snippet.
+//!
+//! Runs are looked up in the execution history (see `execution_history`); the
+//! steps' execution logs are read from the paths recorded there. By default
+//! the report is written next to those logs with the same date prefix, so
+//! retention cleans it up together with them.
+
+use crate::execution_history::{History, RunQuery, RunRecord, RunStatus, StepRecord};
+use crate::execution_logger::{self, CapturedLogEntry, ExecutionLog};
+use crate::tools::validate_workflow::parse_workflow_content;
+use anyhow::{anyhow, Context, Result};
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use chrono::{DateTime, Utc};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::fmt::Write as _;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Longest request/response/log block rendered before truncating
+const MAX_BLOCK_CHARS: usize = 100_000;
+
+/// A screenshot of a step, embedded when its file could be read
+#[derive(Debug, Clone)]
+pub struct ReportScreenshot {
+    /// `before`, `after`, `after 2`, ...
+    pub label: String,
+    pub path: PathBuf,
+    pub data_uri: Option<String>,
+}
+
+#[derive(Debug)]
+pub struct ReportStep {
+    pub record: StepRecord,
+    /// `None` when the execution log was deleted or unreadable
+    pub log: Option<ExecutionLog>,
+    pub typescript: Option<String>,
+    pub expected_ui_changes: Option<String>,
+    pub screenshots: Vec<ReportScreenshot>,
+}
+
+impl ReportStep {
+    /// `ui_diff` and `has_ui_changes` returned by the tool, if it captured a diff
+    pub fn actual_ui_diff(&self) -> Option<(Option<bool>, String)> {
+        let result = self.log.as_ref()?.response.result.as_ref()?;
+        let items = result
+            .get("content")
+            .and_then(|c| c.as_array())
+            .map(|items| items.iter().collect::<Vec<_>>())
+            .unwrap_or_else(|| vec![result]);
+        items.into_iter().find_map(|item| {
+            let diff = item.get("ui_diff")?;
+            let text = match diff {
+                Value::String(s) => s.clone(),
+                other => serde_json::to_string_pretty(other).unwrap_or_default(),
+            };
+            Some((item.get("has_ui_changes").and_then(|v| v.as_bool()), text))
+        })
+    }
+}
+
+#[derive(Debug)]
+pub struct RunReport {
+    pub run: RunRecord,
+    pub steps: Vec<ReportStep>,
+}
+
+impl RunReport {
+    /// Read the execution logs, snippets and screenshots of a run's steps
+    pub fn load(run: RunRecord) -> Self {
+        let expected = run
+            .workflow
+            .as_deref()
+            .map(expected_ui_changes)
+            .unwrap_or_default();
+        let steps = run
+            .steps
+            .iter()
+            .map(|record| load_step(record, &expected))
+            .collect();
+        Self { run, steps }
+    }
+
+    /// Render the report as a single HTML document with no external resources
+    pub fn render(&self) -> String {
+        let run = &self.run;
+        let title = format!(
+            "{} — {}",
+            run.workflow_name()
+                .unwrap_or_else(|| "workflow".to_string()),
+            run.run_id
+        );
+
+        let mut html = String::new();
+        let _ = write!(
+            html,
+            "<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n<title>{}</title>\n<style>{}</style>\n</head>\n<body>\n",
+            escape(&title),
+            STYLE
+        );
+        let _ = write!(
+            html,
+            "<h1>{}</h1>\n<p class=\"status {}\">{}</p>\n<table class=\"meta\">\n",
+            escape(&title),
+            status_class(run.status),
+            status_text(run.status)
+        );
+        let mut meta = |name: &str, value: Option<String>| {
+            if let Some(value) = value {
+                let _ = writeln!(
+                    html,
+                    "<tr><th>{}</th><td>{}</td></tr>",
+                    name,
+                    escape(&value)
+                );
+            }
+        };
+        meta("Run", Some(run.run_id.clone()));
+        meta("Workflow", run.workflow.clone());
+        meta("Workflow id", run.workflow_id.clone());
+        meta("Parent run", run.parent_run_id.clone());
+        meta("Client", run.client.clone());
+        meta("Started", Some(run.started_at.to_rfc3339()));
+        meta("Finished", run.finished_at.map(|t| t.to_rfc3339()));
+        meta("Duration", run.duration_ms.map(format_ms));
+        meta("Steps", Some(run.steps.len().to_string()));
+        meta("Error", run.error.clone());
+        html.push_str("</table>\n");
+
+        if self.steps.is_empty() {
+            html.push_str("<p class=\"empty\">No steps recorded.</p>\n");
+        } else {
+            self.render_timeline(&mut html);
+            for (i, step) in self.steps.iter().enumerate() {
+                render_step(&mut html, i, step);
+            }
+        }
+
+        let _ = write!(
+            html,
+            "<footer>Generated {} by terminator-mcp-agent {}</footer>\n</body>\n</html>\n",
+            Utc::now().to_rfc3339(),
+            env!("CARGO_PKG_VERSION")
+        );
+        html
+    }
+
+    fn render_timeline(&self, html: &mut String) {
+        let offsets: Vec<u64> = self
+            .steps
+            .iter()
+            .map(|step| {
+                DateTime::parse_from_rfc3339(&step.record.started_at)
+                    .map(|t| (t.with_timezone(&Utc) - self.run.started_at).num_milliseconds())
+                    .unwrap_or(0)
+                    .max(0) as u64
+            })
+            .collect();
+        let span = self
+            .steps
+            .iter()
+            .zip(&offsets)
+            .map(|(step, offset)| offset + step.record.duration_ms)
+            .chain(self.run.duration_ms)
+            .max()
+            .unwrap_or(0)
+            .max(1) as f64;
+
+        html.push_str("<h2>Timeline</h2>\n<div class=\"timeline\">\n");
+        for (i, (step, offset)) in self.steps.iter().zip(&offsets).enumerate() {
+            let left = *offset as f64 / span * 100.0;
+            let width = (step.record.duration_ms as f64 / span * 100.0).max(0.5);
+            let _ = writeln!(
+                html,
+                "<a class=\"row\" href=\"#step-{i}\"><span class=\"label\">{}. {}</span><span class=\"track\"><span class=\"bar {}\" style=\"left:{left:.2}%;width:{width:.2}%\"></span></span><span class=\"ms\">{}</span></a>",
+                i + 1,
+                escape(&step.record.label()),
+                if step.record.success { "ok" } else { "failed" },
+                format_ms(step.record.duration_ms)
+            );
+        }
+        html.push_str("</div>\n");
+    }
+}
+
+fn load_step(record: &StepRecord, expected: &HashMap<String, String>) -> ReportStep {
+    let log_path = record.log_path.as_deref().map(PathBuf::from);
+    let log: Option<ExecutionLog> = log_path
+        .as_deref()
+        .and_then(|path| fs::read_to_string(path).ok())
+        .and_then(|content| serde_json::from_str(&content).ok());
+
+    let typescript = log_path
+        .as_deref()
+        .and_then(|path| fs::read_to_string(path.with_extension("ts")).ok())
+        .or_else(|| {
+            let log = log.as_ref()?;
+            let result = match (&log.response.result, &log.response.error) {
+                (_, Some(error)) => Err(error.as_str()),
+                (Some(result), None) => Ok(result),
+                (None, None) => Ok(&Value::Null),
+            };
+            Some(execution_logger::generate_typescript_snippet(
+                &log.tool_name,
+                &log.request,
+                result,
+            ))
+        });
+
+    let expected_ui_changes = log
+        .as_ref()
+        .and_then(|log| log.request.get("expected_ui_changes"))
+        .and_then(|v| v.as_str())
+        .map(str::to_string)
+        .or_else(|| {
+            record
+                .step_id
+                .as_ref()
+                .and_then(|id| expected.get(id))
+                .cloned()
+        });
+
+    // Prefer the log's before/after refs for labels; fall back to the paths in the index
+    let screenshots = match (&log, log_path.as_deref().and_then(Path::parent)) {
+        (
+            Some(ExecutionLog {
+                screenshots: Some(refs),
+                ..
+            }),
+            Some(dir),
+        ) => refs
+            .before
+            .iter()
+            .map(|name| ("before".to_string(), dir.join(name)))
+            .chain(refs.after.iter().enumerate().map(|(i, name)| {
+                let label = match i {
+                    0 => "after".to_string(),
+                    n => format!("after {}", n + 1),
+                };
+                (label, dir.join(name))
+            }))
+            .collect(),
+        _ => record
+            .screenshots
+            .iter()
+            .map(|path| ("screenshot".to_string(), PathBuf::from(path)))
+            .collect::<Vec<_>>(),
+    }
+    .into_iter()
+    .map(|(label, path)| ReportScreenshot {
+        data_uri: fs::read(&path)
+            .ok()
+            .map(|bytes| format!("data:image/png;base64,{}", BASE64.encode(bytes))),
+        label,
+        path,
+    })
+    .collect();
+
+    ReportStep {
+        record: record.clone(),
+        log,
+        typescript,
+        expected_ui_changes,
+        screenshots,
+    }
+}
+
+/// `expected_ui_changes` of the steps of a workflow file, by step id. Empty for
+/// remote, TypeScript or unreadable workflows.
+fn expected_ui_changes(workflow_url: &str) -> HashMap<String, String> {
+    let Some(path) = workflow_url.strip_prefix("file://") else {
+        return HashMap::new();
+    };
+    // Handle Windows file:/// URLs (strip leading / before drive letter like /C:)
+    let path = if path.starts_with('/') && path.len() > 2 && path.chars().nth(2) == Some(':') {
+        &path[1..]
+    } else {
+        path
+    };
+    let Some(workflow) = fs::read_to_string(path)
+        .ok()
+        .and_then(|content| parse_workflow_content(&content).ok())
+    else {
+        return HashMap::new();
+    };
+    workflow
+        .steps
+        .iter()
+        .flatten()
+        .chain(workflow.troubleshooting.iter().flatten())
+        .filter_map(|step| Some((step.id.clone()?, step.expected_ui_changes.clone()?)))
+        .collect()
+}
+
+fn render_step(html: &mut String, index: usize, step: &ReportStep) {
+    let record = &step.record;
+    let _ = write!(
+        html,
+        "<details id=\"step-{index}\" class=\"step {}\"{}>\n<summary><span class=\"mark\">{}</span> {}. {} <code>{}</code> <span class=\"ms\">{}</span>",
+        if record.success { "ok" } else { "failed" },
+        if record.success { "" } else { " open" },
+        if record.success { "✓" } else { "✗" },
+        index + 1,
+        escape(&record.label()),
+        escape(&record.tool_name),
+        format_ms(record.duration_ms)
+    );
+    if let Some(attempt) = record.retry_attempt.filter(|a| *a > 0) {
+        let _ = write!(html, " <span class=\"retry\">retry {attempt}</span>");
+    }
+    html.push_str("</summary>\n");
+
+    if let Some(error) = &record.error {
+        let _ = writeln!(html, "<pre class=\"error\">{}</pre>", escape(error));
+    }
+    if let Some(path) = &record.log_path {
+        let _ = writeln!(html, "<p class=\"path\">{}</p>", escape(path));
+    }
+
+    match &step.log {
+        Some(log) => {
+            section(html, "Request", &pretty(&log.request));
+            match &log.response.result {
+                Some(result) => section(html, "Response", &pretty(result)),
+                None => section(html, "Response", &log.response.status),
+            }
+        }
+        None => html.push_str("<p class=\"empty\">Execution log not available.</p>\n"),
+    }
+
+    if !step.screenshots.is_empty() {
+        html.push_str("<h3>Screenshots</h3>\n<div class=\"shots\">\n");
+        for shot in &step.screenshots {
+            match &shot.data_uri {
+                Some(uri) => {
+                    let _ = writeln!(
+                        html,
+                        "<figure><img src=\"{uri}\" alt=\"{0}\"><figcaption>{0}</figcaption></figure>",
+                        escape(&shot.label)
+                    );
+                }
+                None => {
+                    let _ = writeln!(
+                        html,
+                        "<figure class=\"missing\"><figcaption>{} — missing: {}</figcaption></figure>",
+                        escape(&shot.label),
+                        escape(&shot.path.display().to_string())
+                    );
+                }
+            }
+        }
+        html.push_str("</div>\n");
+    }
+
+    let actual = step.actual_ui_diff();
+    if step.expected_ui_changes.is_some() || actual.is_some() {
+        html.push_str("<h3>UI changes</h3>\n<div class=\"diff\">\n");
+        let _ = writeln!(
+            html,
+            "<div><h4>Expected</h4><pre>{}</pre></div>",
+            escape(
+                step.expected_ui_changes
+                    .as_deref()
+                    .unwrap_or("(not specified)")
+            )
+        );
+        let (heading, text) = match &actual {
+            Some((Some(false), text)) => ("Actual (no changes)", text.as_str()),
+            Some((_, text)) => ("Actual", text.as_str()),
+            None => ("Actual", "(no UI diff captured)"),
+        };
+        let _ = writeln!(
+            html,
+            "<div><h4>{heading}</h4><pre>{}</pre></div>",
+            escape(truncate(text))
+        );
+        html.push_str("</div>\n");
+    }
+
+    if let Some(logs) = step.log.as_ref().and_then(|log| log.logs.as_ref()) {
+        render_logs(html, logs);
+    }
+    if let Some(typescript) = &step.typescript {
+        section(html, "TypeScript", typescript);
+    }
+    html.push_str("</details>\n");
+}
+
+fn render_logs(html: &mut String, logs: &[CapturedLogEntry]) {
+    if logs.is_empty() {
+        return;
+    }
+    html.push_str("<h3>Logs</h3>\n<table class=\"logs\">\n");
+    for entry in logs {
+        let _ = writeln!(
+            html,
+            "<tr class=\"{}\"><td>{}</td><td>{}</td><td>{}</td></tr>",
+            escape(&entry.level.to_lowercase()),
+            entry.timestamp.format("%H:%M:%S%.3f"),
+            escape(&entry.level),
+            escape(truncate(&entry.message))
+        );
+    }
+    html.push_str("</table>\n");
+}
+
+fn section(html: &mut String, title: &str, body: &str) {
+    let _ = writeln!(
+        html,
+        "<h3>{title}</h3>\n<pre>{}</pre>",
+        escape(truncate(body))
+    );
+}
+
+fn pretty(value: &Value) -> String {
+    serde_json::to_string_pretty(value).unwrap_or_default()
+}
+
+fn truncate(text: &str) -> &str {
+    match text.char_indices().nth(MAX_BLOCK_CHARS) {
+        Some((end, _)) => &text[..end],
+        None => text,
+    }
+}
+
+fn escape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for ch in text.chars() {
+        match ch {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&#39;"),
+            _ => out.push(ch),
+        }
+    }
+    out
+}
+
+fn format_ms(ms: u64) -> String {
+    if ms < 1000 {
+        format!("{ms} ms")
+    } else {
+        format!("{:.1} s", ms as f64 / 1000.0)
+    }
+}
+
+fn status_text(status: RunStatus) -> &'static str {
+    match status {
+        RunStatus::Running => "running",
+        RunStatus::Completed => "completed",
+        RunStatus::Failed => "failed",
+        RunStatus::Cancelled => "cancelled",
+    }
+}
+
+fn status_class(status: RunStatus) -> &'static str {
+    match status {
+        RunStatus::Completed => "ok",
+        RunStatus::Failed => "failed",
+        RunStatus::Running | RunStatus::Cancelled => "warn",
+    }
+}
+
+/// Run to report on: `run_id` (or a unique prefix), else the latest run,
+/// optionally of `workflow`
+pub fn find_run(
+    history: &History,
+    run_id: Option<&str>,
+    workflow: Option<&str>,
+) -> Result<RunRecord> {
+    match run_id {
+        Some(id) => history
+            .run(id)?
+            .ok_or_else(|| anyhow!("No run '{id}' in the execution history")),
+        None => history
+            .query(&RunQuery {
+                workflow: workflow.map(str::to_string),
+                limit: 1,
+                ..Default::default()
+            })?
+            .into_iter()
+            .next()
+            .ok_or_else(|| match workflow {
+                Some(w) => anyhow!("No recorded runs of workflow '{w}'"),
+                None => anyhow!("No recorded runs"),
+            }),
+    }
+}
+
+/// Where a run's report goes by default: its execution log directory, with the
+/// run's start time as prefix so retention removes it with the logs
+pub fn default_report_path(run: &RunRecord) -> PathBuf {
+    let dir = match &run.workflow_id {
+        Some(id) => execution_logger::get_workflow_executions_dir(id),
+        None => execution_logger::get_executions_dir(),
+    };
+    let short_id: String = run.run_id.chars().take(12).collect();
+    dir.join(format!(
+        "{}_report_{}.html",
+        run.started_at
+            .with_timezone(&chrono::Local)
+            .format("%Y%m%d_%H%M%S"),
+        short_id.replace(|c: char| !c.is_ascii_alphanumeric() && c != '-', "_")
+    ))
+}
+
+/// Render the report of a run and write it to `output` (or the default path).
+/// Returns the path written.
+pub fn write_report(run: RunRecord, output: Option<&Path>) -> Result<PathBuf> {
+    let path = output
+        .map(Path::to_path_buf)
+        .unwrap_or_else(|| default_report_path(&run));
+    let html = RunReport::load(run).render();
+    if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(&path, html).with_context(|| format!("Failed to write {}", path.display()))?;
+    Ok(path)
+}
+
+const STYLE: &str = r#"
+body{font:14px/1.45 system-ui,-apple-system,"Segoe UI",sans-serif;margin:24px auto;max-width:1200px;padding:0 16px;color:#1f2328}
+h1{font-size:20px;margin-bottom:4px}h2{font-size:16px;margin-top:28px}h3{font-size:13px;margin:14px 0 6px;color:#57606a;text-transform:uppercase}h4{margin:0 0 4px;font-size:13px}
+.status{display:inline-block;padding:2px 10px;border-radius:10px;font-weight:600;margin:0 0 12px}
+.status.ok{background:#dafbe1;color:#1a7f37}.status.failed{background:#ffebe9;color:#cf222e}.status.warn{background:#fff8c5;color:#9a6700}
+table.meta th{text-align:left;padding-right:16px;color:#57606a;font-weight:500}table.meta td{word-break:break-all}
+.timeline .row{display:flex;align-items:center;gap:8px;color:inherit;text-decoration:none;padding:2px 0}
+.timeline .label{width:260px;overflow:hidden;text-overflow:ellipsis;white-space:nowrap}
+.timeline .track{flex:1;position:relative;height:12px;background:#f6f8fa;border-radius:3px}
+.timeline .bar{position:absolute;top:0;bottom:0;border-radius:3px;background:#2da44e}.timeline .bar.failed{background:#cf222e}
+.ms{color:#57606a;font-size:12px;white-space:nowrap}.timeline .ms{width:70px;text-align:right}
+details.step{border:1px solid #d0d7de;border-radius:6px;margin:10px 0;padding:8px 12px}details.step.failed{border-color:#ff8182}
+summary{cursor:pointer;font-weight:600}.mark{display:inline-block;width:1em}.ok .mark{color:#1a7f37}.failed .mark{color:#cf222e}
+.retry{background:#fff8c5;border-radius:8px;padding:0 6px;font-size:12px;font-weight:500}
+pre{background:#f6f8fa;padding:8px;border-radius:4px;overflow:auto;max-height:420px;font-size:12px;white-space:pre-wrap;word-break:break-word}
+pre.error{background:#ffebe9;color:#82071e}.path{font-family:monospace;font-size:12px;color:#57606a;word-break:break-all}
+.shots{display:flex;flex-wrap:wrap;gap:12px}.shots figure{margin:0;max-width:48%}.shots img{max-width:100%;border:1px solid #d0d7de}
+.shots figcaption{font-size:12px;color:#57606a}.missing figcaption{color:#cf222e}
+.diff{display:grid;grid-template-columns:1fr 1fr;gap:12px}
+table.logs{border-collapse:collapse;font:12px monospace;width:100%}table.logs td{padding:1px 8px 1px 0;vertical-align:top}
+table.logs tr.error{color:#cf222e}table.logs tr.warn{color:#9a6700}
+.empty{color:#57606a;font-style:italic}footer{margin-top:32px;color:#8c959f;font-size:12px}
+"#;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::execution_logger::{ExecutionResponse, ScreenshotRefs};
+    use serde_json::json;
+
+    fn run_with_step(dir: &Path) -> RunRecord {
+        let log = ExecutionLog {
+            timestamp: "2025-03-10T12:00:01+00:00".to_string(),
+            workflow_id: None,
+            execution_id: None,
+            step_id: Some("submit".to_string()),
+            step_index: Some(1),
+            retry_attempt: Some(1),
+            tool_name: "click_element".to_string(),
+            request: json!({"selector": "role:Button && name:<Submit>"}),
+            response: ExecutionResponse {
+                status: "executed_without_error".to_string(),
+                duration_ms: 250,
+                result: Some(json!({"content": [{
+                    "action": "click",
+                    "ui_diff": "+ Dialog: Saved",
+                    "has_ui_changes": true
+                }]})),
+                error: None,
+            },
+            screenshots: Some(ScreenshotRefs {
+                before: None,
+                after: vec!["step_after.png".to_string()],
+            }),
+            logs: Some(vec![CapturedLogEntry {
+                timestamp: Utc::now(),
+                level: "WARN".to_string(),
+                message: "slow <button>".to_string(),
+            }]),
+            policy: None,
+            client: None,
+        };
+        let log_path = dir.join("step.json");
+        fs::write(&log_path, serde_json::to_string(&log).unwrap()).unwrap();
+        fs::write(dir.join("step_after.png"), [0x89, b'P', b'N', b'G']).unwrap();
+
+        let workflow_path = dir.join("invoice.yml");
+        fs::write(
+            &workflow_path,
+            "steps:\n  - id: submit\n    tool_name: click_element\n    arguments:\n      selector: \"role:Button\"\n    expected_ui_changes: \"Dialog 'Saved' appears\"\n",
+        )
+        .unwrap();
+
+        RunRecord {
+            run_id: "run-1".to_string(),
+            parent_run_id: None,
+            workflow: Some(format!("file://{}", workflow_path.display())),
+            workflow_id: None,
+            client: None,
+            status: RunStatus::Failed,
+            started_at: DateTime::parse_from_rfc3339("2025-03-10T12:00:00Z")
+                .unwrap()
+                .with_timezone(&Utc),
+            finished_at: None,
+            duration_ms: Some(2000),
+            error: Some("step failed".to_string()),
+            log_path: None,
+            steps: vec![StepRecord {
+                step_index: Some(1),
+                step_id: Some("submit".to_string()),
+                tool_name: "click_element".to_string(),
+                started_at: log.timestamp.clone(),
+                duration_ms: 250,
+                success: true,
+                error: None,
+                retry_attempt: Some(1),
+                log_path: Some(log_path.display().to_string()),
+                screenshots: vec![dir.join("step_after.png").display().to_string()],
+            }],
+        }
+    }
+
+    #[test]
+    fn test_load_step_details() {
+        let dir = tempfile::tempdir().unwrap();
+        let report = RunReport::load(run_with_step(dir.path()));
+        let step = &report.steps[0];
+        assert!(step.log.is_some());
+        assert_eq!(
+            step.expected_ui_changes.as_deref(),
+            Some("Dialog 'Saved' appears")
+        );
+        assert_eq!(
+            step.actual_ui_diff(),
+            Some((Some(true), "+ Dialog: Saved".to_string()))
+        );
+        assert_eq!(step.screenshots.len(), 1);
+        assert_eq!(step.screenshots[0].label, "after");
+        assert_eq!(
+            step.screenshots[0].data_uri.as_deref(),
+            Some("data:image/png;base64,iVBORw==")
+        );
+        // No .ts file next to the log: the snippet is regenerated
+        assert!(step
+            .typescript
+            .as_deref()
+            .is_some_and(|ts| ts.contains("click")));
+    }
+
+    #[test]
+    fn test_render_is_self_contained_and_escaped() {
+        let dir = tempfile::tempdir().unwrap();
+        let html = RunReport::load(run_with_step(dir.path())).render();
+        assert!(html.starts_with("<!DOCTYPE html>"));
+        assert!(html.contains("id=\"step-0\""));
+        assert!(html.contains("left:50.00%"));
+        assert!(html.contains("data:image/png;base64,"));
+        assert!(html.contains("name:&lt;Submit&gt;"));
+        assert!(html.contains("slow &lt;button&gt;"));
+        assert!(html.contains("retry 1"));
+        assert!(!html.contains("<Submit>"));
+        assert!(!html.contains("src=\"http"));
+    }
+
+    #[test]
+    fn test_missing_log_and_default_path() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut run = run_with_step(dir.path());
+        run.steps[0].log_path = Some(dir.path().join("gone.json").display().to_string());
+        run.workflow_id = Some("invoice".to_string());
+        let report = RunReport::load(run.clone());
+        assert!(report.steps[0].log.is_none());
+        assert_eq!(report.steps[0].screenshots[0].label, "screenshot");
+        assert!(report.render().contains("Execution log not available"));
+
+        let path = default_report_path(&run);
+        let name = path.file_name().unwrap().to_string_lossy().to_string();
+        assert!(name.ends_with("_report_run-1.html"), "{name}");
+        assert!(name.starts_with("2025031"), "{name}");
+        assert!(path.starts_with(execution_logger::get_workflow_executions_dir("invoice")));
+    }
+}
@@ -0,0 +1,718 @@
+//! Built-in workflow scheduler
+//!
+//! Runs the workflows in `--workflows-dir` that have a cron trigger (see
+//! [`crate::workflow_triggers`]). Expressions take 5 fields or 6 fields
+//! (leading seconds) and are evaluated in the IANA `timezone` of the workflow
+//! (UTC when omitted).
+//!
+//! A workflow never overlaps itself: a run that comes due while the previous
+//! one is still going is skipped. Runs that were missed while the agent was
+//! down (or the machine asleep) are skipped by default; `missed_runs: run_once`
+//! runs the workflow once on catch-up instead. Last and next run times and the
+//! paused flag are persisted to `schedules.json` so they survive restarts.
+
+use crate::workflow_triggers::{self, RunRequest, RunWorkflow, WorkflowKind};
+use crate::workflow_typescript::TriggerConfig;
+use chrono::{DateTime, Timelike, Utc};
+use chrono_tz::Tz;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::{Duration, Instant};
+use tokio::sync::Notify;
+use tracing::{debug, info, warn};
+use uuid::Uuid;
+
+pub const STATE_FILE_NAME: &str = "schedules.json";
+
+/// How often the workflows directory is rescanned for added/changed workflows
+const RESCAN_INTERVAL: Duration = Duration::from_secs(30);
+
+/// A run this late is considered missed rather than merely delayed
+const MISSED_RUN_GRACE: chrono::Duration = chrono::Duration::seconds(60);
+
+/// Default location of the persisted schedule state
+///   Windows: %LOCALAPPDATA%\mediar\schedules.json
+///   macOS: ~/Library/Application Support/mediar/schedules.json
+///   Linux: ~/.local/share/mediar/schedules.json
+pub fn default_state_path() -> Option<PathBuf> {
+    dirs::data_local_dir().map(|d| d.join("mediar").join(STATE_FILE_NAME))
+}
+
+/// What to do with runs that came due while the agent wasn't running
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MissedRunPolicy {
+    /// Drop missed runs and wait for the next occurrence
+    #[default]
+    Skip,
+    /// Run once on catch-up, however many occurrences were missed
+    RunOnce,
+}
+
+/// A cron expression bound to a timezone
+#[derive(Debug, Clone)]
+pub struct Schedule {
+    cron: croner::Cron,
+    timezone: Tz,
+}
+
+impl Schedule {
+    pub fn parse(expression: &str, timezone: Option<&str>) -> Result<Self, String> {
+        let cron = croner::Cron::new(expression)
+            .with_seconds_optional()
+            .parse()
+            .map_err(|e| format!("Invalid cron expression '{expression}': {e}"))?;
+        let timezone = match timezone {
+            Some(name) => name
+                .parse::<Tz>()
+                .map_err(|_| format!("Unknown timezone '{name}'"))?,
+            None => Tz::UTC,
+        };
+        Ok(Self { cron, timezone })
+    }
+
+    /// First occurrence strictly after `after`
+    pub fn next_after(&self, after: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        let after = after.with_nanosecond(0).unwrap_or(after);
+        self.cron
+            .find_next_occurrence(&after.with_timezone(&self.timezone), false)
+            .ok()
+            .map(|next| next.with_timezone(&Utc))
+    }
+}
+
+/// A workflow with a cron trigger
+#[derive(Debug, Clone, Serialize)]
+pub struct ScheduledWorkflow {
+    /// File stem for YAML workflows, folder name for TypeScript workflows
+    pub id: String,
+    pub path: PathBuf,
+    pub kind: WorkflowKind,
+    pub schedule: String,
+    pub timezone: String,
+    pub missed_runs: MissedRunPolicy,
+}
+
+impl ScheduledWorkflow {
+    pub fn url(&self) -> String {
+        workflow_triggers::file_url(&self.path)
+    }
+}
+
+/// Persisted per-workflow state
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ScheduleState {
+    #[serde(default)]
+    pub paused: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub next_run: Option<DateTime<Utc>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_run: Option<DateTime<Utc>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_status: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_error: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_duration_ms: Option<u64>,
+    /// `execution_id` of the last run, for `GET /executions/<id>` and the execution logs
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_execution_id: Option<String>,
+    /// Occurrences dropped because of overlap or the missed-run policy
+    #[serde(default)]
+    pub skipped_runs: u64,
+}
+
+/// A scheduled workflow with its state, as returned by `list_schedules`
+#[derive(Debug, Clone, Serialize)]
+pub struct ScheduleInfo {
+    #[serde(flatten)]
+    pub workflow: ScheduledWorkflow,
+    #[serde(flatten)]
+    pub state: ScheduleState,
+    pub running: bool,
+}
+
+/// Scan `dir` for workflows with a cron trigger. Unparseable schedules are
+/// reported as warnings instead of failing the whole scan.
+pub fn discover(dir: &Path) -> (Vec<(ScheduledWorkflow, Schedule)>, Vec<String>) {
+    let (found, mut warnings) = workflow_triggers::discover(dir);
+    let mut scheduled = Vec::new();
+    for workflow in found {
+        let TriggerConfig::Cron {
+            schedule, timezone, ..
+        } = workflow.trigger
+        else {
+            continue;
+        };
+        let missed_runs = match workflow.missed_runs {
+            Some(value) => match serde_json::from_value(value) {
+                Ok(policy) => policy,
+                Err(_) => {
+                    warnings.push(format!(
+                        "{}: `missed_runs` must be `skip` or `run_once`",
+                        workflow.path.display()
+                    ));
+                    continue;
+                }
+            },
+            None => MissedRunPolicy::default(),
+        };
+        match Schedule::parse(&schedule, timezone.as_deref()) {
+            Ok(parsed) => scheduled.push((
+                ScheduledWorkflow {
+                    id: workflow.id,
+                    path: workflow.path,
+                    kind: workflow.kind,
+                    schedule,
+                    timezone: timezone.unwrap_or_else(|| "UTC".to_string()),
+                    missed_runs,
+                },
+                parsed,
+            )),
+            Err(e) => warnings.push(format!("{}: {e}", workflow.path.display())),
+        }
+    }
+    (scheduled, warnings)
+}
+
+struct Entry {
+    workflow: ScheduledWorkflow,
+    schedule: Schedule,
+    state: ScheduleState,
+    running: bool,
+}
+
+impl Entry {
+    fn info(&self) -> ScheduleInfo {
+        ScheduleInfo {
+            workflow: self.workflow.clone(),
+            state: self.state.clone(),
+            running: self.running,
+        }
+    }
+}
+
+pub struct Scheduler {
+    dir: PathBuf,
+    state_path: Option<PathBuf>,
+    runner: RunWorkflow,
+    entries: Mutex<BTreeMap<String, Entry>>,
+    /// State of workflows that are not (or no longer) in the directory, kept so
+    /// a workflow that is temporarily removed doesn't lose its history
+    orphaned: Mutex<BTreeMap<String, ScheduleState>>,
+    /// Held while writing the state file so writes land in the order their
+    /// snapshots were taken
+    save_lock: tokio::sync::Mutex<()>,
+    wake: Notify,
+}
+
+impl Scheduler {
+    /// Load persisted state and scan `dir`. Nothing runs until [`Scheduler::spawn`]
+    /// (or [`Scheduler::tick`]) is called.
+    pub async fn new(dir: PathBuf, state_path: Option<PathBuf>, runner: RunWorkflow) -> Arc<Self> {
+        let saved = match state_path.clone() {
+            Some(path) => tokio::task::spawn_blocking(move || read_state(&path))
+                .await
+                .unwrap_or_else(|e| {
+                    warn!("Schedule state read task failed: {}", e);
+                    BTreeMap::new()
+                }),
+            None => BTreeMap::new(),
+        };
+
+        let scheduler = Arc::new(Self {
+            dir,
+            state_path,
+            runner,
+            entries: Mutex::new(BTreeMap::new()),
+            orphaned: Mutex::new(saved),
+            save_lock: tokio::sync::Mutex::new(()),
+            wake: Notify::new(),
+        });
+        scheduler.reload().await;
+        scheduler
+    }
+
+    /// Rescan the workflows directory. Workflows whose schedule changed get a
+    /// fresh next run time; everything else keeps its state.
+    pub async fn reload(&self) {
+        let dir = self.dir.clone();
+        let (found, warnings) = match tokio::task::spawn_blocking(move || discover(&dir)).await {
+            Ok(scan) => scan,
+            Err(e) => {
+                warn!("[scheduler] Workflow scan task failed: {}", e);
+                return;
+            }
+        };
+        for warning in warnings {
+            warn!("[scheduler] {}", warning);
+        }
+
+        self.merge(found, Utc::now());
+        self.save().await;
+    }
+
+    /// Replace the entries with the workflows of a scan
+    fn merge(&self, found: Vec<(ScheduledWorkflow, Schedule)>, now: DateTime<Utc>) {
+        let mut entries = self.entries.lock().unwrap();
+        let mut orphaned = self.orphaned.lock().unwrap();
+        let mut previous = std::mem::take(&mut *entries);
+        for (workflow, schedule) in found {
+            let id = workflow.id.clone();
+            let entry = match previous.remove(&id) {
+                Some(mut entry) => {
+                    if entry.workflow.schedule != workflow.schedule
+                        || entry.workflow.timezone != workflow.timezone
+                    {
+                        info!("[scheduler] Schedule of '{}' changed", id);
+                        entry.state.next_run = None;
+                    }
+                    entry.workflow = workflow;
+                    entry.schedule = schedule;
+                    entry
+                }
+                None => {
+                    info!(
+                        "[scheduler] Scheduling '{}' ({} {})",
+                        id, workflow.schedule, workflow.timezone
+                    );
+                    Entry {
+                        workflow,
+                        schedule,
+                        state: orphaned.remove(&id).unwrap_or_default(),
+                        running: false,
+                    }
+                }
+            };
+            entries.insert(id, entry);
+        }
+        for (id, entry) in previous {
+            info!("[scheduler] '{}' is no longer scheduled", id);
+            orphaned.insert(id, entry.state);
+        }
+
+        for entry in entries.values_mut() {
+            if entry.state.next_run.is_none() && !entry.state.paused {
+                entry.state.next_run = entry.schedule.next_after(now);
+            }
+        }
+    }
+
+    pub fn list(&self) -> Vec<ScheduleInfo> {
+        let entries = self.entries.lock().unwrap();
+        entries.values().map(Entry::info).collect()
+    }
+
+    /// Stop scheduling a workflow until it is resumed
+    pub async fn pause(&self, id: &str) -> Result<ScheduleInfo, String> {
+        self.update(id, |entry, _| {
+            entry.state.paused = true;
+            entry.state.next_run = None;
+        })
+        .await
+    }
+
+    /// Resume a paused workflow. Occurrences that passed while it was paused
+    /// are not made up.
+    pub async fn resume(&self, id: &str) -> Result<ScheduleInfo, String> {
+        self.update(id, |entry, now| {
+            entry.state.paused = false;
+            entry.state.next_run = entry.schedule.next_after(now);
+        })
+        .await
+    }
+
+    /// Start a run right away, outside the schedule
+    pub fn trigger(self: &Arc<Self>, id: &str) -> Result<ScheduleInfo, String> {
+        let mut entries = self.entries.lock().unwrap();
+        let entry = entries
+            .get_mut(id)
+            .ok_or_else(|| format!("No scheduled workflow '{id}'"))?;
+        if entry.running {
+            return Err(format!("Workflow '{id}' is already running"));
+        }
+        info!("[scheduler] Running '{}' on demand", id);
+        self.start_run(entry);
+        Ok(entry.info())
+    }
+
+    async fn update(
+        &self,
+        id: &str,
+        change: impl FnOnce(&mut Entry, DateTime<Utc>),
+    ) -> Result<ScheduleInfo, String> {
+        let info = {
+            let mut entries = self.entries.lock().unwrap();
+            let entry = entries
+                .get_mut(id)
+                .ok_or_else(|| format!("No scheduled workflow '{id}'"))?;
+            change(entry, Utc::now());
+            entry.info()
+        };
+        self.save().await;
+        self.wake.notify_one();
+        Ok(info)
+    }
+
+    /// Start every run that is due at `now`, and return when the next one is
+    pub async fn tick(self: &Arc<Self>, now: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        let (changed, next) = self.start_due_runs(now);
+        if changed {
+            self.save().await;
+        }
+        next
+    }
+
+    /// The synchronous part of [`Scheduler::tick`]: returns whether any state
+    /// changed and the next run time
+    fn start_due_runs(self: &Arc<Self>, now: DateTime<Utc>) -> (bool, Option<DateTime<Utc>>) {
+        let mut entries = self.entries.lock().unwrap();
+        let mut changed = false;
+
+        for (id, entry) in entries.iter_mut() {
+            if entry.state.paused {
+                continue;
+            }
+            let Some(due) = entry.state.next_run else {
+                entry.state.next_run = entry.schedule.next_after(now);
+                changed = true;
+                continue;
+            };
+            if due > now {
+                continue;
+            }
+
+            changed = true;
+            entry.state.next_run = entry.schedule.next_after(now);
+            if entry.running {
+                warn!(
+                    "[scheduler] Skipping '{}' run due at {}: previous run still in progress",
+                    id, due
+                );
+                entry.state.skipped_runs += 1;
+            } else if now - due > MISSED_RUN_GRACE
+                && entry.workflow.missed_runs == MissedRunPolicy::Skip
+            {
+                info!("[scheduler] Skipping missed '{}' run due at {}", id, due);
+                entry.state.skipped_runs += 1;
+            } else {
+                debug!("[scheduler] '{}' is due ({})", id, due);
+                self.start_run(entry);
+            }
+        }
+
+        let next = entries.values().filter_map(|e| e.state.next_run).min();
+        (changed, next)
+    }
+
+    fn start_run(self: &Arc<Self>, entry: &mut Entry) {
+        entry.running = true;
+        let execution_id = Uuid::new_v4().to_string();
+        entry.state.last_execution_id = Some(execution_id.clone());
+        let workflow = entry.workflow.clone();
+        let run = (self.runner)(RunRequest {
+            url: workflow.url(),
+            inputs: None,
+            execution_id: execution_id.clone(),
+        });
+        let scheduler = Arc::clone(self);
+        tokio::spawn(async move {
+            let started_at = Utc::now();
+            let started = Instant::now();
+            info!(
+                "[scheduler] Starting '{}' ({}, execution {})",
+                workflow.id,
+                workflow.url(),
+                execution_id
+            );
+            let outcome = run.await;
+            let elapsed = started.elapsed().as_millis() as u64;
+            match &outcome {
+                Ok(status) => info!(
+                    "[scheduler] '{}' finished in {}ms: {}",
+                    workflow.id, elapsed, status
+                ),
+                Err(e) => warn!(
+                    "[scheduler] '{}' failed after {}ms: {}",
+                    workflow.id, elapsed, e
+                ),
+            }
+            scheduler
+                .finish_run(&workflow.id, started_at, elapsed, outcome)
+                .await;
+        });
+    }
+
+    async fn finish_run(
+        &self,
+        id: &str,
+        started_at: DateTime<Utc>,
+        duration_ms: u64,
+        outcome: Result<String, String>,
+    ) {
+        {
+            let mut entries = self.entries.lock().unwrap();
+            let mut orphaned = self.orphaned.lock().unwrap();
+            let state = match entries.get_mut(id) {
+                Some(entry) => {
+                    entry.running = false;
+                    &mut entry.state
+                }
+                // Removed from the directory while running
+                None => orphaned.entry(id.to_string()).or_default(),
+            };
+            state.last_run = Some(started_at);
+            state.last_duration_ms = Some(duration_ms);
+            match outcome {
+                Ok(status) => {
+                    state.last_status = Some(status);
+                    state.last_error = None;
+                }
+                Err(e) => {
+                    state.last_status = Some("failed".to_string());
+                    state.last_error = Some(e);
+                }
+            }
+        }
+        self.save().await;
+        self.wake.notify_one();
+    }
+
+    /// Write the state of every workflow, including orphaned ones. The state is
+    /// copied under the locks and written on the blocking pool.
+    async fn save(&self) {
+        let Some(path) = self.state_path.clone() else {
+            return;
+        };
+        let _writing = self.save_lock.lock().await;
+        let all = {
+            let entries = self.entries.lock().unwrap();
+            let mut all = self.orphaned.lock().unwrap().clone();
+            all.extend(entries.iter().map(|(id, e)| (id.clone(), e.state.clone())));
+            all
+        };
+
+        let target = path.clone();
+        let result = tokio::task::spawn_blocking(move || write_state(&target, &all))
+            .await
+            .unwrap_or_else(|e| Err(std::io::Error::other(e)));
+        if let Err(e) = result {
+            warn!(
+                "[scheduler] Failed to save schedule state to {}: {}",
+                path.display(),
+                e
+            );
+        }
+    }
+
+    /// Run the scheduler loop in the background
+    pub fn spawn(self: &Arc<Self>) -> tokio::task::JoinHandle<()> {
+        let scheduler = Arc::clone(self);
+        tokio::spawn(async move {
+            let mut last_scan = Instant::now();
+            loop {
+                if last_scan.elapsed() >= RESCAN_INTERVAL {
+                    scheduler.reload().await;
+                    last_scan = Instant::now();
+                }
+                let now = Utc::now();
+                let mut wait = RESCAN_INTERVAL;
+                if let Some(next) = scheduler.tick(now).await {
+                    wait = wait.min((next - now).to_std().unwrap_or_default());
+                }
+                tokio::select! {
+                    _ = tokio::time::sleep(wait) => {}
+                    _ = scheduler.wake.notified() => {}
+                }
+            }
+        })
+    }
+}
+
+fn read_state(path: &Path) -> BTreeMap<String, ScheduleState> {
+    let Ok(content) = std::fs::read_to_string(path) else {
+        return BTreeMap::new();
+    };
+    serde_json::from_str(&content).unwrap_or_else(|e| {
+        warn!("Ignoring unreadable schedule state: {}", e);
+        BTreeMap::new()
+    })
+}
+
+fn write_state(path: &Path, all: &BTreeMap<String, ScheduleState>) -> std::io::Result<()> {
+    let json = serde_json::to_string_pretty(all).map_err(std::io::Error::other)?;
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let tmp = path.with_extension("json.tmp");
+    std::fs::write(&tmp, json)?;
+    std::fs::rename(&tmp, path)
+}
+
+static SCHEDULER: OnceLock<Arc<Scheduler>> = OnceLock::new();
+
+/// Install the process-wide scheduler and start its loop
+pub fn init(scheduler: Arc<Scheduler>) {
+    let count = scheduler.list().len();
+    if SCHEDULER.set(Arc::clone(&scheduler)).is_err() {
+        warn!("[scheduler] Scheduler already initialized");
+        return;
+    }
+    info!(
+        "Workflow scheduler started: {} workflow(s) in {}",
+        count,
+        scheduler.dir.display()
+    );
+    scheduler.spawn();
+}
+
+/// The running scheduler, if `--workflows-dir` was given
+pub fn current() -> Option<Arc<Scheduler>> {
+    SCHEDULER.get().cloned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use tokio::sync::Semaphore;
+
+    fn utc(s: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(s).unwrap().with_timezone(&Utc)
+    }
+
+    /// Runner that counts starts and blocks each run until a permit is added
+    fn gated_runner() -> (RunWorkflow, Arc<AtomicUsize>, Arc<Semaphore>) {
+        let starts = Arc::new(AtomicUsize::new(0));
+        let gate = Arc::new(Semaphore::new(0));
+        let runner: RunWorkflow = {
+            let starts = starts.clone();
+            let gate = gate.clone();
+            Arc::new(move |_workflow| {
+                starts.fetch_add(1, Ordering::SeqCst);
+                let gate = gate.clone();
+                Box::pin(async move {
+                    gate.acquire().await.unwrap().forget();
+                    Ok("executed_without_error".to_string())
+                })
+            })
+        };
+        (runner, starts, gate)
+    }
+
+    async fn wait_idle(scheduler: &Scheduler, id: &str) -> ScheduleInfo {
+        for _ in 0..200 {
+            let info = scheduler.list().into_iter().find(|i| i.workflow.id == id);
+            if let Some(info) = info.filter(|i| !i.running) {
+                return info;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        panic!("run of '{id}' did not finish");
+    }
+
+    #[test]
+    fn test_schedule_uses_timezone() {
+        // 09:00 in New York is 13:00 UTC during daylight saving time
+        let schedule = Schedule::parse("0 9 * * *", Some("America/New_York")).unwrap();
+        let next = schedule.next_after(utc("2025-06-02T12:00:00Z")).unwrap();
+        assert_eq!(next, utc("2025-06-02T13:00:00Z"));
+
+        // Six fields: seconds first
+        let schedule = Schedule::parse("0 30 * * * *", None).unwrap();
+        let next = schedule.next_after(utc("2025-06-02T12:30:00Z")).unwrap();
+        assert_eq!(next, utc("2025-06-02T13:30:00Z"));
+
+        assert!(Schedule::parse("0 9 * * *", Some("Mars/Olympus")).is_err());
+        assert!(Schedule::parse("not cron", None).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_no_overlap_and_missed_runs() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("skip.yml"), "cron: \"0 * * * *\"\n").unwrap();
+        std::fs::write(
+            dir.path().join("catchup.yml"),
+            "cron: \"0 * * * *\"\nmissed_runs: run_once\n",
+        )
+        .unwrap();
+        let state_path = dir.path().join("state").join(STATE_FILE_NAME);
+
+        // Pretend both were last due a few hours ago, while the agent was down
+        let saved = serde_json::json!({
+            "skip": { "next_run": "2025-01-01T09:00:00Z" },
+            "catchup": { "next_run": "2025-01-01T09:00:00Z" },
+        });
+        std::fs::create_dir_all(state_path.parent().unwrap()).unwrap();
+        std::fs::write(&state_path, saved.to_string()).unwrap();
+
+        let (runner, starts, gate) = gated_runner();
+        let scheduler =
+            Scheduler::new(dir.path().to_path_buf(), Some(state_path.clone()), runner).await;
+        let next = scheduler.tick(utc("2025-01-01T12:10:00Z")).await;
+        assert_eq!(next, Some(utc("2025-01-01T13:00:00Z")));
+        assert_eq!(starts.load(Ordering::SeqCst), 1, "only catchup runs");
+
+        // catchup is still running when the next occurrence comes due, so only
+        // skip starts
+        scheduler.tick(utc("2025-01-01T13:00:00Z")).await;
+        assert_eq!(starts.load(Ordering::SeqCst), 2);
+
+        gate.add_permits(2);
+        let skip = wait_idle(&scheduler, "skip").await;
+        assert_eq!(skip.state.skipped_runs, 1);
+        let catchup = wait_idle(&scheduler, "catchup").await;
+        assert_eq!(catchup.state.skipped_runs, 1);
+        assert_eq!(
+            catchup.state.last_status.as_deref(),
+            Some("executed_without_error")
+        );
+        assert_eq!(catchup.state.next_run, Some(utc("2025-01-01T14:00:00Z")));
+
+        // State survives a restart. Saves are queued in order, so one more save
+        // returns once the finished runs are on disk.
+        scheduler.save().await;
+        let (runner, _, _) = gated_runner();
+        let reloaded = Scheduler::new(dir.path().to_path_buf(), Some(state_path), runner).await;
+        let catchup = reloaded
+            .list()
+            .into_iter()
+            .find(|i| i.workflow.id == "catchup")
+            .unwrap();
+        assert!(catchup.state.last_run.is_some());
+        assert_eq!(catchup.state.next_run, Some(utc("2025-01-01T14:00:00Z")));
+    }
+
+    #[tokio::test]
+    async fn test_pause_resume_and_trigger() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("report.yml"), "cron: \"*/5 * * * *\"\n").unwrap();
+        let (runner, starts, gate) = gated_runner();
+        let scheduler = Scheduler::new(dir.path().to_path_buf(), None, runner).await;
+
+        let paused = scheduler.pause("report").await.unwrap();
+        assert!(paused.state.paused);
+        assert_eq!(paused.state.next_run, None);
+        assert_eq!(
+            scheduler
+                .tick(Utc::now() + chrono::Duration::hours(1))
+                .await,
+            None
+        );
+        assert_eq!(starts.load(Ordering::SeqCst), 0);
+
+        let resumed = scheduler.resume("report").await.unwrap();
+        assert!(!resumed.state.paused);
+        assert!(resumed.state.next_run.unwrap() > Utc::now());
+
+        assert!(scheduler.trigger("report").unwrap().running);
+        assert!(scheduler.trigger("report").is_err(), "no overlap");
+        gate.add_permits(1);
+        wait_idle(&scheduler, "report").await;
+        assert_eq!(starts.load(Ordering::SeqCst), 1);
+
+        assert!(scheduler.pause("missing").await.is_err());
+    }
+}
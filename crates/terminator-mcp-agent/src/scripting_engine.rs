@@ -1163,12 +1163,16 @@ try {{
         unique_filename.clone()
     };
 
-    // Set up Windows named pipe for event streaming if event_sender is provided
-    #[cfg(windows)]
+    // Set up the event pipe for event streaming if event_sender is provided
     let pipe_server_handle = if event_sender.is_some() {
         let exec_id = execution_id.unwrap_or("run-command");
         let (pipe_tx, mut pipe_rx) = create_event_channel();
-        let pipe_server = EventPipeServer::new(exec_id, pipe_tx);
+        let pipe_server = EventPipeServer::new(exec_id, pipe_tx).map_err(|e| {
+            McpError::internal_error(
+                format!("Failed to create event pipe: {e}"),
+                Some(json!({"error": e.to_string()})),
+            )
+        })?;
         let pipe_name = pipe_server.pipe_name().to_string();
 
         // Start the pipe server
@@ -1268,9 +1272,6 @@ try {{
         None
     };
 
-    #[cfg(not(windows))]
-    let pipe_server_handle: Option<((), String)> = None;
-
     // Build command based on runtime type
     let mut cmd = if runtime == "bun" && !is_batch_file {
         info!("[Node.js] Using direct bun execution");
@@ -1299,7 +1300,6 @@ try {{
     cmd.envs(std::env::vars());
 
     // Set the pipe path environment variable if we have a pipe server
-    #[cfg(windows)]
     if let Some((_, ref pipe_name)) = pipe_server_handle {
         cmd.env("MCP_EVENT_PIPE", pipe_name);
         info!("[Node.js] Set MCP_EVENT_PIPE={}", pipe_name);
@@ -1509,8 +1509,7 @@ try {{
         script_dir.display()
     );
 
-    // Shutdown the pipe server (Windows only)
-    #[cfg(windows)]
+    // Shutdown the pipe server
     if let Some((handle, _)) = pipe_server_handle {
         handle.shutdown().await;
     }
@@ -1766,12 +1765,16 @@ console.log('[TypeScript] Current working directory:', process.cwd());
         script_dir.clone()
     };
 
-    // Set up Windows named pipe for event streaming if event_sender is provided
-    #[cfg(windows)]
+    // Set up the event pipe for event streaming if event_sender is provided
     let pipe_server_handle = if event_sender.is_some() {
         let exec_id = execution_id.unwrap_or("run-ts-command");
         let (pipe_tx, mut pipe_rx) = create_event_channel();
-        let pipe_server = EventPipeServer::new(exec_id, pipe_tx);
+        let pipe_server = EventPipeServer::new(exec_id, pipe_tx).map_err(|e| {
+            McpError::internal_error(
+                format!("Failed to create event pipe: {e}"),
+                Some(json!({"error": e.to_string()})),
+            )
+        })?;
         let pipe_name = pipe_server.pipe_name().to_string();
 
         // Start the pipe server
@@ -1803,9 +1806,6 @@ console.log('[TypeScript] Current working directory:', process.cwd());
         None
     };
 
-    #[cfg(not(windows))]
-    let pipe_server_handle: Option<((), String)> = None;
-
     let mut cmd = if runtime == "bun" {
         // Bun can run TypeScript directly
         let mut c = Command::new(runtime);
@@ -1829,7 +1829,6 @@ console.log('[TypeScript] Current working directory:', process.cwd());
     cmd.env("TERMINATOR_PARENT_BRIDGE_PORT", "17373"); // Enable subprocess proxy mode
 
     // Set the pipe path environment variable if we have a pipe server
-    #[cfg(windows)]
     if let Some((_, ref pipe_name)) = pipe_server_handle {
         cmd.env("MCP_EVENT_PIPE", pipe_name);
         info!("[TypeScript] Set MCP_EVENT_PIPE={}", pipe_name);
@@ -1986,8 +1985,7 @@ console.log('[TypeScript] Current working directory:', process.cwd());
         process_fut.await
     };
 
-    // Shutdown the pipe server (Windows only)
-    #[cfg(windows)]
+    // Shutdown the pipe server
     if let Some((handle, _)) = pipe_server_handle {
         handle.shutdown().await;
     }
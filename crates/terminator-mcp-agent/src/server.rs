@@ -1,20 +1,30 @@
-use crate::elicitation::{try_elicit, UserResponse};
+use crate::client_auth;
+use crate::elicitation::{try_elicit, ActionConfirmation, UserResponse};
 use crate::event_pipe::{create_event_channel, WorkflowEvent};
+use crate::execution_history::{self, History, RunQuery, RunStatus};
 use crate::execution_logger;
+use crate::execution_queue::EntryState;
 use crate::helpers::*;
+use crate::recording;
+use crate::run_report;
+use crate::scheduler::{self, Scheduler};
 use crate::scripting_engine;
 use crate::telemetry::StepSpan;
+use crate::tool_policy::{self, PolicyAction, PolicyDecision};
 use crate::utils::find_and_execute_with_retry_with_fallback;
 pub use crate::utils::DesktopWrapper;
 use crate::utils::{
     get_timeout, ActivateElementArgs, AskUserArgs, CaptureScreenshotArgs, ClickElementArgs,
     CopyContentArgs, DelayArgs, EditFileArgs, ExecuteBrowserScriptArgs, ExecuteSequenceArgs,
-    GeminiComputerUseArgs, GetApplicationsArgs, GetWindowTreeArgs, GlobFilesArgs, GlobalKeyArgs,
-    GrepFilesArgs, HighlightElementArgs, InvokeElementArgs, MouseDragArgs, NavigateBrowserArgs,
-    OpenApplicationArgs, PressKeyArgs, ReadFileArgs, RunCommandArgs, ScrollElementArgs,
-    SelectOptionArgs, SetSelectedArgs, SetValueArgs, StopHighlightingArgs, TypeIntoElementArgs,
-    ValidateElementArgs, WaitForElementArgs, WriteFileArgs,
+    ExecutionHistoryArgs, ExecutionRunArgs, ExecutionStepStatsArgs, GeminiComputerUseArgs,
+    GetApplicationsArgs, GetWindowTreeArgs, GlobFilesArgs, GlobalKeyArgs, GrepFilesArgs,
+    HighlightElementArgs, InvokeElementArgs, MouseDragArgs, NavigateBrowserArgs,
+    OpenApplicationArgs, PressKeyArgs, QueuedRunArgs, ReadFileArgs, ReorderQueueArgs,
+    RunCommandArgs, RunReportArgs, ScheduleIdArgs, ScrollElementArgs, SelectOptionArgs,
+    SetSelectedArgs, SetValueArgs, StartRecordingArgs, StopHighlightingArgs, StopRecordingArgs,
+    TypeIntoElementArgs, ValidateElementArgs, WaitForElementArgs, WriteFileArgs,
 };
+use crate::workflow_triggers::{RunRequest, RunWorkflow};
 use image::imageops::FilterType;
 use image::{ExtendedColorType, ImageBuffer, ImageEncoder, Rgba};
 use regex::Regex;
@@ -762,6 +772,12 @@ impl DesktopWrapper {
         Self::new_with_log_capture(None)
     }
 
+    /// Definitions (name and input schema) of every tool the server exposes.
+    /// Doesn't need a desktop, so offline tooling such as the CLI can use it.
+    pub fn tool_definitions() -> Vec<rmcp::model::Tool> {
+        Self::tool_router().list_all()
+    }
+
     pub fn new_with_log_capture(
         log_capture: Option<crate::tool_logging::LogCapture>,
     ) -> Result<Self, McpError> {
@@ -809,6 +825,8 @@ impl DesktopWrapper {
             current_mode: Arc::new(Mutex::new(None)),
             blocked_tools: Arc::new(Mutex::new(std::collections::HashSet::new())),
             elicitation_peer: Arc::new(Mutex::new(None)),
+            execution_queue: crate::execution_queue::ExecutionQueue::new(),
+            recording: crate::recording::RecordingManager::new(),
         })
     }
 
@@ -9107,6 +9125,339 @@ console.info = function(...args) {
             }
         }
     }
+
+    #[tool(
+        description = "Statically analyze a YAML/JSON workflow without running it. Reports duplicate step ids, fallback_id/jumps targets that don't exist, unreachable troubleshooting steps, undefined {{variable}} references, unknown tools or arguments (checked against tool schemas), invalid selectors and unparseable if/jump conditions. Provide exactly one of url, content or workflow."
+    )]
+    pub async fn validate_workflow(
+        &self,
+        Parameters(args): Parameters<crate::tools::validate_workflow::ValidateWorkflowArgs>,
+    ) -> Result<CallToolResult, McpError> {
+        use crate::tools::validate_workflow;
+
+        let mut span = StepSpan::new("validate_workflow", None);
+        if let Some(url) = &args.url {
+            span.set_attribute("workflow.url", url.clone());
+        }
+
+        let tools = self.tool_router.list_all();
+        match validate_workflow::validate_workflow(&args, Some(&tools)).await {
+            Ok(result) => {
+                span.set_attribute("issues.errors", result.error_count.to_string());
+                span.set_attribute("issues.warnings", result.warning_count.to_string());
+                span.set_status(result.valid, None);
+                span.end();
+                Ok(CallToolResult::success(vec![Content::json(result)?]))
+            }
+            Err(e) => {
+                span.set_status(false, Some(&e));
+                span.end();
+                Err(McpError::invalid_params(e, None))
+            }
+        }
+    }
+
+    #[tool(
+        description = "List the workflows run by the built-in scheduler (agent started with --workflows-dir) with their cron schedule, timezone, missed-run policy, paused flag, whether a run is in progress, and the last and next run times."
+    )]
+    pub async fn list_schedules(&self) -> Result<CallToolResult, McpError> {
+        let scheduler = running_scheduler()?;
+        let schedules = scheduler.list();
+        Ok(CallToolResult::success(vec![Content::json(json!({
+            "count": schedules.len(),
+            "schedules": schedules,
+        }))?]))
+    }
+
+    #[tool(
+        description = "Pause a scheduled workflow. It won't run on schedule until resumed; the pause survives agent restarts."
+    )]
+    pub async fn pause_schedule(
+        &self,
+        Parameters(args): Parameters<ScheduleIdArgs>,
+    ) -> Result<CallToolResult, McpError> {
+        let info = running_scheduler()?
+            .pause(&args.id)
+            .await
+            .map_err(|e| McpError::invalid_params(e, Some(json!({"id": args.id}))))?;
+        Ok(CallToolResult::success(vec![Content::json(info)?]))
+    }
+
+    #[tool(
+        description = "Resume a paused scheduled workflow. Occurrences that passed while it was paused are not made up."
+    )]
+    pub async fn resume_schedule(
+        &self,
+        Parameters(args): Parameters<ScheduleIdArgs>,
+    ) -> Result<CallToolResult, McpError> {
+        let info = running_scheduler()?
+            .resume(&args.id)
+            .await
+            .map_err(|e| McpError::invalid_params(e, Some(json!({"id": args.id}))))?;
+        Ok(CallToolResult::success(vec![Content::json(info)?]))
+    }
+
+    #[tool(
+        description = "Run a scheduled workflow now, outside its schedule. Returns once the run has started and fails if the workflow is already running. Use list_schedules to see the outcome."
+    )]
+    pub async fn trigger_schedule(
+        &self,
+        Parameters(args): Parameters<ScheduleIdArgs>,
+    ) -> Result<CallToolResult, McpError> {
+        let info = running_scheduler()?
+            .trigger(&args.id)
+            .map_err(|e| McpError::invalid_params(e, Some(json!({"id": args.id}))))?;
+        Ok(CallToolResult::success(vec![Content::json(info)?]))
+    }
+
+    #[tool(
+        description = "List execute_sequence runs on this agent: running ones first, then queued ones in the order they will start, with request id, workflow, priority, concurrency key, whether the run needs the desktop (ui), and queue position."
+    )]
+    pub async fn list_execution_queue(&self) -> Result<CallToolResult, McpError> {
+        let entries = self.execution_queue.list();
+        let queued = entries
+            .iter()
+            .filter(|e| e.state == EntryState::Queued)
+            .count();
+        Ok(CallToolResult::success(vec![Content::json(json!({
+            "running": entries.len() - queued,
+            "queued": queued,
+            "runs": entries,
+        }))?]))
+    }
+
+    #[tool(
+        description = "Move a queued execute_sequence run: give it a new 1-based position, a new priority, or both. Runs that already started can't be reordered."
+    )]
+    pub async fn reorder_execution_queue(
+        &self,
+        Parameters(args): Parameters<ReorderQueueArgs>,
+    ) -> Result<CallToolResult, McpError> {
+        if args.position.is_none() && args.priority.is_none() {
+            return Err(McpError::invalid_params(
+                "Provide a position, a priority, or both",
+                Some(json!({"request_id": args.request_id})),
+            ));
+        }
+        let position = self
+            .execution_queue
+            .reorder(&args.request_id, args.position, args.priority)
+            .map_err(|e| {
+                McpError::invalid_params(e, Some(json!({"request_id": args.request_id})))
+            })?;
+        Ok(CallToolResult::success(vec![Content::json(json!({
+            "request_id": args.request_id,
+            "position": position,
+            "runs": self.execution_queue.list(),
+        }))?]))
+    }
+
+    #[tool(
+        description = "Cancel a queued execute_sequence run before it starts. The caller gets a cancellation error. Use stop_execution to stop runs that are already running."
+    )]
+    pub async fn cancel_queued_execution(
+        &self,
+        Parameters(args): Parameters<QueuedRunArgs>,
+    ) -> Result<CallToolResult, McpError> {
+        if !self.execution_queue.is_queued(&args.request_id) {
+            return Err(McpError::invalid_params(
+                format!("No queued run with request id '{}'", args.request_id),
+                Some(json!({"request_id": args.request_id})),
+            ));
+        }
+        // The waiting run is registered with the request manager; cancelling it there
+        // drops its queue entry
+        let cancelled = self.request_manager.cancel_request(&args.request_id).await;
+        Ok(CallToolResult::success(vec![Content::json(json!({
+            "request_id": args.request_id,
+            "cancelled": cancelled,
+        }))?]))
+    }
+
+    #[tool(
+        description = "Query the execution history of execute_sequence runs, newest first. Filter by workflow, status (running, completed, failed, cancelled) and start time, e.g. the last 20 failed runs of a workflow. Returns run ids, status, duration, error, step count and the first failed step; use get_execution_run for the steps."
+    )]
+    pub async fn query_execution_history(
+        &self,
+        Parameters(args): Parameters<ExecutionHistoryArgs>,
+    ) -> Result<CallToolResult, McpError> {
+        let status = args
+            .status
+            .as_deref()
+            .map(str::parse::<RunStatus>)
+            .transpose()
+            .map_err(|e| {
+                McpError::invalid_params(e.to_string(), Some(json!({"status": args.status})))
+            })?;
+        let query = RunQuery {
+            workflow: args.workflow,
+            status,
+            since: parse_history_since(args.since.as_deref())?,
+            limit: args.limit.unwrap_or(20),
+        };
+        let runs = History::open_default()
+            .query(&query)
+            .map_err(history_error)?;
+        Ok(CallToolResult::success(vec![Content::json(json!({
+            "count": runs.len(),
+            "runs": runs.iter().map(|run| run.summary()).collect::<Vec<_>>(),
+        }))?]))
+    }
+
+    #[tool(
+        description = "Show one execute_sequence run step by step from the execution history: tool, status, duration, error, retry attempt, execution log file and screenshot files of every step."
+    )]
+    pub async fn get_execution_run(
+        &self,
+        Parameters(args): Parameters<ExecutionRunArgs>,
+    ) -> Result<CallToolResult, McpError> {
+        let run = History::open_default()
+            .run(&args.run_id)
+            .map_err(|e| McpError::invalid_params(e.to_string(), None))?
+            .ok_or_else(|| {
+                McpError::invalid_params(
+                    format!("No run '{}' in the execution history", args.run_id),
+                    Some(json!({"run_id": args.run_id})),
+                )
+            })?;
+        Ok(CallToolResult::success(vec![Content::json(run)?]))
+    }
+
+    #[tool(
+        description = "Per-step duration statistics (count, failures, mean, p50, p95, max in ms) over execute_sequence runs in the execution history, slowest p95 first. Filter by workflow and start time."
+    )]
+    pub async fn execution_step_stats(
+        &self,
+        Parameters(args): Parameters<ExecutionStepStatsArgs>,
+    ) -> Result<CallToolResult, McpError> {
+        let query = RunQuery {
+            workflow: args.workflow,
+            since: parse_history_since(args.since.as_deref())?,
+            ..Default::default()
+        };
+        let stats = History::open_default()
+            .step_stats(&query)
+            .map_err(history_error)?;
+        Ok(CallToolResult::success(vec![Content::json(json!({
+            "steps": stats,
+        }))?]))
+    }
+
+    #[tool(
+        description = "Write a self-contained HTML report of an execute_sequence run: a timeline of the steps and, per step, request, response, before/after screenshots, expected_ui_changes vs. the actual UI diff, captured logs and the TypeScript snippet. Returns the file path. Defaults to the latest run."
+    )]
+    pub async fn generate_run_report(
+        &self,
+        Parameters(args): Parameters<RunReportArgs>,
+    ) -> Result<CallToolResult, McpError> {
+        let run = run_report::find_run(
+            &History::open_default(),
+            args.run_id.as_deref(),
+            args.workflow.as_deref(),
+        )
+        .map_err(|e| McpError::invalid_params(format!("{e:#}"), None))?;
+        let (run_id, status, steps) = (run.run_id.clone(), run.status, run.steps.len());
+        let output = args.output_path.as_deref().map(PathBuf::from);
+        let path = run_report::write_report(run, output.as_deref()).map_err(|e| {
+            McpError::internal_error(format!("Failed to write run report: {e:#}"), None)
+        })?;
+        let size_bytes = std::fs::metadata(&path).map(|m| m.len()).unwrap_or(0);
+        Ok(CallToolResult::success(vec![Content::json(json!({
+            "path": path.display().to_string(),
+            "run_id": run_id,
+            "status": status,
+            "steps": steps,
+            "size_bytes": size_bytes,
+        }))?]))
+    }
+
+    #[tool(
+        description = "Start recording the user demonstrating a task (Windows only). Clicks, typed text, app switches, browser navigations and hotkeys are streamed as notifications/message log messages from the 'recording' logger until stop_recording. Pick a performance_mode preset and override WorkflowRecorderConfig fields with config. Only one recording runs at a time; finish it with stop_recording."
+    )]
+    pub async fn start_recording(
+        &self,
+        peer: Peer<RoleServer>,
+        Parameters(args): Parameters<StartRecordingArgs>,
+    ) -> Result<CallToolResult, McpError> {
+        let config =
+            recording::build_config(args.performance_mode.as_deref(), args.config.as_ref())
+                .map_err(|e| McpError::invalid_params(e, None))?;
+        let name = args.name.unwrap_or_else(|| {
+            format!(
+                "Recording {}",
+                chrono::Local::now().format("%Y-%m-%d %H:%M")
+            )
+        });
+        let sink = args
+            .stream_events
+            .unwrap_or(true)
+            .then_some(recording::EventSink { peer });
+        let status = self
+            .recording
+            .start(name, config, sink)
+            .await
+            .map_err(|e| McpError::internal_error(e, None))?;
+        Ok(CallToolResult::success(vec![Content::json(status)?]))
+    }
+
+    #[tool(
+        description = "Stop the running recording and return a workflow generated from it (YAML or TypeScript, with warnings about steps that need review) plus the raw recorded events. With extract_variables, the data typed into form fields becomes typed workflow variables with example inputs. Refine the workflow and run it with execute_sequence."
+    )]
+    pub async fn stop_recording(
+        &self,
+        Parameters(args): Parameters<StopRecordingArgs>,
+    ) -> Result<CallToolResult, McpError> {
+        let output = args
+            .workflow_format
+            .as_deref()
+            .unwrap_or("yaml")
+            .parse::<recording::WorkflowOutput>()
+            .map_err(|e| McpError::invalid_params(e, None))?;
+        let save_path = args.save_path.as_deref().map(PathBuf::from);
+        let result = self
+            .recording
+            .stop(
+                output,
+                args.include_events.unwrap_or(true),
+                args.extract_variables.unwrap_or(false),
+                save_path.as_deref(),
+            )
+            .await
+            .map_err(|e| McpError::internal_error(e, None))?;
+        Ok(CallToolResult::success(vec![Content::json(result)?]))
+    }
+
+    #[tool(
+        description = "Whether a recording is running, with its id, name, duration, number of events captured so far and the latest high-level event."
+    )]
+    pub async fn get_recording_status(&self) -> Result<CallToolResult, McpError> {
+        Ok(CallToolResult::success(vec![Content::json(
+            self.recording.status().await,
+        )?]))
+    }
+}
+
+fn parse_history_since(
+    since: Option<&str>,
+) -> Result<Option<chrono::DateTime<chrono::Utc>>, McpError> {
+    since
+        .map(|s| execution_history::parse_since(s, chrono::Utc::now()))
+        .transpose()
+        .map_err(|e| McpError::invalid_params(format!("{e:#}"), Some(json!({"since": since}))))
+}
+
+fn history_error(e: anyhow::Error) -> McpError {
+    McpError::internal_error(format!("Failed to read execution history: {e:#}"), None)
+}
+
+fn running_scheduler() -> Result<Arc<Scheduler>, McpError> {
+    scheduler::current().ok_or_else(|| {
+        McpError::invalid_request(
+            "The workflow scheduler is not running. Start the agent with --workflows-dir <dir>."
+                .to_string(),
+            None,
+        )
+    })
 }
 
 /// Get the path to the terminator source directory
@@ -9405,6 +9756,103 @@ fn download_terminator_source_direct() -> Result<(), Box<dyn std::error::Error +
 }
 
 impl DesktopWrapper {
+    /// Evaluate the tool policy for a call, asking the user when a rule requires
+    /// confirmation. Returns `None` when no policy is configured.
+    async fn check_tool_policy(
+        &self,
+        peer: &Peer<RoleServer>,
+        tool_name: &str,
+        arguments: &serde_json::Value,
+    ) -> Option<PolicyDecision> {
+        let policy = tool_policy::current()?;
+        let mut decision = policy.evaluate(tool_name, arguments);
+        if decision.action == PolicyAction::Confirm {
+            let message = decision.confirmation_prompt(tool_name);
+            let answer =
+                try_elicit::<ActionConfirmation>(&self.elicitation_peer, peer, &message).await;
+            decision.record_confirmation(answer);
+        }
+        tracing::info!(
+            "[tool_policy] {} -> {:?} (rule: {}, permitted: {})",
+            tool_name,
+            decision.action,
+            decision.rule.as_deref().unwrap_or("default"),
+            decision.permits()
+        );
+        Some(decision)
+    }
+
+    /// Runner for trigger-driven workflows (scheduler and webhooks). Runs are sent
+    /// through an in-process MCP client, so they go down the same
+    /// `execute_sequence` path as calls from a real client (execution logs, tool
+    /// policies, TypeScript runner).
+    pub async fn workflow_runner(&self) -> Result<RunWorkflow, McpError> {
+        use rmcp::ServiceExt;
+
+        let (client_io, server_io) = tokio::io::duplex(64 * 1024);
+        let server = self.clone();
+        tokio::spawn(async move {
+            match server.serve(server_io).await {
+                Ok(service) => {
+                    let _ = service.waiting().await;
+                }
+                Err(e) => tracing::error!("[workflow_runner] Loopback server failed: {}", e),
+            }
+        });
+        let client = ().serve(client_io).await.map_err(|e| {
+            McpError::internal_error(format!("Failed to start workflow runner client: {e}"), None)
+        })?;
+        let peer = client.peer().clone();
+        tokio::spawn(async move {
+            let _ = client.waiting().await;
+        });
+
+        Ok(Arc::new(move |run: RunRequest| {
+            let peer = peer.clone();
+            Box::pin(async move {
+                let mut arguments = serde_json::Map::new();
+                arguments.insert("url".to_string(), json!(run.url));
+                if let Some(inputs) = run.inputs {
+                    arguments.insert("inputs".to_string(), inputs);
+                }
+                arguments.insert("execution_id".to_string(), json!(run.execution_id));
+                let result = peer
+                    .call_tool(rmcp::model::CallToolRequestParam {
+                        name: "execute_sequence".into(),
+                        arguments: Some(arguments),
+                    })
+                    .await
+                    .map_err(|e| e.to_string())?;
+
+                let text = result.content.iter().find_map(extract_content_text);
+                let summary = text
+                    .as_deref()
+                    .and_then(|t| serde_json::from_str::<serde_json::Value>(t).ok());
+                let field = |name: &str| {
+                    summary
+                        .as_ref()
+                        .and_then(|s| s.get(name))
+                        .and_then(|v| v.as_str())
+                        .map(str::to_string)
+                };
+                let status = field("status").unwrap_or_else(|| "completed".to_string());
+                let failed = result.is_error == Some(true)
+                    || matches!(
+                        status.as_str(),
+                        "executed_with_error" | "failed" | "error" | "cancelled"
+                    );
+                if failed {
+                    Err(field("error")
+                        .or_else(|| field("message"))
+                        .or(text)
+                        .unwrap_or(status))
+                } else {
+                    Ok(status)
+                }
+            })
+        }))
+    }
+
     pub(crate) async fn dispatch_tool(
         &self,
         peer: Peer<RoleServer>,
@@ -9423,6 +9871,40 @@ impl DesktopWrapper {
             ));
         }
 
+        // Sequence steps run with the caller's identity, so its scopes apply to each step
+        client_auth::check_tool_scope(tool_name)?;
+
+        let (workflow_id, step_id, step_index, retry_attempt) = execution_context
+            .as_ref()
+            .map(|ctx| {
+                (
+                    ctx.workflow_id.clone(),
+                    ctx.step_id.clone(),
+                    Some(ctx.current_step),
+                    ctx.retry_attempt,
+                )
+            })
+            .unwrap_or((None, None, None, None));
+
+        // Apply the tool policy before touching any window
+        let policy_decision = self.check_tool_policy(&peer, tool_name, arguments).await;
+        if let Some(decision) = policy_decision.as_ref().filter(|d| !d.permits()) {
+            let error = decision.to_error(tool_name);
+            if let Some(ctx) = execution_logger::log_request(
+                tool_name,
+                arguments,
+                workflow_id.as_deref(),
+                step_id.as_deref(),
+                step_index,
+            ) {
+                let ctx = ctx
+                    .with_retry_attempt(retry_attempt)
+                    .with_policy_decision(policy_decision.clone());
+                execution_logger::log_response(ctx, Err(error.message.as_ref()), 0);
+            }
+            return Err(error);
+        }
+
         // Window management for UI interaction tools
         // Check if tool has a 'process' argument - if so, it needs window management
         // No whitelist - any tool with a process argument gets window management
@@ -9474,23 +9956,17 @@ impl DesktopWrapper {
 
         // Start execution logging - capture request before tool dispatch
         let start_time = std::time::Instant::now();
-        let (workflow_id, step_id, step_index) = execution_context
-            .as_ref()
-            .map(|ctx| {
-                (
-                    ctx.workflow_id.clone(),
-                    ctx.step_id.clone(),
-                    Some(ctx.current_step),
-                )
-            })
-            .unwrap_or((None, None, None));
         let log_ctx = execution_logger::log_request(
             tool_name,
             arguments,
             workflow_id.as_deref(),
             step_id.as_deref(),
             step_index,
-        );
+        )
+        .map(|ctx| {
+            ctx.with_retry_attempt(retry_attempt)
+                .with_policy_decision(policy_decision)
+        });
 
         // Start capturing tracing logs for this tool execution
         if let Some(ref log_capture) = self.log_capture {
@@ -10026,20 +10502,9 @@ impl DesktopWrapper {
 
         result
     }
-}
-
-// Manual implementation instead of #[tool_handler] to add execution logging
-impl ServerHandler for DesktopWrapper {
-    fn get_info(&self) -> ServerInfo {
-        ServerInfo {
-            protocol_version: ProtocolVersion::LATEST,
-            capabilities: ServerCapabilities::builder().enable_tools().build(),
-            server_info: Implementation::from_build_env(),
-            instructions: Some(crate::prompt::get_server_instructions().to_string()),
-        }
-    }
 
-    async fn call_tool(
+    /// Run a tool call with policy checks and execution logging
+    async fn handle_tool_call(
         &self,
         request: rmcp::model::CallToolRequestParam,
         context: rmcp::service::RequestContext<rmcp::RoleServer>,
@@ -10079,6 +10544,9 @@ impl ServerHandler for DesktopWrapper {
             }
         }
 
+        // Check the authenticated client's scopes
+        client_auth::check_tool_scope(&tool_name)?;
+
         // Reset cancellation state before starting a new tool call (except for stop_execution itself)
         // This clears any previous stop_execution() so new operations can run
         if tool_name != "stop_execution" {
@@ -10100,6 +10568,19 @@ impl ServerHandler for DesktopWrapper {
             step_id
         );
         let log_ctx = execution_logger::log_request(&tool_name, &arguments, wf_id, step_id, None);
+
+        // Apply the tool policy; refused calls are logged and never reach the tool
+        let policy_decision = self
+            .check_tool_policy(&context.peer, &tool_name, &arguments)
+            .await;
+        let log_ctx = log_ctx.map(|ctx| ctx.with_policy_decision(policy_decision.clone()));
+        if let Some(decision) = policy_decision.filter(|d| !d.permits()) {
+            let error = decision.to_error(&tool_name);
+            if let Some(ctx) = log_ctx {
+                execution_logger::log_response(ctx, Err(error.message.as_ref()), 0);
+            }
+            return Err(error);
+        }
         let start_time = std::time::Instant::now();
 
         // FOCUS RESTORATION: Extract restore_focus from arguments and save focus state BEFORE tool execution
@@ -10175,6 +10656,44 @@ impl ServerHandler for DesktopWrapper {
 
         result
     }
+}
+
+// Manual implementation instead of #[tool_handler] to add execution logging
+impl ServerHandler for DesktopWrapper {
+    fn get_info(&self) -> ServerInfo {
+        ServerInfo {
+            protocol_version: ProtocolVersion::LATEST,
+            capabilities: ServerCapabilities::builder()
+                .enable_tools()
+                .enable_logging()
+                .build(),
+            server_info: Implementation::from_build_env(),
+            instructions: Some(crate::prompt::get_server_instructions().to_string()),
+        }
+    }
+
+    async fn call_tool(
+        &self,
+        request: rmcp::model::CallToolRequestParam,
+        context: rmcp::service::RequestContext<rmcp::RoleServer>,
+    ) -> Result<CallToolResult, McpError> {
+        // Client identity stamped by the HTTP auth middleware (token auth only)
+        let client = context
+            .extensions
+            .get::<axum::http::request::Parts>()
+            .and_then(|parts| parts.extensions.get::<client_auth::ClientIdentity>())
+            .cloned();
+        client_auth::with_client(client, self.handle_tool_call(request, context)).await
+    }
+
+    async fn set_level(
+        &self,
+        request: rmcp::model::SetLevelRequestParam,
+        _context: rmcp::service::RequestContext<rmcp::RoleServer>,
+    ) -> Result<(), McpError> {
+        self.recording.set_log_level(request.level);
+        Ok(())
+    }
 
     async fn list_tools(
         &self,
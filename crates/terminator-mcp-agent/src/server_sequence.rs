@@ -1,12 +1,16 @@
+use crate::execution_history::{RunRecorder, RunStatus};
 use crate::execution_logger;
+use crate::execution_queue::{self, QueueLease, QueueRequest};
 use crate::helpers::substitute_variables;
 use crate::output_parser;
+use crate::retry_policy::{ErrorClass, RetryPolicy};
 use crate::server::extract_content_json;
 use crate::telemetry::{StepSpan, WorkflowSpan};
 use crate::utils::{
     DesktopWrapper, ExecuteSequenceArgs, SequenceItem, ToolCall, ToolGroup, VariableDefinition,
 };
 use crate::workflow_format::{detect_workflow_format, WorkflowFormat};
+use crate::workflow_state::{self, CheckpointStatus, WorkflowCheckpoint};
 use crate::workflow_typescript::{TypeScriptWorkflow, WorkflowEvent};
 use rmcp::model::{
     CallToolResult, Content, LoggingLevel, LoggingMessageNotificationParam, NumberOrString,
@@ -15,7 +19,8 @@ use rmcp::model::{
 use rmcp::service::{Peer, RequestContext, RoleServer};
 use rmcp::ErrorData as McpError;
 use serde_json::{json, Value};
-use std::path::{Path, PathBuf};
+use std::collections::HashMap;
+use std::path::Path;
 use std::sync::{Arc, Mutex};
 use std::time::Duration;
 use tokio::sync::mpsc;
@@ -46,6 +51,32 @@ impl SequenceGuard {
     }
 }
 
+/// History status of a finished `execute_sequence` call, from the `status` of its result
+fn run_outcome(result: &Result<CallToolResult, McpError>) -> (RunStatus, Option<String>) {
+    let result = match result {
+        Ok(result) => result,
+        Err(e) => return (RunStatus::Failed, Some(e.message.to_string())),
+    };
+    let summary = result
+        .content
+        .iter()
+        .filter_map(|content| extract_content_json(content).ok())
+        .find(|value| value.get("status").is_some());
+    let Some(summary) = summary else {
+        return (RunStatus::Completed, None);
+    };
+    let error = summary
+        .get("error")
+        .or_else(|| summary.get("message"))
+        .and_then(|e| e.as_str())
+        .map(str::to_string);
+    match summary["status"].as_str() {
+        Some("cancelled") => (RunStatus::Cancelled, None),
+        Some("executed_with_error" | "failed" | "error") => (RunStatus::Failed, error),
+        _ => (RunStatus::Completed, None),
+    }
+}
+
 /// Helper function to recursively validate a value against a variable definition
 fn validate_variable_value(
     variable_name: &str,
@@ -156,57 +187,41 @@ fn validate_variable_value(
     Ok(())
 }
 
-impl DesktopWrapper {
-    // Get the state file path for a workflow
-    // Uses OS-standard data directories:
-    //   Windows: %LOCALAPPDATA%\mediar\workflows\<workflow_id>\state.json
-    //   macOS: ~/Library/Application Support/mediar/workflows/<workflow_id>/state.json
-    //   Linux: ~/.local/share/mediar/workflows/<workflow_id>/state.json
-    // Priority: workflow_id > URL hash (for backward compatibility)
-    async fn get_state_file_path(
-        workflow_id: Option<&str>,
-        workflow_url: Option<&str>,
-    ) -> Option<PathBuf> {
-        let data_dir = dirs::data_local_dir()?;
-
-        // Priority 1: Use workflow_id if provided (cleaner, no hashing needed)
-        if let Some(id) = workflow_id {
-            debug!("Using workflow_id for state file: {}", id);
-            let state_file = data_dir
-                .join("mediar")
-                .join("workflows")
-                .join(id)
-                .join("state.json");
-            return Some(state_file);
-        }
-
-        // Priority 2: Fallback to URL hash for backward compatibility
-        if let Some(url) = workflow_url {
-            if let Some(file_path) = url.strip_prefix("file://") {
-                debug!("Using URL hash for state file: {}", url);
-                // Create a stable hash of the workflow file path
-                let workflow_hash = {
-                    use std::collections::hash_map::DefaultHasher;
-                    use std::hash::{Hash, Hasher};
-                    let mut hasher = DefaultHasher::new();
-                    file_path.hash(&mut hasher);
-                    format!("{:x}", hasher.finish())
-                };
-
-                let state_file = data_dir
-                    .join("mediar")
-                    .join("workflows")
-                    .join(workflow_hash)
-                    .join("state.json");
-
-                return Some(state_file);
+/// Check `inputs` against a workflow's `variables` schema: every required variable
+/// must be given (or have a default), and every value must match its definition
+pub(crate) fn validate_workflow_inputs(
+    variables: &HashMap<String, VariableDefinition>,
+    inputs: Option<&Value>,
+) -> Result<(), McpError> {
+    let inputs_map = inputs
+        .and_then(|v| v.as_object())
+        .cloned()
+        .unwrap_or_default();
+
+    for (key, def) in variables {
+        let value = inputs_map.get(key).or(def.default.as_ref());
+
+        match value {
+            Some(val) => {
+                // Use the recursive validation helper function
+                validate_variable_value(key, val, def)?;
+            }
+            None => {
+                if def.required.unwrap_or(true) {
+                    return Err(McpError::invalid_params(
+                        format!("Required variable '{key}' is missing."),
+                        None,
+                    ));
+                }
             }
         }
-
-        None
     }
+    Ok(())
+}
 
-    // Save env state after any step that modifies it
+impl DesktopWrapper {
+    // Save env state after any step that modifies it (used by TypeScript workflows;
+    // YAML sequences write full checkpoints through `workflow_state`)
     async fn save_workflow_state(
         workflow_id: Option<&str>,
         workflow_url: Option<&str>,
@@ -214,39 +229,18 @@ impl DesktopWrapper {
         step_index: usize,
         env: &serde_json::Value,
     ) -> Result<(), McpError> {
-        if let Some(state_file) = Self::get_state_file_path(workflow_id, workflow_url).await {
-            if let Some(state_dir) = state_file.parent() {
-                tokio::fs::create_dir_all(state_dir).await.map_err(|e| {
-                    McpError::internal_error(format!("Failed to create state directory: {e}"), None)
-                })?;
-            }
-
-            let state = json!({
-                "last_updated": chrono::Utc::now().to_rfc3339(),
-                "last_step_id": step_id,
-                "last_step_index": step_index,
-                "workflow_id": workflow_id,
-                "workflow_file": workflow_url.and_then(|url| {
-                    Path::new(url.strip_prefix("file://").unwrap_or(url))
-                        .file_name()
-                        .and_then(|n| n.to_str())
-                        .map(|s| s.to_string())
-                }),
-                "env": env,
-            });
-
-            tokio::fs::write(
-                &state_file,
-                serde_json::to_string_pretty(&state).map_err(|e| {
-                    McpError::internal_error(format!("Failed to serialize state: {e}"), None)
-                })?,
-            )
-            .await
-            .map_err(|e| {
-                McpError::internal_error(format!("Failed to write state file: {e}"), None)
-            })?;
-
-            debug!("Saved workflow state to: {:?}", state_file);
+        if let Some(state_file) = workflow_state::state_file_path(workflow_id, workflow_url) {
+            // Keep any checkpoint fields already on disk, only refresh the env snapshot
+            let mut checkpoint = WorkflowCheckpoint::load(&state_file)
+                .ok()
+                .flatten()
+                .unwrap_or_else(|| WorkflowCheckpoint::new(workflow_id, workflow_url, None, None));
+            checkpoint.last_step_id = step_id.map(String::from);
+            checkpoint.last_step_index = step_index;
+            checkpoint.env = env.clone();
+            checkpoint
+                .save(&state_file)
+                .map_err(|e| McpError::internal_error(format!("{e:#}"), None))?;
         }
         Ok(())
     }
@@ -256,28 +250,31 @@ impl DesktopWrapper {
         workflow_id: Option<&str>,
         workflow_url: Option<&str>,
     ) -> Result<Option<serde_json::Value>, McpError> {
-        if let Some(state_file) = Self::get_state_file_path(workflow_id, workflow_url).await {
-            if state_file.exists() {
-                let content = tokio::fs::read_to_string(&state_file).await.map_err(|e| {
-                    McpError::internal_error(format!("Failed to read state file: {e}"), None)
-                })?;
-                let state: serde_json::Value = serde_json::from_str(&content).map_err(|e| {
-                    McpError::internal_error(format!("Failed to parse state file: {e}"), None)
-                })?;
+        Ok(
+            Self::load_checkpoint(workflow_id, workflow_url)?.map(|checkpoint| {
+                debug!(
+                    "Loaded workflow state from step {} ({})",
+                    checkpoint.last_step_index,
+                    checkpoint.last_step_id.as_deref().unwrap_or("unknown")
+                );
+                checkpoint.env
+            }),
+        )
+    }
 
-                if let Some(env) = state.get("env") {
-                    debug!(
-                        "Loaded workflow state from step {} ({})",
-                        state["last_step_index"],
-                        state["last_step_id"].as_str().unwrap_or("unknown")
-                    );
-                    return Ok(Some(env.clone()));
-                }
-            } else {
-                debug!("No saved workflow state found at: {:?}", state_file);
-            }
+    fn load_checkpoint(
+        workflow_id: Option<&str>,
+        workflow_url: Option<&str>,
+    ) -> Result<Option<WorkflowCheckpoint>, McpError> {
+        let Some(state_file) = workflow_state::state_file_path(workflow_id, workflow_url) else {
+            return Ok(None);
+        };
+        let checkpoint = WorkflowCheckpoint::load(&state_file)
+            .map_err(|e| McpError::internal_error(format!("{e:#}"), None))?;
+        if checkpoint.is_none() {
+            debug!("No saved workflow state found at: {:?}", state_file);
         }
-        Ok(None)
+        Ok(checkpoint)
     }
 
     /// Helper function to create a flattened execution context where env properties
@@ -363,8 +360,52 @@ impl DesktopWrapper {
             log_source = "agent",
         );
 
+        // Nested execute_sequence steps run under their parent's lease; queueing them behind
+        // it would deadlock
+        let nested = execution_queue::in_queued_run();
+        let run = {
+            let request_id = request_id.clone();
+            async move {
+                // Wait for our turn: UI workflows hold the desktop exclusively, and runs
+                // sharing a concurrency key never overlap
+                let queue_lease = if nested {
+                    None
+                } else {
+                    Some(
+                        self.wait_in_queue(&peer, &request_context, &args, &request_id)
+                            .await,
+                    )
+                };
+                // Set the in_sequence flag for the duration of the run. Only one UI workflow
+                // runs at a time, so the single flag is enough; non-UI runs don't touch
+                // window focus. The flag is reset to false when this guard is dropped
+                let _sequence_guard = queue_lease
+                    .as_ref()
+                    .filter(|lease| lease.is_ui())
+                    .map(|_| SequenceGuard::new(self.in_sequence.clone()));
+                // Record the run in the execution history; tool calls logged while it runs
+                // become its steps. Dropping the recorder on cancel records it as cancelled
+                let recorder = RunRecorder::start(
+                    args.execution_id.as_deref().unwrap_or(&request_id),
+                    args.url.clone(),
+                    args.workflow_id.clone(),
+                );
+                let result = recorder
+                    .scope(execution_queue::scope(self.execute_sequence_inner(
+                        peer,
+                        request_context,
+                        args,
+                        request_id,
+                    )))
+                    .await;
+                let (status, error) = run_outcome(&result);
+                recorder.finish(status, error);
+                result
+            }
+        };
+
         tokio::select! {
-            result = self.execute_sequence_inner(peer, request_context, args, request_id.clone()).instrument(tracing_span) => {
+            result = run.instrument(tracing_span) => {
                 // Unregister when done
                 self.request_manager.unregister(&request_id).await;
                 result
@@ -380,6 +421,53 @@ impl DesktopWrapper {
         }
     }
 
+    /// Queue this run in the execution queue and wait until it may start, reporting the
+    /// queue position through progress notifications while it waits
+    async fn wait_in_queue(
+        &self,
+        peer: &Peer<RoleServer>,
+        request_context: &RequestContext<RoleServer>,
+        args: &ExecuteSequenceArgs,
+        request_id: &str,
+    ) -> QueueLease {
+        let request = QueueRequest {
+            id: request_id.to_string(),
+            workflow: args.url.clone().or_else(|| args.workflow_id.clone()),
+            priority: args.priority.unwrap_or(0),
+            concurrency_key: args.concurrency_key.clone(),
+            ui: args.ui.unwrap_or(true),
+        };
+        let progress_token = request_context
+            .meta
+            .get_progress_token()
+            .unwrap_or_else(|| {
+                ProgressToken(NumberOrString::String(format!("queue-{request_id}").into()))
+            });
+
+        self.execution_queue
+            .acquire(request, |position, queued| {
+                info!(
+                    "[execution_queue] {} waiting at position {} of {}",
+                    request_id, position, queued
+                );
+                let peer = peer.clone();
+                let progress_token = progress_token.clone();
+                async move {
+                    let _ = peer
+                        .notify_progress(ProgressNotificationParam {
+                            progress_token,
+                            progress: 0.0,
+                            total: None,
+                            message: Some(format!(
+                                "Queued at position {position} of {queued} (request {request_id})"
+                            )),
+                        })
+                        .await;
+                }
+            })
+            .await
+    }
+
     async fn execute_sequence_inner(
         &self,
         peer: Peer<RoleServer>,
@@ -387,10 +475,6 @@ impl DesktopWrapper {
         mut args: ExecuteSequenceArgs,
         execution_id: String,
     ) -> Result<CallToolResult, McpError> {
-        // Set the in_sequence flag for the duration of this function
-        // This flag will be automatically reset to false when this guard is dropped
-        let _sequence_guard = SequenceGuard::new(self.in_sequence.clone());
-
         // Validate that either URL or steps are provided
         if args.url.is_none() && args.steps.as_ref().map(|s| s.is_empty()).unwrap_or(true) {
             return Err(McpError::invalid_params(
@@ -688,31 +772,7 @@ impl DesktopWrapper {
 
         // Re-enabling validation logic
         if let Some(variable_schema) = &args.variables {
-            let inputs_map = args
-                .inputs
-                .as_ref()
-                .and_then(|v| v.as_object())
-                .cloned()
-                .unwrap_or_default();
-
-            for (key, def) in variable_schema {
-                let value = inputs_map.get(key).or(def.default.as_ref());
-
-                match value {
-                    Some(val) => {
-                        // Use the recursive validation helper function
-                        validate_variable_value(key, val, def)?;
-                    }
-                    None => {
-                        if def.required.unwrap_or(true) {
-                            return Err(McpError::invalid_params(
-                                format!("Required variable '{key}' is missing."),
-                                None,
-                            ));
-                        }
-                    }
-                }
-            }
+            validate_workflow_inputs(variable_schema, args.inputs.as_ref())?;
         }
 
         // Build the execution context. It's a combination of the 'inputs' and 'selectors'.
@@ -787,7 +847,6 @@ impl DesktopWrapper {
         execution_context_map.insert("env".to_string(), serde_json::Value::Object(env_map));
 
         // Build a map from step ID to its index for quick lookup (includes both main and troubleshooting steps)
-        use std::collections::HashMap;
         let mut id_to_index: HashMap<String, usize> = HashMap::new();
 
         // Map main workflow steps
@@ -823,7 +882,7 @@ impl DesktopWrapper {
         }
 
         // NEW: Check if we should start from a specific step (now searches both main and troubleshooting)
-        let start_from_index = if let Some(start_step) = &args.start_from_step {
+        let mut start_from_index = if let Some(start_step) = &args.start_from_step {
             // Find the step index by ID using the complete map
             id_to_index.get(start_step).copied().ok_or_else(|| {
                 McpError::invalid_params(
@@ -858,12 +917,108 @@ impl DesktopWrapper {
             main_steps_len.saturating_sub(1)
         };
 
-        // NEW: Load saved state if starting from a specific step
-        if start_from_index > 0 {
-            if let Some(saved_env) =
-                Self::load_workflow_state(args.workflow_id.as_deref(), args.url.as_deref()).await?
+        // Checkpoints are keyed by workflow_id (or file:// URL) and tied to this exact definition
+        let sequence_len =
+            main_steps_len + args.troubleshooting.as_ref().map(|t| t.len()).unwrap_or(0);
+        let state_file =
+            workflow_state::state_file_path(args.workflow_id.as_deref(), args.url.as_deref());
+        let workflow_hash = workflow_state::content_hash(&json!({
+            "steps": &args.steps,
+            "troubleshooting": &args.troubleshooting,
+        }));
+        let mut checkpoint = WorkflowCheckpoint::new(
+            args.workflow_id.as_deref(),
+            args.url.as_deref(),
+            Some(workflow_hash.clone()),
+            Some(execution_id.clone()),
+        );
+
+        // Resume: continue from the last checkpoint, including group and retry position
+        let mut resume_group_from: Option<(usize, usize)> = None;
+        let mut resume_attempt_from: Option<(usize, u32)> = None;
+        let mut resumed_from: Option<Value> = None;
+        if args.resume.unwrap_or(false) {
+            if args.start_from_step.is_some() {
+                return Err(McpError::invalid_params(
+                    "'resume' and 'start_from_step' cannot be used together",
+                    None,
+                ));
+            }
+            let Some(state_path) = state_file.as_deref() else {
+                return Err(McpError::invalid_params(
+                    "'resume' requires a workflow_id or a file:// url to locate saved state",
+                    None,
+                ));
+            };
+            let saved = Self::load_checkpoint(args.workflow_id.as_deref(), args.url.as_deref())?;
+            match saved {
+                Some(saved) if saved.is_stale(&workflow_hash) => {
+                    return Err(McpError::invalid_params(
+                        "Saved state belongs to a different version of this workflow. Clear it (terminator mcp state clear) or run without 'resume'.",
+                        Some(json!({
+                            "error_type": "stale_workflow_state",
+                            "state_file": state_path,
+                            "saved_hash": saved.content_hash,
+                            "current_hash": workflow_hash,
+                        })),
+                    ));
+                }
+                Some(saved) => match saved.resume_point() {
+                    Some(point) if point.step_index < sequence_len => {
+                        info!(
+                            "Resuming workflow at step index {} (group sub-step {}, attempt {}) from checkpoint {:?}",
+                            point.step_index, point.group_step_index, point.retry_attempt + 1, state_path
+                        );
+                        start_from_index = point.step_index;
+                        if point.group_step_index > 0 {
+                            resume_group_from = Some((point.step_index, point.group_step_index));
+                        }
+                        if point.retry_attempt > 0 {
+                            resume_attempt_from = Some((point.step_index, point.retry_attempt));
+                        }
+
+                        // Restore env and {id}_result / {id}_status from the previous run
+                        execution_context_map.insert("env".to_string(), saved.env.clone());
+                        for (key, result) in &saved.step_results {
+                            if key.starts_with('#') {
+                                continue;
+                            }
+                            execution_context_map
+                                .insert(format!("{key}_status"), result["status"].clone());
+                            if let Some(content) = result.get("result") {
+                                execution_context_map.insert(
+                                    format!("{key}_result"),
+                                    content.get("content").unwrap_or(content).clone(),
+                                );
+                            }
+                        }
+                        checkpoint.step_results = saved.step_results.clone();
+                        checkpoint.last_successful_step_index = saved.last_successful_step_index;
+                        resumed_from = Some(json!({
+                            "step_index": point.step_index,
+                            "group_step_index": point.group_step_index,
+                            "retry_attempt": point.retry_attempt,
+                            "previous_execution_id": saved.execution_id,
+                            "previous_status": saved.status,
+                        }));
+                    }
+                    _ => info!("Saved workflow state is complete; starting from the beginning"),
+                },
+                None => {
+                    info!("No saved workflow state to resume from; starting from the beginning")
+                }
+            }
+        } else if start_from_index > 0 {
+            // Load saved state if starting from a specific step
+            if let Some(saved) =
+                Self::load_checkpoint(args.workflow_id.as_deref(), args.url.as_deref())?
             {
-                execution_context_map.insert("env".to_string(), saved_env);
+                if saved.is_stale(&workflow_hash) {
+                    warn!(
+                        "Saved workflow state was created for a different version of this workflow; reusing its env anyway"
+                    );
+                }
+                execution_context_map.insert("env".to_string(), saved.env);
                 debug!(
                     "Loaded saved env state for resuming from step {}",
                     start_from_index
@@ -1334,6 +1489,14 @@ impl DesktopWrapper {
                 break; // Exit loop gracefully and return partial results
             }
 
+            // Checkpoint the next step before anything runs so a crash resumes here
+            checkpoint.next_step_index = Some(current_index);
+            checkpoint.position = None;
+            if let Some(env_value) = execution_context_map.get("env") {
+                checkpoint.env = env_value.clone();
+            }
+            checkpoint.persist(state_file.as_deref()).await;
+
             // Get the original step from either main steps or troubleshooting steps
             let original_step = if current_index < main_steps_len {
                 args.steps.as_ref().and_then(|s| s.get(current_index))
@@ -1344,15 +1507,18 @@ impl DesktopWrapper {
             };
 
             // Extract values from the step if it exists
-            let (if_expr, retries, fallback_id_opt) = if let Some(step) = original_step {
+            let (if_expr, retry_policy, fallback_id_opt) = if let Some(step) = original_step {
                 (
                     step.r#if.clone(),
-                    step.retries.unwrap_or(0),
+                    step.retry
+                        .clone()
+                        .unwrap_or_else(|| RetryPolicy::from_retries(step.retries.unwrap_or(0))),
                     step.fallback_id.clone(),
                 )
             } else {
-                (None, 0, None)
+                (None, RetryPolicy::from_retries(0), None)
             };
+            let retries = retry_policy.max_retries();
 
             let is_always_step = if_expr.as_deref().is_some_and(|s| s.trim() == "always()");
 
@@ -1398,7 +1564,7 @@ impl DesktopWrapper {
                         current_index,
                         tool_name,
                         step.id.as_deref().unwrap_or(""),
-                        step.retries.unwrap_or(0),
+                        retries,
                         step.r#if,
                         step.fallback_id,
                         step.jumps.as_ref().map(|j| j.len()).unwrap_or(0)
@@ -1418,8 +1584,29 @@ impl DesktopWrapper {
             let mut final_result = json!(null);
             let mut step_error_occurred = false;
             let total_steps = sequence_items.len();
+            let critical_error_before_step = critical_error_occurred;
+            let mut retry_reason: Option<String> = None;
+            let mut retry_history: Vec<Value> = Vec::new();
+
+            // A resumed run continues the retry count of the interrupted attempt
+            let first_attempt = resume_attempt_from
+                .take()
+                .filter(|(idx, _)| *idx == current_index)
+                .map(|(_, attempt)| attempt.min(retries))
+                .unwrap_or(0);
+
+            for attempt in first_attempt..=retries {
+                // Each attempt starts clean; only the outcome of the last one counts
+                step_error_occurred = false;
+                critical_error_occurred = critical_error_before_step;
+
+                checkpoint.begin_step(
+                    current_index,
+                    original_step.and_then(|s| s.id.as_deref()),
+                    attempt,
+                );
+                checkpoint.persist(state_file.as_deref()).await;
 
-            for attempt in 0..=retries {
                 let item = &mut sequence_items[current_index];
                 match item {
                     SequenceItem::Tool { tool_call } => {
@@ -1501,6 +1688,10 @@ impl DesktopWrapper {
                         step_span.set_attribute("step.total", total_steps.to_string());
                         if attempt > 0 {
                             step_span.set_attribute("step.retry_attempt", attempt.to_string());
+                            step_span.record_retry(
+                                attempt,
+                                retry_reason.as_deref().unwrap_or("unknown"),
+                            );
                         }
 
                         // Add workflow execution_id to step for correlation
@@ -1576,7 +1767,8 @@ impl DesktopWrapper {
                                 total_steps,
                                 last_executed_process.clone(),
                             )
-                            .with_workflow_context(args.workflow_id.clone(), step_id.clone()),
+                            .with_workflow_context(args.workflow_id.clone(), step_id.clone())
+                            .with_retry_attempt(attempt),
                         );
 
                         let (result, error_occurred) = self
@@ -1653,16 +1845,9 @@ impl DesktopWrapper {
                                         step_id, result_key
                                     );
 
-                                    // Save state after storing tool result
-                                    Self::save_workflow_state(
-                                        args.workflow_id.as_deref(),
-                                        args.url.as_deref(),
-                                        Some(step_id),
-                                        current_index,
-                                        env_value,
-                                    )
-                                    .await
-                                    .ok(); // Don't fail the workflow if state save fails
+                                    // Checkpoint after storing tool result
+                                    checkpoint.env = env_value.clone();
+                                    checkpoint.persist(state_file.as_deref()).await;
                                 }
                             }
                         }
@@ -1919,17 +2104,10 @@ impl DesktopWrapper {
                                 }
                             }
 
-                            // NEW: Save state after env update
+                            // Checkpoint after env update
                             if let Some(env_value) = execution_context_map.get("env") {
-                                Self::save_workflow_state(
-                                    args.workflow_id.as_deref(),
-                                    args.url.as_deref(),
-                                    original_step.and_then(|s| s.id.as_deref()),
-                                    current_index,
-                                    env_value,
-                                )
-                                .await
-                                .ok(); // Don't fail the workflow if state save fails
+                                checkpoint.env = env_value.clone();
+                                checkpoint.persist(state_file.as_deref()).await;
                             }
                         }
                         // Check for executed_without_error status or legacy success boolean
@@ -1999,9 +2177,26 @@ impl DesktopWrapper {
                         let mut group_had_errors = false;
                         let mut group_results = Vec::new();
                         let is_skippable = tool_group.skippable.unwrap_or(false);
+                        // Sub-steps that already succeeded before an interrupted run
+                        let completed_before_resume = resume_group_from
+                            .take()
+                            .filter(|(idx, _)| *idx == current_index)
+                            .map(|(_, completed)| completed)
+                            .unwrap_or(0);
 
                         for (step_index, step_tool_call) in tool_group.steps.iter_mut().enumerate()
                         {
+                            if step_index < completed_before_resume {
+                                group_results.push(json!({
+                                    "tool_name": step_tool_call.tool_name,
+                                    "index": step_index,
+                                    "status": "skipped",
+                                    "reason": "Completed before resume",
+                                }));
+                                checkpoint.advance_group(step_index + 1);
+                                continue;
+                            }
+
                             // Substitute variables in arguments before execution
                             let execution_context =
                                 Self::create_flattened_execution_context(&execution_context_map);
@@ -2026,7 +2221,8 @@ impl DesktopWrapper {
                                 .with_workflow_context(
                                     args.workflow_id.clone(),
                                     step_id_for_ctx.clone(),
-                                ),
+                                )
+                                .with_retry_attempt(attempt),
                             );
 
                             let (result, error_occurred) = self
@@ -2060,6 +2256,10 @@ impl DesktopWrapper {
                             let tool_failed = !(result["status"] == "executed_without_error"
                                 || result["success"] == true
                                 || (result["status"].is_null() && result["success"] != false));
+                            if !tool_failed {
+                                checkpoint.advance_group(step_index + 1);
+                                checkpoint.persist(state_file.as_deref()).await;
+                            }
                             if tool_failed {
                                 group_had_errors = true;
                                 if error_occurred || is_skippable {
@@ -2113,19 +2313,108 @@ impl DesktopWrapper {
                     }
                 }
                 if attempt < retries {
+                    let error_class = ErrorClass::from_step_result(&final_result);
+                    if !retry_policy.should_retry(error_class) {
+                        info!(
+                            "Step {} failed with {:?}, which the retry policy does not cover. Not retrying.",
+                            current_index, error_class
+                        );
+                        break;
+                    }
+
+                    let retry_number = attempt + 1;
+                    let delay = retry_policy.delay_for_retry(retry_number);
                     warn!(
-                        "Step {} failed on attempt {}/{}. Retrying...",
+                        "Step {} failed on attempt {}/{} ({:?}). Retrying in {}ms...",
                         current_index,
                         attempt + 1,
-                        retries
+                        retries + 1,
+                        error_class,
+                        delay.as_millis()
                     );
-                    tokio::time::sleep(Duration::from_millis(500)).await; // Wait before retry
+                    retry_history.push(json!({
+                        "attempt": attempt + 1,
+                        "error_class": error_class,
+                        "error": final_result.get("error").cloned().unwrap_or(Value::Null),
+                        "delay_ms": delay.as_millis() as u64,
+                    }));
+                    retry_reason = Some(format!("{error_class:?}"));
+
+                    // Tool steps record the retry on the next attempt's span; groups have none
+                    if let Some(group_name) = original_step.and_then(|s| s.group_name.as_deref()) {
+                        let mut retry_span =
+                            StepSpan::new(group_name, original_step.and_then(|s| s.id.as_deref()));
+                        retry_span.record_retry(retry_number, &format!("{error_class:?}"));
+                        retry_span.end();
+                    }
+
+                    if let Some(before_retry) = &retry_policy.before_retry {
+                        for hook in before_retry {
+                            let execution_context =
+                                Self::create_flattened_execution_context(&execution_context_map);
+                            let mut hook_args = hook.arguments.clone();
+                            substitute_variables(&mut hook_args, &execution_context);
+
+                            let hook_context = Some(
+                                crate::utils::ToolExecutionContext::sequence_step(
+                                    args.url.clone().unwrap_or_default(),
+                                    current_index + 1,
+                                    total_steps,
+                                    last_executed_process.clone(),
+                                )
+                                .with_workflow_context(args.workflow_id.clone(), hook.id.clone()),
+                            );
+                            let (hook_result, _) = self
+                                .execute_single_tool(
+                                    peer.clone(),
+                                    request_context.clone(),
+                                    &hook.tool_name,
+                                    &hook_args,
+                                    true,
+                                    current_index,
+                                    include_detailed,
+                                    hook.id.as_deref(),
+                                    hook_context,
+                                )
+                                .await;
+                            if hook_result["status"] != "executed_without_error" {
+                                warn!(
+                                    "before_retry step '{}' for step {} did not succeed: {}",
+                                    hook.tool_name,
+                                    current_index,
+                                    hook_result["error"].as_str().unwrap_or("unknown error")
+                                );
+                            }
+                            if let Some(delay_ms) = hook.delay_ms {
+                                if delay_ms > 0 {
+                                    tokio::time::sleep(Duration::from_millis(delay_ms)).await;
+                                }
+                            }
+                        }
+                    }
+
+                    tokio::time::sleep(delay).await;
                 }
             }
 
             // Mark this step as executed (not skipped) and add to results
             if let Some(obj) = final_result.as_object_mut() {
                 obj.insert("executed".to_string(), json!(true));
+                if !retry_history.is_empty() {
+                    obj.insert("retry_history".to_string(), json!(retry_history));
+                }
+            }
+            checkpoint.complete_step(
+                current_index,
+                original_step.and_then(|s| s.id.as_deref()),
+                !step_error_occurred,
+                &final_result,
+            );
+            if step_error_occurred
+                && critical_error_occurred
+                && checkpoint.failed_step_index.is_none()
+            {
+                checkpoint.failed_step_index = Some(current_index);
             }
             results.push(final_result);
             actually_executed_count += 1;
@@ -2310,6 +2599,20 @@ impl DesktopWrapper {
             warn!("Maximum iteration count reached. Possible infinite fallback loop detected.");
         }
 
+        // Final checkpoint: completed runs are not resumed, failed/cancelled ones are
+        checkpoint.status = if cancelled_by_user {
+            CheckpointStatus::Cancelled
+        } else if critical_error_occurred {
+            CheckpointStatus::Failed
+        } else {
+            CheckpointStatus::Completed
+        };
+        checkpoint.next_step_index = Some(current_index);
+        if let Some(env_value) = execution_context_map.get("env") {
+            checkpoint.env = env_value.clone();
+        }
+        checkpoint.persist(state_file.as_deref()).await;
+
         let total_duration = (chrono::Utc::now() - start_time).num_milliseconds();
 
         // Determine final status - executed_without_error, executed_with_error, or cancelled
@@ -2353,6 +2656,9 @@ impl DesktopWrapper {
             "execution_log_path": log_paths.json_path,
             "typescript_snippet_path": log_paths.ts_path,
         });
+        if let (Some(resumed_from), Some(obj)) = (resumed_from, summary.as_object_mut()) {
+            obj.insert("resumed_from".to_string(), resumed_from);
+        }
 
         // Support both 'output_parser' (legacy) and 'output' (simplified)
         let parser_def = args.output_parser.as_ref().or(args.output.as_ref());
@@ -2607,6 +2913,12 @@ impl DesktopWrapper {
                     "duration_ms": duration_ms,
                     "error": format!("{}", e),
                 });
+                // Keep structured error data (e.g. selectors tried) for CI reports
+                if let Some(data) = &e.data {
+                    if let Some(obj) = error_result.as_object_mut() {
+                        obj.insert("error_details".to_string(), data.clone());
+                    }
+                }
 
                 // Include server logs in error result if captured
                 if let Some(logs) = captured_logs {
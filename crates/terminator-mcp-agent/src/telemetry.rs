@@ -47,6 +47,9 @@ mod with_telemetry {
                 .with_kind(SpanKind::Server)
                 .start(&tracer);
             span.set_attribute(KeyValue::new("workflow.name", name.to_string()));
+            if let Some(client) = crate::client_auth::current_client_name() {
+                span.set_attribute(KeyValue::new("client.name", client));
+            }
             WorkflowSpan { span }
         }
 
@@ -103,6 +106,9 @@ mod with_telemetry {
             if let Some(id) = step_id {
                 span.set_attribute(KeyValue::new("step.id", id.to_string()));
             }
+            if let Some(client) = crate::client_auth::current_client_name() {
+                span.set_attribute(KeyValue::new("client.name", client));
+            }
 
             StepSpan {
                 span,
@@ -0,0 +1,627 @@
+//! Tool Policy Engine
+//!
+//! Decides whether a tool call may run, based on a policy file loaded at
+//! startup (`--policy` / `TERMINATOR_MCP_POLICY`) and reloaded whenever the
+//! file changes on disk.
+//!
+//! ```yaml
+//! default: allow              # allow | deny | confirm
+//! rules:
+//!   - name: no-recursive-delete
+//!     tools: [run_command]
+//!     args:
+//!       run: 'rm\s+-rf|Remove-Item\s.*-Recurse'
+//!     action: deny
+//!     message: Recursive deletes are not allowed
+//!   - name: banking-needs-confirmation
+//!     tools: [navigate_browser]
+//!     args:
+//!       url: { domains: ["mybank.com", "*.mybank.com"] }
+//!     action: confirm
+//!   - tools: ["*"]
+//!     process: [regedit, mmc]
+//!     action: deny
+//! ```
+//!
+//! Rules are checked in order and the first match wins; calls no rule matches
+//! get `default`. Within a rule every condition must hold: the tool name
+//! matches one of `tools` (glob patterns, empty means any tool), each `args`
+//! entry matches the argument at that dotted path, and the `process` argument
+//! matches one of `process` (case-insensitive, `.exe` ignored).
+//!
+//! `confirm` decisions are resolved by the server through elicitation with the
+//! [`ActionConfirmation`] schema; clients that can't be asked are refused.
+
+use crate::elicitation::ActionConfirmation;
+use regex::Regex;
+use rmcp::ErrorData as McpError;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, OnceLock, RwLock};
+use std::time::SystemTime;
+use tracing::{info, warn};
+
+/// What to do with a matching tool call
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PolicyAction {
+    #[default]
+    Allow,
+    Deny,
+    Confirm,
+}
+
+/// Outcome of evaluating the policy for one tool call (written to the execution log)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PolicyDecision {
+    pub action: PolicyAction,
+    /// Name of the matching rule (`rules[<index>]` if unnamed); absent for the default
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rule: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+    /// For `confirm`: whether the user approved the call
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub confirmed: Option<bool>,
+    /// Notes the user gave when answering the confirmation
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub notes: Option<String>,
+}
+
+impl PolicyDecision {
+    /// Whether the tool call may proceed
+    pub fn permits(&self) -> bool {
+        match self.action {
+            PolicyAction::Allow => true,
+            PolicyAction::Deny => false,
+            PolicyAction::Confirm => self.confirmed == Some(true),
+        }
+    }
+
+    /// Prompt shown to the user for a `confirm` decision
+    pub fn confirmation_prompt(&self, tool_name: &str) -> String {
+        let rule = self
+            .rule
+            .as_deref()
+            .map(|r| format!(" (policy rule '{r}')"))
+            .unwrap_or_default();
+        match &self.message {
+            Some(message) => format!("{message}\n\nAllow '{tool_name}' to run{rule}?"),
+            None => format!("Allow '{tool_name}' to run{rule}?"),
+        }
+    }
+
+    /// Record the user's answer to a confirmation request.
+    /// `None` means nobody could be asked, which refuses the call.
+    pub fn record_confirmation(&mut self, answer: Option<ActionConfirmation>) {
+        match answer {
+            Some(answer) => {
+                self.confirmed = Some(answer.confirmed);
+                self.notes = answer.notes;
+            }
+            None => {
+                self.confirmed = Some(false);
+                self.notes = Some("No elicitation-capable client available to confirm".into());
+            }
+        }
+    }
+
+    /// Error returned to the client when the call is refused
+    pub fn to_error(&self, tool_name: &str) -> McpError {
+        let reason = if self.action == PolicyAction::Confirm {
+            "was not confirmed"
+        } else {
+            "is denied"
+        };
+        let mut message = match &self.rule {
+            Some(rule) => format!("Tool '{tool_name}' {reason} by policy rule '{rule}'"),
+            None => format!("Tool '{tool_name}' {reason} by the default policy"),
+        };
+        if let Some(detail) = &self.message {
+            message.push_str(": ");
+            message.push_str(detail);
+        }
+        McpError::invalid_request(
+            message,
+            Some(json!({
+                "code": -32003,
+                "tool": tool_name,
+                "policy": self,
+            })),
+        )
+    }
+}
+
+/// Policy file contents, as written by the user
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct PolicyFile {
+    #[serde(default)]
+    default: PolicyAction,
+    #[serde(default)]
+    rules: Vec<RuleSpec>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RuleSpec {
+    #[serde(default)]
+    name: Option<String>,
+    #[serde(default)]
+    tools: Vec<String>,
+    #[serde(default)]
+    args: BTreeMap<String, ArgSpec>,
+    #[serde(default)]
+    process: Vec<String>,
+    action: PolicyAction,
+    #[serde(default)]
+    message: Option<String>,
+}
+
+/// Argument condition: a bare string is a regex
+#[derive(Debug, Clone, Deserialize)]
+#[serde(untagged)]
+enum ArgSpec {
+    Regex(String),
+    Detailed(ArgDetails),
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ArgDetails {
+    #[serde(default)]
+    regex: Option<String>,
+    /// Host patterns for URL arguments (`example.com`, `*.example.com`)
+    #[serde(default)]
+    domains: Vec<String>,
+    #[serde(default)]
+    equals: Option<Value>,
+}
+
+/// A loaded, validated policy
+#[derive(Debug, Clone, Default)]
+pub struct ToolPolicy {
+    default: PolicyAction,
+    rules: Vec<Rule>,
+}
+
+#[derive(Debug, Clone)]
+struct Rule {
+    label: String,
+    tools: Vec<glob::Pattern>,
+    args: Vec<(String, ArgMatcher)>,
+    process: Vec<glob::Pattern>,
+    action: PolicyAction,
+    message: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+struct ArgMatcher {
+    regex: Option<Regex>,
+    domains: Vec<glob::Pattern>,
+    equals: Option<Value>,
+}
+
+impl ToolPolicy {
+    /// Parse a policy from YAML (or JSON, which is valid YAML)
+    pub fn parse(content: &str) -> Result<Self, String> {
+        let file: PolicyFile =
+            serde_yaml::from_str(content).map_err(|e| format!("Invalid policy file: {e}"))?;
+
+        let mut rules = Vec::with_capacity(file.rules.len());
+        for (index, spec) in file.rules.into_iter().enumerate() {
+            let label = spec.name.unwrap_or_else(|| format!("rules[{index}]"));
+            let pattern = |p: &str| {
+                glob::Pattern::new(p).map_err(|e| format!("{label}: invalid pattern '{p}': {e}"))
+            };
+            let tools = spec
+                .tools
+                .iter()
+                .map(|t| pattern(t))
+                .collect::<Result<_, _>>()?;
+            let process = spec
+                .process
+                .iter()
+                .map(|p| pattern(&normalize_process(p)))
+                .collect::<Result<_, _>>()?;
+            let mut args = Vec::with_capacity(spec.args.len());
+            for (path, arg) in spec.args {
+                let details = match arg {
+                    ArgSpec::Regex(regex) => ArgDetails {
+                        regex: Some(regex),
+                        domains: Vec::new(),
+                        equals: None,
+                    },
+                    ArgSpec::Detailed(details) => details,
+                };
+                let regex = details
+                    .regex
+                    .as_deref()
+                    .map(Regex::new)
+                    .transpose()
+                    .map_err(|e| format!("{label}: invalid regex for '{path}': {e}"))?;
+                let domains = details
+                    .domains
+                    .iter()
+                    .map(|d| pattern(&d.to_lowercase()))
+                    .collect::<Result<_, _>>()?;
+                args.push((
+                    path,
+                    ArgMatcher {
+                        regex,
+                        domains,
+                        equals: details.equals,
+                    },
+                ));
+            }
+            rules.push(Rule {
+                label,
+                tools,
+                args,
+                process,
+                action: spec.action,
+                message: spec.message,
+            });
+        }
+
+        Ok(Self {
+            default: file.default,
+            rules,
+        })
+    }
+
+    /// Load a policy file from disk
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let content = std::fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read policy file {}: {e}", path.display()))?;
+        Self::parse(&content)
+    }
+
+    /// Decide what to do with a tool call. `confirm` decisions still need
+    /// [`PolicyDecision::record_confirmation`] before they permit anything.
+    pub fn evaluate(&self, tool_name: &str, arguments: &Value) -> PolicyDecision {
+        let matched = self
+            .rules
+            .iter()
+            .find(|rule| rule.matches(tool_name, arguments));
+        match matched {
+            Some(rule) => PolicyDecision {
+                action: rule.action,
+                rule: Some(rule.label.clone()),
+                message: rule.message.clone(),
+                confirmed: None,
+                notes: None,
+            },
+            None => PolicyDecision {
+                action: self.default,
+                rule: None,
+                message: None,
+                confirmed: None,
+                notes: None,
+            },
+        }
+    }
+}
+
+impl Rule {
+    fn matches(&self, tool_name: &str, arguments: &Value) -> bool {
+        if !self.tools.is_empty() && !self.tools.iter().any(|p| p.matches(tool_name)) {
+            return false;
+        }
+        if !self.process.is_empty() {
+            let Some(process) = arguments.get("process").and_then(Value::as_str) else {
+                return false;
+            };
+            let process = normalize_process(process);
+            if !self.process.iter().any(|p| p.matches(&process)) {
+                return false;
+            }
+        }
+        self.args.iter().all(|(path, matcher)| {
+            lookup(arguments, path)
+                .into_iter()
+                .any(|value| matcher.matches(value))
+        })
+    }
+}
+
+impl ArgMatcher {
+    fn matches(&self, value: &Value) -> bool {
+        if let Value::Array(items) = value {
+            return items.iter().any(|item| self.matches(item));
+        }
+        if let Some(expected) = &self.equals {
+            if expected != value {
+                return false;
+            }
+        }
+        let text = match value {
+            Value::String(s) => s.clone(),
+            Value::Null => String::new(),
+            other => other.to_string(),
+        };
+        if let Some(regex) = &self.regex {
+            if !regex.is_match(&text) {
+                return false;
+            }
+        }
+        if !self.domains.is_empty() {
+            let Some(host) = url_host(&text) else {
+                return false;
+            };
+            if !self.domains.iter().any(|d| d.matches(&host)) {
+                return false;
+            }
+        }
+        true
+    }
+}
+
+/// Resolve a dotted argument path (`url`, `options.target`, `steps.0.tool_name`).
+/// A non-numeric key applied to an array is applied to each of its items, so
+/// `steps.tool_name` yields the tool name of every step.
+fn lookup<'a>(arguments: &'a Value, path: &str) -> Vec<&'a Value> {
+    let mut current = vec![arguments];
+    for key in path.split('.') {
+        current = current
+            .into_iter()
+            .flat_map(|value| match value {
+                Value::Array(items) => match key.parse::<usize>() {
+                    Ok(index) => items.get(index).into_iter().collect(),
+                    Err(_) => items.iter().filter_map(|item| item.get(key)).collect(),
+                },
+                _ => value.get(key).into_iter().collect::<Vec<_>>(),
+            })
+            .collect();
+    }
+    current
+}
+
+/// Host of a URL argument; bare hosts (`example.com/path`) are accepted too
+fn url_host(text: &str) -> Option<String> {
+    let text = text.trim();
+    let parsed = reqwest::Url::parse(text)
+        .ok()
+        .filter(|url| url.host_str().is_some())
+        .or_else(|| reqwest::Url::parse(&format!("https://{text}")).ok())?;
+    parsed.host_str().map(|h| h.to_lowercase())
+}
+
+fn normalize_process(name: &str) -> String {
+    let lower = name.trim().to_lowercase();
+    lower
+        .strip_suffix(".exe")
+        .map(str::to_string)
+        .unwrap_or(lower)
+}
+
+/// The active policy file and the last successfully loaded version of it
+struct PolicyStore {
+    path: PathBuf,
+    state: RwLock<LoadedPolicy>,
+}
+
+struct LoadedPolicy {
+    modified: Option<SystemTime>,
+    policy: Arc<ToolPolicy>,
+}
+
+static POLICY: OnceLock<PolicyStore> = OnceLock::new();
+
+fn modified_time(path: &Path) -> Option<SystemTime> {
+    std::fs::metadata(path).and_then(|m| m.modified()).ok()
+}
+
+impl PolicyStore {
+    fn load(path: &Path) -> Result<Self, String> {
+        let modified = modified_time(path);
+        let policy = ToolPolicy::load(path)?;
+        info!(
+            "[tool_policy] Loaded {} rule(s) from {} (default: {:?})",
+            policy.rules.len(),
+            path.display(),
+            policy.default
+        );
+        Ok(Self {
+            path: path.to_path_buf(),
+            state: RwLock::new(LoadedPolicy {
+                modified,
+                policy: Arc::new(policy),
+            }),
+        })
+    }
+
+    /// The loaded policy, reloaded first if the file changed since the last call
+    fn current(&self) -> Arc<ToolPolicy> {
+        let modified = modified_time(&self.path);
+        {
+            let state = self.state.read().unwrap_or_else(|e| e.into_inner());
+            if modified.is_none() || state.modified == modified {
+                return state.policy.clone();
+            }
+        }
+
+        let mut state = self.state.write().unwrap_or_else(|e| e.into_inner());
+        if state.modified != modified {
+            state.modified = modified;
+            match ToolPolicy::load(&self.path) {
+                Ok(policy) => {
+                    info!(
+                        "[tool_policy] Reloaded {} rule(s) from {}",
+                        policy.rules.len(),
+                        self.path.display()
+                    );
+                    state.policy = Arc::new(policy);
+                }
+                Err(e) => warn!("[tool_policy] Keeping previous policy: {}", e),
+            }
+        }
+        state.policy.clone()
+    }
+}
+
+/// Load the policy file at startup. Fails if the file can't be read or parsed.
+pub fn init(path: &Path) -> Result<(), String> {
+    POLICY
+        .set(PolicyStore::load(path)?)
+        .map_err(|_| "Tool policy already initialized".to_string())
+}
+
+/// The active policy, or `None` when no policy file is configured.
+///
+/// Reloads the file if it changed since the last call. A file that fails to
+/// load is reported and the previous policy stays in effect.
+pub fn current() -> Option<Arc<ToolPolicy>> {
+    POLICY.get().map(PolicyStore::current)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const POLICY: &str = r#"
+default: allow
+rules:
+  - name: no-recursive-delete
+    tools: [run_command]
+    args:
+      run: 'rm\s+-rf'
+    action: deny
+    message: Recursive deletes are not allowed
+  - name: bank
+    tools: [navigate_browser, open_application]
+    args:
+      url: { domains: ["mybank.com", "*.mybank.com"] }
+    action: confirm
+  - tools: ["*"]
+    process: [regedit]
+    action: deny
+"#;
+
+    #[test]
+    fn test_first_matching_rule_wins() {
+        let policy = ToolPolicy::parse(POLICY).unwrap();
+
+        let decision = policy.evaluate("run_command", &json!({"run": "rm -rf /tmp/x"}));
+        assert_eq!(decision.action, PolicyAction::Deny);
+        assert_eq!(decision.rule.as_deref(), Some("no-recursive-delete"));
+        assert!(!decision.permits());
+
+        let decision = policy.evaluate("run_command", &json!({"run": "ls -la"}));
+        assert_eq!(decision.action, PolicyAction::Allow);
+        assert_eq!(decision.rule, None);
+        assert!(decision.permits());
+    }
+
+    #[test]
+    fn test_domain_and_process_matching() {
+        let policy = ToolPolicy::parse(POLICY).unwrap();
+
+        for url in [
+            "https://login.mybank.com/account",
+            "mybank.com",
+            "HTTP://MYBANK.COM",
+        ] {
+            let decision = policy.evaluate("navigate_browser", &json!({ "url": url }));
+            assert_eq!(decision.action, PolicyAction::Confirm, "{url}");
+        }
+        let decision =
+            policy.evaluate("navigate_browser", &json!({"url": "https://notmybank.com"}));
+        assert_eq!(decision.action, PolicyAction::Allow);
+
+        let decision = policy.evaluate("click_element", &json!({"process": "RegEdit.exe"}));
+        assert_eq!(decision.rule.as_deref(), Some("rules[2]"));
+        assert_eq!(decision.action, PolicyAction::Deny);
+        let decision = policy.evaluate("click_element", &json!({"selector": "role:Button"}));
+        assert_eq!(decision.action, PolicyAction::Allow);
+    }
+
+    #[test]
+    fn test_confirmation_outcomes() {
+        let policy = ToolPolicy::parse(POLICY).unwrap();
+        let pending = policy.evaluate("navigate_browser", &json!({"url": "https://mybank.com"}));
+        assert!(!pending.permits());
+        assert!(pending
+            .confirmation_prompt("navigate_browser")
+            .contains("'bank'"));
+
+        let mut approved = pending.clone();
+        approved.record_confirmation(Some(ActionConfirmation {
+            confirmed: true,
+            notes: None,
+        }));
+        assert!(approved.permits());
+
+        let mut unanswered = pending;
+        unanswered.record_confirmation(None);
+        assert!(!unanswered.permits());
+        let error = unanswered.to_error("navigate_browser");
+        assert!(error.message.contains("was not confirmed"));
+    }
+
+    #[test]
+    fn test_nested_paths_arrays_and_equals() {
+        let policy = ToolPolicy::parse(
+            r#"{"default": "deny", "rules": [
+                {"tools": ["execute_*"], "args": {"steps.tool_name": {"equals": "run_command"}}, "action": "confirm"},
+                {"tools": ["execute_*"], "args": {"steps.0.tool_name": "^click"}, "action": "allow"},
+                {"tools": ["get_*"], "action": "allow"}
+            ]}"#,
+        )
+        .unwrap();
+        let decision = policy.evaluate(
+            "execute_sequence",
+            &json!({"steps": [{"tool_name": "click_element"}, {"tool_name": "run_command"}]}),
+        );
+        assert_eq!(decision.action, PolicyAction::Confirm);
+        let decision = policy.evaluate(
+            "execute_sequence",
+            &json!({"steps": [{"tool_name": "click_element"}, {"tool_name": "wait"}]}),
+        );
+        assert_eq!(decision.action, PolicyAction::Allow);
+        let decision = policy.evaluate(
+            "execute_sequence",
+            &json!({"steps": [{"tool_name": "wait"}]}),
+        );
+        assert_eq!(decision.action, PolicyAction::Deny);
+        let decision = policy.evaluate("get_window_tree", &json!({}));
+        assert_eq!(decision.action, PolicyAction::Allow);
+        let decision = policy.evaluate("type_into_element", &json!({}));
+        assert_eq!(decision.action, PolicyAction::Deny);
+    }
+
+    #[test]
+    fn test_policy_file_hot_reload() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("policy.yaml");
+        std::fs::write(&path, "default: deny").unwrap();
+        let store = PolicyStore::load(&path).unwrap();
+        let decision = store.current().evaluate("click_element", &json!({}));
+        assert_eq!(decision.action, PolicyAction::Deny);
+
+        let touch = |content: &str, secs: u64| {
+            std::fs::write(&path, content).unwrap();
+            let file = std::fs::File::options().write(true).open(&path).unwrap();
+            file.set_modified(SystemTime::UNIX_EPOCH + std::time::Duration::from_secs(secs))
+                .unwrap();
+        };
+
+        touch("default: allow", 1_000);
+        let decision = store.current().evaluate("click_element", &json!({}));
+        assert_eq!(decision.action, PolicyAction::Allow);
+
+        // A broken edit keeps the last good policy
+        touch("default: [", 2_000);
+        let decision = store.current().evaluate("click_element", &json!({}));
+        assert_eq!(decision.action, PolicyAction::Allow);
+    }
+
+    #[test]
+    fn test_invalid_policies_are_rejected() {
+        assert!(ToolPolicy::parse("rules: [{tools: [x], action: maybe}]").is_err());
+        assert!(ToolPolicy::parse("rules: [{args: {run: '('}, action: deny}]").is_err());
+        assert!(ToolPolicy::parse("unknown_key: 1").is_err());
+    }
+}
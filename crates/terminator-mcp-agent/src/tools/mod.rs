@@ -3,5 +3,10 @@
 //! Contains implementations for individual MCP tools, keeping server.rs clean.
 
 pub mod typecheck;
+pub mod validate_workflow;
 
 pub use typecheck::{typecheck_workflow, TypeError, TypecheckResult, TypecheckWorkflowArgs};
+pub use validate_workflow::{
+    analyze_workflow, validate_workflow, Severity, ValidateWorkflowArgs, ValidateWorkflowResult,
+    WorkflowIssue,
+};
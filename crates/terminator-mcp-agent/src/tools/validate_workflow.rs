@@ -0,0 +1,973 @@
+//! Static analysis for YAML/JSON workflows.
+//!
+//! Checks an `ExecuteSequenceArgs` workflow without running it: step ids and jump
+//! targets, reachability of troubleshooting steps, `{{var}}` references, tool names
+//! and arguments against the tool schemas, selector syntax and `if` expressions.
+
+use crate::expression_eval;
+use crate::utils::{ExecuteSequenceArgs, SequenceStep};
+use regex::Regex;
+use rmcp::model::Tool;
+use rmcp::{schemars, schemars::JsonSchema};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::collections::{HashMap, HashSet, VecDeque};
+use terminator::Selector;
+
+const TOOL_NAME_PREFIX: &str = "mcp_terminator-mcp-agent_";
+
+/// Tools whose output can add arbitrary keys to `env` at runtime.
+const SCRIPT_TOOLS: [&str; 2] = ["run_command", "execute_browser_script"];
+
+/// Argument fields that are parsed as selectors.
+const SELECTOR_FIELDS: [&str; 3] = ["selector", "window_selector", "tree_from_selector"];
+
+/// Arguments for the validate_workflow tool. Exactly one source must be given.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct ValidateWorkflowArgs {
+    /// Workflow location: file:// URL, local path, or http(s):// URL
+    pub url: Option<String>,
+    /// Workflow YAML or JSON content
+    pub content: Option<String>,
+    /// Workflow as a JSON object (the execute_sequence arguments)
+    pub workflow: Option<Value>,
+}
+
+/// How serious an issue is. Errors make the workflow invalid.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+/// A single finding from the analyzer.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct WorkflowIssue {
+    pub severity: Severity,
+    /// Machine-readable issue code (e.g., "missing_jump_target")
+    pub code: String,
+    /// Human-readable description
+    pub message: String,
+    /// Where the issue is, e.g. "steps[2].jumps[0].to_id"
+    pub location: String,
+    /// Index of the step in its list (steps or troubleshooting)
+    pub step_index: Option<usize>,
+    /// Id of the step, if it has one
+    pub step_id: Option<String>,
+}
+
+/// Result of analyzing a workflow.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct ValidateWorkflowResult {
+    /// True when no errors were found (warnings are allowed)
+    pub valid: bool,
+    pub error_count: usize,
+    pub warning_count: usize,
+    pub issues: Vec<WorkflowIssue>,
+}
+
+/// Load and analyze a workflow from the given source.
+pub async fn validate_workflow(
+    args: &ValidateWorkflowArgs,
+    tools: Option<&[Tool]>,
+) -> Result<ValidateWorkflowResult, String> {
+    let workflow = match (&args.url, &args.content, &args.workflow) {
+        (Some(url), None, None) => parse_workflow_content(&load_workflow(url).await?)?,
+        (None, Some(content), None) => parse_workflow_content(content)?,
+        (None, None, Some(value)) => parse_workflow_value(value.clone())?,
+        _ => return Err("Provide exactly one of 'url', 'content' or 'workflow'".to_string()),
+    };
+    Ok(analyze_workflow(&workflow, tools))
+}
+
+async fn load_workflow(url: &str) -> Result<String, String> {
+    if url.starts_with("http://") || url.starts_with("https://") {
+        let response = reqwest::get(url)
+            .await
+            .map_err(|e| format!("Failed to fetch workflow from URL: {e}"))?;
+        if !response.status().is_success() {
+            return Err(format!(
+                "HTTP error fetching workflow: {}",
+                response.status()
+            ));
+        }
+        return response
+            .text()
+            .await
+            .map_err(|e| format!("Failed to read response text: {e}"));
+    }
+    let path = url.strip_prefix("file://").unwrap_or(url);
+    tokio::fs::read_to_string(path)
+        .await
+        .map_err(|e| format!("Failed to read workflow file '{path}': {e}"))
+}
+
+/// Parse workflow YAML/JSON, accepting both bare arguments and the
+/// `tool_name: execute_sequence` wrapper.
+pub fn parse_workflow_content(content: &str) -> Result<ExecuteSequenceArgs, String> {
+    let value: Value =
+        serde_yaml::from_str(content).map_err(|e| format!("Failed to parse workflow: {e}"))?;
+    parse_workflow_value(value)
+}
+
+fn parse_workflow_value(value: Value) -> Result<ExecuteSequenceArgs, String> {
+    let value = if value.get("tool_name").and_then(|v| v.as_str()) == Some("execute_sequence") {
+        value
+            .get("arguments")
+            .cloned()
+            .ok_or("Workflow has execute_sequence but no arguments field")?
+    } else {
+        value
+    };
+    serde_json::from_value(value).map_err(|e| format!("Failed to parse workflow arguments: {e}"))
+}
+
+/// Run every static check on a parsed workflow.
+///
+/// `tools` are the server's tool definitions; when `None`, tool names and
+/// arguments are not checked.
+pub fn analyze_workflow(
+    workflow: &ExecuteSequenceArgs,
+    tools: Option<&[Tool]>,
+) -> ValidateWorkflowResult {
+    let mut analyzer = Analyzer::new(workflow, tools);
+    analyzer.run();
+
+    let issues = analyzer.issues;
+    let error_count = issues
+        .iter()
+        .filter(|i| i.severity == Severity::Error)
+        .count();
+    ValidateWorkflowResult {
+        valid: error_count == 0,
+        error_count,
+        warning_count: issues.len() - error_count,
+        issues,
+    }
+}
+
+/// Identifies a step by list and index; main steps come before troubleshooting
+/// steps, as in the executed sequence.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum StepRef {
+    Main(usize),
+    Troubleshooting(usize),
+}
+
+impl StepRef {
+    fn location(self) -> String {
+        match self {
+            StepRef::Main(i) => format!("steps[{i}]"),
+            StepRef::Troubleshooting(i) => format!("troubleshooting[{i}]"),
+        }
+    }
+
+    fn index(self) -> usize {
+        match self {
+            StepRef::Main(i) | StepRef::Troubleshooting(i) => i,
+        }
+    }
+}
+
+struct Analyzer<'a> {
+    workflow: &'a ExecuteSequenceArgs,
+    tools: Option<HashMap<&'a str, &'a Tool>>,
+    ids: HashMap<&'a str, StepRef>,
+    issues: Vec<WorkflowIssue>,
+    placeholder_re: Regex,
+}
+
+impl<'a> Analyzer<'a> {
+    fn new(workflow: &'a ExecuteSequenceArgs, tools: Option<&'a [Tool]>) -> Self {
+        Self {
+            workflow,
+            tools: tools.map(|tools| tools.iter().map(|t| (t.name.as_ref(), t)).collect()),
+            ids: HashMap::new(),
+            issues: Vec::new(),
+            placeholder_re: Regex::new(r"\$?\{\{(.*?)\}\}").expect("valid regex"),
+        }
+    }
+
+    fn main_steps(&self) -> &'a [SequenceStep] {
+        self.workflow.steps.as_deref().unwrap_or_default()
+    }
+
+    fn troubleshooting_steps(&self) -> &'a [SequenceStep] {
+        self.workflow.troubleshooting.as_deref().unwrap_or_default()
+    }
+
+    fn all_steps(&self) -> impl Iterator<Item = (StepRef, &'a SequenceStep)> {
+        let main = self.main_steps().iter().enumerate();
+        let troubleshooting = self.troubleshooting_steps().iter().enumerate();
+        main.map(|(i, s)| (StepRef::Main(i), s))
+            .chain(troubleshooting.map(|(i, s)| (StepRef::Troubleshooting(i), s)))
+    }
+
+    fn push(
+        &mut self,
+        severity: Severity,
+        code: &str,
+        message: String,
+        location: String,
+        step: Option<(StepRef, &SequenceStep)>,
+    ) {
+        self.issues.push(WorkflowIssue {
+            severity,
+            code: code.to_string(),
+            message,
+            location,
+            step_index: step.map(|(r, _)| r.index()),
+            step_id: step.and_then(|(_, s)| s.id.clone()),
+        });
+    }
+
+    fn run(&mut self) {
+        if self.main_steps().is_empty() {
+            self.push(
+                Severity::Error,
+                "no_steps",
+                "Workflow has no steps".to_string(),
+                "steps".to_string(),
+                None,
+            );
+        }
+        self.check_ids();
+        self.check_targets();
+        self.check_reachability();
+        let steps: Vec<_> = self.all_steps().collect();
+        for (step_ref, step) in steps {
+            self.check_shape(step_ref, step);
+            self.check_conditions(step_ref, step);
+            self.check_tool_calls(step_ref, step);
+            self.check_selectors_in_step(step_ref, step);
+            self.check_variables(step_ref, step);
+        }
+        self.check_workflow_selectors();
+    }
+
+    fn check_ids(&mut self) {
+        let steps: Vec<_> = self.all_steps().collect();
+        for (step_ref, step) in steps {
+            let Some(id) = step.id.as_deref() else {
+                continue;
+            };
+            if let Some(first) = self.ids.get(id) {
+                let message = format!(
+                    "Duplicate step id '{id}' (first defined at {})",
+                    first.location()
+                );
+                self.push(
+                    Severity::Error,
+                    "duplicate_step_id",
+                    message,
+                    format!("{}.id", step_ref.location()),
+                    Some((step_ref, step)),
+                );
+            } else {
+                self.ids.insert(id, step_ref);
+            }
+        }
+    }
+
+    fn check_targets(&mut self) {
+        let steps: Vec<_> = self.all_steps().collect();
+        for (step_ref, step) in steps {
+            if let Some(target) = step.fallback_id.as_deref() {
+                if !self.ids.contains_key(target) {
+                    self.push(
+                        Severity::Error,
+                        "missing_fallback_target",
+                        format!("fallback_id '{target}' does not match any step id"),
+                        format!("{}.fallback_id", step_ref.location()),
+                        Some((step_ref, step)),
+                    );
+                }
+            }
+            for (j, jump) in step.jumps.iter().flatten().enumerate() {
+                if !self.ids.contains_key(jump.to_id.as_str()) {
+                    self.push(
+                        Severity::Error,
+                        "missing_jump_target",
+                        format!(
+                            "jumps[{j}].to_id '{}' does not match any step id",
+                            jump.to_id
+                        ),
+                        format!("{}.jumps[{j}].to_id", step_ref.location()),
+                        Some((step_ref, step)),
+                    );
+                }
+            }
+        }
+
+        for (field, value) in [
+            ("start_from_step", &self.workflow.start_from_step),
+            ("end_at_step", &self.workflow.end_at_step),
+        ] {
+            if let Some(id) = value.as_deref() {
+                if !self.ids.contains_key(id) {
+                    self.push(
+                        Severity::Error,
+                        "missing_step_reference",
+                        format!("{field} '{id}' does not match any step id"),
+                        field.to_string(),
+                        None,
+                    );
+                }
+            }
+        }
+    }
+
+    /// Troubleshooting steps only run when a fallback or jump lands on them (and then
+    /// fall through to the next troubleshooting step), so anything not reachable from
+    /// the main steps is dead code.
+    fn check_reachability(&mut self) {
+        let troubleshooting = self.troubleshooting_steps();
+        if troubleshooting.is_empty() {
+            return;
+        }
+
+        let mut reached = vec![false; troubleshooting.len()];
+        let mut queue: VecDeque<&SequenceStep> = self.main_steps().iter().collect();
+        if let Some(StepRef::Troubleshooting(i)) = self
+            .workflow
+            .start_from_step
+            .as_deref()
+            .and_then(|id| self.ids.get(id).copied())
+        {
+            reached[i] = true;
+            queue.push_back(&troubleshooting[i]);
+        }
+
+        while let Some(step) = queue.pop_front() {
+            let targets = step
+                .fallback_id
+                .iter()
+                .chain(step.jumps.iter().flatten().map(|j| &j.to_id));
+            let mut next: Vec<usize> = targets
+                .filter_map(|id| match self.ids.get(id.as_str()) {
+                    Some(StepRef::Troubleshooting(i)) => Some(*i),
+                    _ => None,
+                })
+                .collect();
+            if let Some(pos) = troubleshooting.iter().position(|s| std::ptr::eq(s, step)) {
+                if pos + 1 < troubleshooting.len() {
+                    next.push(pos + 1);
+                }
+            }
+            for i in next {
+                if !reached[i] {
+                    reached[i] = true;
+                    queue.push_back(&troubleshooting[i]);
+                }
+            }
+        }
+
+        for (i, step) in troubleshooting.iter().enumerate() {
+            if !reached[i] {
+                let step_ref = StepRef::Troubleshooting(i);
+                self.push(
+                    Severity::Warning,
+                    "unreachable_step",
+                    "Troubleshooting step is never reached by a fallback_id or jump".to_string(),
+                    step_ref.location(),
+                    Some((step_ref, step)),
+                );
+            }
+        }
+    }
+
+    fn check_shape(&mut self, step_ref: StepRef, step: &SequenceStep) {
+        let message = match (&step.tool_name, &step.steps) {
+            (Some(_), Some(_)) => "Step has both tool_name and a group of steps",
+            (None, None) => "Step has neither tool_name nor a group of steps",
+            _ => return,
+        };
+        self.push(
+            Severity::Error,
+            "invalid_step",
+            message.to_string(),
+            step_ref.location(),
+            Some((step_ref, step)),
+        );
+    }
+
+    fn check_conditions(&mut self, step_ref: StepRef, step: &SequenceStep) {
+        let mut conditions = Vec::new();
+        if let Some(cond) = &step.r#if {
+            conditions.push((format!("{}.if", step_ref.location()), cond));
+        }
+        for (j, jump) in step.jumps.iter().flatten().enumerate() {
+            conditions.push((
+                format!("{}.jumps[{j}].if", step_ref.location()),
+                &jump.condition,
+            ));
+        }
+        for (location, condition) in conditions {
+            if let Err(e) = expression_eval::validate(condition) {
+                self.push(
+                    Severity::Error,
+                    "invalid_condition",
+                    format!("Condition '{condition}' cannot be parsed: {e}"),
+                    location,
+                    Some((step_ref, step)),
+                );
+            }
+        }
+    }
+
+    fn check_tool_calls(&mut self, step_ref: StepRef, step: &SequenceStep) {
+        let mut calls: Vec<(String, &str, Option<&Value>)> = Vec::new();
+        if let Some(tool_name) = &step.tool_name {
+            calls.push((step_ref.location(), tool_name, step.arguments.as_ref()));
+        }
+        for (k, call) in step.steps.iter().flatten().enumerate() {
+            let location = format!("{}.steps[{k}]", step_ref.location());
+            calls.push((location, &call.tool_name, Some(&call.arguments)));
+        }
+        let hooks = step.retry.as_ref().and_then(|r| r.before_retry.as_ref());
+        for (k, call) in hooks.into_iter().flatten().enumerate() {
+            let location = format!("{}.retry.before_retry[{k}]", step_ref.location());
+            calls.push((location, &call.tool_name, Some(&call.arguments)));
+        }
+
+        if self.tools.is_none() {
+            return;
+        }
+        for (location, tool_name, arguments) in calls {
+            let name = tool_name
+                .strip_prefix(TOOL_NAME_PREFIX)
+                .unwrap_or(tool_name);
+            let Some(tool) = self.tools.as_ref().and_then(|t| t.get(name).copied()) else {
+                self.push(
+                    Severity::Error,
+                    "unknown_tool",
+                    format!("Unknown tool '{tool_name}'"),
+                    format!("{location}.tool_name"),
+                    Some((step_ref, step)),
+                );
+                continue;
+            };
+            self.check_arguments(step_ref, step, &location, tool, arguments);
+        }
+    }
+
+    fn check_arguments(
+        &mut self,
+        step_ref: StepRef,
+        step: &SequenceStep,
+        location: &str,
+        tool: &Tool,
+        arguments: Option<&Value>,
+    ) {
+        let Some(fields) = SchemaFields::of(&tool.input_schema) else {
+            return;
+        };
+        let empty = Map::new();
+        let arguments = match arguments {
+            None | Some(Value::Null) => &empty,
+            Some(Value::Object(map)) => map,
+            Some(_) => {
+                self.push(
+                    Severity::Error,
+                    "invalid_arguments",
+                    format!("Arguments for '{}' must be an object", tool.name),
+                    format!("{location}.arguments"),
+                    Some((step_ref, step)),
+                );
+                return;
+            }
+        };
+
+        let mut unknown: Vec<&String> = arguments
+            .keys()
+            .filter(|k| !fields.properties.contains(k.as_str()))
+            .collect();
+        unknown.sort();
+        for key in unknown {
+            self.push(
+                Severity::Warning,
+                "unknown_argument",
+                format!("'{}' does not accept argument '{key}'", tool.name),
+                format!("{location}.arguments.{key}"),
+                Some((step_ref, step)),
+            );
+        }
+
+        let mut missing: Vec<&String> = fields
+            .required
+            .iter()
+            .filter(|k| !arguments.contains_key(k.as_str()))
+            .collect();
+        missing.sort();
+        missing.dedup();
+        for key in missing {
+            self.push(
+                Severity::Error,
+                "missing_argument",
+                format!("'{}' requires argument '{key}'", tool.name),
+                format!("{location}.arguments"),
+                Some((step_ref, step)),
+            );
+        }
+    }
+
+    fn check_selectors_in_step(&mut self, step_ref: StepRef, step: &SequenceStep) {
+        let mut found = Vec::new();
+        if let Some(arguments) = &step.arguments {
+            collect_selector_fields(
+                arguments,
+                &format!("{}.arguments", step_ref.location()),
+                &mut found,
+            );
+        }
+        for (k, call) in step.steps.iter().flatten().enumerate() {
+            let location = format!("{}.steps[{k}].arguments", step_ref.location());
+            collect_selector_fields(&call.arguments, &location, &mut found);
+        }
+        for (location, selector) in found {
+            if let Some(reason) = selector_error(&selector) {
+                self.push(
+                    Severity::Error,
+                    "invalid_selector",
+                    format!("Invalid selector '{selector}': {reason}"),
+                    location,
+                    Some((step_ref, step)),
+                );
+            }
+        }
+    }
+
+    fn check_workflow_selectors(&mut self) {
+        let Some(selectors) = &self.workflow.selectors else {
+            return;
+        };
+        let mut found = Vec::new();
+        collect_strings(selectors, "selectors", &mut found);
+        for (location, selector) in found {
+            if let Some(reason) = selector_error(&selector) {
+                self.push(
+                    Severity::Error,
+                    "invalid_selector",
+                    format!("Invalid selector '{selector}': {reason}"),
+                    location,
+                    None,
+                );
+            }
+        }
+    }
+
+    fn check_variables(&mut self, step_ref: StepRef, step: &SequenceStep) {
+        let mut strings = Vec::new();
+        if let Some(arguments) = &step.arguments {
+            collect_strings(
+                arguments,
+                &format!("{}.arguments", step_ref.location()),
+                &mut strings,
+            );
+        }
+        for (k, call) in step.steps.iter().flatten().enumerate() {
+            let location = format!("{}.steps[{k}].arguments", step_ref.location());
+            collect_strings(&call.arguments, &location, &mut strings);
+        }
+
+        // Expressions are evaluated leniently at runtime; only plain paths are checked.
+        let references: Vec<(String, String)> = strings
+            .iter()
+            .flat_map(|(location, text)| {
+                self.placeholder_re
+                    .captures_iter(text)
+                    .map(|caps| caps[1].trim().to_string())
+                    .filter(|inner| {
+                        !inner.is_empty()
+                            && inner.chars().all(|c| {
+                                c.is_ascii_alphanumeric()
+                                    || matches!(c, '_' | '-' | '.' | '[' | ']')
+                            })
+                    })
+                    .map(move |inner| (location.clone(), inner))
+            })
+            .collect();
+
+        let (defined, script_ran) = self.defined_before(step_ref);
+        for (location, inner) in references {
+            let root = inner.split(['.', '[']).next().unwrap_or(&inner);
+            if defined.contains(root) {
+                continue;
+            }
+
+            let (severity, message) = if self.is_later_step_output(root) {
+                (
+                    Severity::Warning,
+                    format!(
+                        "'{{{{{inner}}}}}' refers to the output of a step that has not run yet"
+                    ),
+                )
+            } else if script_ran {
+                (
+                        Severity::Warning,
+                        format!("'{{{{{inner}}}}}' is not declared; it must be set by an earlier script's env output"),
+                    )
+            } else {
+                (
+                        Severity::Error,
+                        format!("'{{{{{inner}}}}}' is not defined in variables, inputs, selectors or earlier step results"),
+                    )
+            };
+            self.push(
+                severity,
+                "undefined_variable",
+                message,
+                location,
+                Some((step_ref, step)),
+            );
+        }
+    }
+
+    /// Context roots available when `step_ref` runs, and whether a script step ran
+    /// before it (scripts can merge arbitrary keys into `env`).
+    fn defined_before(&self, step_ref: StepRef) -> (HashSet<String>, bool) {
+        let mut defined: HashSet<String> = ["env", "inputs", "selectors"]
+            .into_iter()
+            .map(String::from)
+            .collect();
+        defined.extend(
+            self.workflow
+                .variables
+                .iter()
+                .flatten()
+                .map(|(k, _)| k.clone()),
+        );
+        if let Some(Value::Object(inputs)) = &self.workflow.inputs {
+            defined.extend(inputs.keys().cloned());
+        }
+
+        // Troubleshooting steps run after (part of) the main steps.
+        let earlier: Vec<&SequenceStep> = match step_ref {
+            StepRef::Main(i) => self.main_steps()[..i].iter().collect(),
+            StepRef::Troubleshooting(i) => self
+                .main_steps()
+                .iter()
+                .chain(&self.troubleshooting_steps()[..i])
+                .collect(),
+        };
+        let mut script_ran = false;
+        for step in earlier {
+            script_ran |= step_tool_names(step).any(|name| SCRIPT_TOOLS.contains(&name));
+            if let (Some(id), Some(_)) = (&step.id, &step.tool_name) {
+                defined.insert(format!("{id}_result"));
+                defined.insert(format!("{id}_status"));
+            }
+        }
+        (defined, script_ran)
+    }
+
+    fn is_later_step_output(&self, root: &str) -> bool {
+        let id = root
+            .strip_suffix("_result")
+            .or_else(|| root.strip_suffix("_status"));
+        id.is_some_and(|id| self.ids.contains_key(id))
+    }
+}
+
+fn step_tool_names(step: &SequenceStep) -> impl Iterator<Item = &str> {
+    step.tool_name
+        .as_deref()
+        .into_iter()
+        .chain(step.steps.iter().flatten().map(|c| c.tool_name.as_str()))
+        .map(|name| name.strip_prefix(TOOL_NAME_PREFIX).unwrap_or(name))
+}
+
+/// Property names and required fields of a tool input schema, following
+/// `allOf`/`anyOf`/`oneOf` and local `$ref`s.
+struct SchemaFields {
+    properties: HashSet<String>,
+    required: Vec<String>,
+}
+
+impl SchemaFields {
+    /// Returns `None` when the schema doesn't enumerate its properties, in which
+    /// case arguments can't be checked.
+    fn of(schema: &Map<String, Value>) -> Option<Self> {
+        let mut fields = SchemaFields {
+            properties: HashSet::new(),
+            required: Vec::new(),
+        };
+        let open = fields.collect(schema, schema, true, 0);
+        (!open && !fields.properties.is_empty()).then_some(fields)
+    }
+
+    /// Returns true if the schema allows arbitrary extra properties.
+    fn collect(
+        &mut self,
+        root: &Map<String, Value>,
+        schema: &Map<String, Value>,
+        required: bool,
+        depth: usize,
+    ) -> bool {
+        if depth > 16 {
+            return false;
+        }
+        let mut open = schema.get("additionalProperties") == Some(&Value::Bool(true));
+
+        if let Some(reference) = schema.get("$ref").and_then(|r| r.as_str()) {
+            if let Some(target) = resolve_ref(root, reference) {
+                open |= self.collect(root, target, required, depth + 1);
+            }
+        }
+        if let Some(Value::Object(props)) = schema.get("properties") {
+            self.properties.extend(props.keys().cloned());
+        }
+        if required {
+            if let Some(Value::Array(names)) = schema.get("required") {
+                self.required
+                    .extend(names.iter().filter_map(|n| n.as_str().map(String::from)));
+            }
+        }
+        for (key, members_required) in [("allOf", required), ("anyOf", false), ("oneOf", false)] {
+            for member in schema
+                .get(key)
+                .and_then(|v| v.as_array())
+                .into_iter()
+                .flatten()
+            {
+                if let Value::Object(member) = member {
+                    open |= self.collect(root, member, members_required, depth + 1);
+                }
+            }
+        }
+        open
+    }
+}
+
+fn resolve_ref<'s>(
+    root: &'s Map<String, Value>,
+    reference: &str,
+) -> Option<&'s Map<String, Value>> {
+    let pointer = reference.strip_prefix('#')?;
+    let (head, rest) = pointer.trim_start_matches('/').split_once('/')?;
+    root.get(head)?
+        .pointer(&format!("/{rest}"))
+        .and_then(|v| v.as_object())
+}
+
+fn collect_strings(value: &Value, location: &str, out: &mut Vec<(String, String)>) {
+    match value {
+        Value::String(s) => out.push((location.to_string(), s.clone())),
+        Value::Array(items) => {
+            for (i, item) in items.iter().enumerate() {
+                collect_strings(item, &format!("{location}[{i}]"), out);
+            }
+        }
+        Value::Object(map) => {
+            for (key, item) in map {
+                collect_strings(item, &format!("{location}.{key}"), out);
+            }
+        }
+        _ => {}
+    }
+}
+
+fn collect_selector_fields(arguments: &Value, location: &str, out: &mut Vec<(String, String)>) {
+    let Value::Object(map) = arguments else {
+        return;
+    };
+    for field in SELECTOR_FIELDS {
+        if let Some(Value::String(selector)) = map.get(field) {
+            out.push((format!("{location}.{field}"), selector.clone()));
+        }
+    }
+    if let Some(Value::Array(alternatives)) = map.get("alternative_selectors") {
+        for (i, alt) in alternatives.iter().enumerate() {
+            if let Value::String(selector) = alt {
+                out.push((
+                    format!("{location}.alternative_selectors[{i}]"),
+                    selector.clone(),
+                ));
+            }
+        }
+    }
+}
+
+/// Parse a selector string and return why it is invalid, if it is.
+/// Selectors containing `{{...}}` placeholders are only known at runtime and are skipped.
+fn selector_error(selector: &str) -> Option<String> {
+    if selector.contains("{{") {
+        return None;
+    }
+    find_invalid(&Selector::from(selector))
+}
+
+fn find_invalid(selector: &Selector) -> Option<String> {
+    match selector {
+        Selector::Invalid(reason) => Some(reason.clone()),
+        Selector::Chain(parts) | Selector::And(parts) | Selector::Or(parts) => {
+            parts.iter().find_map(find_invalid)
+        }
+        Selector::Not(inner)
+        | Selector::Has(inner)
+        | Selector::RightOf(inner)
+        | Selector::LeftOf(inner)
+        | Selector::Above(inner)
+        | Selector::Below(inner)
+        | Selector::Near(inner) => find_invalid(inner),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use std::sync::Arc;
+
+    fn workflow(value: Value) -> ExecuteSequenceArgs {
+        serde_json::from_value(value).expect("valid workflow")
+    }
+
+    fn codes(result: &ValidateWorkflowResult) -> Vec<(&str, Severity)> {
+        result
+            .issues
+            .iter()
+            .map(|i| (i.code.as_str(), i.severity))
+            .collect()
+    }
+
+    fn tool(name: &str, schema: Value) -> Tool {
+        Tool::new(
+            name.to_string(),
+            "test tool",
+            Arc::new(schema.as_object().cloned().unwrap()),
+        )
+    }
+
+    #[test]
+    fn valid_workflow_has_no_issues() {
+        let wf = workflow(json!({
+            "variables": {"app": {"type": "string"}},
+            "steps": [
+                {"id": "open", "tool_name": "delay", "arguments": {"delay_ms": "{{app}}"}},
+                {"tool_name": "delay", "arguments": {"delay_ms": "{{open_result.content}}"},
+                 "if": "open_status == 'success'", "fallback_id": "recover"}
+            ],
+            "troubleshooting": [
+                {"id": "recover", "tool_name": "delay", "arguments": {"delay_ms": 1}},
+                {"tool_name": "delay", "arguments": {"delay_ms": 2}}
+            ]
+        }));
+        let result = analyze_workflow(&wf, None);
+        assert!(result.valid);
+        assert!(result.issues.is_empty(), "{:?}", result.issues);
+    }
+
+    #[test]
+    fn reports_ids_targets_and_unreachable_steps() {
+        let wf = workflow(json!({
+            "steps": [
+                {"id": "a", "tool_name": "delay", "fallback_id": "missing"},
+                {"id": "a", "tool_name": "delay",
+                 "jumps": [{"if": "always()", "to_id": "nowhere"}]}
+            ],
+            "troubleshooting": [
+                {"id": "orphan", "tool_name": "delay"}
+            ]
+        }));
+        let result = analyze_workflow(&wf, None);
+        let codes = codes(&result);
+        assert!(codes.contains(&("duplicate_step_id", Severity::Error)));
+        assert!(codes.contains(&("missing_fallback_target", Severity::Error)));
+        assert!(codes.contains(&("missing_jump_target", Severity::Error)));
+        assert!(codes.contains(&("unreachable_step", Severity::Warning)));
+        let jump = result
+            .issues
+            .iter()
+            .find(|i| i.code == "missing_jump_target")
+            .unwrap();
+        assert_eq!(jump.location, "steps[1].jumps[0].to_id");
+    }
+
+    #[test]
+    fn reports_undefined_variables_and_bad_conditions() {
+        let wf = workflow(json!({
+            "steps": [
+                {"tool_name": "delay", "arguments": {"x": "{{nope}}", "y": "{{later_result}}"},
+                 "if": "count > many"},
+                {"id": "later", "tool_name": "run_command"},
+                {"tool_name": "delay", "arguments": {"x": "prefix {{from_script}}"}}
+            ]
+        }));
+        let result = analyze_workflow(&wf, None);
+        let by_location: HashMap<_, _> = result
+            .issues
+            .iter()
+            .map(|i| (i.location.as_str(), (i.code.as_str(), i.severity)))
+            .collect();
+        assert_eq!(
+            by_location["steps[0].arguments.x"],
+            ("undefined_variable", Severity::Error)
+        );
+        assert_eq!(
+            by_location["steps[0].arguments.y"],
+            ("undefined_variable", Severity::Warning)
+        );
+        assert_eq!(
+            by_location["steps[2].arguments.x"],
+            ("undefined_variable", Severity::Warning)
+        );
+        assert_eq!(
+            by_location["steps[0].if"],
+            ("invalid_condition", Severity::Error)
+        );
+    }
+
+    #[test]
+    fn checks_tools_against_schemas() {
+        let tools = [tool(
+            "click_element",
+            json!({
+                "type": "object",
+                "properties": {"selector": {"type": "string"}, "timeout_ms": {"type": "integer"}},
+                "required": ["selector"]
+            }),
+        )];
+        let wf = workflow(json!({
+            "steps": [
+                {"tool_name": "mcp_terminator-mcp-agent_click_element", "arguments": {"selector": "role:Button", "colour": "red"}},
+                {"tool_name": "click_element", "arguments": {}},
+                {"tool_name": "teleport"}
+            ]
+        }));
+        let result = analyze_workflow(&wf, Some(&tools));
+        assert_eq!(
+            codes(&result),
+            vec![
+                ("unknown_argument", Severity::Warning),
+                ("missing_argument", Severity::Error),
+                ("unknown_tool", Severity::Error),
+            ]
+        );
+    }
+
+    #[test]
+    fn schema_fields_follow_refs_and_all_of() {
+        let schema = json!({
+            "allOf": [{"$ref": "#/$defs/Base"}],
+            "properties": {"extra": {}},
+            "$defs": {"Base": {"properties": {"selector": {}}, "required": ["selector"]}}
+        });
+        let fields = SchemaFields::of(schema.as_object().unwrap()).unwrap();
+        assert!(fields.properties.contains("selector"));
+        assert!(fields.properties.contains("extra"));
+        assert_eq!(fields.required, vec!["selector".to_string()]);
+
+        let open = json!({"properties": {"a": {}}, "additionalProperties": true});
+        assert!(SchemaFields::of(open.as_object().unwrap()).is_none());
+    }
+
+    #[test]
+    fn parses_wrapped_workflows() {
+        let yaml = "tool_name: execute_sequence\narguments:\n  steps:\n    - tool_name: delay\n";
+        let wf = parse_workflow_content(yaml).unwrap();
+        assert_eq!(wf.steps.unwrap().len(), 1);
+    }
+}
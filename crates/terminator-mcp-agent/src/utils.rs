@@ -255,6 +255,120 @@ pub struct StopHighlightingArgs {
     pub window_mgmt: WindowManagementOptions,
 }
 
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct QueuedRunArgs {
+    #[schemars(description = "Request id of the run, as shown by list_execution_queue")]
+    pub request_id: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct ReorderQueueArgs {
+    #[schemars(description = "Request id of the queued run, as shown by list_execution_queue")]
+    pub request_id: String,
+    #[schemars(description = "New 1-based place in the queue (1 = next to start)")]
+    pub position: Option<usize>,
+    #[schemars(
+        description = "New priority. Without position, the run moves behind queued runs of the same priority."
+    )]
+    pub priority: Option<i32>,
+}
+
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct ExecutionHistoryArgs {
+    #[schemars(
+        description = "Only runs of this workflow: workflow_id, workflow file name without extension, or part of its URL"
+    )]
+    pub workflow: Option<String>,
+    #[schemars(
+        description = "Only runs with this status: running, completed, failed or cancelled"
+    )]
+    pub status: Option<String>,
+    #[schemars(
+        description = "Only runs started after this: RFC 3339 timestamp, date (YYYY-MM-DD), or an age like '7d', '12h'"
+    )]
+    pub since: Option<String>,
+    #[schemars(description = "Maximum number of runs, newest first (default: 20)")]
+    pub limit: Option<usize>,
+}
+
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct ExecutionRunArgs {
+    #[schemars(
+        description = "Id of the run (execution_id, or a unique prefix of it) as shown by query_execution_history"
+    )]
+    pub run_id: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct ExecutionStepStatsArgs {
+    #[schemars(
+        description = "Only runs of this workflow: workflow_id, workflow file name without extension, or part of its URL"
+    )]
+    pub workflow: Option<String>,
+    #[schemars(
+        description = "Only runs started after this: RFC 3339 timestamp, date (YYYY-MM-DD), or an age like '7d', '12h'"
+    )]
+    pub since: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct RunReportArgs {
+    #[schemars(
+        description = "Id of the run (execution_id, or a unique prefix of it). Defaults to the latest run, of 'workflow' if given."
+    )]
+    pub run_id: Option<String>,
+    #[schemars(
+        description = "Report on the latest run of this workflow: workflow_id, workflow file name without extension, or part of its URL"
+    )]
+    pub workflow: Option<String>,
+    #[schemars(
+        description = "Where to write the HTML file. Defaults to the run's execution log directory."
+    )]
+    pub output_path: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct StartRecordingArgs {
+    #[schemars(description = "Name of the recording, used as the generated workflow's name")]
+    pub name: Option<String>,
+    #[schemars(
+        description = "Recorder preset: 'normal' (default, every event in full detail), 'balanced' (skips mouse moves, lighter UI capture) or 'low_energy' (for slow machines)"
+    )]
+    pub performance_mode: Option<String>,
+    #[schemars(
+        description = "WorkflowRecorderConfig fields applied on top of the preset, e.g. {\"record_clipboard\": false, \"ignore_applications\": [\"slack.exe\"], \"enable_highlighting\": true}. sinks streams events while recording so long sessions survive a crash: [{\"type\": \"jsonl\", \"path\": \"C:/recordings/session.jsonl\"}] or [{\"type\": \"websocket\", \"address\": \"127.0.0.1:17380\"}]; WebSocket clients connect to a URL from the status's sink_urls, which carries a per-recording token, and browsers need their origin in allowed_origins"
+    )]
+    pub config: Option<serde_json::Value>,
+    #[schemars(
+        description = "Stream clicks, typed text, app switches, navigations and hotkeys as notifications/message log messages from the 'recording' logger while recording (default: true)"
+    )]
+    pub stream_events: Option<bool>,
+}
+
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct StopRecordingArgs {
+    #[schemars(description = "Format of the generated workflow: 'yaml' (default) or 'typescript'")]
+    pub workflow_format: Option<String>,
+    #[schemars(description = "Return the raw recorded events as well (default: true)")]
+    pub include_events: Option<bool>,
+    #[schemars(
+        description = "Also save the raw recording as JSON to this path, so it can be compiled again later"
+    )]
+    pub save_path: Option<String>,
+    #[schemars(
+        description = "Replace the data typed into form fields with {{variable}} placeholders, and return typed variable definitions plus the recorded values as example inputs (YAML only, default: false)"
+    )]
+    pub extract_variables: Option<bool>,
+}
+
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct ScheduleIdArgs {
+    #[schemars(
+        description = "Id of the scheduled workflow as shown by list_schedules (file name without extension, or the TypeScript workflow folder name)"
+    )]
+    pub id: String,
+}
+
 #[derive(Debug, Serialize, Deserialize, JsonSchema)]
 pub struct DelayArgs {
     #[schemars(description = "Number of milliseconds to delay")]
@@ -278,6 +392,7 @@ pub struct ToolExecutionContext {
     pub previous_process: Option<String>, // Track previous process for detecting switches
     pub workflow_id: Option<String>,      // For execution logging
     pub step_id: Option<String>,          // For execution logging
+    pub retry_attempt: Option<u32>,       // 1-based retry number, None on the first attempt
 }
 
 impl ToolExecutionContext {
@@ -291,6 +406,7 @@ impl ToolExecutionContext {
             previous_process: None,
             workflow_id: None,
             step_id: None,
+            retry_attempt: None,
         }
     }
 
@@ -309,6 +425,7 @@ impl ToolExecutionContext {
             previous_process,
             workflow_id: None,
             step_id: None,
+            retry_attempt: None,
         }
     }
 
@@ -322,6 +439,12 @@ impl ToolExecutionContext {
         self.step_id = step_id;
         self
     }
+
+    /// Mark this execution as a retry of the step (attempt 0 is the first run)
+    pub fn with_retry_attempt(mut self, attempt: u32) -> Self {
+        self.retry_attempt = (attempt > 0).then_some(attempt);
+        self
+    }
 }
 
 fn default_desktop() -> Arc<Desktop> {
@@ -424,6 +547,12 @@ pub struct DesktopWrapper {
     /// Used to show UI prompts to the user even when tool calls come from a different peer
     #[serde(skip)]
     pub elicitation_peer: Arc<TokioMutex<Option<Peer<RoleServer>>>>,
+    /// Queue every execute_sequence call waits in (priorities, desktop lease, concurrency keys)
+    #[serde(skip)]
+    pub execution_queue: Arc<crate::execution_queue::ExecutionQueue>,
+    /// Recording started with start_recording, if any
+    #[serde(skip)]
+    pub recording: Arc<crate::recording::RecordingManager>,
 }
 
 impl Default for DesktopWrapper {
@@ -825,9 +954,27 @@ impl ClickElementArgs {
     }
 }
 
+/// Accepts numbers and booleans as text, so a `{{variable}}` of type number
+/// can be typed into a field
+fn deserialize_text<'de, D>(deserializer: D) -> Result<String, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    match serde_json::Value::deserialize(deserializer)? {
+        serde_json::Value::String(text) => Ok(text),
+        serde_json::Value::Number(number) => Ok(number.to_string()),
+        serde_json::Value::Bool(value) => Ok(value.to_string()),
+        other => Err(serde::de::Error::invalid_type(
+            serde::de::Unexpected::Other(&other.to_string()),
+            &"a string, number or boolean",
+        )),
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize, JsonSchema)]
 pub struct TypeIntoElementArgs {
     #[schemars(description = "The text to type into the element")]
+    #[serde(deserialize_with = "deserialize_text")]
     pub text_to_type: String,
     #[schemars(
         description = "REQUIRED: Whether to clear the element before typing. Set to true to clear existing text, false to append."
@@ -1458,6 +1605,11 @@ pub struct SequenceStep {
     pub r#if: Option<String>,
     #[schemars(description = "Number of times to retry this step or group on failure.")]
     pub retries: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[schemars(
+        description = "Retry policy with backoff, jitter and error-class filters. Takes precedence over 'retries' when both are set."
+    )]
+    pub retry: Option<crate::retry_policy::RetryPolicy>,
     #[schemars(
         description = "Optional unique identifier for this step (string). If provided, it can be a target for other steps' fallback_id. Additionally, the tool's result will be stored as {step_id}_result and its status as {step_id}_status in the environment, making it accessible to subsequent steps."
     )]
@@ -1529,7 +1681,7 @@ pub struct ExecuteSequenceArgs {
 
     // Simplified aliases for common parameters (keeping originals for backward compatibility)
     #[schemars(
-        description = "Simplified alias for 'output_parser'. Processes the final tool output and extracts structured data. Supports JavaScript code or file path.",
+        description = "Simplified alias for 'output_parser'. Processes the final tool output and extracts structured data. Supports JavaScript code or file path, or a declarative definition with 'items' (selector/role/name predicates), 'fields' (attributes or relative selectors) and 'transform' steps evaluated without JavaScript.",
         schema_with = "json_object_schema"
     )]
     pub output: Option<serde_json::Value>,
@@ -1547,6 +1699,10 @@ pub struct ExecuteSequenceArgs {
     pub start_from_step: Option<String>,
     #[schemars(description = "Stop execution after a specific step ID (inclusive)")]
     pub end_at_step: Option<String>,
+    #[schemars(
+        description = "Resume from the last checkpoint of this workflow (requires workflow_id or a file:// url). Continues at the interrupted step, group sub-step and retry attempt after a crash or cancel, or at the failed step after a failure. Fails if the workflow definition changed since the checkpoint. Cannot be combined with start_from_step."
+    )]
+    pub resume: Option<bool>,
     #[schemars(
         description = "Whether to follow fallback_id when end_at_step is specified. When false (default), execution stops at end_at_step regardless of failures. When true, allows following fallback_id even beyond end_at_step boundary."
     )]
@@ -1579,6 +1735,21 @@ pub struct ExecuteSequenceArgs {
     )]
     pub execution_id: Option<String>,
 
+    #[schemars(
+        description = "Queue priority when other workflows are running or waiting on this agent. Higher starts first; runs of equal priority start in arrival order (default: 0)."
+    )]
+    pub priority: Option<i32>,
+
+    #[schemars(
+        description = "Runs sharing a concurrency key never overlap, even non-UI ones (e.g. one key per external system a workflow writes to)."
+    )]
+    pub concurrency_key: Option<String>,
+
+    #[schemars(
+        description = "Whether the workflow drives the desktop UI (default: true). UI workflows take an exclusive desktop lease and run one at a time; set false for workflows that only call APIs, files or scripts so they can run alongside others."
+    )]
+    pub ui: Option<bool>,
+
     #[serde(flatten)]
     pub window_mgmt: WindowManagementOptions,
 }
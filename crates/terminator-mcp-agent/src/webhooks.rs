@@ -0,0 +1,922 @@
+//! Webhook triggers for the HTTP transport
+//!
+//! Workflows in `--workflows-dir` with `trigger: { type: webhook, path }` get a
+//! `POST /hooks/<path>` route (the workflow id when `path` is omitted). The JSON
+//! request body becomes the workflow `inputs`; YAML workflows validate it
+//! against their `variables` before anything runs. The response carries an
+//! execution id right away while the workflow runs in the background, and
+//! `GET /executions/<id>` reports its status, looked up in the execution history
+//! index and completed from the run's execution log once it has finished.
+//!
+//! A request is accepted with a bearer token from the same token store as
+//! `/mcp`, or, when `--webhook-secret` is set, an
+//! `X-Terminator-Signature: sha256=<hex>` HMAC-SHA256 of
+//! `<X-Terminator-Timestamp>.<raw body>` (of the execution id instead of the
+//! body for `GET /executions/<id>`). The timestamp is in Unix seconds and must be
+//! within [`SIGNATURE_TOLERANCE`] of the agent's clock, so a captured request
+//! can't be replayed later, and a `POST` signature is only accepted once within
+//! that window, so it can't be replayed right away either. The seen signatures
+//! are kept in memory: a restart forgets them, and several agents behind one
+//! URL don't share them. With neither configured the routes are open, like
+//! `/mcp`.
+
+use crate::client_auth::{AuthError, Scope, TokenStore};
+use crate::execution_history::{History, RunRecord, RunStatus};
+use crate::execution_logger;
+use crate::utils::VariableDefinition;
+use crate::workflow_triggers::{self, RunRequest, RunWorkflow, TriggeredWorkflow};
+use crate::workflow_typescript::TriggerConfig;
+use axum::body::Bytes;
+use axum::extract::{Path, State};
+use axum::http::{header, HeaderMap, HeaderValue, StatusCode};
+use axum::response::{IntoResponse, Response};
+use axum::routing::{get, post};
+use axum::{Json, Router};
+use chrono::{DateTime, Utc};
+use hmac::{Hmac, Mac};
+use serde::Serialize;
+use serde_json::{json, Value};
+use sha2::Sha256;
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tracing::{info, warn};
+use uuid::Uuid;
+
+pub const SIGNATURE_HEADER: &str = "x-terminator-signature";
+pub const TIMESTAMP_HEADER: &str = "x-terminator-timestamp";
+
+/// How far a signed request's timestamp may be from the agent's clock
+pub const SIGNATURE_TOLERANCE: Duration = Duration::from_secs(300);
+
+/// How long discovered routes are trusted before the directory is rescanned
+const RESCAN_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Minimum time between rescans caused by requests for unknown paths
+const UNKNOWN_PATH_RESCAN: Duration = Duration::from_secs(1);
+
+/// Finished runs kept in memory; older ones are only found through the history
+const MAX_RECORDS: usize = 500;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ExecutionStatus {
+    Running,
+    Completed,
+    Failed,
+}
+
+/// Status of a webhook-triggered run, as returned by `GET /executions/<id>`
+#[derive(Debug, Clone, Serialize)]
+pub struct ExecutionRecord {
+    pub execution_id: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub workflow_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub hook: Option<String>,
+    pub status: ExecutionStatus,
+    pub started_at: DateTime<Utc>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub finished_at: Option<DateTime<Utc>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub duration_ms: Option<u64>,
+    /// Final status reported by the workflow (`executed_without_error`, ...)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub workflow_status: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub log_path: Option<String>,
+    /// `execute_sequence` result from the execution log
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub result: Option<Value>,
+}
+
+impl ExecutionRecord {
+    /// Rebuild a record from the execution history, for runs this process
+    /// doesn't remember (e.g. started before a restart)
+    fn from_run(run: &RunRecord) -> Self {
+        Self {
+            execution_id: run.run_id.clone(),
+            workflow_id: run.workflow_name(),
+            hook: None,
+            status: match run.status {
+                RunStatus::Running => ExecutionStatus::Running,
+                RunStatus::Completed => ExecutionStatus::Completed,
+                RunStatus::Failed | RunStatus::Cancelled => ExecutionStatus::Failed,
+            },
+            started_at: run.started_at,
+            finished_at: run.finished_at,
+            duration_ms: run.duration_ms,
+            workflow_status: None,
+            error: run.error.clone(),
+            log_path: None,
+            result: None,
+        }
+    }
+
+    fn attach_log(&mut self, path: PathBuf, log: execution_logger::ExecutionLog) {
+        let workflow_status = log.response.result.as_ref().and_then(logged_status);
+        let failed = log.response.error.is_some()
+            || matches!(
+                workflow_status.as_deref(),
+                Some("executed_with_error" | "failed" | "error" | "cancelled")
+            );
+        self.status = if failed {
+            ExecutionStatus::Failed
+        } else {
+            ExecutionStatus::Completed
+        };
+        self.workflow_status = workflow_status.or(self.workflow_status.take());
+        self.error = log.response.error.or(self.error.take());
+        self.log_path = Some(path.display().to_string());
+        self.result = log.response.result;
+    }
+}
+
+/// `status` of an `execute_sequence` result as logged: either the result
+/// object itself or the tool's content items, whose text holds the JSON
+fn logged_status(result: &Value) -> Option<String> {
+    if let Some(status) = result.get("status").and_then(|s| s.as_str()) {
+        return Some(status.to_string());
+    }
+    result.as_array()?.iter().find_map(|item| {
+        let text = item.get("text")?.as_str()?;
+        let parsed: Value = serde_json::from_str(text).ok()?;
+        parsed.get("status")?.as_str().map(str::to_string)
+    })
+}
+
+/// A refused or failed webhook request
+#[derive(Debug)]
+pub struct HookError {
+    pub status: StatusCode,
+    pub message: String,
+    /// Sent as `Retry-After` when a client token is rate limited
+    pub retry_after: Option<Duration>,
+}
+
+impl HookError {
+    fn new(status: StatusCode, message: impl Into<String>) -> Self {
+        Self {
+            status,
+            message: message.into(),
+            retry_after: None,
+        }
+    }
+}
+
+impl From<AuthError> for HookError {
+    fn from(e: AuthError) -> Self {
+        match e {
+            AuthError::RateLimited { retry_after, .. } => Self {
+                retry_after: Some(retry_after),
+                ..Self::new(StatusCode::TOO_MANY_REQUESTS, e.to_string())
+            },
+            _ => Self::new(StatusCode::UNAUTHORIZED, e.to_string()),
+        }
+    }
+}
+
+impl IntoResponse for HookError {
+    fn into_response(self) -> Response {
+        let mut response = (self.status, Json(json!({ "error": self.message }))).into_response();
+        if let Some(retry_after) = self.retry_after {
+            let seconds = retry_after.as_secs_f64().ceil() as u64;
+            response
+                .headers_mut()
+                .insert(header::RETRY_AFTER, HeaderValue::from(seconds));
+        }
+        response
+    }
+}
+
+/// HMAC-SHA256 signature of `<timestamp>.<payload>` in the `sha256=<hex>` header format
+pub fn sign(secret: &str, timestamp: i64, payload: &[u8]) -> String {
+    let mac = signing_mac(secret, timestamp, payload);
+    format!("sha256={}", hex::encode(mac.finalize().into_bytes()))
+}
+
+/// Check a `sha256=<hex>` (or bare hex) signature in constant time
+pub fn verify_signature(secret: &str, timestamp: i64, payload: &[u8], signature: &str) -> bool {
+    let Some(expected) = signature_digest(signature) else {
+        return false;
+    };
+    signing_mac(secret, timestamp, payload)
+        .verify_slice(&expected)
+        .is_ok()
+}
+
+/// Digest bytes of a `sha256=<hex>` (or bare hex) signature
+fn signature_digest(signature: &str) -> Option<Vec<u8>> {
+    let signature = signature.trim();
+    let hex_digest = signature.strip_prefix("sha256=").unwrap_or(signature);
+    hex::decode(hex_digest).ok()
+}
+
+fn signing_mac(secret: &str, timestamp: i64, payload: &[u8]) -> Hmac<Sha256> {
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC accepts keys of any size");
+    mac.update(timestamp.to_string().as_bytes());
+    mac.update(b".");
+    mac.update(payload);
+    mac
+}
+
+struct Routes {
+    by_path: BTreeMap<String, TriggeredWorkflow>,
+    scanned_at: Option<Instant>,
+}
+
+pub struct Webhooks {
+    dir: PathBuf,
+    runner: RunWorkflow,
+    secret: Option<String>,
+    auth: Option<Arc<TokenStore>>,
+    routes: Mutex<Routes>,
+    /// Held while rescanning so concurrent requests share one scan
+    scanning: tokio::sync::Mutex<()>,
+    /// Digests of accepted `POST` signatures and their timestamps
+    seen_signatures: Mutex<HashMap<Vec<u8>, i64>>,
+    executions: Arc<Mutex<VecDeque<ExecutionRecord>>>,
+}
+
+impl Webhooks {
+    pub fn new(
+        dir: PathBuf,
+        runner: RunWorkflow,
+        secret: Option<String>,
+        auth: Option<Arc<TokenStore>>,
+    ) -> Arc<Self> {
+        let webhooks = Arc::new(Self {
+            dir,
+            runner,
+            secret,
+            auth,
+            routes: Mutex::new(Routes {
+                by_path: BTreeMap::new(),
+                scanned_at: None,
+            }),
+            scanning: tokio::sync::Mutex::new(()),
+            seen_signatures: Mutex::new(HashMap::new()),
+            executions: Arc::new(Mutex::new(VecDeque::new())),
+        });
+        webhooks.reload();
+        webhooks
+    }
+
+    /// Rescan the workflows directory for webhook triggers
+    pub fn reload(&self) {
+        let (found, warnings) = workflow_triggers::discover(&self.dir);
+        for warning in warnings {
+            warn!("[webhooks] {}", warning);
+        }
+
+        let mut by_path: BTreeMap<String, TriggeredWorkflow> = BTreeMap::new();
+        for workflow in found {
+            let TriggerConfig::Webhook { path, .. } = &workflow.trigger else {
+                continue;
+            };
+            let path = path
+                .as_deref()
+                .map(|p| p.trim_matches('/'))
+                .filter(|p| !p.is_empty())
+                .unwrap_or(&workflow.id)
+                .to_string();
+            if let Some(existing) = by_path.get(&path) {
+                warn!(
+                    "[webhooks] /hooks/{} is already used by '{}', ignoring '{}'",
+                    path, existing.id, workflow.id
+                );
+                continue;
+            }
+            by_path.insert(path, workflow);
+        }
+
+        let mut routes = self.routes.lock().unwrap();
+        routes.by_path = by_path;
+        routes.scanned_at = Some(Instant::now());
+    }
+
+    /// Webhook paths and the workflow each one runs
+    pub fn routes(&self) -> Vec<(String, String)> {
+        let routes = self.routes.lock().unwrap();
+        routes
+            .by_path
+            .iter()
+            .map(|(path, workflow)| (path.clone(), workflow.id.clone()))
+            .collect()
+    }
+
+    fn route(&self, path: &str) -> Option<TriggeredWorkflow> {
+        let routes = self.routes.lock().unwrap();
+        routes.by_path.get(path.trim_matches('/')).cloned()
+    }
+
+    /// Rescan on the blocking pool when the routes are stale, or when `path` is
+    /// unknown and the last scan is at least [`UNKNOWN_PATH_RESCAN`] old
+    async fn refresh(self: &Arc<Self>, path: &str) {
+        if !self.needs_rescan(path) {
+            return;
+        }
+        let _scanning = self.scanning.lock().await;
+        // Another request may have rescanned while this one waited
+        if !self.needs_rescan(path) {
+            return;
+        }
+        let webhooks = Arc::clone(self);
+        if let Err(e) = tokio::task::spawn_blocking(move || webhooks.reload()).await {
+            warn!("[webhooks] Workflow scan task failed: {}", e);
+        }
+    }
+
+    fn needs_rescan(&self, path: &str) -> bool {
+        let routes = self.routes.lock().unwrap();
+        let Some(age) = routes.scanned_at.map(|t| t.elapsed()) else {
+            return true;
+        };
+        age >= RESCAN_INTERVAL
+            || (age >= UNKNOWN_PATH_RESCAN && !routes.by_path.contains_key(path.trim_matches('/')))
+    }
+
+    /// Accept a request with a valid, recent signature or a bearer token with `scope`
+    pub fn authorize(
+        &self,
+        headers: &HeaderMap,
+        payload: &[u8],
+        scope: Scope,
+    ) -> Result<(), HookError> {
+        if let (Some(secret), Some(signature)) = (&self.secret, headers.get(SIGNATURE_HEADER)) {
+            let timestamp = headers
+                .get(TIMESTAMP_HEADER)
+                .and_then(|v| v.to_str().ok())
+                .and_then(|v| v.trim().parse::<i64>().ok())
+                .ok_or_else(|| {
+                    HookError::new(
+                        StatusCode::UNAUTHORIZED,
+                        format!("Missing or invalid {TIMESTAMP_HEADER} header"),
+                    )
+                })?;
+            if Utc::now().timestamp().abs_diff(timestamp) > SIGNATURE_TOLERANCE.as_secs() {
+                return Err(HookError::new(
+                    StatusCode::UNAUTHORIZED,
+                    "Webhook timestamp is outside the allowed window",
+                ));
+            }
+            let signature = signature.to_str().unwrap_or_default();
+            if !verify_signature(secret, timestamp, payload, signature) {
+                return Err(HookError::new(
+                    StatusCode::UNAUTHORIZED,
+                    "Invalid webhook signature",
+                ));
+            }
+            // Status lookups are safe to repeat; starting a run is not
+            if scope == Scope::Action && !self.first_use(signature, timestamp) {
+                return Err(HookError::new(
+                    StatusCode::UNAUTHORIZED,
+                    "Webhook signature was already used",
+                ));
+            }
+            return Ok(());
+        }
+
+        match &self.auth {
+            Some(tokens) => {
+                let header = headers
+                    .get(header::AUTHORIZATION)
+                    .and_then(|v| v.to_str().ok());
+                let client = tokens.authenticate(header)?;
+                if client.scopes.contains(&scope) {
+                    Ok(())
+                } else {
+                    Err(HookError::new(
+                        StatusCode::FORBIDDEN,
+                        format!("Client '{}' is not allowed to do this", client.name),
+                    ))
+                }
+            }
+            None if self.secret.is_some() => Err(HookError::new(
+                StatusCode::UNAUTHORIZED,
+                format!("Missing {SIGNATURE_HEADER} header"),
+            )),
+            None => Ok(()),
+        }
+    }
+
+    /// Record a verified signature, returning false if it was seen before.
+    /// Signatures whose timestamp has left the window are dropped, since
+    /// [`Webhooks::authorize`] refuses them anyway.
+    fn first_use(&self, signature: &str, timestamp: i64) -> bool {
+        let Some(digest) = signature_digest(signature) else {
+            return false;
+        };
+        let now = Utc::now().timestamp();
+        let mut seen = self.seen_signatures.lock().unwrap();
+        seen.retain(|_, ts| now.abs_diff(*ts) <= SIGNATURE_TOLERANCE.as_secs());
+        seen.insert(digest, timestamp).is_none()
+    }
+
+    /// Validate the body and start the workflow behind `path`
+    pub fn trigger(&self, path: &str, body: &[u8]) -> Result<ExecutionRecord, HookError> {
+        let workflow = self.route(path).ok_or_else(|| {
+            HookError::new(
+                StatusCode::NOT_FOUND,
+                format!("No webhook at /hooks/{path}"),
+            )
+        })?;
+
+        let inputs: Value = if body.iter().all(u8::is_ascii_whitespace) {
+            json!({})
+        } else {
+            serde_json::from_slice(body).map_err(|e| {
+                HookError::new(StatusCode::BAD_REQUEST, format!("Invalid JSON body: {e}"))
+            })?
+        };
+        if !inputs.is_object() {
+            return Err(HookError::new(
+                StatusCode::BAD_REQUEST,
+                "Request body must be a JSON object of workflow inputs",
+            ));
+        }
+        if let Some(variables) = &workflow.variables {
+            let variables: HashMap<String, VariableDefinition> =
+                serde_json::from_value(variables.clone()).map_err(|e| {
+                    HookError::new(
+                        StatusCode::INTERNAL_SERVER_ERROR,
+                        format!("Workflow '{}' has invalid variables: {e}", workflow.id),
+                    )
+                })?;
+            crate::server_sequence::validate_workflow_inputs(&variables, Some(&inputs)).map_err(
+                |e| {
+                    let mut message = e.message.to_string();
+                    if let Some(data) = e.data {
+                        message = format!("{message} {data}");
+                    }
+                    HookError::new(StatusCode::BAD_REQUEST, message)
+                },
+            )?;
+        }
+
+        let record = ExecutionRecord {
+            execution_id: Uuid::new_v4().to_string(),
+            workflow_id: Some(workflow.id.clone()),
+            hook: Some(path.trim_matches('/').to_string()),
+            status: ExecutionStatus::Running,
+            started_at: Utc::now(),
+            finished_at: None,
+            duration_ms: None,
+            workflow_status: None,
+            error: None,
+            log_path: None,
+            result: None,
+        };
+        self.remember(record.clone());
+        info!(
+            "[webhooks] /hooks/{} -> '{}' (execution {})",
+            path, workflow.id, record.execution_id
+        );
+
+        let run = (self.runner)(RunRequest {
+            url: workflow.url(),
+            inputs: Some(inputs),
+            execution_id: record.execution_id.clone(),
+        });
+        let execution_id = record.execution_id.clone();
+        let executions = self.executions.clone();
+        let started = Instant::now();
+        tokio::spawn(async move {
+            let outcome = run.await;
+            let elapsed = started.elapsed().as_millis() as u64;
+            let mut records = executions.lock().unwrap();
+            if let Some(record) = records.iter_mut().find(|r| r.execution_id == execution_id) {
+                record.finished_at = Some(Utc::now());
+                record.duration_ms = Some(elapsed);
+                match outcome {
+                    Ok(status) => {
+                        record.status = ExecutionStatus::Completed;
+                        record.workflow_status = Some(status);
+                    }
+                    Err(e) => {
+                        warn!("[webhooks] Execution {} failed: {}", execution_id, e);
+                        record.status = ExecutionStatus::Failed;
+                        record.error = Some(e);
+                    }
+                }
+            }
+        });
+
+        Ok(record)
+    }
+
+    fn remember(&self, record: ExecutionRecord) {
+        let mut records = self.executions.lock().unwrap();
+        if records.len() >= MAX_RECORDS {
+            records.pop_front();
+        }
+        records.push_back(record);
+    }
+
+    /// Status of a run, looked up in the execution history and completed from
+    /// the run's `execute_sequence` log when there is one
+    pub fn status(&self, execution_id: &str) -> Option<ExecutionRecord> {
+        self.status_in(&History::open_default(), execution_id)
+    }
+
+    fn status_in(&self, history: &History, execution_id: &str) -> Option<ExecutionRecord> {
+        let known = {
+            let records = self.executions.lock().unwrap();
+            records
+                .iter()
+                .find(|r| r.execution_id == execution_id)
+                .cloned()
+        };
+        if known
+            .as_ref()
+            .is_some_and(|r| r.status == ExecutionStatus::Running)
+        {
+            return known;
+        }
+        let run = history
+            .run(execution_id)
+            .ok()
+            .flatten()
+            .filter(|run| run.run_id == execution_id);
+        let mut record = match (known, &run) {
+            (Some(record), _) => record,
+            (None, Some(run)) => ExecutionRecord::from_run(run),
+            (None, None) => return None,
+        };
+        if let Some(path) = run.and_then(|run| run.log_path).map(PathBuf::from) {
+            let log = std::fs::read_to_string(&path)
+                .ok()
+                .and_then(|content| serde_json::from_str(&content).ok());
+            if let Some(log) = log {
+                record.attach_log(path, log);
+            }
+        }
+        Some(record)
+    }
+}
+
+async fn trigger_handler(
+    State(webhooks): State<Arc<Webhooks>>,
+    Path(path): Path<String>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Response {
+    if let Err(denied) = webhooks.authorize(&headers, &body, Scope::Action) {
+        return denied.into_response();
+    }
+    webhooks.refresh(&path).await;
+    match webhooks.trigger(&path, &body) {
+        Ok(record) => (
+            StatusCode::ACCEPTED,
+            Json(json!({
+                "execution_id": record.execution_id,
+                "workflow_id": record.workflow_id,
+                "status": record.status,
+                "status_url": format!("/executions/{}", record.execution_id),
+            })),
+        )
+            .into_response(),
+        Err(e) => e.into_response(),
+    }
+}
+
+async fn execution_handler(
+    State(webhooks): State<Arc<Webhooks>>,
+    Path(execution_id): Path<String>,
+    headers: HeaderMap,
+) -> Response {
+    if let Err(denied) = webhooks.authorize(&headers, execution_id.as_bytes(), Scope::Read) {
+        return denied.into_response();
+    }
+    let lookup = {
+        let webhooks = webhooks.clone();
+        let execution_id = execution_id.clone();
+        tokio::task::spawn_blocking(move || webhooks.status(&execution_id)).await
+    };
+    match lookup {
+        Ok(Some(record)) => Json(record).into_response(),
+        Ok(None) => HookError::new(
+            StatusCode::NOT_FOUND,
+            format!("No execution with id {execution_id}"),
+        )
+        .into_response(),
+        Err(e) => HookError::new(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    }
+}
+
+/// `POST /hooks/<path>` and `GET /executions/<id>`
+pub fn router(webhooks: Arc<Webhooks>) -> Router {
+    Router::new()
+        .route("/hooks/{*path}", post(trigger_handler))
+        .route("/executions/{execution_id}", get(execution_handler))
+        .with_state(webhooks)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    fn fake_runner(calls: Arc<Mutex<Vec<RunRequest>>>) -> RunWorkflow {
+        Arc::new(move |run: RunRequest| {
+            calls.lock().unwrap().push(run);
+            Box::pin(async { Ok("executed_without_error".to_string()) })
+        })
+    }
+
+    fn workflows_dir() -> tempfile::TempDir {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(
+            dir.path().join("invoice.yml"),
+            "trigger: { type: webhook, path: /invoices/new/ }\nvariables:\n  amount: { type: number }\n  note: { type: string, required: false }\nsteps: []\n",
+        )
+        .unwrap();
+        std::fs::write(
+            dir.path().join("nightly.yml"),
+            "cron: \"0 0 * * *\"\nsteps: []\n",
+        )
+        .unwrap();
+        dir
+    }
+
+    #[test]
+    fn test_signature_roundtrip() {
+        let ts = 1_700_000_000;
+        let signature = sign("s3cret", ts, b"{\"amount\":1}");
+        assert!(signature.starts_with("sha256="));
+        assert!(verify_signature(
+            "s3cret",
+            ts,
+            b"{\"amount\":1}",
+            &signature
+        ));
+        assert!(verify_signature(
+            "s3cret",
+            ts,
+            b"{\"amount\":1}",
+            signature.trim_start_matches("sha256=")
+        ));
+        assert!(!verify_signature(
+            "other",
+            ts,
+            b"{\"amount\":1}",
+            &signature
+        ));
+        assert!(!verify_signature(
+            "s3cret",
+            ts,
+            b"{\"amount\":2}",
+            &signature
+        ));
+        assert!(!verify_signature(
+            "s3cret",
+            ts + 1,
+            b"{\"amount\":1}",
+            &signature
+        ));
+        assert!(!verify_signature("s3cret", ts, b"{}", "sha256=not-hex"));
+    }
+
+    #[tokio::test]
+    async fn test_trigger_validates_inputs_and_tracks_status() {
+        let dir = workflows_dir();
+        let calls = Arc::new(Mutex::new(Vec::new()));
+        let hooks = Webhooks::new(
+            dir.path().to_path_buf(),
+            fake_runner(calls.clone()),
+            None,
+            None,
+        );
+        assert_eq!(
+            hooks.routes(),
+            [("invoices/new".to_string(), "invoice".to_string())]
+        );
+
+        let missing = hooks.trigger("nightly", b"{}").unwrap_err();
+        assert_eq!(missing.status, StatusCode::NOT_FOUND);
+        let invalid = hooks
+            .trigger("invoices/new", br#"{"amount": "lots"}"#)
+            .unwrap_err();
+        assert_eq!(invalid.status, StatusCode::BAD_REQUEST);
+        let required = hooks.trigger("invoices/new", b"").unwrap_err();
+        assert!(required.message.contains("amount"), "{}", required.message);
+        let not_object = hooks.trigger("invoices/new", b"[1]").unwrap_err();
+        assert_eq!(not_object.status, StatusCode::BAD_REQUEST);
+        assert!(calls.lock().unwrap().is_empty());
+
+        let record = hooks.trigger("invoices/new", br#"{"amount": 42}"#).unwrap();
+        assert_eq!(record.workflow_id.as_deref(), Some("invoice"));
+        assert_eq!(record.status, ExecutionStatus::Running);
+
+        tokio::task::yield_now().await;
+        let run = calls.lock().unwrap()[0].clone();
+        assert_eq!(run.execution_id, record.execution_id);
+        assert_eq!(run.inputs, Some(json!({"amount": 42})));
+        assert!(run.url.starts_with("file://") && run.url.ends_with("invoice.yml"));
+
+        let status = hooks.status(&record.execution_id).unwrap();
+        assert_eq!(status.status, ExecutionStatus::Completed);
+        assert_eq!(
+            status.workflow_status.as_deref(),
+            Some("executed_without_error")
+        );
+        assert!(hooks.status("no-such-run").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_unknown_paths_rescan_at_most_once_a_second() {
+        let dir = workflows_dir();
+        let calls = Arc::new(Mutex::new(Vec::new()));
+        let hooks = Webhooks::new(dir.path().to_path_buf(), fake_runner(calls), None, None);
+        std::fs::write(
+            dir.path().join("late.yml"),
+            "trigger: { type: webhook }\nsteps: []\n",
+        )
+        .unwrap();
+
+        // Just scanned: an unknown path doesn't cause another scan yet
+        assert!(!hooks.needs_rescan("late"));
+        hooks.refresh("late").await;
+        assert!(hooks.trigger("late", b"").is_err());
+
+        hooks.routes.lock().unwrap().scanned_at = Some(Instant::now() - UNKNOWN_PATH_RESCAN);
+        assert!(!hooks.needs_rescan("/invoices/new"));
+        assert!(hooks.needs_rescan("late"));
+        hooks.refresh("late").await;
+        assert!(hooks.trigger("late", b"").is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_failed_run_is_reported() {
+        let dir = workflows_dir();
+        let runs = Arc::new(AtomicUsize::new(0));
+        let runner: RunWorkflow = {
+            let runs = runs.clone();
+            Arc::new(move |_| {
+                runs.fetch_add(1, Ordering::SeqCst);
+                Box::pin(async { Err("element not found".to_string()) })
+            })
+        };
+        let hooks = Webhooks::new(dir.path().to_path_buf(), runner, None, None);
+
+        let record = hooks.trigger("/invoices/new", br#"{"amount": 1}"#).unwrap();
+        tokio::task::yield_now().await;
+        let status = hooks.status(&record.execution_id).unwrap();
+        assert_eq!(runs.load(Ordering::SeqCst), 1);
+        assert_eq!(status.status, ExecutionStatus::Failed);
+        assert_eq!(status.error.as_deref(), Some("element not found"));
+    }
+
+    #[test]
+    fn test_authorize() {
+        let dir = workflows_dir();
+        let runner = fake_runner(Arc::new(Mutex::new(Vec::new())));
+        let tokens = TokenStore::parse(
+            "tokens:\n  - name: ci\n    token: ci-secret\n    scopes: [action]\n  - name: viewer\n    token: view-secret\n    scopes: [read]\n",
+        )
+        .unwrap();
+        let hooks = Webhooks::new(
+            dir.path().to_path_buf(),
+            runner.clone(),
+            Some("s3cret".to_string()),
+            Some(Arc::new(tokens)),
+        );
+        let body = br#"{"amount": 1}"#;
+        let headers = |name: &'static str, value: &str| {
+            let mut headers = HeaderMap::new();
+            headers.insert(name, HeaderValue::from_str(value).unwrap());
+            headers
+        };
+        let signed_at = |secret: &str, ts: i64| {
+            let mut headers = headers(SIGNATURE_HEADER, &sign(secret, ts, body));
+            headers.insert(TIMESTAMP_HEADER, HeaderValue::from(ts));
+            headers
+        };
+        let now = Utc::now().timestamp();
+
+        let signed = signed_at("s3cret", now);
+        assert!(hooks.authorize(&signed, body, Scope::Action).is_ok());
+        // ... but only once, while status lookups may repeat
+        let denied = hooks.authorize(&signed, body, Scope::Action).unwrap_err();
+        assert!(
+            denied.message.contains("already used"),
+            "{}",
+            denied.message
+        );
+        let mut bare = signed.clone();
+        let digest = sign("s3cret", now, body).to_uppercase();
+        bare.insert(
+            SIGNATURE_HEADER,
+            HeaderValue::from_str(digest.trim_start_matches("SHA256=")).unwrap(),
+        );
+        let denied = hooks.authorize(&bare, body, Scope::Action).unwrap_err();
+        assert!(
+            denied.message.contains("already used"),
+            "{}",
+            denied.message
+        );
+        let polled = signed_at("s3cret", now - 1);
+        assert!(hooks.authorize(&polled, body, Scope::Read).is_ok());
+        assert!(hooks.authorize(&polled, body, Scope::Read).is_ok());
+        let forged = signed_at("guess", now);
+        let denied = hooks.authorize(&forged, body, Scope::Action).unwrap_err();
+        assert_eq!(denied.status, StatusCode::UNAUTHORIZED);
+
+        // A captured request can't be replayed once it is out of the window,
+        // and the timestamp can't be swapped for a fresh one
+        let replayed = signed_at("s3cret", now - 600);
+        let denied = hooks.authorize(&replayed, body, Scope::Action).unwrap_err();
+        assert!(denied.message.contains("window"), "{}", denied.message);
+        let mut swapped = signed_at("s3cret", now - 600);
+        swapped.insert(TIMESTAMP_HEADER, HeaderValue::from(now));
+        assert!(hooks.authorize(&swapped, body, Scope::Action).is_err());
+        let unstamped = headers(SIGNATURE_HEADER, &sign("s3cret", now, body));
+        let denied = hooks
+            .authorize(&unstamped, body, Scope::Action)
+            .unwrap_err();
+        assert!(denied.message.contains(TIMESTAMP_HEADER));
+
+        let ci = headers("authorization", "Bearer ci-secret");
+        assert!(hooks.authorize(&ci, body, Scope::Action).is_ok());
+        let viewer = headers("authorization", "Bearer view-secret");
+        let denied = hooks.authorize(&viewer, body, Scope::Action).unwrap_err();
+        assert_eq!(denied.status, StatusCode::FORBIDDEN);
+        let denied = hooks
+            .authorize(&HeaderMap::new(), body, Scope::Action)
+            .unwrap_err();
+        assert_eq!(denied.status, StatusCode::UNAUTHORIZED);
+
+        // Secret only: a signature is required
+        let secret_only = Webhooks::new(
+            dir.path().to_path_buf(),
+            runner.clone(),
+            Some("s3cret".to_string()),
+            None,
+        );
+        let denied = secret_only
+            .authorize(&HeaderMap::new(), body, Scope::Action)
+            .unwrap_err();
+        assert_eq!(denied.status, StatusCode::UNAUTHORIZED);
+
+        // Neither configured: open, like /mcp
+        let open = Webhooks::new(dir.path().to_path_buf(), runner, None, None);
+        assert!(open
+            .authorize(&HeaderMap::new(), body, Scope::Action)
+            .is_ok());
+    }
+
+    #[test]
+    fn test_status_from_history() {
+        let dir = workflows_dir();
+        let hooks = Webhooks::new(
+            dir.path().to_path_buf(),
+            fake_runner(Arc::new(Mutex::new(Vec::new()))),
+            None,
+            None,
+        );
+        let log_path = dir
+            .path()
+            .join("20250310_120000_invoice_full_execute_sequence.json");
+        std::fs::write(
+            &log_path,
+            json!({
+                "timestamp": "2025-03-10T12:00:00+00:00",
+                "tool_name": "execute_sequence",
+                "request": {"execution_id": "run-1"},
+                "response": {
+                    "status": "executed_without_error",
+                    "duration_ms": 2000,
+                    "result": {"status": "executed_with_error"}
+                }
+            })
+            .to_string(),
+        )
+        .unwrap();
+        let history = History::new(dir.path().join("history.jsonl"));
+        let events = [
+            json!({"event": "run_started", "run_id": "run-1", "workflow": "file:///flows/invoice.yml", "started_at": "2025-03-10T12:00:00Z"}),
+            json!({"event": "run_finished", "run_id": "run-1", "finished_at": "2025-03-10T12:00:02Z", "duration_ms": 2000, "status": "failed"}),
+            json!({"event": "run_log", "run_id": "run-1", "log_path": log_path.display().to_string()}),
+            json!({"event": "run_started", "run_id": "run-2", "started_at": "2025-03-10T12:01:00Z"}),
+        ];
+        let lines: Vec<String> = events.iter().map(|e| e.to_string()).collect();
+        std::fs::write(history.path(), lines.join("\n") + "\n").unwrap();
+
+        let finished = hooks.status_in(&history, "run-1").unwrap();
+        assert_eq!(finished.status, ExecutionStatus::Failed);
+        assert_eq!(finished.workflow_id.as_deref(), Some("invoice"));
+        assert_eq!(
+            finished.workflow_status.as_deref(),
+            Some("executed_with_error")
+        );
+        assert_eq!(finished.log_path, Some(log_path.display().to_string()));
+
+        let running = hooks.status_in(&history, "run-2").unwrap();
+        assert_eq!(running.status, ExecutionStatus::Running);
+        assert!(hooks.status_in(&history, "run").is_none());
+    }
+}
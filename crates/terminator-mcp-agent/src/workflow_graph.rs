@@ -0,0 +1,637 @@
+//! Control-flow graph of a sequence workflow, rendered as Graphviz DOT or Mermaid.
+//!
+//! Nodes are the main and troubleshooting steps in execution order. Edges follow
+//! what `execute_sequence` does at runtime: fall through to the next step, take a
+//! matching `jumps` entry after a success, or follow `fallback_id` after a failure.
+//! A past run can be overlaid from the execution logs to show the path it took.
+
+use crate::execution_logger::ExecutionLog;
+use crate::utils::{ExecuteSequenceArgs, SequenceStep};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fmt::Write as _;
+use std::path::Path;
+
+/// Which part of the workflow a step belongs to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Section {
+    Main,
+    Troubleshooting,
+}
+
+#[derive(Debug, Clone)]
+pub struct GraphStep {
+    /// Index in the executed sequence (main steps, then troubleshooting steps)
+    pub index: usize,
+    pub section: Section,
+    pub id: Option<String>,
+    /// Tool name, or group name for grouped steps
+    pub action: String,
+    /// Number of sub-steps for grouped steps
+    pub group_len: Option<usize>,
+    pub condition: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum GraphNode {
+    Start,
+    Step(usize),
+    End,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EdgeKind {
+    /// Fall through to the next step (or finish)
+    Next,
+    /// A `jumps` entry taken after the step succeeds
+    Jump,
+    /// `fallback_id` taken after the step fails
+    Fallback,
+}
+
+#[derive(Debug, Clone)]
+pub struct GraphEdge {
+    pub from: GraphNode,
+    pub to: GraphNode,
+    pub kind: EdgeKind,
+    pub label: Option<String>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct WorkflowGraph {
+    pub steps: Vec<GraphStep>,
+    pub edges: Vec<GraphEdge>,
+}
+
+/// What happened to a step in a past run.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StepOutcome {
+    /// Tool calls logged for the step, including retries and group sub-steps
+    pub calls: usize,
+    /// Whether the step's last logged call failed
+    pub failed: bool,
+}
+
+/// A past run mapped onto the graph.
+#[derive(Debug, Clone, Default)]
+pub struct RunOverlay {
+    /// Steps in the order they ran (consecutive calls of one step collapsed)
+    pub path: Vec<usize>,
+    pub outcomes: BTreeMap<usize, StepOutcome>,
+}
+
+impl RunOverlay {
+    fn traversed(&self) -> HashSet<(GraphNode, GraphNode)> {
+        let mut nodes = vec![GraphNode::Start];
+        nodes.extend(self.path.iter().map(|&i| GraphNode::Step(i)));
+        nodes.windows(2).map(|w| (w[0], w[1])).collect()
+    }
+}
+
+impl WorkflowGraph {
+    pub fn build(workflow: &ExecuteSequenceArgs) -> Self {
+        let main = workflow.steps.as_deref().unwrap_or_default();
+        let troubleshooting = workflow.troubleshooting.as_deref().unwrap_or_default();
+
+        let steps: Vec<GraphStep> = main
+            .iter()
+            .map(|s| (Section::Main, s))
+            .chain(
+                troubleshooting
+                    .iter()
+                    .map(|s| (Section::Troubleshooting, s)),
+            )
+            .enumerate()
+            .map(|(index, (section, step))| GraphStep {
+                index,
+                section,
+                id: step.id.clone(),
+                action: step_action(step),
+                group_len: step.steps.as_ref().map(|s| s.len()),
+                condition: step.r#if.clone(),
+            })
+            .collect();
+
+        // Later duplicates win, matching the executor's id lookup
+        let ids: HashMap<&str, usize> = steps
+            .iter()
+            .filter_map(|s| s.id.as_deref().map(|id| (id, s.index)))
+            .collect();
+
+        let mut edges = Vec::new();
+        let entry = workflow
+            .start_from_step
+            .as_deref()
+            .and_then(|id| ids.get(id).copied())
+            .unwrap_or(0);
+        edges.push(GraphEdge {
+            from: GraphNode::Start,
+            to: if steps.is_empty() {
+                GraphNode::End
+            } else {
+                GraphNode::Step(entry)
+            },
+            kind: EdgeKind::Next,
+            label: None,
+        });
+
+        let all_steps = main.iter().chain(troubleshooting);
+        for (index, step) in all_steps.enumerate() {
+            let from = GraphNode::Step(index);
+            // Main steps never fall through into troubleshooting
+            let next = if index + 1 == main.len() || index + 1 == steps.len() {
+                GraphNode::End
+            } else {
+                GraphNode::Step(index + 1)
+            };
+            edges.push(GraphEdge {
+                from,
+                to: next,
+                kind: EdgeKind::Next,
+                label: None,
+            });
+            for jump in step.jumps.iter().flatten() {
+                if let Some(&target) = ids.get(jump.to_id.as_str()) {
+                    edges.push(GraphEdge {
+                        from,
+                        to: GraphNode::Step(target),
+                        kind: EdgeKind::Jump,
+                        label: Some(jump.condition.clone()),
+                    });
+                }
+            }
+            if let Some(&target) = step.fallback_id.as_deref().and_then(|id| ids.get(id)) {
+                edges.push(GraphEdge {
+                    from,
+                    to: GraphNode::Step(target),
+                    kind: EdgeKind::Fallback,
+                    label: Some("on failure".to_string()),
+                });
+            }
+        }
+
+        Self { steps, edges }
+    }
+
+    /// Whether execution can move directly from one step to another.
+    fn allows(&self, from: usize, to: usize) -> bool {
+        // Steps after `from` may have been skipped by their `if`
+        to > from
+            || self
+                .edges
+                .iter()
+                .any(|e| e.from == GraphNode::Step(from) && e.to == GraphNode::Step(to))
+    }
+
+    /// Map logged tool calls onto the graph.
+    ///
+    /// Only calls of the most recent run (the `execution_id` of the newest log)
+    /// are used, so overlapping or interleaved runs don't mix. Logs written
+    /// before calls carried an `execution_id` are split into runs wherever
+    /// execution moves backwards without a jump or fallback edge to explain it.
+    pub fn overlay_last_run(&self, logs: &[ExecutionLog]) -> RunOverlay {
+        let ids: HashMap<&str, usize> = self
+            .steps
+            .iter()
+            .filter_map(|s| s.id.as_deref().map(|id| (id, s.index)))
+            .collect();
+
+        let latest_run = logs
+            .iter()
+            .rev()
+            .find_map(|log| log.execution_id.as_deref());
+        let mut calls: Vec<(usize, bool)> = logs
+            .iter()
+            .filter(|log| latest_run.is_none() || log.execution_id.as_deref() == latest_run)
+            .filter_map(|log| {
+                // step_index is 1-based; group sub-steps log their own id but the group's index
+                let index = log
+                    .step_index
+                    .and_then(|i| i.checked_sub(1))
+                    .filter(|&i| i < self.steps.len())
+                    .or_else(|| log.step_id.as_deref().and_then(|id| ids.get(id).copied()))?;
+                Some((index, log.response.status == "executed_with_error"))
+            })
+            .collect();
+
+        if latest_run.is_none() {
+            let run_start = calls
+                .windows(2)
+                .rposition(|w| w[1].0 < w[0].0 && !self.allows(w[0].0, w[1].0))
+                .map_or(0, |pos| pos + 1);
+            calls.drain(..run_start);
+        }
+
+        let mut overlay = RunOverlay::default();
+        for (index, failed) in calls {
+            if overlay.path.last() != Some(&index) {
+                overlay.path.push(index);
+            }
+            let outcome = overlay
+                .outcomes
+                .entry(index)
+                .or_insert(StepOutcome { calls: 0, failed });
+            outcome.calls += 1;
+            outcome.failed = failed;
+        }
+        overlay
+    }
+
+    pub fn to_dot(&self, overlay: Option<&RunOverlay>) -> String {
+        let traversed = overlay.map(RunOverlay::traversed).unwrap_or_default();
+        let mut out = String::new();
+        out.push_str("digraph workflow {\n");
+        out.push_str("  rankdir=TB;\n");
+        out.push_str("  node [shape=box, style=\"rounded,filled\", fillcolor=white, fontname=\"Helvetica\"];\n");
+        out.push_str("  edge [fontname=\"Helvetica\", fontsize=10];\n");
+        out.push_str("  start [shape=circle, label=\"start\", fillcolor=\"#eeeeee\"];\n");
+        out.push_str("  end [shape=doublecircle, label=\"end\", fillcolor=\"#eeeeee\"];\n");
+
+        let write_step = |out: &mut String, step: &GraphStep, indent: &str| {
+            let mut attrs = format!("label=\"{}\"", dot_escape(&step.label()));
+            if let Some(outcome) = overlay.and_then(|o| o.outcomes.get(&step.index)) {
+                let color = if outcome.failed { "#f8d0d0" } else { "#d4f7d4" };
+                let _ = write!(attrs, ", fillcolor=\"{color}\", penwidth=2");
+            } else if overlay.is_some() {
+                attrs.push_str(", color=\"#999999\", fontcolor=\"#999999\"");
+            }
+            let _ = writeln!(
+                out,
+                "{indent}{} [{attrs}];",
+                node_name(GraphNode::Step(step.index))
+            );
+        };
+
+        for step in self.steps.iter().filter(|s| s.section == Section::Main) {
+            write_step(&mut out, step, "  ");
+        }
+        if self
+            .steps
+            .iter()
+            .any(|s| s.section == Section::Troubleshooting)
+        {
+            out.push_str("  subgraph cluster_troubleshooting {\n");
+            out.push_str("    label=\"troubleshooting\";\n    style=dashed;\n");
+            for step in self
+                .steps
+                .iter()
+                .filter(|s| s.section == Section::Troubleshooting)
+            {
+                write_step(&mut out, step, "    ");
+            }
+            out.push_str("  }\n");
+        }
+
+        for edge in &self.edges {
+            let mut attrs = Vec::new();
+            if let Some(label) = &edge.label {
+                attrs.push(format!("label=\"{}\"", dot_escape(label)));
+            }
+            match edge.kind {
+                EdgeKind::Next => {}
+                EdgeKind::Jump => {
+                    attrs.push("color=\"#1565c0\", fontcolor=\"#1565c0\"".to_string())
+                }
+                EdgeKind::Fallback => {
+                    attrs.push("style=dashed, color=\"#c62828\", fontcolor=\"#c62828\"".to_string())
+                }
+            }
+            if traversed.contains(&(edge.from, edge.to)) {
+                attrs.push("penwidth=3".to_string());
+            }
+            let attrs = if attrs.is_empty() {
+                String::new()
+            } else {
+                format!(" [{}]", attrs.join(", "))
+            };
+            let _ = writeln!(
+                out,
+                "  {} -> {}{attrs};",
+                node_name(edge.from),
+                node_name(edge.to)
+            );
+        }
+        out.push_str("}\n");
+        out
+    }
+
+    pub fn to_mermaid(&self, overlay: Option<&RunOverlay>) -> String {
+        let traversed = overlay.map(RunOverlay::traversed).unwrap_or_default();
+        let mut out = String::from("flowchart TD\n");
+        out.push_str("  start((start))\n");
+        out.push_str("  end_node((end))\n");
+
+        let write_step = |out: &mut String, step: &GraphStep, indent: &str| {
+            let label = mermaid_escape(&step.label()).replace('\n', "<br/>");
+            let _ = writeln!(
+                out,
+                "{indent}{}[\"{label}\"]",
+                node_name(GraphNode::Step(step.index))
+            );
+        };
+        for step in self.steps.iter().filter(|s| s.section == Section::Main) {
+            write_step(&mut out, step, "  ");
+        }
+        if self
+            .steps
+            .iter()
+            .any(|s| s.section == Section::Troubleshooting)
+        {
+            out.push_str("  subgraph troubleshooting\n");
+            for step in self
+                .steps
+                .iter()
+                .filter(|s| s.section == Section::Troubleshooting)
+            {
+                write_step(&mut out, step, "    ");
+            }
+            out.push_str("  end\n");
+        }
+
+        let mut taken_links = Vec::new();
+        for (n, edge) in self.edges.iter().enumerate() {
+            let arrow = match (edge.kind, &edge.label) {
+                (EdgeKind::Fallback, Some(label)) => {
+                    format!("-.->|\"{}\"|", mermaid_escape(label))
+                }
+                (EdgeKind::Fallback, None) => "-.->".to_string(),
+                (_, Some(label)) => format!("-->|\"{}\"|", mermaid_escape(label)),
+                (_, None) => "-->".to_string(),
+            };
+            let _ = writeln!(
+                out,
+                "  {} {arrow} {}",
+                mermaid_node_name(edge.from),
+                mermaid_node_name(edge.to)
+            );
+            if traversed.contains(&(edge.from, edge.to)) {
+                taken_links.push(n.to_string());
+            }
+        }
+
+        if let Some(overlay) = overlay {
+            out.push_str("  classDef ok fill:#d4f7d4,stroke:#2e7d32,stroke-width:2px\n");
+            out.push_str("  classDef failed fill:#f8d0d0,stroke:#c62828,stroke-width:2px\n");
+            out.push_str("  classDef notrun fill:#ffffff,stroke:#999999,color:#999999\n");
+            let mut classes: BTreeMap<&str, Vec<String>> = BTreeMap::new();
+            for step in &self.steps {
+                let class = match overlay.outcomes.get(&step.index) {
+                    Some(outcome) if outcome.failed => "failed",
+                    Some(_) => "ok",
+                    None => "notrun",
+                };
+                classes
+                    .entry(class)
+                    .or_default()
+                    .push(node_name(GraphNode::Step(step.index)));
+            }
+            for (class, nodes) in classes {
+                let _ = writeln!(out, "  class {} {class}", nodes.join(","));
+            }
+            if !taken_links.is_empty() {
+                let _ = writeln!(
+                    out,
+                    "  linkStyle {} stroke:#2e7d32,stroke-width:3px",
+                    taken_links.join(",")
+                );
+            }
+        }
+        out
+    }
+}
+
+impl GraphStep {
+    fn label(&self) -> String {
+        let number = match self.section {
+            Section::Main => format!("{}", self.index + 1),
+            Section::Troubleshooting => format!("T{}", self.index + 1),
+        };
+        let mut label = match &self.id {
+            Some(id) => format!("{number}. {id}\n{}", self.action),
+            None => format!("{number}. {}", self.action),
+        };
+        if let Some(len) = self.group_len {
+            let _ = write!(label, " ({len} steps)");
+        }
+        if let Some(condition) = &self.condition {
+            let _ = write!(label, "\nif: {condition}");
+        }
+        label
+    }
+}
+
+fn step_action(step: &SequenceStep) -> String {
+    match (&step.tool_name, &step.group_name) {
+        (Some(tool), _) => tool
+            .strip_prefix("mcp_terminator-mcp-agent_")
+            .unwrap_or(tool)
+            .to_string(),
+        (None, Some(group)) => format!("group: {group}"),
+        (None, None) => "group".to_string(),
+    }
+}
+
+fn node_name(node: GraphNode) -> String {
+    match node {
+        GraphNode::Start => "start".to_string(),
+        GraphNode::Step(i) => format!("s{i}"),
+        GraphNode::End => "end".to_string(),
+    }
+}
+
+// `end` is a keyword in Mermaid
+fn mermaid_node_name(node: GraphNode) -> String {
+    match node {
+        GraphNode::End => "end_node".to_string(),
+        other => node_name(other),
+    }
+}
+
+fn dot_escape(s: &str) -> String {
+    s.replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+fn mermaid_escape(s: &str) -> String {
+    s.replace('"', "#quot;")
+        .replace('<', "#lt;")
+        .replace('>', "#gt;")
+        .replace('|', "#124;")
+}
+
+/// Read execution logs (`*.json`) from a directory, oldest first.
+/// Files that aren't execution logs are skipped.
+pub fn load_execution_logs(dir: &Path) -> std::io::Result<Vec<ExecutionLog>> {
+    let mut logs: Vec<(chrono::DateTime<chrono::FixedOffset>, ExecutionLog)> = Vec::new();
+    for entry in std::fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.extension().and_then(|e| e.to_str()) != Some("json") {
+            continue;
+        }
+        let Ok(content) = std::fs::read_to_string(&path) else {
+            continue;
+        };
+        let Ok(log) = serde_json::from_str::<ExecutionLog>(&content) else {
+            continue;
+        };
+        if let Ok(timestamp) = chrono::DateTime::parse_from_rfc3339(&log.timestamp) {
+            logs.push((timestamp, log));
+        }
+    }
+    logs.sort_by_key(|(timestamp, _)| *timestamp);
+    Ok(logs.into_iter().map(|(_, log)| log).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::execution_logger::ExecutionResponse;
+    use serde_json::json;
+
+    fn graph() -> WorkflowGraph {
+        let workflow: ExecuteSequenceArgs = serde_json::from_value(json!({
+            "steps": [
+                {"id": "open", "tool_name": "open_application"},
+                {"id": "login", "tool_name": "click_element", "fallback_id": "recover",
+                 "jumps": [{"if": "login_status == 'done'", "to_id": "finish"}]},
+                {"group_name": "Fill form", "steps": [{"tool_name": "type_into_element", "arguments": {}}]},
+                {"id": "finish", "tool_name": "delay", "if": "env.ready"}
+            ],
+            "troubleshooting": [
+                {"id": "recover", "tool_name": "press_key", "jumps": [{"if": "always()", "to_id": "login"}]}
+            ]
+        }))
+        .unwrap();
+        WorkflowGraph::build(&workflow)
+    }
+
+    fn log(execution_id: Option<&str>, step_index: usize, failed: bool) -> ExecutionLog {
+        ExecutionLog {
+            timestamp: String::new(),
+            workflow_id: None,
+            execution_id: execution_id.map(String::from),
+            step_id: None,
+            step_index: Some(step_index),
+            retry_attempt: None,
+            tool_name: "delay".to_string(),
+            request: json!({}),
+            response: ExecutionResponse {
+                status: if failed {
+                    "executed_with_error"
+                } else {
+                    "executed_without_error"
+                }
+                .to_string(),
+                duration_ms: 1,
+                result: None,
+                error: None,
+            },
+            screenshots: None,
+            logs: None,
+            policy: None,
+            client: None,
+        }
+    }
+
+    #[test]
+    fn builds_edges_for_jumps_fallbacks_and_sections() {
+        let g = graph();
+        let has = |from, to, kind| {
+            g.edges
+                .iter()
+                .any(|e| e.from == from && e.to == to && e.kind == kind)
+        };
+        assert!(has(GraphNode::Start, GraphNode::Step(0), EdgeKind::Next));
+        assert!(has(GraphNode::Step(1), GraphNode::Step(3), EdgeKind::Jump));
+        assert!(has(
+            GraphNode::Step(1),
+            GraphNode::Step(4),
+            EdgeKind::Fallback
+        ));
+        // The last main step finishes instead of falling into troubleshooting
+        assert!(has(GraphNode::Step(3), GraphNode::End, EdgeKind::Next));
+        assert!(has(GraphNode::Step(4), GraphNode::End, EdgeKind::Next));
+        assert!(has(GraphNode::Step(4), GraphNode::Step(1), EdgeKind::Jump));
+    }
+
+    #[test]
+    fn renders_dot_and_mermaid() {
+        let g = graph();
+        let dot = g.to_dot(None);
+        assert!(dot.starts_with("digraph workflow {"));
+        assert!(dot.contains("s1 -> s3 [label=\"login_status == 'done'\""));
+        assert!(dot.contains("subgraph cluster_troubleshooting"));
+        assert!(dot.contains("if: env.ready"));
+
+        let mermaid = g.to_mermaid(None);
+        assert!(mermaid.starts_with("flowchart TD"));
+        assert!(mermaid.contains("s1 -->|\"login_status == 'done'\"| s3"));
+        assert!(mermaid.contains("s1 -.->|\"on failure\"| s4"));
+        assert!(mermaid.contains("s3 --> end_node"));
+    }
+
+    #[test]
+    fn overlays_the_latest_run() {
+        let g = graph();
+        // Second run: 1 ok, 2 fails, fallback to T1, jump back to 2, then done.
+        // A third run started meanwhile is still at step 1, and the first run's
+        // late calls interleave with the second.
+        let logs: Vec<_> = [
+            ("run-1", 1, false),
+            ("run-2", 1, false),
+            ("run-1", 2, false),
+            ("run-2", 2, true),
+            ("run-2", 5, false),
+            ("run-1", 3, false),
+            ("run-2", 2, false),
+            ("run-3", 1, true),
+            ("run-2", 3, false),
+        ]
+        .into_iter()
+        .map(|(run, i, failed)| log(Some(run), i, failed))
+        .collect();
+        // The newest log decides which run is shown
+        let overlay = g.overlay_last_run(&logs);
+        assert_eq!(overlay.path, vec![0, 1, 4, 1, 2]);
+        assert_eq!(
+            overlay.outcomes[&1],
+            StepOutcome {
+                calls: 2,
+                failed: false
+            }
+        );
+        assert!(!overlay.outcomes.contains_key(&3));
+        let overlay_3 = g.overlay_last_run(&logs[..8]);
+        assert_eq!(overlay_3.path, vec![0]);
+        assert!(overlay_3.outcomes[&0].failed);
+
+        // Logs without an execution_id fall back to splitting on backward moves
+        let legacy: Vec<_> = [
+            (1, false),
+            (2, false),
+            (1, false),
+            (2, true),
+            (5, false),
+            (2, false),
+            (3, false),
+        ]
+        .into_iter()
+        .map(|(i, failed)| log(None, i, failed))
+        .collect();
+        assert_eq!(g.overlay_last_run(&legacy).path, overlay.path);
+
+        let dot = g.to_dot(Some(&overlay));
+        assert!(dot.contains("s1 -> s4 [label=\"on failure\", style=dashed, color=\"#c62828\", fontcolor=\"#c62828\", penwidth=3]"));
+        let mermaid = g.to_mermaid(Some(&overlay));
+        assert!(mermaid.contains("class s3 notrun"));
+    }
+
+    #[test]
+    fn marks_failed_steps() {
+        let g = graph();
+        let overlay =
+            g.overlay_last_run(&[log(Some("run-1"), 1, false), log(Some("run-1"), 2, true)]);
+        assert!(overlay.outcomes[&1].failed);
+        assert!(g.to_mermaid(Some(&overlay)).contains("class s1 failed"));
+    }
+}
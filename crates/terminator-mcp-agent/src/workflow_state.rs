@@ -0,0 +1,590 @@
+//! Persistent workflow checkpoints (`state.json`).
+//!
+//! The sequence runner writes a checkpoint before and after every step so an interrupted
+//! run can continue exactly where it stopped (`resume: true`). Besides the accumulated
+//! `env`, a checkpoint records the in-flight step, group sub-step and retry attempt,
+//! per-step results, and a content hash of the workflow definition so state saved for a
+//! different version of the workflow is detected instead of silently reused.
+//!
+//! Files live next to the execution logs:
+//!   Windows: %LOCALAPPDATA%\mediar\workflows\<workflow_id>\state.json
+//!   macOS: ~/Library/Application Support/mediar/workflows/<workflow_id>/state.json
+//!   Linux: ~/.local/share/mediar/workflows/<workflow_id>/state.json
+//!
+//! A resumed run reads `env` and the step results back as real values, so they are
+//! written unredacted. On Unix the file is only readable by its owner; on Windows it
+//! sits in the user's own profile. Use [`WorkflowCheckpoint::redacted`] to show one.
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::fs;
+use std::path::{Path, PathBuf};
+use tracing::{debug, warn};
+
+pub const STATE_FILE_NAME: &str = "state.json";
+
+/// Lifecycle of the run that produced a checkpoint
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CheckpointStatus {
+    /// Still running, or the process died / was cancelled mid-step
+    #[default]
+    Running,
+    Completed,
+    Failed,
+    Cancelled,
+}
+
+/// The step the runner was executing when the checkpoint was written
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StepPosition {
+    pub step_index: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub step_id: Option<String>,
+    /// Number of group sub-steps that already succeeded in the current attempt
+    #[serde(default)]
+    pub group_step_index: usize,
+    /// 0-based attempt of the step (0 = first run)
+    #[serde(default)]
+    pub retry_attempt: u32,
+}
+
+/// Where a resumed run should pick up
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ResumePoint {
+    pub step_index: usize,
+    pub group_step_index: usize,
+    pub retry_attempt: u32,
+}
+
+/// Contents of `state.json`. Field names of the original env-only format are kept so
+/// older state files still load (the new fields simply default).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WorkflowCheckpoint {
+    pub last_updated: String,
+    pub last_step_id: Option<String>,
+    #[serde(default)]
+    pub last_step_index: usize,
+    pub workflow_id: Option<String>,
+    pub workflow_file: Option<String>,
+    #[serde(default)]
+    pub env: Value,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub content_hash: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub execution_id: Option<String>,
+    #[serde(default)]
+    pub status: CheckpointStatus,
+    /// Index the runner will execute next once the in-flight step (if any) is done
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub next_step_index: Option<usize>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub position: Option<StepPosition>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_successful_step_index: Option<usize>,
+    /// Step whose failure stopped the run (only set when status is `failed`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub failed_step_index: Option<usize>,
+    /// Results keyed by step id (or `#<index>` for steps without an id)
+    #[serde(default, skip_serializing_if = "Map::is_empty")]
+    pub step_results: Map<String, Value>,
+}
+
+impl WorkflowCheckpoint {
+    pub fn new(
+        workflow_id: Option<&str>,
+        workflow_url: Option<&str>,
+        content_hash: Option<String>,
+        execution_id: Option<String>,
+    ) -> Self {
+        Self {
+            last_updated: chrono::Utc::now().to_rfc3339(),
+            last_step_id: None,
+            last_step_index: 0,
+            workflow_id: workflow_id.map(String::from),
+            workflow_file: workflow_url.and_then(workflow_file_name),
+            env: Value::Object(Map::new()),
+            content_hash,
+            execution_id,
+            status: CheckpointStatus::Running,
+            next_step_index: None,
+            position: None,
+            last_successful_step_index: None,
+            failed_step_index: None,
+            step_results: Map::new(),
+        }
+    }
+
+    /// Load a checkpoint, returning `None` if the file does not exist
+    pub fn load(path: &Path) -> Result<Option<Self>> {
+        if !path.exists() {
+            return Ok(None);
+        }
+        let content = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read state file {}", path.display()))?;
+        let checkpoint = serde_json::from_str(&content)
+            .with_context(|| format!("Failed to parse state file {}", path.display()))?;
+        Ok(Some(checkpoint))
+    }
+
+    /// Write the checkpoint atomically (temp file + rename) so a crash mid-write
+    /// never leaves a truncated state file behind
+    pub fn save(&mut self, path: &Path) -> Result<()> {
+        self.last_updated = chrono::Utc::now().to_rfc3339();
+        write_atomic(path, &self.to_disk_json()?)
+    }
+
+    /// Save if a state location is known; failures are logged, never fatal to the workflow.
+    /// The file is written on the blocking pool so the runner is not stalled by disk I/O.
+    pub async fn persist(&mut self, path: Option<&Path>) {
+        let Some(path) = path else {
+            return;
+        };
+        self.last_updated = chrono::Utc::now().to_rfc3339();
+        let content = match self.to_disk_json() {
+            Ok(content) => content,
+            Err(e) => {
+                warn!("Failed to serialize workflow checkpoint: {:#}", e);
+                return;
+            }
+        };
+        let path = path.to_path_buf();
+        match tokio::task::spawn_blocking(move || write_atomic(&path, &content)).await {
+            Ok(Ok(())) => {}
+            Ok(Err(e)) => warn!("Failed to save workflow checkpoint: {:#}", e),
+            Err(e) => warn!("Workflow checkpoint write task failed: {}", e),
+        }
+    }
+
+    /// Compact JSON for disk. Not redacted: a resumed run needs the real values
+    fn to_disk_json(&self) -> Result<String> {
+        Ok(serde_json::to_string(self)?)
+    }
+
+    /// The checkpoint as JSON with secrets masked, for display and logs
+    pub fn redacted(&self) -> Value {
+        let mut value = serde_json::to_value(self).unwrap_or_default();
+        terminator::redaction::global().redact_json(&mut value);
+        value
+    }
+
+    /// Mark the start (or retry) of a step
+    pub fn begin_step(&mut self, step_index: usize, step_id: Option<&str>, retry_attempt: u32) {
+        self.status = CheckpointStatus::Running;
+        self.position = Some(StepPosition {
+            step_index,
+            step_id: step_id.map(String::from),
+            group_step_index: 0,
+            retry_attempt,
+        });
+    }
+
+    /// Record that the first `completed` sub-steps of the in-flight group succeeded
+    pub fn advance_group(&mut self, completed: usize) {
+        if let Some(position) = self.position.as_mut() {
+            position.group_step_index = completed;
+        }
+    }
+
+    /// Record the outcome of a finished step
+    pub fn complete_step(
+        &mut self,
+        step_index: usize,
+        step_id: Option<&str>,
+        succeeded: bool,
+        result: &Value,
+    ) {
+        self.position = None;
+        self.last_step_index = step_index;
+        self.last_step_id = step_id.map(String::from);
+        if succeeded {
+            self.last_successful_step_index = Some(step_index);
+        }
+
+        let mut stored = result.clone();
+        if let Some(obj) = stored.as_object_mut() {
+            obj.remove("server_logs");
+        }
+        strip_ui_trees(&mut stored);
+        let key = step_id
+            .map(String::from)
+            .unwrap_or_else(|| format!("#{step_index}"));
+        self.step_results.insert(key, stored);
+    }
+
+    /// Whether this checkpoint was written for a different workflow definition.
+    /// Legacy checkpoints without a hash are never considered stale.
+    pub fn is_stale(&self, content_hash: &str) -> bool {
+        self.content_hash
+            .as_deref()
+            .is_some_and(|saved| saved != content_hash)
+    }
+
+    /// Where `resume: true` should continue, or `None` if the run already completed
+    pub fn resume_point(&self) -> Option<ResumePoint> {
+        match self.status {
+            CheckpointStatus::Completed => None,
+            CheckpointStatus::Failed => {
+                self.failed_step_index
+                    .or(self.next_step_index)
+                    .map(|step_index| ResumePoint {
+                        step_index,
+                        group_step_index: 0,
+                        retry_attempt: 0,
+                    })
+            }
+            CheckpointStatus::Running | CheckpointStatus::Cancelled => {
+                if let Some(position) = &self.position {
+                    Some(ResumePoint {
+                        step_index: position.step_index,
+                        group_step_index: position.group_step_index,
+                        retry_attempt: position.retry_attempt,
+                    })
+                } else if let Some(next) = self.next_step_index {
+                    Some(ResumePoint {
+                        step_index: next,
+                        group_step_index: 0,
+                        retry_attempt: 0,
+                    })
+                } else {
+                    // Legacy env-only state: continue after the last step that saved it
+                    self.last_step_id.as_ref().map(|_| ResumePoint {
+                        step_index: self.last_step_index + 1,
+                        group_step_index: 0,
+                        retry_attempt: 0,
+                    })
+                }
+            }
+        }
+    }
+}
+
+/// A state file found on disk
+#[derive(Debug, Clone)]
+pub struct StoredState {
+    /// Directory name under the workflows dir (workflow id or URL hash)
+    pub key: String,
+    pub path: PathBuf,
+    pub checkpoint: WorkflowCheckpoint,
+}
+
+/// Root directory holding per-workflow state and execution logs
+pub fn workflows_dir() -> Option<PathBuf> {
+    dirs::data_local_dir().map(|d| d.join("mediar").join("workflows"))
+}
+
+/// State key for a workflow: the workflow id, or a hash of the `file://` path for
+/// backward compatibility with workflows that have no id. An id that is not a
+/// valid directory name (see [`validate_key`]) gets no state.
+pub fn state_key(workflow_id: Option<&str>, workflow_url: Option<&str>) -> Option<String> {
+    if let Some(id) = workflow_id {
+        if let Err(e) = validate_key(id) {
+            warn!("Not saving or loading workflow state: {:#}", e);
+            return None;
+        }
+        return Some(id.to_string());
+    }
+    let file_path = workflow_url?.strip_prefix("file://")?;
+    use std::collections::hash_map::DefaultHasher;
+    use std::hash::{Hash, Hasher};
+    let mut hasher = DefaultHasher::new();
+    file_path.hash(&mut hasher);
+    Some(format!("{:x}", hasher.finish()))
+}
+
+/// Path of the state file for a workflow. Priority: workflow_id > URL hash
+pub fn state_file_path(workflow_id: Option<&str>, workflow_url: Option<&str>) -> Option<PathBuf> {
+    let key = state_key(workflow_id, workflow_url)?;
+    state_path_in(&workflows_dir()?, &key).ok()
+}
+
+/// Path of the state file for `key` under `root`
+fn state_path_in(root: &Path, key: &str) -> Result<PathBuf> {
+    validate_key(key)?;
+    Ok(root.join(key).join(STATE_FILE_NAME))
+}
+
+/// Stable FNV-1a hash of a workflow definition's canonical JSON.
+/// `DefaultHasher` is not guaranteed stable across Rust releases, which would make
+/// every checkpoint look stale after a toolchain upgrade.
+pub fn content_hash(definition: &Value) -> String {
+    const OFFSET: u64 = 0xcbf2_9ce4_8422_2325;
+    const PRIME: u64 = 0x0000_0100_0000_01b3;
+    let canonical = serde_json::to_string(definition).unwrap_or_default();
+    let hash = canonical.bytes().fold(OFFSET, |hash, byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(PRIME)
+    });
+    format!("{hash:016x}")
+}
+
+/// All saved states, most recently updated first
+pub fn list_states() -> Result<Vec<StoredState>> {
+    let Some(root) = workflows_dir() else {
+        return Ok(Vec::new());
+    };
+    list_states_in(&root)
+}
+
+fn list_states_in(root: &Path) -> Result<Vec<StoredState>> {
+    let mut states = Vec::new();
+    if !root.exists() {
+        return Ok(states);
+    }
+    for entry in fs::read_dir(root)? {
+        let entry = entry?;
+        let path = entry.path().join(STATE_FILE_NAME);
+        match WorkflowCheckpoint::load(&path) {
+            Ok(Some(checkpoint)) => states.push(StoredState {
+                key: entry.file_name().to_string_lossy().to_string(),
+                path,
+                checkpoint,
+            }),
+            Ok(None) => {}
+            Err(e) => warn!("Skipping unreadable state file: {:#}", e),
+        }
+    }
+    states.sort_by(|a, b| b.checkpoint.last_updated.cmp(&a.checkpoint.last_updated));
+    Ok(states)
+}
+
+/// Look up a saved state by key
+pub fn find_state(key: &str) -> Result<Option<StoredState>> {
+    Ok(list_states()?.into_iter().find(|s| s.key == key))
+}
+
+/// Delete a saved state. Execution logs in the same directory are left untouched.
+/// Returns false if there was nothing to delete.
+pub fn clear_state(key: &str) -> Result<bool> {
+    validate_key(key)?;
+    let Some(root) = workflows_dir() else {
+        return Ok(false);
+    };
+    let path = state_path_in(&root, key)?;
+    if !path.exists() {
+        return Ok(false);
+    }
+    fs::remove_file(&path).with_context(|| format!("Failed to delete {}", path.display()))?;
+    Ok(true)
+}
+
+/// State keys are single directory names; anything that could escape the
+/// workflows dir is rejected
+fn validate_key(key: &str) -> Result<()> {
+    if key.is_empty() || key == "." || key.contains("..") || key.contains(['/', '\\', ':']) {
+        anyhow::bail!("Invalid state key '{key}'");
+    }
+    Ok(())
+}
+
+fn write_atomic(path: &Path, content: &str) -> Result<()> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)
+            .with_context(|| format!("Failed to create state directory {}", dir.display()))?;
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            fs::set_permissions(dir, fs::Permissions::from_mode(0o700))
+                .with_context(|| format!("Failed to restrict {}", dir.display()))?;
+        }
+    }
+    let tmp = path.with_extension("json.tmp");
+    write_private(&tmp, content)
+        .with_context(|| format!("Failed to write state file {}", tmp.display()))?;
+    fs::rename(&tmp, path)
+        .with_context(|| format!("Failed to replace state file {}", path.display()))?;
+    debug!("Saved workflow checkpoint to: {:?}", path);
+    Ok(())
+}
+
+/// Writes `content` to a file only its owner can read
+fn write_private(path: &Path, content: &str) -> std::io::Result<()> {
+    #[cfg(unix)]
+    {
+        use std::io::Write;
+        use std::os::unix::fs::{OpenOptionsExt, PermissionsExt};
+        let mut file = fs::OpenOptions::new()
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .mode(0o600)
+            .open(path)?;
+        // `mode` only applies to new files; a leftover temp file keeps its own
+        file.set_permissions(fs::Permissions::from_mode(0o600))?;
+        file.write_all(content.as_bytes())
+    }
+    #[cfg(not(unix))]
+    {
+        fs::write(path, content)
+    }
+}
+
+/// UI trees can be megabytes per step and are never needed to resume a run
+fn strip_ui_trees(value: &mut Value) {
+    match value {
+        Value::Object(map) => {
+            map.remove("ui_tree");
+            map.values_mut().for_each(strip_ui_trees);
+        }
+        Value::Array(items) => items.iter_mut().for_each(strip_ui_trees),
+        _ => {}
+    }
+}
+
+fn workflow_file_name(url: &str) -> Option<String> {
+    Path::new(url.strip_prefix("file://").unwrap_or(url))
+        .file_name()
+        .and_then(|n| n.to_str())
+        .map(|s| s.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_legacy_state_file_loads() {
+        let legacy = json!({
+            "last_updated": "2025-01-01T00:00:00Z",
+            "last_step_id": "login",
+            "last_step_index": 2,
+            "workflow_id": null,
+            "workflow_file": "flow.yml",
+            "env": {"token": "abc"}
+        });
+        let cp: WorkflowCheckpoint = serde_json::from_value(legacy).unwrap();
+        assert_eq!(cp.status, CheckpointStatus::Running);
+        assert!(!cp.is_stale("anything"));
+        assert_eq!(cp.resume_point().unwrap().step_index, 3);
+    }
+
+    #[test]
+    fn test_resume_point_prefers_in_flight_position() {
+        let mut cp = WorkflowCheckpoint::new(Some("wf"), None, Some("h".into()), None);
+        cp.complete_step(
+            0,
+            Some("open"),
+            true,
+            &json!({"status": "executed_without_error"}),
+        );
+        cp.next_step_index = Some(1);
+        assert_eq!(cp.resume_point().unwrap().step_index, 1);
+
+        cp.begin_step(1, Some("fill"), 2);
+        cp.advance_group(3);
+        assert_eq!(
+            cp.resume_point(),
+            Some(ResumePoint {
+                step_index: 1,
+                group_step_index: 3,
+                retry_attempt: 2
+            })
+        );
+
+        cp.status = CheckpointStatus::Completed;
+        assert_eq!(cp.resume_point(), None);
+    }
+
+    #[test]
+    fn test_failed_run_resumes_at_failed_step() {
+        let mut cp = WorkflowCheckpoint::new(Some("wf"), None, None, None);
+        cp.status = CheckpointStatus::Failed;
+        cp.failed_step_index = Some(4);
+        cp.next_step_index = Some(7);
+        assert_eq!(cp.resume_point().unwrap().step_index, 4);
+    }
+
+    #[test]
+    fn test_content_hash_detects_changes() {
+        let a = json!({"steps": [{"tool_name": "click_element"}]});
+        let b = json!({"steps": [{"tool_name": "type_into_element"}]});
+        assert_eq!(content_hash(&a), content_hash(&a.clone()));
+        assert_ne!(content_hash(&a), content_hash(&b));
+
+        let cp = WorkflowCheckpoint::new(None, None, Some(content_hash(&a)), None);
+        assert!(!cp.is_stale(&content_hash(&a)));
+        assert!(cp.is_stale(&content_hash(&b)));
+    }
+
+    #[tokio::test]
+    async fn test_persist_load_and_list() {
+        let root = tempfile::tempdir().unwrap();
+        let path = root.path().join("wf").join(STATE_FILE_NAME);
+        let mut cp = WorkflowCheckpoint::new(
+            Some("wf"),
+            Some("file:///tmp/flow.yml"),
+            Some("h".into()),
+            None,
+        );
+        cp.complete_step(
+            0,
+            None,
+            true,
+            &json!({"status": "ok", "server_logs": [1], "result": {"ui_tree": {"role": "Window"}}}),
+        );
+        cp.persist(Some(&path)).await;
+
+        let content = fs::read_to_string(&path).unwrap();
+        assert!(!content.contains('\n'), "checkpoint is written compact");
+        let loaded = WorkflowCheckpoint::load(&path).unwrap().unwrap();
+        assert_eq!(loaded.workflow_file.as_deref(), Some("flow.yml"));
+        assert_eq!(
+            loaded.step_results["#0"],
+            json!({"status": "ok", "result": {}})
+        );
+
+        let states = list_states_in(root.path()).unwrap();
+        assert_eq!(states.len(), 1);
+        assert_eq!(states[0].key, "wf");
+    }
+
+    #[test]
+    fn test_clear_state_rejects_path_keys() {
+        for key in ["", "..", "../other", "a/b", "a\\b", "C:evil"] {
+            assert!(clear_state(key).is_err(), "{key:?}");
+            assert!(state_path_in(Path::new("/state"), key).is_err(), "{key:?}");
+        }
+        assert_eq!(state_key(Some("../x"), Some("file:///tmp/flow.yml")), None);
+        assert_eq!(state_file_path(Some("../x"), None), None);
+        assert_eq!(
+            state_key(Some("invoice-entry"), None).as_deref(),
+            Some("invoice-entry")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_secrets_survive_a_checkpoint_round_trip() {
+        let root = tempfile::tempdir().unwrap();
+        let path = root.path().join("login").join(STATE_FILE_NAME);
+        let mut cp = WorkflowCheckpoint::new(Some("login"), None, None, None);
+        cp.env = json!({"username": "jane", "password": "hunter2"});
+        cp.complete_step(
+            0,
+            Some("fetch_token"),
+            true,
+            &json!({"status": "ok", "result": {"api_key": "sk-123"}}),
+        );
+        cp.persist(Some(&path)).await;
+
+        // What the resume block puts back into the execution context
+        let resumed = WorkflowCheckpoint::load(&path).unwrap().unwrap();
+        assert_eq!(resumed.env["password"], "hunter2");
+        assert_eq!(
+            resumed.step_results["fetch_token"]["result"]["api_key"],
+            "sk-123"
+        );
+
+        let shown = resumed.redacted();
+        assert_eq!(shown["env"]["password"], terminator::redaction::REDACTED);
+        assert_eq!(shown["env"]["username"], "jane");
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = |p: &Path| fs::metadata(p).unwrap().permissions().mode() & 0o777;
+            assert_eq!(mode(&path), 0o600);
+            assert_eq!(mode(path.parent().unwrap()), 0o700);
+        }
+    }
+}
@@ -0,0 +1,262 @@
+//! Discovery of trigger-driven workflows
+//!
+//! The agent's workflows directory (`--workflows-dir`) holds workflows that run
+//! on their own, either on a cron schedule ([`crate::scheduler`]) or when a
+//! webhook is called ([`crate::webhooks`]). Two kinds of workflow are picked up:
+//!
+//! - YAML/JSON files (`*.yml`, `*.yaml`, `*.json`) with a `trigger:` block in
+//!   the shape of [`TriggerConfig`], or the shorthand top-level `cron:` key
+//!   (plus optional `timezone:`).
+//! - TypeScript workflow folders (`terminator.ts` or `src/terminator.ts`)
+//!   whose `createWorkflow` call declares a literal `trigger: { ... }` object.
+//!
+//! Both kinds run through `execute_sequence`, which dispatches TypeScript
+//! workflows to the TypeScript runner.
+
+use crate::workflow_typescript::TriggerConfig;
+use futures::future::BoxFuture;
+use regex::Regex;
+use serde::Serialize;
+use serde_json::{Map, Value};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, OnceLock};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum WorkflowKind {
+    Yaml,
+    TypeScript,
+}
+
+/// A workflow with an enabled cron or webhook trigger
+#[derive(Debug, Clone)]
+pub struct TriggeredWorkflow {
+    /// File stem for YAML workflows, folder name for TypeScript workflows
+    pub id: String,
+    pub path: PathBuf,
+    pub kind: WorkflowKind,
+    pub trigger: TriggerConfig,
+    /// `variables` schema of a YAML workflow
+    pub variables: Option<Value>,
+    /// Raw `missed_runs` key of a YAML workflow (see [`crate::scheduler`])
+    pub missed_runs: Option<Value>,
+}
+
+impl TriggeredWorkflow {
+    pub fn url(&self) -> String {
+        file_url(&self.path)
+    }
+}
+
+/// `file://` URL of a workflow, as accepted by `execute_sequence`
+pub fn file_url(path: &Path) -> String {
+    format!("file://{}", path.display())
+}
+
+/// One run of a triggered workflow
+#[derive(Debug, Clone)]
+pub struct RunRequest {
+    pub url: String,
+    pub inputs: Option<Value>,
+    /// Passed as the `execution_id` of the `execute_sequence` call, so the run
+    /// can be found in the execution logs
+    pub execution_id: String,
+}
+
+/// Runs a workflow and resolves to its final status, or an error message
+pub type RunWorkflow =
+    Arc<dyn Fn(RunRequest) -> BoxFuture<'static, Result<String, String>> + Send + Sync>;
+
+/// Scan `dir` for workflows with an enabled cron or webhook trigger. Files that
+/// can't be parsed are reported as warnings instead of failing the whole scan.
+pub fn discover(dir: &Path) -> (Vec<TriggeredWorkflow>, Vec<String>) {
+    let mut found: Vec<TriggeredWorkflow> = Vec::new();
+    let mut warnings = Vec::new();
+
+    let mut entries: Vec<PathBuf> = match std::fs::read_dir(dir) {
+        Ok(entries) => entries.flatten().map(|e| e.path()).collect(),
+        Err(e) => {
+            warnings.push(format!(
+                "Cannot read workflows directory {}: {e}",
+                dir.display()
+            ));
+            return (found, warnings);
+        }
+    };
+    entries.sort();
+
+    for path in entries {
+        let parsed = if path.is_dir() {
+            discover_typescript(&path)
+        } else {
+            match path.extension().and_then(|e| e.to_str()) {
+                Some("yml" | "yaml" | "json") => discover_yaml(&path),
+                _ => continue,
+            }
+        };
+        match parsed {
+            Ok(Some(workflow)) if !is_enabled(&workflow.trigger) => {}
+            Ok(Some(workflow)) => {
+                if found.iter().any(|w| w.id == workflow.id) {
+                    warnings.push(format!(
+                        "{}: another workflow already uses the id '{}'",
+                        path.display(),
+                        workflow.id
+                    ));
+                } else {
+                    found.push(workflow);
+                }
+            }
+            Ok(None) => {}
+            Err(e) => warnings.push(format!("{}: {e}", path.display())),
+        }
+    }
+
+    (found, warnings)
+}
+
+fn is_enabled(trigger: &TriggerConfig) -> bool {
+    match trigger {
+        TriggerConfig::Cron { enabled, .. } | TriggerConfig::Webhook { enabled, .. } => *enabled,
+        TriggerConfig::Manual { .. } => false,
+    }
+}
+
+fn discover_yaml(path: &Path) -> Result<Option<TriggeredWorkflow>, String> {
+    let content = std::fs::read_to_string(path).map_err(|e| e.to_string())?;
+    let doc: Value = serde_yaml::from_str(&content).map_err(|e| e.to_string())?;
+
+    let trigger = match (doc.get("trigger"), doc.get("cron")) {
+        (Some(trigger), _) => serde_json::from_value::<TriggerConfig>(trigger.clone())
+            .map_err(|e| format!("invalid trigger: {e}"))?,
+        (None, Some(Value::String(schedule))) => TriggerConfig::Cron {
+            schedule: schedule.clone(),
+            timezone: doc
+                .get("timezone")
+                .and_then(|v| v.as_str())
+                .map(str::to_string),
+            enabled: true,
+        },
+        (None, Some(_)) => return Err("`cron` must be a string".to_string()),
+        (None, None) => return Ok(None),
+    };
+
+    let id = path
+        .file_stem()
+        .and_then(|s| s.to_str())
+        .ok_or("invalid file name")?;
+    Ok(Some(TriggeredWorkflow {
+        id: id.to_string(),
+        path: path.to_path_buf(),
+        kind: WorkflowKind::Yaml,
+        trigger,
+        variables: doc.get("variables").cloned(),
+        missed_runs: doc.get("missed_runs").cloned(),
+    }))
+}
+
+fn discover_typescript(dir: &Path) -> Result<Option<TriggeredWorkflow>, String> {
+    let entry = [
+        dir.join("terminator.ts"),
+        dir.join("src").join("terminator.ts"),
+    ]
+    .into_iter()
+    .find(|p| p.is_file());
+    let Some(entry) = entry else {
+        return Ok(None);
+    };
+    let source = std::fs::read_to_string(&entry).map_err(|e| e.to_string())?;
+    let Some(trigger) = typescript_trigger(&source)? else {
+        return Ok(None);
+    };
+
+    let id = dir
+        .file_name()
+        .and_then(|s| s.to_str())
+        .ok_or("invalid folder name")?;
+    Ok(Some(TriggeredWorkflow {
+        id: id.to_string(),
+        path: dir.to_path_buf(),
+        kind: WorkflowKind::TypeScript,
+        trigger,
+        variables: None,
+        missed_runs: None,
+    }))
+}
+
+/// Pull a literal `trigger: { ... }` object out of TypeScript source
+fn typescript_trigger(source: &str) -> Result<Option<TriggerConfig>, String> {
+    static TRIGGER: OnceLock<Regex> = OnceLock::new();
+    static FIELD: OnceLock<Regex> = OnceLock::new();
+    let trigger = TRIGGER.get_or_init(|| Regex::new(r"\btrigger\s*:\s*\{([^{}]*)\}").unwrap());
+    let field = FIELD.get_or_init(|| {
+        Regex::new(r#"(\w+)\s*:\s*(?:'([^']*)'|"([^"]*)"|`([^`$]*)`|(true|false)\b)"#).unwrap()
+    });
+
+    let Some(body) = trigger.captures(source).map(|c| c[1].to_string()) else {
+        return Ok(None);
+    };
+    let mut object = Map::new();
+    for c in field.captures_iter(&body) {
+        let value = match (c.get(2).or(c.get(3)).or(c.get(4)), c.get(5)) {
+            (Some(s), _) => Value::String(s.as_str().to_string()),
+            (None, Some(b)) => Value::Bool(b.as_str() == "true"),
+            (None, None) => continue,
+        };
+        object.insert(c[1].to_string(), value);
+    }
+    serde_json::from_value(Value::Object(object))
+        .map(Some)
+        .map_err(|e| format!("invalid trigger in terminator.ts: {e}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_discover_trigger_forms() {
+        let dir = tempfile::tempdir().unwrap();
+        let write = |name: &str, content: &str| {
+            let path = dir.path().join(name);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, content).unwrap();
+        };
+        write("hourly.yml", "cron: \"0 30 * * * *\"\nsteps: []\n");
+        write(
+            "invoice.yaml",
+            "trigger: { type: webhook, path: invoices/new }\nvariables:\n  amount: { type: number }\nsteps: []\n",
+        );
+        write(
+            "disabled.yml",
+            "trigger: { type: cron, schedule: \"* * * * *\", enabled: false }\n",
+        );
+        write("manual.yml", "trigger: { type: manual }\nsteps: []\n");
+        write("plain.yml", "steps: []\n");
+        write("broken.yml", "trigger: { type: sometimes }\n");
+        write(
+            "sync/src/terminator.ts",
+            "export default createWorkflow({\n  name: 'Sync',\n  trigger: { type: 'webhook', path: `sync` },\n  steps: [],\n});\n",
+        );
+        write(
+            "untriggered/terminator.ts",
+            "export default createWorkflow({ steps: [] });\n",
+        );
+
+        let (found, warnings) = discover(dir.path());
+        let ids: Vec<_> = found.iter().map(|w| w.id.as_str()).collect();
+        assert_eq!(ids, ["hourly", "invoice", "sync"]);
+        assert_eq!(warnings.len(), 1, "{warnings:?}");
+        assert!(warnings[0].contains("broken.yml"));
+
+        assert!(
+            matches!(&found[0].trigger, TriggerConfig::Cron { schedule, timezone: None, .. } if schedule == "0 30 * * * *")
+        );
+        assert!(
+            matches!(&found[1].trigger, TriggerConfig::Webhook { path: Some(p), .. } if p == "invoices/new")
+        );
+        assert!(found[1].variables.is_some());
+        assert_eq!(found[2].kind, WorkflowKind::TypeScript);
+        assert!(found[2].url().ends_with("sync"));
+    }
+}
@@ -391,22 +391,33 @@ impl TypeScriptWorkflow {
         // Use tokio::process for async stderr streaming with tracing integration
         let runtime = detect_js_runtime();
 
-        // Set up Windows named pipe for event streaming if event_sender is provided
-        #[cfg(windows)]
-        let pipe_server_handle = if event_sender.is_some() {
+        // Set up the event pipe for event streaming if event_sender is provided. Without
+        // MCP_EVENT_PIPE the workflow writes its events to stderr, which is parsed below,
+        // so a pipe that can't be created doesn't fail the run.
+        let event_pipe = if event_sender.is_some() {
             let exec_id = execution_id.unwrap_or("default");
-            let (pipe_tx, mut pipe_rx) = create_event_channel();
-            let pipe_server = EventPipeServer::new(exec_id, pipe_tx);
-            let pipe_name = pipe_server.pipe_name().to_string();
-
-            // Start the pipe server
-            let handle = pipe_server.start().await.map_err(|e| {
-                McpError::internal_error(
-                    format!("Failed to start event pipe server: {e}"),
-                    Some(json!({"error": e.to_string()})),
-                )
-            })?;
-
+            let (pipe_tx, pipe_rx) = create_event_channel();
+            let started = match EventPipeServer::new(exec_id, pipe_tx) {
+                Ok(pipe_server) => {
+                    let pipe_name = pipe_server.pipe_name().to_string();
+                    pipe_server.start().await.map(|handle| (handle, pipe_name))
+                }
+                Err(e) => Err(e),
+            };
+            match started {
+                Ok((handle, pipe_name)) => Some((handle, pipe_name, pipe_rx)),
+                Err(e) => {
+                    warn!(
+                        "Failed to create event pipe ({}), reading workflow events from stderr",
+                        e
+                    );
+                    None
+                }
+            }
+        } else {
+            None
+        };
+        let pipe_server_handle = if let Some((handle, pipe_name, mut pipe_rx)) = event_pipe {
             // Spawn a task to forward pipe events to the event_sender
             let event_sender_clone = event_sender.clone();
             tokio::spawn(async move {
@@ -502,25 +513,33 @@ impl TypeScriptWorkflow {
             None
         };
 
-        #[cfg(not(windows))]
-        let pipe_server_handle: Option<((), String)> = None;
-
-        // Set up Windows named pipe for log streaming
-        #[cfg(windows)]
-        let log_pipe_handle = {
+        // Set up the log pipe for log streaming. Without MCP_LOG_PIPE the workflow logs to
+        // stderr with a level prefix, which is captured below.
+        let log_pipe = {
             let exec_id = execution_id.unwrap_or("default");
-            let (log_tx, mut log_rx) = create_log_channel();
-            let log_server = LogPipeServer::new(exec_id, log_tx);
-            let log_pipe_name = log_server.pipe_name().to_string();
-
-            // Start the log pipe server
-            let handle = log_server.start().await.map_err(|e| {
-                McpError::internal_error(
-                    format!("Failed to start log pipe server: {e}"),
-                    Some(json!({"error": e.to_string()})),
-                )
-            })?;
-
+            let (log_tx, log_rx) = create_log_channel();
+            let started = match LogPipeServer::new(exec_id, log_tx) {
+                Ok(log_server) => {
+                    let log_pipe_name = log_server.pipe_name().to_string();
+                    log_server
+                        .start()
+                        .await
+                        .map(|handle| (handle, log_pipe_name))
+                }
+                Err(e) => Err(e),
+            };
+            match started {
+                Ok((handle, log_pipe_name)) => Some((handle, log_pipe_name, log_rx)),
+                Err(e) => {
+                    warn!(
+                        "Failed to create log pipe ({}), reading workflow logs from stderr",
+                        e
+                    );
+                    None
+                }
+            }
+        };
+        let log_pipe_handle = if let Some((handle, log_pipe_name, mut log_rx)) = log_pipe {
             // Clone for the spawned task
             let captured_logs_for_pipe: Arc<Mutex<Vec<CapturedLogEntry>>> =
                 Arc::new(Mutex::new(Vec::new()));
@@ -546,16 +565,10 @@ impl TypeScriptWorkflow {
             });
 
             Some((handle, log_pipe_name, captured_logs_for_pipe, receiver_task))
+        } else {
+            None
         };
 
-        #[cfg(not(windows))]
-        let log_pipe_handle: Option<(
-            (),
-            String,
-            Arc<Mutex<Vec<CapturedLogEntry>>>,
-            tokio::task::JoinHandle<()>,
-        )> = None;
-
         use std::process::Stdio;
         let mut cmd = match runtime {
             JsRuntime::Bun(ref bun_path) => {
@@ -591,13 +604,11 @@ impl TypeScriptWorkflow {
         };
 
         // Set the pipe path environment variables if we have pipe servers
-        #[cfg(windows)]
         if let Some((_, ref pipe_name)) = pipe_server_handle {
             cmd.env("MCP_EVENT_PIPE", pipe_name);
             info!("Set MCP_EVENT_PIPE={}", pipe_name);
         }
 
-        #[cfg(windows)]
         if let Some((_, ref log_pipe_name, _, _)) = log_pipe_handle {
             cmd.env("MCP_LOG_PIPE", log_pipe_name);
             info!("Set MCP_LOG_PIPE={}", log_pipe_name);
@@ -631,16 +642,8 @@ impl TypeScriptWorkflow {
         let captured_logs: Arc<Mutex<Vec<CapturedLogEntry>>> = Arc::new(Mutex::new(Vec::new()));
         let logs_clone = captured_logs.clone();
 
-        // Determine if events/logs are going through pipe (Windows only)
-        #[cfg(windows)]
-        let events_via_pipe = pipe_server_handle.is_some();
-        #[cfg(not(windows))]
-        let events_via_pipe = false;
-
-        #[cfg(windows)]
+        // Determine if logs are going through the log pipe
         let logs_via_pipe = log_pipe_handle.is_some();
-        #[cfg(not(windows))]
-        let logs_via_pipe = false;
 
         #[allow(clippy::manual_map)]
         let stderr_handle = if let Some(stderr) = stderr {
@@ -649,81 +652,74 @@ impl TypeScriptWorkflow {
                     let reader = BufReader::new(stderr);
                     let mut lines = reader.lines();
                     while let Ok(Some(line)) = lines.next_line().await {
-                        // On non-Windows or when pipe is not available, parse events from stderr
-                        // This is kept as a fallback for backwards compatibility
-                        if !events_via_pipe {
-                            if let Some(event) = try_parse_event(&line) {
-                                debug!(target: "workflow.event", "Received workflow event from stderr: {:?}", event);
-
-                                // Send event through channel if sender is available
-                                if let Some(ref sender) = event_sender {
-                                    if let Err(e) = sender.send(event.clone()) {
-                                        debug!("Failed to send workflow event: {}", e);
-                                    }
+                        // Events also arrive on stderr when the client could not connect to the
+                        // event pipe (or predates it), so always parse them here
+                        if let Some(event) = try_parse_event(&line) {
+                            debug!(target: "workflow.event", "Received workflow event from stderr: {:?}", event);
+
+                            // Send event through channel if sender is available
+                            if let Some(ref sender) = event_sender {
+                                if let Err(e) = sender.send(event.clone()) {
+                                    debug!("Failed to send workflow event: {}", e);
                                 }
+                            }
 
-                                // Log events as structured data for OTEL
-                                match &event {
-                                    WorkflowEvent::Progress { current, total, message, .. } => {
-                                        info!(
-                                            target: "workflow.event",
-                                            event_type = "progress",
-                                            current = %current,
-                                            total = ?total,
-                                            "Progress: {}", message.as_deref().unwrap_or("...")
-                                        );
-                                    }
-                                    WorkflowEvent::StepStarted { step_id, step_name, step_index, total_steps, .. } => {
-                                        info!(
-                                            target: "workflow.event",
-                                            event_type = "step_started",
-                                            step_id = %step_id,
-                                            step_name = %step_name,
-                                            step_index = ?step_index,
-                                            total_steps = ?total_steps,
-                                            "Step started: {}", step_name
-                                        );
-                                    }
-                                    WorkflowEvent::StepCompleted { step_id, step_name, duration, .. } => {
-                                        info!(
-                                            target: "workflow.event",
-                                            event_type = "step_completed",
-                                            step_id = %step_id,
-                                            step_name = %step_name,
-                                            duration_ms = ?duration,
-                                            "Step completed: {}", step_name
-                                        );
-                                    }
-                                    WorkflowEvent::StepFailed { step_id, step_name, error, .. } => {
-                                        error!(
-                                            target: "workflow.event",
-                                            event_type = "step_failed",
-                                            step_id = %step_id,
-                                            step_name = %step_name,
-                                            error = ?error,
-                                            "Step failed: {}", step_name
-                                        );
-                                    }
-                                    WorkflowEvent::Log { level, message, .. } => {
-                                        match level.as_str() {
-                                            "error" => error!(target: "workflow.event", "{}", message),
-                                            "warn" => warn!(target: "workflow.event", "{}", message),
-                                            "debug" => debug!(target: "workflow.event", "{}", message),
-                                            _ => info!(target: "workflow.event", "{}", message),
-                                        }
-                                    }
-                                    _ => {
-                                        debug!(target: "workflow.event", "Event: {:?}", event);
+                            // Log events as structured data for OTEL
+                            match &event {
+                                WorkflowEvent::Progress { current, total, message, .. } => {
+                                    info!(
+                                        target: "workflow.event",
+                                        event_type = "progress",
+                                        current = %current,
+                                        total = ?total,
+                                        "Progress: {}", message.as_deref().unwrap_or("...")
+                                    );
+                                }
+                                WorkflowEvent::StepStarted { step_id, step_name, step_index, total_steps, .. } => {
+                                    info!(
+                                        target: "workflow.event",
+                                        event_type = "step_started",
+                                        step_id = %step_id,
+                                        step_name = %step_name,
+                                        step_index = ?step_index,
+                                        total_steps = ?total_steps,
+                                        "Step started: {}", step_name
+                                    );
+                                }
+                                WorkflowEvent::StepCompleted { step_id, step_name, duration, .. } => {
+                                    info!(
+                                        target: "workflow.event",
+                                        event_type = "step_completed",
+                                        step_id = %step_id,
+                                        step_name = %step_name,
+                                        duration_ms = ?duration,
+                                        "Step completed: {}", step_name
+                                    );
+                                }
+                                WorkflowEvent::StepFailed { step_id, step_name, error, .. } => {
+                                    error!(
+                                        target: "workflow.event",
+                                        event_type = "step_failed",
+                                        step_id = %step_id,
+                                        step_name = %step_name,
+                                        error = ?error,
+                                        "Step failed: {}", step_name
+                                    );
+                                }
+                                WorkflowEvent::Log { level, message, .. } => {
+                                    match level.as_str() {
+                                        "error" => error!(target: "workflow.event", "{}", message),
+                                        "warn" => warn!(target: "workflow.event", "{}", message),
+                                        "debug" => debug!(target: "workflow.event", "{}", message),
+                                        _ => info!(target: "workflow.event", "{}", message),
                                     }
                                 }
-
-                                // Don't process as a regular log line
-                                continue;
+                                _ => {
+                                    debug!(target: "workflow.event", "Event: {:?}", event);
+                                }
                             }
-                        }
 
-                        // Skip MCP event lines when using event pipe
-                        if events_via_pipe && line.trim_start().starts_with("{\"__mcp_event__\":true") {
+                            // Don't process as a regular log line
                             continue;
                         }
 
@@ -887,13 +883,11 @@ impl TypeScriptWorkflow {
                     .collect::<Vec<_>>());
             }
 
-            // Shutdown the pipe servers on error (Windows only)
-            #[cfg(windows)]
+            // Shutdown the pipe servers on error
             if let Some((handle, _)) = pipe_server_handle {
                 handle.shutdown().await;
             }
 
-            #[cfg(windows)]
             if let Some((handle, _, _, _receiver_task)) = log_pipe_handle {
                 handle.shutdown_and_wait().await;
                 // Note: We don't wait for receiver_task on error path - just clean up
@@ -949,8 +943,7 @@ impl TypeScriptWorkflow {
             cleanup_temp_dir(&temp_dir);
         }
 
-        // Shutdown the pipe servers (Windows only)
-        #[cfg(windows)]
+        // Shutdown the pipe servers
         if let Some((handle, _)) = pipe_server_handle {
             handle.shutdown().await;
         }
@@ -969,8 +962,7 @@ impl TypeScriptWorkflow {
             .drain(..)
             .collect();
 
-        // Merge logs from log pipe (Windows only)
-        #[cfg(windows)]
+        // Merge logs from log pipe
         if let Some((handle, _, pipe_logs, receiver_task)) = log_pipe_handle {
             // Wait for the pipe server to finish reading all data from the pipe
             // This is important: shutdown_and_wait() ensures the pipe server has read
@@ -1038,19 +1030,32 @@ impl TypeScriptWorkflow {
         // This automatically skips onError when step control options are present
         Ok(format!(
             r#"
-// Set up logging transport - uses named pipe if MCP_LOG_PIPE is set, otherwise stderr
+// Set up logging transport - uses the log pipe if MCP_LOG_PIPE is set, otherwise stderr
 const fs = require('fs');
+const net = require('net');
 const originalLog = console.log;
 const originalError = console.error;
 
-// Log pipe transport
+// Log pipe transport: a named pipe on Windows, a Unix socket path or tcp://host:port?token=... elsewhere
 let logPipe = null;
 let logPipeReady = false;
 const logPipePath = process.env.MCP_LOG_PIPE;
 
 if (logPipePath) {{
     try {{
-        logPipe = fs.createWriteStream(logPipePath, {{ flags: 'w' }});
+        if (logPipePath.startsWith('tcp://')) {{
+            const url = new URL(logPipePath);
+            logPipe = net.createConnection({{ host: url.hostname, port: Number(url.port) }});
+            // The agent drops TCP clients whose first line isn't the endpoint token
+            const token = url.searchParams.get('token');
+            if (token) {{
+                logPipe.write(token + '\n');
+            }}
+        }} else if (process.platform === 'win32') {{
+            logPipe = fs.createWriteStream(logPipePath, {{ flags: 'w' }});
+        }} else {{
+            logPipe = net.createConnection(logPipePath);
+        }}
         logPipe.on('error', () => {{ logPipe = null; }});
         logPipeReady = true;
     }} catch (e) {{
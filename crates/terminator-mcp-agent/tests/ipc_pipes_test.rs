@@ -0,0 +1,148 @@
+//! Integration tests for the event and log pipes.
+//!
+//! The "child process" is this test binary re-executed with `FAKE_CHILD_ROLE`
+//! set, so the pipes are exercised across a real process boundary the same way
+//! a TypeScript workflow uses them: over the platform transport (Unix socket or
+//! named pipe) and over the TCP loopback fallback.
+
+use std::process::Stdio;
+use std::time::Duration;
+use terminator_mcp_agent::event_pipe::{create_event_channel, EventPipeServer, WorkflowEvent};
+use terminator_mcp_agent::ipc_transport::{self, IpcListener, TransportKind};
+use terminator_mcp_agent::log_pipe::{create_log_channel, LogPipeServer};
+use tokio::io::AsyncWriteExt;
+use tokio::time::timeout;
+
+const EVENT_LINES: &[&str] = &[
+    r#"{"__mcp_event__":true,"type":"step_started","stepId":"s1","stepName":"Open app","timestamp":"2025-01-01T00:00:00Z"}"#,
+    "not an event",
+    r#"{"__mcp_event__":true,"type":"progress","current":1,"total":2,"message":"Halfway","timestamp":"2025-01-01T00:00:00Z"}"#,
+    r#"{"__mcp_event__":true,"type":"step_completed","stepId":"s1","stepName":"Open app","duration":12,"timestamp":"2025-01-01T00:00:00Z"}"#,
+];
+
+const LOG_LINES: &[&str] = &[
+    r#"{"level":"info","message":"starting","timestamp":"2025-01-01T00:00:00Z"}"#,
+    "[INFO] plain stderr-style line",
+    r#"{"level":"error","message":"boom","data":{"code":7},"timestamp":"2025-01-01T00:00:01Z"}"#,
+];
+
+/// Body of the fake child process. Does nothing when run as a normal test.
+#[tokio::test]
+async fn fake_child() {
+    let Ok(role) = std::env::var("FAKE_CHILD_ROLE") else {
+        return;
+    };
+    let (var, lines) = match role.as_str() {
+        "events" => ("MCP_EVENT_PIPE", EVENT_LINES),
+        _ => ("MCP_LOG_PIPE", LOG_LINES),
+    };
+    let endpoint = std::env::var(var).expect("pipe endpoint not set");
+    let mut stream = ipc_transport::connect(&endpoint).await.unwrap();
+    for line in lines {
+        stream.write_all(line.as_bytes()).await.unwrap();
+        stream.write_all(b"\n").await.unwrap();
+    }
+    stream.shutdown().await.unwrap();
+}
+
+async fn run_fake_child(role: &str, var: &str, endpoint: &str) {
+    let status = tokio::process::Command::new(std::env::current_exe().unwrap())
+        .args(["fake_child", "--exact", "--nocapture"])
+        .env("FAKE_CHILD_ROLE", role)
+        .env(var, endpoint)
+        .stdout(Stdio::null())
+        .status()
+        .await
+        .unwrap();
+    assert!(status.success(), "fake child failed: {status}");
+}
+
+#[tokio::test]
+async fn event_pipe_receives_events_from_child_process() {
+    let (tx, mut rx) = create_event_channel();
+    let server = EventPipeServer::new(&format!("it-events-{}", std::process::id()), tx).unwrap();
+    let endpoint = server.pipe_name().to_string();
+    let handle = server.start().await.unwrap();
+
+    run_fake_child("events", "MCP_EVENT_PIPE", &endpoint).await;
+    handle.shutdown_and_wait().await;
+
+    let mut received = Vec::new();
+    while let Ok(Some(event)) = timeout(Duration::from_secs(5), rx.recv()).await {
+        received.push(event);
+    }
+    assert_eq!(received.len(), 3, "got {received:?}");
+    assert!(matches!(received[0], WorkflowEvent::StepStarted { .. }));
+    assert!(
+        matches!(&received[1], WorkflowEvent::Progress { message, .. } if message.as_deref() == Some("Halfway"))
+    );
+    assert!(matches!(received[2], WorkflowEvent::StepCompleted { .. }));
+}
+
+#[tokio::test]
+async fn log_pipe_receives_logs_from_child_process() {
+    let (tx, mut rx) = create_log_channel();
+    let server = LogPipeServer::new(&format!("it-logs-{}", std::process::id()), tx).unwrap();
+    let endpoint = server.pipe_name().to_string();
+    let handle = server.start().await.unwrap();
+
+    run_fake_child("logs", "MCP_LOG_PIPE", &endpoint).await;
+    handle.shutdown_and_wait().await;
+
+    let mut received = Vec::new();
+    while let Ok(Some(entry)) = timeout(Duration::from_secs(5), rx.recv()).await {
+        received.push(entry);
+    }
+    let messages: Vec<_> = received.iter().map(|e| e.message.as_str()).collect();
+    assert_eq!(messages, ["starting", "boom"]);
+    assert_eq!(received[1].level, "error");
+}
+
+#[tokio::test]
+async fn tcp_fallback_carries_events_from_child_process() {
+    let listener = IpcListener::bind_with(TransportKind::Tcp, "it-tcp").unwrap();
+    let endpoint = listener.endpoint().to_string();
+    assert!(endpoint.starts_with(ipc_transport::TCP_ENDPOINT_PREFIX));
+
+    let (tx, mut rx) = create_event_channel();
+    let handle = ipc_transport::serve_lines(
+        listener,
+        "event pipe",
+        terminator_mcp_agent::event_pipe::try_parse_event,
+        tx,
+    );
+
+    run_fake_child("events", "MCP_EVENT_PIPE", &endpoint).await;
+    handle.shutdown_and_wait().await;
+
+    let mut count = 0;
+    while let Ok(Some(_)) = timeout(Duration::from_secs(5), rx.recv()).await {
+        count += 1;
+    }
+    assert_eq!(count, 3);
+}
+
+#[tokio::test]
+async fn concurrent_runs_with_the_same_id_get_separate_endpoints() {
+    let (tx, _rx) = create_log_channel();
+    let first_logs = LogPipeServer::new("default", tx.clone()).unwrap();
+    let second_logs = LogPipeServer::new("default", tx).unwrap();
+    assert_ne!(first_logs.pipe_name(), second_logs.pipe_name());
+
+    let (first_tx, _first_rx) = create_event_channel();
+    let first = EventPipeServer::new("default", first_tx).unwrap();
+    let (tx, mut rx) = create_event_channel();
+    let second = EventPipeServer::new("default", tx).unwrap();
+    assert_ne!(first.pipe_name(), second.pipe_name());
+
+    // The first run finishing leaves the second run's endpoint working
+    drop(first);
+    let endpoint = second.pipe_name().to_string();
+    let handle = second.start().await.unwrap();
+    run_fake_child("events", "MCP_EVENT_PIPE", &endpoint).await;
+    handle.shutdown_and_wait().await;
+    assert!(matches!(
+        timeout(Duration::from_secs(5), rx.recv()).await,
+        Ok(Some(WorkflowEvent::StepStarted { .. }))
+    ));
+}
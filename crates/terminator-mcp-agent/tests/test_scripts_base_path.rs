@@ -103,6 +103,7 @@ steps:
             verbosity: Some("normal".to_string()),
             start_from_step: Some("step1".to_string()),
             end_at_step: Some("step5".to_string()),
+            resume: Some(false),
             follow_fallback: Some(false),
             scripts_base_path: Some("/custom/path".to_string()),
             execute_jumps_at_end: Some(false),
@@ -110,6 +111,9 @@ steps:
             skip_preflight_check: Some(false),
             trace_id: Some("test-trace-123".to_string()),
             execution_id: Some("test-execution-456".to_string()),
+            priority: Some(5),
+            concurrency_key: Some("crm".to_string()),
+            ui: Some(false),
             window_mgmt: Default::default(),
         };
 
@@ -29,12 +29,16 @@ fn test_execute_sequence_args_serialization() {
         start_from_step: None,
         follow_fallback: None,
         end_at_step: None,
+        resume: None,
         troubleshooting: None,
         execute_jumps_at_end: None,
         workflow_id: None,
         skip_preflight_check: None,
         trace_id: None,
         execution_id: None,
+        priority: None,
+        concurrency_key: None,
+        ui: None,
         window_mgmt: Default::default(),
     };
 
@@ -0,0 +1,1526 @@
+//! Compiles a recording into a runnable workflow.
+//!
+//! [`compile`] turns a [`SerializableRecordedWorkflow`] (what
+//! [`RecordedWorkflow::to_json`](crate::RecordedWorkflow::to_json) writes) into
+//! [`McpToolStep`]s, and [`CompiledWorkflow`] renders those as an
+//! `execute_sequence` YAML workflow or as a TypeScript workflow. The compiler only
+//! looks at the recording — no UI automation, no clock — so the same recording
+//! always compiles to the same workflow, on any platform.
+//!
+//! Low-level input (`Mouse`, `Keyboard`, `PendingAction`, ...) is only used to
+//! refine the high-level events derived from it. Events that describe the same
+//! user action are coalesced: the `BrowserClick` and `Click` of one click, a click
+//! on a field and the text typed into it, Alt+Tab cycling, redirect chains, and
+//! navigations or window switches caused by the previous action. The latter become
+//! that step's `expected_ui_changes`, and the next element step is preceded by a
+//! `wait_for_element`, as it is wherever the recording shows the UI taking a while.
+
+use crate::events::{
+    contains_relative_time, is_empty_string, ApplicationSwitchMethod, BrowserClickEvent,
+    BrowserTextInputEvent, FileOpenedEvent, FilePathConfidence, McpToolStep, MouseButton,
+    MouseEventType, Position, SelectorCandidate, SerializableRecordedWorkflow,
+    SerializableUIElement, SerializableWorkflowEvent, TabAction, TabNavigationMethod,
+};
+use crate::Result;
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::collections::{HashMap, HashSet};
+
+pub(crate) const BROWSERS: &[&str] = &["chrome", "msedge", "firefox", "brave", "opera", "vivaldi"];
+
+/// Processes that are part of the Windows shell. Switching to them never needs an
+/// `open_application` step.
+const SHELL_PROCESSES: &[&str] = &[
+    "explorer",
+    "searchhost",
+    "searchapp",
+    "startmenuexperiencehost",
+    "shellexperiencehost",
+    "textinputhost",
+    "lockapp",
+];
+
+/// Options for [`compile`]
+#[derive(Debug, Clone)]
+pub struct CompileOptions {
+    /// A gap of at least this long before an element step is treated as the UI
+    /// catching up, and a `wait_for_element` is inserted before the step
+    pub latency_threshold_ms: u64,
+    /// Minimum timeout of inserted `wait_for_element` steps; longer gaps get twice
+    /// the recorded gap, up to a minute
+    pub wait_timeout_ms: u64,
+    /// Events this close together can describe the same user action
+    pub coalesce_window_ms: u64,
+}
+
+impl Default for CompileOptions {
+    fn default() -> Self {
+        Self {
+            latency_threshold_ms: 1500,
+            wait_timeout_ms: 10_000,
+            coalesce_window_ms: 2000,
+        }
+    }
+}
+
+/// A workflow compiled from a recording
+#[derive(Debug, Clone)]
+pub struct CompiledWorkflow {
+    /// Name of the recording
+    pub name: String,
+    /// Steps for `execute_sequence`, in order
+    pub steps: Vec<McpToolStep>,
+    /// Events that were dropped or could only be compiled to something fragile
+    pub warnings: Vec<String>,
+}
+
+/// Compile a recording into workflow steps. See the [module docs](self).
+pub fn compile(
+    workflow: &SerializableRecordedWorkflow,
+    options: &CompileOptions,
+) -> CompiledWorkflow {
+    let mut compiler = Compiler {
+        options,
+        pending: Vec::new(),
+        warnings: Vec::new(),
+        current_process: None,
+        urls: HashMap::new(),
+    };
+
+    let mut events: Vec<_> = workflow.events.iter().collect();
+    events.sort_by_key(|e| e.timestamp);
+    for recorded in events {
+        compiler.add(recorded.timestamp, &recorded.event);
+    }
+
+    let steps = compiler.steps();
+    CompiledWorkflow {
+        name: workflow.name.clone(),
+        steps,
+        warnings: compiler.warnings,
+    }
+}
+
+/// Rank DOM selector candidates, most robust first.
+///
+/// Test ids and element ids beat ARIA labels, which beat text, classes and
+/// positional XPath/CSS paths. Candidates that need jQuery or look generated
+/// (long digit runs, hashes) are pushed down. Ties keep the
+/// recorded specificity order.
+pub fn rank_selectors(candidates: &[SelectorCandidate]) -> Vec<&SelectorCandidate> {
+    let mut ranked: Vec<&SelectorCandidate> = candidates.iter().collect();
+    ranked.sort_by(|a, b| {
+        robustness(b)
+            .cmp(&robustness(a))
+            .then(b.specificity.cmp(&a.specificity))
+            .then(a.selector.len().cmp(&b.selector.len()))
+    });
+    ranked
+}
+
+fn robustness(candidate: &SelectorCandidate) -> i32 {
+    let selector = candidate.selector.as_str();
+    let mut score = match candidate.selector_type.as_str() {
+        "Id" => 100,
+        "DataAttribute" => {
+            let lower = selector.to_lowercase();
+            if ["data-testid", "data-test", "data-qa", "data-cy"]
+                .iter()
+                .any(|attr| lower.starts_with(&format!("[{attr}=")))
+            {
+                110
+            } else {
+                80
+            }
+        }
+        "AriaLabel" => 90,
+        "Text" => 60,
+        "Class" => 50,
+        "XPath" => 30,
+        "CssPath" => 20,
+        _ => 40,
+    };
+    if candidate.requires_jquery {
+        score -= 100;
+    }
+    if looks_generated(selector) {
+        score -= 80;
+    }
+    if candidate.selector_type == "Class" && selector.matches('.').count() > 3 {
+        score -= 10;
+    }
+    score
+}
+
+/// Whether a selector embeds something a page generates per build or per load
+pub(crate) fn looks_generated(selector: &str) -> bool {
+    let mut digits = 0;
+    let mut max_digits = 0;
+    for c in selector.chars() {
+        if c.is_ascii_digit() {
+            digits += 1;
+            max_digits = max_digits.max(digits);
+        } else {
+            digits = 0;
+        }
+    }
+    max_digits >= 4
+        || selector
+            .split(['#', '.', '-', '_', '"', '[', ']', '=', ' '])
+            .any(|part| {
+                part.len() >= 6
+                    && part.chars().all(|c| c.is_ascii_alphanumeric())
+                    && part.chars().any(|c| c.is_ascii_digit())
+                    && part.chars().any(|c| c.is_ascii_alphabetic())
+            })
+}
+
+impl CompiledWorkflow {
+    /// Step ids for the YAML and TypeScript output, derived from the descriptions
+    pub fn step_ids(&self) -> Vec<String> {
+        let mut seen = HashSet::new();
+        self.steps
+            .iter()
+            .map(|step| {
+                let base = slug(&step.description);
+                let mut id = base.clone();
+                let mut n = 2;
+                while !seen.insert(id.clone()) {
+                    id = format!("{base}_{n}");
+                    n += 1;
+                }
+                id
+            })
+            .collect()
+    }
+
+    /// Render as an `execute_sequence` workflow file
+    pub fn to_yaml(&self) -> Result<String> {
+        let steps = self
+            .steps
+            .iter()
+            .zip(self.step_ids())
+            .map(|(step, id)| YamlStep {
+                tool_name: &step.tool_name,
+                id,
+                arguments: &step.arguments,
+                continue_on_error: step.continue_on_error,
+                delay_ms: step.delay_ms,
+                expected_ui_changes: step.expected_ui_changes.as_deref(),
+            })
+            .collect();
+        let workflow = YamlWorkflow {
+            tool_name: "execute_sequence",
+            arguments: YamlArguments {
+                steps,
+                stop_on_error: true,
+            },
+        };
+        Ok(format!(
+            "# Compiled from recording '{}'\n{}",
+            self.name,
+            serde_yaml::to_string(&workflow)?
+        ))
+    }
+
+    /// Render as a TypeScript workflow for `@mediar-ai/workflow`
+    pub fn to_typescript(&self) -> String {
+        let mut out = String::from(
+            "import { createWorkflow, createStep, z } from '@mediar-ai/workflow';\n\n\
+             export default createWorkflow({\n",
+        );
+        out.push_str(&format!("  name: {},\n", js_string(&self.name)));
+        out.push_str("  input: z.object({}),\n  steps: [\n");
+        for (step, id) in self.steps.iter().zip(self.step_ids()) {
+            out.push_str("    createStep({\n");
+            out.push_str(&format!("      id: {},\n", js_string(&id)));
+            out.push_str(&format!("      name: {},\n", js_string(&step.description)));
+            out.push_str("      execute: async ({ desktop }) => {\n");
+            if let Some(expected) = &step.expected_ui_changes {
+                // As a string literal, so a newline in the recorded text can't end the comment
+                out.push_str(&format!("        // Expected: {}\n", js_string(expected)));
+            }
+            for line in typescript_body(step).lines() {
+                out.push_str(&format!("        {line}\n"));
+            }
+            out.push_str("      },\n    }),\n");
+        }
+        out.push_str("  ],\n});\n");
+        out
+    }
+}
+
+// Field order of the YAML output
+#[derive(Serialize)]
+struct YamlWorkflow<'a> {
+    tool_name: &'static str,
+    arguments: YamlArguments<'a>,
+}
+
+#[derive(Serialize)]
+struct YamlArguments<'a> {
+    steps: Vec<YamlStep<'a>>,
+    stop_on_error: bool,
+}
+
+#[derive(Serialize)]
+struct YamlStep<'a> {
+    tool_name: &'a str,
+    id: String,
+    arguments: &'a Value,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    continue_on_error: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    delay_ms: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    expected_ui_changes: Option<&'a str>,
+}
+
+fn typescript_body(step: &McpToolStep) -> String {
+    let args = &step.arguments;
+    let str_arg = |key: &str| args.get(key).and_then(Value::as_str).unwrap_or_default();
+    let locator = || {
+        js_string(&format!(
+            "process:{} >> {}",
+            str_arg("process"),
+            str_arg("selector")
+        ))
+    };
+    match step.tool_name.as_str() {
+        "open_application" => format!(
+            "desktop.openApplication({});",
+            js_string(str_arg("app_name"))
+        ),
+        "navigate_browser" => format!(
+            "await desktop.navigateBrowser({}, {});",
+            js_string(str_arg("url")),
+            js_string(str_arg("process"))
+        ),
+        "run_command" => format!(
+            "await desktop.run({}, {});",
+            js_string(str_arg("run")),
+            js_string(str_arg("shell"))
+        ),
+        "press_key_global" => format!("await desktop.pressKey({});", js_string(str_arg("key"))),
+        "execute_browser_script" => format!(
+            "await desktop.executeBrowserScript({}, {});",
+            js_string(str_arg("script")),
+            js_string(str_arg("process"))
+        ),
+        "wait_for_element" => format!(
+            "await desktop.locator({}).waitFor({}, {});",
+            locator(),
+            js_string(str_arg("condition")),
+            args.get("timeout_ms")
+                .and_then(Value::as_u64)
+                .unwrap_or(10_000)
+        ),
+        "type_into_element" => format!(
+            "const element = await desktop.locator({}).first(5000);\n\
+             await element.typeText({}, {{ clearBeforeTyping: true }});",
+            locator(),
+            js_string(str_arg("text_to_type"))
+        ),
+        "click_element" if args.get("selector").is_some() => {
+            let method = match str_arg("click_type") {
+                "double" => "doubleClick",
+                "right" => "rightClick",
+                _ => "click",
+            };
+            format!(
+                "const element = await desktop.locator({}).first(5000);\nawait element.{method}();",
+                locator()
+            )
+        }
+        "click_element" => format!(
+            "desktop.clickAtBounds({}, {}, 1, 1);",
+            args.get("x").and_then(Value::as_i64).unwrap_or_default(),
+            args.get("y").and_then(Value::as_i64).unwrap_or_default()
+        ),
+        other => format!("// No TypeScript equivalent for {other}"),
+    }
+}
+
+/// JSON string literal that is also safe in a line comment: JSON leaves the
+/// U+2028/U+2029 line terminators unescaped
+fn js_string(s: &str) -> String {
+    serde_json::to_string(s)
+        .unwrap_or_else(|_| "\"\"".to_string())
+        .replace('\u{2028}', "\\u2028")
+        .replace('\u{2029}', "\\u2029")
+}
+
+fn slug(description: &str) -> String {
+    let mut slug = String::new();
+    for c in description.chars() {
+        if c.is_ascii_alphanumeric() {
+            slug.push(c.to_ascii_lowercase());
+        } else if !slug.ends_with('_') && !slug.is_empty() {
+            slug.push('_');
+        }
+        if slug.len() >= 40 {
+            break;
+        }
+    }
+    let slug = slug.trim_end_matches('_');
+    if slug.is_empty() {
+        "step".to_string()
+    } else {
+        slug.to_string()
+    }
+}
+
+/// An element as the compiler addresses it: a process plus selectors, most
+/// robust first
+#[derive(Debug, Clone, Default)]
+struct Target {
+    process: String,
+    role: String,
+    label: Option<String>,
+    window: Option<String>,
+    selectors: Vec<String>,
+    /// Name based selector that identifies the element across events, kept
+    /// when synthesized selectors are put in front
+    key: Option<String>,
+}
+
+impl Target {
+    fn new(
+        process: String,
+        role: &str,
+        names: &[Option<&str>],
+        texts: &[String],
+        window: Option<&str>,
+    ) -> Self {
+        let role = match role.trim() {
+            r if r.eq_ignore_ascii_case("unknown") => "",
+            r => r,
+        };
+        let name = names
+            .iter()
+            .flatten()
+            .map(|n| n.trim())
+            .find(|n| stable_text(n));
+        let text = texts
+            .iter()
+            .map(|t| t.trim())
+            .find(|t| stable_text(t) && Some(*t) != name);
+
+        let mut selectors = Vec::new();
+        if let Some(name) = name {
+            if plain_text(name) && !role.is_empty() {
+                selectors.push(format!("role:{role} && name:{name}"));
+                selectors.push(format!("name:{name}"));
+            } else {
+                selectors.push(format!("text:{name}"));
+            }
+        }
+        if let Some(text) = text {
+            if plain_text(text) && !role.is_empty() {
+                selectors.push(format!("role:{role} && text:{text}"));
+            } else {
+                selectors.push(format!("text:{text}"));
+            }
+        }
+        if selectors.is_empty() && !role.is_empty() {
+            selectors.push(format!("role:{role}"));
+        }
+
+        Self {
+            process,
+            role: role.to_string(),
+            label: name.or(text).map(str::to_string),
+            key: selectors.first().cloned(),
+            window: window
+                .filter(|w| !is_empty_string(&Some(w.to_string())))
+                .map(str::to_string),
+            selectors,
+        }
+    }
+
+    fn from_ui_element(process: String, element: &SerializableUIElement, extra: &[&str]) -> Self {
+        let mut names = vec![element.name.as_deref()];
+        names.extend(extra.iter().map(|n| Some(*n)));
+        Self::new(
+            process,
+            &element.role,
+            &names,
+            &[],
+            element.window_title.as_deref(),
+        )
+    }
+
+    /// Puts the selectors synthesized at record time (already ranked) first
+    fn with_candidates(mut self, candidates: &[SelectorCandidate]) -> Self {
+        let mut selectors: Vec<String> = candidates.iter().map(|c| c.selector.clone()).collect();
+        for selector in self.selectors.drain(..) {
+            if !selectors.contains(&selector) {
+                selectors.push(selector);
+            }
+        }
+        self.selectors = selectors;
+        self
+    }
+
+    fn same_element(&self, other: &Target) -> bool {
+        self.process == other.process && self.key.is_some() && self.key == other.key
+    }
+
+    /// Only a role to go by, which may match several elements
+    fn is_ambiguous(&self) -> bool {
+        self.selectors.len() == 1 && self.selectors[0].starts_with("role:") && self.label.is_none()
+    }
+
+    fn is_address_bar(&self) -> bool {
+        BROWSERS.contains(&self.process.as_str())
+            && matches!(self.role.as_str(), "Edit" | "ComboBox")
+            && self
+                .label
+                .as_deref()
+                .is_some_and(|l| l.to_lowercase().contains("address"))
+    }
+
+    fn describe(&self) -> String {
+        match &self.label {
+            Some(label) => format!("'{label}'"),
+            None if !self.role.is_empty() => self.role.clone(),
+            None => "element".to_string(),
+        }
+    }
+
+    /// `process`, `selector` and `fallback_selectors` arguments
+    fn arguments(&self) -> serde_json::Map<String, Value> {
+        let mut args = serde_json::Map::new();
+        args.insert("process".into(), json!(self.process));
+        args.insert("selector".into(), json!(self.selectors[0]));
+        // fallback_selectors is comma separated, so selectors with commas can't go in
+        let fallbacks: Vec<&str> = self.selectors[1..]
+            .iter()
+            .map(String::as_str)
+            .filter(|s| !s.contains(','))
+            .collect();
+        if !fallbacks.is_empty() {
+            args.insert("fallback_selectors".into(), json!(fallbacks.join(", ")));
+        }
+        args
+    }
+}
+
+/// Text that identifies an element across runs: not empty, not a timestamp,
+/// counter or clock, and not a whole paragraph
+fn stable_text(text: &str) -> bool {
+    if is_empty_string(&Some(text.to_string()))
+        || text.chars().count() > 100
+        || text.contains('\n')
+        || (text.starts_with('[') && text.ends_with(']'))
+        || contains_relative_time(&text.to_lowercase())
+    {
+        return false;
+    }
+    let alphanumeric = text.chars().filter(|c| c.is_alphanumeric()).count();
+    let digits = text.chars().filter(|c| c.is_ascii_digit()).count();
+    alphanumeric > 0 && digits * 2 <= alphanumeric
+}
+
+/// Text that can go into a `&&` selector without being read as an operator
+pub(crate) fn plain_text(text: &str) -> bool {
+    !text.contains(['&', '|', '!', '(', ')', ',']) && !text.contains(">>")
+}
+
+pub(crate) fn process_name(name: &str) -> String {
+    let lower = name.trim().to_lowercase();
+    lower.strip_suffix(".exe").unwrap_or(&lower).to_string()
+}
+
+pub(crate) fn browser_process(browser: &str) -> String {
+    let lower = process_name(browser);
+    if lower.contains("edge") {
+        "msedge".to_string()
+    } else if lower.contains("chrome") {
+        "chrome".to_string()
+    } else if lower.contains("firefox") {
+        "firefox".to_string()
+    } else {
+        lower
+    }
+}
+
+/// Convert a recorded combination like `Ctrl+Shift+S` to `{Ctrl}{Shift}{S}`
+pub(crate) fn key_sequence(combination: &str) -> Option<String> {
+    let keys: Vec<String> = combination
+        .split('+')
+        .map(str::trim)
+        .filter(|k| !k.is_empty())
+        .map(|key| match key.to_lowercase().as_str() {
+            "ctrl" | "control" => "Ctrl".to_string(),
+            "alt" => "Alt".to_string(),
+            "shift" => "Shift".to_string(),
+            "win" | "windows" | "meta" | "super" => "Win".to_string(),
+            _ if key.chars().count() == 1 => key.to_uppercase(),
+            _ => key.to_string(),
+        })
+        .collect();
+    if keys.is_empty() {
+        None
+    } else {
+        Some(keys.iter().map(|k| format!("{{{k}}}")).collect())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum ClickKind {
+    Left,
+    Double,
+    Right,
+}
+
+#[derive(Debug, Clone)]
+enum Action {
+    OpenApplication {
+        process: String,
+    },
+    OpenFile {
+        process: String,
+        path: String,
+        filename: String,
+    },
+    Navigate {
+        browser: String,
+        url: String,
+    },
+    Click {
+        target: Target,
+        kind: ClickKind,
+        position: Option<Position>,
+        relative: Option<(f32, f32)>,
+        /// Best DOM selector, when the click came with browser context
+        dom_selector: Option<SelectorCandidate>,
+        from_browser: bool,
+    },
+    Type {
+        target: Target,
+        text: String,
+    },
+    Key {
+        process: String,
+        key: String,
+    },
+}
+
+impl Action {
+    fn process(&self) -> &str {
+        match self {
+            Action::OpenApplication { process }
+            | Action::OpenFile { process, .. }
+            | Action::Key { process, .. } => process,
+            Action::Navigate { browser, .. } => browser,
+            Action::Click { target, .. } | Action::Type { target, .. } => &target.process,
+        }
+    }
+
+    /// The element a `wait_for_element` before this action would wait for
+    fn wait_target(&self) -> Option<&Target> {
+        match self {
+            Action::Click { target, .. } | Action::Type { target, .. }
+                if !target.selectors.is_empty() && !target.is_ambiguous() =>
+            {
+                Some(target)
+            }
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Pending {
+    action: Action,
+    start: u64,
+    end: u64,
+    expected: Vec<String>,
+}
+
+struct Compiler<'a> {
+    options: &'a CompileOptions,
+    pending: Vec<Pending>,
+    warnings: Vec<String>,
+    current_process: Option<String>,
+    urls: HashMap<String, String>,
+}
+
+impl Compiler<'_> {
+    fn add(&mut self, ts: u64, event: &SerializableWorkflowEvent) {
+        match event {
+            SerializableWorkflowEvent::ApplicationSwitch(e) => {
+                let Some(process) = e.to_process_name.as_deref().map(process_name) else {
+                    self.warn(format!(
+                        "Skipped switch to '{}': no process name recorded",
+                        e.to_window_and_application_name
+                    ));
+                    return;
+                };
+                self.on_switch(
+                    ts,
+                    process,
+                    &e.to_window_and_application_name,
+                    &e.switch_method,
+                );
+            }
+            SerializableWorkflowEvent::FileOpened(e) => self.on_file_opened(ts, e),
+            SerializableWorkflowEvent::BrowserTabNavigation(e) => {
+                let browser = browser_process(&e.browser);
+                match e.action {
+                    TabAction::Moved | TabAction::Pinned => {}
+                    TabAction::Closed => self.on_tab_key(ts, browser, "{Ctrl}{W}", "Tab closes"),
+                    TabAction::Refreshed => self.on_tab_key(ts, browser, "{F5}", "Page reloads"),
+                    TabAction::Created | TabAction::Switched | TabAction::Duplicated => {
+                        if let Some(url) = e.to_url.as_deref() {
+                            self.on_navigate(ts, browser, url, &e.method);
+                        }
+                    }
+                }
+            }
+            SerializableWorkflowEvent::Click(e) => {
+                let process = e
+                    .process_name
+                    .as_deref()
+                    .map(process_name)
+                    .or_else(|| self.current_process.clone())
+                    .unwrap_or_default();
+                let ui_element = e.metadata.ui_element.as_ref();
+                let target = Target::new(
+                    process,
+                    ui_element.map_or(e.element_role.as_str(), |el| el.role.as_str()),
+                    &[
+                        ui_element.and_then(|el| el.name.as_deref()),
+                        Some(e.element_text.as_str()),
+                    ],
+                    &e.child_text_content,
+                    ui_element.and_then(|el| el.window_title.as_deref()),
+                )
+                .with_candidates(&e.selector_candidates);
+                self.on_click(
+                    ts,
+                    Action::Click {
+                        target,
+                        kind: ClickKind::Left,
+                        position: e.click_position,
+                        relative: e.relative_position,
+                        dom_selector: None,
+                        from_browser: false,
+                    },
+                );
+            }
+            SerializableWorkflowEvent::BrowserClick(e) => self.on_browser_click(ts, e),
+            SerializableWorkflowEvent::Mouse(e) => {
+                let kind = match e.event_type {
+                    MouseEventType::DoubleClick => ClickKind::Double,
+                    MouseEventType::RightClick => ClickKind::Right,
+                    _ => return,
+                };
+                let window = self.options.coalesce_window_ms;
+                if let Some(last) = self.pending.last_mut() {
+                    if let Action::Click {
+                        kind: last_kind,
+                        position,
+                        ..
+                    } = &mut last.action
+                    {
+                        if ts.abs_diff(last.end) <= window && near(*position, Some(e.position)) {
+                            *last_kind = kind;
+                        }
+                    }
+                }
+            }
+            SerializableWorkflowEvent::TextInputCompleted(e) => {
+                let ui_element = e.metadata.ui_element.as_ref();
+                let process = e
+                    .process_name
+                    .as_deref()
+                    .map(process_name)
+                    .or_else(|| self.current_process.clone())
+                    .unwrap_or_default();
+                let target = match ui_element {
+                    Some(el) => Target::from_ui_element(
+                        process,
+                        el,
+                        &[e.field_name.as_deref().unwrap_or("")],
+                    ),
+                    None => Target::new(
+                        process,
+                        &e.field_type,
+                        &[e.field_name.as_deref()],
+                        &[],
+                        None,
+                    ),
+                };
+                self.on_type(
+                    ts.saturating_sub(e.typing_duration_ms),
+                    ts,
+                    target,
+                    &e.text_value,
+                );
+            }
+            SerializableWorkflowEvent::BrowserTextInput(e) => self.on_browser_text_input(ts, e),
+            SerializableWorkflowEvent::Hotkey(e) => {
+                let Some(key) = key_sequence(&e.combination) else {
+                    return;
+                };
+                let Some(process) = e
+                    .process_name
+                    .as_deref()
+                    .map(process_name)
+                    .or_else(|| self.current_process.clone())
+                else {
+                    self.warn(format!("Skipped {}: no process recorded", e.combination));
+                    return;
+                };
+                let lower = key.to_lowercase();
+                // Covered by the ApplicationSwitch / BrowserTabNavigation they cause
+                let switches = ["{alt}{tab}", "{alt}{shift}{tab}", "{win}{tab}"];
+                let tab_keys = [
+                    "{ctrl}{t}",
+                    "{ctrl}{w}",
+                    "{ctrl}{tab}",
+                    "{ctrl}{shift}{tab}",
+                    "{ctrl}{l}",
+                    "{ctrl}{r}",
+                    "{f5}",
+                ];
+                if switches.contains(&lower.as_str())
+                    || (BROWSERS.contains(&process.as_str()) && tab_keys.contains(&lower.as_str()))
+                {
+                    return;
+                }
+                self.current_process = Some(process.clone());
+                self.push(ts, ts, Action::Key { process, key });
+            }
+            SerializableWorkflowEvent::Keyboard(_)
+            | SerializableWorkflowEvent::Clipboard(_)
+            | SerializableWorkflowEvent::TextSelection(_)
+            | SerializableWorkflowEvent::DragDrop(_)
+            | SerializableWorkflowEvent::PendingAction(_) => {}
+        }
+    }
+
+    fn push(&mut self, start: u64, end: u64, action: Action) {
+        self.pending.push(Pending {
+            action,
+            start,
+            end,
+            expected: Vec::new(),
+        });
+    }
+
+    fn warn(&mut self, warning: String) {
+        self.warnings.push(warning);
+    }
+
+    /// The last action, if it ended within the coalesce window before `ts`
+    fn recent(&mut self, ts: u64) -> Option<&mut Pending> {
+        let window = self.options.coalesce_window_ms;
+        self.pending
+            .last_mut()
+            .filter(|last| ts.saturating_sub(last.end) <= window)
+    }
+
+    fn has_opened(&self, process: &str) -> bool {
+        self.pending.iter().any(|p| p.action.process() == process)
+    }
+
+    fn on_switch(
+        &mut self,
+        ts: u64,
+        process: String,
+        window: &str,
+        method: &ApplicationSwitchMethod,
+    ) {
+        if self.current_process.as_deref() == Some(process.as_str()) {
+            return;
+        }
+        self.current_process = Some(process.clone());
+        if SHELL_PROCESSES.contains(&process.as_str()) {
+            return;
+        }
+
+        if *method != ApplicationSwitchMethod::AltTab {
+            if let Some(last) = self.recent(ts) {
+                if matches!(last.action, Action::Click { .. } | Action::Key { .. }) {
+                    last.expected
+                        .push(format!("'{window}' comes to the foreground"));
+                    return;
+                }
+            }
+        }
+        // Alt+Tab cycling: only where the user ended up matters
+        if let Some(last) = self.recent(ts) {
+            if matches!(last.action, Action::OpenApplication { .. }) {
+                self.pending.pop();
+            }
+        }
+        if !self.has_opened(&process) {
+            self.push(ts, ts, Action::OpenApplication { process });
+        }
+    }
+
+    fn on_file_opened(&mut self, ts: u64, e: &FileOpenedEvent) {
+        let Some(path) = e.primary_path.clone() else {
+            self.warn(format!("Skipped opening '{}': path not found", e.filename));
+            return;
+        };
+        if e.confidence == FilePathConfidence::Low {
+            self.warn(format!(
+                "'{}' resolved to {path} with low confidence; check the path",
+                e.filename
+            ));
+        }
+        let process = e
+            .process_name
+            .as_deref()
+            .map(process_name)
+            .unwrap_or_else(|| process_name(&e.application_name));
+        self.current_process = Some(process.clone());
+
+        let window = self.options.coalesce_window_ms + e.search_time_ms as u64;
+        if let Some(last) = self
+            .pending
+            .last_mut()
+            .filter(|last| ts.saturating_sub(last.end) <= window)
+        {
+            match &last.action {
+                Action::OpenApplication { process: opened } if *opened == process => {
+                    last.action = Action::OpenFile {
+                        process,
+                        path,
+                        filename: e.filename.clone(),
+                    };
+                    return;
+                }
+                Action::Click { .. } | Action::Key { .. } => {
+                    last.expected
+                        .push(format!("'{}' opens in {}", e.filename, e.application_name));
+                    return;
+                }
+                _ => {}
+            }
+        }
+        self.push(
+            ts,
+            ts,
+            Action::OpenFile {
+                process,
+                path,
+                filename: e.filename.clone(),
+            },
+        );
+    }
+
+    fn on_tab_key(&mut self, ts: u64, browser: String, key: &str, change: &str) {
+        if let Some(last) = self.recent(ts) {
+            if matches!(&last.action, Action::Click { target, .. } if target.process == browser) {
+                last.expected.push(change.to_string());
+                return;
+            }
+        }
+        self.current_process = Some(browser.clone());
+        self.push(
+            ts,
+            ts,
+            Action::Key {
+                process: browser,
+                key: key.to_string(),
+            },
+        );
+    }
+
+    fn on_navigate(&mut self, ts: u64, browser: String, url: &str, method: &TabNavigationMethod) {
+        let navigable = ["http://", "https://", "file://"]
+            .iter()
+            .any(|scheme| url.starts_with(scheme));
+        if !navigable || self.urls.get(&browser).map(String::as_str) == Some(url) {
+            return;
+        }
+        self.urls.insert(browser.clone(), url.to_string());
+        self.current_process = Some(browser.clone());
+
+        let typed = *method == TabNavigationMethod::AddressBar;
+        if typed {
+            // Clicking and typing into the address bar is what navigate_browser does
+            while let Some(last) = self.pending.last() {
+                match &last.action {
+                    Action::Click { target, .. } | Action::Type { target, .. }
+                        if target.process == browser && target.is_address_bar() =>
+                    {
+                        self.pending.pop();
+                    }
+                    _ => break,
+                }
+            }
+        }
+
+        if let Some(last) = self.recent(ts) {
+            match &mut last.action {
+                // Redirects
+                Action::Navigate { browser: b, url: u } if *b == browser => {
+                    *u = url.to_string();
+                    last.end = ts;
+                    return;
+                }
+                Action::Click { target, .. } if !typed && target.process == browser => {
+                    last.expected.push(format!("Browser navigates to {url}"));
+                    return;
+                }
+                Action::Key { process, .. } if !typed && *process == browser => {
+                    last.expected.push(format!("Browser navigates to {url}"));
+                    return;
+                }
+                // navigate_browser starts the browser itself
+                Action::OpenApplication { process } if *process == browser => {
+                    self.pending.pop();
+                }
+                _ => {}
+            }
+        }
+        self.push(
+            ts,
+            ts,
+            Action::Navigate {
+                browser,
+                url: url.to_string(),
+            },
+        );
+    }
+
+    fn on_click(&mut self, ts: u64, click: Action) {
+        let Action::Click {
+            target: new_target,
+            kind: new_kind,
+            position: new_position,
+            dom_selector: new_dom,
+            from_browser: new_from_browser,
+            ..
+        } = &click
+        else {
+            return;
+        };
+        self.current_process = Some(new_target.process.clone()).filter(|p| !p.is_empty());
+
+        // The recorder reports a click in a browser twice: once with DOM context
+        // and once as a plain Click
+        if let Some(last) = self.recent(ts) {
+            if let Action::Click {
+                target,
+                kind,
+                position,
+                dom_selector,
+                from_browser,
+                ..
+            } = &mut last.action
+            {
+                if *from_browser != *new_from_browser && near(*position, *new_position) {
+                    let keep_new_target =
+                        !*new_from_browser || target.selectors.is_empty() || target.is_ambiguous();
+                    if keep_new_target && !new_target.selectors.is_empty() {
+                        *target = new_target.clone();
+                    }
+                    if *new_kind != ClickKind::Left {
+                        *kind = *new_kind;
+                    }
+                    if dom_selector.is_none() {
+                        *dom_selector = new_dom.clone();
+                    }
+                    *from_browser = *from_browser || *new_from_browser;
+                    last.end = ts;
+                    return;
+                }
+            }
+        }
+        self.push(ts, ts, click);
+    }
+
+    fn on_browser_click(&mut self, ts: u64, e: &BrowserClickEvent) {
+        let candidates = if e.selectors.is_empty() {
+            e.dom_element
+                .as_ref()
+                .map(|dom| dom.selector_candidates.as_slice())
+                .unwrap_or_default()
+        } else {
+            e.selectors.as_slice()
+        };
+        let dom_selector = rank_selectors(candidates)
+            .into_iter()
+            .find(|c| !c.requires_jquery)
+            .cloned();
+
+        let process = e
+            .ui_element
+            .as_ref()
+            .map(|el| process_name(&el.application_name()))
+            .filter(|p| BROWSERS.contains(&p.as_str()))
+            .or_else(|| self.current_process.clone())
+            .unwrap_or_default();
+        let dom = e.dom_element.as_ref();
+        let role = e
+            .ui_element
+            .as_ref()
+            .map(|el| el.role())
+            .unwrap_or_else(|| {
+                dom.map(|d| html_role(&d.tag_name))
+                    .unwrap_or_default()
+                    .to_string()
+            });
+        let name = e.ui_element.as_ref().and_then(|el| el.name());
+        let texts: Vec<String> = dom.and_then(|d| d.inner_text.clone()).into_iter().collect();
+        let target = Target::new(
+            process,
+            &role,
+            &[name.as_deref(), dom.and_then(|d| d.aria_label.as_deref())],
+            &texts,
+            None,
+        );
+        let kind = if e.is_double_click {
+            ClickKind::Double
+        } else if e.button == MouseButton::Right {
+            ClickKind::Right
+        } else {
+            ClickKind::Left
+        };
+        self.on_click(
+            ts,
+            Action::Click {
+                target,
+                kind,
+                position: Some(e.position),
+                relative: None,
+                dom_selector,
+                from_browser: true,
+            },
+        );
+    }
+
+    fn on_type(&mut self, start: u64, end: u64, target: Target, text: &str) {
+        if target.selectors.is_empty() {
+            self.warn(format!(
+                "Skipped typing into an unidentified {} field in {}",
+                target.role, target.process
+            ));
+            return;
+        }
+        self.current_process = Some(target.process.clone());
+        let mut start = start;
+        let mut expected = Vec::new();
+
+        // type_into_element focuses (and if needed clicks) the field itself
+        while let Some(last) = self.pending.last() {
+            let redundant = match &last.action {
+                Action::Click {
+                    target: clicked, ..
+                } => clicked.same_element(&target),
+                Action::Key { process, key } => {
+                    *process == target.process && key.eq_ignore_ascii_case("{Ctrl}{V}")
+                }
+                _ => false,
+            };
+            if !redundant {
+                break;
+            }
+            let last = self.pending.pop().expect("checked above");
+            start = start.min(last.start);
+            expected.extend(last.expected);
+        }
+
+        // The recorder reports the field's final value, so a later report for the
+        // same field (or the same text from the DOM) replaces the earlier one
+        if let Some(last) = self.recent(start) {
+            if let Action::Type {
+                target: typed,
+                text: typed_text,
+            } = &mut last.action
+            {
+                if typed.same_element(&target) || typed_text == text {
+                    if !typed.same_element(&target) && typed.is_ambiguous() {
+                        *typed = target;
+                    }
+                    *typed_text = text.to_string();
+                    last.end = end;
+                    last.expected.extend(expected);
+                    return;
+                }
+            }
+        }
+
+        self.pending.push(Pending {
+            action: Action::Type {
+                target,
+                text: text.to_string(),
+            },
+            start,
+            end,
+            expected,
+        });
+    }
+
+    fn on_browser_text_input(&mut self, ts: u64, e: &BrowserTextInputEvent) {
+        let process = self
+            .current_process
+            .clone()
+            .filter(|p| BROWSERS.contains(&p.as_str()))
+            .unwrap_or_else(|| "chrome".to_string());
+        let dom = e.dom_element.as_ref();
+        let target = Target::new(
+            process,
+            dom.map(|d| html_role(&d.tag_name)).unwrap_or("Edit"),
+            &[dom.and_then(|d| d.aria_label.as_deref())],
+            &[],
+            None,
+        );
+        self.on_type(ts, ts, target, &e.text);
+    }
+
+    fn steps(&mut self) -> Vec<McpToolStep> {
+        let mut steps = Vec::new();
+        let pending = std::mem::take(&mut self.pending);
+        let mut previous: Option<&Pending> = None;
+        for (i, p) in pending.iter().enumerate() {
+            if let (Some(prev), Some(target)) = (previous, p.action.wait_target()) {
+                let gap = p.start.saturating_sub(prev.end);
+                if gap >= self.options.latency_threshold_ms || !prev.expected.is_empty() {
+                    let mut args = target.arguments();
+                    args.remove("fallback_selectors");
+                    args.insert("condition".into(), json!("visible"));
+                    args.insert(
+                        "timeout_ms".into(),
+                        json!(self.options.wait_timeout_ms.max(gap * 2).min(60_000)),
+                    );
+                    args.insert("verify_element_exists".into(), json!(""));
+                    args.insert("verify_element_not_exists".into(), json!(""));
+                    steps.push(step(
+                        "wait_for_element",
+                        Value::Object(args),
+                        format!("Wait for {}", target.describe()),
+                        None,
+                    ));
+                }
+            }
+
+            let mut expected = p.expected.clone();
+            if expected.is_empty() {
+                expected.extend(window_change(p, pending.get(i + 1)));
+            }
+            let expected = (!expected.is_empty()).then(|| expected.join("; "));
+            if let Some(step) = self.action_step(&p.action, expected) {
+                steps.push(step);
+            }
+            previous = Some(p);
+        }
+        steps
+    }
+
+    fn action_step(&mut self, action: &Action, expected: Option<String>) -> Option<McpToolStep> {
+        let verify = |mut args: Value| {
+            args["verify_element_exists"] = json!("");
+            args["verify_element_not_exists"] = json!("");
+            args
+        };
+        let step = match action {
+            Action::OpenApplication { process } => step(
+                "open_application",
+                verify(json!({ "app_name": process })),
+                format!("Open {process}"),
+                expected,
+            ),
+            Action::OpenFile { path, filename, .. } => step(
+                "run_command",
+                json!({
+                    "run": format!("Start-Process -FilePath '{}'", path.replace('\'', "''")),
+                    "shell": "powershell",
+                }),
+                format!("Open {filename}"),
+                expected,
+            ),
+            Action::Navigate { browser, url } => step(
+                "navigate_browser",
+                verify(json!({ "url": url, "process": browser })),
+                format!("Navigate to {url}"),
+                expected,
+            ),
+            Action::Key { process, key } => step(
+                "press_key_global",
+                verify(json!({ "process": process, "key": key })),
+                format!("Press {key}"),
+                expected,
+            ),
+            Action::Type { target, text } => {
+                let mut args = target.arguments();
+                args.insert("text_to_type".into(), json!(text));
+                args.insert("clear_before_typing".into(), json!(true));
+                step(
+                    "type_into_element",
+                    Value::Object(args),
+                    format!("Type into {}", target.describe()),
+                    expected,
+                )
+            }
+            Action::Click {
+                target,
+                kind,
+                position,
+                relative,
+                dom_selector,
+                ..
+            } => {
+                let verb = match kind {
+                    ClickKind::Left => "Click",
+                    ClickKind::Double => "Double-click",
+                    ClickKind::Right => "Right-click",
+                };
+                let description = format!("{verb} {}", target.describe());
+                // A DOM selector beats a bare role
+                let use_selectors = !target.selectors.is_empty()
+                    && (dom_selector.is_none() || !target.is_ambiguous());
+                let mut args = if use_selectors {
+                    if target.is_ambiguous() {
+                        self.warn(format!(
+                            "{description}: only the role is known, '{}' may match several elements",
+                            target.selectors[0]
+                        ));
+                    }
+                    let mut args = target.arguments();
+                    if let Some((x, y)) =
+                        relative.filter(|(x, y)| (x - 0.5).abs() > 0.25 || (y - 0.5).abs() > 0.25)
+                    {
+                        args.insert(
+                            "click_position".into(),
+                            json!({
+                                "x_percentage": (x.clamp(0.0, 1.0) * 100.0).round() as u32,
+                                "y_percentage": (y.clamp(0.0, 1.0) * 100.0).round() as u32,
+                            }),
+                        );
+                    }
+                    args
+                } else if let Some(candidate) = dom_selector {
+                    let description = match &target.label {
+                        Some(_) => description,
+                        None => format!("{verb} {}", candidate.selector),
+                    };
+                    return Some(step(
+                        "execute_browser_script",
+                        verify(json!({
+                            "process": target.process,
+                            "script": dom_click_script(candidate, *kind),
+                        })),
+                        description,
+                        expected,
+                    ));
+                } else if let Some(position) = position {
+                    self.warn(format!(
+                        "{description}: no usable selector, replaying at screen position ({}, {})",
+                        position.x, position.y
+                    ));
+                    let mut args = serde_json::Map::new();
+                    args.insert("x".into(), json!(position.x));
+                    args.insert("y".into(), json!(position.y));
+                    args
+                } else {
+                    self.warn(format!("Skipped {description}: nothing to locate it by"));
+                    return None;
+                };
+                match kind {
+                    ClickKind::Left => {}
+                    ClickKind::Double => {
+                        args.insert("click_type".into(), json!("double"));
+                    }
+                    ClickKind::Right => {
+                        args.insert("click_type".into(), json!("right"));
+                    }
+                }
+                step(
+                    "click_element",
+                    verify(Value::Object(args)),
+                    description,
+                    expected,
+                )
+            }
+        };
+        Some(step)
+    }
+}
+
+fn step(
+    tool_name: &str,
+    arguments: Value,
+    description: String,
+    expected: Option<String>,
+) -> McpToolStep {
+    McpToolStep {
+        tool_name: tool_name.to_string(),
+        arguments,
+        description,
+        timeout_ms: None,
+        continue_on_error: None,
+        delay_ms: None,
+        expected_ui_changes: expected,
+        expected_dom_changes: None,
+    }
+}
+
+/// A new window of the same (non-browser) app showing up right after an action,
+/// like a dialog opened by a click
+fn window_change(p: &Pending, next: Option<&Pending>) -> Option<String> {
+    let window_of = |pending: &Pending| match &pending.action {
+        Action::Click { target, .. } | Action::Type { target, .. } => target.window.clone(),
+        _ => None,
+    };
+    let before = window_of(p)?;
+    let next = next?;
+    let after = window_of(next)?;
+    (next.action.process() == p.action.process()
+        && !BROWSERS.contains(&p.action.process())
+        && before != after)
+        .then(|| format!("Window '{after}' appears"))
+}
+
+pub(crate) fn near(a: Option<Position>, b: Option<Position>) -> bool {
+    match (a, b) {
+        (Some(a), Some(b)) => (a.x - b.x).abs() <= 4 && (a.y - b.y).abs() <= 4,
+        _ => true,
+    }
+}
+
+fn html_role(tag: &str) -> &'static str {
+    match tag.to_lowercase().as_str() {
+        "a" => "Hyperlink",
+        "button" => "Button",
+        "input" | "textarea" => "Edit",
+        "select" => "ComboBox",
+        "img" => "Image",
+        "li" => "ListItem",
+        _ => "",
+    }
+}
+
+fn dom_click_script(candidate: &SelectorCandidate, kind: ClickKind) -> String {
+    let selector = js_string(&candidate.selector);
+    let find = if candidate.selector_type == "XPath" {
+        format!(
+            "document.evaluate({selector}, document, null, XPathResult.FIRST_ORDERED_NODE_TYPE, null).singleNodeValue"
+        )
+    } else {
+        format!("document.querySelector({selector})")
+    };
+    let click = match kind {
+        ClickKind::Left => "el.click();",
+        ClickKind::Double => "el.dispatchEvent(new MouseEvent('dblclick', { bubbles: true }));",
+        ClickKind::Right => "el.dispatchEvent(new MouseEvent('contextmenu', { bubbles: true }));",
+    };
+    format!(
+        "(() => {{ const el = {find}; if (!el) throw new Error('Element not found: ' + {selector}); {click} return 'clicked'; }})()"
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn candidate(selector: &str, selector_type: &str, specificity: u32) -> SelectorCandidate {
+        SelectorCandidate {
+            selector: selector.to_string(),
+            selector_type: selector_type.to_string(),
+            specificity,
+            requires_jquery: selector_type == "Text",
+            score: None,
+        }
+    }
+
+    #[test]
+    fn ranks_stable_selectors_first() {
+        let candidates = vec![
+            candidate("#ember1234", "Id", 100),
+            candidate("[data-testid=\"submit\"]", "DataAttribute", 90),
+            candidate("[aria-label=\"Submit\"]", "AriaLabel", 85),
+            candidate("button:contains(\"Submit\")", "Text", 60),
+            candidate("/html/body/form/button[2]", "XPath", 40),
+        ];
+        let ranked: Vec<&str> = rank_selectors(&candidates)
+            .iter()
+            .map(|c| c.selector.as_str())
+            .collect();
+        assert_eq!(
+            ranked,
+            vec![
+                "[data-testid=\"submit\"]",
+                "[aria-label=\"Submit\"]",
+                "/html/body/form/button[2]",
+                "#ember1234",
+                "button:contains(\"Submit\")",
+            ]
+        );
+    }
+
+    #[test]
+    fn synthesizes_selectors_from_stable_names() {
+        let target = Target::new("notepad".into(), "Button", &[Some("Save")], &[], None);
+        assert_eq!(
+            target.selectors,
+            vec!["role:Button && name:Save", "name:Save"]
+        );
+
+        let target = Target::new(
+            "outlook".into(),
+            "ListItem",
+            &[Some("5 minutes ago")],
+            &["Inbox, 3 unread".into()],
+            None,
+        );
+        assert_eq!(target.selectors, vec!["text:Inbox, 3 unread"]);
+
+        let target = Target::new("calc".into(), "Button", &[Some("12:45")], &[], None);
+        assert!(target.is_ambiguous());
+    }
+
+    #[test]
+    fn recorded_candidates_go_first_but_keep_element_identity() {
+        let field = Target::new("app".into(), "Edit", &[Some("Email")], &[], None);
+        let clicked = field.clone().with_candidates(&[
+            candidate("nativeid:EmailBox", "AutomationId", 2),
+            candidate("role:Edit && name:Email", "RoleName", 2),
+        ]);
+        assert_eq!(
+            clicked.selectors,
+            vec!["nativeid:EmailBox", "role:Edit && name:Email", "name:Email"]
+        );
+        assert!(clicked.same_element(&field));
+    }
+
+    #[test]
+    fn typescript_comments_stay_on_one_line() {
+        let workflow = CompiledWorkflow {
+            name: "notes".into(),
+            steps: vec![step(
+                "press_key",
+                json!({"key": "{Enter}"}),
+                "Press Enter".into(),
+                Some("Saved\n});\nrequire('fs')\u{2028}x".into()),
+            )],
+            warnings: Vec::new(),
+        };
+        let ts = workflow.to_typescript();
+        let comment = ts
+            .lines()
+            .find(|line| line.trim_start().starts_with("// Expected:"))
+            .unwrap();
+        assert_eq!(
+            comment.trim(),
+            r#"// Expected: "Saved\n});\nrequire('fs')\u2028x""#
+        );
+    }
+
+    #[test]
+    fn converts_hotkeys_to_key_sequences() {
+        assert_eq!(
+            key_sequence("Ctrl+Shift+s").as_deref(),
+            Some("{Ctrl}{Shift}{S}")
+        );
+        assert_eq!(key_sequence("Enter").as_deref(), Some("{Enter}"));
+        assert_eq!(key_sequence(""), None);
+    }
+}
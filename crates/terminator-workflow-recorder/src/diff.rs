@@ -0,0 +1,631 @@
+//! Compares a recording with a reference recording of the same task.
+//!
+//! [`diff_recordings`] cleans both recordings with [`postprocess`], reduces them
+//! to their high-level steps (clicks, typed text, app switches, navigation,
+//! hotkeys, drags, opened files) and aligns the two step sequences by edit
+//! distance, in memory linear in the number of steps. Steps match when they do the same thing to the same target, e.g.
+//! typing into the "Customer" field. The result lists steps of the reference that
+//! are missing, extra steps, different values typed into the same field and
+//! different selectors for the same element, and renders as Markdown for a
+//! trainee or a bug report.
+//!
+//! Like [`compile`](crate::compile) this only looks at the recordings, so it is
+//! deterministic and runs on any platform.
+
+use crate::compiler::{process_name, rank_selectors};
+use crate::events::{
+    SerializableRecordedWorkflow, SerializableUIElement, SerializableWorkflowEvent,
+};
+use crate::postprocess::{display_name, host, postprocess, PostProcessOptions};
+use serde::{Deserialize, Serialize};
+
+/// What a step does
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StepAction {
+    Click,
+    Type,
+    SwitchApp,
+    Navigate,
+    PressKey,
+    Drag,
+    OpenFile,
+}
+
+/// A high-level step of a recording
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DiffStep {
+    /// Position of the event in its recording
+    pub index: usize,
+    /// Name of the intent the step belongs to, see [`postprocess`]
+    pub intent: String,
+    pub action: StepAction,
+    /// What the step acts on: the element or field name, application, site,
+    /// key combination or file
+    pub target: String,
+    /// Typed text or the URL navigated to
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub value: Option<String>,
+    /// Selector of the element, the best recorded candidate first
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub selector: Option<String>,
+}
+
+impl DiffStep {
+    /// Steps with the same key do the same thing and can be aligned
+    fn key(&self) -> (StepAction, String) {
+        (self.action, self.target.trim().to_lowercase())
+    }
+}
+
+impl std::fmt::Display for DiffStep {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let target = &self.target;
+        match (self.action, &self.value) {
+            (StepAction::Type, Some(value)) => write!(f, "Type \"{value}\" into {target}"),
+            (StepAction::Type, None) => write!(f, "Type into {target}"),
+            (StepAction::Click, _) => write!(f, "Click {target}"),
+            (StepAction::SwitchApp, _) => write!(f, "Switch to {target}"),
+            (StepAction::Navigate, Some(url)) => write!(f, "Go to {url}"),
+            (StepAction::Navigate, None) => write!(f, "Go to {target}"),
+            (StepAction::PressKey, _) => write!(f, "Press {target}"),
+            (StepAction::Drag, _) => write!(f, "Drag {target}"),
+            (StepAction::OpenFile, _) => write!(f, "Open {target}"),
+        }
+    }
+}
+
+/// How an aligned step differs
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Difference {
+    /// The reference has the step, the recording does not
+    Missing,
+    /// The recording has a step the reference does not
+    Extra,
+    /// Different text typed into the same field, or a different URL on the same site
+    Value,
+    /// The same element recorded with a different selector
+    Selector,
+}
+
+impl Difference {
+    fn describe(self, count: usize) -> String {
+        let (one, many) = match self {
+            Difference::Missing => ("missing step", "missing steps"),
+            Difference::Extra => ("extra step", "extra steps"),
+            Difference::Value => ("different value", "different values"),
+            Difference::Selector => ("different selector", "different selectors"),
+        };
+        format!("{count} {}", if count == 1 { one } else { many })
+    }
+}
+
+/// A step of the reference, of the recording, or of both
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AlignedStep {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reference: Option<DiffStep>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub actual: Option<DiffStep>,
+    /// Empty when the steps match
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub differences: Vec<Difference>,
+}
+
+/// Result of [`diff_recordings`]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RecordingDiff {
+    /// Name of the reference recording
+    pub reference: String,
+    /// Name of the compared recording
+    pub actual: String,
+    /// Steps of both recordings, aligned
+    pub steps: Vec<AlignedStep>,
+}
+
+impl RecordingDiff {
+    /// Whether both recordings have the same steps with the same values and selectors
+    pub fn is_identical(&self) -> bool {
+        self.steps.iter().all(|s| s.differences.is_empty())
+    }
+
+    /// Number of aligned steps with this difference
+    pub fn count(&self, difference: Difference) -> usize {
+        self.steps
+            .iter()
+            .filter(|s| s.differences.contains(&difference))
+            .count()
+    }
+
+    /// The differences with the recordings' steps as a Markdown table
+    pub fn to_markdown(&self) -> String {
+        let mut out = format!(
+            "# {} compared with {}\n\n",
+            escape(&self.actual),
+            escape(&self.reference)
+        );
+        let matching = self
+            .steps
+            .iter()
+            .filter(|s| s.differences.is_empty())
+            .count();
+        let mut summary: Vec<String> = [
+            Difference::Missing,
+            Difference::Extra,
+            Difference::Value,
+            Difference::Selector,
+        ]
+        .into_iter()
+        .filter_map(|d| {
+            Some(self.count(d))
+                .filter(|&n| n > 0)
+                .map(|n| d.describe(n))
+        })
+        .collect();
+        summary.push(format!(
+            "{matching} matching step{}",
+            if matching == 1 { "" } else { "s" }
+        ));
+        out.push_str(&format!("{}.\n\n", summary.join(", ")));
+        if self.steps.is_empty() {
+            return out;
+        }
+
+        out.push_str("| # | Intent | Reference | Actual | Difference |\n");
+        out.push_str("|---|--------|-----------|--------|------------|\n");
+        for (i, step) in self.steps.iter().enumerate() {
+            let intent = step
+                .reference
+                .as_ref()
+                .or(step.actual.as_ref())
+                .map(|s| s.intent.as_str())
+                .unwrap_or_default();
+            let cell = |s: &Option<DiffStep>| {
+                s.as_ref()
+                    .map(|s| escape(&s.to_string()))
+                    .unwrap_or_default()
+            };
+            let differences: Vec<String> = step
+                .differences
+                .iter()
+                .map(|d| match d {
+                    Difference::Missing => "**missing**".to_string(),
+                    Difference::Extra => "**extra**".to_string(),
+                    Difference::Value => "different value".to_string(),
+                    Difference::Selector => {
+                        let selector = |s: &Option<DiffStep>| {
+                            s.as_ref()
+                                .and_then(|s| s.selector.as_deref())
+                                .map(|s| escape(&s.replace('`', "'")))
+                                .unwrap_or_default()
+                        };
+                        format!(
+                            "selector `{}` → `{}`",
+                            selector(&step.reference),
+                            selector(&step.actual)
+                        )
+                    }
+                })
+                .collect();
+            out.push_str(&format!(
+                "| {} | {} | {} | {} | {} |\n",
+                i + 1,
+                escape(intent),
+                cell(&step.reference),
+                cell(&step.actual),
+                differences.join("; ")
+            ));
+        }
+        out
+    }
+}
+
+/// Compare `actual` with `reference`. See the [module docs](self).
+pub fn diff_recordings(
+    reference: &SerializableRecordedWorkflow,
+    actual: &SerializableRecordedWorkflow,
+    options: &PostProcessOptions,
+) -> RecordingDiff {
+    let expected = steps(reference, options);
+    let actual_steps = steps(actual, options);
+    RecordingDiff {
+        reference: reference.name.clone(),
+        actual: actual.name.clone(),
+        steps: align(expected, actual_steps),
+    }
+}
+
+/// The high-level steps of a cleaned recording
+fn steps(workflow: &SerializableRecordedWorkflow, options: &PostProcessOptions) -> Vec<DiffStep> {
+    let processed = postprocess(workflow, options);
+    processed
+        .events
+        .iter()
+        .filter_map(|e| {
+            let (action, target, value, selector) = describe(&e.event)?;
+            Some(DiffStep {
+                index: e.index,
+                intent: processed.intents[e.intent].name.clone(),
+                action,
+                target,
+                value,
+                selector,
+            })
+        })
+        .collect()
+}
+
+type Description = (StepAction, String, Option<String>, Option<String>);
+
+fn describe(event: &SerializableWorkflowEvent) -> Option<Description> {
+    use SerializableWorkflowEvent as E;
+    let description = match event {
+        E::Click(c) => {
+            let element = c.metadata.ui_element.as_ref();
+            let target = element
+                .and_then(|el| el.name.clone())
+                .filter(|name| !name.trim().is_empty())
+                .unwrap_or_else(|| c.element_text.clone());
+            let selector = c
+                .selector_candidates
+                .first()
+                .map(|s| s.selector.clone())
+                .or_else(|| element.and_then(element_selector));
+            (StepAction::Click, target, None, selector)
+        }
+        E::BrowserClick(b) => {
+            let dom = b.dom_element.as_ref();
+            let target = dom
+                .and_then(|d| d.aria_label.clone().or_else(|| d.inner_text.clone()))
+                .map(|text| text.trim().chars().take(60).collect::<String>())
+                .filter(|text| !text.is_empty())
+                .or_else(|| dom.map(|d| d.css_selector.clone()))
+                .unwrap_or_else(|| format!("{},{}", b.position.x, b.position.y));
+            let selector = rank_selectors(&b.selectors)
+                .first()
+                .map(|s| s.selector.clone())
+                .or_else(|| dom.map(|d| d.css_selector.clone()));
+            (StepAction::Click, target, None, selector)
+        }
+        E::TextInputCompleted(t) => {
+            let element = t.metadata.ui_element.as_ref();
+            let target = t
+                .field_name
+                .clone()
+                .or_else(|| element.and_then(|el| el.name.clone()))
+                .filter(|name| !name.trim().is_empty())
+                .unwrap_or_else(|| t.field_type.clone());
+            let selector = element.and_then(element_selector);
+            (
+                StepAction::Type,
+                target,
+                Some(t.text_value.clone()),
+                selector,
+            )
+        }
+        E::BrowserTextInput(t) => {
+            let target = t
+                .dom_element
+                .as_ref()
+                .and_then(|d| d.aria_label.clone().or_else(|| d.id.clone()))
+                .filter(|label| !label.trim().is_empty())
+                .unwrap_or_else(|| t.selector.clone());
+            (
+                StepAction::Type,
+                target,
+                Some(t.text.clone()),
+                Some(t.selector.clone()),
+            )
+        }
+        E::ApplicationSwitch(s) => {
+            let target = s
+                .to_process_name
+                .as_deref()
+                .map(|p| display_name(&process_name(p)))
+                .filter(|name| !name.is_empty())
+                .unwrap_or_else(|| s.to_window_and_application_name.clone());
+            (StepAction::SwitchApp, target, None, None)
+        }
+        E::BrowserTabNavigation(n) => {
+            let url = n.to_url.clone();
+            let target = url
+                .as_deref()
+                .and_then(host)
+                .or_else(|| n.to_title.clone())?;
+            (StepAction::Navigate, target, url, None)
+        }
+        E::Hotkey(h) => (StepAction::PressKey, h.combination.clone(), None, None),
+        E::DragDrop(d) => {
+            let target = d
+                .source_element
+                .as_ref()
+                .and_then(|el| el.name.clone())
+                .filter(|name| !name.trim().is_empty())
+                .unwrap_or_else(|| format!("{},{}", d.start_position.x, d.start_position.y));
+            let selector = d.source_element.as_ref().and_then(element_selector);
+            (StepAction::Drag, target, None, selector)
+        }
+        E::FileOpened(f) => (StepAction::OpenFile, f.filename.clone(), None, None),
+        _ => return None,
+    };
+    Some(description)
+}
+
+fn element_selector(element: &SerializableUIElement) -> Option<String> {
+    let name = element.name.as_deref()?.trim();
+    (!name.is_empty()).then(|| format!("role:{} && name:{name}", element.role))
+}
+
+/// Align two step sequences with the fewest missing and extra steps. Steps only
+/// align with a step of the same [`DiffStep::key`].
+fn align(reference: Vec<DiffStep>, actual: Vec<DiffStep>) -> Vec<AlignedStep> {
+    let reference_keys: Vec<_> = reference.iter().map(DiffStep::key).collect();
+    let actual_keys: Vec<_> = actual.iter().map(DiffStep::key).collect();
+    let script = edit_script(&reference_keys, &actual_keys);
+
+    let mut reference = reference.into_iter();
+    let mut actual = actual.into_iter();
+    script
+        .into_iter()
+        .map(|edit| match edit {
+            Edit::Keep => {
+                let (r, a) = (reference.next().unwrap(), actual.next().unwrap());
+                let mut differences = Vec::new();
+                if r.value != a.value {
+                    differences.push(Difference::Value);
+                }
+                if r.selector.is_some() && a.selector.is_some() && r.selector != a.selector {
+                    differences.push(Difference::Selector);
+                }
+                AlignedStep {
+                    reference: Some(r),
+                    actual: Some(a),
+                    differences,
+                }
+            }
+            Edit::Missing => AlignedStep {
+                reference: reference.next(),
+                actual: None,
+                differences: vec![Difference::Missing],
+            },
+            Edit::Extra => AlignedStep {
+                reference: None,
+                actual: actual.next(),
+                differences: vec![Difference::Extra],
+            },
+        })
+        .collect()
+}
+
+/// One step of an edit script turning `a` into `b`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Edit {
+    /// The next items of `a` and `b` are equal
+    Keep,
+    /// The next item of `a` is deleted
+    Missing,
+    /// The next item of `b` is inserted
+    Extra,
+}
+
+/// Shortest edit script of deletions and insertions turning `a` into `b`.
+///
+/// Uses Myers' divide-and-conquer variant, which needs O(n + m) memory and
+/// O((n + m) * D) time for D edits, so long recordings that differ in a few
+/// steps stay cheap.
+fn edit_script<T: PartialEq>(a: &[T], b: &[T]) -> Vec<Edit> {
+    let mut script = Vec::with_capacity(a.len() + b.len());
+    let size = (a.len() + b.len()).div_ceil(2) + 1;
+    let mut forward = Diagonals::new(size);
+    let mut backward = Diagonals::new(size);
+    conquer(a, b, &mut forward, &mut backward, &mut script);
+    script
+}
+
+fn conquer<T: PartialEq>(
+    a: &[T],
+    b: &[T],
+    forward: &mut Diagonals,
+    backward: &mut Diagonals,
+    script: &mut Vec<Edit>,
+) {
+    let prefix = common_prefix(a, b);
+    script.extend(std::iter::repeat_n(Edit::Keep, prefix));
+    let (a, b) = (&a[prefix..], &b[prefix..]);
+    let suffix = common_suffix(a, b);
+    let (a, b) = (&a[..a.len() - suffix], &b[..b.len() - suffix]);
+
+    if a.is_empty() {
+        script.extend(std::iter::repeat_n(Edit::Extra, b.len()));
+    } else if b.is_empty() {
+        script.extend(std::iter::repeat_n(Edit::Missing, a.len()));
+    } else {
+        // Both ends differ, so there are at least two edits and the split
+        // point lies strictly inside; each half is a smaller problem
+        let (x, y) = middle_snake(a, b, forward, backward);
+        conquer(&a[..x], &b[..y], forward, backward, script);
+        conquer(&a[x..], &b[y..], forward, backward, script);
+    }
+    script.extend(std::iter::repeat_n(Edit::Keep, suffix));
+}
+
+/// A point on an optimal edit path through the middle, found by searching
+/// from both ends at once
+fn middle_snake<T: PartialEq>(
+    a: &[T],
+    b: &[T],
+    forward: &mut Diagonals,
+    backward: &mut Diagonals,
+) -> (usize, usize) {
+    let (n, m) = (a.len(), b.len());
+    let delta = n as isize - m as isize;
+    let odd = delta & 1 == 1;
+    forward.set(1, 0);
+    backward.set(1, 0);
+    let max_d = (n + m).div_ceil(2) as isize;
+    for d in 0..=max_d {
+        for k in (-d..=d).rev().step_by(2) {
+            let mut x = if k == -d || (k != d && forward.get(k - 1) < forward.get(k + 1)) {
+                forward.get(k + 1)
+            } else {
+                forward.get(k - 1) + 1
+            };
+            let y = (x as isize - k) as usize;
+            let (x0, y0) = (x, y);
+            if x < n && y < m {
+                x += common_prefix(&a[x..], &b[y..]);
+            }
+            forward.set(k, x);
+            if odd && (k - delta).abs() < d && x + backward.get(delta - k) >= n {
+                return (x0, y0);
+            }
+        }
+        for k in (-d..=d).rev().step_by(2) {
+            let mut x = if k == -d || (k != d && backward.get(k - 1) < backward.get(k + 1)) {
+                backward.get(k + 1)
+            } else {
+                backward.get(k - 1) + 1
+            };
+            let mut y = (x as isize - k) as usize;
+            if x < n && y < m {
+                let snake = common_suffix(&a[..n - x], &b[..m - y]);
+                x += snake;
+                y += snake;
+            }
+            backward.set(k, x);
+            if !odd && (k - delta).abs() <= d && x + forward.get(delta - k) >= n {
+                return (n - x, m - y);
+            }
+        }
+    }
+    unreachable!("the forward and backward searches always meet")
+}
+
+/// Furthest x reached on each diagonal k = x - y, for k in -size..=size
+struct Diagonals {
+    size: isize,
+    x: Vec<usize>,
+}
+
+impl Diagonals {
+    fn new(size: usize) -> Self {
+        Self {
+            size: size as isize,
+            x: vec![0; 2 * size + 1],
+        }
+    }
+
+    fn get(&self, k: isize) -> usize {
+        self.x[(k + self.size) as usize]
+    }
+
+    fn set(&mut self, k: isize, x: usize) {
+        self.x[(k + self.size) as usize] = x;
+    }
+}
+
+fn common_prefix<T: PartialEq>(a: &[T], b: &[T]) -> usize {
+    a.iter().zip(b).take_while(|(x, y)| x == y).count()
+}
+
+fn common_suffix<T: PartialEq>(a: &[T], b: &[T]) -> usize {
+    a.iter()
+        .rev()
+        .zip(b.iter().rev())
+        .take_while(|(x, y)| x == y)
+        .count()
+}
+
+/// Keeps text from breaking out of a Markdown table cell
+fn escape(text: &str) -> String {
+    text.replace('|', "\\|").replace(['\r', '\n'], " ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn step(action: StepAction, target: &str, value: Option<&str>) -> DiffStep {
+        DiffStep {
+            index: 0,
+            intent: "Fill form".to_string(),
+            action,
+            target: target.to_string(),
+            value: value.map(str::to_string),
+            selector: None,
+        }
+    }
+
+    #[test]
+    fn aligns_by_action_and_target() {
+        let reference = vec![
+            step(StepAction::Click, "New", None),
+            step(StepAction::Type, "Customer", Some("ACME")),
+            step(StepAction::Type, "Amount", Some("10")),
+            step(StepAction::Click, "Save", None),
+        ];
+        let actual = vec![
+            step(StepAction::Click, "new", None),
+            step(StepAction::Type, "Customer", Some("Contoso")),
+            step(StepAction::Click, "Help", None),
+            step(StepAction::Click, "Save", None),
+        ];
+        let differences: Vec<_> = align(reference, actual)
+            .into_iter()
+            .map(|s| s.differences)
+            .collect();
+        assert_eq!(
+            differences,
+            vec![
+                vec![],
+                vec![Difference::Value],
+                vec![Difference::Missing],
+                vec![Difference::Extra],
+                vec![],
+            ]
+        );
+    }
+
+    #[test]
+    fn edit_script_is_minimal_on_long_recordings() {
+        assert_eq!(
+            edit_script(&[1, 2, 3, 4, 5], &[1, 3, 4, 6, 5]),
+            vec![
+                Edit::Keep,
+                Edit::Missing,
+                Edit::Keep,
+                Edit::Keep,
+                Edit::Extra,
+                Edit::Keep,
+            ]
+        );
+        assert_eq!(edit_script(&[1, 2], &[3]).len(), 3);
+        assert!(edit_script::<u8>(&[], &[]).is_empty());
+
+        let reference: Vec<usize> = (0..100_000).collect();
+        let mut actual = reference.clone();
+        actual.remove(10);
+        actual[50_000] = 7;
+        let edits = edit_script(&reference, &actual)
+            .into_iter()
+            .filter(|edit| *edit != Edit::Keep)
+            .count();
+        assert_eq!(edits, 3);
+    }
+
+    #[test]
+    fn markdown_cells_cannot_break_the_table() {
+        assert_eq!(escape("a|b\nc"), "a\\|b c");
+        let diff = RecordingDiff {
+            reference: "Golden".to_string(),
+            actual: "Run".to_string(),
+            steps: Vec::new(),
+        };
+        assert!(diff.is_identical());
+        assert_eq!(
+            diff.to_markdown(),
+            "# Run compared with Golden\n\n0 matching steps.\n\n"
+        );
+    }
+}
@@ -33,6 +33,18 @@ pub enum WorkflowRecorderError {
     #[error("JSON error: {0}")]
     JsonError(#[from] serde_json::Error),
 
+    /// Error when serializing a compiled workflow to YAML
+    #[error("YAML error: {0}")]
+    YamlError(#[from] serde_yaml::Error),
+
+    /// Error when replaying a recording
+    #[error("Playback error: {0}")]
+    PlaybackError(String),
+
+    /// Error when encoding or decoding a CBOR recording
+    #[error("CBOR error: {0}")]
+    CborError(String),
+
     /// I/O error
     #[error("I/O error: {0}")]
     IoError(#[from] std::io::Error),
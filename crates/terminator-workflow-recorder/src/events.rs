@@ -1,3 +1,5 @@
+use crate::format::LoadReport;
+use schemars::JsonSchema;
 use serde::{Deserialize, Deserializer, Serialize};
 use std::collections::HashSet;
 use std::sync::LazyLock;
@@ -36,7 +38,7 @@ static NULL_LIKE_VALUES: LazyLock<HashSet<&'static str>> = LazyLock::new(|| {
 });
 
 // Helper function to filter empty strings and null-like values for serde skip_serializing_if
-fn is_empty_string(s: &Option<String>) -> bool {
+pub(crate) fn is_empty_string(s: &Option<String>) -> bool {
     match s {
         Some(s) => {
             // Fast path for completely empty strings
@@ -66,7 +68,7 @@ fn is_empty_string(s: &Option<String>) -> bool {
 
 /// Check if text contains relative time patterns that would make selectors unstable
 /// Examples: "3 hours ago", "5 minutes ago", "yesterday", "just now"
-fn contains_relative_time(text: &str) -> bool {
+pub(crate) fn contains_relative_time(text: &str) -> bool {
     // Common relative time patterns (text is already lowercase)
     text.contains(" ago")
         || text.contains("just now")
@@ -81,7 +83,7 @@ fn contains_relative_time(text: &str) -> bool {
 }
 
 /// Represents a position on the screen
-#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, JsonSchema)]
 pub struct Position {
     pub x: i32,
     pub y: i32,
@@ -97,7 +99,7 @@ pub struct Rect {
 }
 
 /// Represents the type of mouse button
-#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, JsonSchema, PartialEq)]
 pub enum MouseButton {
     Left,
     Right,
@@ -105,7 +107,7 @@ pub enum MouseButton {
 }
 
 /// Represents the type of mouse event
-#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, JsonSchema, PartialEq, Eq)]
 pub enum MouseEventType {
     Click,
     DoubleClick,
@@ -177,7 +179,7 @@ pub struct MouseEvent {
 }
 
 /// Represents clipboard actions
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, PartialEq)]
 pub enum ClipboardAction {
     Copy,
     Cut,
@@ -233,7 +235,7 @@ pub struct TextSelectionEvent {
 }
 
 /// Represents how text was selected
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub enum SelectionMethod {
     MouseDrag,
     DoubleClick,      // Word selection
@@ -296,7 +298,7 @@ pub struct HotkeyEvent {
 }
 
 /// Represents the type of button interaction
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, PartialEq)]
 pub enum ButtonInteractionType {
     /// Simple button click
     Click,
@@ -311,7 +313,7 @@ pub enum ButtonInteractionType {
 }
 
 /// Type of pending action being processed
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, PartialEq)]
 pub enum PendingActionType {
     /// A click action is being processed
     Click,
@@ -323,7 +325,7 @@ pub enum PendingActionType {
 
 /// Represents a pending action that is being processed
 /// Emitted immediately when an action is detected, before UI element capture completes
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct PendingActionEvent {
     /// The type of action being processed
     pub action_type: PendingActionType,
@@ -380,15 +382,20 @@ pub struct ClickEvent {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub page_url: Option<String>,
 
+    /// Selectors synthesized from a snapshot of the window, most robust first
+    #[serde(skip_serializing_if = "Vec::is_empty", default)]
+    pub selector_candidates: Vec<SelectorCandidate>,
+
     /// Event metadata with UI element context
     pub metadata: EventMetadata,
 }
 
 /// Browser-specific click event with DOM information
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct BrowserClickEvent {
     /// UI automation element info (Windows UI tree)
     #[serde(default, deserialize_with = "deserialize_optional_ui_element")]
+    #[schemars(with = "Option<serde_json::Value>")]
     pub ui_element: Option<UIElement>,
 
     /// DOM element information from browser
@@ -420,7 +427,7 @@ pub struct BrowserClickEvent {
 }
 
 /// DOM element information captured from browser
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct DomElementInfo {
     pub tag_name: String,
     pub id: Option<String>,
@@ -435,17 +442,39 @@ pub struct DomElementInfo {
     pub selector_candidates: Vec<SelectorCandidate>,
 }
 
-/// Selector candidate for DOM element
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// Selector candidate for a DOM element, or for a UI Automation element
+/// (see [`synthesize_selectors`](crate::synthesize_selectors))
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct SelectorCandidate {
     pub selector: String,
     pub selector_type: String,
     pub specificity: u32,
     pub requires_jquery: bool,
+    /// Robustness score, set for synthesized UI Automation selectors
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub score: Option<SelectorScore>,
+}
+
+/// How robust a synthesized selector is in the snapshot it was generated from
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct SelectorScore {
+    /// 0-100, higher is more robust
+    pub total: u32,
+    /// Elements the selector matched in the snapshot
+    pub matches: usize,
+    /// 50 when the selector only matches the element, 15 when it matches it first
+    pub uniqueness: u32,
+    /// Up to 40, minus points for digits, dates, generated ids, hierarchy and positions
+    pub stability: u32,
+    /// Up to 15, shorter selectors score higher
+    pub length: u32,
+    /// Why stability points were taken off
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub penalties: Vec<String>,
 }
 
 /// Browser text input event with DOM context
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct BrowserTextInputEvent {
     /// DOM element being typed into
     pub dom_element: Option<DomElementInfo>,
@@ -891,60 +920,124 @@ impl RecordedWorkflow {
         self.end_time = Some(now);
     }
 
+    /// Convert to serializable form with the process-wide redaction
+    /// ([`terminator::redaction::global`]) applied
+    pub fn to_redacted(&self) -> SerializableRecordedWorkflow {
+        let mut serializable: SerializableRecordedWorkflow = self.into();
+        serializable.redact(&terminator::redaction::global());
+        serializable
+    }
+
     /// Serialize the workflow to JSON string
-    /// This converts UIElement instances to serializable form
+    /// This converts UIElement instances to serializable form and redacts it
     pub fn to_json(&self) -> Result<String, serde_json::Error> {
-        let serializable: SerializableRecordedWorkflow = self.into();
-        serde_json::to_string_pretty(&serializable)
+        serde_json::to_string_pretty(&self.to_redacted_json()?)
     }
 
     /// Serialize the workflow to JSON bytes
-    /// This converts UIElement instances to serializable form
+    /// This converts UIElement instances to serializable form and redacts it
     pub fn to_json_bytes(&self) -> Result<Vec<u8>, serde_json::Error> {
-        let serializable: SerializableRecordedWorkflow = self.into();
-        serde_json::to_vec_pretty(&serializable)
+        serde_json::to_vec_pretty(&self.to_redacted_json()?)
+    }
+
+    /// Patterns are applied once more to the JSON so text inside live
+    /// `UIElement`s (browser and file events) is covered too
+    fn to_redacted_json(&self) -> Result<serde_json::Value, serde_json::Error> {
+        let mut value = serde_json::to_value(self.to_redacted())?;
+        terminator::redaction::global().redact_json(&mut value);
+        Ok(value)
     }
 
-    /// Deserialize a workflow from JSON string
+    /// Serialize the workflow to CBOR, redacted like [`RecordedWorkflow::to_json`].
+    /// Considerably smaller than JSON for long recordings
+    pub fn to_cbor(&self) -> crate::Result<Vec<u8>> {
+        crate::format::to_cbor(&self.to_redacted_json()?)
+    }
+
+    /// Deserialize a workflow from JSON string, upgrading older recordings
+    /// to the current format
     /// Note: This creates a workflow with serializable UI elements,
     /// not the original UIElement instances
     pub fn from_json(json: &str) -> Result<SerializableRecordedWorkflow, serde_json::Error> {
-        serde_json::from_str(json)
+        Self::from_json_with_report(json).map(|(workflow, _)| workflow)
+    }
+
+    /// Like [`RecordedWorkflow::from_json`], also reporting what loading changed
+    pub fn from_json_with_report(
+        json: &str,
+    ) -> Result<(SerializableRecordedWorkflow, LoadReport), serde_json::Error> {
+        crate::format::load_value(serde_json::from_str(json)?)
     }
 
-    /// Deserialize a workflow from JSON bytes
+    /// Deserialize a workflow from JSON bytes, upgrading older recordings
+    /// to the current format
     /// Note: This creates a workflow with serializable UI elements,
     /// not the original UIElement instances
     pub fn from_json_bytes(
         bytes: &[u8],
     ) -> Result<SerializableRecordedWorkflow, serde_json::Error> {
-        serde_json::from_slice(bytes)
+        crate::format::load_value(serde_json::from_slice(bytes)?).map(|(workflow, _)| workflow)
+    }
+
+    /// Deserialize a workflow from CBOR written by [`RecordedWorkflow::to_cbor`]
+    pub fn from_cbor(bytes: &[u8]) -> crate::Result<(SerializableRecordedWorkflow, LoadReport)> {
+        Ok(crate::format::load_value(crate::format::from_cbor(bytes)?)?)
     }
 
-    /// Save the workflow to a JSON file
+    /// Save the workflow to a file, redacted like [`RecordedWorkflow::to_json`].
+    /// Files ending in `.cbor` are written as CBOR, anything else as JSON
     pub fn save_to_file<P: AsRef<std::path::Path>>(
         &self,
         path: P,
     ) -> Result<(), Box<dyn std::error::Error>> {
-        let json = self.to_json()?;
-        std::fs::write(path, json)?;
+        let bytes = if crate::format::is_cbor_path(path.as_ref()) {
+            self.to_cbor()?
+        } else {
+            self.to_json_bytes()?
+        };
+        std::fs::write(path, bytes)?;
         Ok(())
     }
 
-    /// Load a workflow from a JSON file
+    /// Load a workflow from a JSON or CBOR file, or a JSONL sink's journal,
+    /// upgrading older recordings
+    /// to the current format. Fields that had to be dropped or defaulted are
+    /// logged; use [`RecordedWorkflow::load_from_file_with_report`] to inspect them
     /// Note: This creates a workflow with serializable UI elements,
     /// not the original UIElement instances
     pub fn load_from_file<P: AsRef<std::path::Path>>(
         path: P,
     ) -> Result<SerializableRecordedWorkflow, Box<dyn std::error::Error>> {
-        let json = std::fs::read_to_string(path)?;
-        let workflow = Self::from_json(&json)?;
+        let (workflow, report) = Self::load_from_file_with_report(&path)?;
+        if !report.is_clean() {
+            tracing::warn!("Loaded {}: {report}", path.as_ref().display());
+        }
         Ok(workflow)
     }
+
+    /// Like [`RecordedWorkflow::load_from_file`], also reporting what loading changed
+    pub fn load_from_file_with_report<P: AsRef<std::path::Path>>(
+        path: P,
+    ) -> Result<(SerializableRecordedWorkflow, LoadReport), Box<dyn std::error::Error>> {
+        if path
+            .as_ref()
+            .extension()
+            .is_some_and(|extension| extension == "jsonl")
+        {
+            let (workflow, report) = crate::replay_jsonl(path)?;
+            return Ok((workflow, report.load));
+        }
+        let bytes = std::fs::read(&path)?;
+        if crate::format::is_cbor(path.as_ref(), &bytes) {
+            Ok(Self::from_cbor(&bytes)?)
+        } else {
+            Ok(crate::format::load_value(serde_json::from_slice(&bytes)?)?)
+        }
+    }
 }
 
 /// Method used to input text
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, PartialEq)]
 pub enum TextInputMethod {
     /// Text was typed character by character
     Typed,
@@ -959,7 +1052,7 @@ pub enum TextInputMethod {
 }
 
 /// How a text field received focus before input
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Default)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, PartialEq, Default)]
 pub enum FieldFocusMethod {
     /// User clicked the field with mouse
     MouseClick,
@@ -994,12 +1087,16 @@ pub struct TextInputCompletedEvent {
     /// Process executable name (e.g., "chrome.exe", "Notepad.exe")
     #[serde(skip_serializing_if = "Option::is_none")]
     pub process_name: Option<String>,
+    /// Whether the field is a password field; its value and keystrokes are
+    /// masked when the recording is written
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub is_password: bool,
     /// Event metadata with UI element context
     pub metadata: EventMetadata,
 }
 
 /// Method used to switch applications
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, PartialEq, Eq)]
 pub enum ApplicationSwitchMethod {
     /// Alt+Tab keyboard shortcut
     AltTab,
@@ -1050,7 +1147,7 @@ pub struct ApplicationSwitchEvent {
 }
 
 /// Browser tab navigation action type
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, PartialEq, Eq)]
 pub enum TabAction {
     /// New tab created
     Created,
@@ -1069,7 +1166,7 @@ pub enum TabAction {
 }
 
 /// Method used for tab navigation
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, PartialEq, Eq)]
 pub enum TabNavigationMethod {
     /// Keyboard shortcut (Ctrl+T, Ctrl+W, Ctrl+Tab, etc.)
     KeyboardShortcut,
@@ -1126,7 +1223,7 @@ pub struct BrowserTabNavigationEvent {
 }
 
 /// Confidence level for file path resolution
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, PartialEq, Eq)]
 pub enum FilePathConfidence {
     /// High confidence - only one file found with this name
     High,
@@ -1137,7 +1234,7 @@ pub enum FilePathConfidence {
 }
 
 /// Represents a candidate file path with its metadata
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct FileCandidatePath {
     /// Full path to the file
     pub path: String,
@@ -1151,7 +1248,7 @@ pub struct FileCandidatePath {
 
 /// File opened event detected via window title parsing and file system search
 /// Captures when a file is opened from Explorer or other applications
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct FileOpenedEvent {
     /// File name extracted from window title (e.g., "todolist-backup.txt")
     pub filename: String,
@@ -1235,7 +1332,7 @@ where
 }
 
 /// Unified metadata for all workflow events
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct EventMetadata {
     /// The UI element associated with this event (if available)
     #[serde(
@@ -1243,6 +1340,7 @@ pub struct EventMetadata {
         default,
         deserialize_with = "deserialize_optional_ui_element"
     )]
+    #[schemars(with = "Option<serde_json::Value>")]
     pub ui_element: Option<UIElement>,
 
     /// The exact timestamp when this event occurred (milliseconds since epoch)
@@ -1288,7 +1386,7 @@ impl EventMetadata {
 }
 
 /// Serializable version of UIElement for JSON export
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct SerializableUIElement {
     #[serde(skip_serializing_if = "is_empty_string")]
     pub id: Option<String>,
@@ -1309,6 +1407,8 @@ pub struct SerializableUIElement {
     pub url: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub process_id: Option<u32>,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub is_password: bool,
 }
 
 impl From<&UIElement> for SerializableUIElement {
@@ -1332,12 +1432,13 @@ impl From<&UIElement> for SerializableUIElement {
             window_title: filter_empty(Some(element.window_title())),
             url: element.url(),
             process_id: element.process_id().ok(),
+            is_password: element.is_password().unwrap_or(false),
         }
     }
 }
 
 /// Serializable version of EventMetadata for JSON export
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct SerializableEventMetadata {
     /// The UI element associated with this event (if available)
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -1358,7 +1459,7 @@ impl From<&EventMetadata> for SerializableEventMetadata {
 }
 
 /// Serializable version of KeyboardEvent for JSON export
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct SerializableKeyboardEvent {
     pub key_code: u32,
     pub is_key_down: bool,
@@ -1390,7 +1491,7 @@ impl From<&KeyboardEvent> for SerializableKeyboardEvent {
 }
 
 /// Serializable version of MouseEvent for JSON export
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct SerializableMouseEvent {
     pub event_type: MouseEventType,
     pub button: MouseButton,
@@ -1416,7 +1517,7 @@ impl From<&MouseEvent> for SerializableMouseEvent {
 }
 
 /// Serializable version of ClipboardEvent for JSON export
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct SerializableClipboardEvent {
     pub action: ClipboardAction,
     #[serde(skip_serializing_if = "is_empty_string")]
@@ -1443,7 +1544,7 @@ impl From<&ClipboardEvent> for SerializableClipboardEvent {
 }
 
 /// Serializable version of TextSelectionEvent for JSON export
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct SerializableTextSelectionEvent {
     pub selected_text: String,
     pub start_position: Position,
@@ -1467,7 +1568,7 @@ impl From<&TextSelectionEvent> for SerializableTextSelectionEvent {
 }
 
 /// Serializable version of DragDropEvent for JSON export
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct SerializableDragDropEvent {
     pub start_position: Position,
     pub end_position: Position,
@@ -1496,7 +1597,7 @@ impl From<&DragDropEvent> for SerializableDragDropEvent {
 }
 
 /// Serializable version of HotkeyEvent for JSON export
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct SerializableHotkeyEvent {
     pub combination: String,
     #[serde(skip_serializing_if = "is_empty_string")]
@@ -1520,7 +1621,7 @@ impl From<&HotkeyEvent> for SerializableHotkeyEvent {
 }
 
 /// Serializable version of TextInputCompletedEvent for JSON export
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct SerializableTextInputCompletedEvent {
     pub text_value: String,
     #[serde(skip_serializing_if = "is_empty_string")]
@@ -1532,6 +1633,8 @@ pub struct SerializableTextInputCompletedEvent {
     pub keystroke_count: u32,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub process_name: Option<String>,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub is_password: bool,
     pub metadata: SerializableEventMetadata,
 }
 
@@ -1546,13 +1649,14 @@ impl From<&TextInputCompletedEvent> for SerializableTextInputCompletedEvent {
             typing_duration_ms: event.typing_duration_ms,
             keystroke_count: event.keystroke_count,
             process_name: event.process_name.clone(),
+            is_password: event.is_password,
             metadata: (&event.metadata).into(),
         }
     }
 }
 
 /// Serializable version of ApplicationSwitchEvent for JSON export
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct SerializableApplicationSwitchEvent {
     #[serde(skip_serializing_if = "is_empty_string")]
     pub from_window_and_application_name: Option<String>,
@@ -1590,7 +1694,7 @@ impl From<&ApplicationSwitchEvent> for SerializableApplicationSwitchEvent {
 }
 
 /// Serializable version of BrowserTabNavigationEvent for JSON export
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct SerializableBrowserTabNavigationEvent {
     pub action: TabAction,
     pub method: TabNavigationMethod,
@@ -1633,7 +1737,7 @@ impl From<&BrowserTabNavigationEvent> for SerializableBrowserTabNavigationEvent
 }
 
 /// Serializable version of ButtonClickEvent for JSON export
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct SerializableClickEvent {
     pub element_text: String,
     pub interaction_type: ButtonInteractionType,
@@ -1651,6 +1755,8 @@ pub struct SerializableClickEvent {
     pub process_name: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub page_url: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty", default)]
+    pub selector_candidates: Vec<SelectorCandidate>,
     pub metadata: SerializableEventMetadata,
 }
 
@@ -1667,13 +1773,14 @@ impl From<&ClickEvent> for SerializableClickEvent {
             relative_position: event.relative_position,
             process_name: event.process_name.clone(),
             page_url: event.page_url.clone(),
+            selector_candidates: event.selector_candidates.clone(),
             metadata: (&event.metadata).into(),
         }
     }
 }
 
 /// Serializable version of WorkflowEvent for JSON export
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub enum SerializableWorkflowEvent {
     Mouse(SerializableMouseEvent),
     Keyboard(SerializableKeyboardEvent),
@@ -1721,7 +1828,7 @@ impl From<&WorkflowEvent> for SerializableWorkflowEvent {
 }
 
 /// Serializable version of RecordedEvent for JSON export
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct SerializableRecordedEvent {
     pub timestamp: u64,
     pub event: SerializableWorkflowEvent,
@@ -1737,8 +1844,11 @@ impl From<&RecordedEvent> for SerializableRecordedEvent {
 }
 
 /// Serializable version of RecordedWorkflow for JSON export
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct SerializableRecordedWorkflow {
+    /// Version of the recording format, see [`crate::FORMAT_VERSION`]
+    #[serde(default = "crate::format::legacy_format_version")]
+    pub format_version: u32,
     pub name: String,
     pub start_time: u64,
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -1749,6 +1859,7 @@ pub struct SerializableRecordedWorkflow {
 impl From<&RecordedWorkflow> for SerializableRecordedWorkflow {
     fn from(workflow: &RecordedWorkflow) -> Self {
         Self {
+            format_version: crate::FORMAT_VERSION,
             name: workflow.name.clone(),
             start_time: workflow.start_time,
             end_time: workflow.end_time,
@@ -0,0 +1,424 @@
+//! Versioning of the recording format.
+//!
+//! Every recording carries a `format_version`; files written before the field
+//! existed are version 1. On load, older recordings are upgraded by the
+//! migrations for their version and then conformed to the JSON Schema of
+//! [`SerializableRecordedWorkflow`] ([`recording_schema`], published as
+//! `schema/recording.schema.json`): fields the current format does not know are
+//! dropped and missing required fields get a default. Both are listed in the
+//! [`LoadReport`] instead of failing the load or passing silently.
+//!
+//! Recordings are stored as JSON or, for long sessions, as CBOR (files ending
+//! in `.cbor`), which keeps the same structure at a fraction of the size.
+
+use crate::{Result, SerializableRecordedWorkflow, WorkflowRecorderError};
+use serde::Serialize;
+use serde_json::{Map, Value};
+use std::fmt;
+use std::path::Path;
+use std::sync::LazyLock;
+
+/// Version written into new recordings
+///
+/// 1. Recordings written before `format_version` existed
+/// 2. Adds `format_version`; password fields carry `is_password`
+pub const FORMAT_VERSION: u32 = 2;
+
+pub(crate) fn legacy_format_version() -> u32 {
+    1
+}
+
+/// Upgrades a recording in its JSON form from `from` to `from + 1`, returning
+/// how many values it changed
+struct Migration {
+    from: u32,
+    description: &'static str,
+    apply: fn(&mut Value) -> usize,
+}
+
+const MIGRATIONS: &[Migration] = &[Migration {
+    from: 1,
+    description: "flagged password fields with is_password",
+    apply: flag_password_fields,
+}];
+
+static SCHEMA: LazyLock<Value> = LazyLock::new(|| {
+    serde_json::to_value(schemars::schema_for!(SerializableRecordedWorkflow))
+        .expect("the recording schema serializes")
+});
+
+/// What loading a recording changed to fit the current format
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct LoadReport {
+    /// The `format_version` the recording was written with
+    pub format_version: u32,
+    /// Migrations applied, oldest first
+    pub migrations: Vec<String>,
+    /// Fields the current format does not know, e.g. `events[3].event.Click.window_handle`
+    pub dropped_fields: Vec<String>,
+    /// Required fields that were missing and got a default value
+    pub defaulted_fields: Vec<String>,
+}
+
+impl LoadReport {
+    /// Whether the recording loaded exactly as written
+    pub fn is_clean(&self) -> bool {
+        self.migrations.is_empty()
+            && self.dropped_fields.is_empty()
+            && self.defaulted_fields.is_empty()
+    }
+}
+
+impl fmt::Display for LoadReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "format version {}", self.format_version)?;
+        if !self.migrations.is_empty() {
+            write!(f, "; migrated: {}", self.migrations.join(", "))?;
+        }
+        if !self.dropped_fields.is_empty() {
+            write!(f, "; dropped: {}", self.dropped_fields.join(", "))?;
+        }
+        if !self.defaulted_fields.is_empty() {
+            write!(f, "; defaulted: {}", self.defaulted_fields.join(", "))?;
+        }
+        Ok(())
+    }
+}
+
+/// JSON Schema of a saved recording ([`SerializableRecordedWorkflow`])
+pub fn recording_schema() -> Value {
+    SCHEMA.clone()
+}
+
+/// Upgrade a recording in its JSON form to [`FORMAT_VERSION`] and conform it
+/// to [`recording_schema`]
+pub fn upgrade_recording(value: &mut Value) -> LoadReport {
+    let version = value
+        .get("format_version")
+        .and_then(Value::as_u64)
+        .map_or(legacy_format_version(), |v| v as u32);
+    let mut report = LoadReport {
+        format_version: version,
+        ..LoadReport::default()
+    };
+
+    if version > FORMAT_VERSION {
+        tracing::warn!(
+            "Recording has format version {version}, newer than {FORMAT_VERSION}; unknown fields are dropped"
+        );
+    }
+    for migration in MIGRATIONS.iter().filter(|m| m.from >= version) {
+        let changed = (migration.apply)(value);
+        report.migrations.push(format!(
+            "{} -> {}: {} ({changed} changed)",
+            migration.from,
+            migration.from + 1,
+            migration.description
+        ));
+    }
+    if version < FORMAT_VERSION {
+        if let Some(object) = value.as_object_mut() {
+            object.insert("format_version".to_string(), FORMAT_VERSION.into());
+        }
+    }
+
+    conform(&SCHEMA, &SCHEMA, value, "", &mut report);
+    report
+}
+
+/// Deserialize a recording after [`upgrade_recording`]
+pub(crate) fn load_value(
+    mut value: Value,
+) -> std::result::Result<(SerializableRecordedWorkflow, LoadReport), serde_json::Error> {
+    let report = upgrade_recording(&mut value);
+    Ok((serde_json::from_value(value)?, report))
+}
+
+pub(crate) fn to_cbor(value: &Value) -> Result<Vec<u8>> {
+    let mut bytes = Vec::new();
+    ciborium::into_writer(value, &mut bytes)
+        .map_err(|e| WorkflowRecorderError::CborError(e.to_string()))?;
+    Ok(bytes)
+}
+
+pub(crate) fn from_cbor(bytes: &[u8]) -> Result<Value> {
+    ciborium::from_reader(bytes).map_err(|e| WorkflowRecorderError::CborError(e.to_string()))
+}
+
+pub(crate) fn is_cbor_path(path: &Path) -> bool {
+    path.extension()
+        .is_some_and(|extension| extension.eq_ignore_ascii_case("cbor"))
+}
+
+/// By extension, or for renamed files by content: JSON recordings are objects
+pub(crate) fn is_cbor(path: &Path, bytes: &[u8]) -> bool {
+    is_cbor_path(path)
+        || bytes
+            .iter()
+            .find(|b| !b.is_ascii_whitespace())
+            .is_some_and(|b| *b != b'{')
+}
+
+/// Version 1 recorded password fields like any other text field; flag them
+/// so redaction and compilation treat them as secrets
+fn flag_password_fields(value: &mut Value) -> usize {
+    let mut changed = 0;
+    match value {
+        Value::Object(object) => {
+            for child in object.values_mut() {
+                changed += flag_password_fields(child);
+            }
+            if object.get("is_password") == Some(&Value::Bool(true)) {
+                return changed;
+            }
+            let mentions_password = |key: &str| {
+                object
+                    .get(key)
+                    .and_then(Value::as_str)
+                    .is_some_and(|s| s.to_lowercase().contains("password"))
+            };
+            let is_edit = object
+                .get("role")
+                .and_then(Value::as_str)
+                .is_some_and(|role| role.to_lowercase().contains("edit"));
+            let password_element = is_edit && mentions_password("name");
+            let password_input = object.contains_key("text_value")
+                && (mentions_password("field_type")
+                    || mentions_password("field_name")
+                    || object
+                        .get("metadata")
+                        .and_then(|m| m.get("ui_element"))
+                        .and_then(|e| e.get("is_password"))
+                        == Some(&Value::Bool(true)));
+            if password_element || password_input {
+                object.insert("is_password".to_string(), Value::Bool(true));
+                changed += 1;
+            }
+        }
+        Value::Array(items) => {
+            for item in items {
+                changed += flag_password_fields(item);
+            }
+        }
+        _ => {}
+    }
+    changed
+}
+
+fn resolve<'a>(root: &'a Value, mut schema: &'a Value) -> &'a Value {
+    while let Some(reference) = schema.get("$ref").and_then(Value::as_str) {
+        match reference
+            .strip_prefix("#/$defs/")
+            .and_then(|name| root["$defs"].get(name))
+        {
+            Some(definition) => schema = definition,
+            None => break,
+        }
+    }
+    schema
+}
+
+fn branches(schema: &Value) -> Option<&Vec<Value>> {
+    schema
+        .get("anyOf")
+        .or_else(|| schema.get("oneOf"))
+        .and_then(Value::as_array)
+}
+
+fn field_path(path: &str, key: &str) -> String {
+    if path.is_empty() {
+        key.to_string()
+    } else {
+        format!("{path}.{key}")
+    }
+}
+
+fn conform(root: &Value, schema: &Value, value: &mut Value, path: &str, report: &mut LoadReport) {
+    let schema = resolve(root, schema);
+    if let Some(branches) = branches(schema) {
+        if let Some(branch) = matching_branch(root, branches, value) {
+            conform(root, branch, value, path, report);
+        }
+        return;
+    }
+    match value {
+        Value::Object(object) => {
+            let Some(properties) = schema.get("properties").and_then(Value::as_object) else {
+                return;
+            };
+            let unknown: Vec<String> = object
+                .keys()
+                .filter(|key| !properties.contains_key(*key))
+                .cloned()
+                .collect();
+            for key in unknown {
+                object.remove(&key);
+                report.dropped_fields.push(field_path(path, &key));
+            }
+            let required = schema.get("required").and_then(Value::as_array);
+            for (key, property) in properties {
+                let path = field_path(path, key);
+                match object.get_mut(key) {
+                    Some(field) => conform(root, property, field, &path, report),
+                    None if required.is_some_and(|r| r.iter().any(|k| k == key)) => {
+                        object.insert(key.clone(), default_value(root, property));
+                        report.defaulted_fields.push(path);
+                    }
+                    None => {}
+                }
+            }
+        }
+        Value::Array(items) => {
+            if let Some(item_schema) = schema.get("items") {
+                for (i, item) in items.iter_mut().enumerate() {
+                    conform(root, item_schema, item, &format!("{path}[{i}]"), report);
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
+/// The branch of an `anyOf`/`oneOf` an object belongs to: for externally
+/// tagged enums the one naming its variant, for options the non-null one
+fn matching_branch<'a>(root: &'a Value, branches: &'a [Value], value: &Value) -> Option<&'a Value> {
+    let object = value.as_object()?;
+    let mut best = None;
+    let mut best_score = 0;
+    for branch in branches.iter().map(|b| resolve(root, b)) {
+        let Some(properties) = branch.get("properties").and_then(Value::as_object) else {
+            continue;
+        };
+        let score = object
+            .keys()
+            .filter(|k| properties.contains_key(*k))
+            .count();
+        if score > best_score {
+            best = Some(branch);
+            best_score = score;
+        }
+    }
+    best
+}
+
+/// A value for a missing required field; enums prefer a catch-all variant
+fn default_value(root: &Value, schema: &Value) -> Value {
+    let schema = resolve(root, schema);
+    if let Some(default) = schema.get("default") {
+        return default.clone();
+    }
+    if let Some(values) = schema.get("enum").and_then(Value::as_array) {
+        return preferred_variant(values);
+    }
+    if let Some(branches) = branches(schema) {
+        let branches: Vec<&Value> = branches.iter().map(|b| resolve(root, b)).collect();
+        if branches.iter().any(|b| b["type"] == "null") {
+            return Value::Null;
+        }
+        let constants: Vec<Value> = branches
+            .iter()
+            .filter_map(|b| b.get("const"))
+            .cloned()
+            .collect();
+        if !constants.is_empty() {
+            return preferred_variant(&constants);
+        }
+        return branches
+            .first()
+            .map_or(Value::Null, |branch| default_value(root, branch));
+    }
+    let kind = match schema.get("type") {
+        Some(Value::String(kind)) => kind.as_str(),
+        Some(Value::Array(kinds)) if kinds.iter().any(|k| k == "null") => return Value::Null,
+        Some(Value::Array(kinds)) => kinds.first().and_then(Value::as_str).unwrap_or_default(),
+        _ => "",
+    };
+    match kind {
+        "string" => Value::String(String::new()),
+        "integer" | "number" => 0.into(),
+        "boolean" => Value::Bool(false),
+        "array" => Value::Array(Vec::new()),
+        "object" => {
+            let mut object = Map::new();
+            let properties = schema.get("properties").and_then(Value::as_object);
+            for key in schema
+                .get("required")
+                .and_then(Value::as_array)
+                .into_iter()
+                .flatten()
+                .filter_map(Value::as_str)
+            {
+                if let Some(property) = properties.and_then(|p| p.get(key)) {
+                    object.insert(key.to_string(), default_value(root, property));
+                }
+            }
+            Value::Object(object)
+        }
+        _ => Value::Null,
+    }
+}
+
+fn preferred_variant(values: &[Value]) -> Value {
+    values
+        .iter()
+        .find(|v| *v == "Other" || *v == "Unknown")
+        .or_else(|| values.first())
+        .cloned()
+        .unwrap_or(Value::Null)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn unknown_fields_are_dropped_and_missing_ones_defaulted() {
+        let mut recording = json!({
+            "format_version": FORMAT_VERSION,
+            "name": "switch",
+            "start_time": 0,
+            "recorder_host": "build-01",
+            "events": [{
+                "timestamp": 5,
+                "event": {"ApplicationSwitch": {
+                    "to_window_and_application_name": "Notepad",
+                    "switch_method": "AltTab",
+                    "metadata": {}
+                }}
+            }]
+        });
+        let report = upgrade_recording(&mut recording);
+
+        assert!(report.migrations.is_empty());
+        assert_eq!(report.dropped_fields, vec!["recorder_host"]);
+        assert_eq!(
+            report.defaulted_fields,
+            vec!["events[0].event.ApplicationSwitch.to_process_id"]
+        );
+        assert_eq!(
+            recording["events"][0]["event"]["ApplicationSwitch"]["to_process_id"],
+            0
+        );
+        assert!(serde_json::from_value::<SerializableRecordedWorkflow>(recording).is_ok());
+    }
+
+    #[test]
+    fn current_recordings_load_clean() {
+        let mut recording = json!({
+            "format_version": FORMAT_VERSION,
+            "name": "empty",
+            "start_time": 0,
+            "events": []
+        });
+        let report = upgrade_recording(&mut recording);
+        assert!(report.is_clean(), "{report}");
+    }
+
+    #[test]
+    fn enums_default_to_their_catch_all_variant() {
+        let values = vec![json!("Typed"), json!("Other")];
+        assert_eq!(preferred_variant(&values), "Other");
+        assert_eq!(preferred_variant(&values[..1]), "Typed");
+    }
+}
@@ -2,26 +2,60 @@
 //!
 //! This crate provides functionality to record user interactions with the Windows UI,
 //! including mouse clicks, keyboard input, and window focus changes.
-//! The recorded workflow can be saved as a JSON file for later playback or analysis.
+//! The recorded workflow can be saved as a JSON file for later playback or analysis,
+//! cleaned up and split into named intents with [`postprocess`], and compiled into a
+//! runnable workflow with [`compile`], or their raw input replayed with [`play`].
+//! [`diff_recordings`] compares a recording with a reference recording of the same task.
+//! Recordings are redacted with [`terminator::redaction`] before they are written, carry a
+//! [`FORMAT_VERSION`] and are upgraded to the current format when loaded.
 
 #![cfg_attr(not(target_os = "windows"), allow(unused))]
 
+mod compiler;
+mod diff;
 mod error;
 mod events;
+mod format;
+mod playback;
+mod postprocess;
 mod recorder;
+mod redaction;
+mod sinks;
+mod synthesizer;
 
+pub use compiler::{compile, rank_selectors, CompileOptions, CompiledWorkflow};
+pub use diff::{diff_recordings, AlignedStep, DiffStep, Difference, RecordingDiff, StepAction};
 pub use error::*;
 pub use events::{
     build_chained_selector, build_parent_hierarchy, ApplicationSwitchEvent,
     ApplicationSwitchMethod, BrowserClickEvent, BrowserTabNavigationEvent, BrowserTextInputEvent,
     ButtonInteractionType, ClickEvent, ClipboardAction, ClipboardEvent, DomElementInfo,
-    DragDropEvent, EnhancedUIElement, EventMetadata, FieldFocusMethod, FileOpenedEvent,
-    HotkeyEvent, InteractionContext, KeyboardEvent, McpToolStep, MouseButton, MouseEvent,
-    MouseEventType, PendingActionEvent, PendingActionType, Position, RecordedEvent,
-    RecordedWorkflow, Rect, SelectionMethod, SelectorCandidate, TabAction, TabNavigationMethod,
-    TextInputCompletedEvent, TextInputMethod, TextSelectionEvent, UIElementInfo, WorkflowEvent,
+    DragDropEvent, EnhancedUIElement, EventMetadata, FieldFocusMethod, FileCandidatePath,
+    FileOpenedEvent, FilePathConfidence, HotkeyEvent, InteractionContext, KeyboardEvent,
+    McpToolStep, MouseButton, MouseEvent, MouseEventType, PendingActionEvent, PendingActionType,
+    Position, RecordedEvent, RecordedWorkflow, Rect, SelectionMethod, SelectorCandidate,
+    SelectorScore, SerializableApplicationSwitchEvent, SerializableBrowserTabNavigationEvent,
+    SerializableClickEvent, SerializableClipboardEvent, SerializableDragDropEvent,
+    SerializableEventMetadata, SerializableHotkeyEvent, SerializableKeyboardEvent,
+    SerializableMouseEvent, SerializableRecordedEvent, SerializableRecordedWorkflow,
+    SerializableTextInputCompletedEvent, SerializableTextSelectionEvent, SerializableUIElement,
+    SerializableWorkflowEvent, TabAction, TabNavigationMethod, TextInputCompletedEvent,
+    TextInputMethod, TextSelectionEvent, UIElementInfo, WorkflowEvent,
+};
+pub use format::{recording_schema, upgrade_recording, LoadReport, FORMAT_VERSION};
+pub use playback::{
+    play, MonitorMap, PlaybackInput, PlaybackOptions, PlaybackReport, SkipReason, SkippedEvent,
+};
+pub use postprocess::{
+    postprocess, AnnotatedEvent, DropReason, DroppedEvent, Intent, IntentBoundary,
+    PostProcessOptions, ProcessedRecording,
 };
 pub use recorder::*;
+pub use sinks::{
+    replay_jsonl, EventSink, JournalEntry, JsonlSinkConfig, ReplayReport, SinkConfig, SinkStats,
+    WebSocketSinkConfig,
+};
+pub use synthesizer::{find_node_path, synthesize_selectors, NodePath};
 
 #[cfg(target_os = "windows")]
 pub mod structs {
@@ -0,0 +1,588 @@
+//! Replays a recording's raw input with its original timing.
+//!
+//! Where [`compile`](crate::compile) turns a recording into selector-based steps,
+//! [`play`] drives the recorded mouse and keyboard input again: mouse paths,
+//! presses and releases, drags and every keystroke, spaced as they were recorded
+//! (optionally faster or slower). This is for reproducing bugs that depend on
+//! exact input, not for automating a task.
+//!
+//! Input goes through [`PlaybackInput`], which [`Desktop`] implements with the
+//! `UIElement`/`Desktop` input APIs. High-level events (clicks, drags, hotkeys)
+//! are only replayed when the recording has no raw events for them, and
+//! events that describe input rather than being input (typed text, app switches)
+//! are skipped. Before acting on an element, playback checks the element is
+//! still there, and coordinates can be remapped to other monitors with a
+//! [`MonitorMap`].
+
+use crate::compiler::{key_sequence, plain_text};
+use crate::events::{
+    MouseButton, MouseEventType, Position, SerializableKeyboardEvent, SerializableRecordedWorkflow,
+    SerializableUIElement, SerializableWorkflowEvent,
+};
+use crate::postprocess::MODIFIER_KEYS;
+use crate::{Result, WorkflowRecorderError};
+use serde::{Deserialize, Serialize};
+use std::future::Future;
+use std::time::{Duration, Instant};
+use terminator::{AutomationError, ClickType, Desktop, Monitor};
+use tracing::{debug, warn};
+
+/// The input APIs playback drives
+pub trait PlaybackInput {
+    fn mouse_move(&self, x: f64, y: f64) -> std::result::Result<(), AutomationError>;
+    /// Press and hold the left button at (x, y)
+    fn mouse_down(&self, x: f64, y: f64) -> std::result::Result<(), AutomationError>;
+    /// Release the left button
+    fn mouse_up(&self) -> std::result::Result<(), AutomationError>;
+    fn mouse_drag(
+        &self,
+        from: (f64, f64),
+        to: (f64, f64),
+    ) -> std::result::Result<(), AutomationError>;
+    fn click(
+        &self,
+        x: f64,
+        y: f64,
+        click_type: ClickType,
+    ) -> std::result::Result<(), AutomationError>;
+    /// Press a key in `press_key` syntax, e.g. `a`, `{Enter}` or `{Ctrl}{S}`
+    fn press_key(
+        &self,
+        key: &str,
+    ) -> impl Future<Output = std::result::Result<(), AutomationError>> + Send;
+    /// Whether an element matching `selector` appears within `timeout`
+    fn element_exists(
+        &self,
+        selector: &str,
+        timeout: Duration,
+    ) -> impl Future<Output = bool> + Send;
+}
+
+impl PlaybackInput for Desktop {
+    fn mouse_move(&self, x: f64, y: f64) -> std::result::Result<(), AutomationError> {
+        self.root().mouse_move(x, y)
+    }
+
+    fn mouse_down(&self, x: f64, y: f64) -> std::result::Result<(), AutomationError> {
+        self.root().mouse_click_and_hold(x, y)
+    }
+
+    fn mouse_up(&self) -> std::result::Result<(), AutomationError> {
+        self.root().mouse_release()
+    }
+
+    fn mouse_drag(
+        &self,
+        from: (f64, f64),
+        to: (f64, f64),
+    ) -> std::result::Result<(), AutomationError> {
+        self.root().mouse_drag(from.0, from.1, to.0, to.1)
+    }
+
+    fn click(
+        &self,
+        x: f64,
+        y: f64,
+        click_type: ClickType,
+    ) -> std::result::Result<(), AutomationError> {
+        self.click_at_coordinates_with_type(x, y, click_type, false)
+    }
+
+    fn press_key(
+        &self,
+        key: &str,
+    ) -> impl Future<Output = std::result::Result<(), AutomationError>> + Send {
+        Desktop::press_key(self, key)
+    }
+
+    fn element_exists(
+        &self,
+        selector: &str,
+        timeout: Duration,
+    ) -> impl Future<Output = bool> + Send {
+        let locator = self.locator(selector);
+        async move { locator.first(Some(timeout)).await.is_ok() }
+    }
+}
+
+/// Maps coordinates from the monitors a recording was made on to the monitors
+/// it is played on. A point keeps its relative position on its monitor, so a
+/// recording made at 1920x1080 lands in the same place at 2560x1440 or 125%
+/// scaling.
+#[derive(Debug, Clone, Default)]
+pub struct MonitorMap {
+    source: Vec<Monitor>,
+    target: Vec<Monitor>,
+}
+
+impl MonitorMap {
+    /// `source` are the monitors of the machine the recording was made on, e.g.
+    /// saved from [`Desktop::list_monitors`] at the time; `target` those of
+    /// this machine
+    pub fn new(source: Vec<Monitor>, target: Vec<Monitor>) -> Self {
+        Self { source, target }
+    }
+
+    /// Where a recorded point is on the target monitors; unchanged when either
+    /// layout is unknown
+    pub fn map(&self, x: f64, y: f64) -> (f64, f64) {
+        let Some((index, from)) = self
+            .source
+            .iter()
+            .enumerate()
+            .find(|(_, m)| m.contains_point(x as i32, y as i32))
+        else {
+            return (x, y);
+        };
+        let to = self
+            .target
+            .iter()
+            .find(|m| m.id == from.id)
+            .or_else(|| self.target.iter().find(|m| m.name == from.name))
+            .or_else(|| {
+                from.is_primary
+                    .then(|| self.target.iter().find(|m| m.is_primary))
+                    .flatten()
+            })
+            .or_else(|| self.target.get(index));
+        let Some(to) = to else {
+            return (x, y);
+        };
+        let scale_x = to.width as f64 / from.width.max(1) as f64;
+        let scale_y = to.height as f64 / from.height.max(1) as f64;
+        (
+            to.x as f64 + (x - from.x as f64) * scale_x,
+            to.y as f64 + (y - from.y as f64) * scale_y,
+        )
+    }
+}
+
+/// Options for [`play`]
+#[derive(Debug, Clone)]
+pub struct PlaybackOptions {
+    /// Playback speed; 2.0 plays twice as fast
+    pub speed: f64,
+    /// Longest wait between two events, so idle time is not replayed
+    pub max_delay_ms: Option<u64>,
+    /// Replay mouse movement between presses, e.g. hover effects and drag paths
+    pub mouse_moves: bool,
+    /// Check that the element of an event is still there before acting on it
+    pub verify_elements: bool,
+    /// How long to wait for an element to appear
+    pub element_timeout_ms: u64,
+    /// Stop at the first missing element instead of skipping the event
+    pub stop_on_missing_element: bool,
+    pub monitors: MonitorMap,
+}
+
+impl Default for PlaybackOptions {
+    fn default() -> Self {
+        Self {
+            speed: 1.0,
+            max_delay_ms: Some(5000),
+            mouse_moves: true,
+            verify_elements: true,
+            element_timeout_ms: 2000,
+            stop_on_missing_element: true,
+            monitors: MonitorMap::default(),
+        }
+    }
+}
+
+/// Why an event was not replayed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SkipReason {
+    /// The raw events it was derived from are replayed instead, e.g. a click
+    /// when the recording has the button presses, or a key release
+    CoveredByRawInput,
+    /// It describes input rather than being input, e.g. typed text or an
+    /// application switch
+    NotInput,
+    /// A keystroke masked by redaction
+    Redacted,
+    /// Mouse movement with [`PlaybackOptions::mouse_moves`] off
+    MouseMove,
+    /// Input the playback APIs cannot produce, e.g. the wheel or the middle button
+    Unsupported,
+    /// Its element was not found and [`PlaybackOptions::stop_on_missing_element`] is off
+    ElementMissing,
+}
+
+/// An event that was not replayed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SkippedEvent {
+    /// Index into the recording's events
+    pub index: usize,
+    pub reason: SkipReason,
+}
+
+/// What [`play`] did
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PlaybackReport {
+    /// Events replayed
+    pub replayed: usize,
+    pub skipped: Vec<SkippedEvent>,
+    /// Event whose element was missing, when playback stopped there
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stopped_at: Option<usize>,
+    pub duration_ms: u64,
+    /// How far playback fell behind the recorded timing at worst
+    pub max_lag_ms: u64,
+}
+
+#[derive(Debug, Clone)]
+enum Input {
+    Move(Position),
+    Down(Position),
+    Up(Position),
+    Click(Position, ClickType),
+    Drag(Position, Position),
+    Key(String),
+}
+
+/// Replay a recording's input; see the [module docs](self)
+pub async fn play<I: PlaybackInput>(
+    recording: &SerializableRecordedWorkflow,
+    input: &I,
+    options: &PlaybackOptions,
+) -> Result<PlaybackReport> {
+    if options.speed.is_nan() || options.speed <= 0.0 {
+        return Err(WorkflowRecorderError::PlaybackError(format!(
+            "speed must be positive, got {}",
+            options.speed
+        )));
+    }
+    let raw_mouse = recording.events.iter().any(|e| {
+        matches!(&e.event, SerializableWorkflowEvent::Mouse(m)
+            if matches!(m.event_type, MouseEventType::Down | MouseEventType::Up))
+    });
+    let raw_keys = recording
+        .events
+        .iter()
+        .any(|e| matches!(&e.event, SerializableWorkflowEvent::Keyboard(k) if k.is_key_down));
+
+    let mut report = PlaybackReport::default();
+    let started = Instant::now();
+    let mut schedule = Duration::ZERO;
+    let mut previous = recording.events.first().map(|e| e.timestamp);
+    let mut verified: Option<String> = None;
+
+    for (index, recorded) in recording.events.iter().enumerate() {
+        let gap = recorded
+            .timestamp
+            .saturating_sub(previous.unwrap_or(recorded.timestamp));
+        previous = Some(recorded.timestamp);
+        let gap = options.max_delay_ms.map_or(gap, |max| gap.min(max));
+        schedule += Duration::from_millis(gap).div_f64(options.speed);
+
+        let (action, element) = match plan(&recorded.event, raw_mouse, raw_keys, options) {
+            Ok(planned) => planned,
+            Err(reason) => {
+                report.skipped.push(SkippedEvent { index, reason });
+                continue;
+            }
+        };
+
+        if let Some(wait) = schedule.checked_sub(started.elapsed()) {
+            tokio::time::sleep(wait).await;
+        }
+        let lag = started.elapsed().saturating_sub(schedule);
+        report.max_lag_ms = report.max_lag_ms.max(lag.as_millis() as u64);
+
+        if let Some(selector) = element
+            .filter(|_| options.verify_elements)
+            .and_then(selector)
+        {
+            if verified.as_ref() != Some(&selector) {
+                let timeout = Duration::from_millis(options.element_timeout_ms);
+                if !input.element_exists(&selector, timeout).await {
+                    warn!("Event {index}: element '{selector}' not found");
+                    if options.stop_on_missing_element {
+                        report.stopped_at = Some(index);
+                        break;
+                    }
+                    report.skipped.push(SkippedEvent {
+                        index,
+                        reason: SkipReason::ElementMissing,
+                    });
+                    continue;
+                }
+                verified = Some(selector);
+            }
+        }
+
+        debug!("Replaying event {index}: {action:?}");
+        perform(input, &action, &options.monitors)
+            .await
+            .map_err(|e| WorkflowRecorderError::PlaybackError(format!("event {index}: {e}")))?;
+        report.replayed += 1;
+    }
+
+    report.duration_ms = started.elapsed().as_millis() as u64;
+    Ok(report)
+}
+
+async fn perform<I: PlaybackInput>(
+    input: &I,
+    action: &Input,
+    monitors: &MonitorMap,
+) -> std::result::Result<(), AutomationError> {
+    let at = |p: &Position| monitors.map(p.x as f64, p.y as f64);
+    match action {
+        Input::Move(p) => {
+            let (x, y) = at(p);
+            input.mouse_move(x, y)
+        }
+        Input::Down(p) => {
+            let (x, y) = at(p);
+            input.mouse_down(x, y)
+        }
+        Input::Up(p) => {
+            let (x, y) = at(p);
+            input.mouse_move(x, y)?;
+            input.mouse_up()
+        }
+        Input::Click(p, click_type) => {
+            let (x, y) = at(p);
+            input.click(x, y, *click_type)
+        }
+        Input::Drag(from, to) => input.mouse_drag(at(from), at(to)),
+        Input::Key(key) => input.press_key(key).await,
+    }
+}
+
+/// The input for an event and the element it acts on
+fn plan<'a>(
+    event: &'a SerializableWorkflowEvent,
+    raw_mouse: bool,
+    raw_keys: bool,
+    options: &PlaybackOptions,
+) -> std::result::Result<(Input, Option<&'a SerializableUIElement>), SkipReason> {
+    use SerializableWorkflowEvent as E;
+    match event {
+        E::Mouse(e) => {
+            let element = e.metadata.ui_element.as_ref();
+            let p = e.position;
+            match (&e.event_type, &e.button) {
+                (MouseEventType::Move, _) if options.mouse_moves => Ok((Input::Move(p), None)),
+                (MouseEventType::Move, _) => Err(SkipReason::MouseMove),
+                (MouseEventType::Down, MouseButton::Left) => Ok((Input::Down(p), element)),
+                (MouseEventType::Up, MouseButton::Left) => Ok((Input::Up(p), None)),
+                // The playback APIs click the right button as a whole
+                (MouseEventType::Down, MouseButton::Right) => Err(SkipReason::CoveredByRawInput),
+                (MouseEventType::Up, MouseButton::Right) => {
+                    Ok((Input::Click(p, ClickType::Right), None))
+                }
+                (
+                    MouseEventType::Click
+                    | MouseEventType::DoubleClick
+                    | MouseEventType::RightClick,
+                    _,
+                ) if raw_mouse => Err(SkipReason::CoveredByRawInput),
+                (MouseEventType::Click, MouseButton::Left) => {
+                    Ok((Input::Click(p, ClickType::Left), element))
+                }
+                (MouseEventType::DoubleClick, MouseButton::Left) => {
+                    Ok((Input::Click(p, ClickType::Double), element))
+                }
+                (MouseEventType::RightClick, _) | (MouseEventType::Click, MouseButton::Right) => {
+                    Ok((Input::Click(p, ClickType::Right), element))
+                }
+                (MouseEventType::DragStart | MouseEventType::DragEnd | MouseEventType::Drop, _)
+                    if raw_mouse =>
+                {
+                    Err(SkipReason::CoveredByRawInput)
+                }
+                _ => Err(SkipReason::Unsupported),
+            }
+        }
+        E::Click(_) | E::DragDrop(_) | E::BrowserClick(_) if raw_mouse => {
+            Err(SkipReason::CoveredByRawInput)
+        }
+        E::Click(e) => match e.click_position {
+            Some(p) => Ok((
+                Input::Click(p, ClickType::Left),
+                e.metadata.ui_element.as_ref(),
+            )),
+            None => Err(SkipReason::Unsupported),
+        },
+        E::DragDrop(e) => Ok((
+            Input::Drag(e.start_position, e.end_position),
+            e.source_element.as_ref(),
+        )),
+        E::Keyboard(e) if !e.is_key_down => Err(SkipReason::CoveredByRawInput),
+        E::Keyboard(e) if MODIFIER_KEYS.contains(&e.key_code) => Err(SkipReason::CoveredByRawInput),
+        E::Keyboard(e) if e.key_code == 0 && e.character.is_none() => Err(SkipReason::Redacted),
+        E::Keyboard(e) => match key_input(e) {
+            Some(key) => Ok((Input::Key(key), e.metadata.ui_element.as_ref())),
+            None => Err(SkipReason::Unsupported),
+        },
+        E::Hotkey(_) if raw_keys => Err(SkipReason::CoveredByRawInput),
+        E::Hotkey(e) => match key_sequence(&e.combination) {
+            Some(key) => Ok((Input::Key(key), None)),
+            None => Err(SkipReason::Unsupported),
+        },
+        _ => Err(SkipReason::NotInput),
+    }
+}
+
+/// Selector that finds the element again, if it has a usable name
+fn selector(element: &SerializableUIElement) -> Option<String> {
+    let name = element.name.as_deref().map(str::trim)?;
+    let role = element.role.trim();
+    (!name.is_empty() && plain_text(name) && !role.is_empty())
+        .then(|| format!("role:{role} && name:{name}"))
+}
+
+/// Virtual-key codes of keys without a character, by `press_key` name
+const NAMED_KEYS: &[(u32, &str)] = &[
+    (0x08, "Backspace"),
+    (0x09, "Tab"),
+    (0x0D, "Enter"),
+    (0x1B, "Esc"),
+    (0x21, "PageUp"),
+    (0x22, "PageDown"),
+    (0x23, "End"),
+    (0x24, "Home"),
+    (0x25, "Left"),
+    (0x26, "Up"),
+    (0x27, "Right"),
+    (0x28, "Down"),
+    (0x2D, "Insert"),
+    (0x2E, "Delete"),
+];
+
+/// The keystroke in `press_key` syntax: the typed character, or the key with
+/// its modifiers, e.g. `{Ctrl}{S}`
+fn key_input(key: &SerializableKeyboardEvent) -> Option<String> {
+    let named = NAMED_KEYS
+        .iter()
+        .find(|(code, _)| *code == key.key_code)
+        .map(|(_, name)| name.to_string())
+        .or_else(|| {
+            (0x70..=0x87)
+                .contains(&key.key_code)
+                .then(|| format!("F{}", key.key_code - 0x6F))
+        });
+    let chord = key.ctrl_pressed || key.alt_pressed || key.win_pressed;
+
+    if !chord && named.is_none() {
+        let character = key.character.filter(|c| !c.is_control())?;
+        // Braces and parentheses are syntax in `press_key`
+        return Some(match character {
+            '{' | '}' | '(' | ')' => format!("{{{character}}}"),
+            c => c.to_string(),
+        });
+    }
+
+    let base = named.or_else(|| match key.key_code {
+        0x30..=0x39 | 0x41..=0x5A => char::from_u32(key.key_code).map(String::from),
+        _ => key
+            .character
+            .filter(|c| !c.is_control())
+            .map(|c| c.to_uppercase().to_string()),
+    })?;
+    let modifiers = [
+        (key.ctrl_pressed, "{Ctrl}"),
+        (key.alt_pressed, "{Alt}"),
+        (key.shift_pressed, "{Shift}"),
+        (key.win_pressed, "{Win}"),
+    ];
+    let mut sequence: String = modifiers
+        .iter()
+        .filter(|(held, _)| *held)
+        .map(|(_, name)| *name)
+        .collect();
+    sequence.push_str(&format!("{{{base}}}"));
+    Some(sequence)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::events::SerializableEventMetadata;
+
+    fn key(
+        key_code: u32,
+        character: Option<char>,
+        ctrl: bool,
+        shift: bool,
+    ) -> SerializableKeyboardEvent {
+        SerializableKeyboardEvent {
+            key_code,
+            is_key_down: true,
+            ctrl_pressed: ctrl,
+            alt_pressed: false,
+            shift_pressed: shift,
+            win_pressed: false,
+            character,
+            scan_code: None,
+            metadata: SerializableEventMetadata {
+                ui_element: None,
+                timestamp: None,
+            },
+        }
+    }
+
+    #[test]
+    fn keystrokes_in_press_key_syntax() {
+        assert_eq!(key_input(&key(0x41, Some('a'), false, false)).unwrap(), "a");
+        assert_eq!(key_input(&key(0x41, Some('A'), false, true)).unwrap(), "A");
+        assert_eq!(
+            key_input(&key(0x39, Some('('), false, true)).unwrap(),
+            "{(}"
+        );
+        assert_eq!(
+            key_input(&key(0x0D, Some('\r'), false, false)).unwrap(),
+            "{Enter}"
+        );
+        assert_eq!(
+            key_input(&key(0x09, None, false, true)).unwrap(),
+            "{Shift}{Tab}"
+        );
+        assert_eq!(
+            key_input(&key(0x53, Some('\u{13}'), true, false)).unwrap(),
+            "{Ctrl}{S}"
+        );
+        assert_eq!(
+            key_input(&key(0x53, None, true, true)).unwrap(),
+            "{Ctrl}{Shift}{S}"
+        );
+        assert_eq!(key_input(&key(0x73, None, false, false)).unwrap(), "{F4}");
+        assert_eq!(key_input(&key(0xAD, None, false, false)), None);
+    }
+
+    fn monitor(id: &str, x: i32, width: u32, height: u32, is_primary: bool) -> Monitor {
+        Monitor {
+            id: id.to_string(),
+            name: format!("Display {id}"),
+            is_primary,
+            width,
+            height,
+            x,
+            y: 0,
+            scale_factor: 1.0,
+            work_area: None,
+        }
+    }
+
+    #[test]
+    fn points_keep_their_place_on_their_monitor() {
+        let map = MonitorMap::new(
+            vec![
+                monitor("1", 0, 1920, 1080, true),
+                monitor("2", 1920, 1920, 1080, false),
+            ],
+            vec![
+                monitor("2", -2560, 2560, 1440, false),
+                monitor("1", 0, 2560, 1440, true),
+            ],
+        );
+        assert_eq!(map.map(960.0, 540.0), (1280.0, 720.0));
+        assert_eq!(map.map(1920.0 + 480.0, 270.0), (-2560.0 + 640.0, 360.0));
+        // Off every recorded monitor, and without a layout
+        assert_eq!(map.map(-50.0, 10.0), (-50.0, 10.0));
+        assert_eq!(MonitorMap::default().map(5.0, 6.0), (5.0, 6.0));
+    }
+}
@@ -0,0 +1,1059 @@
+//! Cleans up a recording and splits it into named intents.
+//!
+//! [`postprocess`] takes a [`SerializableRecordedWorkflow`] and drops the noise
+//! that gets past the recorder's throttles: mouse movement, keystrokes undone with
+//! Backspace, text typed and then retyped in the same field, the second click of a
+//! double click, repeated clicks, the keys of an Alt+Tab and the windows passed on
+//! the way, and switches that end where they started. What is left is split into
+//! [`Intent`]s at application and window boundaries and idle gaps, and each intent
+//! is named after what happens in it ("Log in to SAP", "Fill shipping form").
+//!
+//! Like [`compile`](crate::compile) this only looks at the recording, so it is
+//! deterministic and runs on any platform. Every dropped event is listed with the
+//! reason, and [`ProcessedRecording::to_workflow`] turns the cleaned events back
+//! into a recording that `compile` accepts.
+
+use crate::compiler::{browser_process, near, process_name, BROWSERS};
+use crate::events::{
+    ApplicationSwitchMethod, MouseEventType, SerializableRecordedEvent,
+    SerializableRecordedWorkflow, SerializableWorkflowEvent, TabAction,
+};
+use crate::redaction::is_password_selector;
+use serde::{Deserialize, Serialize};
+use std::ops::Range;
+
+const VK_BACK: u32 = 8;
+const VK_TAB: u32 = 9;
+const ALT_KEYS: &[u32] = &[18, 164, 165];
+/// Shift, Ctrl, Alt and Windows keys
+pub(crate) const MODIFIER_KEYS: &[u32] = &[16, 17, 18, 91, 92, 160, 161, 162, 163, 164, 165];
+/// Keys that move the caret. A Backspace after them no longer deletes the last
+/// typed character.
+const CARET_KEYS: &[u32] = &[9, 13, 33, 34, 35, 36, 37, 38, 39, 40, 45, 46];
+
+/// Note on a click that was the first of a double click
+const DOUBLE_CLICK: &str = "double click";
+
+/// Display names of common applications, by process name
+const APP_NAMES: &[(&str, &str)] = &[
+    ("saplogon", "SAP"),
+    ("sapgui", "SAP"),
+    ("chrome", "Chrome"),
+    ("msedge", "Edge"),
+    ("firefox", "Firefox"),
+    ("notepad", "Notepad"),
+    ("excel", "Excel"),
+    ("winword", "Word"),
+    ("outlook", "Outlook"),
+    ("olk", "Outlook"),
+    ("explorer", "File Explorer"),
+    ("calculatorapp", "Calculator"),
+    ("windowsterminal", "Terminal"),
+];
+
+/// Options for [`postprocess`]
+#[derive(Debug, Clone)]
+pub struct PostProcessOptions {
+    /// A pause at least this long starts a new intent
+    pub idle_gap_ms: u64,
+    /// Clicks on the same element with nothing in between and closer together than
+    /// this are one click
+    pub duplicate_click_ms: u64,
+    /// How far from a double click its clicks can be reported
+    pub double_click_ms: u64,
+    /// How long before and after an Alt+Tab switch its Alt and Tab keys can be
+    pub alt_tab_ms: u64,
+}
+
+impl Default for PostProcessOptions {
+    fn default() -> Self {
+        Self {
+            idle_gap_ms: 20_000,
+            duplicate_click_ms: 1000,
+            double_click_ms: 1500,
+            alt_tab_ms: 3000,
+        }
+    }
+}
+
+/// Why an event was dropped
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DropReason {
+    /// Mouse movement
+    Hover,
+    /// Keystrokes undone with Backspace, or text that was typed again later
+    Correction,
+    /// Alt and Tab keys of an application switch, or a window passed on the way
+    AltTab,
+    /// A switch to what was already active, away and straight back, or with
+    /// nothing done afterwards
+    NoOpFocusChange,
+    /// The second report of a double click
+    DoubleClick,
+    /// A click repeated on the same element
+    DuplicateClick,
+}
+
+/// What started an intent
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum IntentBoundary {
+    /// The start of the recording
+    Start,
+    /// The user moved to another application
+    ApplicationSwitch,
+    /// The user moved to another window of the application, or another site
+    WindowChange,
+    /// The user paused for at least [`PostProcessOptions::idle_gap_ms`]
+    IdleGap,
+}
+
+/// A stretch of the recording that does one thing
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Intent {
+    /// What the user does, e.g. "Log in to SAP"
+    pub name: String,
+    pub boundary: IntentBoundary,
+    /// Process name, without `.exe`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub application: Option<String>,
+    /// Last window title seen in the intent
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub window: Option<String>,
+    pub start_time: u64,
+    pub end_time: u64,
+    /// Indices into [`ProcessedRecording::events`]
+    pub events: Range<usize>,
+}
+
+/// An event that was kept
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AnnotatedEvent {
+    /// Position in the original recording
+    pub index: usize,
+    pub timestamp: u64,
+    /// Index into [`ProcessedRecording::intents`]
+    pub intent: usize,
+    /// What was merged into the event
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub notes: Vec<String>,
+    pub event: SerializableWorkflowEvent,
+}
+
+/// An event that was dropped
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DroppedEvent {
+    /// Position in the original recording
+    pub index: usize,
+    pub timestamp: u64,
+    pub reason: DropReason,
+}
+
+/// A cleaned recording split into intents
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProcessedRecording {
+    pub name: String,
+    pub start_time: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub end_time: Option<u64>,
+    pub intents: Vec<Intent>,
+    pub events: Vec<AnnotatedEvent>,
+    pub dropped: Vec<DroppedEvent>,
+}
+
+impl ProcessedRecording {
+    /// The events of an intent
+    pub fn intent_events(&self, intent: &Intent) -> &[AnnotatedEvent] {
+        &self.events[intent.events.clone()]
+    }
+
+    /// The kept events as a recording, e.g. for [`compile`](crate::compile)
+    pub fn to_workflow(&self) -> SerializableRecordedWorkflow {
+        SerializableRecordedWorkflow {
+            format_version: crate::FORMAT_VERSION,
+            name: self.name.clone(),
+            start_time: self.start_time,
+            end_time: self.end_time,
+            events: self
+                .events
+                .iter()
+                .map(|e| SerializableRecordedEvent {
+                    timestamp: e.timestamp,
+                    event: e.event.clone(),
+                })
+                .collect(),
+        }
+    }
+}
+
+/// Clean up a recording and split it into intents. See the [module docs](self).
+pub fn postprocess(
+    workflow: &SerializableRecordedWorkflow,
+    options: &PostProcessOptions,
+) -> ProcessedRecording {
+    let mut cleaner = Cleaner {
+        options,
+        events: workflow.events.clone(),
+        dropped: vec![None; workflow.events.len()],
+        notes: vec![Vec::new(); workflow.events.len()],
+    };
+    cleaner.hovers();
+    cleaner.keystroke_corrections();
+    cleaner.double_clicks();
+    cleaner.duplicate_clicks();
+    cleaner.retyped_text();
+    cleaner.alt_tab_keys();
+    cleaner.focus_changes();
+    cleaner.finish(workflow)
+}
+
+struct Cleaner<'a> {
+    options: &'a PostProcessOptions,
+    events: Vec<SerializableRecordedEvent>,
+    dropped: Vec<Option<DropReason>>,
+    notes: Vec<Vec<String>>,
+}
+
+impl Cleaner<'_> {
+    fn drop_event(&mut self, index: usize, reason: DropReason) {
+        self.dropped[index].get_or_insert(reason);
+    }
+
+    fn note(&mut self, index: usize, note: String) {
+        if !self.notes[index].contains(&note) {
+            self.notes[index].push(note);
+        }
+    }
+
+    fn is_kept(&self, index: usize) -> bool {
+        self.dropped[index].is_none()
+    }
+
+    fn timestamp(&self, index: usize) -> u64 {
+        self.events[index].timestamp
+    }
+
+    /// The next kept event after `index` that is not passive
+    fn next_action(&self, index: usize) -> Option<usize> {
+        (index + 1..self.events.len())
+            .find(|&i| self.is_kept(i) && !is_passive(&self.events[i].event))
+    }
+
+    /// The key-up that ends the key-down at `down`
+    fn key_up(&self, down: usize) -> Option<usize> {
+        let SerializableWorkflowEvent::Keyboard(key) = &self.events[down].event else {
+            return None;
+        };
+        let key_code = key.key_code;
+        (down + 1..self.events.len()).find(|&i| {
+            matches!(&self.events[i].event,
+                SerializableWorkflowEvent::Keyboard(k) if k.key_code == key_code && !k.is_key_down)
+        })
+    }
+
+    fn hovers(&mut self) {
+        for i in 0..self.events.len() {
+            if let SerializableWorkflowEvent::Mouse(e) = &self.events[i].event {
+                if e.event_type == MouseEventType::Move {
+                    self.drop_event(i, DropReason::Hover);
+                }
+            }
+        }
+    }
+
+    /// Drops each Backspace together with the character it deleted, as long as
+    /// the caret has not moved since the character was typed
+    fn keystroke_corrections(&mut self) {
+        let mut typed: Vec<usize> = Vec::new();
+        for i in 0..self.events.len() {
+            let (key_code, chord, character) = match &self.events[i].event {
+                SerializableWorkflowEvent::Keyboard(k) if k.is_key_down => (
+                    k.key_code,
+                    k.ctrl_pressed || k.alt_pressed || k.win_pressed,
+                    k.character.is_some(),
+                ),
+                SerializableWorkflowEvent::Keyboard(_) => continue,
+                SerializableWorkflowEvent::Mouse(e) if e.event_type == MouseEventType::Move => {
+                    continue
+                }
+                _ => {
+                    typed.clear();
+                    continue;
+                }
+            };
+            if MODIFIER_KEYS.contains(&key_code) {
+                continue;
+            }
+            if key_code == VK_BACK && !chord {
+                if let Some(deleted) = typed.pop() {
+                    for down in [deleted, i] {
+                        self.drop_event(down, DropReason::Correction);
+                        if let Some(up) = self.key_up(down) {
+                            self.drop_event(up, DropReason::Correction);
+                        }
+                    }
+                }
+            } else if chord || !character || CARET_KEYS.contains(&key_code) {
+                typed.clear();
+            } else {
+                typed.push(i);
+            }
+        }
+    }
+
+    /// The recorder reports both clicks of a double click. The first `Click` is
+    /// kept (the `DoubleClick` mouse event after it makes it a double click), and
+    /// for browser clicks the one flagged `is_double_click`.
+    fn double_clicks(&mut self) {
+        let window = self.options.double_click_ms;
+        for d in 0..self.events.len() {
+            let ts = self.timestamp(d);
+            let in_window = |i: &usize| self.timestamp(*i).abs_diff(ts) <= window;
+            match &self.events[d].event {
+                SerializableWorkflowEvent::Mouse(e)
+                    if e.event_type == MouseEventType::DoubleClick =>
+                {
+                    let position = Some(e.position);
+                    let is_click = |i: &usize| {
+                        self.is_kept(*i)
+                            && matches!(&self.events[*i].event,
+                                SerializableWorkflowEvent::Click(c) if near(c.click_position, position))
+                    };
+                    let first = (0..d).rev().take_while(in_window).find(is_click);
+                    let second = (d + 1..self.events.len())
+                        .take_while(in_window)
+                        .find(is_click);
+                    match (first, second) {
+                        (Some(first), Some(second)) => {
+                            self.drop_event(second, DropReason::DoubleClick);
+                            self.note(first, DOUBLE_CLICK.to_string());
+                        }
+                        (Some(click), None) | (None, Some(click)) => {
+                            self.note(click, DOUBLE_CLICK.to_string())
+                        }
+                        (None, None) => {}
+                    }
+                }
+                SerializableWorkflowEvent::BrowserClick(e) if e.is_double_click => {
+                    let position = Some(e.position);
+                    let first = (0..d).rev().take_while(in_window).find(|&i| {
+                        self.is_kept(i)
+                            && matches!(&self.events[i].event,
+                                SerializableWorkflowEvent::BrowserClick(b)
+                                    if !b.is_double_click && near(Some(b.position), position))
+                    });
+                    if let Some(first) = first {
+                        self.drop_event(first, DropReason::DoubleClick);
+                    }
+                    self.note(d, DOUBLE_CLICK.to_string());
+                }
+                _ => {}
+            }
+        }
+    }
+
+    fn duplicate_clicks(&mut self) {
+        for i in 0..self.events.len() {
+            if !self.is_kept(i) {
+                continue;
+            }
+            let Some(key) = click_key(&self.events[i].event) else {
+                continue;
+            };
+            // The recorder reports a click in a browser twice, as a Click and as a
+            // BrowserClick; skip the other report
+            let mut next = self.next_action(i);
+            while let Some(j) = next {
+                match click_key(&self.events[j].event) {
+                    Some(other) if other.0 != key.0 && is_companion(&self.events, i, j) => {
+                        next = self.next_action(j)
+                    }
+                    _ => break,
+                }
+            }
+            let Some(j) = next else {
+                continue;
+            };
+            if self.timestamp(j) - self.timestamp(i) <= self.options.duplicate_click_ms
+                && click_key(&self.events[j].event).as_ref() == Some(&key)
+            {
+                self.drop_event(j, DropReason::DuplicateClick);
+            }
+        }
+    }
+
+    /// Keeps only the last of several texts typed into the same field, with clicks
+    /// back into the field in between
+    fn retyped_text(&mut self) {
+        let mut merged = vec![0usize; self.events.len()];
+        for i in 0..self.events.len() {
+            if !self.is_kept(i) {
+                continue;
+            }
+            let Some(field) = field_key(&self.events[i].event) else {
+                continue;
+            };
+            let mut clicks = Vec::new();
+            let mut next = self.next_action(i);
+            while let Some(j) = next {
+                if click_field_key(&self.events[j].event).as_ref() != Some(&field) {
+                    break;
+                }
+                clicks.push(j);
+                next = self.next_action(j);
+            }
+            let Some(j) = next else {
+                continue;
+            };
+            if field_key(&self.events[j].event).as_ref() != Some(&field) {
+                continue;
+            }
+            for click in clicks {
+                self.drop_event(click, DropReason::Correction);
+            }
+            self.drop_event(i, DropReason::Correction);
+            merged[j] = merged[i] + 1;
+
+            let earlier = self.events[i].clone();
+            let end = self.timestamp(j);
+            if let (
+                SerializableWorkflowEvent::TextInputCompleted(typed),
+                SerializableWorkflowEvent::TextInputCompleted(later),
+            ) = (&earlier.event, &mut self.events[j].event)
+            {
+                let start = earlier.timestamp.saturating_sub(typed.typing_duration_ms);
+                later.typing_duration_ms = end.saturating_sub(start);
+                later.keystroke_count += typed.keystroke_count;
+                later.focus_method = typed.focus_method.clone();
+            }
+        }
+        for (i, count) in merged.into_iter().enumerate() {
+            if count > 0 && self.is_kept(i) {
+                self.note(
+                    i,
+                    format!("merged {} text inputs into the final text", count + 1),
+                );
+            }
+        }
+    }
+
+    fn alt_tab_keys(&mut self) {
+        let window = self.options.alt_tab_ms;
+        for i in 0..self.events.len() {
+            let SerializableWorkflowEvent::ApplicationSwitch(e) = &self.events[i].event else {
+                continue;
+            };
+            if e.switch_method != ApplicationSwitchMethod::AltTab {
+                continue;
+            }
+            let ts = self.timestamp(i);
+            let part_of_switch = |j: &usize| {
+                let event = &self.events[*j].event;
+                is_alt_tab_key(event)
+                    || is_passive(event)
+                    || matches!(event, SerializableWorkflowEvent::ApplicationSwitch(_))
+            };
+            let before = (0..i)
+                .rev()
+                .take_while(|&j| self.timestamp(j) + window >= ts)
+                .take_while(part_of_switch);
+            let after = (i + 1..self.events.len())
+                .take_while(|&j| self.timestamp(j) <= ts + window)
+                .take_while(part_of_switch);
+            let keys: Vec<usize> = before
+                .chain(after)
+                .filter(|&j| is_alt_tab_key(&self.events[j].event))
+                .collect();
+            for j in keys {
+                self.drop_event(j, DropReason::AltTab);
+            }
+        }
+    }
+
+    /// Collapses switches with nothing done in between into one switch, and drops
+    /// switches that end where they started or that nothing follows
+    fn focus_changes(&mut self) {
+        let mut app: Option<String> = None;
+        let mut url: Option<String> = None;
+        let mut chain: Vec<usize> = Vec::new();
+        for i in 0..self.events.len() {
+            if !self.is_kept(i) {
+                continue;
+            }
+            match &self.events[i].event {
+                SerializableWorkflowEvent::ApplicationSwitch(e) => {
+                    let to = e.to_process_name.as_deref().map(process_name);
+                    if chain.is_empty() && to.is_some() && to == app {
+                        self.drop_event(i, DropReason::NoOpFocusChange);
+                    } else {
+                        chain.push(i);
+                    }
+                    continue;
+                }
+                SerializableWorkflowEvent::BrowserTabNavigation(e)
+                    if e.action == TabAction::Switched && e.to_url.is_some() && e.to_url == url =>
+                {
+                    self.drop_event(i, DropReason::NoOpFocusChange);
+                    continue;
+                }
+                event if is_passive(event) || is_alt_tab_key(event) => continue,
+                _ => {}
+            }
+            app = self.close_chain(&mut chain, app, false);
+            let event = &self.events[i].event;
+            if let Some(process) = event_process(event) {
+                app = Some(process);
+            }
+            if let Some(page) = event_url(event) {
+                url = Some(page.to_string());
+            }
+        }
+        self.close_chain(&mut chain, app, true);
+    }
+
+    fn close_chain(
+        &mut self,
+        chain: &mut Vec<usize>,
+        app: Option<String>,
+        at_end: bool,
+    ) -> Option<String> {
+        let (Some(&first), Some(&last)) = (chain.first(), chain.last()) else {
+            return app;
+        };
+        let SerializableWorkflowEvent::ApplicationSwitch(first_switch) =
+            self.events[first].event.clone()
+        else {
+            return app;
+        };
+        let origin = first_switch
+            .from_process_name
+            .as_deref()
+            .map(process_name)
+            .or(app);
+        let destination = match &self.events[last].event {
+            SerializableWorkflowEvent::ApplicationSwitch(e) => {
+                e.to_process_name.as_deref().map(process_name)
+            }
+            _ => None,
+        };
+
+        if at_end || (destination.is_some() && destination == origin) {
+            for &i in chain.iter() {
+                self.drop_event(i, DropReason::NoOpFocusChange);
+            }
+            chain.clear();
+            return origin;
+        }
+
+        for &i in &chain[..chain.len() - 1] {
+            let reason = match &self.events[i].event {
+                SerializableWorkflowEvent::ApplicationSwitch(e)
+                    if e.switch_method == ApplicationSwitchMethod::AltTab =>
+                {
+                    DropReason::AltTab
+                }
+                _ => DropReason::NoOpFocusChange,
+            };
+            self.drop_event(i, reason);
+        }
+        if chain.len() > 1 {
+            let count = chain.len();
+            if let SerializableWorkflowEvent::ApplicationSwitch(e) = &mut self.events[last].event {
+                e.from_window_and_application_name = first_switch.from_window_and_application_name;
+                e.from_process_name = first_switch.from_process_name;
+                e.from_process_id = first_switch.from_process_id;
+                e.switch_count = Some(count as u32);
+            }
+            self.note(last, format!("collapsed {count} application switches"));
+        }
+        chain.clear();
+        destination.or(origin)
+    }
+
+    fn finish(self, workflow: &SerializableRecordedWorkflow) -> ProcessedRecording {
+        let mut events: Vec<AnnotatedEvent> = Vec::new();
+        let mut dropped = Vec::new();
+        let mut intents: Vec<Intent> = Vec::new();
+        let mut context = Context::default();
+        let mut has_action = false;
+
+        let kept = self.events.into_iter().zip(self.dropped).zip(self.notes);
+        for (index, ((recorded, reason), notes)) in kept.enumerate() {
+            let timestamp = recorded.timestamp;
+            if let Some(reason) = reason {
+                dropped.push(DroppedEvent {
+                    index,
+                    timestamp,
+                    reason,
+                });
+                continue;
+            }
+            let seen = Context::of(&recorded.event);
+            let boundary = match events.last() {
+                None => Some(IntentBoundary::Start),
+                // Switching somewhere and moving on belongs with what follows
+                Some(_) if !has_action => None,
+                Some(previous) => context.boundary(
+                    &seen,
+                    timestamp.saturating_sub(previous.timestamp),
+                    self.options,
+                ),
+            };
+            context.update(seen);
+            if let Some(boundary) = boundary {
+                intents.push(Intent {
+                    name: String::new(),
+                    boundary,
+                    application: None,
+                    window: None,
+                    start_time: timestamp,
+                    end_time: timestamp,
+                    events: events.len()..events.len(),
+                });
+                has_action = false;
+            }
+            has_action |= !is_passive(&recorded.event)
+                && !matches!(
+                    recorded.event,
+                    SerializableWorkflowEvent::ApplicationSwitch(_)
+                );
+
+            let intent = intents.last_mut().expect("an intent was started");
+            intent.end_time = timestamp;
+            intent.events.end = events.len() + 1;
+            intent.application = context.app.clone();
+            intent.window = context.window.clone();
+            events.push(AnnotatedEvent {
+                index,
+                timestamp,
+                intent: intents.len() - 1,
+                notes,
+                event: recorded.event,
+            });
+        }
+
+        for intent in &mut intents {
+            intent.name = name_intent(intent, &events[intent.events.clone()]);
+        }
+        ProcessedRecording {
+            name: workflow.name.clone(),
+            start_time: workflow.start_time,
+            end_time: workflow.end_time,
+            intents,
+            events,
+            dropped,
+        }
+    }
+}
+
+/// Where the user is, as far as the events so far tell
+#[derive(Debug, Default)]
+struct Context {
+    app: Option<String>,
+    window: Option<String>,
+    host: Option<String>,
+}
+
+impl Context {
+    fn of(event: &SerializableWorkflowEvent) -> Self {
+        let ui_window = |metadata: &crate::SerializableEventMetadata| {
+            metadata
+                .ui_element
+                .as_ref()
+                .and_then(|el| el.window_title.clone())
+        };
+        let process = event_process(event);
+        let host = event_url(event).and_then(host);
+        let window = match event {
+            SerializableWorkflowEvent::ApplicationSwitch(e) => {
+                Some(e.to_window_and_application_name.clone())
+            }
+            SerializableWorkflowEvent::Click(e) => ui_window(&e.metadata),
+            SerializableWorkflowEvent::TextInputCompleted(e) => ui_window(&e.metadata),
+            SerializableWorkflowEvent::FileOpened(e) => Some(e.window_title.clone()),
+            _ => None,
+        };
+        Self {
+            app: process,
+            window: window.filter(|w| !w.trim().is_empty()),
+            host,
+        }
+    }
+
+    fn boundary(
+        &self,
+        seen: &Context,
+        gap_ms: u64,
+        options: &PostProcessOptions,
+    ) -> Option<IntentBoundary> {
+        if seen.app.is_some() && self.app.is_some() && seen.app != self.app {
+            return Some(IntentBoundary::ApplicationSwitch);
+        }
+        if let (Some(seen), Some(current)) = (&seen.host, &self.host) {
+            if seen != current {
+                return Some(IntentBoundary::WindowChange);
+            }
+        }
+        if let (Some(seen), Some(current)) = (&seen.window, &self.window) {
+            if !same_window(seen, current) {
+                return Some(IntentBoundary::WindowChange);
+            }
+        }
+        (gap_ms >= options.idle_gap_ms).then_some(IntentBoundary::IdleGap)
+    }
+
+    fn update(&mut self, seen: Context) {
+        if seen.app.is_some() && seen.app != self.app {
+            *self = Context {
+                app: seen.app,
+                ..Context::default()
+            };
+        }
+        if seen.window.is_some() {
+            self.window = seen.window;
+        }
+        if seen.host.is_some() {
+            self.host = seen.host;
+        }
+    }
+}
+
+/// Names an intent after the most telling thing that happens in it
+fn name_intent(intent: &Intent, events: &[AnnotatedEvent]) -> String {
+    let app = intent.application.as_deref();
+    let is_browser = app.is_some_and(|app| BROWSERS.contains(&app));
+    let site = events
+        .iter()
+        .rev()
+        .find_map(|e| event_url(&e.event))
+        .and_then(host);
+    let place = match (is_browser, &site, app) {
+        (true, Some(site), _) => site.clone(),
+        (_, _, Some(app)) => display_name(app),
+        _ => intent
+            .window
+            .clone()
+            .unwrap_or_else(|| "the desktop".to_string()),
+    };
+
+    let mut password = false;
+    let mut saved = false;
+    let mut navigated = false;
+    let mut opened = None;
+    let mut fields: Vec<String> = Vec::new();
+    let mut clicks: Vec<(String, bool)> = Vec::new();
+    for e in events {
+        let double = e.notes.iter().any(|n| n == DOUBLE_CLICK);
+        match &e.event {
+            SerializableWorkflowEvent::TextInputCompleted(t) => {
+                let field = t
+                    .field_name
+                    .clone()
+                    .or_else(|| t.metadata.ui_element.as_ref()?.name.clone())
+                    .unwrap_or_else(|| t.field_type.clone());
+                if is_browser && field.to_lowercase().contains("address") {
+                    navigated = true;
+                    continue;
+                }
+                password |= t.is_password || field.to_lowercase().contains("password");
+                if !fields.contains(&field) {
+                    fields.push(field);
+                }
+            }
+            SerializableWorkflowEvent::BrowserTextInput(t) => {
+                password |= is_password_selector(&t.selector);
+                let field = t
+                    .dom_element
+                    .as_ref()
+                    .and_then(|d| d.aria_label.clone())
+                    .unwrap_or_else(|| t.selector.clone());
+                if !fields.contains(&field) {
+                    fields.push(field);
+                }
+            }
+            SerializableWorkflowEvent::Hotkey(h) => {
+                saved |= h.action.as_deref() == Some("Save")
+                    || h.combination.eq_ignore_ascii_case("ctrl+s");
+            }
+            SerializableWorkflowEvent::FileOpened(f) => opened = Some(f.filename.clone()),
+            SerializableWorkflowEvent::Click(c) => {
+                let text = c
+                    .metadata
+                    .ui_element
+                    .as_ref()
+                    .and_then(|el| el.name.clone())
+                    .unwrap_or_else(|| c.element_text.clone());
+                clicks.push((text, double));
+            }
+            SerializableWorkflowEvent::BrowserClick(b) => {
+                let text = b
+                    .dom_element
+                    .as_ref()
+                    .and_then(|d| d.aria_label.clone().or_else(|| d.inner_text.clone()))
+                    .unwrap_or_default();
+                clicks.push((text, double || b.is_double_click));
+            }
+            SerializableWorkflowEvent::BrowserTabNavigation(n) if n.to_url.is_some() => {
+                navigated = true
+            }
+            _ => {}
+        }
+    }
+
+    if password {
+        return format!("Log in to {place}");
+    }
+    if fields.len() >= 2 {
+        return match intent.window.as_deref().and_then(form_label) {
+            Some(label) => format!("Fill {label} form"),
+            None => format!("Fill form in {place}"),
+        };
+    }
+    if let Some(file) = opened {
+        return format!("Open {file}");
+    }
+    if let [field] = fields.as_slice() {
+        let field = sentence_case(field.trim());
+        return if field.to_lowercase().contains("search") {
+            format!("Search in {place}")
+        } else {
+            format!("Enter {field} in {place}")
+        };
+    }
+    if saved {
+        return format!("Save in {place}");
+    }
+    match clicks.as_slice() {
+        [(text, double)] if !text.trim().is_empty() => {
+            let verb = if *double { "Double-click" } else { "Click" };
+            return format!("{verb} {} in {place}", text.trim());
+        }
+        [] if navigated => {
+            if let Some(site) = site {
+                return format!("Open {site}");
+            }
+        }
+        _ => {}
+    }
+    format!("Use {place}")
+}
+
+/// Events other than these never mean the user did something
+fn is_passive(event: &SerializableWorkflowEvent) -> bool {
+    match event {
+        SerializableWorkflowEvent::Mouse(e) => matches!(
+            e.event_type,
+            MouseEventType::Move | MouseEventType::Down | MouseEventType::Up
+        ),
+        SerializableWorkflowEvent::Keyboard(e) => MODIFIER_KEYS.contains(&e.key_code),
+        SerializableWorkflowEvent::PendingAction(_) => true,
+        _ => false,
+    }
+}
+
+fn is_alt_tab_key(event: &SerializableWorkflowEvent) -> bool {
+    match event {
+        SerializableWorkflowEvent::Keyboard(k) => {
+            ALT_KEYS.contains(&k.key_code) || (k.key_code == VK_TAB && k.alt_pressed)
+        }
+        SerializableWorkflowEvent::Hotkey(h) => {
+            let combination = h.combination.to_lowercase().replace(' ', "");
+            combination == "alt+tab" || combination == "alt+shift+tab"
+        }
+        _ => false,
+    }
+}
+
+/// Identifies the element a click landed on. The first part tells UI Automation
+/// clicks (0) from browser clicks (1).
+fn click_key(event: &SerializableWorkflowEvent) -> Option<(u8, String)> {
+    match event {
+        SerializableWorkflowEvent::Click(c) => Some((
+            0,
+            click_field_key(event).unwrap_or_else(|| c.element_text.clone()),
+        )),
+        SerializableWorkflowEvent::BrowserClick(b) => {
+            let selector = b
+                .dom_element
+                .as_ref()
+                .map(|d| d.css_selector.clone())
+                .or_else(|| b.selectors.first().map(|s| s.selector.clone()))
+                .unwrap_or_else(|| format!("{},{}", b.position.x, b.position.y));
+            Some((1, format!("{}|{selector}", b.page_url)))
+        }
+        _ => None,
+    }
+}
+
+/// Whether `j` is the browser's report of the click at `i`, or the other way round
+fn is_companion(events: &[SerializableRecordedEvent], i: usize, j: usize) -> bool {
+    let position = |event: &SerializableWorkflowEvent| match event {
+        SerializableWorkflowEvent::Click(c) => c.click_position,
+        SerializableWorkflowEvent::BrowserClick(b) => Some(b.position),
+        _ => None,
+    };
+    let (a, b) = (position(&events[i].event), position(&events[j].event));
+    a.is_some() && b.is_some() && near(a, b)
+}
+
+/// Identifies the field a text input went into
+fn field_key(event: &SerializableWorkflowEvent) -> Option<String> {
+    match event {
+        SerializableWorkflowEvent::TextInputCompleted(t) => {
+            let ui_element = t.metadata.ui_element.as_ref();
+            let role = ui_element.map_or(t.field_type.as_str(), |el| el.role.as_str());
+            let name = t
+                .field_name
+                .as_deref()
+                .or_else(|| ui_element?.name.as_deref())
+                .unwrap_or_default();
+            Some(format!(
+                "{}|{role}|{name}",
+                process_name(t.process_name.as_deref().unwrap_or_default())
+            ))
+        }
+        SerializableWorkflowEvent::BrowserTextInput(t) => {
+            Some(format!("browser|{}|{}", t.page_url, t.selector))
+        }
+        _ => None,
+    }
+}
+
+/// Same as [`field_key`], for a click on the field
+fn click_field_key(event: &SerializableWorkflowEvent) -> Option<String> {
+    let SerializableWorkflowEvent::Click(c) = event else {
+        return None;
+    };
+    let ui_element = c.metadata.ui_element.as_ref();
+    let role = ui_element.map_or(c.element_role.as_str(), |el| el.role.as_str());
+    let name = ui_element
+        .and_then(|el| el.name.as_deref())
+        .unwrap_or(&c.element_text);
+    Some(format!(
+        "{}|{role}|{name}",
+        process_name(c.process_name.as_deref().unwrap_or_default())
+    ))
+}
+
+/// Process an event happened in, without `.exe`
+fn event_process(event: &SerializableWorkflowEvent) -> Option<String> {
+    let process = match event {
+        SerializableWorkflowEvent::ApplicationSwitch(e) => e.to_process_name.as_deref(),
+        SerializableWorkflowEvent::Click(e) => e.process_name.as_deref(),
+        SerializableWorkflowEvent::TextInputCompleted(e) => e.process_name.as_deref(),
+        SerializableWorkflowEvent::Hotkey(e) => e.process_name.as_deref(),
+        SerializableWorkflowEvent::FileOpened(e) => e.process_name.as_deref(),
+        SerializableWorkflowEvent::BrowserTabNavigation(e) => {
+            return Some(browser_process(&e.browser))
+        }
+        _ => None,
+    };
+    process
+        .map(process_name)
+        .filter(|process| !process.is_empty())
+}
+
+/// Page an event happened on
+fn event_url(event: &SerializableWorkflowEvent) -> Option<&str> {
+    match event {
+        SerializableWorkflowEvent::Click(e) => e.page_url.as_deref(),
+        SerializableWorkflowEvent::TextInputCompleted(e) => {
+            e.metadata.ui_element.as_ref()?.url.as_deref()
+        }
+        SerializableWorkflowEvent::BrowserTabNavigation(e) => e.to_url.as_deref(),
+        SerializableWorkflowEvent::BrowserClick(e) => Some(e.page_url.as_str()),
+        SerializableWorkflowEvent::BrowserTextInput(e) => Some(e.page_url.as_str()),
+        _ => None,
+    }
+    .filter(|url| !url.is_empty())
+}
+
+/// `https://www.shop.example.com/cart?x=1` → `shop.example.com`
+pub(crate) fn host(url: &str) -> Option<String> {
+    let rest = url.split_once("://").map_or(url, |(_, rest)| rest);
+    let host = rest.split(['/', '?', '#', ':']).next()?.trim();
+    let host = host.strip_prefix("www.").unwrap_or(host);
+    (!host.is_empty()).then(|| host.to_lowercase())
+}
+
+/// Whether two titles belong to the same window. Titles of document windows
+/// change with the document ("*Untitled - Notepad", "notes.txt - Notepad") but
+/// keep the application part after the last " - ".
+fn same_window(a: &str, b: &str) -> bool {
+    let a = a.trim().trim_start_matches('*');
+    let b = b.trim().trim_start_matches('*');
+    a == b
+        || matches!((a.rsplit_once(" - "), b.rsplit_once(" - ")),
+            (Some((_, a)), Some((_, b))) if a == b)
+}
+
+/// "Shipping - Contoso Shop - Google Chrome" → "shipping"
+fn form_label(window: &str) -> Option<String> {
+    let title = window.trim().trim_start_matches('*');
+    let first = [" - ", " | ", " – ", " — "]
+        .iter()
+        .filter_map(|separator| title.find(separator))
+        .min()
+        .map_or(title, |end| &title[..end])
+        .trim();
+    let first = first
+        .strip_suffix(" form")
+        .or_else(|| first.strip_suffix(" Form"))
+        .unwrap_or(first);
+    (!first.is_empty()).then(|| sentence_case(first))
+}
+
+/// Lowercases the first letter unless the word is an acronym
+fn sentence_case(text: &str) -> String {
+    let mut chars = text.chars();
+    match (chars.next(), chars.next()) {
+        (Some(first), Some(second)) if first.is_uppercase() && second.is_lowercase() => {
+            first.to_lowercase().chain(text.chars().skip(1)).collect()
+        }
+        _ => text.to_string(),
+    }
+}
+
+pub(crate) fn display_name(process: &str) -> String {
+    if let Some((_, name)) = APP_NAMES.iter().find(|(p, _)| *p == process) {
+        return name.to_string();
+    }
+    let mut chars = process.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn window_titles_and_labels() {
+        assert!(same_window("Untitled - Notepad", "*Hello world - Notepad"));
+        assert!(!same_window("Hello world - Notepad", "Save As"));
+        assert!(!same_window("SAP", "SAP Easy Access"));
+
+        assert_eq!(
+            form_label("Shipping - Contoso Shop - Google Chrome").as_deref(),
+            Some("shipping")
+        );
+        assert_eq!(
+            form_label("Customer details form").as_deref(),
+            Some("customer details")
+        );
+        assert_eq!(
+            form_label("VAT registration").as_deref(),
+            Some("VAT registration")
+        );
+
+        assert_eq!(
+            host("https://www.Shop.example.com:8443/cart?x=1").as_deref(),
+            Some("shop.example.com")
+        );
+        assert_eq!(display_name("saplogon"), "SAP");
+        assert_eq!(display_name("acmecrm"), "Acmecrm");
+    }
+}
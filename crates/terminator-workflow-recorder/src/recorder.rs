@@ -1,4 +1,7 @@
-use crate::{RecordedWorkflow, Result, WorkflowEvent, WorkflowRecorderError};
+use crate::{
+    EventSink, RecordedWorkflow, Result, SinkConfig, WorkflowEvent, WorkflowRecorderError,
+};
+use serde::{Deserialize, Serialize};
 use std::{
     collections::{HashSet, VecDeque},
     path::Path,
@@ -18,7 +21,8 @@ pub mod browser_context;
 pub use self::windows::*;
 
 /// Performance mode for the workflow recorder
-#[derive(Debug, Clone, PartialEq, Default)]
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
 pub enum PerformanceMode {
     /// Default behavior - captures all events with full detail
     #[default]
@@ -77,7 +81,11 @@ impl PerformanceMode {
 }
 
 /// Configuration for the workflow recorder
-#[derive(Debug, Clone)]
+///
+/// Deserializes from a partial object: missing fields take their default
+/// values, unknown fields are rejected.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
 pub struct WorkflowRecorderConfig {
     /// Whether to record mouse events
     pub record_mouse: bool,
@@ -171,6 +179,10 @@ pub struct WorkflowRecorderConfig {
     /// Reduce expensive UI element capture operations
     pub reduce_ui_element_capture: bool,
 
+    /// Snapshot the window on clicks and store scored selector candidates
+    /// (see `synthesize_selectors`) in the click event
+    pub synthesize_selectors: bool,
+
     // Visual highlighting options
     /// Enable real-time visual highlighting during recording
     pub enable_highlighting: bool,
@@ -190,6 +202,10 @@ pub struct WorkflowRecorderConfig {
     /// Older highlights are automatically closed when this limit is reached
     /// Default: 10
     pub highlight_max_concurrent: usize,
+
+    /// Where events are streamed while recording, so a long recording
+    /// survives a crash before it is saved (see `replay_jsonl`)
+    pub sinks: Vec<SinkConfig>,
 }
 
 impl Default for WorkflowRecorderConfig {
@@ -220,12 +236,14 @@ impl Default for WorkflowRecorderConfig {
             filter_mouse_noise: false,
             filter_keyboard_noise: false,
             reduce_ui_element_capture: false,
+            synthesize_selectors: true,
             // Highlighting defaults
             enable_highlighting: false,
             highlight_color: Some(0x0000FF),  // Red in BGR
             highlight_duration_ms: Some(500), // 500ms
             show_highlight_labels: true,
             highlight_max_concurrent: 10,
+            sinks: Vec::new(),
         }
     }
 }
@@ -271,6 +289,11 @@ impl WorkflowRecorderConfig {
         self.filter_keyboard_noise || matches!(self.performance_mode, PerformanceMode::LowEnergy)
     }
 
+    /// Check if clicks should get synthesized selector candidates
+    pub fn should_synthesize_selectors(&self) -> bool {
+        self.synthesize_selectors && self.capture_ui_elements && !self.should_reduce_ui_capture()
+    }
+
     /// Check if UI element capture should be reduced
     pub fn should_reduce_ui_capture(&self) -> bool {
         self.reduce_ui_element_capture
@@ -301,6 +324,9 @@ pub struct WorkflowRecorder {
 
     /// Handle to the highlighting task
     highlight_task_handle: Option<tokio::task::JoinHandle<()>>,
+
+    /// Running event sinks
+    sinks: Vec<EventSink>,
 }
 
 impl WorkflowRecorder {
@@ -317,9 +343,18 @@ impl WorkflowRecorder {
             windows_recorder: None,
             highlight_handles: Arc::new(tokio::sync::Mutex::new(VecDeque::new())),
             highlight_task_handle: None,
+            sinks: Vec::new(),
         }
     }
 
+    /// URLs of the running WebSocket sinks, with the token their clients need
+    pub fn sink_urls(&self) -> Vec<String> {
+        self.sinks
+            .iter()
+            .filter_map(|sink| sink.url().map(str::to_string))
+            .collect()
+    }
+
     /// Get a stream of events
     pub fn event_stream(&self) -> impl Stream<Item = WorkflowEvent> {
         let mut rx = self.event_tx.subscribe();
@@ -363,6 +398,9 @@ impl WorkflowRecorder {
             let workflow = Arc::clone(&self.workflow);
             let event_tx = self.event_tx.clone();
 
+            // Subscribe the sinks before the first event can be sent
+            self.start_sinks().await?;
+
             // Start the Windows recorder
             let windows_recorder = WindowsRecorder::new(self.config.clone(), event_tx).await?;
             self.windows_recorder = Some(windows_recorder);
@@ -494,13 +532,36 @@ impl WorkflowRecorder {
         }
 
         // Mark the workflow as finished
+        let mut end_time = None;
         if let Ok(mut workflow) = self.workflow.lock() {
             workflow.finish();
+            end_time = workflow.end_time;
+        }
+
+        // Let the sinks write what is still buffered and end their streams
+        for sink in self.sinks.drain(..) {
+            sink.finish(end_time.unwrap_or_default()).await;
         }
 
         Ok(())
     }
 
+    /// Start the sinks of the configuration
+    async fn start_sinks(&mut self) -> Result<()> {
+        let (name, start_time) = {
+            let workflow = self.workflow.lock().map_err(|e| {
+                WorkflowRecorderError::InitializationError(format!("Failed to lock workflow: {e}"))
+            })?;
+            (workflow.name.clone(), workflow.start_time)
+        };
+        for config in &self.config.sinks {
+            let sink =
+                EventSink::start(config, &name, start_time, self.event_tx.subscribe()).await?;
+            self.sinks.push(sink);
+        }
+        Ok(())
+    }
+
     /// Save the recorded workflow to a file
     pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<()> {
         info!("Saving workflow recording to {:?}", path.as_ref());
@@ -3,6 +3,7 @@ use crate::events::{
     TabNavigationMethod,
 };
 use crate::recorder::browser_context::BrowserContextRecorder;
+use crate::synthesizer::{find_node_path, synthesize_selectors};
 use crate::{
     ApplicationSwitchMethod, ClipboardAction, ClipboardEvent, EventMetadata, HotkeyEvent,
     KeyboardEvent, MouseButton, MouseEvent, MouseEventType, Position, Result, WorkflowEvent,
@@ -22,7 +23,7 @@ use std::{
 static UIA_TRAVERSAL_COUNT: AtomicUsize = AtomicUsize::new(0);
 static UIA_TRAVERSAL_TOTAL: AtomicUsize = AtomicUsize::new(0);
 use sysinfo::{Pid, ProcessesToUpdate, System};
-use terminator::{convert_uiautomation_element_to_terminator, UIElement};
+use terminator::{convert_uiautomation_element_to_terminator, UIElement, UINode};
 
 use tokio::runtime::Runtime;
 use tokio::sync::broadcast;
@@ -42,6 +43,10 @@ pub mod structs;
 use structs::*;
 
 // Bundle related parameters for UIA button press handling to keep function signatures small
+/// Elements read from a window to synthesize selectors for one click
+const SELECTOR_SNAPSHOT_MAX_NODES: usize = 800;
+const SELECTOR_SNAPSHOT_MAX_DEPTH: usize = 25;
+
 struct ButtonPressContext<'a> {
     position: &'a Position,
     config: &'a WorkflowRecorderConfig,
@@ -124,6 +129,55 @@ impl WindowsRecorder {
             .as_millis() as u64
     }
 
+    /// Scored selector candidates for a clicked element, from a snapshot of its window
+    fn synthesize_element_selectors(element: &UIElement) -> Vec<crate::SelectorCandidate> {
+        let Some(window) = element.window().ok().flatten() else {
+            return Vec::new();
+        };
+        let mut budget = SELECTOR_SNAPSHOT_MAX_NODES;
+        let root = Self::snapshot_node(&window, 0, &mut budget);
+
+        let target_id = element.id();
+        let role = element.role();
+        let name = element.name();
+        let path = find_node_path(&root, |node| target_id.is_some() && node.id == target_id)
+            .or_else(|| {
+                find_node_path(&root, |node| {
+                    node.attributes.role == role && node.attributes.name == name
+                })
+            });
+        match path {
+            Some(path) => synthesize_selectors(&root, &path),
+            None => {
+                debug!(
+                    "Clicked element '{}' not found in a {}-node window snapshot, no selector candidates",
+                    element.name_or_empty(),
+                    SELECTOR_SNAPSHOT_MAX_NODES - budget
+                );
+                Vec::new()
+            }
+        }
+    }
+
+    fn snapshot_node(element: &UIElement, depth: usize, budget: &mut usize) -> UINode {
+        *budget = budget.saturating_sub(1);
+        let mut children = Vec::new();
+        if depth < SELECTOR_SNAPSHOT_MAX_DEPTH {
+            for child in element.children().unwrap_or_default() {
+                if *budget == 0 {
+                    break;
+                }
+                children.push(Self::snapshot_node(&child, depth + 1, budget));
+            }
+        }
+        UINode {
+            id: element.id(),
+            attributes: element.attributes(),
+            children,
+            selector: None,
+        }
+    }
+
     /// Get process name from a UI element's process ID
     fn get_process_name_from_element(element: &UIElement) -> Option<String> {
         let process_id = element.process_id().ok()?;
@@ -2468,6 +2522,7 @@ impl WindowsRecorder {
                                     process_name: Self::get_process_name_from_element(
                                         &text_input.element,
                                     ),
+                                    is_password: text_input.element.is_password().unwrap_or(false),
                                     metadata: EventMetadata::with_ui_element_and_timestamp(Some(
                                         text_input.element.clone(),
                                     )),
@@ -2531,6 +2586,10 @@ impl WindowsRecorder {
                                     process_name: Self::get_process_name_from_element(
                                         &temp_tracker.element,
                                     ),
+                                    is_password: temp_tracker
+                                        .element
+                                        .is_password()
+                                        .unwrap_or(false),
                                     metadata: EventMetadata::with_ui_element_and_timestamp(Some(
                                         temp_tracker.element.clone(),
                                     )),
@@ -2663,6 +2722,7 @@ impl WindowsRecorder {
                                                         ),
                                                         specificity: sc.specificity,
                                                         requires_jquery: sc.requires_jquery,
+                                                        score: None,
                                                     })
                                                     .collect(),
                                             };
@@ -2766,6 +2826,11 @@ impl WindowsRecorder {
                         relative_position,
                         process_name: Self::get_process_name_from_element(element),
                         page_url,
+                        selector_candidates: if ctx.config.should_synthesize_selectors() {
+                            Self::synthesize_element_selectors(element)
+                        } else {
+                            Vec::new()
+                        },
                         metadata: EventMetadata::with_ui_element_and_timestamp(Some(
                             element.clone(),
                         )),
@@ -2802,6 +2867,7 @@ impl WindowsRecorder {
                 relative_position: None,
                 process_name: None,
                 page_url: None, // No element available to get URL from
+                selector_candidates: Vec::new(),
                 metadata: EventMetadata {
                     ui_element: None,
                     timestamp: Some(Self::capture_timestamp()),
@@ -3301,6 +3367,11 @@ impl WindowsRecorder {
                     relative_position: None, // No relative position for keyboard-triggered clicks
                     process_name: Self::get_process_name_from_element(&element),
                     page_url,
+                    selector_candidates: if config.should_synthesize_selectors() {
+                        Self::synthesize_element_selectors(&element)
+                    } else {
+                        Vec::new()
+                    },
                     metadata: EventMetadata::with_ui_element_and_timestamp(Some(element.clone())),
                 };
 
@@ -236,6 +236,7 @@ impl TextInputTracker {
             typing_duration_ms,
             keystroke_count: self.keystroke_count,
             process_name: Self::get_process_name_from_element(&self.element),
+            is_password: self.element.is_password().unwrap_or(false),
             metadata: EventMetadata::with_ui_element_and_timestamp(Some(self.element.clone())),
         })
     }
@@ -0,0 +1,497 @@
+//! Redaction of recordings before they are written
+//!
+//! Applies a [`Redactor`] to a [`SerializableRecordedWorkflow`]. Text typed into
+//! password fields or opted-out applications is masked together with the
+//! keystrokes that produced it. The same goes for text matching a redaction
+//! pattern. Clipboard content and element values from those fields and
+//! applications are dropped, and patterns are applied to every other piece of
+//! captured text. Keystrokes are tied to a field by the typing window of its
+//! `TextInputCompleted` event, or by their own element when it is sensitive.
+
+use crate::{
+    BrowserClickEvent, BrowserTextInputEvent, SerializableEventMetadata, SerializableKeyboardEvent,
+    SerializableRecordedEvent, SerializableRecordedWorkflow, SerializableUIElement,
+    SerializableWorkflowEvent,
+};
+use terminator::redaction::{Redactor, REDACTED};
+use terminator::UIElement;
+
+/// Keystrokes this long before typing started still belong to the field
+const TYPING_WINDOW_SLACK_MS: u64 = 500;
+
+/// Virtual-key codes that never carry typed content and are kept as is:
+/// backspace, tab, enter, modifiers, escape, navigation, delete, Windows keys
+/// and function keys
+fn is_control_key(key_code: u32) -> bool {
+    matches!(
+        key_code,
+        0x08 | 0x09 | 0x0D | 0x10..=0x14 | 0x1B | 0x21..=0x28 | 0x2E | 0x5B | 0x5C | 0x70..=0x87 | 0xA0..=0xA5
+    )
+}
+
+impl SerializableRecordedWorkflow {
+    /// Redacts the recording in place and returns the number of masked values
+    pub fn redact(&mut self, redactor: &Redactor) -> usize {
+        if !redactor.is_enabled() {
+            return 0;
+        }
+        let mut pass = RedactionPass {
+            redactor,
+            masked: 0,
+        };
+        pass.keystrokes(&mut self.events);
+        for event in &mut self.events {
+            pass.event(&mut event.event);
+        }
+        pass.masked
+    }
+}
+
+struct RedactionPass<'a> {
+    redactor: &'a Redactor,
+    masked: usize,
+}
+
+impl RedactionPass<'_> {
+    /// Masks the keystrokes typed into sensitive fields, and into fields whose
+    /// text matched a pattern
+    fn keystrokes(&mut self, events: &mut [SerializableRecordedEvent]) {
+        let windows: Vec<(u64, u64)> = events
+            .iter()
+            .filter_map(|recorded| match &recorded.event {
+                SerializableWorkflowEvent::TextInputCompleted(e) => {
+                    let sensitive = self.text_input_is_sensitive(e)
+                        || !self.redactor.find_matches(&e.text_value).is_empty();
+                    sensitive.then(|| {
+                        let end = e.metadata.timestamp.unwrap_or(recorded.timestamp);
+                        let start =
+                            end.saturating_sub(e.typing_duration_ms + TYPING_WINDOW_SLACK_MS);
+                        (start, end)
+                    })
+                }
+                _ => None,
+            })
+            .collect();
+        if windows.is_empty() {
+            return;
+        }
+        for recorded in events.iter_mut() {
+            let SerializableWorkflowEvent::Keyboard(key) = &mut recorded.event else {
+                continue;
+            };
+            let at = key.metadata.timestamp.unwrap_or(recorded.timestamp);
+            if windows
+                .iter()
+                .any(|(start, end)| (*start..=*end).contains(&at))
+            {
+                self.mask_key(key);
+            }
+        }
+    }
+
+    fn mask_key(&mut self, key: &mut SerializableKeyboardEvent) {
+        if key.character.is_none() && (key.key_code == 0 || is_control_key(key.key_code)) {
+            return;
+        }
+        key.character = None;
+        key.key_code = 0;
+        key.scan_code = None;
+        self.masked += 1;
+    }
+
+    fn text_input_is_sensitive(&self, e: &crate::SerializableTextInputCompletedEvent) -> bool {
+        let password = self.redactor.masks_passwords()
+            && (e.is_password
+                || e.field_type.to_lowercase().contains("password")
+                || e.metadata
+                    .ui_element
+                    .as_ref()
+                    .is_some_and(|el| el.is_password));
+        password
+            || self.excluded(e.process_name.as_deref())
+            || e.metadata
+                .ui_element
+                .as_ref()
+                .is_some_and(|el| self.excluded(el.application.as_deref()))
+    }
+
+    fn event(&mut self, event: &mut SerializableWorkflowEvent) {
+        match event {
+            SerializableWorkflowEvent::Mouse(e) => {
+                self.metadata(&mut e.metadata);
+            }
+            SerializableWorkflowEvent::Keyboard(e) => {
+                // Streamed events are redacted one at a time, before the
+                // field's typing window is known
+                if self.metadata(&mut e.metadata) {
+                    self.mask_key(e);
+                }
+            }
+            SerializableWorkflowEvent::Hotkey(e) => {
+                self.metadata(&mut e.metadata);
+            }
+            SerializableWorkflowEvent::Clipboard(e) => {
+                if self.metadata(&mut e.metadata) {
+                    if e.content.take().is_some() {
+                        self.masked += 1;
+                    }
+                } else {
+                    self.text_opt(&mut e.content);
+                }
+            }
+            SerializableWorkflowEvent::TextSelection(e) => {
+                if self.metadata(&mut e.metadata) {
+                    self.mask(&mut e.selected_text);
+                } else {
+                    self.text(&mut e.selected_text);
+                }
+            }
+            SerializableWorkflowEvent::DragDrop(e) => {
+                let source = e.source_element.as_mut().is_some_and(|el| self.element(el));
+                if self.metadata(&mut e.metadata) || source {
+                    self.mask_opt(&mut e.content);
+                } else {
+                    self.text_opt(&mut e.content);
+                }
+            }
+            SerializableWorkflowEvent::TextInputCompleted(e) => {
+                let sensitive = self.text_input_is_sensitive(e);
+                self.metadata(&mut e.metadata);
+                if sensitive {
+                    self.mask(&mut e.text_value);
+                } else {
+                    self.text(&mut e.text_value);
+                }
+                self.text_opt(&mut e.field_name);
+            }
+            SerializableWorkflowEvent::ApplicationSwitch(e) => {
+                self.metadata(&mut e.metadata);
+                self.text_opt(&mut e.from_window_and_application_name);
+                self.text(&mut e.to_window_and_application_name);
+            }
+            SerializableWorkflowEvent::BrowserTabNavigation(e) => {
+                self.metadata(&mut e.metadata);
+                self.text_opt(&mut e.to_url);
+                self.text_opt(&mut e.from_url);
+                self.text_opt(&mut e.to_title);
+                self.text_opt(&mut e.from_title);
+            }
+            SerializableWorkflowEvent::Click(e) => {
+                self.metadata(&mut e.metadata);
+                self.text(&mut e.element_text);
+                for text in &mut e.child_text_content {
+                    self.text(text);
+                }
+                self.text_opt(&mut e.element_description);
+                self.text_opt(&mut e.page_url);
+            }
+            SerializableWorkflowEvent::BrowserClick(e) => self.browser_click(e),
+            SerializableWorkflowEvent::BrowserTextInput(e) => self.browser_text_input(e),
+            SerializableWorkflowEvent::FileOpened(e) => {
+                self.text(&mut e.window_title);
+            }
+            SerializableWorkflowEvent::PendingAction(_) => {}
+        }
+    }
+
+    fn browser_click(&mut self, e: &mut BrowserClickEvent) {
+        let mut sensitive = self.drop_sensitive_element(&mut e.ui_element);
+        sensitive |= self.drop_sensitive_element(&mut e.metadata.ui_element);
+        if let Some(dom) = e.dom_element.as_mut() {
+            if sensitive || is_password_selector(&dom.css_selector) {
+                self.mask_opt(&mut dom.input_value);
+            } else {
+                self.text_opt(&mut dom.input_value);
+            }
+            self.text_opt(&mut dom.inner_text);
+        }
+        self.text(&mut e.page_url);
+        self.text(&mut e.page_title);
+    }
+
+    fn browser_text_input(&mut self, e: &mut BrowserTextInputEvent) {
+        let mut sensitive = self.drop_sensitive_element(&mut e.metadata.ui_element);
+        sensitive |= self.redactor.masks_passwords()
+            && (is_password_selector(&e.selector)
+                || e.dom_element
+                    .as_ref()
+                    .is_some_and(|dom| is_password_selector(&dom.css_selector)));
+        if let Some(dom) = e.dom_element.as_mut() {
+            if sensitive {
+                self.mask_opt(&mut dom.input_value);
+            } else {
+                self.text_opt(&mut dom.input_value);
+            }
+        }
+        if sensitive {
+            self.mask(&mut e.text);
+        } else {
+            self.text(&mut e.text);
+        }
+        self.text(&mut e.page_url);
+        self.text(&mut e.page_title);
+    }
+
+    /// Live elements can't be edited, so sensitive ones are left out entirely
+    fn drop_sensitive_element(&mut self, element: &mut Option<UIElement>) -> bool {
+        let sensitive = element.as_ref().is_some_and(|el| {
+            (self.redactor.masks_passwords() && el.is_password().unwrap_or(false))
+                || self.excluded(Some(&el.application_name()))
+        });
+        if sensitive {
+            *element = None;
+            self.masked += 1;
+        }
+        sensitive
+    }
+
+    /// Redacts the event's element; returns whether it is sensitive
+    fn metadata(&mut self, metadata: &mut SerializableEventMetadata) -> bool {
+        metadata
+            .ui_element
+            .as_mut()
+            .is_some_and(|el| self.element(el))
+    }
+
+    fn element(&mut self, element: &mut SerializableUIElement) -> bool {
+        let sensitive = (self.redactor.masks_passwords() && element.is_password)
+            || self.excluded(element.application.as_deref());
+        if sensitive {
+            self.mask_opt(&mut element.value);
+        } else {
+            self.text_opt(&mut element.value);
+        }
+        self.text_opt(&mut element.name);
+        self.text_opt(&mut element.description);
+        self.text_opt(&mut element.window_title);
+        self.text_opt(&mut element.url);
+        sensitive
+    }
+
+    fn excluded(&self, application: Option<&str>) -> bool {
+        application.is_some_and(|app| self.redactor.is_excluded_application(app))
+    }
+
+    fn text(&mut self, text: &mut String) {
+        if self.redactor.redact_string(text) {
+            self.masked += 1;
+        }
+    }
+
+    fn text_opt(&mut self, text: &mut Option<String>) {
+        if let Some(text) = text {
+            self.text(text);
+        }
+    }
+
+    fn mask(&mut self, text: &mut String) {
+        if !text.is_empty() && text != REDACTED {
+            *text = REDACTED.to_string();
+            self.masked += 1;
+        }
+    }
+
+    fn mask_opt(&mut self, text: &mut Option<String>) {
+        if let Some(text) = text {
+            self.mask(text);
+        }
+    }
+}
+
+pub(crate) fn is_password_selector(selector: &str) -> bool {
+    let selector = selector.to_lowercase().replace(['"', '\'', ' '], "");
+    selector.contains("type=password")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        FieldFocusMethod, SerializableClipboardEvent, SerializableTextInputCompletedEvent,
+        TextInputMethod,
+    };
+    use terminator::redaction::RedactionConfig;
+
+    fn metadata(
+        timestamp: u64,
+        element: Option<SerializableUIElement>,
+    ) -> SerializableEventMetadata {
+        SerializableEventMetadata {
+            ui_element: element,
+            timestamp: Some(timestamp),
+        }
+    }
+
+    fn edit(name: &str, application: &str, is_password: bool) -> SerializableUIElement {
+        SerializableUIElement {
+            id: None,
+            role: "Edit".to_string(),
+            name: Some(name.to_string()),
+            bounds: None,
+            value: Some("typed value".to_string()),
+            description: None,
+            application: Some(application.to_string()),
+            window_title: None,
+            url: None,
+            process_id: None,
+            is_password,
+        }
+    }
+
+    fn key(timestamp: u64, key_code: u32, down: bool) -> SerializableRecordedEvent {
+        SerializableRecordedEvent {
+            timestamp,
+            event: SerializableWorkflowEvent::Keyboard(SerializableKeyboardEvent {
+                key_code,
+                is_key_down: down,
+                ctrl_pressed: false,
+                alt_pressed: false,
+                shift_pressed: false,
+                win_pressed: false,
+                character: (down && (32..=126).contains(&key_code))
+                    .then_some(key_code as u8 as char),
+                scan_code: None,
+                metadata: metadata(timestamp, None),
+            }),
+        }
+    }
+
+    fn typed(
+        timestamp: u64,
+        text: &str,
+        field: SerializableUIElement,
+    ) -> SerializableRecordedEvent {
+        SerializableRecordedEvent {
+            timestamp,
+            event: SerializableWorkflowEvent::TextInputCompleted(
+                SerializableTextInputCompletedEvent {
+                    text_value: text.to_string(),
+                    field_name: field.name.clone(),
+                    field_type: "Edit".to_string(),
+                    input_method: TextInputMethod::Typed,
+                    focus_method: FieldFocusMethod::MouseClick,
+                    typing_duration_ms: 300,
+                    keystroke_count: text.len() as u32,
+                    process_name: field.application.clone(),
+                    is_password: false,
+                    metadata: metadata(timestamp, Some(field)),
+                },
+            ),
+        }
+    }
+
+    fn workflow(events: Vec<SerializableRecordedEvent>) -> SerializableRecordedWorkflow {
+        SerializableRecordedWorkflow {
+            format_version: crate::FORMAT_VERSION,
+            name: "login".to_string(),
+            start_time: 0,
+            end_time: None,
+            events,
+        }
+    }
+
+    fn redactor(yaml: &str) -> Redactor {
+        Redactor::new(RedactionConfig::parse(yaml).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn password_text_and_its_keystrokes_are_masked() {
+        let mut recording = workflow(vec![
+            key(1_000, 0x48, true),
+            key(1_050, 0x48, false),
+            key(1_100, 0x0D, true),
+            typed(1_200, "hunter2", edit("Password", "app.exe", true)),
+            key(5_000, 0x41, true),
+            typed(5_100, "alice", edit("User", "app.exe", false)),
+        ]);
+        let masked = recording.redact(&Redactor::default());
+        let keys: Vec<(u32, Option<char>)> = recording
+            .events
+            .iter()
+            .filter_map(|e| match &e.event {
+                SerializableWorkflowEvent::Keyboard(k) => Some((k.key_code, k.character)),
+                _ => None,
+            })
+            .collect();
+        // Enter is kept; the keystroke typed into the user name field is not touched
+        assert_eq!(
+            keys,
+            vec![(0, None), (0, None), (0x0D, None), (0x41, Some('A'))]
+        );
+
+        let texts: Vec<(&str, Option<&str>)> = recording
+            .events
+            .iter()
+            .filter_map(|e| match &e.event {
+                SerializableWorkflowEvent::TextInputCompleted(t) => Some((
+                    t.text_value.as_str(),
+                    t.metadata.ui_element.as_ref().unwrap().value.as_deref(),
+                )),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(
+            texts,
+            vec![(REDACTED, Some(REDACTED)), ("alice", Some("typed value"))]
+        );
+        assert_eq!(masked, 4);
+    }
+
+    #[test]
+    fn patterns_and_excluded_applications() {
+        let r = redactor("patterns: [card_number, email]\nexcluded_applications: [KeePass]");
+        let mut recording = workflow(vec![
+            key(900, 0x34, true),
+            typed(
+                1_000,
+                "4111 1111 1111 1111",
+                edit("Card", "shop.exe", false),
+            ),
+            typed(2_000, "correct horse", edit("Entry", "KeePass.exe", false)),
+            SerializableRecordedEvent {
+                timestamp: 3_000,
+                event: SerializableWorkflowEvent::Clipboard(SerializableClipboardEvent {
+                    action: crate::ClipboardAction::Copy,
+                    content: Some("mail jane@contoso.com".to_string()),
+                    content_size: Some(21),
+                    format: None,
+                    truncated: false,
+                    metadata: metadata(3_000, None),
+                }),
+            },
+        ]);
+        recording.redact(&r);
+
+        let SerializableWorkflowEvent::Keyboard(k) = &recording.events[0].event else {
+            panic!("expected keyboard event");
+        };
+        assert_eq!(k.character, None);
+        let SerializableWorkflowEvent::TextInputCompleted(card) = &recording.events[1].event else {
+            panic!("expected text input");
+        };
+        assert_eq!(card.text_value, "[REDACTED:card_number]");
+        let SerializableWorkflowEvent::TextInputCompleted(vault) = &recording.events[2].event
+        else {
+            panic!("expected text input");
+        };
+        assert_eq!(vault.text_value, REDACTED);
+        let SerializableWorkflowEvent::Clipboard(clip) = &recording.events[3].event else {
+            panic!("expected clipboard event");
+        };
+        assert_eq!(clip.content.as_deref(), Some("mail [REDACTED:email]"));
+
+        let mut untouched = workflow(vec![typed(
+            1_000,
+            "hunter2",
+            edit("Password", "app.exe", true),
+        )]);
+        assert_eq!(untouched.redact(&Redactor::disabled()), 0);
+    }
+
+    #[test]
+    fn password_selectors() {
+        assert!(is_password_selector("input[type=\"password\"]"));
+        assert!(is_password_selector("#login > INPUT[type='Password']"));
+        assert!(!is_password_selector("input[name=password-hint]"));
+    }
+}
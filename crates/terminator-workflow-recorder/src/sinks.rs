@@ -0,0 +1,832 @@
+//! Streaming recorded events out while recording
+//!
+//! The recorder keeps a recording in memory until it is saved, so a crash loses
+//! the whole session. Sinks listed in
+//! [`WorkflowRecorderConfig::sinks`](crate::WorkflowRecorderConfig::sinks)
+//! receive every event as it is recorded, redacted like a saved recording, as
+//! one JSON [`JournalEntry`] per line:
+//!
+//! - [`SinkConfig::Jsonl`] appends to rotating files and flushes after every
+//!   burst of events; [`replay_jsonl`] rebuilds the recording from them
+//! - [`SinkConfig::WebSocket`] serves the entries to live dashboards, which can
+//!   reconnect and resume from the last `seq` they saw. Clients need the
+//!   session token in the URL the recorder logs, and browsers need an allowed
+//!   origin
+//! - [`SinkConfig::Stdout`] writes NDJSON for piping into other tools
+//!
+//! A sink never slows the recorder down: if it falls behind the recorder's
+//! event buffer, it writes how many events it missed as
+//! [`JournalEntry::Lagged`] and carries on.
+
+use crate::format::{self, LoadReport};
+use crate::{
+    RecordedEvent, Result, SerializableRecordedWorkflow, SerializableWorkflowEvent, WorkflowEvent,
+    WorkflowRecorderError, FORMAT_VERSION,
+};
+use futures::{SinkExt, StreamExt};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::VecDeque;
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime};
+use tokio::io::{AsyncWrite, AsyncWriteExt, BufWriter};
+use tokio::net::TcpListener;
+use tokio::sync::{broadcast, mpsc, oneshot};
+use tokio::task::JoinHandle;
+use tokio_tungstenite::tungstenite::handshake::server::{ErrorResponse, Request, Response};
+use tokio_tungstenite::tungstenite::http::header::ORIGIN;
+use tokio_tungstenite::tungstenite::http::StatusCode;
+use tokio_tungstenite::tungstenite::Message;
+use tracing::{debug, error, info, warn};
+
+/// Attempts to reopen a journal file after a failed write
+const JSONL_RETRIES: u32 = 5;
+
+/// Where a recording's events are streamed while recording
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum SinkConfig {
+    /// Rotating append-only JSONL files
+    Jsonl(JsonlSinkConfig),
+    /// A local WebSocket server broadcasting the entries
+    #[serde(rename = "websocket")]
+    WebSocket(WebSocketSinkConfig),
+    /// NDJSON on standard output. Not for the MCP agent on the stdio
+    /// transport, where stdout carries the protocol
+    Stdout,
+}
+
+/// Configuration of [`SinkConfig::Jsonl`]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct JsonlSinkConfig {
+    /// First file of the journal; later files are numbered, e.g. `session.1.jsonl`.
+    /// It must not exist yet
+    pub path: PathBuf,
+
+    /// Start a new file once the current one reaches this size
+    pub max_file_bytes: u64,
+
+    /// How often written entries are synced to disk (milliseconds). Entries
+    /// reach the OS after every burst of events, so a crash of the process
+    /// loses nothing; a power loss loses at most this interval
+    pub sync_interval_ms: u64,
+}
+
+impl Default for JsonlSinkConfig {
+    fn default() -> Self {
+        Self {
+            path: PathBuf::from("recording.jsonl"),
+            max_file_bytes: 64 * 1024 * 1024,
+            sync_interval_ms: 1000,
+        }
+    }
+}
+
+/// Configuration of [`SinkConfig::WebSocket`]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct WebSocketSinkConfig {
+    /// Address to listen on; clients connect to the [`EventSink::url`], e.g.
+    /// `ws://<address>/?token=<token>`, and add `&since=<seq>` to resume after
+    /// the entry they saw last
+    pub address: String,
+
+    /// Origins of the web pages allowed to connect, e.g. `http://localhost:3000`.
+    /// Handshakes with any other `Origin` header are rejected; clients that
+    /// send none, i.e. not browsers, only need the token
+    pub allowed_origins: Vec<String>,
+
+    /// Entries queued per client; a client that falls this far behind is
+    /// disconnected and has to reconnect with `since`
+    pub client_buffer: usize,
+
+    /// Recent entries kept for clients that reconnect
+    pub replay_buffer: usize,
+}
+
+impl Default for WebSocketSinkConfig {
+    fn default() -> Self {
+        Self {
+            address: "127.0.0.1:17380".to_string(),
+            allowed_origins: Vec::new(),
+            client_buffer: 256,
+            replay_buffer: 1000,
+        }
+    }
+}
+
+/// One line written by a sink
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum JournalEntry {
+    /// First entry of every file and connection
+    Start {
+        format_version: u32,
+        name: String,
+        start_time: u64,
+    },
+    /// A recorded event; `seq` counts the recorder's events from 1, so a gap
+    /// means the sink missed events
+    Event {
+        seq: u64,
+        timestamp: u64,
+        event: Box<SerializableWorkflowEvent>,
+    },
+    /// The sink fell behind and missed `skipped` events
+    Lagged { skipped: u64 },
+    /// The recording was stopped
+    End { end_time: u64 },
+}
+
+/// What a sink wrote
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct SinkStats {
+    /// Events written
+    pub written: u64,
+    /// Events missed because the sink fell behind
+    pub lagged: u64,
+    /// Events that could not be written, e.g. after stdout was closed
+    pub dropped: u64,
+    /// Journal files reopened after a failed write
+    pub reconnects: u64,
+}
+
+/// A running sink, see the [module docs](self)
+pub struct EventSink {
+    description: String,
+    local_addr: Option<SocketAddr>,
+    url: Option<String>,
+    shutdown: oneshot::Sender<u64>,
+    task: JoinHandle<SinkStats>,
+}
+
+impl EventSink {
+    /// Start streaming `events` of the recording `name` that started at `start_time`
+    pub async fn start(
+        config: &SinkConfig,
+        name: &str,
+        start_time: u64,
+        events: broadcast::Receiver<WorkflowEvent>,
+    ) -> Result<Self> {
+        let start = line(&JournalEntry::Start {
+            format_version: FORMAT_VERSION,
+            name: name.to_string(),
+            start_time,
+        })?;
+        let (output, description, local_addr, url) = match config {
+            SinkConfig::Jsonl(config) => (
+                Output::Jsonl(JsonlOutput::create(config.clone(), start.clone()).await?),
+                format!("JSONL journal {}", config.path.display()),
+                None,
+                None,
+            ),
+            SinkConfig::WebSocket(config) => {
+                let server = WebSocketOutput::bind(config, start.clone()).await?;
+                let addr = server.local_addr;
+                let url = format!("ws://{addr}/?token={}", server.token);
+                (
+                    Output::WebSocket(server),
+                    format!("WebSocket server {url}"),
+                    Some(addr),
+                    Some(url),
+                )
+            }
+            SinkConfig::Stdout => (
+                Output::Stdout(Some(BufWriter::new(tokio::io::stdout()))),
+                "stdout".to_string(),
+                None,
+                None,
+            ),
+        };
+        info!("Streaming recorded events to {description}");
+
+        let (shutdown, shutdown_rx) = oneshot::channel();
+        let task = tokio::spawn(run(output, start, events, shutdown_rx));
+        Ok(Self {
+            description,
+            local_addr,
+            url,
+            shutdown,
+            task,
+        })
+    }
+
+    /// Where the sink writes to
+    pub fn description(&self) -> &str {
+        &self.description
+    }
+
+    /// The address a WebSocket sink listens on
+    pub fn local_addr(&self) -> Option<SocketAddr> {
+        self.local_addr
+    }
+
+    /// The URL WebSocket clients connect to, with this session's token
+    pub fn url(&self) -> Option<&str> {
+        self.url.as_deref()
+    }
+
+    /// Write the events recorded so far, end the stream and wait for the sink
+    pub async fn finish(self, end_time: u64) -> SinkStats {
+        let _ = self.shutdown.send(end_time);
+        match self.task.await {
+            Ok(stats) => {
+                info!("{} finished: {stats:?}", self.description);
+                stats
+            }
+            Err(e) => {
+                error!("{} failed: {e}", self.description);
+                SinkStats::default()
+            }
+        }
+    }
+}
+
+async fn run(
+    mut output: Output,
+    start: String,
+    mut events: broadcast::Receiver<WorkflowEvent>,
+    mut shutdown: oneshot::Receiver<u64>,
+) -> SinkStats {
+    let mut stats = SinkStats::default();
+    let mut seq = 0;
+    // WebSocket clients get the start entry when they connect
+    if !matches!(output, Output::WebSocket(_)) {
+        output.write(0, &start, &mut stats).await;
+        output.flush(&mut stats).await;
+    }
+
+    let end_time = loop {
+        tokio::select! {
+            biased;
+            received = events.recv() => match received {
+                Ok(event) => {
+                    seq += 1;
+                    write_event(&mut output, seq, &event, &mut stats).await;
+                    if events.is_empty() {
+                        output.flush(&mut stats).await;
+                    }
+                }
+                Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                    seq += skipped;
+                    write_lagged(&mut output, seq, skipped, &mut stats).await;
+                }
+                Err(broadcast::error::RecvError::Closed) => break now_ms(),
+            },
+            end_time = &mut shutdown => {
+                loop {
+                    match events.try_recv() {
+                        Ok(event) => {
+                            seq += 1;
+                            write_event(&mut output, seq, &event, &mut stats).await;
+                        }
+                        Err(broadcast::error::TryRecvError::Lagged(skipped)) => {
+                            seq += skipped;
+                            write_lagged(&mut output, seq, skipped, &mut stats).await;
+                        }
+                        Err(_) => break,
+                    }
+                }
+                break end_time.unwrap_or_else(|_| now_ms());
+            }
+        }
+    };
+
+    if let Ok(end) = line(&JournalEntry::End { end_time }) {
+        output.write(seq, &end, &mut stats).await;
+    }
+    output.close(&mut stats).await;
+    stats
+}
+
+async fn write_event(output: &mut Output, seq: u64, event: &WorkflowEvent, stats: &mut SinkStats) {
+    match event_line(seq, event) {
+        Ok(line) => {
+            if output.write(seq, &line, stats).await {
+                stats.written += 1;
+            } else {
+                stats.dropped += 1;
+            }
+        }
+        Err(e) => {
+            warn!("Failed to serialize event {seq} for streaming: {e}");
+            stats.dropped += 1;
+        }
+    }
+}
+
+async fn write_lagged(output: &mut Output, seq: u64, skipped: u64, stats: &mut SinkStats) {
+    warn!("Event sink fell behind and missed {skipped} events");
+    stats.lagged += skipped;
+    if let Ok(line) = line(&JournalEntry::Lagged { skipped }) {
+        output.write(seq, &line, stats).await;
+    }
+}
+
+/// Redacts the event like [`RecordedWorkflow::to_json`](crate::RecordedWorkflow::to_json)
+fn event_line(seq: u64, event: &WorkflowEvent) -> Result<String> {
+    let recorded = RecordedEvent {
+        timestamp: event.timestamp().unwrap_or_else(now_ms),
+        event: event.clone(),
+        metadata: None,
+    };
+    let mut single = SerializableRecordedWorkflow {
+        format_version: FORMAT_VERSION,
+        name: String::new(),
+        start_time: recorded.timestamp,
+        end_time: None,
+        events: vec![(&recorded).into()],
+    };
+    let redactor = terminator::redaction::global();
+    single.redact(&redactor);
+    let recorded = single.events.remove(0);
+    let mut value = serde_json::to_value(JournalEntry::Event {
+        seq,
+        timestamp: recorded.timestamp,
+        event: Box::new(recorded.event),
+    })?;
+    redactor.redact_json(&mut value);
+    Ok(value.to_string())
+}
+
+fn line(entry: &JournalEntry) -> Result<String> {
+    Ok(serde_json::to_string(entry)?)
+}
+
+fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis() as u64
+}
+
+enum Output {
+    Jsonl(JsonlOutput),
+    WebSocket(WebSocketOutput),
+    /// `None` once stdout was closed
+    Stdout(Option<BufWriter<tokio::io::Stdout>>),
+}
+
+impl Output {
+    /// Writes one entry; false if it was dropped
+    async fn write(&mut self, seq: u64, line: &str, stats: &mut SinkStats) -> bool {
+        match self {
+            Output::Jsonl(jsonl) => jsonl.write(line, stats).await,
+            Output::WebSocket(server) => {
+                server.broadcast(seq, line);
+                true
+            }
+            Output::Stdout(stdout) => {
+                let Some(writer) = stdout else {
+                    return false;
+                };
+                if let Err(e) = write_line(writer, line).await {
+                    // Nothing to reconnect to once the reading end is gone
+                    warn!("Stopped streaming events to stdout: {e}");
+                    *stdout = None;
+                    return false;
+                }
+                true
+            }
+        }
+    }
+
+    async fn flush(&mut self, stats: &mut SinkStats) {
+        match self {
+            Output::Jsonl(jsonl) => jsonl.flush(false).await,
+            Output::WebSocket(_) => {}
+            Output::Stdout(stdout) => {
+                if let Some(writer) = stdout {
+                    if let Err(e) = writer.flush().await {
+                        warn!("Stopped streaming events to stdout: {e}");
+                        *stdout = None;
+                    }
+                }
+            }
+        }
+    }
+
+    async fn close(&mut self, stats: &mut SinkStats) {
+        match self {
+            Output::Jsonl(jsonl) => jsonl.flush(true).await,
+            Output::WebSocket(server) => server.close(),
+            Output::Stdout(_) => self.flush(stats).await,
+        }
+    }
+}
+
+async fn write_line<W: AsyncWrite + Unpin>(writer: &mut W, line: &str) -> std::io::Result<()> {
+    writer.write_all(line.as_bytes()).await?;
+    writer.write_all(b"\n").await
+}
+
+/// Path of the `index`th file of a JSONL journal: `session.jsonl`,
+/// `session.1.jsonl`, `session.2.jsonl`, ...
+fn journal_file(path: &Path, index: usize) -> PathBuf {
+    if index == 0 {
+        return path.to_path_buf();
+    }
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+    let name = match path.extension() {
+        Some(extension) => format!("{stem}.{index}.{}", extension.to_string_lossy()),
+        None => format!("{stem}.{index}"),
+    };
+    path.with_file_name(name)
+}
+
+struct JsonlOutput {
+    config: JsonlSinkConfig,
+    start: String,
+    index: usize,
+    file: BufWriter<tokio::fs::File>,
+    file_bytes: u64,
+    last_sync: Instant,
+}
+
+impl JsonlOutput {
+    async fn create(config: JsonlSinkConfig, start: String) -> Result<Self> {
+        let file = tokio::fs::OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&config.path)
+            .await
+            .map_err(|e| {
+                WorkflowRecorderError::InitializationError(format!(
+                    "Failed to create journal {}: {e}",
+                    config.path.display()
+                ))
+            })?;
+        Ok(Self {
+            config,
+            start,
+            index: 0,
+            file: BufWriter::new(file),
+            file_bytes: 0,
+            last_sync: Instant::now(),
+        })
+    }
+
+    async fn open(&self, create: bool) -> std::io::Result<BufWriter<tokio::fs::File>> {
+        let file = tokio::fs::OpenOptions::new()
+            .append(true)
+            .create(create)
+            .open(journal_file(&self.config.path, self.index))
+            .await?;
+        Ok(BufWriter::new(file))
+    }
+
+    async fn write(&mut self, line: &str, stats: &mut SinkStats) -> bool {
+        let size = line.len() as u64 + 1;
+        if self.file_bytes > 0 && self.file_bytes + size > self.config.max_file_bytes {
+            self.rotate(stats).await;
+        }
+        let mut backoff = Duration::from_millis(100);
+        for attempt in 0..=JSONL_RETRIES {
+            match write_line(&mut self.file, line).await {
+                Ok(()) => {
+                    self.file_bytes += size;
+                    return true;
+                }
+                Err(e) if attempt < JSONL_RETRIES => {
+                    warn!("Failed to write journal, reopening in {backoff:?}: {e}");
+                    tokio::time::sleep(backoff).await;
+                    backoff *= 2;
+                    match self.open(true).await {
+                        Ok(file) => {
+                            self.file = file;
+                            stats.reconnects += 1;
+                        }
+                        Err(e) => warn!("Failed to reopen journal: {e}"),
+                    }
+                }
+                Err(e) => error!("Dropping event after {JSONL_RETRIES} failed journal writes: {e}"),
+            }
+        }
+        false
+    }
+
+    /// Continues in the next file, which starts with the recording's `start` entry
+    async fn rotate(&mut self, stats: &mut SinkStats) {
+        self.flush(true).await;
+        self.index += 1;
+        match self.open(true).await {
+            Ok(file) => {
+                debug!(
+                    "Journal continues in {}",
+                    journal_file(&self.config.path, self.index).display()
+                );
+                self.file = file;
+                self.file_bytes = 0;
+                let start = self.start.clone();
+                Box::pin(self.write(&start, stats)).await;
+            }
+            Err(e) => {
+                warn!("Failed to rotate journal, keeping the current file: {e}");
+                self.index -= 1;
+            }
+        }
+    }
+
+    /// Hands written entries to the OS, and syncs them to disk when due or `sync` is set
+    async fn flush(&mut self, sync: bool) {
+        if let Err(e) = self.file.flush().await {
+            warn!("Failed to flush journal: {e}");
+            return;
+        }
+        let interval = Duration::from_millis(self.config.sync_interval_ms);
+        if sync || self.last_sync.elapsed() >= interval {
+            if let Err(e) = self.file.get_ref().sync_data().await {
+                warn!("Failed to sync journal to disk: {e}");
+            }
+            self.last_sync = Instant::now();
+        }
+    }
+}
+
+/// Entries seen by WebSocket clients: the recent ones for clients that
+/// reconnect, and a queue per connected client
+struct Broadcast {
+    start: String,
+    token: String,
+    allowed_origins: Vec<String>,
+    recent: VecDeque<(u64, Arc<str>)>,
+    replay_buffer: usize,
+    client_buffer: usize,
+    clients: Vec<mpsc::Sender<Arc<str>>>,
+}
+
+struct WebSocketOutput {
+    local_addr: SocketAddr,
+    token: String,
+    shared: Arc<Mutex<Broadcast>>,
+    listener: JoinHandle<()>,
+}
+
+impl WebSocketOutput {
+    async fn bind(config: &WebSocketSinkConfig, start: String) -> Result<Self> {
+        let listener = TcpListener::bind(&config.address).await.map_err(|e| {
+            WorkflowRecorderError::InitializationError(format!(
+                "Failed to listen on {}: {e}",
+                config.address
+            ))
+        })?;
+        let local_addr = listener.local_addr()?;
+        let token = uuid::Uuid::new_v4().simple().to_string();
+        let shared = Arc::new(Mutex::new(Broadcast {
+            start,
+            token: token.clone(),
+            allowed_origins: config.allowed_origins.clone(),
+            recent: VecDeque::new(),
+            replay_buffer: config.replay_buffer,
+            client_buffer: config.client_buffer.max(1),
+            clients: Vec::new(),
+        }));
+        let accepting = Arc::clone(&shared);
+        let listener = tokio::spawn(async move {
+            loop {
+                match listener.accept().await {
+                    Ok((stream, peer)) => {
+                        tokio::spawn(serve_client(stream, peer, Arc::clone(&accepting)));
+                    }
+                    Err(e) => warn!("Failed to accept WebSocket client: {e}"),
+                }
+            }
+        });
+        Ok(Self {
+            local_addr,
+            token,
+            shared,
+            listener,
+        })
+    }
+
+    fn broadcast(&self, seq: u64, line: &str) {
+        let Ok(mut shared) = self.shared.lock() else {
+            return;
+        };
+        let line: Arc<str> = Arc::from(line);
+        if shared.replay_buffer > 0 {
+            if shared.recent.len() >= shared.replay_buffer {
+                shared.recent.pop_front();
+            }
+            shared.recent.push_back((seq, Arc::clone(&line)));
+        }
+        shared
+            .clients
+            .retain(|client| match client.try_send(Arc::clone(&line)) {
+                Ok(()) => true,
+                Err(mpsc::error::TrySendError::Full(_)) => {
+                    warn!("Disconnecting a WebSocket client that fell behind");
+                    false
+                }
+                Err(mpsc::error::TrySendError::Closed(_)) => false,
+            });
+    }
+
+    /// Stops accepting clients; connected ones get the queued entries and are closed
+    fn close(&mut self) {
+        self.listener.abort();
+        if let Ok(mut shared) = self.shared.lock() {
+            shared.clients.clear();
+        }
+    }
+}
+
+/// A query parameter of a client's request
+fn query_param<'a>(request: &'a Request, name: &str) -> Option<&'a str> {
+    request.uri().query()?.split('&').find_map(|pair| {
+        pair.split_once('=')
+            .filter(|(key, _)| *key == name)
+            .map(|(_, value)| value)
+    })
+}
+
+/// Why a client's handshake is rejected, if it is
+fn reject_handshake(
+    request: &Request,
+    token: &str,
+    allowed_origins: &[String],
+) -> Option<(StatusCode, &'static str)> {
+    if let Some(origin) = request.headers().get(ORIGIN) {
+        let allowed = origin
+            .to_str()
+            .is_ok_and(|origin| allowed_origins.iter().any(|allowed| allowed == origin));
+        if !allowed {
+            return Some((StatusCode::FORBIDDEN, "origin not allowed"));
+        }
+    }
+    if query_param(request, "token") != Some(token) {
+        return Some((StatusCode::UNAUTHORIZED, "missing or wrong token"));
+    }
+    None
+}
+
+async fn serve_client(
+    stream: tokio::net::TcpStream,
+    peer: SocketAddr,
+    shared: Arc<Mutex<Broadcast>>,
+) {
+    let (token, allowed_origins) = match shared.lock() {
+        Ok(shared) => (shared.token.clone(), shared.allowed_origins.clone()),
+        Err(_) => return,
+    };
+    let mut since = None;
+    // The handshake's error type is tungstenite's
+    #[allow(clippy::result_large_err)]
+    let callback = |request: &Request, response: Response| {
+        if let Some((status, reason)) = reject_handshake(request, &token, &allowed_origins) {
+            warn!("Rejected WebSocket client {peer}: {reason}");
+            let mut rejection = ErrorResponse::new(Some(reason.to_string()));
+            *rejection.status_mut() = status;
+            return Err(rejection);
+        }
+        since = query_param(request, "since").and_then(|since| since.parse().ok());
+        Ok(response)
+    };
+    let ws = match tokio_tungstenite::accept_hdr_async(stream, callback).await {
+        Ok(ws) => ws,
+        Err(e) => {
+            debug!("WebSocket handshake with {peer} failed: {e}");
+            return;
+        }
+    };
+
+    // Replay and subscribe under one lock so no entry is missed or repeated
+    let (backlog, mut queue) = {
+        let Ok(mut shared) = shared.lock() else {
+            return;
+        };
+        let mut backlog = vec![Arc::from(shared.start.as_str())];
+        if let Some(since) = since {
+            let oldest = shared.recent.front().map_or(u64::MAX, |(seq, _)| *seq);
+            if oldest > since + 1 && oldest != u64::MAX {
+                let skipped = oldest - since - 1;
+                if let Ok(lagged) = line(&JournalEntry::Lagged { skipped }) {
+                    backlog.push(Arc::from(lagged));
+                }
+            }
+            backlog.extend(
+                shared
+                    .recent
+                    .iter()
+                    .filter(|(seq, _)| *seq > since)
+                    .map(|(_, line)| Arc::clone(line)),
+            );
+        }
+        let (tx, rx) = mpsc::channel(shared.client_buffer);
+        shared.clients.push(tx);
+        (backlog, rx)
+    };
+    debug!("WebSocket client {peer} connected (since {since:?})");
+
+    let (mut sink, mut incoming) = ws.split();
+    for entry in backlog {
+        if sink.send(Message::Text(entry.to_string())).await.is_err() {
+            return;
+        }
+    }
+    loop {
+        tokio::select! {
+            entry = queue.recv() => match entry {
+                Some(entry) => {
+                    if sink.send(Message::Text(entry.to_string())).await.is_err() {
+                        break;
+                    }
+                }
+                None => {
+                    let _ = sink.send(Message::Close(None)).await;
+                    break;
+                }
+            },
+            message = incoming.next() => match message {
+                Some(Ok(Message::Close(_))) | None | Some(Err(_)) => break,
+                Some(Ok(_)) => {}
+            },
+        }
+    }
+    debug!("WebSocket client {peer} disconnected");
+}
+
+/// What [`replay_jsonl`] found besides the recording
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct ReplayReport {
+    /// Journal files read, in order
+    pub files: Vec<PathBuf>,
+    /// Events the sink missed, from `lagged` entries and gaps in `seq`
+    pub missing_events: u64,
+    /// Lines that could not be read, e.g. the last one after a crash
+    pub skipped_lines: usize,
+    /// Whether the journal ends with an `end` entry; false after a crash
+    pub complete: bool,
+    /// Migrations and dropped or defaulted fields, as for saved recordings
+    pub load: LoadReport,
+}
+
+/// Rebuild a recording from the files of a [`SinkConfig::Jsonl`] journal,
+/// e.g. after the recording process crashed
+pub fn replay_jsonl<P: AsRef<Path>>(
+    path: P,
+) -> Result<(SerializableRecordedWorkflow, ReplayReport)> {
+    let path = path.as_ref();
+    let mut report = ReplayReport::default();
+    let mut start: Option<Value> = None;
+    let mut end_time = None;
+    let mut events = Vec::new();
+    let mut last_seq = 0;
+
+    while let Ok(text) = std::fs::read_to_string(journal_file(path, report.files.len())) {
+        report.files.push(journal_file(path, report.files.len()));
+        for line in text.lines().filter(|l| !l.trim().is_empty()) {
+            let Ok(Value::Object(mut entry)) = serde_json::from_str::<Value>(line) else {
+                report.skipped_lines += 1;
+                continue;
+            };
+            if let Some(value) = entry.remove("start") {
+                start.get_or_insert(value);
+            } else if let Some(mut value) = entry.remove("event") {
+                let seq = value["seq"].as_u64().unwrap_or(last_seq + 1);
+                report.missing_events += seq.saturating_sub(last_seq + 1);
+                last_seq = last_seq.max(seq);
+                if let Some(object) = value.as_object_mut() {
+                    object.remove("seq");
+                }
+                events.push(value);
+            } else if let Some(end) = entry.get("end") {
+                end_time = end["end_time"].as_u64();
+            } else if !entry.contains_key("lagged") {
+                report.skipped_lines += 1;
+            }
+        }
+    }
+    if report.files.is_empty() {
+        return Err(WorkflowRecorderError::IoError(std::io::Error::new(
+            std::io::ErrorKind::NotFound,
+            format!("No journal at {}", path.display()),
+        )));
+    }
+    report.complete = end_time.is_some();
+
+    let start = start.unwrap_or_else(|| {
+        json!({
+            "name": path.file_stem().unwrap_or_default().to_string_lossy(),
+            "start_time": events.first().map_or(Value::from(0), |e| e["timestamp"].clone()),
+        })
+    });
+    let mut recording = json!({
+        "name": start["name"],
+        "start_time": start["start_time"],
+        "events": events,
+    });
+    if let Some(version) = start.get("format_version") {
+        recording["format_version"] = version.clone();
+    }
+    if let Some(end_time) = end_time {
+        recording["end_time"] = end_time.into();
+    }
+    let (workflow, load) = format::load_value(recording)?;
+    report.load = load;
+    Ok((workflow, report))
+}
@@ -0,0 +1,636 @@
+//! Selector synthesis for recorded UI elements.
+//!
+//! `build_chained_selector` chains `role:X && text:Name` through every named
+//! parent, which breaks as soon as a dialog title or a list position changes.
+//! The synthesizer instead generates several candidates per element and scores
+//! each one against a snapshot of the window it was recorded in:
+//!
+//! - `AutomationId`: `nativeid:` when the app exposes a non-generated id
+//! - `RoleName`: `role:X && name:Y` for elements with a stable name
+//! - `Anchored`: relative to the nearest identifiable container, or to the
+//!   label next to an unnamed field (`label >> .. >> role:Edit`)
+//! - `Chain`: the shortest chain of identifiable ancestors that is unique
+//! - `Nth`: position among same-role elements of a stable container
+//!
+//! Scores combine uniqueness in the snapshot, stability heuristics (digits,
+//! dates, clocks, generated ids, list positions) and length. Everything here is
+//! pure over a [`UINode`] tree, so it runs and is tested without a desktop.
+
+use crate::compiler::{looks_generated, plain_text};
+use crate::events::{contains_relative_time, is_empty_string, SelectorCandidate, SelectorScore};
+use std::collections::HashSet;
+use terminator::UINode;
+
+/// Candidates kept per element
+const MAX_CANDIDATES: usize = 6;
+
+/// Ancestors a `Chain` candidate may go through
+const MAX_CHAIN_STEPS: usize = 4;
+
+const MONTHS: &[&str] = &[
+    "jan", "feb", "mar", "apr", "may", "jun", "jul", "aug", "sep", "oct", "nov", "dec",
+];
+
+/// Path of child indices from the root of a snapshot to one of its nodes
+pub type NodePath = Vec<usize>;
+
+/// Finds the first node (pre-order) matching `predicate`
+pub fn find_node_path(root: &UINode, predicate: impl Fn(&UINode) -> bool) -> Option<NodePath> {
+    fn walk(node: &UINode, path: &mut NodePath, predicate: &dyn Fn(&UINode) -> bool) -> bool {
+        if predicate(node) {
+            return true;
+        }
+        for (i, child) in node.children.iter().enumerate() {
+            path.push(i);
+            if walk(child, path, predicate) {
+                return true;
+            }
+            path.pop();
+        }
+        false
+    }
+
+    let mut path = Vec::new();
+    walk(root, &mut path, &predicate).then_some(path)
+}
+
+/// Generates selector candidates for the node at `target` in `root`, most
+/// robust first.
+///
+/// `root` is the window the element was recorded in; selectors are relative
+/// to it, the way `process:` scoped selectors resolve. Candidates that would
+/// resolve to a different element first are dropped.
+pub fn synthesize_selectors(root: &UINode, target: &[usize]) -> Vec<SelectorCandidate> {
+    let snapshot = Snapshot::new(root);
+    let Some(target) = snapshot.index_of(target) else {
+        return Vec::new();
+    };
+    if target == 0 {
+        return Vec::new();
+    }
+
+    let mut queries: Vec<(&'static str, Query)> = Vec::new();
+    let node = snapshot.nodes[target];
+    let own = own_step(node);
+
+    if let Some(id) = automation_id(node) {
+        queries.push((
+            "AutomationId",
+            Query::new(vec![Step::Match(vec![Atom::NativeId(id.clone())])]),
+        ));
+        if !node.attributes.role.is_empty() {
+            queries.push((
+                "AutomationId",
+                Query::new(vec![Step::Match(vec![
+                    Atom::Role(node.attributes.role.clone()),
+                    Atom::NativeId(id),
+                ])]),
+            ));
+        }
+    }
+    if let Some(name) = stable_name(node) {
+        queries.push((
+            "RoleName",
+            Query::new(vec![Step::Match(vec![
+                Atom::Role(node.attributes.role.clone()),
+                Atom::Name(name),
+            ])]),
+        ));
+    }
+
+    let ancestors = snapshot.ancestors(target);
+    let anchor = ancestors
+        .iter()
+        .copied()
+        .find_map(|i| identity(snapshot.nodes[i]).map(|step| (i, step)));
+    if let Some((_, anchor_step)) = &anchor {
+        queries.push((
+            "Anchored",
+            Query::new(vec![anchor_step.clone(), own.clone()]),
+        ));
+    }
+    if stable_name(node).is_none() {
+        if let Some(label) = snapshot.label_for(target) {
+            queries.push((
+                "Anchored",
+                Query::new(vec![label, Step::Parent, own.clone()]),
+            ));
+        }
+    }
+
+    // Shortest unique chain: add identifiable ancestors, nearest first
+    let mut chain = vec![own.clone()];
+    for &ancestor in &ancestors {
+        if chain.len() > MAX_CHAIN_STEPS {
+            break;
+        }
+        let Some(step) = identity(snapshot.nodes[ancestor]) else {
+            continue;
+        };
+        chain.insert(0, step);
+        let query = Query::new(chain.clone());
+        if snapshot.evaluate(&query) == [target] {
+            queries.push(("Chain", query));
+            break;
+        }
+    }
+
+    // Position among same-role elements of the nearest stable container
+    if !node.attributes.role.is_empty() {
+        let mut steps: Vec<Step> = anchor.iter().map(|(_, step)| step.clone()).collect();
+        steps.push(Step::Match(vec![Atom::Role(node.attributes.role.clone())]));
+        let collection = snapshot.evaluate(&Query::new(steps.clone()));
+        if let Some(position) = collection.iter().position(|&i| i == target) {
+            if collection.len() > 1 {
+                queries.push((
+                    "Nth",
+                    Query {
+                        steps,
+                        nth: Some(position),
+                    },
+                ));
+            }
+        }
+    }
+
+    let mut seen = HashSet::new();
+    let mut candidates: Vec<SelectorCandidate> = queries
+        .into_iter()
+        .filter_map(|(selector_type, query)| {
+            let selector = query.render();
+            if !seen.insert(selector.clone()) {
+                return None;
+            }
+            let score = score(&snapshot, &query, &selector, target)?;
+            Some(SelectorCandidate {
+                selector,
+                selector_type: selector_type.to_string(),
+                specificity: query.atom_count(),
+                requires_jquery: false,
+                score: Some(score),
+            })
+        })
+        .collect();
+    candidates.sort_by(|a, b| {
+        let total = |c: &SelectorCandidate| c.score.as_ref().map_or(0, |s| s.total);
+        total(b)
+            .cmp(&total(a))
+            .then(a.selector.len().cmp(&b.selector.len()))
+    });
+    candidates.truncate(MAX_CANDIDATES);
+    candidates
+}
+
+/// Scores a candidate, or `None` when it doesn't resolve to the target
+fn score(
+    snapshot: &Snapshot,
+    query: &Query,
+    selector: &str,
+    target: usize,
+) -> Option<SelectorScore> {
+    let matches = snapshot.evaluate(query);
+    let uniqueness = match matches.as_slice() {
+        [only] if *only == target => 50,
+        [first, ..] if *first == target => 15,
+        _ => return None,
+    };
+
+    let mut penalties = Vec::new();
+    let mut stability: i32 = 35;
+    for step in &query.steps {
+        let Step::Match(atoms) = step else {
+            continue;
+        };
+        for atom in atoms {
+            match atom {
+                Atom::NativeId(id) if looks_generated(id) => {
+                    stability -= 20;
+                    penalties.push(format!("automation id '{id}' looks generated"));
+                }
+                Atom::NativeId(_) => stability += 5,
+                Atom::Name(name) => {
+                    if looks_like_date(name) {
+                        stability -= 20;
+                        penalties.push(format!("name '{name}' contains a date or time"));
+                    } else if name.chars().any(|c| c.is_ascii_digit()) {
+                        stability -= 10;
+                        penalties.push(format!("name '{name}' contains digits"));
+                    }
+                    if name.chars().count() > 40 {
+                        stability -= 5;
+                        penalties.push(format!("name '{name}' is long"));
+                    }
+                }
+                Atom::Role(_) => {}
+            }
+        }
+    }
+    if query.steps.len() > 1 {
+        stability -= 3 * (query.steps.len() as i32 - 1);
+        penalties.push(format!(
+            "depends on {} levels of hierarchy",
+            query.steps.len()
+        ));
+    }
+    if let Some(nth) = query.nth {
+        stability -= 15;
+        penalties.push(format!("depends on list position {nth}"));
+    }
+    let stability = stability.clamp(0, 40) as u32;
+    let length = 15u32.saturating_sub(selector.chars().count() as u32 / 8);
+
+    Some(SelectorScore {
+        total: (uniqueness + stability + length).min(100),
+        matches: matches.len(),
+        uniqueness,
+        stability,
+        length,
+        penalties,
+    })
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Atom {
+    Role(String),
+    Name(String),
+    NativeId(String),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Step {
+    /// Descendants matching all atoms
+    Match(Vec<Atom>),
+    /// `..`: the parent of each current element
+    Parent,
+}
+
+#[derive(Debug, Clone)]
+struct Query {
+    steps: Vec<Step>,
+    /// `nth:` applied to the final collection
+    nth: Option<usize>,
+}
+
+impl Query {
+    fn new(steps: Vec<Step>) -> Self {
+        Self { steps, nth: None }
+    }
+
+    fn render(&self) -> String {
+        let mut parts: Vec<String> = self
+            .steps
+            .iter()
+            .map(|step| match step {
+                Step::Parent => "..".to_string(),
+                Step::Match(atoms) => atoms
+                    .iter()
+                    .map(|atom| match atom {
+                        Atom::Role(role) => format!("role:{role}"),
+                        Atom::Name(name) => format!("name:{name}"),
+                        Atom::NativeId(id) => format!("nativeid:{id}"),
+                    })
+                    .collect::<Vec<_>>()
+                    .join(" && "),
+            })
+            .collect();
+        if let Some(nth) = self.nth {
+            parts.push(format!("nth:{nth}"));
+        }
+        parts.join(" >> ")
+    }
+
+    fn atom_count(&self) -> u32 {
+        self.steps
+            .iter()
+            .map(|step| match step {
+                Step::Match(atoms) => atoms.len() as u32,
+                Step::Parent => 0,
+            })
+            .sum::<u32>()
+            + u32::from(self.nth.is_some())
+    }
+}
+
+/// The snapshot flattened in document order
+struct Snapshot<'a> {
+    nodes: Vec<&'a UINode>,
+    parent: Vec<Option<usize>>,
+    paths: Vec<NodePath>,
+}
+
+impl<'a> Snapshot<'a> {
+    fn new(root: &'a UINode) -> Self {
+        let mut snapshot = Self {
+            nodes: Vec::new(),
+            parent: Vec::new(),
+            paths: Vec::new(),
+        };
+        snapshot.push(root, None, Vec::new());
+        snapshot
+    }
+
+    fn push(&mut self, node: &'a UINode, parent: Option<usize>, path: NodePath) {
+        let index = self.nodes.len();
+        self.nodes.push(node);
+        self.parent.push(parent);
+        self.paths.push(path.clone());
+        for (i, child) in node.children.iter().enumerate() {
+            let mut child_path = path.clone();
+            child_path.push(i);
+            self.push(child, Some(index), child_path);
+        }
+    }
+
+    fn index_of(&self, path: &[usize]) -> Option<usize> {
+        self.paths.iter().position(|p| p == path)
+    }
+
+    /// Ancestors below the root, nearest first
+    fn ancestors(&self, index: usize) -> Vec<usize> {
+        let mut ancestors = Vec::new();
+        let mut current = self.parent[index];
+        while let Some(i) = current {
+            if i == 0 {
+                break;
+            }
+            ancestors.push(i);
+            current = self.parent[i];
+        }
+        ancestors
+    }
+
+    fn is_descendant(&self, index: usize, of: &HashSet<usize>) -> bool {
+        let mut current = self.parent[index];
+        while let Some(i) = current {
+            if of.contains(&i) {
+                return true;
+            }
+            current = self.parent[i];
+        }
+        false
+    }
+
+    /// Elements the query resolves to, in document order
+    fn evaluate(&self, query: &Query) -> Vec<usize> {
+        let mut current: Vec<usize> = vec![0];
+        for step in &query.steps {
+            current = match step {
+                Step::Parent => {
+                    let mut parents: Vec<usize> =
+                        current.iter().filter_map(|&i| self.parent[i]).collect();
+                    parents.sort_unstable();
+                    parents.dedup();
+                    parents
+                }
+                Step::Match(atoms) => {
+                    let scope: HashSet<usize> = current.iter().copied().collect();
+                    (0..self.nodes.len())
+                        .filter(|&i| {
+                            atoms.iter().all(|atom| matches_atom(self.nodes[i], atom))
+                                && self.is_descendant(i, &scope)
+                        })
+                        .collect()
+                }
+            };
+            if current.is_empty() {
+                break;
+            }
+        }
+        match query.nth {
+            Some(nth) => current.get(nth).map(|&i| vec![i]).unwrap_or_default(),
+            None => current,
+        }
+    }
+
+    /// A text element right before `index` among its siblings that labels it
+    fn label_for(&self, index: usize) -> Option<Step> {
+        let parent = self.parent[index]?;
+        let siblings: Vec<usize> = (0..self.nodes.len())
+            .filter(|&i| self.parent[i] == Some(parent))
+            .collect();
+        let position = siblings.iter().position(|&i| i == index)?;
+        let previous = self.nodes[siblings[position.checked_sub(1)?]];
+        if !previous.attributes.role.eq_ignore_ascii_case("Text") {
+            return None;
+        }
+        let name = stable_name(previous)?;
+        Some(Step::Match(vec![
+            Atom::Role(previous.attributes.role.clone()),
+            Atom::Name(name),
+        ]))
+    }
+}
+
+fn matches_atom(node: &UINode, atom: &Atom) -> bool {
+    match atom {
+        Atom::Role(role) => node.attributes.role.eq_ignore_ascii_case(role),
+        // name: is a case-insensitive substring match
+        Atom::Name(name) => node
+            .attributes
+            .name
+            .as_deref()
+            .is_some_and(|n| n.to_lowercase().contains(&name.to_lowercase())),
+        Atom::NativeId(id) => automation_id(node).as_deref() == Some(id.as_str()),
+    }
+}
+
+/// How the node selects itself inside an anchor
+fn own_step(node: &UINode) -> Step {
+    let mut atoms = vec![Atom::Role(node.attributes.role.clone())];
+    if let Some(name) = stable_name(node) {
+        atoms.push(Atom::Name(name));
+    }
+    Step::Match(atoms)
+}
+
+/// A step that identifies a container on its own, if it has one
+fn identity(node: &UINode) -> Option<Step> {
+    if let Some(id) = automation_id(node).filter(|id| !looks_generated(id)) {
+        return Some(Step::Match(vec![Atom::NativeId(id)]));
+    }
+    // Window and dialog titles change with the open document
+    if matches!(node.attributes.role.as_str(), "Window" | "TitleBar") {
+        return None;
+    }
+    stable_name(node).map(|name| {
+        Step::Match(vec![
+            Atom::Role(node.attributes.role.clone()),
+            Atom::Name(name),
+        ])
+    })
+}
+
+fn automation_id(node: &UINode) -> Option<String> {
+    node.attributes
+        .properties
+        .get("AutomationId")
+        .and_then(|v| v.as_ref())
+        .and_then(|v| v.as_str())
+        .map(str::trim)
+        .filter(|id| !id.is_empty() && plain_text(id))
+        .map(str::to_string)
+}
+
+/// A name fit for a selector: usable as `name:` and not obviously dynamic
+fn stable_name(node: &UINode) -> Option<String> {
+    let name = node.attributes.name.as_deref()?.trim();
+    if is_empty_string(&Some(name.to_string()))
+        || !plain_text(name)
+        || name.contains('\n')
+        || name.chars().count() > 100
+        || contains_relative_time(&name.to_lowercase())
+    {
+        return None;
+    }
+    let alphanumeric = name.chars().filter(|c| c.is_alphanumeric()).count();
+    let digits = name.chars().filter(|c| c.is_ascii_digit()).count();
+    (alphanumeric > 0 && digits * 2 <= alphanumeric).then(|| name.to_string())
+}
+
+/// Dates and clock times: `2024-05-12`, `12/05/24`, `10:45`, `May 12`
+fn looks_like_date(text: &str) -> bool {
+    let chars: Vec<char> = text.chars().collect();
+    let digit_at = |i: usize| chars.get(i).is_some_and(|c| c.is_ascii_digit());
+    let separated = chars.iter().enumerate().any(|(i, c)| {
+        i > 0 && matches!(c, ':' | '/' | '-' | '.') && digit_at(i - 1) && digit_at(i + 1)
+    });
+    if separated {
+        return true;
+    }
+    let lower = text.to_lowercase();
+    text.chars().any(|c| c.is_ascii_digit())
+        && lower
+            .split(|c: char| !c.is_alphabetic())
+            .any(|word| word.len() >= 3 && MONTHS.iter().any(|m| word.starts_with(m)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+    use terminator::UIElementAttributes;
+
+    fn node(role: &str, name: &str, children: Vec<UINode>) -> UINode {
+        UINode {
+            id: None,
+            attributes: UIElementAttributes {
+                role: role.to_string(),
+                name: (!name.is_empty()).then(|| name.to_string()),
+                ..Default::default()
+            },
+            children,
+            selector: None,
+        }
+    }
+
+    fn with_id(mut node: UINode, id: &str) -> UINode {
+        node.attributes.properties = HashMap::from([(
+            "AutomationId".to_string(),
+            Some(serde_json::Value::String(id.to_string())),
+        )]);
+        node
+    }
+
+    #[test]
+    fn name_matching_is_substring_so_prefixes_are_not_unique() {
+        let root = node(
+            "Window",
+            "Untitled - Notepad",
+            vec![node(
+                "Pane",
+                "",
+                vec![
+                    node("Button", "Save", vec![]),
+                    node("Button", "Save As", vec![]),
+                ],
+            )],
+        );
+        let candidates = synthesize_selectors(&root, &[0, 0]);
+        let role_name = candidates
+            .iter()
+            .find(|c| c.selector == "role:Button && name:Save")
+            .unwrap();
+        let score = role_name.score.as_ref().unwrap();
+        assert_eq!(score.matches, 2);
+        assert_eq!(score.uniqueness, 15);
+
+        // 'Save As' only matches itself
+        let candidates = synthesize_selectors(&root, &[0, 1]);
+        assert_eq!(candidates[0].selector, "role:Button && name:Save As");
+        assert_eq!(candidates[0].score.as_ref().unwrap().matches, 1);
+    }
+
+    #[test]
+    fn dates_and_digits_cost_stability() {
+        assert!(looks_like_date("Report 2024-05-12"));
+        assert!(looks_like_date("Meeting at 10:45"));
+        assert!(looks_like_date("May 12"));
+        assert!(!looks_like_date("Mayor"));
+        assert!(!looks_like_date("Save As"));
+
+        let root = node(
+            "Window",
+            "Mail",
+            vec![
+                node("ListItem", "Invoice from May 12", vec![]),
+                node("ListItem", "Inbox 3", vec![]),
+                node("ListItem", "Drafts", vec![]),
+            ],
+        );
+        let stability = |path: &[usize]| {
+            synthesize_selectors(&root, path)
+                .into_iter()
+                .find(|c| c.selector_type == "RoleName")
+                .and_then(|c| c.score)
+                .map(|s| s.stability)
+        };
+        assert_eq!(stability(&[2]), Some(35));
+        assert_eq!(stability(&[1]), Some(25));
+        assert_eq!(stability(&[0]), Some(15));
+    }
+
+    #[test]
+    fn generated_automation_ids_are_not_anchors() {
+        let root = node(
+            "Window",
+            "App",
+            vec![with_id(
+                node("Group", "", vec![node("Button", "OK", vec![])]),
+                "c7f3a9e2b1d4",
+            )],
+        );
+        assert!(identity(&root.children[0]).is_none());
+        let candidates = synthesize_selectors(&root, &[0, 0]);
+        assert!(candidates
+            .iter()
+            .all(|c| !c.selector.contains("c7f3a9e2b1d4")));
+    }
+
+    #[test]
+    fn render_joins_steps_and_nth() {
+        let query = Query {
+            steps: vec![
+                Step::Match(vec![Atom::NativeId("list".into())]),
+                Step::Parent,
+                Step::Match(vec![Atom::Role("ListItem".into())]),
+            ],
+            nth: Some(2),
+        };
+        assert_eq!(
+            query.render(),
+            "nativeid:list >> .. >> role:ListItem >> nth:2"
+        );
+        assert_eq!(query.atom_count(), 3);
+    }
+
+    #[test]
+    fn root_and_unknown_paths_have_no_candidates() {
+        let root = node("Window", "App", vec![node("Button", "OK", vec![])]);
+        assert!(synthesize_selectors(&root, &[]).is_empty());
+        assert!(synthesize_selectors(&root, &[3]).is_empty());
+        assert_eq!(
+            find_node_path(&root, |n| n.attributes.role == "Button"),
+            Some(vec![0])
+        );
+    }
+}
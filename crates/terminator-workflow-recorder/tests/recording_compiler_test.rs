@@ -0,0 +1,121 @@
+//! Compiles saved recordings; runs on any platform.
+
+use serde_json::Value;
+use terminator_workflow_recorder::{compile, CompileOptions, CompiledWorkflow, RecordedWorkflow};
+
+fn compile_fixture(name: &str) -> CompiledWorkflow {
+    let path = format!("{}/tests/fixtures/{name}", env!("CARGO_MANIFEST_DIR"));
+    let recording = RecordedWorkflow::load_from_file(&path).expect("fixture should load");
+    compile(&recording, &CompileOptions::default())
+}
+
+fn tools(workflow: &CompiledWorkflow) -> Vec<&str> {
+    workflow
+        .steps
+        .iter()
+        .map(|s| s.tool_name.as_str())
+        .collect()
+}
+
+#[test]
+fn compiles_desktop_app_recording() {
+    let workflow = compile_fixture("notepad_save.json");
+    assert_eq!(
+        tools(&workflow),
+        vec![
+            "open_application",
+            "type_into_element",
+            "press_key_global",
+            "wait_for_element",
+            "click_element",
+        ]
+    );
+
+    // Alt+Tab passed through Calculator on the way to Notepad
+    assert_eq!(workflow.steps[0].arguments["app_name"], "notepad");
+    // The click that focused the field is folded into type_into_element
+    let typing = &workflow.steps[1].arguments;
+    assert_eq!(typing["selector"], "role:Edit && name:Text editor");
+    assert_eq!(typing["fallback_selectors"], "name:Text editor");
+    assert_eq!(typing["text_to_type"], "Hello world");
+    assert_eq!(typing["clear_before_typing"], true);
+    assert_eq!(workflow.steps[2].arguments["key"], "{Ctrl}{S}");
+    // The Save As dialog took a while to come up
+    assert_eq!(
+        workflow.steps[3].arguments["selector"],
+        "role:Button && name:Save"
+    );
+    assert_eq!(workflow.steps[3].arguments["timeout_ms"], 10_000);
+    assert_eq!(workflow.steps[4].arguments["process"], "notepad");
+    assert!(workflow.warnings.is_empty(), "{:?}", workflow.warnings);
+}
+
+#[test]
+fn compiles_browser_recording() {
+    let workflow = compile_fixture("browser_login.json");
+    assert_eq!(
+        tools(&workflow),
+        vec![
+            "navigate_browser",
+            "wait_for_element",
+            "type_into_element",
+            "execute_browser_script",
+            "wait_for_element",
+            "click_element",
+        ]
+    );
+
+    // Address bar typing becomes the navigation; the redirect is followed
+    let navigate = &workflow.steps[0].arguments;
+    assert_eq!(navigate["url"], "https://example.com/login?next=%2F");
+    assert_eq!(navigate["process"], "chrome");
+    assert_eq!(
+        workflow.steps[2].arguments["selector"],
+        "role:Edit && name:Email"
+    );
+
+    // No UI Automation element: the most robust DOM selector is used
+    let sign_in = &workflow.steps[3];
+    let script = sign_in.arguments["script"].as_str().unwrap();
+    assert!(script.contains(r#"document.querySelector("[data-testid=\"sign-in\"]")"#));
+    assert_eq!(
+        sign_in.expected_ui_changes.as_deref(),
+        Some("Browser navigates to https://example.com/dashboard")
+    );
+    assert_eq!(
+        workflow.steps[4].arguments["selector"],
+        "role:Hyperlink && name:Reports"
+    );
+}
+
+#[test]
+fn renders_yaml_and_typescript() {
+    let workflow = compile_fixture("browser_login.json");
+
+    let yaml = workflow.to_yaml().unwrap();
+    let parsed: Value = serde_yaml::from_str(&yaml).unwrap();
+    assert_eq!(parsed["tool_name"], "execute_sequence");
+    let steps = parsed["arguments"]["steps"].as_array().unwrap();
+    assert_eq!(steps.len(), workflow.steps.len());
+    assert_eq!(steps[0]["id"], "navigate_to_https_example_com_login_next");
+    assert_eq!(
+        steps[3]["expected_ui_changes"],
+        "Browser navigates to https://example.com/dashboard"
+    );
+
+    let ts = workflow.to_typescript();
+    assert!(ts.contains(
+        "await desktop.navigateBrowser(\"https://example.com/login?next=%2F\", \"chrome\");"
+    ));
+    assert!(ts.contains("desktop.locator(\"process:chrome >> role:Edit && name:Email\").waitFor(\"visible\", 10000)"));
+    assert!(
+        ts.contains("await element.typeText(\"jane@example.com\", { clearBeforeTyping: true });")
+    );
+    assert!(ts.contains("// Expected: Browser navigates to https://example.com/dashboard"));
+
+    // Same recording, same workflow
+    assert_eq!(
+        compile_fixture("browser_login.json").to_yaml().unwrap(),
+        yaml
+    );
+}
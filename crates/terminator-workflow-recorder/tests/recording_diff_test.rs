@@ -0,0 +1,78 @@
+//! Compares recordings with a reference; runs on any platform.
+
+use terminator_workflow_recorder::{
+    diff_recordings, Difference, PostProcessOptions, RecordedWorkflow, RecordingDiff, StepAction,
+};
+
+fn diff_fixtures(reference: &str, actual: &str) -> RecordingDiff {
+    let load = |name: &str| {
+        let path = format!("{}/tests/fixtures/{name}", env!("CARGO_MANIFEST_DIR"));
+        RecordedWorkflow::load_from_file(&path).expect("fixture should load")
+    };
+    diff_recordings(
+        &load(reference),
+        &load(actual),
+        &PostProcessOptions::default(),
+    )
+}
+
+#[test]
+fn reports_how_a_run_differs_from_the_reference() {
+    let diff = diff_fixtures("invoice_reference.json", "invoice_run.json");
+    let rows: Vec<_> = diff
+        .steps
+        .iter()
+        .map(|s| {
+            let step = s.reference.as_ref().or(s.actual.as_ref()).unwrap();
+            (step.action, step.target.as_str(), s.differences.clone())
+        })
+        .collect();
+    assert_eq!(
+        rows,
+        vec![
+            (StepAction::SwitchApp, "Contoso", vec![]),
+            (StepAction::Click, "New invoice", vec![Difference::Selector]),
+            (StepAction::Type, "Customer", vec![Difference::Value]),
+            (StepAction::Type, "Amount", vec![Difference::Missing]),
+            (StepAction::Click, "Help", vec![Difference::Extra]),
+            (StepAction::Click, "Save", vec![]),
+        ]
+    );
+    assert!(!diff.is_identical());
+    assert_eq!(diff.count(Difference::Missing), 1);
+
+    let customer = &diff.steps[2];
+    assert_eq!(
+        customer.reference.as_ref().unwrap().value.as_deref(),
+        Some("ACME Corp")
+    );
+    assert_eq!(
+        customer.actual.as_ref().unwrap().value.as_deref(),
+        Some("ACME Corporation")
+    );
+    // Indices point into each recording
+    assert_eq!(diff.steps[4].actual.as_ref().unwrap().index, 3);
+    assert_eq!(diff.steps[5].reference.as_ref().unwrap().index, 4);
+
+    let markdown = diff.to_markdown();
+    assert!(markdown.starts_with(
+        "# Enter invoice (trainee) compared with Enter invoice (reference)\n\n\
+         1 missing step, 1 extra step, 1 different value, 1 different selector, 2 matching steps.\n"
+    ));
+    assert!(markdown.contains(
+        "| 3 | Fill new invoice form | Type \"ACME Corp\" into Customer | Type \"ACME Corporation\" into Customer | different value |"
+    ), "{markdown}");
+    assert!(markdown
+        .contains("selector `role:Button && name:New invoice` → `nativeid:NewInvoiceButton`"));
+    assert!(markdown.contains("| Type \"120.00\" into Amount |  | **missing** |"));
+}
+
+#[test]
+fn a_recording_matches_itself() {
+    let diff = diff_fixtures("notepad_save.json", "notepad_save.json");
+    assert!(diff.is_identical());
+    assert!(!diff.steps.is_empty());
+    assert!(diff.steps.iter().all(|s| s.reference == s.actual));
+    let json = serde_json::to_value(&diff).unwrap();
+    assert!(json["steps"][0].get("differences").is_none());
+}
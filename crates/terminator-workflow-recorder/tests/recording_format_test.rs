@@ -0,0 +1,133 @@
+//! Recording format versioning, migration and CBOR storage; runs on any platform.
+
+use terminator_workflow_recorder::{
+    recording_schema, EventMetadata, KeyboardEvent, RecordedWorkflow, SerializableWorkflowEvent,
+    WorkflowEvent, FORMAT_VERSION,
+};
+
+fn fixture(name: &str) -> String {
+    format!("{}/tests/fixtures/{name}", env!("CARGO_MANIFEST_DIR"))
+}
+
+/// Run with `UPDATE_RECORDING_SCHEMA=1` after changing the recording types
+#[test]
+fn published_schema_is_up_to_date() {
+    let path = format!(
+        "{}/schema/recording.schema.json",
+        env!("CARGO_MANIFEST_DIR")
+    );
+    let generated = recording_schema();
+    if std::env::var_os("UPDATE_RECORDING_SCHEMA").is_some() {
+        std::fs::write(
+            &path,
+            serde_json::to_string_pretty(&generated).unwrap() + "\n",
+        )
+        .unwrap();
+    }
+    let published: serde_json::Value =
+        serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+    assert!(
+        published == generated,
+        "schema/recording.schema.json is stale; rerun with UPDATE_RECORDING_SCHEMA=1"
+    );
+}
+
+#[test]
+fn legacy_recording_is_migrated_with_a_report() {
+    let (recording, report) =
+        RecordedWorkflow::load_from_file_with_report(fixture("legacy_v1_recording.json"))
+            .expect("legacy recordings still load");
+
+    assert_eq!(report.format_version, 1);
+    assert_eq!(
+        report.migrations,
+        vec!["1 -> 2: flagged password fields with is_password (3 changed)"]
+    );
+    assert_eq!(
+        report.dropped_fields,
+        vec!["recorder_host", "events[1].event.Click.window_handle"]
+    );
+    assert_eq!(
+        report.defaulted_fields,
+        vec!["events[0].event.ApplicationSwitch.to_process_id"]
+    );
+    assert_eq!(recording.format_version, FORMAT_VERSION);
+
+    let SerializableWorkflowEvent::TextInputCompleted(input) = &recording.events[2].event else {
+        panic!("expected the password input");
+    };
+    assert!(input.is_password);
+    let SerializableWorkflowEvent::Click(link) = &recording.events[3].event else {
+        panic!("expected the link click");
+    };
+    let link = link.metadata.ui_element.as_ref().unwrap();
+    assert!(!link.is_password, "only edits named password are flagged");
+}
+
+#[test]
+fn older_fixtures_load_and_current_recordings_round_trip_clean() {
+    let (recording, report) =
+        RecordedWorkflow::load_from_file_with_report(fixture("notepad_save.json")).unwrap();
+    assert_eq!(report.format_version, 1);
+    assert!(report.dropped_fields.is_empty() && report.defaulted_fields.is_empty());
+
+    let json = serde_json::to_string(&recording).unwrap();
+    let (_, report) = RecordedWorkflow::from_json_with_report(&json).unwrap();
+    assert!(report.is_clean(), "{report}");
+}
+
+fn typing_session(keystrokes: usize) -> RecordedWorkflow {
+    let mut workflow = RecordedWorkflow::new("typing".to_string());
+    for i in 0..keystrokes {
+        let character = (b'a' + (i % 26) as u8) as char;
+        workflow.add_event(WorkflowEvent::Keyboard(KeyboardEvent {
+            key_code: character.to_ascii_uppercase() as u32,
+            is_key_down: i % 2 == 0,
+            ctrl_pressed: false,
+            alt_pressed: false,
+            shift_pressed: false,
+            win_pressed: false,
+            character: Some(character),
+            scan_code: Some(30 + i as u32 % 26),
+            metadata: EventMetadata {
+                ui_element: None,
+                timestamp: Some(1_000 + i as u64 * 40),
+            },
+        }));
+    }
+    workflow.finish();
+    workflow
+}
+
+#[test]
+fn cbor_round_trips_and_is_smaller_than_json() {
+    let workflow = typing_session(500);
+    let dir = tempfile::tempdir().unwrap();
+    let json_path = dir.path().join("typing.json");
+    let cbor_path = dir.path().join("typing.cbor");
+    workflow.save_to_file(&json_path).unwrap();
+    workflow.save_to_file(&cbor_path).unwrap();
+
+    let json_size = std::fs::metadata(&json_path).unwrap().len();
+    let cbor_size = std::fs::metadata(&cbor_path).unwrap().len();
+    assert!(cbor_size * 2 < json_size, "{cbor_size} vs {json_size}");
+
+    let from_json = RecordedWorkflow::load_from_file(&json_path).unwrap();
+    let (from_cbor, report) = RecordedWorkflow::load_from_file_with_report(&cbor_path).unwrap();
+    assert!(report.is_clean(), "{report}");
+    assert_eq!(
+        serde_json::to_value(&from_json).unwrap(),
+        serde_json::to_value(&from_cbor).unwrap()
+    );
+
+    // Renamed files are recognised by their content
+    let renamed = dir.path().join("typing.recording");
+    std::fs::rename(&cbor_path, &renamed).unwrap();
+    assert_eq!(
+        RecordedWorkflow::load_from_file(&renamed)
+            .unwrap()
+            .events
+            .len(),
+        500
+    );
+}
@@ -0,0 +1,242 @@
+//! Replays recordings against a fake input device; runs on any platform.
+
+use serde_json::json;
+use std::future::Future;
+use std::sync::Mutex;
+use std::time::Duration;
+use terminator::{AutomationError, ClickType, Monitor};
+use terminator_workflow_recorder::{
+    play, MonitorMap, PlaybackInput, PlaybackOptions, SerializableRecordedWorkflow, SkipReason,
+    WorkflowRecorderError,
+};
+
+/// Records the input it is asked for; only elements in `elements` exist
+#[derive(Default)]
+struct FakeInput {
+    elements: Vec<&'static str>,
+    calls: Mutex<Vec<String>>,
+}
+
+impl FakeInput {
+    fn with_elements(elements: &[&'static str]) -> Self {
+        Self {
+            elements: elements.to_vec(),
+            ..Self::default()
+        }
+    }
+
+    fn log(&self, call: String) -> Result<(), AutomationError> {
+        self.calls.lock().unwrap().push(call);
+        Ok(())
+    }
+
+    fn calls(&self) -> Vec<String> {
+        self.calls.lock().unwrap().clone()
+    }
+}
+
+impl PlaybackInput for FakeInput {
+    fn mouse_move(&self, x: f64, y: f64) -> Result<(), AutomationError> {
+        self.log(format!("move {x},{y}"))
+    }
+
+    fn mouse_down(&self, x: f64, y: f64) -> Result<(), AutomationError> {
+        self.log(format!("down {x},{y}"))
+    }
+
+    fn mouse_up(&self) -> Result<(), AutomationError> {
+        self.log("up".to_string())
+    }
+
+    fn mouse_drag(&self, from: (f64, f64), to: (f64, f64)) -> Result<(), AutomationError> {
+        self.log(format!("drag {},{} {},{}", from.0, from.1, to.0, to.1))
+    }
+
+    fn click(&self, x: f64, y: f64, click_type: ClickType) -> Result<(), AutomationError> {
+        self.log(format!("{click_type:?} {x},{y}"))
+    }
+
+    fn press_key(&self, key: &str) -> impl Future<Output = Result<(), AutomationError>> + Send {
+        let result = self.log(format!("key {key}"));
+        async move { result }
+    }
+
+    fn element_exists(&self, selector: &str, _: Duration) -> impl Future<Output = bool> + Send {
+        self.calls.lock().unwrap().push(format!("find {selector}"));
+        let exists = self.elements.contains(&selector);
+        async move { exists }
+    }
+}
+
+fn fixture(name: &str) -> SerializableRecordedWorkflow {
+    let path = format!("{}/tests/fixtures/{name}", env!("CARGO_MANIFEST_DIR"));
+    let text = std::fs::read_to_string(path).expect("fixture should exist");
+    serde_json::from_str(&text).expect("fixture should load")
+}
+
+fn fast() -> PlaybackOptions {
+    PlaybackOptions {
+        speed: 10.0,
+        max_delay_ms: Some(1000),
+        ..PlaybackOptions::default()
+    }
+}
+
+#[tokio::test]
+async fn replays_raw_input_with_its_timing() {
+    let recording = fixture("paint_drag_raw.json");
+    let input = FakeInput::with_elements(&["role:Pane && name:Canvas"]);
+    let report = play(&recording, &input, &fast()).await.unwrap();
+
+    assert_eq!(
+        input.calls(),
+        vec![
+            "move 100,100",
+            "find role:Pane && name:Canvas",
+            "down 200,150",
+            "move 300,200",
+            "move 400,250",
+            "up",
+            "key h",
+            "key !",
+            "key {Ctrl}{S}",
+        ]
+    );
+    assert_eq!(report.replayed, 7);
+    assert_eq!(report.stopped_at, None);
+    let skipped: Vec<_> = report.skipped.iter().map(|s| (s.index, s.reason)).collect();
+    assert_eq!(
+        skipped,
+        vec![
+            // The drag and the hotkey are replayed through their raw events
+            (4, SkipReason::CoveredByRawInput),
+            (6, SkipReason::CoveredByRawInput),
+            (7, SkipReason::CoveredByRawInput),
+            (10, SkipReason::CoveredByRawInput),
+            (11, SkipReason::NotInput),
+            (12, SkipReason::Unsupported),
+            (13, SkipReason::Redacted),
+        ]
+    );
+    // The Ctrl+S keystroke was 500 ms into the recording
+    assert!(report.duration_ms >= 50, "{report:?}");
+    assert!(report.duration_ms < 1000, "idle time is capped: {report:?}");
+}
+
+#[tokio::test]
+async fn stops_where_an_element_is_missing() {
+    let recording = fixture("paint_drag_raw.json");
+    let input = FakeInput::default();
+    let report = play(&recording, &input, &fast()).await.unwrap();
+    assert_eq!(report.stopped_at, Some(1));
+    assert_eq!(report.replayed, 1);
+    assert_eq!(
+        input.calls(),
+        vec!["move 100,100", "find role:Pane && name:Canvas"]
+    );
+
+    // Or carries on without the press
+    let input = FakeInput::default();
+    let options = PlaybackOptions {
+        stop_on_missing_element: false,
+        mouse_moves: false,
+        ..fast()
+    };
+    let report = play(&recording, &input, &options).await.unwrap();
+    assert_eq!(report.stopped_at, None);
+    assert_eq!(report.skipped[0].reason, SkipReason::MouseMove);
+    assert!(report
+        .skipped
+        .iter()
+        .any(|s| s.index == 1 && s.reason == SkipReason::ElementMissing));
+    assert_eq!(
+        &input.calls()[..3],
+        ["find role:Pane && name:Canvas", "move 400,250", "up"]
+    );
+}
+
+#[tokio::test]
+async fn replays_high_level_events_without_raw_input() {
+    let save = json!({"role": "Button", "name": "Save"});
+    let recording: SerializableRecordedWorkflow = serde_json::from_value(json!({
+        "format_version": 2,
+        "name": "Semantic",
+        "start_time": 0,
+        "events": [
+            {"timestamp": 0, "event": {"Click": {
+                "element_text": "Save", "interaction_type": "Click", "element_role": "Button",
+                "was_enabled": true, "click_position": {"x": 50, "y": 60},
+                "metadata": {"ui_element": save, "timestamp": 0}}}},
+            {"timestamp": 10, "event": {"Mouse": {
+                "event_type": "DoubleClick", "button": "Left", "position": {"x": 70, "y": 80},
+                "metadata": {"ui_element": save, "timestamp": 10}}}},
+            {"timestamp": 20, "event": {"DragDrop": {
+                "start_position": {"x": 1, "y": 2}, "end_position": {"x": 3, "y": 4},
+                "success": true, "metadata": {"timestamp": 20}}}},
+            {"timestamp": 30, "event": {"Hotkey": {
+                "combination": "Ctrl+Shift+S", "is_global": false,
+                "metadata": {"timestamp": 30}}}}
+        ]
+    }))
+    .unwrap();
+
+    let input = FakeInput::with_elements(&["role:Button && name:Save"]);
+    let report = play(&recording, &input, &fast()).await.unwrap();
+    assert_eq!(report.replayed, 4);
+    assert_eq!(
+        input.calls(),
+        vec![
+            "find role:Button && name:Save",
+            "Left 50,60",
+            // Already found for the previous event
+            "Double 70,80",
+            "drag 1,2 3,4",
+            "key {Ctrl}{Shift}{S}",
+        ]
+    );
+}
+
+fn monitor(id: &str, x: i32, width: u32, height: u32) -> Monitor {
+    Monitor {
+        id: id.to_string(),
+        name: id.to_string(),
+        is_primary: x == 0,
+        width,
+        height,
+        x,
+        y: 0,
+        scale_factor: 1.0,
+        work_area: None,
+    }
+}
+
+#[tokio::test]
+async fn remaps_coordinates_to_the_playback_monitors() {
+    let recording = fixture("paint_drag_raw.json");
+    let input = FakeInput::with_elements(&["role:Pane && name:Canvas"]);
+    let options = PlaybackOptions {
+        monitors: MonitorMap::new(
+            vec![monitor("DISPLAY1", 0, 1000, 500)],
+            vec![monitor("DISPLAY1", 0, 2000, 1000)],
+        ),
+        ..fast()
+    };
+    play(&recording, &input, &options).await.unwrap();
+    assert_eq!(
+        &input.calls()[..3],
+        [
+            "move 200,200",
+            "find role:Pane && name:Canvas",
+            "down 400,300"
+        ]
+    );
+
+    let options = PlaybackOptions {
+        speed: 0.0,
+        ..PlaybackOptions::default()
+    };
+    assert!(matches!(
+        play(&recording, &input, &options).await,
+        Err(WorkflowRecorderError::PlaybackError(_))
+    ));
+}
@@ -0,0 +1,188 @@
+//! Cleans up saved recordings and splits them into intents; runs on any platform.
+
+use terminator_workflow_recorder::{
+    compile, postprocess, CompileOptions, DropReason, IntentBoundary, PostProcessOptions,
+    ProcessedRecording, RecordedWorkflow, SerializableWorkflowEvent,
+};
+
+fn process_fixture(name: &str) -> ProcessedRecording {
+    let path = format!("{}/tests/fixtures/{name}", env!("CARGO_MANIFEST_DIR"));
+    let recording = RecordedWorkflow::load_from_file(&path).expect("fixture should load");
+    postprocess(&recording, &PostProcessOptions::default())
+}
+
+fn dropped(processed: &ProcessedRecording, reason: DropReason) -> Vec<usize> {
+    processed
+        .dropped
+        .iter()
+        .filter(|d| d.reason == reason)
+        .map(|d| d.index)
+        .collect()
+}
+
+fn intent_names(processed: &ProcessedRecording) -> Vec<&str> {
+    processed.intents.iter().map(|i| i.name.as_str()).collect()
+}
+
+#[test]
+fn drops_noise_with_reasons() {
+    let processed = process_fixture("sap_shipping_noisy.json");
+
+    assert_eq!(dropped(&processed, DropReason::Hover), vec![5, 6]);
+    // Alt down, Tab down and up before the switch, Alt up after it
+    assert_eq!(dropped(&processed, DropReason::AltTab), vec![0, 1, 2, 4]);
+    // 'X' and the Backspace that deleted it; the first text typed into Street
+    // and the click back into the field
+    assert_eq!(
+        dropped(&processed, DropReason::Correction),
+        vec![14, 15, 16, 17, 36, 37]
+    );
+    assert_eq!(dropped(&processed, DropReason::DuplicateClick), vec![26]);
+    assert_eq!(dropped(&processed, DropReason::DoubleClick), vec![31]);
+    // Outlook and straight back, and the switch back to the terminal at the end
+    assert_eq!(
+        dropped(&processed, DropReason::NoOpFocusChange),
+        vec![27, 28, 42]
+    );
+
+    let typed: Vec<char> = processed
+        .events
+        .iter()
+        .filter_map(|e| match &e.event {
+            SerializableWorkflowEvent::Keyboard(k) if k.is_key_down => k.character,
+            _ => None,
+        })
+        .collect();
+    assert_eq!(typed, vec!['J', 'D', 'O', 'E']);
+}
+
+#[test]
+fn merges_retyped_text_and_notes_double_clicks() {
+    let processed = process_fixture("sap_shipping_noisy.json");
+
+    let street = processed.events.iter().find(|e| e.index == 38).unwrap();
+    let SerializableWorkflowEvent::TextInputCompleted(input) = &street.event else {
+        panic!("expected the final Street input");
+    };
+    assert_eq!(input.text_value, "Main Street 1");
+    // From the start of the first attempt to the end of the last
+    assert_eq!(input.typing_duration_ms, 52_000 - (50_000 - 1500));
+    assert_eq!(input.keystroke_count, 9 + 17);
+    assert_eq!(
+        street.notes,
+        vec!["merged 2 text inputs into the final text"]
+    );
+
+    let open_orders = processed.events.iter().find(|e| e.index == 29).unwrap();
+    assert_eq!(open_orders.notes, vec!["double click"]);
+}
+
+#[test]
+fn segments_and_names_intents() {
+    let processed = process_fixture("sap_shipping_noisy.json");
+    assert_eq!(
+        intent_names(&processed),
+        vec![
+            "Log in to SAP",
+            "Double-click Open orders in SAP",
+            "Fill shipping form",
+        ]
+    );
+    let boundaries: Vec<IntentBoundary> = processed.intents.iter().map(|i| i.boundary).collect();
+    assert_eq!(
+        boundaries,
+        vec![
+            IntentBoundary::Start,
+            IntentBoundary::WindowChange,
+            IntentBoundary::ApplicationSwitch,
+        ]
+    );
+
+    let login = &processed.intents[0];
+    assert_eq!(login.application.as_deref(), Some("saplogon"));
+    assert_eq!((login.start_time, login.end_time), (1000, 4100));
+    let shipping = &processed.intents[2];
+    assert_eq!(shipping.application.as_deref(), Some("chrome"));
+    assert_eq!(
+        shipping.window.as_deref(),
+        Some("Shipping - Contoso Shop - Google Chrome")
+    );
+    // Every kept event belongs to the intent whose range holds it
+    for (i, intent) in processed.intents.iter().enumerate() {
+        assert!(processed
+            .intent_events(intent)
+            .iter()
+            .all(|e| e.intent == i));
+    }
+    assert_eq!(
+        processed.events.len() + processed.dropped.len(),
+        43,
+        "every event is either kept or dropped"
+    );
+}
+
+#[test]
+fn collapses_alt_tab_cycling() {
+    let processed = process_fixture("notepad_save.json");
+
+    // Calculator was only passed on the way to Notepad
+    assert_eq!(dropped(&processed, DropReason::AltTab), vec![0]);
+    let SerializableWorkflowEvent::ApplicationSwitch(switch) = &processed.events[0].event else {
+        panic!("expected the switch to Notepad first");
+    };
+    assert_eq!(
+        switch.from_process_name.as_deref(),
+        Some("WindowsTerminal.exe")
+    );
+    assert_eq!(switch.to_process_name.as_deref(), Some("Notepad.exe"));
+    assert_eq!(
+        processed.events[0].notes,
+        vec!["collapsed 2 application switches"]
+    );
+
+    assert_eq!(
+        intent_names(&processed),
+        vec!["Enter text editor in Notepad", "Click Save in Notepad"]
+    );
+}
+
+#[test]
+fn cleaned_recording_compiles_without_the_noise() {
+    let processed = process_fixture("sap_shipping_noisy.json");
+    let workflow = compile(&processed.to_workflow(), &CompileOptions::default());
+    let descriptions: Vec<&str> = workflow
+        .steps
+        .iter()
+        .map(|s| s.description.as_str())
+        .collect();
+
+    assert_eq!(
+        descriptions.iter().filter(|d| d.contains("Log On")).count(),
+        1,
+        "{descriptions:?}"
+    );
+    assert!(descriptions
+        .iter()
+        .any(|d| d.starts_with("Double-click") && d.contains("Open orders")));
+    let typed: Vec<&str> = workflow
+        .steps
+        .iter()
+        .filter_map(|s| s.arguments.get("text_to_type")?.as_str())
+        .collect();
+    assert!(typed.contains(&"Main Street 1"));
+    assert!(!typed.contains(&"Main St 1"));
+    assert!(!workflow.steps.iter().any(|s| s
+        .arguments
+        .get("app_name")
+        .is_some_and(|app| app == "outlook")));
+
+    // Same input, same output
+    let again = process_fixture("sap_shipping_noisy.json");
+    assert_eq!(
+        serde_json::to_string(&processed).unwrap(),
+        serde_json::to_string(&again).unwrap()
+    );
+    let json = serde_json::to_value(&processed).unwrap();
+    assert_eq!(json["dropped"][0]["reason"], "alt_tab");
+    assert_eq!(json["intents"][2]["boundary"], "application_switch");
+}
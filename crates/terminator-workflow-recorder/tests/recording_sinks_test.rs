@@ -0,0 +1,267 @@
+//! Streaming events to sinks and replaying JSONL journals; runs on any platform.
+
+use futures::StreamExt;
+use std::time::Duration;
+use terminator_workflow_recorder::{
+    replay_jsonl, EventMetadata, EventSink, FieldFocusMethod, JsonlSinkConfig, KeyboardEvent,
+    RecordedWorkflow, SerializableWorkflowEvent, SinkConfig, TextInputCompletedEvent,
+    TextInputMethod, WebSocketSinkConfig, WorkflowEvent,
+};
+use tokio::sync::broadcast;
+use tokio_tungstenite::tungstenite::client::IntoClientRequest;
+use tokio_tungstenite::tungstenite::Message;
+
+fn key(i: u64) -> WorkflowEvent {
+    let character = (b'a' + (i % 26) as u8) as char;
+    WorkflowEvent::Keyboard(KeyboardEvent {
+        key_code: character.to_ascii_uppercase() as u32,
+        is_key_down: true,
+        ctrl_pressed: false,
+        alt_pressed: false,
+        shift_pressed: false,
+        win_pressed: false,
+        character: Some(character),
+        scan_code: None,
+        metadata: EventMetadata {
+            ui_element: None,
+            timestamp: Some(1_000 + i * 100),
+        },
+    })
+}
+
+fn password_input(timestamp: u64) -> WorkflowEvent {
+    WorkflowEvent::TextInputCompleted(TextInputCompletedEvent {
+        text_value: "hunter2".to_string(),
+        field_name: Some("Password".to_string()),
+        field_type: "PasswordBox".to_string(),
+        input_method: TextInputMethod::Typed,
+        focus_method: FieldFocusMethod::MouseClick,
+        typing_duration_ms: 900,
+        keystroke_count: 7,
+        process_name: Some("contoso.exe".to_string()),
+        is_password: true,
+        metadata: EventMetadata {
+            ui_element: None,
+            timestamp: Some(timestamp),
+        },
+    })
+}
+
+fn jsonl_sink(path: &std::path::Path, max_file_bytes: u64) -> SinkConfig {
+    SinkConfig::Jsonl(JsonlSinkConfig {
+        path: path.to_path_buf(),
+        max_file_bytes,
+        ..JsonlSinkConfig::default()
+    })
+}
+
+#[tokio::test]
+async fn jsonl_journal_rotates_and_replays() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("session.jsonl");
+    let (tx, rx) = broadcast::channel(1000);
+    let sink = EventSink::start(&jsonl_sink(&path, 2_000), "Typing", 1_000, rx)
+        .await
+        .unwrap();
+
+    for i in 0..40 {
+        tx.send(key(i)).unwrap();
+    }
+    tx.send(password_input(6_000)).unwrap();
+    let stats = sink.finish(7_000).await;
+    assert_eq!((stats.written, stats.lagged, stats.dropped), (41, 0, 0));
+
+    let (recording, report) = replay_jsonl(&path).unwrap();
+    assert!(report.files.len() > 1, "{:?}", report.files);
+    assert!(dir.path().join("session.1.jsonl").exists());
+    assert!(report.complete);
+    assert_eq!((report.missing_events, report.skipped_lines), (0, 0));
+    assert!(report.load.dropped_fields.is_empty() && report.load.defaulted_fields.is_empty());
+    assert_eq!(recording.name, "Typing");
+    assert_eq!(
+        (recording.start_time, recording.end_time),
+        (1_000, Some(7_000))
+    );
+    assert_eq!(recording.events.len(), 41);
+
+    let SerializableWorkflowEvent::TextInputCompleted(input) = &recording.events[40].event else {
+        panic!("expected the password input last");
+    };
+    assert_ne!(input.text_value, "hunter2", "streamed events are redacted");
+
+    // An existing journal is never appended to by a new recording
+    let (_, rx) = broadcast::channel(1);
+    assert!(EventSink::start(&jsonl_sink(&path, 2_000), "Again", 0, rx)
+        .await
+        .is_err());
+}
+
+#[tokio::test]
+async fn journal_cut_off_by_a_crash_still_replays() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("crash.jsonl");
+    let (tx, rx) = broadcast::channel(1000);
+    let sink = EventSink::start(&jsonl_sink(&path, u64::MAX), "Crash", 1_000, rx)
+        .await
+        .unwrap();
+    for i in 0..5 {
+        tx.send(key(i)).unwrap();
+    }
+    sink.finish(2_000).await;
+
+    // Drop the end entry and cut the last event in half, as a crash would
+    let text = std::fs::read_to_string(&path).unwrap();
+    let mut lines: Vec<&str> = text.lines().collect();
+    lines.pop();
+    let last = lines.pop().unwrap();
+    let cut = format!("{}\n{}", lines.join("\n"), &last[..last.len() / 2]);
+    std::fs::write(&path, cut).unwrap();
+
+    let (recording, report) = replay_jsonl(&path).unwrap();
+    assert!(!report.complete);
+    assert_eq!(report.skipped_lines, 1);
+    assert_eq!(recording.events.len(), 4);
+    assert_eq!(recording.end_time, None);
+    assert_eq!(
+        RecordedWorkflow::load_from_file(&path)
+            .unwrap()
+            .events
+            .len(),
+        4
+    );
+}
+
+#[tokio::test]
+async fn lagging_sink_records_the_missed_events() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("lagged.jsonl");
+    let (tx, rx) = broadcast::channel(4);
+    let sink = EventSink::start(&jsonl_sink(&path, u64::MAX), "Lagged", 1_000, rx)
+        .await
+        .unwrap();
+    // The sink task cannot run before these are sent, so it falls behind
+    for i in 0..10 {
+        tx.send(key(i)).unwrap();
+    }
+    let stats = sink.finish(3_000).await;
+    assert_eq!((stats.written, stats.lagged), (4, 6));
+
+    let (recording, report) = replay_jsonl(&path).unwrap();
+    assert_eq!(report.missing_events, 6);
+    assert_eq!(recording.events.len(), 4);
+    assert!(std::fs::read_to_string(&path)
+        .unwrap()
+        .contains(r#"{"lagged":{"skipped":6}}"#));
+}
+
+async fn next_entry<S>(client: &mut S) -> serde_json::Value
+where
+    S: futures::Stream<Item = tokio_tungstenite::tungstenite::Result<Message>> + Unpin,
+{
+    loop {
+        let message = tokio::time::timeout(Duration::from_secs(5), client.next())
+            .await
+            .expect("entry in time")
+            .expect("connection open")
+            .unwrap();
+        if let Message::Text(text) = message {
+            return serde_json::from_str(&text).unwrap();
+        }
+    }
+}
+
+#[tokio::test]
+async fn websocket_clients_resume_after_reconnecting() {
+    let (tx, rx) = broadcast::channel(1000);
+    let config = SinkConfig::WebSocket(WebSocketSinkConfig {
+        address: "127.0.0.1:0".to_string(),
+        ..WebSocketSinkConfig::default()
+    });
+    let sink = EventSink::start(&config, "Live", 1_000, rx).await.unwrap();
+    let url = sink.url().unwrap().to_string();
+
+    let (mut client, _) = tokio_tungstenite::connect_async(&url).await.unwrap();
+    assert_eq!(next_entry(&mut client).await["start"]["name"], "Live");
+    tx.send(key(0)).unwrap();
+    tx.send(key(1)).unwrap();
+    assert_eq!(next_entry(&mut client).await["event"]["seq"], 1);
+    assert_eq!(next_entry(&mut client).await["event"]["seq"], 2);
+    client.close(None).await.unwrap();
+
+    // Events recorded while the dashboard was away are replayed
+    tx.send(key(2)).unwrap();
+    tx.send(key(3)).unwrap();
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    let (mut client, _) = tokio_tungstenite::connect_async(format!("{url}&since=2"))
+        .await
+        .unwrap();
+    assert!(next_entry(&mut client).await.get("start").is_some());
+    let seqs = [
+        next_entry(&mut client).await["event"]["seq"].clone(),
+        next_entry(&mut client).await["event"]["seq"].clone(),
+    ];
+    assert_eq!(seqs, [3, 4]);
+
+    let finishing = tokio::spawn(sink.finish(9_000));
+    assert_eq!(next_entry(&mut client).await["end"]["end_time"], 9_000);
+    assert_eq!(finishing.await.unwrap().written, 4);
+}
+
+#[tokio::test]
+async fn websocket_clients_need_the_token_and_an_allowed_origin() {
+    let (_tx, rx) = broadcast::channel(1000);
+    let config = SinkConfig::WebSocket(WebSocketSinkConfig {
+        address: "127.0.0.1:0".to_string(),
+        allowed_origins: vec!["http://localhost:3000".to_string()],
+        ..WebSocketSinkConfig::default()
+    });
+    let sink = EventSink::start(&config, "Live", 1_000, rx).await.unwrap();
+    let url = sink.url().unwrap().to_string();
+    let addr = sink.local_addr().unwrap();
+
+    let status = |result: Result<_, tokio_tungstenite::tungstenite::Error>| match result {
+        Err(tokio_tungstenite::tungstenite::Error::Http(response)) => response.status().as_u16(),
+        other => panic!("expected a rejected handshake, got {other:?}"),
+    };
+    for url in [format!("ws://{addr}/"), format!("ws://{addr}/?token=guess")] {
+        let result = tokio_tungstenite::connect_async(url).await.map(|_| ());
+        assert_eq!(status(result), 401);
+    }
+
+    let from = |origin: &str| {
+        let mut request = url.as_str().into_client_request().unwrap();
+        request
+            .headers_mut()
+            .insert("Origin", origin.parse().unwrap());
+        request
+    };
+    let result = tokio_tungstenite::connect_async(from("https://evil.example"))
+        .await
+        .map(|_| ());
+    assert_eq!(status(result), 403);
+
+    let (mut client, _) = tokio_tungstenite::connect_async(from("http://localhost:3000"))
+        .await
+        .unwrap();
+    assert!(next_entry(&mut client).await.get("start").is_some());
+    sink.finish(2_000).await;
+}
+
+#[test]
+fn sinks_are_configured_by_type() {
+    let sinks: Vec<SinkConfig> = serde_json::from_value(serde_json::json!([
+        {"type": "jsonl", "path": "session.jsonl"},
+        {"type": "websocket", "address": "127.0.0.1:9000"},
+        {"type": "stdout"}
+    ]))
+    .unwrap();
+    assert_eq!(
+        sinks[0],
+        SinkConfig::Jsonl(JsonlSinkConfig {
+            path: "session.jsonl".into(),
+            ..JsonlSinkConfig::default()
+        })
+    );
+    assert!(matches!(&sinks[1], SinkConfig::WebSocket(ws) if ws.client_buffer == 256));
+    assert_eq!(sinks[2], SinkConfig::Stdout);
+}
@@ -0,0 +1,135 @@
+//! Synthesizes selectors against a saved window snapshot; runs on any platform.
+
+use terminator::UINode;
+use terminator_workflow_recorder::{find_node_path, synthesize_selectors, SelectorCandidate};
+
+fn window() -> UINode {
+    let path = format!(
+        "{}/tests/fixtures/invoice_window_tree.json",
+        env!("CARGO_MANIFEST_DIR")
+    );
+    let json = std::fs::read_to_string(path).expect("fixture should exist");
+    serde_json::from_str(&json).expect("fixture should parse")
+}
+
+fn selectors(candidates: &[SelectorCandidate]) -> Vec<&str> {
+    candidates.iter().map(|c| c.selector.as_str()).collect()
+}
+
+fn total(candidate: &SelectorCandidate) -> u32 {
+    candidate
+        .score
+        .as_ref()
+        .expect("synthesized candidates are scored")
+        .total
+}
+
+#[test]
+fn automation_id_beats_ambiguous_name() {
+    let root = window();
+    let candidates = synthesize_selectors(&root, &[3, 0]);
+    assert_eq!(candidates[0].selector, "nativeid:btnSave");
+    assert_eq!(candidates[0].selector_type, "AutomationId");
+
+    // 'name:Save' also matches 'Save as draft', so it only works as find-first
+    let by_name = candidates
+        .iter()
+        .find(|c| c.selector == "role:Button && name:Save")
+        .unwrap();
+    assert_eq!(by_name.score.as_ref().unwrap().matches, 2);
+    assert!(total(by_name) < total(&candidates[0]));
+
+    // The window title changes with the open invoice and never goes in
+    for candidate in synthesize_selectors(&root, &[0, 2]) {
+        assert!(!candidate.selector.contains("Invoice #4711"));
+    }
+}
+
+#[test]
+fn unnamed_fields_are_anchored_to_their_label() {
+    let root = window();
+    let email = synthesize_selectors(&root, &[1, 0, 1]);
+    assert_eq!(
+        email[0].selector,
+        "role:Text && name:Email >> .. >> role:Edit"
+    );
+    assert_eq!(email[0].selector_type, "Anchored");
+
+    let phone = synthesize_selectors(&root, &[1, 1, 1]);
+    assert_eq!(
+        selectors(&phone),
+        vec![
+            "role:Text && name:Phone >> .. >> role:Edit",
+            "nativeid:CustomerForm >> role:Edit >> nth:1",
+        ]
+    );
+    // 'nativeid:CustomerForm >> role:Edit' would find the email field first
+    assert!(phone
+        .iter()
+        .all(|c| c.selector != "nativeid:CustomerForm >> role:Edit"));
+}
+
+#[test]
+fn list_position_is_last_resort_and_penalized() {
+    let root = window();
+    let candidates = synthesize_selectors(&root, &[2, 2]);
+    assert_eq!(
+        selectors(&candidates),
+        vec!["role:List && name:Line items >> role:ListItem >> nth:2"]
+    );
+    let score = candidates[0].score.as_ref().unwrap();
+    assert_eq!(score.matches, 1);
+    assert!(score.penalties.iter().any(|p| p.contains("list position")));
+
+    let customers = synthesize_selectors(&root, &[0, 2]);
+    assert_eq!(customers[0].selector, "role:ListItem && name:Customers");
+    assert_eq!(customers[0].selector_type, "RoleName");
+    assert!(customers
+        .iter()
+        .any(|c| c.selector == "role:Pane && name:Navigation >> role:ListItem && name:Customers"));
+}
+
+#[test]
+fn generated_ids_rank_below_stable_names() {
+    let root = window();
+    let path = find_node_path(&root, |n| {
+        n.attributes.name.as_deref() == Some("3 notifications")
+    })
+    .unwrap();
+    assert_eq!(path, vec![3, 2]);
+
+    let candidates = synthesize_selectors(&root, &path);
+    assert_eq!(
+        candidates[0].selector,
+        "role:Button && name:3 notifications"
+    );
+    let generated = candidates
+        .iter()
+        .find(|c| c.selector == "nativeid:a3f9c27d11e8")
+        .unwrap();
+    assert!(generated
+        .score
+        .as_ref()
+        .unwrap()
+        .penalties
+        .iter()
+        .any(|p| p.contains("looks generated")));
+    assert!(total(generated) < total(&candidates[0]));
+}
+
+#[test]
+fn candidates_round_trip_and_old_recordings_still_load() {
+    let root = window();
+    let candidates = synthesize_selectors(&root, &[3, 0]);
+    let json = serde_json::to_string(&candidates).unwrap();
+    let parsed: Vec<SelectorCandidate> = serde_json::from_str(&json).unwrap();
+    assert_eq!(parsed[0].score, candidates[0].score);
+
+    // DOM candidates recorded before scores existed have no score field
+    let old: SelectorCandidate = serde_json::from_str(
+        r##"{"selector":"#submit","selector_type":"Id","specificity":100,"requires_jquery":false}"##,
+    )
+    .unwrap();
+    assert!(old.score.is_none());
+    assert!(!serde_json::to_string(&old).unwrap().contains("score"));
+}
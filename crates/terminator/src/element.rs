@@ -611,6 +611,12 @@ pub trait UIElementImpl: Send + Sync + Debug {
     // New method for keyboard focusable
     fn is_keyboard_focusable(&self) -> Result<bool, AutomationError>;
 
+    /// Whether the element is a password field whose content should never be
+    /// logged. Platforms without the information report `false`.
+    fn is_password(&self) -> Result<bool, AutomationError> {
+        Ok(false)
+    }
+
     // New method for mouse drag
     fn mouse_drag(
         &self,
@@ -1183,6 +1189,11 @@ impl UIElement {
         self.inner.is_keyboard_focusable()
     }
 
+    /// Check if element is a password field
+    pub fn is_password(&self) -> Result<bool, AutomationError> {
+        self.inner.is_password()
+    }
+
     /// Drag mouse from start to end coordinates
     pub fn mouse_drag(
         &self,
@@ -16,6 +16,7 @@ pub mod extension_bridge;
 pub mod health;
 pub mod locator;
 pub mod platforms;
+pub mod redaction;
 pub mod screenshot;
 pub mod screenshot_logger;
 pub mod selector;
@@ -1585,6 +1585,17 @@ impl UIElementImpl for WindowsUIElement {
         })
     }
 
+    fn is_password(&self) -> Result<bool, AutomationError> {
+        let variant = self
+            .element
+            .0
+            .get_property_value(UIProperty::IsPassword)
+            .map_err(|e| AutomationError::PlatformError(e.to_string()))?;
+        variant.try_into().map_err(|e| {
+            AutomationError::PlatformError(format!("Failed to convert IsPassword to bool: {e:?}"))
+        })
+    }
+
     // New method for mouse drag
     fn mouse_drag(
         &self,
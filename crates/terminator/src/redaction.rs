@@ -0,0 +1,740 @@
+//! Privacy redaction for recordings, execution logs and logged screenshots
+//!
+//! Everything Terminator writes to disk goes through a [`Redactor`] first. It
+//! masks values of password fields (UI Automation `IsPassword`), text matching
+//! the configured patterns (card numbers, emails, SSNs, custom regexes),
+//! values under sensitive JSON keys, and all content from opted-out
+//! applications. Password fields seen in tool results are also pixelated in
+//! screenshots saved shortly afterwards.
+//!
+//! The process-wide redactor ([`global`]) is configured by `redaction.yaml` in
+//! the Terminator data directory, or the file named by
+//! `TERMINATOR_REDACTION_CONFIG`:
+//!
+//! ```yaml
+//! patterns: [card_number, email, ssn]
+//! custom_patterns:
+//!   - name: employee_id
+//!     regex: 'EMP-\d{6}'
+//! excluded_applications: [keepass, 1password.exe]
+//! ```
+//!
+//! Without a file only password fields and the default sensitive keys are
+//! masked.
+
+use crate::{AutomationError, ScreenshotResult};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::borrow::Cow;
+use std::collections::HashSet;
+use std::ops::Range;
+use std::path::PathBuf;
+use std::sync::{Arc, LazyLock, Mutex, RwLock};
+use std::time::{Duration, Instant};
+use tracing::{info, warn};
+
+/// Replacement for password field values, sensitive keys and opted-out applications
+pub const REDACTED: &str = "[REDACTED]";
+
+/// How long a password field's bounds are pixelated in new screenshots
+const REGION_TTL: Duration = Duration::from_secs(60);
+const MAX_REGIONS: usize = 32;
+/// Side of the pixelation blocks, in image pixels
+const PIXEL_BLOCK: u32 = 16;
+
+/// Keys that hold the value of an element in tool results and recordings
+const ELEMENT_VALUE_KEYS: &[&str] = &["value", "text", "text_value", "input_value"];
+/// Keys that name the application an element or event belongs to
+const APPLICATION_KEYS: &[&str] = &["application", "application_name", "process_name"];
+
+/// Screen bounds as `(x, y, width, height)`
+type Bounds = (f64, f64, f64, f64);
+
+/// Built-in patterns that can be switched on by name
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BuiltinPattern {
+    /// 13-19 digit card numbers (spaces or dashes allowed) that pass the Luhn check
+    CardNumber,
+    Email,
+    /// US social security numbers written as `123-45-6789`
+    Ssn,
+}
+
+impl BuiltinPattern {
+    fn name(self) -> &'static str {
+        match self {
+            BuiltinPattern::CardNumber => "card_number",
+            BuiltinPattern::Email => "email",
+            BuiltinPattern::Ssn => "ssn",
+        }
+    }
+
+    fn regex(self) -> &'static str {
+        match self {
+            BuiltinPattern::CardNumber => r"\b\d(?:[ -]?\d){12,18}\b",
+            BuiltinPattern::Email => r"\b[A-Za-z0-9._%+-]+@[A-Za-z0-9.-]+\.[A-Za-z]{2,}\b",
+            BuiltinPattern::Ssn => r"\b\d{3}-\d{2}-\d{4}\b",
+        }
+    }
+}
+
+/// A named regex whose matches are replaced by `[REDACTED:<name>]`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct CustomPattern {
+    pub name: String,
+    pub regex: String,
+}
+
+/// What gets redacted. Every field is optional in `redaction.yaml`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RedactionConfig {
+    /// Master switch; nothing is redacted when false
+    pub enabled: bool,
+    /// Mask values of password fields
+    pub passwords: bool,
+    /// Built-in patterns masked wherever they appear
+    pub patterns: Vec<BuiltinPattern>,
+    /// Additional patterns masked wherever they appear
+    pub custom_patterns: Vec<CustomPattern>,
+    /// JSON keys (case-insensitive) whose values are always masked
+    pub sensitive_fields: Vec<String>,
+    /// Applications whose typed text, clipboard and element values are never
+    /// written. Matched case-insensitively against the process name, with or
+    /// without `.exe`.
+    pub excluded_applications: Vec<String>,
+    /// Pixelate recently seen password fields in saved screenshots
+    pub screenshots: bool,
+}
+
+impl Default for RedactionConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            passwords: true,
+            patterns: Vec::new(),
+            custom_patterns: Vec::new(),
+            sensitive_fields: [
+                "password",
+                "passwd",
+                "pwd",
+                "secret",
+                "client_secret",
+                "api_key",
+                "access_token",
+            ]
+            .map(String::from)
+            .to_vec(),
+            excluded_applications: Vec::new(),
+            screenshots: true,
+        }
+    }
+}
+
+impl RedactionConfig {
+    /// Parses a YAML (or JSON) configuration
+    pub fn parse(content: &str) -> Result<Self, AutomationError> {
+        serde_yaml::from_str(content)
+            .map_err(|e| AutomationError::InvalidArgument(format!("Invalid redaction config: {e}")))
+    }
+}
+
+/// Where a screenshot sits on the screen, to place element bounds in it
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ImageOrigin {
+    /// Screen coordinates of the image's top-left corner
+    pub x: f64,
+    pub y: f64,
+    /// Image pixels per screen pixel (below 1 when the image was downscaled)
+    pub scale: f64,
+}
+
+/// Summary of a [`Redactor::redact_json`] pass
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct JsonRedaction {
+    /// Number of values that were masked or rewritten
+    pub masked: usize,
+    /// Number of password fields found
+    pub password_fields: usize,
+    /// Whether content from an excluded application was found
+    pub excluded_application: bool,
+}
+
+impl JsonRedaction {
+    /// Whether the value touched a password field or an excluded application
+    pub fn is_sensitive(&self) -> bool {
+        self.password_fields > 0 || self.excluded_application
+    }
+}
+
+struct NamedPattern {
+    name: String,
+    regex: Regex,
+    luhn: bool,
+}
+
+/// Applies a [`RedactionConfig`]
+pub struct Redactor {
+    config: RedactionConfig,
+    patterns: Vec<NamedPattern>,
+    sensitive_fields: HashSet<String>,
+    excluded_applications: Vec<String>,
+    regions: Mutex<Vec<(Bounds, Instant)>>,
+}
+
+impl std::fmt::Debug for Redactor {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Redactor")
+            .field("config", &self.config)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Default for Redactor {
+    fn default() -> Self {
+        Self::new(RedactionConfig::default()).expect("default redaction config is valid")
+    }
+}
+
+impl Redactor {
+    /// Compiles the configured patterns
+    pub fn new(config: RedactionConfig) -> Result<Self, AutomationError> {
+        let mut patterns: Vec<NamedPattern> = config
+            .patterns
+            .iter()
+            .map(|p| NamedPattern {
+                name: p.name().to_string(),
+                regex: Regex::new(p.regex()).expect("built-in patterns are valid"),
+                luhn: *p == BuiltinPattern::CardNumber,
+            })
+            .collect();
+        for custom in &config.custom_patterns {
+            let regex = Regex::new(&custom.regex).map_err(|e| {
+                AutomationError::InvalidArgument(format!(
+                    "Invalid regex for redaction pattern '{}': {e}",
+                    custom.name
+                ))
+            })?;
+            patterns.push(NamedPattern {
+                name: custom.name.clone(),
+                regex,
+                luhn: false,
+            });
+        }
+        let sensitive_fields = config
+            .sensitive_fields
+            .iter()
+            .map(|f| normalize_key(f))
+            .collect();
+        let excluded_applications = config
+            .excluded_applications
+            .iter()
+            .map(|a| normalize_application(a))
+            .collect();
+        Ok(Self {
+            config,
+            patterns,
+            sensitive_fields,
+            excluded_applications,
+            regions: Mutex::new(Vec::new()),
+        })
+    }
+
+    /// A redactor that leaves everything as is
+    pub fn disabled() -> Self {
+        Self::new(RedactionConfig {
+            enabled: false,
+            ..RedactionConfig::default()
+        })
+        .expect("default redaction config is valid")
+    }
+
+    pub fn config(&self) -> &RedactionConfig {
+        &self.config
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.config.enabled
+    }
+
+    /// Whether values of password fields are masked
+    pub fn masks_passwords(&self) -> bool {
+        self.config.enabled && self.config.passwords
+    }
+
+    /// Whether `application` (a process name such as `KeePass.exe`) opted out
+    pub fn is_excluded_application(&self, application: &str) -> bool {
+        if !self.config.enabled || application.is_empty() {
+            return false;
+        }
+        let application = normalize_application(application);
+        self.excluded_applications.contains(&application)
+    }
+
+    /// Byte ranges of `text` matched by the configured patterns, sorted and
+    /// non-overlapping, each with the name of the pattern
+    pub fn find_matches(&self, text: &str) -> Vec<(Range<usize>, &str)> {
+        if !self.config.enabled {
+            return Vec::new();
+        }
+        let mut matches: Vec<(Range<usize>, &str)> = Vec::new();
+        for pattern in &self.patterns {
+            for m in pattern.regex.find_iter(text) {
+                if pattern.luhn && !passes_luhn(m.as_str()) {
+                    continue;
+                }
+                matches.push((m.range(), pattern.name.as_str()));
+            }
+        }
+        matches.sort_by_key(|(range, _)| (range.start, std::cmp::Reverse(range.end)));
+        let mut merged: Vec<(Range<usize>, &str)> = Vec::new();
+        for (range, name) in matches {
+            match merged.last_mut() {
+                Some((last, _)) if range.start < last.end => last.end = last.end.max(range.end),
+                _ => merged.push((range, name)),
+            }
+        }
+        merged
+    }
+
+    /// Replaces pattern matches with `[REDACTED:<pattern>]`
+    pub fn redact_text<'a>(&self, text: &'a str) -> Cow<'a, str> {
+        let matches = self.find_matches(text);
+        if matches.is_empty() {
+            return Cow::Borrowed(text);
+        }
+        let mut redacted = String::with_capacity(text.len());
+        let mut last = 0;
+        for (range, name) in matches {
+            redacted.push_str(&text[last..range.start]);
+            redacted.push_str(&format!("[REDACTED:{name}]"));
+            last = range.end;
+        }
+        redacted.push_str(&text[last..]);
+        Cow::Owned(redacted)
+    }
+
+    /// [`Redactor::redact_text`] in place; returns whether anything changed
+    pub fn redact_string(&self, text: &mut String) -> bool {
+        match self.redact_text(text) {
+            Cow::Owned(redacted) => {
+                *text = redacted;
+                true
+            }
+            Cow::Borrowed(_) => false,
+        }
+    }
+
+    /// Redacts a JSON value in place: element objects with `is_password: true`
+    /// or an excluded `application` get their value masked (and their bounds
+    /// noted for screenshots), sensitive keys are masked, and pattern matches
+    /// are replaced in every string, including JSON embedded in strings.
+    pub fn redact_json(&self, value: &mut Value) -> JsonRedaction {
+        let mut summary = JsonRedaction::default();
+        if self.config.enabled {
+            self.redact_value(value, &mut summary);
+        }
+        summary
+    }
+
+    fn redact_value(&self, value: &mut Value, summary: &mut JsonRedaction) {
+        match value {
+            Value::Object(map) => {
+                let password = self.config.passwords
+                    && ["is_password", "IsPassword"]
+                        .iter()
+                        .any(|key| map.get(*key).and_then(Value::as_bool) == Some(true));
+                let excluded = APPLICATION_KEYS.iter().any(|key| {
+                    map.get(*key)
+                        .and_then(Value::as_str)
+                        .is_some_and(|app| self.is_excluded_application(app))
+                });
+                if password {
+                    summary.password_fields += 1;
+                    if let Some(bounds) = map.get("bounds").and_then(parse_bounds) {
+                        self.note_sensitive_region(bounds);
+                    }
+                }
+                summary.excluded_application |= excluded;
+                for (key, child) in map.iter_mut() {
+                    let masked_value = ((password || excluded)
+                        && ELEMENT_VALUE_KEYS.contains(&key.as_str()))
+                        || self.sensitive_fields.contains(&normalize_key(key));
+                    if masked_value && !child.is_null() && child.as_str() != Some("") {
+                        if child.as_str() != Some(REDACTED) {
+                            *child = Value::String(REDACTED.to_string());
+                            summary.masked += 1;
+                        }
+                    } else {
+                        self.redact_value(child, summary);
+                    }
+                }
+            }
+            Value::Array(items) => {
+                for item in items {
+                    self.redact_value(item, summary);
+                }
+            }
+            Value::String(text) => {
+                let trimmed = text.trim_start();
+                if trimmed.starts_with('{') || trimmed.starts_with('[') {
+                    if let Ok(mut embedded) = serde_json::from_str::<Value>(text) {
+                        let before = *summary;
+                        self.redact_value(&mut embedded, summary);
+                        if *summary != before {
+                            if let Ok(rewritten) = serde_json::to_string(&embedded) {
+                                *text = rewritten;
+                            }
+                        }
+                        return;
+                    }
+                }
+                if self.redact_string(text) {
+                    summary.masked += 1;
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Remembers the screen bounds (x, y, width, height) of a password field
+    /// so screenshots saved in the next minute pixelate it
+    pub fn note_sensitive_region(&self, bounds: Bounds) {
+        if !self.config.enabled || !self.config.screenshots || bounds.2 <= 0.0 || bounds.3 <= 0.0 {
+            return;
+        }
+        let mut regions = self.regions.lock().unwrap_or_else(|e| e.into_inner());
+        let now = Instant::now();
+        regions
+            .retain(|(region, seen)| *region != bounds && now.duration_since(*seen) < REGION_TTL);
+        if regions.len() >= MAX_REGIONS {
+            regions.remove(0);
+        }
+        regions.push((bounds, now));
+    }
+
+    /// Password field bounds noted in the last minute
+    pub fn sensitive_regions(&self) -> Vec<Bounds> {
+        let now = Instant::now();
+        self.regions
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .iter()
+            .filter(|(_, seen)| now.duration_since(*seen) < REGION_TTL)
+            .map(|(region, _)| *region)
+            .collect()
+    }
+
+    /// Pixelates the noted password fields in a screenshot. Returns `None`
+    /// when there is nothing to hide. Without an `origin` the fields can't be
+    /// placed, so the whole image is pixelated.
+    pub fn redact_screenshot(
+        &self,
+        screenshot: &ScreenshotResult,
+        origin: Option<ImageOrigin>,
+    ) -> Option<ScreenshotResult> {
+        let mut redacted = screenshot.clone();
+        self.pixelate_regions(
+            &mut redacted.image_data,
+            redacted.width,
+            redacted.height,
+            origin,
+        )
+        .then_some(redacted)
+    }
+
+    /// [`Redactor::redact_screenshot`] for an encoded image; returns the
+    /// re-encoded PNG, or `None` when nothing was hidden (or it can't be decoded)
+    pub fn redact_png(&self, bytes: &[u8], origin: Option<ImageOrigin>) -> Option<Vec<u8>> {
+        if self.sensitive_regions().is_empty() || !self.config.screenshots {
+            return None;
+        }
+        let mut rgba = match image::load_from_memory(bytes) {
+            Ok(decoded) => decoded.to_rgba8(),
+            Err(e) => {
+                warn!("[redaction] Can't decode screenshot to redact it: {}", e);
+                return None;
+            }
+        };
+        let (width, height) = rgba.dimensions();
+        if !self.pixelate_regions(&mut rgba, width, height, origin) {
+            return None;
+        }
+        let mut png = Vec::new();
+        rgba.write_to(&mut std::io::Cursor::new(&mut png), image::ImageFormat::Png)
+            .map_err(|e| warn!("[redaction] Failed to encode redacted screenshot: {}", e))
+            .ok()?;
+        Some(png)
+    }
+
+    fn pixelate_regions(
+        &self,
+        pixels: &mut [u8],
+        width: u32,
+        height: u32,
+        origin: Option<ImageOrigin>,
+    ) -> bool {
+        if !self.config.enabled || !self.config.screenshots {
+            return false;
+        }
+        let regions = self.sensitive_regions();
+        if regions.is_empty() {
+            return false;
+        }
+        let Some(origin) = origin else {
+            pixelate(pixels, width, height, (0, 0, width, height));
+            return true;
+        };
+        let mut changed = false;
+        for (x, y, w, h) in regions {
+            let left = ((x - origin.x) * origin.scale).floor().max(0.0);
+            let top = ((y - origin.y) * origin.scale).floor().max(0.0);
+            let right = ((x + w - origin.x) * origin.scale).ceil().min(width as f64);
+            let bottom = ((y + h - origin.y) * origin.scale)
+                .ceil()
+                .min(height as f64);
+            if right <= left || bottom <= top {
+                continue;
+            }
+            pixelate(
+                pixels,
+                width,
+                height,
+                (left as u32, top as u32, right as u32, bottom as u32),
+            );
+            changed = true;
+        }
+        changed
+    }
+}
+
+/// Averages each `PIXEL_BLOCK` square inside `area` (left, top, right,
+/// bottom) of a 4-bytes-per-pixel image, whatever the channel order
+fn pixelate(pixels: &mut [u8], width: u32, height: u32, area: (u32, u32, u32, u32)) {
+    let (left, top, right, bottom) = area;
+    if pixels.len() < (width as usize) * (height as usize) * 4 {
+        return;
+    }
+    let index = |x: u32, y: u32| ((y as usize) * (width as usize) + x as usize) * 4;
+    for block_y in (top..bottom).step_by(PIXEL_BLOCK as usize) {
+        for block_x in (left..right).step_by(PIXEL_BLOCK as usize) {
+            let x_end = (block_x + PIXEL_BLOCK).min(right);
+            let y_end = (block_y + PIXEL_BLOCK).min(bottom);
+            let mut sum = [0u64; 4];
+            for y in block_y..y_end {
+                for x in block_x..x_end {
+                    let i = index(x, y);
+                    for (channel, total) in sum.iter_mut().enumerate() {
+                        *total += pixels[i + channel] as u64;
+                    }
+                }
+            }
+            let count = ((x_end - block_x) * (y_end - block_y)) as u64;
+            let average = sum.map(|total| (total / count) as u8);
+            for y in block_y..y_end {
+                for x in block_x..x_end {
+                    let i = index(x, y);
+                    pixels[i..i + 4].copy_from_slice(&average);
+                }
+            }
+        }
+    }
+}
+
+/// Reads `{x, y, width, height}` or `[x, y, width, height]` bounds
+fn parse_bounds(value: &Value) -> Option<(f64, f64, f64, f64)> {
+    match value {
+        Value::Object(map) => Some((
+            map.get("x")?.as_f64()?,
+            map.get("y")?.as_f64()?,
+            map.get("width")?.as_f64()?,
+            map.get("height")?.as_f64()?,
+        )),
+        Value::Array(items) if items.len() == 4 => Some((
+            items[0].as_f64()?,
+            items[1].as_f64()?,
+            items[2].as_f64()?,
+            items[3].as_f64()?,
+        )),
+        _ => None,
+    }
+}
+
+fn passes_luhn(candidate: &str) -> bool {
+    let digits: Vec<u32> = candidate.chars().filter_map(|c| c.to_digit(10)).collect();
+    if !(13..=19).contains(&digits.len()) {
+        return false;
+    }
+    let sum: u32 = digits
+        .iter()
+        .rev()
+        .enumerate()
+        .map(|(i, &d)| match (i % 2 == 1, d * 2) {
+            (true, doubled) if doubled > 9 => doubled - 9,
+            (true, doubled) => doubled,
+            (false, _) => d,
+        })
+        .sum();
+    sum.is_multiple_of(10)
+}
+
+fn normalize_key(key: &str) -> String {
+    key.to_lowercase().replace('-', "_")
+}
+
+fn normalize_application(application: &str) -> String {
+    let lower = application.trim().to_lowercase();
+    lower.strip_suffix(".exe").unwrap_or(&lower).to_string()
+}
+
+/// Path of the redaction config: `TERMINATOR_REDACTION_CONFIG`, or
+/// `redaction.yaml` in the Terminator data directory
+pub fn config_path() -> PathBuf {
+    std::env::var_os("TERMINATOR_REDACTION_CONFIG")
+        .map(PathBuf::from)
+        .unwrap_or_else(|| {
+            dirs::data_local_dir()
+                .unwrap_or_else(std::env::temp_dir)
+                .join("terminator")
+                .join("redaction.yaml")
+        })
+}
+
+/// Loads the redactor from [`config_path`], falling back to the default
+/// configuration (with a warning) when the file is missing or invalid
+pub fn load() -> Redactor {
+    let path = config_path();
+    let Ok(content) = std::fs::read_to_string(&path) else {
+        return Redactor::default();
+    };
+    match RedactionConfig::parse(&content).and_then(Redactor::new) {
+        Ok(redactor) => {
+            info!("[redaction] Loaded {}", path.display());
+            redactor
+        }
+        Err(e) => {
+            warn!("[redaction] Ignoring {}: {}", path.display(), e);
+            Redactor::default()
+        }
+    }
+}
+
+static GLOBAL: LazyLock<RwLock<Arc<Redactor>>> = LazyLock::new(|| RwLock::new(Arc::new(load())));
+
+/// The process-wide redactor used by the loggers and the recorder
+pub fn global() -> Arc<Redactor> {
+    GLOBAL.read().unwrap_or_else(|e| e.into_inner()).clone()
+}
+
+/// Replaces the process-wide redactor, e.g. with one built from a config
+/// passed on the command line
+pub fn set_global(redactor: Redactor) {
+    *GLOBAL.write().unwrap_or_else(|e| e.into_inner()) = Arc::new(redactor);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn redactor(yaml: &str) -> Redactor {
+        Redactor::new(RedactionConfig::parse(yaml).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn builtin_patterns_mask_only_real_matches() {
+        let r = redactor("patterns: [card_number, email, ssn]");
+        assert_eq!(
+            r.redact_text("Card 4111 1111 1111 1111, mail jane.doe@contoso.com"),
+            "Card [REDACTED:card_number], mail [REDACTED:email]"
+        );
+        assert_eq!(r.redact_text("SSN 123-45-6789"), "SSN [REDACTED:ssn]");
+        // Fails the Luhn check, so it is an order number and not a card
+        assert_eq!(
+            r.redact_text("Order 1234567890123456"),
+            "Order 1234567890123456"
+        );
+        assert!(matches!(r.redact_text("nothing here"), Cow::Borrowed(_)));
+    }
+
+    #[test]
+    fn custom_patterns_and_invalid_config() {
+        let r = redactor("custom_patterns:\n  - name: employee_id\n    regex: 'EMP-\\d{6}'\n");
+        assert_eq!(
+            r.redact_text("Badge EMP-004211"),
+            "Badge [REDACTED:employee_id]"
+        );
+        let bad = RedactionConfig::parse("custom_patterns:\n  - name: x\n    regex: '('\n")
+            .and_then(Redactor::new);
+        assert!(bad.is_err());
+        assert!(RedactionConfig::parse("pattern: [email]").is_err());
+    }
+
+    #[test]
+    fn json_masks_password_fields_sensitive_keys_and_excluded_apps() {
+        let r = redactor("patterns: [email]\nexcluded_applications: [KeePass.exe]");
+        let mut value = json!({
+            "element": {"role": "Edit", "name": "Password", "text": "hunter2",
+                        "is_password": true, "bounds": {"x": 10, "y": 20, "width": 100, "height": 30}},
+            "env": {"API_KEY": "abc123", "user": "jane@contoso.com"},
+            "content": [{"type": "text", "text": "{\"application\":\"keepass\",\"value\":\"vault\"}"}],
+        });
+        let summary = r.redact_json(&mut value);
+        assert_eq!(value["element"]["text"], REDACTED);
+        assert_eq!(value["element"]["name"], "Password");
+        assert_eq!(value["env"]["API_KEY"], REDACTED);
+        assert_eq!(value["env"]["user"], "[REDACTED:email]");
+        assert_eq!(
+            value["content"][0]["text"],
+            "{\"application\":\"keepass\",\"value\":\"[REDACTED]\"}"
+        );
+        assert_eq!(summary.password_fields, 1);
+        assert!(summary.excluded_application);
+        assert_eq!(r.sensitive_regions(), vec![(10.0, 20.0, 100.0, 30.0)]);
+
+        let mut untouched = value.clone();
+        assert_eq!(Redactor::disabled().redact_json(&mut untouched).masked, 0);
+    }
+
+    #[test]
+    fn screenshots_pixelate_noted_regions() {
+        let r = Redactor::default();
+        let shot = ScreenshotResult {
+            image_data: (0..64 * 64 * 4).map(|i| (i % 251) as u8).collect(),
+            width: 64,
+            height: 64,
+            monitor: None,
+        };
+        assert!(r.redact_screenshot(&shot, None).is_none());
+
+        r.note_sensitive_region((116.0, 216.0, 16.0, 16.0));
+        let origin = ImageOrigin {
+            x: 100.0,
+            y: 200.0,
+            scale: 1.0,
+        };
+        let redacted = r.redact_screenshot(&shot, Some(origin)).unwrap();
+        let pixel = |data: &[u8], x: usize, y: usize| data[(y * 64 + x) * 4..][..4].to_vec();
+        // Inside the field every pixel of the block is the same
+        assert_eq!(
+            pixel(&redacted.image_data, 16, 16),
+            pixel(&redacted.image_data, 31, 31)
+        );
+        assert_ne!(
+            pixel(&redacted.image_data, 16, 16),
+            pixel(&shot.image_data, 16, 16)
+        );
+        // Outside it nothing changed
+        assert_eq!(
+            pixel(&redacted.image_data, 0, 0),
+            pixel(&shot.image_data, 0, 0)
+        );
+        assert_eq!(
+            pixel(&redacted.image_data, 40, 40),
+            pixel(&shot.image_data, 40, 40)
+        );
+    }
+}
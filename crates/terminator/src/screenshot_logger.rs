@@ -2,8 +2,10 @@
 //!
 //! Saves screenshots to %LOCALAPPDATA%\terminator\executions\ (Windows)
 //! or ~/.local/share/terminator/executions/ (Linux/macOS).
-//! Used by both MCP agent and SDK bindings.
+//! Used by both MCP agent and SDK bindings. Screenshots pass through
+//! [`crate::redaction`] before they are written.
 
+use crate::redaction::{self, ImageOrigin};
 use crate::ScreenshotResult;
 use chrono::Local;
 use std::fs;
@@ -105,6 +107,17 @@ pub fn save_screenshot(
     let filename = format!("{}_{}.png", prefix, suffix);
     let path = dir.join(&filename);
 
+    // Pixelate password fields before anything touches the disk. Monitor
+    // captures know where they sit on screen; for others the whole image is
+    // pixelated while a password field was seen recently.
+    let origin = screenshot.monitor.as_ref().map(|m| ImageOrigin {
+        x: m.x as f64,
+        y: m.y as f64,
+        scale: 1.0,
+    });
+    let redacted = redaction::global().redact_screenshot(screenshot, origin);
+    let screenshot = redacted.as_ref().unwrap_or(screenshot);
+
     // Get PNG bytes (with optional resize)
     let png_bytes = match screenshot.to_png_resized(max_dimension) {
         Ok(bytes) => bytes,
@@ -110,11 +110,31 @@ type EventPayload = {
     [key: string]: any;
 };
 
+/**
+ * Open a write stream to an MCP agent pipe endpoint. A TCP endpoint carries a
+ * token that has to be the first line sent, or the agent drops the connection.
+ */
+function openPipe(pipePath: string): fs.WriteStream | net.Socket {
+    if (pipePath.startsWith('tcp://')) {
+        const url = new URL(pipePath);
+        const socket = net.createConnection({ host: url.hostname, port: Number(url.port) });
+        const token = url.searchParams.get('token');
+        if (token) {
+            socket.write(token + '\n');
+        }
+        return socket;
+    }
+    if (process.platform === 'win32') {
+        return fs.createWriteStream(pipePath, { flags: 'w' });
+    }
+    return net.createConnection(pipePath);
+}
+
 /**
  * Event transport - handles sending events to the MCP agent
  */
 class EventTransport {
-    private pipeStream: fs.WriteStream | null = null;
+    private pipeStream: fs.WriteStream | net.Socket | null = null;
     private pipePath: string | null = null;
     private connectionAttempted = false;
     private useStderr = false;
@@ -130,7 +150,10 @@ class EventTransport {
     }
 
     /**
-     * Connect to the named pipe (lazy connection on first write)
+     * Connect to the event pipe (lazy connection on first write).
+     *
+     * MCP_EVENT_PIPE is a named pipe on Windows, a Unix domain socket path on
+     * other platforms, or `tcp://host:port?token=...` for the loopback fallback.
      */
     private connect(): boolean {
         if (this.useStderr) {
@@ -148,8 +171,7 @@ class EventTransport {
         this.connectionAttempted = true;
 
         try {
-            // Open the named pipe for writing
-            this.pipeStream = fs.createWriteStream(this.pipePath!, { flags: 'w' });
+            this.pipeStream = openPipe(this.pipePath!);
 
             this.pipeStream.on('error', (err) => {
                 console.error(`[workflow-events] Pipe error: ${err.message}`);